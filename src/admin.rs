@@ -0,0 +1,127 @@
+//! Managing a Pantry instance without the UI.
+//!
+//! Headless deployments have nobody to click "accept", so a superuser has to manage
+//! users and requests through the API instead:
+//!
+//! ```no_run
+//! # use pantry_rs::{AdminClient, PantryClient, UserId};
+//! # async fn example(admin_id: UserId, admin_key: String) -> Result<(), Box<dyn std::error::Error>> {
+//! let admin = AdminClient::new(PantryClient::login(admin_id, admin_key, None));
+//! for user in admin.users().await? {
+//!     println!("{} {:?}", user.name, user.permissions);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Every call requires [UserPermissions::perm_superuser]; without it Pantry answers with
+//! [PantryError::PermissionDenied].
+use crate::error::PantryError;
+use crate::ids::{RequestId, UserId};
+use crate::interface::{UserPermissions, UserRequestStatus, UserStatus};
+use crate::logging::REDACTED;
+use crate::{PantryBackend, PantryClient};
+use std::fmt;
+use std::sync::Arc;
+
+/// Superuser operations, on top of a [PantryClient] for a superuser.
+#[derive(Clone)]
+pub struct AdminClient {
+    pub user_id: UserId,
+    pub api_key: String,
+
+    pub client: Arc<dyn PantryBackend>,
+}
+
+// Keeps the superuser's key out of logs and panics.
+impl fmt::Debug for AdminClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdminClient")
+            .field("user_id", &self.user_id)
+            .field("api_key", &REDACTED)
+            .field("client", &self.client)
+            .finish()
+    }
+}
+
+impl AdminClient {
+    /// Uses `pantry`'s credentials, which need [UserPermissions::perm_superuser].
+    ///
+    /// Does not make any API calls.
+    pub fn new(pantry: PantryClient) -> Self {
+        AdminClient {
+            user_id: pantry.user_id,
+            api_key: pantry.api_key,
+            client: pantry.client,
+        }
+    }
+
+    /// Lists every registered user.
+    pub async fn users(&self) -> Result<Vec<UserStatus>, PantryError> {
+        self.client
+            .list_users(self.user_id, self.api_key.clone())
+            .await
+    }
+
+    /// Gets a single user.
+    pub async fn user(&self, user_id: UserId) -> Result<UserStatus, PantryError> {
+        self.client
+            .get_user(self.user_id, self.api_key.clone(), user_id)
+            .await
+    }
+
+    /// Gets the permissions a user currently has.
+    pub async fn permissions(&self, user_id: UserId) -> Result<UserPermissions, PantryError> {
+        Ok(self.user(user_id).await?.permissions)
+    }
+
+    /// Lists requests from all users that haven't been decided yet.
+    pub async fn pending_requests(&self) -> Result<Vec<UserRequestStatus>, PantryError> {
+        self.client
+            .list_pending_requests(self.user_id, self.api_key.clone())
+            .await
+    }
+
+    /// Accepts a pending request.
+    ///
+    /// ```no_run
+    /// # use pantry_rs::interface::UserPermissions;
+    /// # use pantry_rs::{AdminClient, PantryClient};
+    /// # async fn example(admin: AdminClient, perms: UserPermissions) -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pantry, request) = PantryClient::register("ci".into(), perms, None).await?;
+    /// admin.approve_request(request.id).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn approve_request(
+        &self,
+        request_id: RequestId,
+    ) -> Result<UserRequestStatus, PantryError> {
+        self.client
+            .approve_request(self.user_id, self.api_key.clone(), request_id)
+            .await
+    }
+
+    /// Rejects a pending request.
+    pub async fn deny_request(
+        &self,
+        request_id: RequestId,
+    ) -> Result<UserRequestStatus, PantryError> {
+        self.client
+            .deny_request(self.user_id, self.api_key.clone(), request_id)
+            .await
+    }
+
+    /// Revokes a user's API key, locking them out until they register again.
+    pub async fn revoke_api_key(&self, user_id: UserId) -> Result<(), PantryError> {
+        self.client
+            .revoke_api_key(self.user_id, self.api_key.clone(), user_id)
+            .await
+    }
+}
+
+impl From<PantryClient> for AdminClient {
+    fn from(pantry: PantryClient) -> Self {
+        AdminClient::new(pantry)
+    }
+}
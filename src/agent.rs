@@ -0,0 +1,202 @@
+//! Running an LLM with tools until it has an answer.
+//!
+//! An [Agent] chats with an LLM that has [tools](crate::tools) on offer. Whenever the
+//! LLM calls some, the agent runs their handlers and hands the results back, until the
+//! LLM replies without calling anything:
+//!
+//! ```no_run
+//! # use pantry_rs::agent::Agent;
+//! # use pantry_rs::tools::{Tool, ToolCall};
+//! # use pantry_rs::LLMSession;
+//! # use serde_json::{json, Value};
+//! # async fn example(sess: LLMSession, schema: Value) -> Result<(), Box<dyn std::error::Error>> {
+//! let mut agent = Agent::new(sess)
+//!     .system("You are a helpful assistant.")
+//!     .tool(
+//!         Tool::new("get_weather", "Current weather for a city", schema),
+//!         |call: ToolCall| async move {
+//!             let city: String = call.arguments["city"].as_str().unwrap_or("").into();
+//!             Ok(json!({ "city": city, "forecast": "rain" }))
+//!         },
+//!     )
+//!     .max_steps(5)
+//!     .on_step(|step| println!("{} tool calls", step.results.len()));
+//!
+//! let run = agent.run("Do I need an umbrella in Paris?").await?;
+//! println!("{}", run.answer);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A handler's `Err` is passed to the LLM as the tool's error, so it can try something
+//! else; so is a call to a tool that doesn't exist, or one that can't be read.
+use crate::chat::{ChatMessage, ChatSession};
+use crate::error::PantryError;
+use crate::tools::{Tool, ToolCall, ToolResult, ToolSet};
+use crate::LLMSession;
+use futures::future::BoxFuture;
+use futures::Future;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Rounds of tool calls [Agent::run] allows unless told otherwise.
+pub const DEFAULT_MAX_STEPS: usize = 10;
+
+type Handler = Arc<dyn Fn(ToolCall) -> BoxFuture<'static, Result<Value, String>> + Send + Sync>;
+type StepHook = Arc<dyn Fn(&AgentStep) + Send + Sync>;
+
+/// Runs a chat with tools, see the [module docs](self).
+pub struct Agent {
+    chat: ChatSession,
+    system: Option<String>,
+    tools: ToolSet,
+    handlers: HashMap<String, Handler>,
+    max_steps: usize,
+    on_step: Option<StepHook>,
+    // Whether the system prompt has been added to the chat.
+    started: bool,
+}
+
+/// One reply from the LLM that called tools, and what they returned.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgentStep {
+    pub reply: String,
+    pub results: Vec<ToolResult>,
+}
+
+/// How [Agent::run] went.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgentRun {
+    /// The LLM's final reply, the first that called no tools.
+    pub answer: String,
+    /// Every round of tool calls before it, in order.
+    pub steps: Vec<AgentStep>,
+}
+
+impl Agent {
+    /// An agent chatting in `session`, in its LLM's format, see [ChatSession::new].
+    pub fn new(session: LLMSession) -> Self {
+        let tools = ToolSet::detect(&session.llm_status);
+        Self::with_chat(ChatSession::new(session), tools)
+    }
+
+    /// An agent continuing `chat`, calling tools in the syntax of `tools`. Tools already
+    /// in `tools` have no handler, so calls to them fail until one is added.
+    pub fn with_chat(chat: ChatSession, tools: ToolSet) -> Self {
+        Agent {
+            chat,
+            system: None,
+            tools,
+            handlers: HashMap::new(),
+            max_steps: DEFAULT_MAX_STEPS,
+            on_step: None,
+            started: false,
+        }
+    }
+
+    /// Instructions for the LLM, put in front of the tool descriptions.
+    pub fn system(mut self, prompt: impl Into<String>) -> Self {
+        self.system = Some(prompt.into());
+        self
+    }
+
+    /// Offers `tool` to the LLM, running `handler` when it's called.
+    pub fn tool<F, Fut>(mut self, tool: Tool, handler: F) -> Self
+    where
+        F: Fn(ToolCall) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value, String>> + Send + 'static,
+    {
+        let handler: Handler = Arc::new(move |call| Box::pin(handler(call)));
+        self.handlers.insert(tool.name.clone(), handler);
+        self.tools = self.tools.tool(tool);
+        self
+    }
+
+    /// Give up with [PantryError::AgentStepLimit] after `max_steps` rounds of tool
+    /// calls without an answer.
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Calls `on_step` after every round of tool calls, e.g. to log what the agent does.
+    pub fn on_step(mut self, on_step: impl Fn(&AgentStep) + Send + Sync + 'static) -> Self {
+        self.on_step = Some(Arc::new(on_step));
+        self
+    }
+
+    pub fn tools(&self) -> &ToolSet {
+        &self.tools
+    }
+
+    /// The chat so far, tool calls and results included.
+    pub fn chat(&self) -> &ChatSession {
+        &self.chat
+    }
+
+    /// Sends `message` and keeps running tools until the LLM answers without calling
+    /// any. Can be called again to continue the conversation.
+    pub async fn run(&mut self, message: impl Into<String>) -> Result<AgentRun, PantryError> {
+        if !self.started {
+            let prompt = match &self.system {
+                Some(system) => format!("{}\n\n{}", system, self.tools.system_prompt()),
+                None => self.tools.system_prompt(),
+            };
+            self.chat
+                .messages_mut()
+                .insert(0, ChatMessage::system(prompt));
+            self.started = true;
+        }
+
+        let mut message = message.into();
+        let mut steps = Vec::new();
+        loop {
+            let reply = self.chat.send_and_collect(message).await?;
+            // A garbled call is answered like a failed one, with no name to it.
+            let calls = match self.tools.parse(&reply) {
+                Ok(calls) => calls.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e.to_string())],
+            };
+            if calls.is_empty() {
+                return Ok(AgentRun {
+                    answer: reply,
+                    steps,
+                });
+            }
+            if steps.len() >= self.max_steps {
+                return Err(PantryError::AgentStepLimit(self.max_steps));
+            }
+
+            let mut results = Vec::new();
+            for call in calls {
+                let result = match call {
+                    Ok(call) => self.call(call).await,
+                    Err(problem) => ToolResult {
+                        call: ToolCall {
+                            name: String::new(),
+                            arguments: Value::Null,
+                        },
+                        result: Err(problem),
+                    },
+                };
+                results.push(result);
+            }
+            message = self.tools.render_results(&results);
+            let step = AgentStep { reply, results };
+            if let Some(on_step) = &self.on_step {
+                on_step(&step);
+            }
+            steps.push(step);
+        }
+    }
+
+    /// Runs `call`'s handler.
+    async fn call(&self, call: ToolCall) -> ToolResult {
+        let result = match self.handlers.get(&call.name) {
+            Some(handler) => handler(call.clone()).await,
+            None => Err(format!("there is no tool named {}", call.name)),
+        };
+        ToolResult { call, result }
+    }
+}
@@ -1,26 +1,68 @@
 //! Low Level API Wrapper
+use crate::auth::RemoteAuth;
 use crate::error::PantryError;
+#[cfg(feature = "streaming")]
+use crate::event_bus::{self, ClientEvent};
+use crate::fixtures::Fixtures;
 use crate::interface;
-use futures::stream::{Stream, StreamExt, TryStreamExt};
+#[cfg(feature = "streaming")]
+use crate::interface::LLMEvent;
+#[cfg(feature = "streaming")]
+use crate::lifecycle::Lifecycle;
+use crate::logging;
+use crate::metrics::{MetricsSink, RequestMetric};
+#[cfg(feature = "streaming")]
+use crate::middleware::PromptMiddleware;
+use crate::multimodal::AttachmentTransport;
+#[cfg(feature = "streaming")]
+use crate::multimodal::{self, Attachment, PromptInput};
+#[cfg(feature = "streaming")]
+use crate::ndjson;
+use crate::params::LoadOptions;
+use crate::rate_limit::RateLimiter;
+use crate::retry::RetryPolicy;
+#[cfg(feature = "streaming")]
+use crate::sse;
+#[cfg(feature = "streaming")]
+use crate::stream::RawEventStream;
+#[cfg(feature = "streaming")]
+pub use crate::stream::{LLMEventStream, ServerEventStream};
+#[cfg(feature = "templates")]
+use crate::templates::PromptTemplates;
+use chrono::{DateTime, Utc};
+use futures::future::{self, Either, Future};
+#[cfg(feature = "streaming")]
+use futures::stream::{self, Stream, StreamExt};
+use futures_timer::Delay;
 use hyper;
 use hyper::body::HttpBody;
+#[cfg(not(target_arch = "wasm32"))]
 use hyper::Client;
 use hyper::StatusCode;
+#[cfg(feature = "streaming")]
+use serde::de::DeserializeOwned;
 use serde_json;
 use serde_json::Value;
-use sse_codec::{decode_stream, Event};
 use std::collections::HashMap;
-use std::fmt;
-use std::io; // for try_next()
+#[cfg(feature = "streaming")]
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(feature = "streaming")]
 use uuid::Uuid;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
 
-#[cfg(target_family = "unix")]
+#[cfg(all(unix, feature = "unix-socket"))]
 use hyperlocal::UnixClientExt;
 
+use crate::ids::{LeaseId, LlmUuid, RequestId, SessionId, UserId};
 use crate::interface::{
-    LLMEvent, LLMRegistryEntry, LLMRunningStatus, LLMStatus, UserInfo, UserPermissions,
-    UserRequestStatus,
+    AdapterEntry, AdapterStatus, ExportMode, LLMConnectorType, LLMHistoryItem, LLMRegistryEntry,
+    LLMRunningStatus, LLMSessionStatus, LLMStatus, ServerInfo, StorageInfo, SystemStatus, UserInfo,
+    UserPermissions, UserRequestStatus, UserStatus,
 };
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -28,30 +70,13 @@ struct RegisterUserRequest {
     user_name: String,
 }
 
-/// Enum representing valid capability ratings for LLMs.
-#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug)]
-#[serde(rename_all = "lowercase")]
-pub enum CapabilityType {
-    General,
-    Assistant,
-    Writing,
-    Coding,
-}
-
-impl fmt::Display for CapabilityType {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            CapabilityType::General => write!(f, "general"),
-            CapabilityType::Assistant => write!(f, "assistant"),
-            CapabilityType::Writing => write!(f, "writing"),
-            CapabilityType::Coding => write!(f, "coding"),
-        }
-    }
-}
+/// Enum representing valid capability ratings for LLMs. Shared with [LLMStatus::capabilities]
+/// and [LLMRegistryEntry::capabilities].
+pub use crate::interface::CapabilityType;
 
 /// Filter structure for capabilities, for use when
 /// describing LLM filters or preferences.
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct CapabilityFilter {
     pub capability: CapabilityType,
     pub value: i32,
@@ -63,14 +88,167 @@ pub struct CapabilityFilter {
 /// filter cannot be satisfied, the function will return a 404.
 ///
 /// An empty filter structure will allow any LLM to be used.
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+///
+/// ```
+/// # use pantry_rs::interface::CapabilityType;
+/// # use pantry_rs::LLMFilter;
+/// // Any Apache-licensed coding model that fits in 8 GB.
+/// let filter = LLMFilter::new()
+///     .local(true)
+///     .min_capability(CapabilityType::Coding, 6)
+///     .license("apache-2.0")
+///     .max_ram_bytes(8 << 30);
+/// ```
+///
+/// The tag, license, organization, context length and RAM filters need a Pantry
+/// version that understands them; older versions ignore them.
+/// [LLMFilter::matches] applies all of them locally.
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
 pub struct LLMFilter {
     /// UUID. This specifies a single LLM, making the rest of the options unnecessary.
-    pub llm_uuid: Option<Uuid>,
+    pub llm_uuid: Option<LlmUuid>,
     pub llm_id: Option<String>,
     pub family_id: Option<String>,
     pub local: Option<bool>,
     pub minimum_capabilities: Option<Vec<CapabilityFilter>>,
+    /// The LLM must have all of these tags.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    /// The LLM must have one of these licenses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub licenses: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+    /// Minimum context window in tokens. LLMs that don't report one don't match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_context_length: Option<u64>,
+    /// Maximum memory needed to run. LLMs that don't report it don't match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_ram_bytes: Option<u64>,
+}
+
+impl LLMFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only this exact LLM.
+    pub fn uuid(mut self, llm_uuid: LlmUuid) -> Self {
+        self.llm_uuid = Some(llm_uuid);
+        self
+    }
+
+    /// Only LLMs with this registry id.
+    pub fn id(mut self, llm_id: impl Into<String>) -> Self {
+        self.llm_id = Some(llm_id.into());
+        self
+    }
+
+    pub fn family(mut self, family_id: impl Into<String>) -> Self {
+        self.family_id = Some(family_id.into());
+        self
+    }
+
+    pub fn local(mut self, local: bool) -> Self {
+        self.local = Some(local);
+        self
+    }
+
+    /// Requires a rating of at least `value` for `capability`. Can be repeated.
+    pub fn min_capability(mut self, capability: CapabilityType, value: i32) -> Self {
+        self.minimum_capabilities
+            .get_or_insert_with(Vec::new)
+            .push(CapabilityFilter { capability, value });
+        self
+    }
+
+    /// Requires a tag. Can be repeated, in which case all of them are required.
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.get_or_insert_with(Vec::new).push(tag.into());
+        self
+    }
+
+    /// Allows a license. Can be repeated, in which case any of them will do.
+    pub fn license(mut self, license: impl Into<String>) -> Self {
+        self.licenses
+            .get_or_insert_with(Vec::new)
+            .push(license.into());
+        self
+    }
+
+    pub fn organization(mut self, organization: impl Into<String>) -> Self {
+        self.organization = Some(organization.into());
+        self
+    }
+
+    pub fn min_context_length(mut self, tokens: u64) -> Self {
+        self.min_context_length = Some(tokens);
+        self
+    }
+
+    pub fn max_ram_bytes(mut self, bytes: u64) -> Self {
+        self.max_ram_bytes = Some(bytes);
+        self
+    }
+
+    /// Whether `llm` passes this filter. Text comparisons ignore case.
+    pub fn matches(&self, llm: &LLMStatus) -> bool {
+        let eq = |a: &str, b: &str| a.eq_ignore_ascii_case(b);
+        if let Some(uuid) = self.llm_uuid {
+            if uuid != llm.uuid {
+                return false;
+            }
+        }
+        if let Some(id) = &self.llm_id {
+            if !eq(id, &llm.id) {
+                return false;
+            }
+        }
+        if let Some(family_id) = &self.family_id {
+            if !eq(family_id, &llm.family_id) {
+                return false;
+            }
+        }
+        if let Some(local) = self.local {
+            if local != llm.local {
+                return false;
+            }
+        }
+        for filter in self.minimum_capabilities.iter().flatten() {
+            match llm.capabilities.get(&filter.capability) {
+                Some(value) if *value >= filter.value => {}
+                _ => return false,
+            }
+        }
+        for tag in self.tags.iter().flatten() {
+            if !llm.tags.iter().any(|t| eq(t, tag)) {
+                return false;
+            }
+        }
+        if let Some(licenses) = &self.licenses {
+            if !licenses.iter().any(|l| eq(l, &llm.license)) {
+                return false;
+            }
+        }
+        if let Some(organization) = &self.organization {
+            if !eq(organization, &llm.organization) {
+                return false;
+            }
+        }
+        if let Some(min) = self.min_context_length {
+            match llm.context_length() {
+                Some(tokens) if tokens >= min => {}
+                _ => return false,
+            }
+        }
+        if let Some(max) = self.max_ram_bytes {
+            match llm.ram_bytes() {
+                Some(bytes) if bytes <= max => {}
+                _ => return false,
+            }
+        }
+        true
+    }
 }
 
 /// Preference structure for calls that allow flexible choice of LLMs.
@@ -90,20 +268,113 @@ pub struct LLMFilter {
 /// the results are filtered to those LLMs and the next preference
 /// is applied. If no capability type is provided, the final sorting
 /// (should multiple LLMs be left over) is based on [CapabilityType::General].
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+///
+/// ```
+/// # use pantry_rs::interface::CapabilityType;
+/// # use pantry_rs::LLMPreference;
+/// let preference = LLMPreference::new().local(true).capability(CapabilityType::Assistant);
+/// ```
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
 pub struct LLMPreference {
-    pub llm_uuid: Option<Uuid>,
+    pub llm_uuid: Option<LlmUuid>,
     pub llm_id: Option<String>,
     pub local: Option<bool>,
     pub family_id: Option<String>,
     pub capability_type: Option<CapabilityType>,
 }
 
+impl LLMPreference {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn uuid(mut self, llm_uuid: LlmUuid) -> Self {
+        self.llm_uuid = Some(llm_uuid);
+        self
+    }
+
+    pub fn id(mut self, llm_id: impl Into<String>) -> Self {
+        self.llm_id = Some(llm_id.into());
+        self
+    }
+
+    pub fn local(mut self, local: bool) -> Self {
+        self.local = Some(local);
+        self
+    }
+
+    pub fn family(mut self, family_id: impl Into<String>) -> Self {
+        self.family_id = Some(family_id.into());
+        self
+    }
+
+    /// Rank what's left by this capability instead of [CapabilityType::General].
+    pub fn capability(mut self, capability: CapabilityType) -> Self {
+        self.capability_type = Some(capability);
+        self
+    }
+
+    /// Picks from `llms` the way Pantry does, see the ordering above.
+    ///
+    /// Ties go to whichever LLM comes first. LLMs without a rating for the capability
+    /// rank as unevaluated (-1).
+    pub fn choose<'a>(&self, llms: &[&'a LLMStatus]) -> Option<&'a LLMStatus> {
+        let mut candidates: Vec<&'a LLMStatus> = llms.to_vec();
+        let mut narrow = |keep: &dyn Fn(&LLMStatus) -> bool| {
+            let kept: Vec<&'a LLMStatus> = candidates.iter().copied().filter(|l| keep(l)).collect();
+            if !kept.is_empty() {
+                candidates = kept;
+            }
+        };
+        if let Some(uuid) = self.llm_uuid {
+            narrow(&|l| l.uuid == uuid);
+        }
+        if let Some(id) = &self.llm_id {
+            narrow(&|l| l.id.eq_ignore_ascii_case(id));
+        }
+        if let Some(local) = self.local {
+            narrow(&|l| l.local == local);
+        }
+        if let Some(family_id) = &self.family_id {
+            narrow(&|l| l.family_id.eq_ignore_ascii_case(family_id));
+        }
+
+        let capability = self
+            .capability_type
+            .clone()
+            .unwrap_or(CapabilityType::General);
+        let rating = |l: &LLMStatus| *l.capabilities.get(&capability).unwrap_or(&-1);
+        // max_by_key keeps the last maximum, so go backwards to favour the first.
+        candidates.into_iter().rev().max_by_key(|l| rating(l))
+    }
+}
+
+/// Picks an LLM from `llms` the way the `_flex` calls do: drop everything that fails
+/// `filter`, then rank the rest by `preference`.
+///
+/// Returns `None` where Pantry would answer with a 404.
+pub fn select_llm<'a>(
+    llms: &'a [LLMStatus],
+    filter: Option<&LLMFilter>,
+    preference: Option<&LLMPreference>,
+) -> Option<&'a LLMStatus> {
+    let passed: Vec<&LLMStatus> = llms
+        .iter()
+        .filter(|l| match filter {
+            Some(filter) => filter.matches(l),
+            None => true,
+        })
+        .collect();
+    preference.cloned().unwrap_or_default().choose(&passed)
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 struct RequestPermissionRequest {
     user_id: String,
     api_key: String,
     requested_permissions: UserPermissions, // You might want to replace this with an actual Permissions type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_secs: Option<u64>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -111,6 +382,8 @@ struct RequestDownloadRequest {
     user_id: String,
     api_key: String,
     llm_registry_entry: String, // You might want to replace this with an actual LLMRegistryEntry type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_dir: Option<String>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -140,6 +413,8 @@ struct LoadLLMRequest {
     user_id: String,
     api_key: String,
     llm_id: String,
+    #[serde(default, skip_serializing_if = "LoadOptions::is_empty")]
+    config: LoadOptions,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -154,6 +429,8 @@ struct DownloadLLMRequest {
     user_id: String,
     api_key: String,
     llm_registry_entry: interface::LLMRegistryEntry, // You might want to replace this with an actual LLMRegistryEntry type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_dir: Option<String>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -175,6 +452,8 @@ struct LoadLLMFlexRequest {
     api_key: String,
     filter: Option<LLMFilter>,
     preference: Option<LLMPreference>,
+    #[serde(default, skip_serializing_if = "LoadOptions::is_empty")]
+    config: LoadOptions,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -205,10 +484,11 @@ struct CreateSessionFlexRequest {
 pub struct CreateSessionResponse {
     pub session_parameters: HashMap<String, Value>,
     pub llm_status: LLMStatus,
-    pub session_id: String,
+    pub session_id: SessionId,
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[cfg(feature = "streaming")]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 struct PromptSessionStreamRequest {
     user_id: String,
     api_key: String,
@@ -216,6 +496,26 @@ struct PromptSessionStreamRequest {
     llm_uuid: String,
     prompt: String,
     parameters: HashMap<String, Value>,
+    /// Set when reconnecting, to continue this stream rather than start a new one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resume_stream_id: Option<Uuid>,
+    /// The id of the last event received before the connection dropped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_event_id: Option<String>,
+    /// Images in the prompt, see [crate::multimodal].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    attachments: Vec<Attachment>,
+}
+
+#[cfg(feature = "streaming")]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct TranscribeStreamRequest {
+    user_id: String,
+    api_key: String,
+    session_id: String,
+    llm_uuid: String,
+    audio: Attachment,
+    parameters: HashMap<String, Value>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -239,12 +539,97 @@ struct InterruptSessionRequest {
     session_id: String,
 }
 
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct GetSessionsRequest {
+    user_id: String,
+    api_key: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct LoadSessionIdRequest {
+    user_id: String,
+    api_key: String,
+    session_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct ForkSessionRequest {
+    user_id: String,
+    api_key: String,
+    session_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct GetSessionHistoryRequest {
+    user_id: String,
+    api_key: String,
+    session_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct DeleteSessionRequest {
+    user_id: String,
+    api_key: String,
+    session_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct ListUsersRequest {
+    user_id: String,
+    api_key: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct GetUserRequest {
+    user_id: String,
+    api_key: String,
+    target_user_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct ListPendingRequestsRequest {
+    user_id: String,
+    api_key: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct DecideRequestRequest {
+    user_id: String,
+    api_key: String,
+    request_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct RevokeApiKeyRequest {
+    user_id: String,
+    api_key: String,
+    target_user_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct GetUserInfoRequest {
+    user_id: String,
+    api_key: String,
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 struct GetRunningLLMRequest {
     user_id: String,
     api_key: String,
 }
 
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct GetSystemStatusRequest {
+    user_id: String,
+    api_key: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct GetStorageInfoRequest {
+    user_id: String,
+    api_key: String,
+}
+
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 struct BareModelRequest {
     user_id: String,
@@ -266,101 +651,2589 @@ pub struct BareModelResponse {
     pub path: String,
 }
 
-/// PantryAPI is a thin wrapper, just meant to minimize retyping of
-/// client and baseurl in function calls. Feel free to make multiple,
-/// or to clone.
-#[derive(Clone, Debug)]
-pub struct PantryAPI {
-    pub client: Client<hyper::client::connect::HttpConnector>,
-    pub base_url: Option<String>,
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct ExportBareModelRequest {
+    user_id: String,
+    api_key: String,
+    llm_id: String,
+    destination: String,
+    mode: ExportMode,
 }
 
-impl PantryAPI {
-    pub fn new(base_url: Option<String>) -> Self {
-        PantryAPI {
-            client: Client::new(),
-            base_url,
-        }
-    }
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct BareModelLeaseRequest {
+    user_id: String,
+    api_key: String,
+    llm_id: String,
+    ttl_secs: u64,
+}
 
-    #[cfg(target_family = "windows")]
-    async fn double_edge(
-        &self,
-        method: hyper::Method,
-        body: String,
-        path: String,
-    ) -> Result<hyper::Response<hyper::body::Body>, PantryError> {
-        let url = match self.base_url.clone() {
-            Some(u) => u,
-            None => "http://localhost:9404/".into(),
-        };
-        let url3 = url + &path;
-        println!("url3: {:?}", url3);
-        let req3: hyper::Request<hyper::body::Body> = hyper::Request::builder()
-            .method(method.clone())
-            .header("Content-Type", "application/json")
-            .uri(url3)
-            .body(hyper::Body::from(body.clone()))?;
-        return Ok(self.client.request(req3).await?);
-    }
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct BareModelLeaseResponse {
+    pub lease_id: LeaseId,
+    pub model: LLMStatus,
+    pub path: String,
+    /// When the file stops being pinned, unless released before.
+    pub expires_at: DateTime<Utc>,
+}
 
-    #[cfg(target_family = "unix")]
-    async fn double_edge(
-        &self,
-        method: hyper::Method,
-        body: String,
-        path: String,
-    ) -> Result<hyper::Response<hyper::body::Body>, PantryError> {
-        if let Some(url) = self.base_url.clone() {
-            let url3 = url + &path;
-            println!("url3: {:?}", url3);
-            let req3: hyper::Request<hyper::body::Body> = hyper::Request::builder()
-                .method(method.clone())
-                .header("Content-Type", "application/json")
-                .uri(url3)
-                .body(hyper::Body::from(body.clone()))?;
-            return Ok(self.client.request(req3).await?);
-        }
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct ReleaseBareModelRequest {
+    user_id: String,
+    api_key: String,
+    lease_id: String,
+}
 
-        let url1 = hyperlocal::Uri::new("/tmp/pantrylocal.sock", &path.clone());
-        let req1: hyper::Request<hyper::body::Body> = hyper::Request::builder()
-            .method(method.clone())
-            .header("Content-Type", "application/json")
-            .uri(url1)
-            .body(hyper::Body::from(body.clone()))?;
-        let DEFAULT_URL: String = "http://localhost:9404/".into();
-        let url2 = DEFAULT_URL.clone() + &path;
-        let req2: hyper::Request<hyper::body::Body> = hyper::Request::builder()
-            .method(method.clone())
-            .header("Content-Type", "application/json")
-            .uri(url2)
-            .body(hyper::Body::from(body.clone()))?;
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct ListAdaptersRequest {
+    user_id: String,
+    api_key: String,
+    llm_id: String,
+}
 
-        let unix = Client::unix();
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct DownloadAdapterRequest {
+    user_id: String,
+    api_key: String,
+    llm_id: String,
+    adapter: AdapterEntry,
+}
 
-        match unix.request(req1).await {
-            Ok(resp) => Ok(resp),
-            Err(err) => {
-                println!("Error sending to socket: {:?}", err);
-                println!("Trying: {:?}", req2);
-                Ok(self.client.request(req2).await?)
-            }
-        }
-    }
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct AdapterRequest {
+    user_id: String,
+    api_key: String,
+    llm_uuid: String,
+    adapter_id: String,
+}
 
-    /// Accessing the API requires a registered user demarcated by a user_id and an api_key.
-    ///
-    /// This function supplies both. When using the API manually, you'll probably also
-    /// need to call [PantryAPI::request_permissions] to do anything useful.
-    ///
-    /// # Arguments
-    /// * `user_name` — used for debug output and manager display.
-    pub async fn register_user(&self, user_name: String) -> Result<UserInfo, PantryError> {
-        let register_user_request = RegisterUserRequest { user_name };
+// No Debug, so the secret can't end up in logs.
+#[derive(serde::Serialize)]
+struct SetConnectorSecretRequest {
+    user_id: String,
+    api_key: String,
+    connector: LLMConnectorType,
+    secret: String,
+}
 
-        let body = serde_json::to_string(&register_user_request)?;
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct RequestDeleteRequest {
+    user_id: String,
+    api_key: String,
+    llm_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct DeleteLLMRequest {
+    user_id: String,
+    api_key: String,
+    llm_id: String,
+}
+
+#[cfg(feature = "streaming")]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct SubscribeEventsRequest {
+    user_id: String,
+    api_key: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct GetPendingRequestsRequest {
+    user_id: String,
+    api_key: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct CancelRequestRequest {
+    user_id: String,
+    api_key: String,
+    request_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct GetPermissionsRequest {
+    user_id: String,
+    api_key: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct RequestRegisterLocalRequest {
+    user_id: String,
+    api_key: String,
+    path: String,
+    llm_registry_entry: LLMRegistryEntry,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct RegisterLocalModelRequest {
+    user_id: String,
+    api_key: String,
+    path: String,
+    llm_registry_entry: LLMRegistryEntry,
+}
+
+/// Connector used for TCP connections. With the `rustls` feature this also
+/// speaks HTTPS, see [crate::tls].
+#[cfg(all(not(feature = "rustls"), not(target_arch = "wasm32")))]
+pub type Connector = hyper::client::connect::HttpConnector;
+#[cfg(all(feature = "rustls", not(target_arch = "wasm32")))]
+pub type Connector = hyper_rustls::HttpsConnector<hyper::client::connect::HttpConnector>;
+
+#[cfg(all(not(feature = "rustls"), not(target_arch = "wasm32")))]
+fn default_client() -> Client<Connector> {
+    Client::new()
+}
+
+#[cfg(all(feature = "rustls", not(target_arch = "wasm32")))]
+fn default_client() -> Client<Connector> {
+    // The default config has no user supplied PEMs, so it can't fail to build.
+    let connector = crate::tls::TlsConfig::default()
+        .connector()
+        .expect("default TLS config is valid");
+    Client::builder().build(connector)
+}
+
+/// Decodes a streamed response into its JSON payloads, as server-sent events or NDJSON
+/// depending on its content type.
+#[cfg(feature = "streaming")]
+fn decode_events<T>(
+    resp: hyper::Response<hyper::Body>,
+) -> Pin<Box<dyn Stream<Item = Result<T, PantryError>> + Send>>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    Box::pin(decode_events_with_ids(resp).map(|event| event.map(|(_, event)| event)))
+}
+
+/// Decoded events along with their SSE ids.
+#[cfg(feature = "streaming")]
+type IdentifiedEvents<T> =
+    Pin<Box<dyn Stream<Item = Result<(Option<String>, T), PantryError>> + Send>>;
+
+/// Same as [decode_events], keeping each event's SSE id alongside it.
+#[cfg(feature = "streaming")]
+fn decode_events_with_ids<T>(resp: hyper::Response<hyper::Body>) -> IdentifiedEvents<T>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    if StreamFormat::of(&resp) == StreamFormat::Ndjson {
+        let events = ndjson::decode(resp.into_body())
+            .map(|line| line.and_then(|line| Ok((None, serde_json::from_str::<T>(&line)?))));
+        return Box::pin(events);
+    }
+    let events = sse::decode(resp.into_body()).map(|event| {
+        event.and_then(|event| {
+            let payload = serde_json::from_str::<T>(&event.data)?;
+            Ok((event.id, payload))
+        })
+    });
+    Box::pin(events)
+}
+
+/// State of a prompt stream that reconnects when its connection drops.
+#[cfg(feature = "streaming")]
+struct ResumableStream {
+    api: PantryAPI,
+    request: PromptSessionStreamRequest,
+    events: IdentifiedEvents<LLMEvent>,
+    // Reconnects since the last event came through.
+    reconnects: u32,
+    done: bool,
+}
+
+#[cfg(feature = "streaming")]
+impl ResumableStream {
+    /// Only a stream that's identified itself, and said how far it got, can be resumed.
+    /// Anything else would have to prompt again.
+    fn can_reconnect(&self) -> bool {
+        self.request.resume_stream_id.is_some()
+            && self.request.last_event_id.is_some()
+            && self.reconnects + 1 < self.api.reconnect.max_attempts
+    }
+}
+
+#[cfg(feature = "streaming")]
+impl PantryAPI {
+    /// Reconnects to a prompt stream, picking up after `request.last_event_id`.
+    async fn resume_prompt_stream(
+        &self,
+        request: &PromptSessionStreamRequest,
+    ) -> Result<hyper::Response<hyper::Body>, PantryError> {
+        let body = serde_json::to_string(request)?;
+        let resp = self
+            .double_edge_accepting(
+                hyper::Method::POST,
+                body,
+                "/prompt_session_stream".into(),
+                self.stream_format.accept(),
+            )
+            .await?;
+        if resp.status() != StatusCode::OK {
+            let code = resp.status();
+            let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+            let body_str = std::str::from_utf8(&body_bytes)?;
+            return Err(PantryError::from_endpoint_response(
+                "/prompt_session_stream",
+                code,
+                body_str,
+            ));
+        }
+        Ok(resp)
+    }
+
+    /// Stops a prompt stream's generation on the server, for when it can't be followed
+    /// anymore.
+    async fn abandon_prompt_stream(&self, request: &PromptSessionStreamRequest) {
+        if let (Ok(user_id), Ok(llm_id), Ok(session_id)) = (
+            request.user_id.parse::<UserId>(),
+            request.llm_uuid.parse::<LlmUuid>(),
+            request.session_id.parse::<SessionId>(),
+        ) {
+            let _ = self
+                .interrupt_session(user_id, request.api_key.clone(), llm_id, session_id)
+                .await;
+        }
+    }
+}
+
+/// Events of a prompt stream, reconnecting as allowed by [PantryAPI::reconnect] when the
+/// connection breaks and picking up after the last event received.
+#[cfg(feature = "streaming")]
+fn resumable_events(
+    api: PantryAPI,
+    request: PromptSessionStreamRequest,
+    resp: hyper::Response<hyper::Body>,
+) -> RawEventStream {
+    let state = ResumableStream {
+        api,
+        request,
+        events: decode_events_with_ids(resp),
+        reconnects: 0,
+        done: false,
+    };
+    Box::pin(stream::unfold(state, |mut state| async move {
+        if state.done {
+            return None;
+        }
+        loop {
+            match state.events.next().await {
+                Some(Ok((id, event))) => {
+                    if let Some(stream_id) = state.request.resume_stream_id {
+                        if stream_id != event.stream_id {
+                            // Pantry started the prompt over rather than resuming it, so
+                            // the text so far wouldn't line up with what comes next.
+                            state.api.abandon_prompt_stream(&state.request).await;
+                            state.done = true;
+                            let err = PantryError::StreamError(format!(
+                                "stream {} couldn't be resumed after reconnecting",
+                                stream_id
+                            ));
+                            return Some((Err(err), state));
+                        }
+                    }
+                    state.request.resume_stream_id = Some(event.stream_id);
+                    // Resuming doesn't need the images again.
+                    state.request.attachments.clear();
+                    if id.is_some() {
+                        state.request.last_event_id = id;
+                    }
+                    state.reconnects = 0;
+                    return Some((Ok(event), state));
+                }
+                Some(Err(PantryError::StreamError(e))) => {
+                    let mut error = PantryError::StreamError(e);
+                    let mut resumed = None;
+                    while resumed.is_none() && state.can_reconnect() {
+                        Delay::new(state.api.reconnect.delay(state.reconnects)).await;
+                        state.reconnects += 1;
+                        match state.api.resume_prompt_stream(&state.request).await {
+                            Ok(resp) => resumed = Some(resp),
+                            Err(e) => {
+                                let retryable = e.is_retryable();
+                                error = e;
+                                if !retryable {
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    match resumed {
+                        Some(resp) => state.events = decode_events_with_ids(resp),
+                        None => {
+                            state.done = true;
+                            return Some((Err(error), state));
+                        }
+                    }
+                }
+                Some(Err(e)) => {
+                    state.done = true;
+                    return Some((Err(e), state));
+                }
+                // A clean end, including after an interrupt, is never reconnected.
+                None => return None,
+            }
+        }
+    }))
+}
+
+/// Default location of the unix socket Pantry listens on.
+pub const DEFAULT_SOCKET_PATH: &str = "/tmp/pantrylocal.sock";
+
+/// How long [PantryAPI] sticks with TCP before retrying the unix socket.
+pub const DEFAULT_TRANSPORT_REPROBE: Duration = Duration::from_secs(60);
+
+/// Environment variable overriding [DEFAULT_SOCKET_PATH].
+pub const SOCKET_PATH_ENV: &str = "PANTRY_SOCKET";
+
+/// Socket path to use when none is given explicitly: `$PANTRY_SOCKET` if set,
+/// otherwise [DEFAULT_SOCKET_PATH].
+pub fn default_socket_path() -> String {
+    match std::env::var(SOCKET_PATH_ENV) {
+        Ok(path) if !path.is_empty() => path,
+        _ => DEFAULT_SOCKET_PATH.into(),
+    }
+}
+
+/// What a call sends.
+struct RequestBody {
+    bytes: hyper::body::Bytes,
+    content_type: String,
+    /// Stands in for `bytes` in logs and [Fixtures]: the JSON itself, or the JSON part
+    /// of a multipart body.
+    summary: String,
+}
+
+impl RequestBody {
+    const JSON: &'static str = "application/json";
+
+    fn json(body: String) -> Self {
+        RequestBody {
+            bytes: body.clone().into(),
+            content_type: Self::JSON.into(),
+            summary: body,
+        }
+    }
+
+    /// The JSON `request`, followed by `parts` as multipart if there are any, see
+    /// [AttachmentTransport].
+    #[cfg(feature = "streaming")]
+    fn with_attachments(request: String, attachments: &[Attachment], parts: &[Vec<u8>]) -> Self {
+        if parts.is_empty() {
+            return RequestBody::json(request);
+        }
+        let (bytes, content_type) = multimodal::multipart_body(&request, attachments, parts);
+        RequestBody {
+            bytes,
+            content_type,
+            summary: request,
+        }
+    }
+}
+
+/// PantryAPI is a thin wrapper, just meant to minimize retyping of
+/// client and baseurl in function calls. Feel free to make multiple,
+/// or to clone.
+///
+/// For anything beyond a base url, [crate::PantryClientBuilder] is the
+/// easier way to put one together.
+#[derive(Clone, Debug)]
+pub struct PantryAPI {
+    /// Not there on wasm, where calls go through `fetch`, see [crate::wasm].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub client: Client<Connector>,
+    pub base_url: Option<String>,
+    /// Unix socket used when no `base_url` is set. Ignored on windows.
+    ///
+    /// Defaults to [default_socket_path], so `PANTRY_SOCKET` can point clients at
+    /// an instance running somewhere other than `/tmp/pantrylocal.sock`.
+    pub socket_path: String,
+    /// Maximum time to wait for Pantry to respond to a call, after which it fails
+    /// with [PantryError::Timeout]. `None` waits forever.
+    ///
+    /// For streaming calls this only bounds the wait for the stream to start. Use
+    /// [PantryAPI::with_timeout] to override it for individual calls.
+    pub timeout: Option<Duration>,
+    /// Retrying for read-only calls, see [RetryPolicy].
+    pub retry: RetryPolicy,
+    /// Default for [LLMEventStream::set_stall_timeout] on prompt streams.
+    pub stall_timeout: Option<Duration>,
+    /// Format to ask for on streaming calls, see [StreamFormat].
+    pub stream_format: StreamFormat,
+    /// How prompt images are sent, see [crate::multimodal].
+    pub attachment_transport: AttachmentTransport,
+    /// Reconnecting prompt streams whose connection drops midway, resuming after the
+    /// last event received. `max_attempts - 1` reconnects are made in a row before
+    /// giving up with [PantryError::StreamError]; [RetryPolicy::none] turns it off.
+    pub reconnect: RetryPolicy,
+    /// Prompting again in a fresh session when the LLM fails before generating
+    /// anything, see [crate::PantryClientBuilder::prompt_retry]. Off by default.
+    pub prompt_retry: RetryPolicy,
+    /// Gets told about every call, see [crate::metrics].
+    pub metrics: Option<Arc<dyn MetricsSink>>,
+    /// Throttles calls and generated tokens, see [crate::rate_limit]. Copies of this
+    /// API share the same limiter.
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    /// What prompts and their events go through, in order, see [crate::middleware].
+    #[cfg(feature = "streaming")]
+    pub middleware: Vec<Arc<dyn PromptMiddleware>>,
+    /// Named prompt templates for [crate::LLMSession::prompt_template].
+    #[cfg(feature = "templates")]
+    pub templates: Option<Arc<PromptTemplates>>,
+    /// Where the client's [ClientEvent]s go, shared between copies of this API. See
+    /// [crate::event_bus].
+    #[cfg(feature = "streaming")]
+    pub event_bus: tokio::sync::broadcast::Sender<ClientEvent>,
+    /// Prompts in flight and whether the client was shut down, shared between copies
+    /// of this API. See [crate::lifecycle].
+    #[cfg(feature = "streaming")]
+    pub lifecycle: Arc<Lifecycle>,
+    /// After falling back to TCP, how long to wait before giving the unix socket
+    /// another try.
+    pub transport_reprobe: Duration,
+    /// Record calls to, or replay them from, a fixture file, see [crate::fixtures].
+    pub fixtures: Option<Arc<Fixtures>>,
+    /// Sent with calls to `base_url`, for remote instances that want more than an API
+    /// key. See [crate::auth].
+    pub remote_auth: Option<RemoteAuth>,
+    // Shared between clones, so they all benefit from one fallback.
+    transport: Arc<Mutex<Option<TransportMemo>>>,
+}
+
+/// How [PantryAPI] reaches Pantry when no `base_url` is set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transport {
+    UnixSocket,
+    Tcp,
+}
+
+/// Wire format for streaming calls, negotiated with an `Accept` header.
+///
+/// Pantry may answer in either, and responses are decoded by their content type, so
+/// this only states a preference.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// Server-sent events, `text/event-stream`. The only format that can resume after
+    /// a dropped connection, see [PantryAPI::reconnect].
+    #[default]
+    Sse,
+    /// Newline-delimited JSON, `application/x-ndjson`, for when a proxy in between
+    /// buffers or mangles server-sent events.
+    Ndjson,
+}
+
+impl StreamFormat {
+    pub const SSE_CONTENT_TYPE: &'static str = "text/event-stream";
+    pub const NDJSON_CONTENT_TYPE: &'static str = "application/x-ndjson";
+
+    /// `Accept` header asking for this format, with the other one as a fallback.
+    pub fn accept(self) -> &'static str {
+        match self {
+            StreamFormat::Sse => "text/event-stream, application/x-ndjson;q=0.5",
+            StreamFormat::Ndjson => "application/x-ndjson, text/event-stream;q=0.5",
+        }
+    }
+
+    /// The format a response came in. Anything that doesn't say NDJSON is taken to
+    /// be server-sent events, which is what Pantry always used to send.
+    pub fn of<B>(resp: &hyper::Response<B>) -> Self {
+        let ndjson = resp
+            .headers()
+            .get(hyper::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.trim().starts_with(Self::NDJSON_CONTENT_TYPE));
+        match ndjson {
+            true => StreamFormat::Ndjson,
+            false => StreamFormat::Sse,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TransportMemo {
+    transport: Transport,
+    // Only consulted when there's a socket to go back to.
+    #[cfg_attr(not(all(unix, feature = "unix-socket")), allow(dead_code))]
+    checked: Instant,
+}
+
+impl PantryAPI {
+    pub fn new(base_url: Option<String>) -> Self {
+        PantryAPI {
+            #[cfg(not(target_arch = "wasm32"))]
+            client: default_client(),
+            base_url,
+            socket_path: default_socket_path(),
+            timeout: None,
+            retry: RetryPolicy::default(),
+            stall_timeout: None,
+            stream_format: StreamFormat::default(),
+            attachment_transport: AttachmentTransport::default(),
+            reconnect: RetryPolicy::default(),
+            prompt_retry: RetryPolicy::none(),
+            metrics: None,
+            rate_limiter: None,
+            #[cfg(feature = "streaming")]
+            middleware: Vec::new(),
+            #[cfg(feature = "templates")]
+            templates: None,
+            #[cfg(feature = "streaming")]
+            event_bus: event_bus::channel(),
+            #[cfg(feature = "streaming")]
+            lifecycle: Arc::default(),
+            transport_reprobe: DEFAULT_TRANSPORT_REPROBE,
+            fixtures: None,
+            remote_auth: None,
+            transport: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Returns a copy of this API with a different timeout, for calls that are
+    /// expected to take unusually long (or short).
+    ///
+    /// ```no_run
+    /// # use pantry_rs::{LoadOptions, PantryAPI, UserId};
+    /// # use std::time::Duration;
+    /// # async fn example(api: PantryAPI, user_id: UserId, api_key: String, llm_id: String) -> Result<(), Box<dyn std::error::Error>> {
+    /// let status = api
+    ///     .with_timeout(Some(Duration::from_secs(600)))
+    ///     .load_llm(user_id, api_key, llm_id, LoadOptions::new())
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_timeout(&self, timeout: Option<Duration>) -> Self {
+        PantryAPI {
+            timeout,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this API retrying read-only calls according to `retry`.
+    pub fn with_retry(&self, retry: RetryPolicy) -> Self {
+        PantryAPI {
+            retry,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this API talking to the unix socket at `socket_path`.
+    pub fn with_socket_path(mut self, socket_path: impl Into<String>) -> Self {
+        self.socket_path = socket_path.into();
+        self
+    }
+
+    /// Waits on `fut`, giving up with [PantryError::Timeout] once the
+    /// configured timeout runs out.
+    async fn timed<T, E, F>(&self, fut: F) -> Result<T, PantryError>
+    where
+        F: Future<Output = Result<T, E>>,
+        PantryError: From<E>,
+    {
+        let duration = match self.timeout {
+            Some(duration) => duration,
+            None => return Ok(fut.await?),
+        };
+        futures::pin_mut!(fut);
+        match future::select(fut, Delay::new(duration)).await {
+            Either::Left((res, _)) => Ok(res?),
+            Either::Right(_) => Err(PantryError::Timeout(duration)),
+        }
+    }
+
+    /// Sends a call through whichever transport applies, reporting it to
+    /// [PantryAPI::metrics] if set and logging it with the `logging` feature.
+    ///
+    /// Waits for [PantryAPI::rate_limiter] first, if there is one.
+    async fn double_edge(
+        &self,
+        method: hyper::Method,
+        body: String,
+        path: String,
+    ) -> Result<hyper::Response<hyper::body::Body>, PantryError> {
+        self.double_edge_accepting(method, body, path, "application/json")
+            .await
+    }
+
+    /// [PantryAPI::double_edge], asking for an `accept`able response, for streaming
+    /// calls.
+    async fn double_edge_accepting(
+        &self,
+        method: hyper::Method,
+        body: String,
+        path: String,
+        accept: &'static str,
+    ) -> Result<hyper::Response<hyper::body::Body>, PantryError> {
+        self.double_edge_body(method, RequestBody::json(body), path, accept)
+            .await
+    }
+
+    /// [PantryAPI::double_edge_accepting], for bodies that aren't JSON.
+    async fn double_edge_body(
+        &self,
+        method: hyper::Method,
+        body: RequestBody,
+        path: String,
+        accept: &'static str,
+    ) -> Result<hyper::Response<hyper::body::Body>, PantryError> {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+        logging::log_request(&method, &path, &body.summary);
+        let started = Instant::now();
+        let endpoint = path.clone();
+        let res = match &self.fixtures {
+            Some(fixtures) => {
+                let request = body.summary.clone();
+                let send = self.send_request(method, body, path, accept);
+                fixtures.exchange(&endpoint, &request, send).await
+            }
+            None => self.send_request(method, body, path, accept).await,
+        };
+        if let Some(metrics) = &self.metrics {
+            metrics.request(&RequestMetric {
+                endpoint: endpoint.clone(),
+                latency: started.elapsed(),
+                status: res.as_ref().ok().map(|resp| resp.status()),
+            });
+        }
+        #[cfg(feature = "streaming")]
+        match &res {
+            Ok(resp) if resp.status().is_success() => {}
+            res => event_bus::publish(Some(&self.event_bus), || ClientEvent::Error {
+                endpoint: endpoint.clone(),
+                message: match res {
+                    Ok(resp) => resp.status().to_string(),
+                    Err(e) => e.to_string(),
+                },
+            }),
+        }
+        logging::log_response(&endpoint, res).await
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn send_request(
+        &self,
+        method: hyper::Method,
+        body: RequestBody,
+        path: String,
+        accept: &'static str,
+    ) -> Result<hyper::Response<hyper::body::Body>, PantryError> {
+        if body.content_type != RequestBody::JSON {
+            return Err(PantryError::Unsupported(format!(
+                "sending {} from the browser",
+                body.content_type
+            )));
+        }
+        let (url, headers) = match self.base_url.clone() {
+            Some(u) => (u, self.remote_headers()),
+            None => ("http://localhost:9404".into(), Vec::new()),
+        };
+        self.timed(crate::wasm::fetch(
+            method,
+            url + &path,
+            body.summary,
+            accept,
+            headers,
+        ))
+        .await
+    }
+
+    #[cfg(not(any(all(unix, feature = "unix-socket"), target_arch = "wasm32")))]
+    async fn send_request(
+        &self,
+        method: hyper::Method,
+        body: RequestBody,
+        path: String,
+        accept: &'static str,
+    ) -> Result<hyper::Response<hyper::body::Body>, PantryError> {
+        let (url, headers) = match self.base_url.clone() {
+            Some(u) => (u, self.remote_headers()),
+            None => ("http://localhost:9404/".into(), Vec::new()),
+        };
+        let url3 = url + &path;
+        let mut req3 = hyper::Request::builder()
+            .method(method.clone())
+            .header("Content-Type", body.content_type.as_str())
+            .header("Accept", accept)
+            .uri(url3);
+        for (name, value) in headers {
+            req3 = req3.header(name, value);
+        }
+        let req3: hyper::Request<hyper::body::Body> =
+            req3.body(hyper::Body::from(body.bytes.clone()))?;
+        return self.timed(self.client.request(req3)).await;
+    }
+
+    #[cfg(all(unix, feature = "unix-socket"))]
+    async fn send_request(
+        &self,
+        method: hyper::Method,
+        body: RequestBody,
+        path: String,
+        accept: &'static str,
+    ) -> Result<hyper::Response<hyper::body::Body>, PantryError> {
+        if let Some(url) = self.base_url.clone() {
+            let url3 = url + &path;
+            let mut req3 = hyper::Request::builder()
+                .method(method.clone())
+                .header("Content-Type", body.content_type.as_str())
+                .header("Accept", accept)
+                .uri(url3);
+            for (name, value) in self.remote_headers() {
+                req3 = req3.header(name, value);
+            }
+            let req3: hyper::Request<hyper::body::Body> =
+                req3.body(hyper::Body::from(body.bytes.clone()))?;
+            return self.timed(self.client.request(req3)).await;
+        }
+
+        let DEFAULT_URL: String = "http://localhost:9404/".into();
+        let url2 = DEFAULT_URL.clone() + &path;
+        let req2: hyper::Request<hyper::body::Body> = hyper::Request::builder()
+            .method(method.clone())
+            .header("Content-Type", body.content_type.as_str())
+            .header("Accept", accept)
+            .uri(url2)
+            .body(hyper::Body::from(body.bytes.clone()))?;
+
+        let tried_socket = self.should_try_socket();
+        if tried_socket {
+            let url1 = hyperlocal::Uri::new(&self.socket_path, &path.clone());
+            let req1: hyper::Request<hyper::body::Body> = hyper::Request::builder()
+                .method(method.clone())
+                .header("Content-Type", body.content_type.as_str())
+                .header("Accept", accept)
+                .uri(url1)
+                .body(hyper::Body::from(body.bytes.clone()))?;
+
+            let unix = Client::unix();
+
+            match self.timed(unix.request(req1)).await {
+                Ok(resp) => {
+                    self.remember_transport(Transport::UnixSocket);
+                    return Ok(resp);
+                }
+                Err(PantryError::Timeout(duration)) => return Err(PantryError::Timeout(duration)),
+                #[cfg(feature = "logging")]
+                Err(err) => log::info!("Unix socket failed, trying TCP: {:?}", err),
+                #[cfg(not(feature = "logging"))]
+                Err(_) => {}
+            }
+        }
+
+        #[cfg(feature = "logging")]
+        let uri = req2.uri().clone();
+        let resp = self.timed(self.client.request(req2)).await?;
+        // Only worth sticking to once TCP works, and only worth mentioning when we
+        // actually switch over.
+        if tried_socket && self.remember_transport(Transport::Tcp) != Some(Transport::Tcp) {
+            #[cfg(feature = "logging")]
+            log::warn!("Unix socket unavailable, falling back to {}", uri);
+        }
+        Ok(resp)
+    }
+
+    /// Headers of [PantryAPI::remote_auth], if any.
+    fn remote_headers(&self) -> Vec<(String, String)> {
+        self.remote_auth
+            .as_ref()
+            .map(RemoteAuth::to_headers)
+            .unwrap_or_default()
+    }
+
+    /// Whether the next call should go through the unix socket. Once the socket fails
+    /// we stick to TCP, only retrying the socket every [PantryAPI::transport_reprobe].
+    #[cfg(all(unix, feature = "unix-socket"))]
+    fn should_try_socket(&self) -> bool {
+        let memo = self.transport.lock().unwrap();
+        match *memo {
+            Some(TransportMemo {
+                transport: Transport::Tcp,
+                checked,
+            }) => checked.elapsed() >= self.transport_reprobe,
+            _ => true,
+        }
+    }
+
+    /// Records the transport that just worked, returning the previous one.
+    #[cfg(all(unix, feature = "unix-socket"))]
+    fn remember_transport(&self, transport: Transport) -> Option<Transport> {
+        let mut memo = self.transport.lock().unwrap();
+        let previous = memo.as_ref().map(|m| m.transport);
+        *memo = Some(TransportMemo {
+            transport,
+            checked: Instant::now(),
+        });
+        previous
+    }
+
+    /// The transport the last call without a `base_url` went through, if any call has
+    /// been made yet.
+    pub fn current_transport(&self) -> Option<Transport> {
+        self.transport.lock().unwrap().as_ref().map(|m| m.transport)
+    }
+
+    /// Checks whether Pantry answers over `transport`, without falling back to the
+    /// other one. Any HTTP response counts, so this works with Pantry versions of any
+    /// age. Needs no credentials.
+    ///
+    /// TCP goes to `base_url`, or `http://localhost:9404` without one. Unix sockets fail
+    /// with [PantryError::Unsupported] where they're not available.
+    pub async fn probe(&self, transport: Transport) -> Result<(), PantryError> {
+        match transport {
+            Transport::Tcp => self.probe_tcp().await,
+            Transport::UnixSocket => self.probe_socket().await,
+        }
+    }
+
+    fn probe_url(&self) -> String {
+        let url = match self.base_url.clone() {
+            Some(u) => u,
+            None => "http://localhost:9404".into(),
+        };
+        url.trim_end_matches('/').to_string() + "/server_info"
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn probe_tcp(&self) -> Result<(), PantryError> {
+        let mut req = hyper::Request::builder()
+            .method(hyper::Method::POST)
+            .header("Content-Type", "application/json")
+            .uri(self.probe_url());
+        for (name, value) in self.remote_headers() {
+            req = req.header(name, value);
+        }
+        let req = req.body(hyper::Body::from("{}"))?;
+        self.timed(self.client.request(req)).await.map(|_| ())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn probe_tcp(&self) -> Result<(), PantryError> {
+        self.timed(crate::wasm::fetch(
+            hyper::Method::POST,
+            self.probe_url(),
+            "{}".into(),
+            "application/json",
+            self.remote_headers(),
+        ))
+        .await
+        .map(|_| ())
+    }
+
+    #[cfg(all(unix, feature = "unix-socket"))]
+    async fn probe_socket(&self) -> Result<(), PantryError> {
+        let req = hyper::Request::builder()
+            .method(hyper::Method::POST)
+            .header("Content-Type", "application/json")
+            .uri(hyperlocal::Uri::new(&self.socket_path, "/server_info"))
+            .body(hyper::Body::from("{}"))?;
+        self.timed(Client::unix().request(req)).await.map(|_| ())
+    }
+
+    #[cfg(not(all(unix, feature = "unix-socket")))]
+    async fn probe_socket(&self) -> Result<(), PantryError> {
+        Err(PantryError::Unsupported(
+            "unix sockets, which need unix and the unix-socket feature".into(),
+        ))
+    }
+
+    /// Asks the daemon for its version and which endpoints it serves. Needs no
+    /// credentials.
+    ///
+    /// Daemons from before this endpoint existed fail with [PantryError::Unsupported],
+    /// which still means Pantry is running.
+    pub async fn server_info(&self) -> Result<ServerInfo, PantryError> {
+        let resp = self
+            .retry
+            .run(|| self.double_edge(hyper::Method::POST, "{}".into(), "/server_info".to_string()))
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(body_str)?)
+            }
+            StatusCode::NOT_FOUND => Err(PantryError::Unsupported(
+                "server_info, this Pantry predates it".into(),
+            )),
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/server_info",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Accessing the API requires a registered user demarcated by a user_id and an api_key.
+    ///
+    /// This function supplies both. When using the API manually, you'll probably also
+    /// need to call [PantryAPI::request_permissions] to do anything useful.
+    ///
+    /// # Arguments
+    /// * `user_name` — used for debug output and manager display.
+    pub async fn register_user(&self, user_name: String) -> Result<UserInfo, PantryError> {
+        let register_user_request = RegisterUserRequest { user_name };
+
+        let body = serde_json::to_string(&register_user_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, format!("/register_user"))
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/register_user",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+
+        // let damn: UserInfo = serde_json::from_slice(ff).unwrap();
+        // Ok(serde_json::from_slice(&ff)?)
+    }
+
+    /// Requests permissions. See the [UserPermissions] struct for more details.
+    /// The system owner must accept the request (currently in the UI).
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `requested_permissions` — The permissions this api user wants, limited to the
+    ///   LLMs in their `llm_scope` if set.
+    pub async fn request_permissions(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        requested_permissions: UserPermissions,
+    ) -> Result<UserRequestStatus, PantryError> {
+        self.send_permission_request(user_id, api_key, requested_permissions, None)
+            .await
+    }
+
+    /// Like [PantryAPI::request_permissions], for permissions that lapse `duration`
+    /// after they're accepted.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `requested_permissions` — The permissions this api user wants, limited to the
+    ///   LLMs in their `llm_scope` if set.
+    /// * `duration` — How long the grant should last once accepted.
+    pub async fn request_permissions_with(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        requested_permissions: UserPermissions,
+        duration: Duration,
+    ) -> Result<UserRequestStatus, PantryError> {
+        self.send_permission_request(user_id, api_key, requested_permissions, Some(duration))
+            .await
+    }
+
+    async fn send_permission_request(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        requested_permissions: UserPermissions,
+        duration: Option<Duration>,
+    ) -> Result<UserRequestStatus, PantryError> {
+        let request_permission_request = RequestPermissionRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            requested_permissions,
+            duration_secs: duration.map(|d| d.as_secs().max(1)),
+        };
+        let body = serde_json::to_string(&request_permission_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, format!("/request_permissions"))
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/request_permissions",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Creates a request to download a new model. Must be accepted by the system
+    /// owner (currently via the UI).
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `llm_registry_entry` — A valid LLM registry entry to download. This specifies
+    /// the location of the model as well as any metadata. For better usability, try
+    /// being comprehensive about this.
+    /// * `target_dir` — Directory on the Pantry machine to store the model in, `None`
+    ///   for Pantry's own.
+    pub async fn request_download(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_registry_entry: LLMRegistryEntry,
+        target_dir: Option<String>,
+    ) -> Result<UserRequestStatus, PantryError> {
+        let request_download_request = RequestDownloadRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            llm_registry_entry: serde_json::to_string(&llm_registry_entry)?,
+            target_dir,
+        };
+        let body = serde_json::to_string(&request_download_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, format!("/request_download"))
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/request_download",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Requests a load, but doesn't predetermine the exact LLM ahead of time.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `filter` — An [LLMFilter] specifying hard requirements for the LLM.
+    /// * `preference` — An [LLMPreference] specifying soft requirements for the LLM.
+    pub async fn request_load_flex(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        filter: Option<LLMFilter>,
+        preference: Option<LLMPreference>,
+    ) -> Result<UserRequestStatus, PantryError> {
+        let request_load_request = RequestLoadFlexRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            filter,
+            preference,
+        };
+        let body = serde_json::to_string(&request_load_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, format!("/request_load"))
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/request_load",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Requests Pantry to load a specific LLM.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `llm_id` — A UUID for the LLM you want to load. Find one via [PantryAPI::get_available_llms].
+    pub async fn request_load(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: LlmUuid,
+    ) -> Result<UserRequestStatus, PantryError> {
+        let request_load_request = RequestLoadRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            llm_id: llm_id.to_string(),
+        };
+        let body = serde_json::to_string(&request_load_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, format!("/request_load"))
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/request_load",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Requests an LLM be shutdown, conserving resources. This should
+    /// generally be non-destructive—Pantry attempts to save sessions to disc on shutdown.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `llm_id` — UUID of the LLM. Find running llms via [PantryAPI::get_running_llms].
+    pub async fn request_unload(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: LlmUuid,
+    ) -> Result<UserRequestStatus, PantryError> {
+        let request_unload_request = RequestUnloadRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            llm_id: llm_id.to_string(),
+        };
+        let body = serde_json::to_string(&request_unload_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, format!("/request_unload"))
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/request_unload",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    pub async fn get_request_status(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        request_id: RequestId,
+    ) -> Result<UserRequestStatus, PantryError> {
+        let request_unload_request = RequestStatusRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            request_id: request_id.to_string(),
+        };
+        let body = serde_json::to_string(&request_unload_request)?;
+        let resp = self
+            .retry
+            .run(|| {
+                self.double_edge(
+                    hyper::Method::POST,
+                    body.clone(),
+                    "/get_request_status".to_string(),
+                )
+            })
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/get_request_status",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Gets the current status of an LLM
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// *
+    pub async fn get_llm_status(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: LlmUuid,
+    ) -> Result<LLMStatus, PantryError> {
+        let request_unload_request = GetLLMStatusRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            llm_id: llm_id.to_string(),
+        };
+        let body = serde_json::to_string(&request_unload_request)?;
+        let resp = self
+            .retry
+            .run(|| {
+                self.double_edge(
+                    hyper::Method::POST,
+                    body.clone(),
+                    "/get_llm_status".to_string(),
+                )
+            })
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/get_llm_status",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Gets the calling user's info, including the permissions they've been granted.
+    ///
+    /// Needs no permissions, so it doubles as a check that the credentials are valid:
+    /// unknown or revoked keys fail with [PantryError::InvalidApiKey].
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    pub async fn get_user_info(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<UserInfo, PantryError> {
+        let get_user_info_request = GetUserInfoRequest {
+            user_id: user_id.to_string(),
+            api_key,
+        };
+        let body = serde_json::to_string(&get_user_info_request)?;
+        let resp = self
+            .retry
+            .run(|| {
+                self.double_edge(
+                    hyper::Method::POST,
+                    body.clone(),
+                    "/get_user_info".to_string(),
+                )
+            })
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/get_user_info",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Gets currently running LLMs.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    pub async fn get_running_llms(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<Vec<LLMStatus>, PantryError> {
+        let request_running_llms = GetRunningLLMRequest {
+            user_id: user_id.to_string(),
+            api_key,
+        };
+        let body = serde_json::to_string(&request_running_llms)?;
+        let resp = self
+            .retry
+            .run(|| {
+                self.double_edge(
+                    hyper::Method::POST,
+                    body.clone(),
+                    "/get_running_llms".to_string(),
+                )
+            })
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/get_running_llms",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Gets the RAM, VRAM and disk usage of the machine Pantry runs on, and what each
+    /// running LLM takes up.
+    ///
+    /// Requires [UserPermissions::perm_view_llms]. Fails with [PantryError::Unsupported]
+    /// on Pantry versions that don't report it.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    pub async fn get_system_status(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<SystemStatus, PantryError> {
+        let request = GetSystemStatusRequest {
+            user_id: user_id.to_string(),
+            api_key,
+        };
+        let body = serde_json::to_string(&request)?;
+        let resp = self
+            .retry
+            .run(|| {
+                self.double_edge(
+                    hyper::Method::POST,
+                    body.clone(),
+                    "/get_system_status".to_string(),
+                )
+            })
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(body_str)?)
+            }
+            StatusCode::NOT_FOUND => Err(PantryError::Unsupported(
+                "get_system_status, this Pantry predates it".into(),
+            )),
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/get_system_status",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Gets the on-disk size and location of each downloaded LLM, and their total.
+    ///
+    /// Requires [UserPermissions::perm_view_llms]. Fails with [PantryError::Unsupported]
+    /// on Pantry versions that don't report it.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    pub async fn get_storage_info(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<StorageInfo, PantryError> {
+        let request = GetStorageInfoRequest {
+            user_id: user_id.to_string(),
+            api_key,
+        };
+        let body = serde_json::to_string(&request)?;
+        let resp = self
+            .retry
+            .run(|| {
+                self.double_edge(
+                    hyper::Method::POST,
+                    body.clone(),
+                    "/get_storage_info".to_string(),
+                )
+            })
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(body_str)?)
+            }
+            StatusCode::NOT_FOUND => Err(PantryError::Unsupported(
+                "get_storage_info, this Pantry predates it".into(),
+            )),
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/get_storage_info",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Gets currently downloaded LLMs.
+    ///
+    /// In order to create a session, these must first be activated, requiring the
+    /// correct permissions. If you're the system owner, you can also activate
+    /// them manually using the UI.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    pub async fn get_available_llms(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<Vec<LLMStatus>, PantryError> {
+        let request_available_llms = GetAvailableLLMRequest {
+            user_id: user_id.to_string(),
+            api_key,
+        };
+        let body = serde_json::to_string(&request_available_llms)?;
+        let resp = self
+            .retry
+            .run(|| {
+                self.double_edge(
+                    hyper::Method::POST,
+                    body.clone(),
+                    "/get_available_llms".to_string(),
+                )
+            })
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/get_available_llms",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Gets the user's sessions, across all LLMs.
+    ///
+    /// Includes sessions Pantry has moved to disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    pub async fn get_sessions(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<Vec<LLMSessionStatus>, PantryError> {
+        let get_sessions_request = GetSessionsRequest {
+            user_id: user_id.to_string(),
+            api_key,
+        };
+        let body = serde_json::to_string(&get_sessions_request)?;
+        let resp = self
+            .retry
+            .run(|| {
+                self.double_edge(
+                    hyper::Method::POST,
+                    body.clone(),
+                    "/get_sessions".to_string(),
+                )
+            })
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/get_sessions",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Puts a session Pantry has moved to disk back into memory.
+    ///
+    /// Doing this repeatedly for different sessions will result in thrash. The session's
+    /// LLM must be running, or Pantry will return an error. Loading a session that's
+    /// already in memory is fine and just returns it.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `session_id` — A UUID of a session. You should have gotten it from creating your session.
+    pub async fn load_session_id(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+    ) -> Result<CreateSessionResponse, PantryError> {
+        let load_session_id_request = LoadSessionIdRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            session_id: session_id.to_string(),
+        };
+        let body = serde_json::to_string(&load_session_id_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, "/load_session_id".to_string())
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/load_session_id",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Duplicates a session's state into a new session on the same LLM.
+    ///
+    /// The copy starts with everything the original has seen, and the two diverge from
+    /// there. You must own the session.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `session_id` — A UUID of a session. You should have gotten it from creating your session.
+    pub async fn fork_session(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+    ) -> Result<CreateSessionResponse, PantryError> {
+        let fork_session_request = ForkSessionRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            session_id: session_id.to_string(),
+        };
+        let body = serde_json::to_string(&fork_session_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, "/fork_session".to_string())
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/fork_session",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Gets a session's prompts and completions, oldest first.
+    ///
+    /// You must own the session.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `session_id` — A UUID of a session. You should have gotten it from creating your session.
+    pub async fn get_session_history(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+    ) -> Result<Vec<LLMHistoryItem>, PantryError> {
+        let get_session_history_request = GetSessionHistoryRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            session_id: session_id.to_string(),
+        };
+        let body = serde_json::to_string(&get_session_history_request)?;
+        let resp = self
+            .retry
+            .run(|| {
+                self.double_edge(
+                    hyper::Method::POST,
+                    body.clone(),
+                    "/get_session_history".to_string(),
+                )
+            })
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/get_session_history",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Deletes a session, freeing its memory and any state Pantry saved to disk.
+    ///
+    /// Interrupts inference if it's ongoing. You must own the session to delete it.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `session_id` — A UUID of a session. You should have gotten it from creating your session.
+    pub async fn delete_session(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+    ) -> Result<(), PantryError> {
+        let delete_session_request = DeleteSessionRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            session_id: session_id.to_string(),
+        };
+        let body = serde_json::to_string(&delete_session_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, "/delete_session".to_string())
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Nothing useful in the body.
+                hyper::body::to_bytes(resp.into_body()).await?;
+                Ok(())
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/delete_session",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Interrupts an ongoing inference session.
+    ///
+    /// Internally this uses a cancellation callback to cancel inference _after the next token_.
+    /// Depending on your system, this might take a moment, especially given that some
+    /// tokens might already be inferred but not yet transmitted.
+    ///
+    /// You must own the session to interrupt it.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `llm_id` — A UUID of an LLM. You should have gotten it from creating your session.
+    /// * `session_id` — A UUID of a session. You should have gotten it from creating your session.
+    pub async fn interrupt_session(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: LlmUuid,
+        session_id: SessionId,
+    ) -> Result<LLMRunningStatus, PantryError> {
+        let interrupt_session_request = InterruptSessionRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            llm_uuid: llm_id.to_string(),
+            session_id: session_id.to_string(),
+        };
+        let body = serde_json::to_string(&interrupt_session_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, format!("/interrupt_session"))
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/interrupt_session",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Loads an LLM.
+    ///
+    /// Requires the [UserPermissions::perm_load_llm] permission.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `filter` — A [LLMFilter] object, for what _must_ be true of an LLM to load it.
+    /// * `preference` — A [LLMPreference] object, for how to rank and then select from the LLMs
+    /// that pass the filter.
+    /// * `options` — Resource limits for this load, see [LoadOptions].
+    pub async fn load_llm_flex(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        filter: Option<LLMFilter>,
+        preference: Option<LLMPreference>,
+        options: LoadOptions,
+    ) -> Result<LLMRunningStatus, PantryError> {
+        let load_llm_request = LoadLLMFlexRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            filter,
+            preference,
+            config: options,
+        };
+        let body = serde_json::to_string(&load_llm_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, format!("/load_llm_flex"))
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/load_llm_flex",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Loads an LLM.
+    ///
+    /// Requires [UserPermissions::perm_load_llm].
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `llm_id` — UUID or ID of an LLM. Will fail for duplicate IDs.
+    /// * `options` — Resource limits for this load, see [LoadOptions].
+    pub async fn load_llm(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: String,
+        options: LoadOptions,
+    ) -> Result<LLMRunningStatus, PantryError> {
+        let load_llm_request = LoadLLMRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            llm_id: llm_id.to_string(),
+            config: options,
+        };
+        let bod = serde_json::to_string(&load_llm_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, bod, format!("/load_llm"))
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/load_llm",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Unloads an LLM, conserving resources.
+    ///
+    /// Requires [UserPermissions::perm_unload_llm].
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `llm_id` — UUID or model id of an LLM.
+    pub async fn unload_llm(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: String,
+    ) -> Result<LLMStatus, PantryError> {
+        let unload_llm_request = UnloadLLMRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            llm_id,
+        };
+        let body = serde_json::to_string(&unload_llm_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, format!("/unload_llm"))
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/unload_llm",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Downloads an LLM.
+    ///
+    /// Requires [UserPermissions::perm_download_llm].
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `llm_registry_entry` — [LLMRegistryEntry] for the LLM. The only "mandatory" fields are
+    /// the [LLMRegistryEntry::connector_type] and [LLMRegistryEntry::id]. If the connector type is
+    /// [crate::interface::LLMConnectorType::LLMrs], config must include the key `model_architecture`. For more
+    /// details see the [rustformers/llm
+    /// documentation](https://docs.rs/llm/latest/llm/enum.ModelArchitecture.html)
+    /// * `target_dir` — Directory on the Pantry machine to store the model in, `None`
+    ///   for Pantry's own.
+    pub async fn download_llm(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_registry_entry: LLMRegistryEntry,
+        target_dir: Option<String>,
+    ) -> Result<Value, PantryError> {
+        let download_llm_request = DownloadLLMRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            llm_registry_entry,
+            target_dir,
+        };
+        let body = serde_json::to_string(&download_llm_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, format!("/download_llm"))
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/download_llm",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Creates a session, using the best currently running LLM.
+    ///
+    /// Requires [UserPermissions::perm_session].
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `user_session_parameters` — A hashmap of _requested_ parameters. The returning
+    /// [LLMStatus] object will inform which ones got accepted by the LLM.
+    pub async fn create_session(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        user_session_parameters: HashMap<String, Value>,
+    ) -> Result<CreateSessionResponse, PantryError> {
+        let create_session_request = CreateSessionRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            user_session_parameters,
+        };
+        let body = serde_json::to_string(&create_session_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, format!("/create_session"))
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/create_session",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Creates a session, using the LLM with the given id. If the LLM doesn't exist or isn't
+    /// running, errors.
+    ///
+    /// Requires [UserPermissions::perm_session].
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user] OR a machine-id. The API
+    /// will attempt to match on UUID first, otherwise treat it as a flex request matching on id.
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `llm_id` — A UUID for which LLM to use.
+    /// * `user_session_parameters` — A hashmap of _requested_ parameters. The returning
+    /// [LLMStatus] object will inform which ones got accepted by the LLM.
+    pub async fn create_session_id(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: LlmUuid,
+        user_session_parameters: HashMap<String, Value>,
+    ) -> Result<CreateSessionResponse, PantryError> {
+        let create_session_id_request = CreateSessionIdRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            llm_id: llm_id.to_string(),
+            user_session_parameters,
+        };
+        let body = serde_json::to_string(&create_session_id_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, format!("/create_session_id"))
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/create_session_id",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Creates a session based on `filter` and `preference`. Selects only from currently running
+    /// LLMs.
+    ///
+    /// Requires [UserPermissions::perm_session].
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `filter` — A [LLMFilter] object, for what _must_ be true of an LLM to use it.
+    /// * `preference` — A [LLMPreference] object, for how to rank and then select from the LLMs
+    /// * `user_session_parameters` — A hashmap of _requested_ parameters. The returning
+    /// [LLMStatus] object will inform which ones got accepted by the LLM.
+    pub async fn create_session_flex(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        filter: Option<LLMFilter>,
+        preference: Option<LLMPreference>,
+        user_session_parameters: HashMap<String, Value>,
+    ) -> Result<CreateSessionResponse, PantryError> {
+        let create_session_flex_request = CreateSessionFlexRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            filter,
+            preference,
+            user_session_parameters,
+        };
+        let body = serde_json::to_string(&create_session_flex_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, format!("/create_session_flex"))
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(&body_str)?)
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/create_session_flex",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
+
+    /// Prompts a session, triggering inference by the LLM.
+    ///
+    /// Requires [UserPermissions::perm_session].
+    ///
+    /// Session must be running first, call [PantryAPI::create_session],
+    /// [PantryAPI::create_session_id], or [PantryAPI::create_session_flex] first.
+    ///
+    /// If the connection drops midway, the stream reconnects and carries on from the
+    /// last event it got, as set by [PantryAPI::reconnect].
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `session_id` — A UUID representing the session. Obtained by calling
+    /// [PantryAPI::create_session] or its variants.
+    /// * `llm_uuid` — UUID of the llm. Must match the call used to make the session.
+    /// * `prompt` — Prompt for the LLM. Pantry does no preprompting, so if you want a
+    /// chatbot style response, you'll need to insert a chatbot type prompt _then_ whatever
+    /// the user requested.
+    /// * `parameters` — Things like temperature or k value. Whats available varies by LLM,
+    /// you can find out what an LLM has either in the UI or in the `user_parameters` and
+    /// `user_session_parameters` vectors of an [LLMStatus].
+    #[cfg(feature = "streaming")]
+    pub async fn prompt_session_stream(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+        llm_uuid: LlmUuid,
+        prompt: String,
+        parameters: HashMap<String, Value>,
+    ) -> Result<LLMEventStream, PantryError> {
+        let prompt_session_stream_request = PromptSessionStreamRequest {
+            user_id: user_id.to_string(),
+            api_key: api_key.clone(),
+            session_id: session_id.to_string(),
+            llm_uuid: llm_uuid.to_string(),
+            prompt,
+            parameters,
+            resume_stream_id: None,
+            last_event_id: None,
+            attachments: Vec::new(),
+        };
+        let body = serde_json::to_string(&prompt_session_stream_request)?;
+        self.start_prompt_stream(
+            RequestBody::json(body),
+            prompt_session_stream_request,
+            user_id,
+            api_key,
+            session_id,
+            llm_uuid,
+        )
+        .await
+    }
+
+    /// Prompts a session with text and images, see [crate::multimodal]. Otherwise the
+    /// same as [PantryAPI::prompt_session_stream].
+    ///
+    /// Whether the LLM can see images is up to it, see [LLMStatus::accepts_images].
+    /// Prompts without images go through [PantryAPI::prompt_session_stream].
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` — The prompt, text and images in the order the LLM should see them.
+    /// * the rest — As for [PantryAPI::prompt_session_stream].
+    #[cfg(feature = "streaming")]
+    pub async fn prompt_session_stream_multimodal(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+        llm_uuid: LlmUuid,
+        inputs: Vec<PromptInput>,
+        parameters: HashMap<String, Value>,
+    ) -> Result<LLMEventStream, PantryError> {
+        let prompt = multimodal::split_inputs(inputs, self.attachment_transport);
+        if prompt.attachments.is_empty() {
+            return self
+                .prompt_session_stream(
+                    user_id,
+                    api_key,
+                    session_id,
+                    llm_uuid,
+                    prompt.text,
+                    parameters,
+                )
+                .await;
+        }
+        let prompt_session_stream_request = PromptSessionStreamRequest {
+            user_id: user_id.to_string(),
+            api_key: api_key.clone(),
+            session_id: session_id.to_string(),
+            llm_uuid: llm_uuid.to_string(),
+            prompt: prompt.text,
+            parameters,
+            resume_stream_id: None,
+            last_event_id: None,
+            attachments: prompt.attachments,
+        };
+        let json = serde_json::to_string(&prompt_session_stream_request)?;
+        let body = RequestBody::with_attachments(
+            json,
+            &prompt_session_stream_request.attachments,
+            &prompt.parts,
+        );
+        self.start_prompt_stream(
+            body,
+            prompt_session_stream_request,
+            user_id,
+            api_key,
+            session_id,
+            llm_uuid,
+        )
+        .await
+    }
+
+    /// Transcribes speech with a Whisper-class LLM, streaming the transcript as it
+    /// comes, see [crate::transcription].
+    ///
+    /// Requires [UserPermissions::perm_session]. Transcripts arrive the way completions
+    /// do: each [interface::LLMEventInternal::PromptProgress] carries the next piece of
+    /// text, and the [interface::LLMEventInternal::PromptCompletion] the whole of it.
+    /// Unlike prompts, they aren't resumed when the connection drops.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `session_id` — A session of the LLM, see [PantryAPI::create_session_id].
+    /// * `llm_uuid` — UUID of the llm. Must match the call used to make the session.
+    /// * `audio` — An encoded recording, e.g. a WAV file.
+    /// * `mime` — Its type, e.g. `audio/wav`.
+    /// * `parameters` — e.g. `language`, see the LLM's `user_parameters`.
+    #[cfg(feature = "streaming")]
+    #[allow(clippy::too_many_arguments)]
+    pub async fn transcribe_stream(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+        llm_uuid: LlmUuid,
+        audio: Vec<u8>,
+        mime: String,
+        parameters: HashMap<String, Value>,
+    ) -> Result<LLMEventStream, PantryError> {
+        let mut parts = Vec::new();
+        let request = TranscribeStreamRequest {
+            user_id: user_id.to_string(),
+            api_key: api_key.clone(),
+            session_id: session_id.to_string(),
+            llm_uuid: llm_uuid.to_string(),
+            audio: multimodal::attach(audio, mime, 0, self.attachment_transport, &mut parts),
+            parameters,
+        };
+        let json = serde_json::to_string(&request)?;
+        let body =
+            RequestBody::with_attachments(json, std::slice::from_ref(&request.audio), &parts);
+
+        let sent = Instant::now();
+        let resp = self
+            .double_edge_body(
+                hyper::Method::POST,
+                body,
+                "/transcribe_stream".into(),
+                self.stream_format.accept(),
+            )
+            .await?;
+        if resp.status() != StatusCode::OK {
+            let code = resp.status();
+            let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+            let body_str = std::str::from_utf8(&body_bytes)?;
+            return Err(PantryError::from_endpoint_response(
+                "/transcribe_stream",
+                code,
+                body_str,
+            ));
+        }
+        let mut stream = LLMEventStream::new(
+            decode_events(resp),
+            Arc::new(self.clone()),
+            user_id,
+            api_key,
+            session_id,
+            llm_uuid,
+            sent,
+        );
+        stream.set_stall_timeout(self.stall_timeout);
+        Ok(stream)
+    }
+
+    /// Sends a prompt, and wraps its response in a stream.
+    #[cfg(feature = "streaming")]
+    async fn start_prompt_stream(
+        &self,
+        body: RequestBody,
+        prompt_session_stream_request: PromptSessionStreamRequest,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+        llm_uuid: LlmUuid,
+    ) -> Result<LLMEventStream, PantryError> {
+        let sent = Instant::now();
+        let resp = self
+            .double_edge_body(
+                hyper::Method::POST,
+                body,
+                "/prompt_session_stream".into(),
+                self.stream_format.accept(),
+            )
+            .await?;
+        if resp.status() != StatusCode::OK {
+            let code = resp.status();
+            let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+            let body_str = std::str::from_utf8(&body_bytes)?;
+            return Err(PantryError::from_endpoint_response(
+                "/prompt_session_stream",
+                code,
+                body_str,
+            ));
+        }
+        let mut stream = LLMEventStream::new(
+            resumable_events(self.clone(), prompt_session_stream_request, resp),
+            Arc::new(self.clone()),
+            user_id,
+            api_key,
+            session_id,
+            llm_uuid,
+            sent,
+        );
+        stream.set_stall_timeout(self.stall_timeout);
+        Ok(stream)
+    }
+
+    /// Acquire a bare model.
+    ///
+    /// In practice this means a file path to a GGML file that you can then run yourself
+    /// with whatever your preferred LLM runner is.
+    ///
+    /// Requires [UserPermissions::perm_bare_model].
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `llm_id` — UUID of an LLM.
+    pub async fn bare_model(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: String,
+    ) -> Result<BareModelResponse, PantryError> {
+        let load_llm_request = BareModelRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            llm_id: llm_id.to_string(),
+        };
+        let bod = serde_json::to_string(&load_llm_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/register_user"))
+            .double_edge(hyper::Method::POST, bod, format!("/bare_model"))
             .await?;
         match resp.status() {
             StatusCode::OK => {
@@ -370,7 +3243,7 @@ impl PantryAPI {
                 // Convert the body bytes to utf-8
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(body_str)?)
+                Ok(serde_json::from_str(&body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
@@ -379,300 +3252,407 @@ impl PantryAPI {
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
 
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/bare_model",
+                    code,
+                    body_str,
+                ))
             }
         }
-
-        // let damn: UserInfo = serde_json::from_slice(ff).unwrap();
-        // Ok(serde_json::from_slice(&ff)?)
     }
 
-    /// Requests permissions. See the [UserPermissions] struct for more details.
-    /// The system owner must accept the request (currently in the UI).
+    /// Has Pantry put a bare model's file at `destination`, for consumers that can't
+    /// reach the path [PantryAPI::bare_model] returns, e.g. sandboxed apps. The returned
+    /// path is where the file ended up.
+    ///
+    /// Requires [UserPermissions::perm_bare_model].
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// * `requested_permissions` — The permissions this api user wants.
-    pub async fn request_permissions(
+    /// * `llm_id` — UUID of an LLM.
+    /// * `destination` — Path on the Pantry machine to put the file at, or a directory
+    ///   to put it in under its own name.
+    /// * `mode` — Whether to copy or link the file.
+    pub async fn export_bare_model(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        requested_permissions: UserPermissions,
-    ) -> Result<UserRequestStatus, PantryError> {
-        let request_permission_request = RequestPermissionRequest {
+        llm_id: String,
+        destination: String,
+        mode: ExportMode,
+    ) -> Result<BareModelResponse, PantryError> {
+        let export_request = ExportBareModelRequest {
             user_id: user_id.to_string(),
             api_key,
-            requested_permissions,
+            llm_id,
+            destination,
+            mode,
         };
-        let body = serde_json::to_string(&request_permission_request)?;
+        let body = serde_json::to_string(&export_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/request_permissions"))
+            .double_edge(hyper::Method::POST, body, "/export_bare_model".into())
             .await?;
         match resp.status() {
             StatusCode::OK => {
-                // Get the response body bytes.
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
-
-                // Convert the body bytes to utf-8
-                // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
-
-                // Convert the body bytes to utf-8
-                // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/export_bare_model",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    /// Creates a request to download a new model. Must be accepted by the system
-    /// owner (currently via the UI).
+    /// Like [PantryAPI::bare_model], but Pantry won't delete or move the file until the
+    /// lease is released with [PantryAPI::release_bare_model], or `ttl` passes.
+    ///
+    /// Requires [UserPermissions::perm_bare_model].
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// * `llm_registry_entry` — A valid LLM registry entry to download. This specifies
-    /// the location of the model as well as any metadata. For better usability, try
-    /// being comprehensive about this.
-    pub async fn request_download(
+    /// * `llm_id` — UUID of an LLM.
+    /// * `ttl` — How long to pin the file for at most.
+    pub async fn bare_model_lease(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        llm_registry_entry: LLMRegistryEntry,
-    ) -> Result<UserRequestStatus, PantryError> {
-        let request_download_request = RequestDownloadRequest {
+        llm_id: String,
+        ttl: Duration,
+    ) -> Result<BareModelLeaseResponse, PantryError> {
+        let lease_request = BareModelLeaseRequest {
             user_id: user_id.to_string(),
             api_key,
-            llm_registry_entry: serde_json::to_string(&llm_registry_entry)?,
+            llm_id,
+            ttl_secs: ttl.as_secs().max(1),
         };
-        let body = serde_json::to_string(&request_download_request)?;
+        let body = serde_json::to_string(&lease_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/request_download"))
+            .double_edge(hyper::Method::POST, body, "/bare_model_lease".into())
             .await?;
         match resp.status() {
             StatusCode::OK => {
-                // Get the response body bytes.
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
-
-                // Convert the body bytes to utf-8
-                // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
-
-                // Convert the body bytes to utf-8
-                // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
+                Err(PantryError::from_endpoint_response(
+                    "/bare_model_lease",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
 
-                Err(PantryError::ApiError(code, body_str.into()))
+    /// Releases a lease from [PantryAPI::bare_model_lease], letting Pantry delete or
+    /// move the file again. Releasing a lease that has already expired is fine.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `lease_id` — The lease, from [BareModelLeaseResponse::lease_id].
+    pub async fn release_bare_model(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        lease_id: LeaseId,
+    ) -> Result<(), PantryError> {
+        let release_request = ReleaseBareModelRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            lease_id: lease_id.to_string(),
+        };
+        let body = serde_json::to_string(&release_request)?;
+        let resp = self
+            .double_edge(hyper::Method::POST, body, "/release_bare_model".into())
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                // Nothing useful in the body.
+                hyper::body::to_bytes(resp.into_body()).await?;
+                Ok(())
+            }
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Err(PantryError::from_endpoint_response(
+                    "/release_bare_model",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    /// Requests a load, but doesn't predetermine the exact LLM ahead of time.
+    /// Gives Pantry the secret a connector authenticates with, e.g. the OpenAI API
+    /// key. Unlike [LLMStatus::config], secrets are never sent back, so this is where
+    /// keys belong rather than in a registry entry.
+    ///
+    /// Requires [UserPermissions::perm_download_llm].
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// * `filter` — An [LLMFilter] specifying hard requirements for the LLM.
-    /// * `preference` — An [LLMPreference] specifying soft requirements for the LLM.
-    pub async fn request_load_flex(
+    /// * `connector` — The connector to use the secret for.
+    /// * `secret` — The secret, replacing any the connector had.
+    pub async fn set_connector_secret(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        filter: Option<LLMFilter>,
-        preference: Option<LLMPreference>,
-    ) -> Result<UserRequestStatus, PantryError> {
-        let request_load_request = RequestLoadFlexRequest {
+        connector: LLMConnectorType,
+        secret: String,
+    ) -> Result<(), PantryError> {
+        let secret_request = SetConnectorSecretRequest {
             user_id: user_id.to_string(),
             api_key,
-            filter,
-            preference,
+            connector,
+            secret,
         };
-        let body = serde_json::to_string(&request_load_request)?;
+        let body = serde_json::to_string(&secret_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/request_load"))
+            .double_edge(hyper::Method::POST, body, "/set_connector_secret".into())
             .await?;
         match resp.status() {
             StatusCode::OK => {
-                // Get the response body bytes.
+                // Nothing useful in the body.
+                hyper::body::to_bytes(resp.into_body()).await?;
+                Ok(())
+            }
+            code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Err(PantryError::from_endpoint_response(
+                    "/set_connector_secret",
+                    code,
+                    body_str,
+                ))
+            }
+        }
+    }
 
-                // Convert the body bytes to utf-8
-                // let body = String::from_slice(body_bytes.into()).unwrap();
+    /// Lists the LoRA adapters Pantry has for an LLM, downloaded or offered by its
+    /// registry entry.
+    ///
+    /// Requires [UserPermissions::perm_view_llms].
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `llm_id` — UUID of the base LLM, or its id.
+    pub async fn list_adapters(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: String,
+    ) -> Result<Vec<AdapterStatus>, PantryError> {
+        let adapter_request = ListAdaptersRequest {
+            user_id: user_id.to_string(),
+            api_key,
+            llm_id,
+        };
+        let body = serde_json::to_string(&adapter_request)?;
+        let resp = self
+            .retry
+            .run(|| self.double_edge(hyper::Method::POST, body.clone(), "/list_adapters".into()))
+            .await?;
+        match resp.status() {
+            StatusCode::OK => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
-
-                // Convert the body bytes to utf-8
-                // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/list_adapters",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    /// Requests Pantry to load a specific LLM.
+    /// Downloads a LoRA adapter for an LLM. Adapters offered by the LLM's registry
+    /// entry can be passed as they are.
+    ///
+    /// Requires [UserPermissions::perm_download_llm].
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// * `llm_id` — A UUID for the LLM you want to load. Find one via [PantryAPI::get_available_llms].
-    pub async fn request_load(
+    /// * `llm_id` — UUID of the base LLM, or its id.
+    /// * `adapter` — The adapter to download.
+    pub async fn download_adapter(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        llm_id: Uuid,
-    ) -> Result<UserRequestStatus, PantryError> {
-        let request_load_request = RequestLoadRequest {
+        llm_id: String,
+        adapter: AdapterEntry,
+    ) -> Result<AdapterStatus, PantryError> {
+        let adapter_request = DownloadAdapterRequest {
             user_id: user_id.to_string(),
             api_key,
-            llm_id: llm_id.to_string(),
+            llm_id,
+            adapter,
         };
-        let body = serde_json::to_string(&request_load_request)?;
+        let body = serde_json::to_string(&adapter_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/request_load"))
+            .double_edge(hyper::Method::POST, body, "/download_adapter".into())
             .await?;
         match resp.status() {
             StatusCode::OK => {
-                // Get the response body bytes.
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
-
-                // Convert the body bytes to utf-8
-                // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
-
-                // Convert the body bytes to utf-8
-                // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/download_adapter",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    /// Requests an LLM be shutdown, conserving resources. This should
-    /// generally be non-destructive—Pantry attempts to save sessions to disc on shutdown.
+    /// Applies a downloaded adapter to a running LLM, until it's removed with
+    /// [PantryAPI::remove_adapter] or the LLM is unloaded.
+    ///
+    /// Requires [UserPermissions::perm_load_llm].
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// * `llm_id` — UUID of the LLM. Find running llms via [PantryAPI::get_running_llms].
-    pub async fn request_unload(
+    /// * `llm_uuid` — UUID of the running base LLM.
+    /// * `adapter_id` — Id of a downloaded adapter, see [PantryAPI::list_adapters].
+    pub async fn apply_adapter(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        llm_id: Uuid,
-    ) -> Result<UserRequestStatus, PantryError> {
-        let request_unload_request = RequestUnloadRequest {
+        llm_uuid: LlmUuid,
+        adapter_id: String,
+    ) -> Result<LLMStatus, PantryError> {
+        let adapter_request = AdapterRequest {
             user_id: user_id.to_string(),
             api_key,
-            llm_id: llm_id.to_string(),
+            llm_uuid: llm_uuid.to_string(),
+            adapter_id,
         };
-        let body = serde_json::to_string(&request_unload_request)?;
+        let body = serde_json::to_string(&adapter_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/request_unload"))
+            .double_edge(hyper::Method::POST, body, "/apply_adapter".into())
             .await?;
         match resp.status() {
             StatusCode::OK => {
-                // Get the response body bytes.
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
-
-                // Convert the body bytes to utf-8
-                // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
-
-                // Convert the body bytes to utf-8
-                // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/apply_adapter",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    pub async fn get_request_status(
+    /// Takes an adapter applied with [PantryAPI::apply_adapter] off a running LLM.
+    ///
+    /// Requires [UserPermissions::perm_load_llm].
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `llm_uuid` — UUID of the running base LLM.
+    /// * `adapter_id` — Id of the applied adapter.
+    pub async fn remove_adapter(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        request_id: Uuid,
-    ) -> Result<UserRequestStatus, PantryError> {
-        let request_unload_request = RequestStatusRequest {
+        llm_uuid: LlmUuid,
+        adapter_id: String,
+    ) -> Result<LLMStatus, PantryError> {
+        let adapter_request = AdapterRequest {
             user_id: user_id.to_string(),
             api_key,
-            request_id: request_id.to_string(),
+            llm_uuid: llm_uuid.to_string(),
+            adapter_id,
         };
-        let body = serde_json::to_string(&request_unload_request)?;
+        let body = serde_json::to_string(&adapter_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/get_request_status"))
+            .double_edge(hyper::Method::POST, body, "/remove_adapter".into())
             .await?;
         match resp.status() {
             StatusCode::OK => {
-                // Get the response body bytes.
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
-
-                // Convert the body bytes to utf-8
-                // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
-
-                // Convert the body bytes to utf-8
-                // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/remove_adapter",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    /// Gets the current status of an LLM
+    /// Returns a bare model based on filter and preference.
+    ///
+    /// Requires [UserPermissions::perm_bare_model].
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// *
-    pub async fn get_llm_status(
+    /// * `filter` — A [LLMFilter] object, for what _must_ be true of an LLM to use it.
+    /// * `preference` — A [LLMPreference] object, for how to rank and then select from the LLMs
+    pub async fn bare_model_flex(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        llm_id: Uuid,
-    ) -> Result<LLMStatus, PantryError> {
-        let request_unload_request = GetLLMStatusRequest {
+        filter: Option<LLMFilter>,
+        preference: Option<LLMPreference>,
+    ) -> Result<BareModelResponse, PantryError> {
+        let load_llm_request = BareModelFlexRequest {
             user_id: user_id.to_string(),
             api_key,
-            llm_id: llm_id.to_string(),
+            filter,
+            preference,
         };
-        let body = serde_json::to_string(&request_unload_request)?;
+        let body = serde_json::to_string(&load_llm_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/get_llm_status"))
+            .double_edge(hyper::Method::POST, body, format!("/bare_model_flex"))
             .await?;
         match resp.status() {
             StatusCode::OK => {
@@ -691,29 +3671,28 @@ impl PantryAPI {
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
 
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/bare_model_flex",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
-
-    /// Gets currently running LLMs.
-    ///
-    /// # Arguments
-    ///
-    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
-    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    pub async fn get_running_llms(
+    pub async fn get_or_download_llm(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-    ) -> Result<Vec<LLMStatus>, PantryError> {
-        let request_running_llms = GetRunningLLMRequest {
+        llm_registry_entry: LLMRegistryEntry,
+    ) -> Result<Value, PantryError> {
+        let download_llm_request = GetOrDownloadLLMRequest {
             user_id: user_id.to_string(),
             api_key,
+            llm_registry_entry,
         };
-        let body = serde_json::to_string(&request_running_llms)?;
+        let body = serde_json::to_string(&download_llm_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/get_running_llms"))
+            .double_edge(hyper::Method::POST, body, format!("/get_or_download_llm"))
             .await?;
         match resp.status() {
             StatusCode::OK => {
@@ -732,33 +3711,39 @@ impl PantryAPI {
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
 
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/get_or_download_llm",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    /// Gets currently downloaded LLMs.
+    /// Requests an LLM be deleted from disk. Must be accepted by the system owner
+    /// (currently via the UI).
     ///
-    /// In order to create a session, these must first be activated, requiring the
-    /// correct permissions. If you're the system owner, you can also activate
-    /// them manually using the UI.
+    /// Requires [UserPermissions::perm_request_download].
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    pub async fn get_available_llms(
+    /// * `llm_id` — UUID of the LLM. Find downloaded llms via [PantryAPI::get_available_llms].
+    pub async fn request_delete(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-    ) -> Result<Vec<LLMStatus>, PantryError> {
-        let request_available_llms = GetAvailableLLMRequest {
+        llm_id: LlmUuid,
+    ) -> Result<UserRequestStatus, PantryError> {
+        let request_delete_request = RequestDeleteRequest {
             user_id: user_id.to_string(),
             api_key,
+            llm_id: llm_id.to_string(),
         };
-        let body = serde_json::to_string(&request_available_llms)?;
+        let body = serde_json::to_string(&request_delete_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/get_available_llms"))
+            .double_edge(hyper::Method::POST, body, "/request_delete".to_string())
             .await?;
         match resp.status() {
             StatusCode::OK => {
@@ -768,7 +3753,7 @@ impl PantryAPI {
                 // Convert the body bytes to utf-8
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
@@ -777,41 +3762,38 @@ impl PantryAPI {
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
 
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/request_delete",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    /// Interrupts an ongoing inference session.
-    ///
-    /// Internally this uses a cancellation callback to cancel inference _after the next token_.
-    /// Depending on your system, this might take a moment, especially given that some
-    /// tokens might already be inferred but not yet transmitted.
+    /// Deletes a downloaded LLM, removing it from disk. Running LLMs get unloaded first.
     ///
-    /// You must own the session to interrupt it.
+    /// Requires [UserPermissions::perm_download_llm].
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// * `llm_id` — A UUID of an LLM. You should have gotten it from creating your session.
-    /// * `session_id` — A UUID of a session. You should have gotten it from creating your session.
-    pub async fn interrupt_session(
+    /// * `llm_id` — UUID of the LLM. Find downloaded llms via [PantryAPI::get_available_llms].
+    pub async fn delete_llm(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        llm_id: Uuid,
-        session_id: Uuid,
-    ) -> Result<LLMRunningStatus, PantryError> {
-        let interrupt_session_request = InterruptSessionRequest {
+        llm_id: LlmUuid,
+    ) -> Result<LLMStatus, PantryError> {
+        let delete_llm_request = DeleteLLMRequest {
             user_id: user_id.to_string(),
             api_key,
-            llm_uuid: llm_id.to_string(),
-            session_id: session_id.to_string(),
+            llm_id: llm_id.to_string(),
         };
-        let body = serde_json::to_string(&interrupt_session_request)?;
+        let body = serde_json::to_string(&delete_llm_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/interrupt_session"))
+            .double_edge(hyper::Method::POST, body, "/delete_llm".to_string())
             .await?;
         match resp.status() {
             StatusCode::OK => {
@@ -821,7 +3803,7 @@ impl PantryAPI {
                 // Convert the body bytes to utf-8
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
@@ -830,38 +3812,40 @@ impl PantryAPI {
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
 
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/delete_llm",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    /// Loads an LLM.
-    ///
-    /// Requires the [UserPermissions::perm_load_llm] permission.
+    /// Gets all of this user's requests that haven't been accepted or rejected yet.
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// * `filter` — A [LLMFilter] object, for what _must_ be true of an LLM to load it.
-    /// * `preference` — A [LLMPreference] object, for how to rank and then select from the LLMs
-    /// that pass the filter.
-    pub async fn load_llm_flex(
+    pub async fn get_pending_requests(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        filter: Option<LLMFilter>,
-        preference: Option<LLMPreference>,
-    ) -> Result<LLMRunningStatus, PantryError> {
-        let load_llm_request = LoadLLMFlexRequest {
+    ) -> Result<Vec<UserRequestStatus>, PantryError> {
+        let get_pending_requests_request = GetPendingRequestsRequest {
             user_id: user_id.to_string(),
             api_key,
-            filter,
-            preference,
         };
-        let body = serde_json::to_string(&load_llm_request)?;
+        let body = serde_json::to_string(&get_pending_requests_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/load_llm_flex"))
+            .retry
+            .run(|| {
+                self.double_edge(
+                    hyper::Method::POST,
+                    body.clone(),
+                    "/get_pending_requests".to_string(),
+                )
+            })
             .await?;
         match resp.status() {
             StatusCode::OK => {
@@ -871,7 +3855,7 @@ impl PantryAPI {
                 // Convert the body bytes to utf-8
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
@@ -880,34 +3864,39 @@ impl PantryAPI {
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
 
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/get_pending_requests",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    /// Loads an LLM.
+    /// Withdraws a request that hasn't been handled yet, removing it from the owner's UI.
     ///
-    /// Requires [UserPermissions::perm_load_llm].
+    /// Returns the request's final status. Fails if the request is already complete.
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// * `llm_id` — UUID or ID of an LLM. Will fail for duplicate IDs.
-    pub async fn load_llm(
+    /// * `request_id` — UUID of the request, from the [UserRequestStatus] returned when
+    ///   making it or from [PantryAPI::get_pending_requests].
+    pub async fn cancel_request(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        llm_id: String,
-    ) -> Result<LLMRunningStatus, PantryError> {
-        let load_llm_request = LoadLLMRequest {
+        request_id: RequestId,
+    ) -> Result<UserRequestStatus, PantryError> {
+        let cancel_request_request = CancelRequestRequest {
             user_id: user_id.to_string(),
             api_key,
-            llm_id: llm_id.to_string(),
+            request_id: request_id.to_string(),
         };
-        let bod = serde_json::to_string(&load_llm_request)?;
+        let body = serde_json::to_string(&cancel_request_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, bod, format!("/load_llm"))
+            .double_edge(hyper::Method::POST, body, "/cancel_request".to_string())
             .await?;
         match resp.status() {
             StatusCode::OK => {
@@ -917,7 +3906,7 @@ impl PantryAPI {
                 // Convert the body bytes to utf-8
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
@@ -926,34 +3915,82 @@ impl PantryAPI {
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
 
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/cancel_request",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    /// Unloads an LLM, conserving resources.
+    /// Subscribes to server side events, like LLMs getting loaded or requests getting
+    /// accepted. Lets reactive apps skip polling.
     ///
-    /// Requires [UserPermissions::perm_unload_llm].
+    /// The stream stays open until Pantry shuts down or the stream is dropped. You only
+    /// receive events for LLMs you can see and for your own requests.
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// * `llm_id` — UUID or model id of an LLM.
-    pub async fn unload_llm(
+    #[cfg(feature = "streaming")]
+    pub async fn subscribe_events(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        llm_id: String,
-    ) -> Result<LLMStatus, PantryError> {
-        let unload_llm_request = UnloadLLMRequest {
+    ) -> Result<ServerEventStream, PantryError> {
+        let subscribe_events_request = SubscribeEventsRequest {
             user_id: user_id.to_string(),
             api_key,
-            llm_id,
         };
-        let body = serde_json::to_string(&unload_llm_request)?;
+        let body = serde_json::to_string(&subscribe_events_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/unload_llm"))
+            .double_edge_accepting(
+                hyper::Method::POST,
+                body,
+                "/subscribe_events".into(),
+                self.stream_format.accept(),
+            )
+            .await?;
+        if resp.status() != StatusCode::OK {
+            let code = resp.status();
+            let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+            let body_str = std::str::from_utf8(&body_bytes)?;
+            return Err(PantryError::from_endpoint_response(
+                "/subscribe_events",
+                code,
+                body_str,
+            ));
+        }
+        Ok(decode_events(resp))
+    }
+
+    /// Gets the permissions this user has been granted so far.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    pub async fn get_permissions(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<UserPermissions, PantryError> {
+        let get_permissions_request = GetPermissionsRequest {
+            user_id: user_id.to_string(),
+            api_key,
+        };
+        let body = serde_json::to_string(&get_permissions_request)?;
+        let resp = self
+            .retry
+            .run(|| {
+                self.double_edge(
+                    hyper::Method::POST,
+                    body.clone(),
+                    "/get_permissions".to_string(),
+                )
+            })
             .await?;
         match resp.status() {
             StatusCode::OK => {
@@ -963,7 +4000,7 @@ impl PantryAPI {
                 // Convert the body bytes to utf-8
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
@@ -972,38 +4009,46 @@ impl PantryAPI {
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
 
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/get_permissions",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    /// Downloads an LLM.
+    /// Requests Pantry add a model file that's already on disk, without downloading
+    /// anything. Must be accepted by the system owner (currently via the UI).
     ///
-    /// Requires [UserPermissions::perm_download_llm].
+    /// Requires [UserPermissions::perm_request_download].
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// * `llm_registry_entry` — [LLMRegistryEntry] for the LLM. The only "mandatory" fields are
-    /// the [LLMRegistryEntry::connector_type] and [LLMRegistryEntry::id]. If the connector type is
-    /// [crate::interface::LLMConnectorType::LLMrs], config must include the key `model_architecture`. For more
-    /// details see the [rustformers/llm
-    /// documentation](https://docs.rs/llm/latest/llm/enum.ModelArchitecture.html)
-    pub async fn download_llm(
+    /// * `path` — Absolute path of the model file, on the machine Pantry runs on.
+    /// * `llm_registry_entry` — [LLMRegistryEntry] describing the model. The url is ignored.
+    pub async fn request_register_local(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
+        path: String,
         llm_registry_entry: LLMRegistryEntry,
-    ) -> Result<Value, PantryError> {
-        let download_llm_request = DownloadLLMRequest {
+    ) -> Result<UserRequestStatus, PantryError> {
+        let request_register_local_request = RequestRegisterLocalRequest {
             user_id: user_id.to_string(),
             api_key,
+            path,
             llm_registry_entry,
         };
-        let body = serde_json::to_string(&download_llm_request)?;
+        let body = serde_json::to_string(&request_register_local_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/download_llm"))
+            .double_edge(
+                hyper::Method::POST,
+                body,
+                "/request_register_local".to_string(),
+            )
             .await?;
         match resp.status() {
             StatusCode::OK => {
@@ -1013,7 +4058,7 @@ impl PantryAPI {
                 // Convert the body bytes to utf-8
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
@@ -1022,35 +4067,46 @@ impl PantryAPI {
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
 
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/request_register_local",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    /// Creates a session, using the best currently running LLM.
+    /// Adds a model file that's already on disk, without downloading anything. The
+    /// model is available right away.
     ///
-    /// Requires [UserPermissions::perm_session].
+    /// Requires the [UserPermissions::perm_download_llm] permission.
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// * `user_session_parameters` — A hashmap of _requested_ parameters. The returning
-    /// [LLMStatus] object will inform which ones got accepted by the LLM.
-    pub async fn create_session(
+    /// * `path` — Absolute path of the model file, on the machine Pantry runs on.
+    /// * `llm_registry_entry` — [LLMRegistryEntry] describing the model. The url is ignored.
+    pub async fn register_local_model(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        user_session_parameters: HashMap<String, Value>,
-    ) -> Result<CreateSessionResponse, PantryError> {
-        let create_session_request = CreateSessionRequest {
+        path: String,
+        llm_registry_entry: LLMRegistryEntry,
+    ) -> Result<LLMStatus, PantryError> {
+        let register_local_model_request = RegisterLocalModelRequest {
             user_id: user_id.to_string(),
             api_key,
-            user_session_parameters,
+            path,
+            llm_registry_entry,
         };
-        let body = serde_json::to_string(&create_session_request)?;
+        let body = serde_json::to_string(&register_local_model_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/create_session"))
+            .double_edge(
+                hyper::Method::POST,
+                body,
+                "/register_local_model".to_string(),
+            )
             .await?;
         match resp.status() {
             StatusCode::OK => {
@@ -1060,7 +4116,7 @@ impl PantryAPI {
                 // Convert the body bytes to utf-8
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
@@ -1069,40 +4125,36 @@ impl PantryAPI {
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
 
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/register_local_model",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    /// Creates a session, using the LLM with the given id. If the LLM doesn't exist or isn't
-    /// running, errors.
+    /// Lists all registered users and their permissions.
     ///
-    /// Requires [UserPermissions::perm_session].
+    /// Requires [UserPermissions::perm_superuser].
     ///
     /// # Arguments
     ///
-    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user] OR a machine-id. The API
-    /// will attempt to match on UUID first, otherwise treat it as a flex request matching on id.
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// * `llm_id` — A UUID for which LLM to use.
-    /// * `user_session_parameters` — A hashmap of _requested_ parameters. The returning
-    /// [LLMStatus] object will inform which ones got accepted by the LLM.
-    pub async fn create_session_id(
+    pub async fn list_users(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        llm_id: Uuid,
-        user_session_parameters: HashMap<String, Value>,
-    ) -> Result<CreateSessionResponse, PantryError> {
-        let create_session_id_request = CreateSessionIdRequest {
+    ) -> Result<Vec<UserStatus>, PantryError> {
+        let list_users_request = ListUsersRequest {
             user_id: user_id.to_string(),
             api_key,
-            llm_id: llm_id.to_string(),
-            user_session_parameters,
         };
-        let body = serde_json::to_string(&create_session_id_request)?;
+        let body = serde_json::to_string(&list_users_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/create_session_id"))
+            .retry
+            .run(|| self.double_edge(hyper::Method::POST, body.clone(), "/list_users".to_string()))
             .await?;
         match resp.status() {
             StatusCode::OK => {
@@ -1112,7 +4164,7 @@ impl PantryAPI {
                 // Convert the body bytes to utf-8
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
@@ -1121,42 +4173,39 @@ impl PantryAPI {
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
 
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/list_users",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    /// Creates a session based on `filter` and `preference`. Selects only from currently running
-    /// LLMs.
+    /// Gets a user and their permissions.
     ///
-    /// Requires [UserPermissions::perm_session].
+    /// Requires [UserPermissions::perm_superuser].
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// * `filter` — A [LLMFilter] object, for what _must_ be true of an LLM to use it.
-    /// * `preference` — A [LLMPreference] object, for how to rank and then select from the LLMs
-    /// * `user_session_parameters` — A hashmap of _requested_ parameters. The returning
-    /// [LLMStatus] object will inform which ones got accepted by the LLM.
-    pub async fn create_session_flex(
+    /// * `target_user_id` — The user to look up.
+    pub async fn get_user(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        filter: Option<LLMFilter>,
-        preference: Option<LLMPreference>,
-        user_session_parameters: HashMap<String, Value>,
-    ) -> Result<CreateSessionResponse, PantryError> {
-        let create_session_flex_request = CreateSessionFlexRequest {
+        target_user_id: UserId,
+    ) -> Result<UserStatus, PantryError> {
+        let get_user_request = GetUserRequest {
             user_id: user_id.to_string(),
             api_key,
-            filter,
-            preference,
-            user_session_parameters,
+            target_user_id: target_user_id.to_string(),
         };
-        let body = serde_json::to_string(&create_session_flex_request)?;
+        let body = serde_json::to_string(&get_user_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/create_session_flex"))
+            .retry
+            .run(|| self.double_edge(hyper::Method::POST, body.clone(), "/get_user".to_string()))
             .await?;
         match resp.status() {
             StatusCode::OK => {
@@ -1166,7 +4215,7 @@ impl PantryAPI {
                 // Convert the body bytes to utf-8
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
@@ -1175,121 +4224,94 @@ impl PantryAPI {
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
 
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/get_user",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    /// Prompts a session, triggering inference by the LLM.
-    ///
-    /// Requires [UserPermissions::perm_session].
+    /// Lists requests from all users that are still waiting for a decision.
     ///
-    /// Session must be running first, call [PantryAPI::create_session],
-    /// [PantryAPI::create_session_id], or [PantryAPI::create_session_flex] first.
+    /// Requires [UserPermissions::perm_superuser].
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// * `session_id` — A UUID representing the session. Obtained by calling
-    /// [PantryAPI::create_session] or its variants.
-    /// * `llm_uuid` — UUID of the llm. Must match the call used to make the session.
-    /// * `prompt` — Prompt for the LLM. Pantry does no preprompting, so if you want a
-    /// chatbot style response, you'll need to insert a chatbot type prompt _then_ whatever
-    /// the user requested.
-    /// * `parameters` — Things like temperature or k value. Whats available varies by LLM,
-    /// you can find out what an LLM has either in the UI or in the `user_parameters` and
-    /// `user_session_parameters` vectors of an [LLMStatus].
-    pub async fn prompt_session_stream(
+    pub async fn list_pending_requests(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        session_id: Uuid,
-        llm_uuid: String,
-        prompt: String,
-        parameters: HashMap<String, Value>,
-    ) -> Result<LLMEventStream, PantryError> {
-        let prompt_session_stream_request = PromptSessionStreamRequest {
+    ) -> Result<Vec<UserRequestStatus>, PantryError> {
+        let list_pending_requests_request = ListPendingRequestsRequest {
             user_id: user_id.to_string(),
             api_key,
-            session_id: session_id.to_string(),
-            llm_uuid: llm_uuid.to_string(),
-            prompt,
-            parameters,
         };
-        let body = serde_json::to_string(&prompt_session_stream_request)?;
-
+        let body = serde_json::to_string(&list_pending_requests_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/prompt_session_stream"))
+            .retry
+            .run(|| {
+                self.double_edge(
+                    hyper::Method::POST,
+                    body.clone(),
+                    "/list_pending_requests".to_string(),
+                )
+            })
             .await?;
-        let bod = resp.into_body();
-
-        let stream = decode_stream(TryStreamExt::into_async_read(
-            bod.into_stream()
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e)),
-        ));
-
-        let events = stream.into_stream().filter_map(|x| async move {
-            match x {
-                Ok(event) => match event {
-                    Event::Retry { retry: _ } => None,
-                    Event::Message {
-                        id: _,
-                        event: _,
-                        data,
-                    } => {
-                        let llm_event: LLMEvent = serde_json::from_str(&data).ok()?;
-                        Some(llm_event)
-                    }
-                },
-                Err(e) => {
-                    println!("Error: {:?}", e);
-                    None
-                }
+        match resp.status() {
+            StatusCode::OK => {
+                // Get the response body bytes.
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+                Ok(serde_json::from_str(body_str)?)
             }
-        });
-        let out = Box::pin(events);
-        // // println!("test2 {:?}", (out.next().into() as LLMEvent));
-        // let item_option = out.next().await; // This will give you Option<LLMEvent>
-        // match item_option {
-        //     Some(item) => println!("test2 {:?}", item),
-        //     None => println!("Stream is empty or has ended"),
-        // }
-        // let item_option = out.next().await; // This will give you Option<LLMEvent>
-        // match item_option {
-        //     Some(item) => println!("test2 {:?}", item),
-        //     None => println!("Stream is empty or has ended"),
-        // }
-
-        Ok(out)
+            code => {
+                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+
+                // Convert the body bytes to utf-8
+                // let body = String::from_slice(body_bytes.into()).unwrap();
+                let body_str = std::str::from_utf8(&body_bytes)?;
+
+                Err(PantryError::from_endpoint_response(
+                    "/list_pending_requests",
+                    code,
+                    body_str,
+                ))
+            }
+        }
     }
 
-    /// Acquire a bare model.
-    ///
-    /// In practice this means a file path to a GGML file that you can then run yourself
-    /// with whatever your preferred LLM runner is.
+    /// Accepts a pending request, as if the owner had clicked "accept" in the UI.
     ///
-    /// Requires [UserPermissions::perm_bare_model].
+    /// Requires [UserPermissions::perm_superuser]. For permission requests the user
+    /// gets the permissions immediately; downloads and loads start in the background, so
+    /// poll [PantryAPI::get_request_status] for when they're done.
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// * `llm_id` — UUID of an LLM.
-    pub async fn bare_model(
+    /// * `request_id` — The `id` of a [UserRequestStatus].
+    pub async fn approve_request(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        llm_id: String,
-    ) -> Result<BareModelResponse, PantryError> {
-        let load_llm_request = BareModelRequest {
+        request_id: RequestId,
+    ) -> Result<UserRequestStatus, PantryError> {
+        let approve_request_request = DecideRequestRequest {
             user_id: user_id.to_string(),
             api_key,
-            llm_id: llm_id.to_string(),
+            request_id: request_id.to_string(),
         };
-        let bod = serde_json::to_string(&load_llm_request)?;
+        let body = serde_json::to_string(&approve_request_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, bod, format!("/bare_model"))
+            .double_edge(hyper::Method::POST, body, "/approve_request".to_string())
             .await?;
         match resp.status() {
             StatusCode::OK => {
@@ -1299,7 +4321,7 @@ impl PantryAPI {
                 // Convert the body bytes to utf-8
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
@@ -1308,37 +4330,38 @@ impl PantryAPI {
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
 
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/approve_request",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 
-    /// Returns a bare model based on filter and preference.
+    /// Rejects a pending request.
     ///
-    /// Requires [UserPermissions::perm_bare_model].
+    /// Requires [UserPermissions::perm_superuser].
     ///
     /// # Arguments
     ///
     /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
     /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
-    /// * `filter` — A [LLMFilter] object, for what _must_ be true of an LLM to use it.
-    /// * `preference` — A [LLMPreference] object, for how to rank and then select from the LLMs
-    pub async fn bare_model_flex(
+    /// * `request_id` — The `id` of a [UserRequestStatus].
+    pub async fn deny_request(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        filter: Option<LLMFilter>,
-        preference: Option<LLMPreference>,
-    ) -> Result<BareModelResponse, PantryError> {
-        let load_llm_request = BareModelFlexRequest {
+        request_id: RequestId,
+    ) -> Result<UserRequestStatus, PantryError> {
+        let deny_request_request = DecideRequestRequest {
             user_id: user_id.to_string(),
             api_key,
-            filter,
-            preference,
+            request_id: request_id.to_string(),
         };
-        let body = serde_json::to_string(&load_llm_request)?;
+        let body = serde_json::to_string(&deny_request_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/bare_model_flex"))
+            .double_edge(hyper::Method::POST, body, "/deny_request".to_string())
             .await?;
         match resp.status() {
             StatusCode::OK => {
@@ -1348,7 +4371,7 @@ impl PantryAPI {
                 // Convert the body bytes to utf-8
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                Ok(serde_json::from_str(body_str)?)
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
@@ -1357,34 +4380,45 @@ impl PantryAPI {
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
 
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/deny_request",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
-    pub async fn get_or_download_llm(
+
+    /// Revokes a user's API key. Every call made with it fails from then on, and the
+    /// user has to register again.
+    ///
+    /// Requires [UserPermissions::perm_superuser].
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` — A UUID, obtained from [PantryAPI::register_user].
+    /// * `api_key` — An API key, obtained from [PantryAPI::register_user]
+    /// * `target_user_id` — The user whose key to revoke.
+    pub async fn revoke_api_key(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
         api_key: String,
-        llm_registry_entry: LLMRegistryEntry,
-    ) -> Result<Value, PantryError> {
-        let download_llm_request = GetOrDownloadLLMRequest {
+        target_user_id: UserId,
+    ) -> Result<(), PantryError> {
+        let revoke_api_key_request = RevokeApiKeyRequest {
             user_id: user_id.to_string(),
             api_key,
-            llm_registry_entry,
+            target_user_id: target_user_id.to_string(),
         };
-        let body = serde_json::to_string(&download_llm_request)?;
+        let body = serde_json::to_string(&revoke_api_key_request)?;
         let resp = self
-            .double_edge(hyper::Method::POST, body, format!("/get_or_download_llm"))
+            .double_edge(hyper::Method::POST, body, "/revoke_api_key".to_string())
             .await?;
         match resp.status() {
             StatusCode::OK => {
-                // Get the response body bytes.
-                let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
-
-                // Convert the body bytes to utf-8
-                // let body = String::from_slice(body_bytes.into()).unwrap();
-                let body_str = std::str::from_utf8(&body_bytes)?;
-                Ok(serde_json::from_str(&body_str)?)
+                // Nothing useful in the body.
+                hyper::body::to_bytes(resp.into_body()).await?;
+                Ok(())
             }
             code => {
                 let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
@@ -1393,12 +4427,15 @@ impl PantryAPI {
                 // let body = String::from_slice(body_bytes.into()).unwrap();
                 let body_str = std::str::from_utf8(&body_bytes)?;
 
-                Err(PantryError::ApiError(code, body_str.into()))
+                Err(PantryError::from_endpoint_response(
+                    "/revoke_api_key",
+                    code,
+                    body_str,
+                ))
             }
         }
     }
 }
-pub type LLMEventStream = Pin<Box<dyn Stream<Item = LLMEvent> + Send>>;
 
 // while let Some(item) = stream.next().await {
 //     match item {
@@ -0,0 +1,82 @@
+//! Extra credentials for reaching a remote Pantry.
+//!
+//! A Pantry listening on `0.0.0.0:9404` takes calls from anyone who can reach it, with
+//! only the user's API key to tell them apart. Putting it behind a reverse proxy or
+//! gateway that checks a token of its own keeps everyone else out; a [RemoteAuth] is
+//! what gets the client past that check:
+//!
+//! ```no_run
+//! # use pantry_rs::{PantryClient, RemoteAuth, UserId};
+//! # fn example(token: String, user_id: UserId, api_key: String) {
+//! let pantry = PantryClient::builder()
+//!     .base_url("https://pantry.example.com")
+//!     .remote_auth(RemoteAuth::bearer(token))
+//!     .login(user_id, api_key);
+//! # }
+//! ```
+//!
+//! It's only sent to the `base_url`, never over the local unix socket, and can be set
+//! per host in the client config, see [crate::config].
+use crate::logging::REDACTED;
+use std::fmt;
+
+/// Header a pre-shared secret is sent in, unless told otherwise.
+pub const DEFAULT_SECRET_HEADER: &str = "X-Pantry-Secret";
+
+/// Headers sent with every call to a remote Pantry, on top of the user's API key. See
+/// the [module docs](self).
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct RemoteAuth {
+    /// Sent as `Authorization: Bearer <token>`.
+    pub bearer_token: Option<String>,
+    /// Any other headers, e.g. a pre-shared secret.
+    pub headers: Vec<(String, String)>,
+}
+
+impl RemoteAuth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Authenticates with `Authorization: Bearer <token>`.
+    pub fn bearer(token: impl Into<String>) -> Self {
+        RemoteAuth {
+            bearer_token: Some(token.into()),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Authenticates with a pre-shared `secret`, sent in [DEFAULT_SECRET_HEADER].
+    pub fn secret(secret: impl Into<String>) -> Self {
+        Self::new().header(DEFAULT_SECRET_HEADER, secret)
+    }
+
+    /// Also sends header `name`. Can be repeated.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Every header to send, `Authorization` included.
+    pub fn to_headers(&self) -> Vec<(String, String)> {
+        let bearer = self
+            .bearer_token
+            .iter()
+            .map(|token| ("Authorization".to_string(), format!("Bearer {}", token)));
+        bearer.chain(self.headers.iter().cloned()).collect()
+    }
+}
+
+// Keeps tokens out of logs and panics.
+impl fmt::Debug for RemoteAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let headers: Vec<&str> = self.headers.iter().map(|(name, _)| name.as_str()).collect();
+        f.debug_struct("RemoteAuth")
+            .field(
+                "bearer_token",
+                &self.bearer_token.as_ref().map(|_| REDACTED),
+            )
+            .field("headers", &headers)
+            .finish()
+    }
+}
@@ -0,0 +1,1266 @@
+//! The operations [crate::PantryClient] needs from Pantry, as a trait.
+//!
+//! [PantryAPI] is the implementation that talks to a real daemon. Implement
+//! [PantryBackend] yourself to unit-test code built on [crate::PantryClient] without
+//! one running:
+//!
+//! ```no_run
+//! # use async_trait::async_trait;
+//! # use pantry_rs::interface::LLMStatus;
+//! # use pantry_rs::{PantryBackend, PantryClient, PantryError, RetryPolicy, UserId};
+//! # use std::sync::Arc;
+//! # use std::time::Duration;
+//! # async fn example(user_id: UserId, api_key: String) -> Result<(), Box<dyn std::error::Error>> {
+//! #[derive(Clone, Debug)]
+//! struct MockBackend;
+//!
+//! #[async_trait]
+//! impl PantryBackend for MockBackend {
+//!     fn with_timeout(&self, _timeout: Option<Duration>) -> Arc<dyn PantryBackend> {
+//!         Arc::new(self.clone())
+//!     }
+//!
+//!     fn with_retry(&self, _retry: RetryPolicy) -> Arc<dyn PantryBackend> {
+//!         Arc::new(self.clone())
+//!     }
+//!
+//!     async fn get_running_llms(
+//!         &self,
+//!         _user_id: UserId,
+//!         _api_key: String,
+//!     ) -> Result<Vec<LLMStatus>, PantryError> {
+//!         Ok(vec![])
+//!     }
+//! }
+//!
+//! let pantry = PantryClient::with_backend(Arc::new(MockBackend), user_id, api_key);
+//! assert!(pantry.get_running_llms().await?.is_empty());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Every call has the same arguments as the [PantryAPI] method of the same name, and
+//! fails with [PantryError::Unsupported] unless implemented.
+use crate::api::{
+    BareModelLeaseResponse, BareModelResponse, CreateSessionResponse, LLMFilter, LLMPreference,
+    PantryAPI, Transport,
+};
+use crate::error::PantryError;
+#[cfg(feature = "streaming")]
+use crate::event_bus::ClientEvent;
+use crate::ids::{LeaseId, LlmUuid, RequestId, SessionId, UserId};
+use crate::interface::{
+    AdapterEntry, AdapterStatus, ExportMode, LLMConnectorType, LLMHistoryItem, LLMRegistryEntry,
+    LLMRunningStatus, LLMSessionStatus, LLMStatus, ServerInfo, StorageInfo, SystemStatus, UserInfo,
+    UserPermissions, UserRequestStatus, UserStatus,
+};
+#[cfg(feature = "streaming")]
+use crate::lifecycle::Lifecycle;
+use crate::metrics::MetricsSink;
+#[cfg(feature = "streaming")]
+use crate::middleware::PromptMiddleware;
+#[cfg(feature = "streaming")]
+use crate::multimodal::{self, AttachmentTransport, PromptInput};
+use crate::params::LoadOptions;
+use crate::rate_limit::RateLimiter;
+use crate::retry::RetryPolicy;
+#[cfg(feature = "streaming")]
+use crate::stream::{LLMEventStream, ServerEventStream};
+#[cfg(feature = "templates")]
+use crate::templates::PromptTemplates;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "streaming")]
+use tokio::sync::broadcast;
+
+/// Everything [crate::PantryClient], [crate::LLMSession] and [crate::AdminClient] call on
+/// Pantry. See the [module docs](self).
+#[async_trait]
+pub trait PantryBackend: Send + Sync + Debug {
+    /// A copy that gives up on calls after `timeout`, `None` to wait forever.
+    fn with_timeout(&self, timeout: Option<Duration>) -> Arc<dyn PantryBackend>;
+
+    /// A copy that retries read-only calls according to `retry`.
+    fn with_retry(&self, retry: RetryPolicy) -> Arc<dyn PantryBackend>;
+
+    /// Where the backend reports metrics, if anywhere.
+    fn metrics(&self) -> Option<Arc<dyn MetricsSink>> {
+        None
+    }
+
+    /// What throttles the backend's calls, if anything.
+    fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        None
+    }
+
+    /// How prompts the LLM fails on get sent again, see
+    /// [crate::PantryClientBuilder::prompt_retry].
+    fn prompt_retry(&self) -> RetryPolicy {
+        RetryPolicy::none()
+    }
+
+    /// What prompts and their events go through, see [crate::middleware].
+    #[cfg(feature = "streaming")]
+    fn middleware(&self) -> Vec<Arc<dyn PromptMiddleware>> {
+        Vec::new()
+    }
+
+    /// Named prompt templates sessions can use, if any.
+    #[cfg(feature = "templates")]
+    fn templates(&self) -> Option<Arc<PromptTemplates>> {
+        None
+    }
+
+    /// Where the client publishes its events, if anywhere, see [crate::event_bus].
+    #[cfg(feature = "streaming")]
+    fn event_bus(&self) -> Option<&broadcast::Sender<ClientEvent>> {
+        None
+    }
+
+    /// What keeps track of prompts in flight, for [crate::PantryClient::shutdown].
+    #[cfg(feature = "streaming")]
+    fn lifecycle(&self) -> Option<&Arc<Lifecycle>> {
+        None
+    }
+
+    /// Url of the Pantry instance, `None` for the local one.
+    fn base_url(&self) -> Option<String> {
+        None
+    }
+
+    /// Whether Pantry answers over `transport`, see [PantryAPI::probe].
+    async fn probe(&self, _transport: Transport) -> Result<(), PantryError> {
+        unsupported("probe")
+    }
+
+    async fn server_info(&self) -> Result<ServerInfo, PantryError> {
+        unsupported("server_info")
+    }
+
+    async fn register_user(&self, _user_name: String) -> Result<UserInfo, PantryError> {
+        unsupported("register_user")
+    }
+
+    async fn request_permissions(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _requested_permissions: UserPermissions,
+    ) -> Result<UserRequestStatus, PantryError> {
+        unsupported("request_permissions")
+    }
+
+    async fn request_permissions_with(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _requested_permissions: UserPermissions,
+        _duration: Duration,
+    ) -> Result<UserRequestStatus, PantryError> {
+        unsupported("request_permissions_with")
+    }
+
+    async fn request_download(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_registry_entry: LLMRegistryEntry,
+        _target_dir: Option<String>,
+    ) -> Result<UserRequestStatus, PantryError> {
+        unsupported("request_download")
+    }
+
+    async fn request_load_flex(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _filter: Option<LLMFilter>,
+        _preference: Option<LLMPreference>,
+    ) -> Result<UserRequestStatus, PantryError> {
+        unsupported("request_load_flex")
+    }
+
+    async fn request_load(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_id: LlmUuid,
+    ) -> Result<UserRequestStatus, PantryError> {
+        unsupported("request_load")
+    }
+
+    async fn request_unload(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_id: LlmUuid,
+    ) -> Result<UserRequestStatus, PantryError> {
+        unsupported("request_unload")
+    }
+
+    async fn get_request_status(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _request_id: RequestId,
+    ) -> Result<UserRequestStatus, PantryError> {
+        unsupported("get_request_status")
+    }
+
+    async fn get_llm_status(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_id: LlmUuid,
+    ) -> Result<LLMStatus, PantryError> {
+        unsupported("get_llm_status")
+    }
+
+    async fn get_user_info(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+    ) -> Result<UserInfo, PantryError> {
+        unsupported("get_user_info")
+    }
+
+    async fn get_running_llms(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+    ) -> Result<Vec<LLMStatus>, PantryError> {
+        unsupported("get_running_llms")
+    }
+
+    async fn get_system_status(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+    ) -> Result<SystemStatus, PantryError> {
+        unsupported("get_system_status")
+    }
+
+    async fn get_storage_info(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+    ) -> Result<StorageInfo, PantryError> {
+        unsupported("get_storage_info")
+    }
+
+    async fn get_available_llms(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+    ) -> Result<Vec<LLMStatus>, PantryError> {
+        unsupported("get_available_llms")
+    }
+
+    async fn get_sessions(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+    ) -> Result<Vec<LLMSessionStatus>, PantryError> {
+        unsupported("get_sessions")
+    }
+
+    async fn load_session_id(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _session_id: SessionId,
+    ) -> Result<CreateSessionResponse, PantryError> {
+        unsupported("load_session_id")
+    }
+
+    async fn fork_session(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _session_id: SessionId,
+    ) -> Result<CreateSessionResponse, PantryError> {
+        unsupported("fork_session")
+    }
+
+    async fn get_session_history(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _session_id: SessionId,
+    ) -> Result<Vec<LLMHistoryItem>, PantryError> {
+        unsupported("get_session_history")
+    }
+
+    async fn delete_session(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _session_id: SessionId,
+    ) -> Result<(), PantryError> {
+        unsupported("delete_session")
+    }
+
+    async fn interrupt_session(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_id: LlmUuid,
+        _session_id: SessionId,
+    ) -> Result<LLMRunningStatus, PantryError> {
+        unsupported("interrupt_session")
+    }
+
+    async fn load_llm_flex(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _filter: Option<LLMFilter>,
+        _preference: Option<LLMPreference>,
+        _options: LoadOptions,
+    ) -> Result<LLMRunningStatus, PantryError> {
+        unsupported("load_llm_flex")
+    }
+
+    async fn load_llm(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_id: String,
+        _options: LoadOptions,
+    ) -> Result<LLMRunningStatus, PantryError> {
+        unsupported("load_llm")
+    }
+
+    async fn unload_llm(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_id: String,
+    ) -> Result<LLMStatus, PantryError> {
+        unsupported("unload_llm")
+    }
+
+    async fn download_llm(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_registry_entry: LLMRegistryEntry,
+        _target_dir: Option<String>,
+    ) -> Result<Value, PantryError> {
+        unsupported("download_llm")
+    }
+
+    async fn create_session(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _user_session_parameters: HashMap<String, Value>,
+    ) -> Result<CreateSessionResponse, PantryError> {
+        unsupported("create_session")
+    }
+
+    async fn create_session_id(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_id: LlmUuid,
+        _user_session_parameters: HashMap<String, Value>,
+    ) -> Result<CreateSessionResponse, PantryError> {
+        unsupported("create_session_id")
+    }
+
+    async fn create_session_flex(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _filter: Option<LLMFilter>,
+        _preference: Option<LLMPreference>,
+        _user_session_parameters: HashMap<String, Value>,
+    ) -> Result<CreateSessionResponse, PantryError> {
+        unsupported("create_session_flex")
+    }
+
+    #[cfg(feature = "streaming")]
+    async fn prompt_session_stream(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _session_id: SessionId,
+        _llm_uuid: LlmUuid,
+        _prompt: String,
+        _parameters: HashMap<String, Value>,
+    ) -> Result<LLMEventStream, PantryError> {
+        unsupported("prompt_session_stream")
+    }
+
+    /// Prompts without images go to [PantryBackend::prompt_session_stream] unless
+    /// implemented.
+    #[cfg(feature = "streaming")]
+    async fn prompt_session_stream_multimodal(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+        llm_uuid: LlmUuid,
+        inputs: Vec<PromptInput>,
+        parameters: HashMap<String, Value>,
+    ) -> Result<LLMEventStream, PantryError> {
+        let prompt = multimodal::split_inputs(inputs, AttachmentTransport::Base64);
+        if !prompt.attachments.is_empty() {
+            return unsupported("prompt_session_stream_multimodal");
+        }
+        self.prompt_session_stream(
+            user_id,
+            api_key,
+            session_id,
+            llm_uuid,
+            prompt.text,
+            parameters,
+        )
+        .await
+    }
+
+    #[cfg(feature = "streaming")]
+    #[allow(clippy::too_many_arguments)]
+    async fn transcribe_stream(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _session_id: SessionId,
+        _llm_uuid: LlmUuid,
+        _audio: Vec<u8>,
+        _mime: String,
+        _parameters: HashMap<String, Value>,
+    ) -> Result<LLMEventStream, PantryError> {
+        unsupported("transcribe_stream")
+    }
+
+    async fn bare_model(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_id: String,
+    ) -> Result<BareModelResponse, PantryError> {
+        unsupported("bare_model")
+    }
+
+    async fn export_bare_model(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_id: String,
+        _destination: String,
+        _mode: ExportMode,
+    ) -> Result<BareModelResponse, PantryError> {
+        unsupported("export_bare_model")
+    }
+
+    async fn bare_model_lease(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_id: String,
+        _ttl: Duration,
+    ) -> Result<BareModelLeaseResponse, PantryError> {
+        unsupported("bare_model_lease")
+    }
+
+    async fn release_bare_model(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _lease_id: LeaseId,
+    ) -> Result<(), PantryError> {
+        unsupported("release_bare_model")
+    }
+
+    async fn set_connector_secret(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _connector: LLMConnectorType,
+        _secret: String,
+    ) -> Result<(), PantryError> {
+        unsupported("set_connector_secret")
+    }
+
+    async fn list_adapters(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_id: String,
+    ) -> Result<Vec<AdapterStatus>, PantryError> {
+        unsupported("list_adapters")
+    }
+
+    async fn download_adapter(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_id: String,
+        _adapter: AdapterEntry,
+    ) -> Result<AdapterStatus, PantryError> {
+        unsupported("download_adapter")
+    }
+
+    async fn apply_adapter(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_uuid: LlmUuid,
+        _adapter_id: String,
+    ) -> Result<LLMStatus, PantryError> {
+        unsupported("apply_adapter")
+    }
+
+    async fn remove_adapter(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_uuid: LlmUuid,
+        _adapter_id: String,
+    ) -> Result<LLMStatus, PantryError> {
+        unsupported("remove_adapter")
+    }
+
+    async fn bare_model_flex(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _filter: Option<LLMFilter>,
+        _preference: Option<LLMPreference>,
+    ) -> Result<BareModelResponse, PantryError> {
+        unsupported("bare_model_flex")
+    }
+
+    async fn get_or_download_llm(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_registry_entry: LLMRegistryEntry,
+    ) -> Result<Value, PantryError> {
+        unsupported("get_or_download_llm")
+    }
+
+    async fn request_delete(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_id: LlmUuid,
+    ) -> Result<UserRequestStatus, PantryError> {
+        unsupported("request_delete")
+    }
+
+    async fn delete_llm(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _llm_id: LlmUuid,
+    ) -> Result<LLMStatus, PantryError> {
+        unsupported("delete_llm")
+    }
+
+    async fn get_pending_requests(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+    ) -> Result<Vec<UserRequestStatus>, PantryError> {
+        unsupported("get_pending_requests")
+    }
+
+    async fn cancel_request(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _request_id: RequestId,
+    ) -> Result<UserRequestStatus, PantryError> {
+        unsupported("cancel_request")
+    }
+
+    #[cfg(feature = "streaming")]
+    async fn subscribe_events(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+    ) -> Result<ServerEventStream, PantryError> {
+        unsupported("subscribe_events")
+    }
+
+    async fn get_permissions(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+    ) -> Result<UserPermissions, PantryError> {
+        unsupported("get_permissions")
+    }
+
+    async fn request_register_local(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _path: String,
+        _llm_registry_entry: LLMRegistryEntry,
+    ) -> Result<UserRequestStatus, PantryError> {
+        unsupported("request_register_local")
+    }
+
+    async fn register_local_model(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _path: String,
+        _llm_registry_entry: LLMRegistryEntry,
+    ) -> Result<LLMStatus, PantryError> {
+        unsupported("register_local_model")
+    }
+
+    async fn list_users(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+    ) -> Result<Vec<UserStatus>, PantryError> {
+        unsupported("list_users")
+    }
+
+    async fn get_user(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _target_user_id: UserId,
+    ) -> Result<UserStatus, PantryError> {
+        unsupported("get_user")
+    }
+
+    async fn list_pending_requests(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+    ) -> Result<Vec<UserRequestStatus>, PantryError> {
+        unsupported("list_pending_requests")
+    }
+
+    async fn approve_request(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _request_id: RequestId,
+    ) -> Result<UserRequestStatus, PantryError> {
+        unsupported("approve_request")
+    }
+
+    async fn deny_request(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _request_id: RequestId,
+    ) -> Result<UserRequestStatus, PantryError> {
+        unsupported("deny_request")
+    }
+
+    async fn revoke_api_key(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+        _target_user_id: UserId,
+    ) -> Result<(), PantryError> {
+        unsupported("revoke_api_key")
+    }
+}
+
+#[async_trait]
+impl PantryBackend for PantryAPI {
+    fn with_timeout(&self, timeout: Option<Duration>) -> Arc<dyn PantryBackend> {
+        Arc::new(PantryAPI::with_timeout(self, timeout))
+    }
+
+    fn with_retry(&self, retry: RetryPolicy) -> Arc<dyn PantryBackend> {
+        Arc::new(PantryAPI::with_retry(self, retry))
+    }
+
+    fn metrics(&self) -> Option<Arc<dyn MetricsSink>> {
+        self.metrics.clone()
+    }
+
+    fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        self.rate_limiter.clone()
+    }
+
+    fn prompt_retry(&self) -> RetryPolicy {
+        self.prompt_retry.clone()
+    }
+
+    #[cfg(feature = "streaming")]
+    fn middleware(&self) -> Vec<Arc<dyn PromptMiddleware>> {
+        self.middleware.clone()
+    }
+
+    #[cfg(feature = "templates")]
+    fn templates(&self) -> Option<Arc<PromptTemplates>> {
+        self.templates.clone()
+    }
+
+    #[cfg(feature = "streaming")]
+    fn event_bus(&self) -> Option<&broadcast::Sender<ClientEvent>> {
+        Some(&self.event_bus)
+    }
+
+    #[cfg(feature = "streaming")]
+    fn lifecycle(&self) -> Option<&Arc<Lifecycle>> {
+        Some(&self.lifecycle)
+    }
+
+    fn base_url(&self) -> Option<String> {
+        self.base_url.clone()
+    }
+
+    async fn probe(&self, transport: Transport) -> Result<(), PantryError> {
+        PantryAPI::probe(self, transport).await
+    }
+
+    async fn server_info(&self) -> Result<ServerInfo, PantryError> {
+        PantryAPI::server_info(self).await
+    }
+
+    async fn register_user(&self, user_name: String) -> Result<UserInfo, PantryError> {
+        PantryAPI::register_user(self, user_name).await
+    }
+
+    async fn request_permissions(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        requested_permissions: UserPermissions,
+    ) -> Result<UserRequestStatus, PantryError> {
+        PantryAPI::request_permissions(self, user_id, api_key, requested_permissions).await
+    }
+
+    async fn request_permissions_with(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        requested_permissions: UserPermissions,
+        duration: Duration,
+    ) -> Result<UserRequestStatus, PantryError> {
+        PantryAPI::request_permissions_with(self, user_id, api_key, requested_permissions, duration)
+            .await
+    }
+
+    async fn request_download(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_registry_entry: LLMRegistryEntry,
+        target_dir: Option<String>,
+    ) -> Result<UserRequestStatus, PantryError> {
+        PantryAPI::request_download(self, user_id, api_key, llm_registry_entry, target_dir).await
+    }
+
+    async fn request_load_flex(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        filter: Option<LLMFilter>,
+        preference: Option<LLMPreference>,
+    ) -> Result<UserRequestStatus, PantryError> {
+        PantryAPI::request_load_flex(self, user_id, api_key, filter, preference).await
+    }
+
+    async fn request_load(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: LlmUuid,
+    ) -> Result<UserRequestStatus, PantryError> {
+        PantryAPI::request_load(self, user_id, api_key, llm_id).await
+    }
+
+    async fn request_unload(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: LlmUuid,
+    ) -> Result<UserRequestStatus, PantryError> {
+        PantryAPI::request_unload(self, user_id, api_key, llm_id).await
+    }
+
+    async fn get_request_status(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        request_id: RequestId,
+    ) -> Result<UserRequestStatus, PantryError> {
+        PantryAPI::get_request_status(self, user_id, api_key, request_id).await
+    }
+
+    async fn get_llm_status(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: LlmUuid,
+    ) -> Result<LLMStatus, PantryError> {
+        PantryAPI::get_llm_status(self, user_id, api_key, llm_id).await
+    }
+
+    async fn get_user_info(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<UserInfo, PantryError> {
+        PantryAPI::get_user_info(self, user_id, api_key).await
+    }
+
+    async fn get_running_llms(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<Vec<LLMStatus>, PantryError> {
+        PantryAPI::get_running_llms(self, user_id, api_key).await
+    }
+
+    async fn get_system_status(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<SystemStatus, PantryError> {
+        PantryAPI::get_system_status(self, user_id, api_key).await
+    }
+
+    async fn get_storage_info(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<StorageInfo, PantryError> {
+        PantryAPI::get_storage_info(self, user_id, api_key).await
+    }
+
+    async fn get_available_llms(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<Vec<LLMStatus>, PantryError> {
+        PantryAPI::get_available_llms(self, user_id, api_key).await
+    }
+
+    async fn get_sessions(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<Vec<LLMSessionStatus>, PantryError> {
+        PantryAPI::get_sessions(self, user_id, api_key).await
+    }
+
+    async fn load_session_id(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+    ) -> Result<CreateSessionResponse, PantryError> {
+        PantryAPI::load_session_id(self, user_id, api_key, session_id).await
+    }
+
+    async fn fork_session(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+    ) -> Result<CreateSessionResponse, PantryError> {
+        PantryAPI::fork_session(self, user_id, api_key, session_id).await
+    }
+
+    async fn get_session_history(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+    ) -> Result<Vec<LLMHistoryItem>, PantryError> {
+        PantryAPI::get_session_history(self, user_id, api_key, session_id).await
+    }
+
+    async fn delete_session(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+    ) -> Result<(), PantryError> {
+        PantryAPI::delete_session(self, user_id, api_key, session_id).await
+    }
+
+    async fn interrupt_session(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: LlmUuid,
+        session_id: SessionId,
+    ) -> Result<LLMRunningStatus, PantryError> {
+        PantryAPI::interrupt_session(self, user_id, api_key, llm_id, session_id).await
+    }
+
+    async fn load_llm_flex(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        filter: Option<LLMFilter>,
+        preference: Option<LLMPreference>,
+        options: LoadOptions,
+    ) -> Result<LLMRunningStatus, PantryError> {
+        PantryAPI::load_llm_flex(self, user_id, api_key, filter, preference, options).await
+    }
+
+    async fn load_llm(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: String,
+        options: LoadOptions,
+    ) -> Result<LLMRunningStatus, PantryError> {
+        PantryAPI::load_llm(self, user_id, api_key, llm_id, options).await
+    }
+
+    async fn unload_llm(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: String,
+    ) -> Result<LLMStatus, PantryError> {
+        PantryAPI::unload_llm(self, user_id, api_key, llm_id).await
+    }
+
+    async fn download_llm(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_registry_entry: LLMRegistryEntry,
+        target_dir: Option<String>,
+    ) -> Result<Value, PantryError> {
+        PantryAPI::download_llm(self, user_id, api_key, llm_registry_entry, target_dir).await
+    }
+
+    async fn create_session(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        user_session_parameters: HashMap<String, Value>,
+    ) -> Result<CreateSessionResponse, PantryError> {
+        PantryAPI::create_session(self, user_id, api_key, user_session_parameters).await
+    }
+
+    async fn create_session_id(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: LlmUuid,
+        user_session_parameters: HashMap<String, Value>,
+    ) -> Result<CreateSessionResponse, PantryError> {
+        PantryAPI::create_session_id(self, user_id, api_key, llm_id, user_session_parameters).await
+    }
+
+    async fn create_session_flex(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        filter: Option<LLMFilter>,
+        preference: Option<LLMPreference>,
+        user_session_parameters: HashMap<String, Value>,
+    ) -> Result<CreateSessionResponse, PantryError> {
+        PantryAPI::create_session_flex(
+            self,
+            user_id,
+            api_key,
+            filter,
+            preference,
+            user_session_parameters,
+        )
+        .await
+    }
+
+    #[cfg(feature = "streaming")]
+    async fn prompt_session_stream(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+        llm_uuid: LlmUuid,
+        prompt: String,
+        parameters: HashMap<String, Value>,
+    ) -> Result<LLMEventStream, PantryError> {
+        PantryAPI::prompt_session_stream(
+            self, user_id, api_key, session_id, llm_uuid, prompt, parameters,
+        )
+        .await
+    }
+
+    #[cfg(feature = "streaming")]
+    async fn prompt_session_stream_multimodal(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+        llm_uuid: LlmUuid,
+        inputs: Vec<PromptInput>,
+        parameters: HashMap<String, Value>,
+    ) -> Result<LLMEventStream, PantryError> {
+        PantryAPI::prompt_session_stream_multimodal(
+            self, user_id, api_key, session_id, llm_uuid, inputs, parameters,
+        )
+        .await
+    }
+
+    #[cfg(feature = "streaming")]
+    async fn transcribe_stream(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+        llm_uuid: LlmUuid,
+        audio: Vec<u8>,
+        mime: String,
+        parameters: HashMap<String, Value>,
+    ) -> Result<LLMEventStream, PantryError> {
+        PantryAPI::transcribe_stream(
+            self, user_id, api_key, session_id, llm_uuid, audio, mime, parameters,
+        )
+        .await
+    }
+
+    async fn bare_model(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: String,
+    ) -> Result<BareModelResponse, PantryError> {
+        PantryAPI::bare_model(self, user_id, api_key, llm_id).await
+    }
+
+    async fn export_bare_model(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: String,
+        destination: String,
+        mode: ExportMode,
+    ) -> Result<BareModelResponse, PantryError> {
+        PantryAPI::export_bare_model(self, user_id, api_key, llm_id, destination, mode).await
+    }
+
+    async fn bare_model_lease(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: String,
+        ttl: Duration,
+    ) -> Result<BareModelLeaseResponse, PantryError> {
+        PantryAPI::bare_model_lease(self, user_id, api_key, llm_id, ttl).await
+    }
+
+    async fn release_bare_model(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        lease_id: LeaseId,
+    ) -> Result<(), PantryError> {
+        PantryAPI::release_bare_model(self, user_id, api_key, lease_id).await
+    }
+
+    async fn set_connector_secret(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        connector: LLMConnectorType,
+        secret: String,
+    ) -> Result<(), PantryError> {
+        PantryAPI::set_connector_secret(self, user_id, api_key, connector, secret).await
+    }
+
+    async fn list_adapters(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: String,
+    ) -> Result<Vec<AdapterStatus>, PantryError> {
+        PantryAPI::list_adapters(self, user_id, api_key, llm_id).await
+    }
+
+    async fn download_adapter(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: String,
+        adapter: AdapterEntry,
+    ) -> Result<AdapterStatus, PantryError> {
+        PantryAPI::download_adapter(self, user_id, api_key, llm_id, adapter).await
+    }
+
+    async fn apply_adapter(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_uuid: LlmUuid,
+        adapter_id: String,
+    ) -> Result<LLMStatus, PantryError> {
+        PantryAPI::apply_adapter(self, user_id, api_key, llm_uuid, adapter_id).await
+    }
+
+    async fn remove_adapter(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_uuid: LlmUuid,
+        adapter_id: String,
+    ) -> Result<LLMStatus, PantryError> {
+        PantryAPI::remove_adapter(self, user_id, api_key, llm_uuid, adapter_id).await
+    }
+
+    async fn bare_model_flex(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        filter: Option<LLMFilter>,
+        preference: Option<LLMPreference>,
+    ) -> Result<BareModelResponse, PantryError> {
+        PantryAPI::bare_model_flex(self, user_id, api_key, filter, preference).await
+    }
+
+    async fn get_or_download_llm(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_registry_entry: LLMRegistryEntry,
+    ) -> Result<Value, PantryError> {
+        PantryAPI::get_or_download_llm(self, user_id, api_key, llm_registry_entry).await
+    }
+
+    async fn request_delete(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: LlmUuid,
+    ) -> Result<UserRequestStatus, PantryError> {
+        PantryAPI::request_delete(self, user_id, api_key, llm_id).await
+    }
+
+    async fn delete_llm(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        llm_id: LlmUuid,
+    ) -> Result<LLMStatus, PantryError> {
+        PantryAPI::delete_llm(self, user_id, api_key, llm_id).await
+    }
+
+    async fn get_pending_requests(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<Vec<UserRequestStatus>, PantryError> {
+        PantryAPI::get_pending_requests(self, user_id, api_key).await
+    }
+
+    async fn cancel_request(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        request_id: RequestId,
+    ) -> Result<UserRequestStatus, PantryError> {
+        PantryAPI::cancel_request(self, user_id, api_key, request_id).await
+    }
+
+    #[cfg(feature = "streaming")]
+    async fn subscribe_events(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<ServerEventStream, PantryError> {
+        PantryAPI::subscribe_events(self, user_id, api_key).await
+    }
+
+    async fn get_permissions(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<UserPermissions, PantryError> {
+        PantryAPI::get_permissions(self, user_id, api_key).await
+    }
+
+    async fn request_register_local(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        path: String,
+        llm_registry_entry: LLMRegistryEntry,
+    ) -> Result<UserRequestStatus, PantryError> {
+        PantryAPI::request_register_local(self, user_id, api_key, path, llm_registry_entry).await
+    }
+
+    async fn register_local_model(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        path: String,
+        llm_registry_entry: LLMRegistryEntry,
+    ) -> Result<LLMStatus, PantryError> {
+        PantryAPI::register_local_model(self, user_id, api_key, path, llm_registry_entry).await
+    }
+
+    async fn list_users(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<Vec<UserStatus>, PantryError> {
+        PantryAPI::list_users(self, user_id, api_key).await
+    }
+
+    async fn get_user(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        target_user_id: UserId,
+    ) -> Result<UserStatus, PantryError> {
+        PantryAPI::get_user(self, user_id, api_key, target_user_id).await
+    }
+
+    async fn list_pending_requests(
+        &self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<Vec<UserRequestStatus>, PantryError> {
+        PantryAPI::list_pending_requests(self, user_id, api_key).await
+    }
+
+    async fn approve_request(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        request_id: RequestId,
+    ) -> Result<UserRequestStatus, PantryError> {
+        PantryAPI::approve_request(self, user_id, api_key, request_id).await
+    }
+
+    async fn deny_request(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        request_id: RequestId,
+    ) -> Result<UserRequestStatus, PantryError> {
+        PantryAPI::deny_request(self, user_id, api_key, request_id).await
+    }
+
+    async fn revoke_api_key(
+        &self,
+        user_id: UserId,
+        api_key: String,
+        target_user_id: UserId,
+    ) -> Result<(), PantryError> {
+        PantryAPI::revoke_api_key(self, user_id, api_key, target_user_id).await
+    }
+}
+
+fn unsupported<T>(call: &str) -> Result<T, PantryError> {
+    Err(PantryError::Unsupported(format!(
+        "{} isn't implemented by this backend",
+        call
+    )))
+}
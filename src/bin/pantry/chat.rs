@@ -0,0 +1,172 @@
+//! `pantry chat`, an interactive chat on a [ChatSession].
+//!
+//! Every finished exchange is saved to a transcript, by default under `chats/` in the
+//! pantry config directory. Pointing `--transcript` at an existing one picks the
+//! conversation back up.
+use clap::Args;
+use futures::stream::StreamExt;
+use pantry_rs::chat::ChatMessage;
+use pantry_rs::config::ClientConfig;
+use pantry_rs::interface::LLMEventInternal;
+use pantry_rs::{ChatSession, PantryClient, PantryError, PromptFormat};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, BufReader, Lines, Stdin};
+
+const HELP: &str = "\
+/interrupt       stop the reply being written
+/reset           forget the conversation, keeping the system prompt
+/save [path]     save the transcript, to the given path or the current one
+/quit            leave";
+
+#[derive(Args)]
+pub struct ChatArgs {
+    /// LLM to use, by UUID or id. Defaults to whichever is running.
+    #[arg(long)]
+    llm: Option<String>,
+    /// Chat format, e.g. chatml or llama2. Detected from the LLM by default.
+    #[arg(long)]
+    template: Option<PromptFormat>,
+    /// System prompt for a new conversation.
+    #[arg(long)]
+    system: Option<String>,
+    /// Transcript to save to, and resume from if it exists.
+    #[arg(long)]
+    transcript: Option<PathBuf>,
+}
+
+pub async fn run(pantry: &PantryClient, args: ChatArgs) -> Result<(), PantryError> {
+    let sess = crate::create_session(pantry, args.llm).await?;
+    let llm_id = sess.llm_status.id.clone();
+    let transcript = match args.transcript {
+        Some(path) => path,
+        None => default_transcript(&sess.id.to_string())?,
+    };
+    let mut chat = match args.template {
+        Some(template) => ChatSession::with_template(sess, template),
+        None => ChatSession::new(sess),
+    };
+    if transcript.exists() {
+        let text = std::fs::read_to_string(&transcript)?;
+        *chat.messages_mut() = serde_json::from_str(&text)?;
+    } else if let Some(system) = args.system {
+        chat = chat.system(system);
+    }
+
+    println!("Chatting with {}. /help for commands.", llm_id);
+    let mut input = Input {
+        lines: BufReader::new(tokio::io::stdin()).lines(),
+        queued: VecDeque::new(),
+        closed: false,
+    };
+    loop {
+        print!("> ");
+        std::io::stdout().flush()?;
+        let line = match input.next().await? {
+            Some(line) => line,
+            None => break,
+        };
+        let (command, rest) = match line.trim().split_once(' ') {
+            Some((command, rest)) => (command, rest.trim()),
+            None => (line.trim(), ""),
+        };
+        match command {
+            "" => {}
+            "/quit" | "/exit" => break,
+            "/help" => println!("{}", HELP),
+            "/interrupt" => println!("(nothing to interrupt)"),
+            "/reset" => {
+                chat.clear();
+                save(&transcript, chat.messages())?;
+                println!("(conversation reset)");
+            }
+            "/save" => {
+                let path = match rest {
+                    "" => transcript.as_path(),
+                    path => Path::new(path),
+                };
+                save(path, chat.messages())?;
+                println!("(saved to {})", path.display());
+            }
+            command if command.starts_with('/') => {
+                println!("unknown command {}, /help for commands", command)
+            }
+            _ => match reply(&mut chat, line.trim(), &mut input).await {
+                Ok(()) => save(&transcript, chat.messages())?,
+                Err(e) => eprintln!("error: {}", e),
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Lines from stdin, including those typed ahead while a reply was coming in.
+struct Input {
+    lines: Lines<BufReader<Stdin>>,
+    queued: VecDeque<String>,
+    closed: bool,
+}
+
+impl Input {
+    async fn next(&mut self) -> Result<Option<String>, PantryError> {
+        match self.queued.pop_front() {
+            Some(line) => Ok(Some(line)),
+            None if self.closed => Ok(None),
+            None => Ok(self.lines.next_line().await?),
+        }
+    }
+}
+
+/// Sends `message`, printing the reply as it comes in. `/interrupt` stops it, anything
+/// else typed meanwhile waits its turn.
+async fn reply(
+    chat: &mut ChatSession,
+    message: &str,
+    input: &mut Input,
+) -> Result<(), PantryError> {
+    let mut stream = chat.send(message).await?;
+    loop {
+        tokio::select! {
+            event = stream.next() => match event {
+                None => break,
+                Some(event) => match event?.event {
+                    LLMEventInternal::PromptProgress { next, .. } => {
+                        print!("{}", next);
+                        std::io::stdout().flush()?;
+                    }
+                    LLMEventInternal::PromptError { message } => {
+                        return Err(PantryError::PromptError(message))
+                    }
+                    _ => {}
+                },
+            },
+            line = input.lines.next_line(), if !input.closed => match line? {
+                Some(line) if line.trim() == "/interrupt" => {
+                    stream.interrupt().await?;
+                    println!("\n(interrupted)");
+                    return Ok(());
+                }
+                Some(line) => input.queued.push_back(line),
+                None => input.closed = true,
+            },
+        }
+    }
+    println!();
+    Ok(())
+}
+
+/// `chats/<session id>.json` in the pantry config directory.
+fn default_transcript(session_id: &str) -> Result<PathBuf, PantryError> {
+    let config = ClientConfig::default_path()?;
+    let dir = config.parent().unwrap_or(Path::new("."));
+    Ok(dir.join("chats").join(format!("{}.json", session_id)))
+}
+
+fn save(path: &Path, messages: &[ChatMessage]) -> Result<(), PantryError> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(messages)?)?;
+    Ok(())
+}
@@ -0,0 +1,449 @@
+//! `pantry`, a command line client for Pantry.
+//!
+//! Handy for checking a Pantry setup and for scripting model management:
+//!
+//! ```text
+//! pantry register deploy-script --perm load-llm --perm unload-llm --perm view-llms
+//! pantry llms list --running
+//! pantry llm load llama-2-7b-chat
+//! pantry prompt "Write a haiku about pantries"
+//! pantry chat --template chatml
+//! pantry doctor
+//! pantry --json request status 1d2c3b4a-5e6f-4a1b-8c9d-0e1f2a3b4c5d
+//! ```
+//!
+//! Connection settings and credentials come from `PANTRY_*` variables or
+//! `client.toml`, see [pantry_rs::config]. Without credentials there, the ones saved by
+//! `pantry register` are used. `--json` prints machine-readable output instead of
+//! tables.
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use futures::stream::StreamExt;
+use pantry_rs::config::ClientConfig;
+use pantry_rs::diagnose::Check;
+use pantry_rs::interface::{
+    LLMEventInternal, LLMRegistryEntry, LLMStatus, UserPermissions, UserRequestStatus,
+};
+use pantry_rs::{
+    InferenceParams, LLMFilter, LLMSession, LlmUuid, PantryClient, PantryCredentials, PantryError,
+    RequestId, SessionId, UserId,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use uuid::Uuid;
+
+mod chat;
+
+/// App name `pantry register` saves credentials under, see [PantryCredentials::save].
+const CREDENTIALS_APP: &str = "pantry-cli";
+
+#[derive(Parser)]
+#[command(name = "pantry", version, about = "Command line client for Pantry")]
+struct Cli {
+    /// Connect over TCP to this url instead of the unix socket.
+    #[arg(long, global = true)]
+    url: Option<String>,
+    /// Unix socket to connect to.
+    #[arg(long, global = true)]
+    socket: Option<String>,
+    /// Print JSON instead of tables.
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Registers a new user and saves its credentials for later commands.
+    Register {
+        name: String,
+        /// Permission to request, can be repeated.
+        #[arg(long = "perm", value_enum)]
+        perms: Vec<Permission>,
+    },
+    /// Lists LLMs.
+    Llms {
+        #[command(subcommand)]
+        command: LlmsCommand,
+    },
+    /// Loads and unloads LLMs.
+    Llm {
+        #[command(subcommand)]
+        command: LlmCommand,
+    },
+    /// Downloads the LLM described by a registry entry file, JSON or TOML.
+    Download {
+        entry: PathBuf,
+        /// Ask the owner to download it instead of downloading it directly.
+        #[arg(long)]
+        request: bool,
+        /// Directory on the Pantry machine to store the model in, e.g. on an external
+        /// drive.
+        #[arg(long)]
+        target_dir: Option<String>,
+    },
+    /// Prompts an LLM in a new session, printing the reply as it comes in.
+    Prompt(PromptArgs),
+    /// Chats with an LLM. Type `/help` for commands.
+    Chat(chat::ChatArgs),
+    /// Checks that Pantry is running and the credentials work.
+    Doctor,
+    /// Permission, download, load and unload requests.
+    Request {
+        #[command(subcommand)]
+        command: RequestCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum LlmsCommand {
+    /// Lists available LLMs.
+    List {
+        /// Only the running ones.
+        #[arg(long)]
+        running: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum LlmCommand {
+    /// Loads an LLM, by UUID or id.
+    Load { llm: String },
+    /// Unloads an LLM, by UUID or id.
+    Unload { llm: String },
+}
+
+#[derive(Subcommand)]
+enum RequestCommand {
+    /// Shows whether a request has been accepted.
+    Status { request_id: RequestId },
+}
+
+#[derive(Args)]
+struct PromptArgs {
+    prompt: String,
+    /// LLM to use, by UUID or id. Defaults to whichever is running.
+    #[arg(long)]
+    llm: Option<String>,
+    #[arg(long)]
+    temperature: Option<f32>,
+    #[arg(long)]
+    max_tokens: Option<u32>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Permission {
+    Superuser,
+    LoadLlm,
+    UnloadLlm,
+    DownloadLlm,
+    Session,
+    RequestDownload,
+    RequestLoad,
+    RequestUnload,
+    ViewLlms,
+    BareModel,
+}
+
+fn permissions(perms: &[Permission]) -> UserPermissions {
+    let mut permissions = UserPermissions::default();
+    for perm in perms {
+        let flag = match perm {
+            Permission::Superuser => &mut permissions.perm_superuser,
+            Permission::LoadLlm => &mut permissions.perm_load_llm,
+            Permission::UnloadLlm => &mut permissions.perm_unload_llm,
+            Permission::DownloadLlm => &mut permissions.perm_download_llm,
+            Permission::Session => &mut permissions.perm_session,
+            Permission::RequestDownload => &mut permissions.perm_request_download,
+            Permission::RequestLoad => &mut permissions.perm_request_load,
+            Permission::RequestUnload => &mut permissions.perm_request_unload,
+            Permission::ViewLlms => &mut permissions.perm_view_llms,
+            Permission::BareModel => &mut permissions.perm_bare_model,
+        };
+        *flag = true;
+    }
+    permissions
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), PantryError> {
+    let mut configured = ClientConfig::load()?;
+    // A socket asked for on the command line beats a configured url.
+    if cli.socket.is_some() {
+        configured.url = None;
+    }
+    let config = ClientConfig {
+        url: cli.url.clone(),
+        socket: cli.socket.clone(),
+        ..Default::default()
+    }
+    .or(configured);
+
+    match cli.command {
+        Command::Register { name, perms } => {
+            let (pantry, request) = config.builder().register(name, permissions(&perms)).await?;
+            pantry.credentials().save(CREDENTIALS_APP)?;
+            output(
+                cli.json,
+                &Registered {
+                    user_id: pantry.user_id,
+                    request: &request,
+                },
+                || {
+                    println!("Registered user {}", pantry.user_id);
+                    print_request(&request);
+                },
+            )
+        }
+        Command::Llms {
+            command: LlmsCommand::List { running },
+        } => {
+            let pantry = login(&config)?;
+            let llms = if running {
+                pantry.get_running_llms().await?
+            } else {
+                pantry.get_available_llms().await?
+            };
+            output(cli.json, &llms, || print_llms(&llms))
+        }
+        Command::Llm {
+            command: LlmCommand::Load { llm },
+        } => {
+            let running = login(&config)?.load_llm(llm).await?;
+            output(cli.json, &running, || {
+                println!("Loaded {} ({})", running.llm_info.id, running.llm_info.uuid)
+            })
+        }
+        Command::Llm {
+            command: LlmCommand::Unload { llm },
+        } => {
+            let status = login(&config)?.unload_llm(llm).await?;
+            output(cli.json, &status, || {
+                println!("Unloaded {} ({})", status.id, status.uuid)
+            })
+        }
+        Command::Download {
+            entry,
+            request,
+            target_dir,
+        } => {
+            let pantry = login(&config)?;
+            let entry = read_entry(&entry)?;
+            if request {
+                let request = match target_dir {
+                    Some(dir) => pantry.request_download_llm_to(entry, dir).await?,
+                    None => pantry.request_download_llm(entry).await?,
+                };
+                output(cli.json, &request, || print_request(&request))
+            } else {
+                let uuid = match target_dir {
+                    Some(dir) => pantry.download_llm_to(entry, dir).await?,
+                    None => pantry.download_llm(entry).await?,
+                };
+                output(cli.json, &uuid, || println!("Downloading {}", uuid))
+            }
+        }
+        Command::Prompt(args) => prompt(&login(&config)?, args, cli.json).await,
+        Command::Chat(args) => chat::run(&login(&config)?, args).await,
+        Command::Doctor => doctor(&config, cli.json).await,
+        Command::Request {
+            command: RequestCommand::Status { request_id },
+        } => {
+            let request = login(&config)?.get_request_status(request_id).await?;
+            output(cli.json, &request, || print_request(&request))
+        }
+    }
+}
+
+/// A client with the configured credentials, or the ones `pantry register` saved. Those
+/// go to the Pantry they were registered with, unless a url or socket is configured.
+fn login(config: &ClientConfig) -> Result<PantryClient, PantryError> {
+    if let Some((user_id, api_key)) = config.credentials() {
+        return Ok(config.builder().login(user_id, api_key));
+    }
+    match PantryCredentials::load(CREDENTIALS_APP)? {
+        Some(creds) => {
+            let mut config = config.clone();
+            if config.url.is_none() && config.socket.is_none() {
+                config.url = creds.url;
+            }
+            Ok(config.builder().login(creds.user_id, creds.api_key))
+        }
+        None => Err(PantryError::CredentialError(
+            "no credentials: run `pantry register` or set PANTRY_USER_ID and PANTRY_API_KEY".into(),
+        )),
+    }
+}
+
+/// Prints [PantryClient::diagnose]'s report, failing if Pantry isn't healthy. Checks
+/// the connection even without credentials.
+async fn doctor(config: &ClientConfig, json: bool) -> Result<(), PantryError> {
+    let (pantry, missing) = match login(config) {
+        Ok(pantry) => (pantry, None),
+        Err(e) => (
+            config.builder().login(UserId(Uuid::nil()), String::new()),
+            Some(e),
+        ),
+    };
+    let mut report = pantry.diagnose().await;
+    if let Some(e) = missing {
+        report.credentials = Check::Failed(e.to_string());
+        report.llms = Check::Skipped("no credentials".into());
+    }
+    output(json, &report, || {
+        for (name, check) in report.checks() {
+            println!("{:<12} {}", name, check);
+        }
+    })?;
+    if report.is_healthy() {
+        Ok(())
+    } else {
+        Err(PantryError::OtherFailure("Pantry isn't healthy".into()))
+    }
+}
+
+/// A session on `llm`, by UUID or id, or on whichever LLM is running.
+async fn create_session(
+    pantry: &PantryClient,
+    llm: Option<String>,
+) -> Result<LLMSession, PantryError> {
+    match llm {
+        None => pantry.create_session(HashMap::new()).await,
+        Some(llm) => match llm.parse::<LlmUuid>() {
+            Ok(uuid) => pantry.create_session_id(uuid, HashMap::new()).await,
+            Err(_) => {
+                let filter = LLMFilter {
+                    llm_id: Some(llm),
+                    ..Default::default()
+                };
+                pantry
+                    .create_session_flex(Some(filter), None, HashMap::new())
+                    .await
+            }
+        },
+    }
+}
+
+/// Prompts a fresh session, deleting it afterwards.
+async fn prompt(pantry: &PantryClient, args: PromptArgs, json: bool) -> Result<(), PantryError> {
+    let sess = create_session(pantry, args.llm.clone()).await?;
+    let (session_id, llm_uuid) = (sess.id, sess.llm_status.uuid);
+    let text = reply(&sess, args, json).await;
+    let deleted = sess.delete().await;
+    let text = text?;
+    deleted?;
+    output(
+        json,
+        &Prompted {
+            session_id,
+            llm_uuid,
+            text: &text,
+        },
+        || println!(),
+    )
+}
+
+/// Streams the reply to `args.prompt`, printing it unless `json`.
+async fn reply(sess: &LLMSession, args: PromptArgs, json: bool) -> Result<String, PantryError> {
+    let mut params = InferenceParams::new();
+    params.temperature = args.temperature;
+    params.max_tokens = args.max_tokens;
+
+    let mut stream = sess.prompt_session(args.prompt, params).await?;
+    let mut text = String::new();
+    while let Some(event) = stream.next().await {
+        match event?.event {
+            LLMEventInternal::PromptProgress { next, .. } => {
+                if !json {
+                    print!("{}", next);
+                    std::io::stdout().flush()?;
+                }
+                text.push_str(&next);
+            }
+            LLMEventInternal::PromptError { message } => {
+                return Err(PantryError::PromptError(message))
+            }
+            _ => {}
+        }
+    }
+    Ok(text)
+}
+
+#[derive(Serialize)]
+struct Registered<'a> {
+    user_id: UserId,
+    request: &'a UserRequestStatus,
+}
+
+#[derive(Serialize)]
+struct Prompted<'a> {
+    session_id: SessionId,
+    llm_uuid: LlmUuid,
+    text: &'a str,
+}
+
+/// Prints `value` as JSON with `--json`, otherwise calls `human`.
+fn output<T: Serialize>(json: bool, value: &T, human: impl FnOnce()) -> Result<(), PantryError> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(value)?);
+    } else {
+        human();
+    }
+    Ok(())
+}
+
+fn print_llms(llms: &[LLMStatus]) {
+    if llms.is_empty() {
+        println!("No LLMs");
+        return;
+    }
+    println!(
+        "{:<36}  {:<24}  {:<8}  {:<10}  NAME",
+        "UUID", "ID", "RUNNING", "DOWNLOADED"
+    );
+    for llm in llms {
+        println!(
+            "{:<36}  {:<24}  {:<8}  {:<10}  {}",
+            llm.uuid,
+            llm.id,
+            if llm.running { "yes" } else { "no" },
+            format!("{:.0}%", llm.download_progress),
+            llm.name
+        );
+    }
+}
+
+fn print_request(request: &UserRequestStatus) {
+    let state = match (request.complete, request.accepted) {
+        (false, _) => "pending",
+        (true, true) => "accepted",
+        (true, false) => "denied",
+    };
+    println!("Request {}: {}", request.id, state);
+}
+
+fn read_entry(path: &Path) -> Result<LLMRegistryEntry, PantryError> {
+    let text = std::fs::read_to_string(path)?;
+    let invalid = |e: String| {
+        PantryError::OtherFailure(format!("invalid registry entry {}: {}", path.display(), e))
+    };
+    if path.extension().is_some_and(|ext| ext == "toml") {
+        toml::from_str(&text).map_err(|e| invalid(e.to_string()))
+    } else {
+        serde_json::from_str(&text).map_err(|e| invalid(e.to_string()))
+    }
+}
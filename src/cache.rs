@@ -0,0 +1,316 @@
+//! Reusing completions for prompts that were already answered.
+//!
+//! With a fixed seed, or a temperature of 0, an LLM answers the same prompt the same
+//! way every time, so running it again only costs time. A [ResponseCache] remembers
+//! completions by LLM, session history, parameters and prompt, and hands them back
+//! instead:
+//!
+//! ```no_run
+//! # use pantry_rs::{InferenceParams, PantryClient, ResponseCache};
+//! # use std::collections::HashMap;
+//! # use std::time::Duration;
+//! # async fn example(pantry: PantryClient) -> Result<(), Box<dyn std::error::Error>> {
+//! let cache = ResponseCache::new(1000).ttl(Duration::from_secs(3600));
+//! let params = InferenceParams::new().temperature(0.0).seed(42);
+//! let sess = pantry.create_session(HashMap::new()).await?;
+//! let first = cache.prompt(&sess, "Summarize: ...".into(), params.clone()).await?;
+//! // Comes straight from the cache.
+//! let fresh = pantry.create_session(HashMap::new()).await?;
+//! let again = cache.prompt(&fresh, "Summarize: ...".into(), params).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A session's history is part of what the LLM answers, so the same prompt only hits
+//! the cache after the same history, e.g. in fresh sessions. A hit doesn't prompt the
+//! session, so the exchange doesn't make it into its history. Prompts with neither a
+//! seed nor a temperature of 0 aren't cached at all.
+//!
+//! Identical prompts made while one is still running wait for it rather than running
+//! alongside it. Only completions that ran to the end, by [FinishReason::Stop] or
+//! [FinishReason::Length], are kept. Use [ResponseCache::refresh] to skip the cache
+//! for a prompt and replace what it had.
+use crate::error::PantryError;
+use crate::ids::LlmUuid;
+use crate::interface::{Completion, FinishReason};
+use crate::params::InferenceParams;
+use crate::LLMSession;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// Completions cached by LLM, session history, parameters and prompt, see the
+/// [module docs](self).
+///
+/// Cloning gives another handle to the same cache.
+#[derive(Clone, Debug)]
+pub struct ResponseCache {
+    capacity: usize,
+    ttl: Option<Duration>,
+    state: Arc<Mutex<CacheState>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct CacheKey {
+    llm_uuid: LlmUuid,
+    // Hash of everything the session was fed before the prompt.
+    history: u64,
+    // Parameters as JSON with sorted keys, so equal maps give equal keys.
+    parameters: String,
+    prompt: String,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, Entry>,
+    // One lock per prompt being run, which identical prompts wait on.
+    running: HashMap<CacheKey, Arc<tokio::sync::Mutex<()>>>,
+    // Bumped on every use, to find the least recently used entry.
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    completion: Completion,
+    stored: Instant,
+    used: u64,
+}
+
+impl ResponseCache {
+    /// A cache holding up to `capacity` completions, dropping the least recently used
+    /// one to make room. Entries don't expire unless given a [ResponseCache::ttl].
+    pub fn new(capacity: usize) -> Self {
+        ResponseCache {
+            capacity,
+            ttl: None,
+            state: Arc::new(Mutex::new(CacheState::default())),
+        }
+    }
+
+    /// Forget completions once they're older than `ttl`.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Completions currently cached, including expired ones not cleaned up yet.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forgets every cached completion.
+    pub fn clear(&self) {
+        self.state.lock().unwrap().entries.clear();
+    }
+
+    /// Returns the cached completion for `prompt` on `session`'s LLM, or prompts the
+    /// session and collects one. See [LLMSession::prompt_session].
+    ///
+    /// # Arguments
+    ///
+    /// * `session` — The session to prompt on a miss.
+    /// * `prompt` — Prompt for the LLM.
+    /// * `parameters` — As for [LLMSession::prompt_session]. Part of the cache key, and
+    ///   without a seed or a temperature of 0 the cache is skipped.
+    pub async fn prompt(
+        &self,
+        session: &LLMSession,
+        prompt: String,
+        parameters: impl Into<InferenceParams>,
+    ) -> Result<Completion, PantryError> {
+        let parameters = parameters.into();
+        if !cacheable(&parameters) {
+            return collect(session, prompt, parameters).await;
+        }
+        let key = CacheKey::new(session, &parameters, &prompt).await?;
+        if let Some(completion) = self.lookup(&key) {
+            return Ok(completion);
+        }
+
+        let running = self.running(&key);
+        let _running = running.lock.lock().await;
+        // Whoever held the lock before may have just answered it.
+        match self.lookup(&key) {
+            Some(completion) => Ok(completion),
+            None => self.run(session, key.clone(), prompt, parameters).await,
+        }
+    }
+
+    /// Prompts the session without looking at the cache, replacing what it had for
+    /// `prompt` with the new completion.
+    ///
+    /// Takes the same arguments as [ResponseCache::prompt].
+    pub async fn refresh(
+        &self,
+        session: &LLMSession,
+        prompt: String,
+        parameters: impl Into<InferenceParams>,
+    ) -> Result<Completion, PantryError> {
+        let parameters = parameters.into();
+        if !cacheable(&parameters) {
+            return collect(session, prompt, parameters).await;
+        }
+        let key = CacheKey::new(session, &parameters, &prompt).await?;
+        self.run(session, key, prompt, parameters).await
+    }
+
+    /// Forgets the completions cached for `prompt`, after any session history.
+    pub fn invalidate(
+        &self,
+        llm_uuid: LlmUuid,
+        prompt: &str,
+        parameters: impl Into<InferenceParams>,
+    ) -> Result<(), PantryError> {
+        let parameters = sorted(&parameters.into())?;
+        self.state.lock().unwrap().entries.retain(|key, _| {
+            key.llm_uuid != llm_uuid || key.parameters != parameters || key.prompt != prompt
+        });
+        Ok(())
+    }
+
+    async fn run(
+        &self,
+        session: &LLMSession,
+        key: CacheKey,
+        prompt: String,
+        parameters: InferenceParams,
+    ) -> Result<Completion, PantryError> {
+        let completion = collect(session, prompt, parameters).await?;
+        if matches!(
+            completion.finish_reason,
+            FinishReason::Stop | FinishReason::Length
+        ) {
+            self.store(key, completion.clone());
+        }
+        Ok(completion)
+    }
+
+    fn lookup(&self, key: &CacheKey) -> Option<Completion> {
+        let mut state = self.state.lock().unwrap();
+        let expired = match state.entries.get(key) {
+            Some(entry) => self.ttl.is_some_and(|ttl| entry.stored.elapsed() > ttl),
+            None => return None,
+        };
+        if expired {
+            state.entries.remove(key);
+            return None;
+        }
+        state.clock += 1;
+        let clock = state.clock;
+        let entry = state.entries.get_mut(key)?;
+        entry.used = clock;
+        Some(entry.completion.clone())
+    }
+
+    fn store(&self, key: CacheKey, completion: Completion) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let used = state.clock;
+        state.entries.insert(
+            key,
+            Entry {
+                completion,
+                stored: Instant::now(),
+                used,
+            },
+        );
+        while state.entries.len() > self.capacity {
+            let oldest = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(key) => state.entries.remove(&key),
+                None => break,
+            };
+        }
+    }
+
+    fn running(&self, key: &CacheKey) -> Running<'_> {
+        let mut state = self.state.lock().unwrap();
+        Running {
+            cache: self,
+            key: key.clone(),
+            lock: state.running.entry(key.clone()).or_default().clone(),
+        }
+    }
+}
+
+/// Our hold on the lock for a running prompt, cleaned up once nobody waits on it, even
+/// if the prompt is dropped halfway.
+struct Running<'a> {
+    cache: &'a ResponseCache,
+    key: CacheKey,
+    lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl Drop for Running<'_> {
+    fn drop(&mut self) {
+        let mut state = self.cache.state.lock().unwrap();
+        // Only the map's copy and ours left, so nobody else is waiting on it.
+        if Arc::strong_count(&self.lock) == 2 {
+            state.running.remove(&self.key);
+        }
+    }
+}
+
+impl CacheKey {
+    async fn new(
+        session: &LLMSession,
+        parameters: &InferenceParams,
+        prompt: &str,
+    ) -> Result<Self, PantryError> {
+        let mut history = DefaultHasher::new();
+        for item in session.history().await? {
+            item.input.hash(&mut history);
+            item.output.hash(&mut history);
+        }
+        Ok(CacheKey {
+            llm_uuid: session.llm_uuid,
+            history: history.finish(),
+            parameters: sorted(parameters)?,
+            prompt: prompt.into(),
+        })
+    }
+}
+
+fn sorted(parameters: &InferenceParams) -> Result<String, PantryError> {
+    let parameters: BTreeMap<_, _> = parameters.clone().into_map().into_iter().collect();
+    Ok(serde_json::to_string(&parameters)?)
+}
+
+/// Whether completions for `parameters` come out the same every time. An unset
+/// temperature is the LLM's default, which usually samples.
+fn cacheable(parameters: &InferenceParams) -> bool {
+    parameters.temperature == Some(0.0) || parameters.seed.is_some()
+}
+
+async fn collect(
+    session: &LLMSession,
+    prompt: String,
+    parameters: InferenceParams,
+) -> Result<Completion, PantryError> {
+    session
+        .prompt_session(prompt, parameters)
+        .await?
+        .collect_completion()
+        .await
+}
@@ -0,0 +1,208 @@
+//! Browsing the curated index of models known to work with Pantry.
+//!
+//! Rather than hardcoding a download url, an app can offer its users a pick of
+//! known-good models and submit the one they choose:
+//!
+//! ```no_run
+//! # use pantry_rs::catalog::browse_registry;
+//! # use pantry_rs::interface::CapabilityType;
+//! # use pantry_rs::PantryClient;
+//! # async fn example(pantry: PantryClient) -> Result<(), Box<dyn std::error::Error>> {
+//! let coders = browse_registry("", &["chat"], Some(CapabilityType::Coding)).await?;
+//! // Best coders first.
+//! let req = pantry.request_download_llm(coders[0].clone()).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [crate::PantryClient::check_updates] compares the models Pantry has against the same
+//! index, see [find_updates].
+//!
+//! The index is a JSON array of [LLMRegistryEntry]s, or an object with them under
+//! `llms`. Entries this crate can't read, or that don't pass
+//! [LLMRegistryEntry::validate], are left out.
+use crate::error::PantryError;
+use crate::huggingface::get;
+use crate::interface::{CapabilityType, DownloadRequest, LLMRegistryEntry, LLMStatus};
+use hyper::{Body, Client};
+use hyper_tls::HttpsConnector;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Where the upstream Pantry index lives.
+pub const PANTRY_REGISTRY_URL: &str =
+    "https://raw.githubusercontent.com/JuliaMerz/pantry/master/models/index.json";
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RegistryIndex {
+    List(Vec<Value>),
+    Wrapped { llms: Vec<Value> },
+}
+
+/// Fetches the upstream index and searches it, see [search_registry].
+pub async fn browse_registry(
+    query: &str,
+    tags: &[&str],
+    capability: Option<CapabilityType>,
+) -> Result<Vec<LLMRegistryEntry>, PantryError> {
+    browse_registry_at(PANTRY_REGISTRY_URL, query, tags, capability).await
+}
+
+/// Like [browse_registry], for an index somewhere else, e.g. a mirror or an
+/// organisation's own.
+pub async fn browse_registry_at(
+    index_url: &str,
+    query: &str,
+    tags: &[&str],
+    capability: Option<CapabilityType>,
+) -> Result<Vec<LLMRegistryEntry>, PantryError> {
+    let entries = fetch_registry(index_url).await?;
+    Ok(search_registry(entries, query, tags, capability))
+}
+
+/// Every entry of the index at `index_url`, e.g. [PANTRY_REGISTRY_URL].
+pub async fn fetch_registry(index_url: &str) -> Result<Vec<LLMRegistryEntry>, PantryError> {
+    let client = Client::builder().build::<_, Body>(HttpsConnector::new());
+    let body = get(&client, index_url.to_string()).await?;
+    parse_registry_index(&body)
+}
+
+/// Reads an index, skipping entries that can't be read or aren't valid.
+pub fn parse_registry_index(body: &str) -> Result<Vec<LLMRegistryEntry>, PantryError> {
+    let values = match serde_json::from_str(body)? {
+        RegistryIndex::List(values) | RegistryIndex::Wrapped { llms: values } => values,
+    };
+    Ok(values
+        .into_iter()
+        .filter_map(|value| serde_json::from_value::<LLMRegistryEntry>(value).ok())
+        .filter(|entry| entry.validate().is_ok())
+        .collect())
+}
+
+/// Offline half of [browse_registry], for entries you've already got.
+///
+/// # Arguments
+///
+/// * `query` — Matched case insensitively against the id, name, family, organization,
+///   description and tags. Empty matches everything.
+/// * `tags` — Tags every entry must have.
+/// * `capability` — Only entries rated for it, best first. Otherwise the index's order
+///   is kept.
+pub fn search_registry(
+    entries: Vec<LLMRegistryEntry>,
+    query: &str,
+    tags: &[&str],
+    capability: Option<CapabilityType>,
+) -> Vec<LLMRegistryEntry> {
+    let query = query.trim().to_lowercase();
+    let mut found: Vec<LLMRegistryEntry> = entries
+        .into_iter()
+        .filter(|entry| query.is_empty() || matches_query(entry, &query))
+        .filter(|entry| {
+            tags.iter()
+                .all(|tag| entry.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+        })
+        .collect();
+    if let Some(capability) = capability {
+        let rating = |entry: &LLMRegistryEntry| *entry.capabilities.get(&capability).unwrap_or(&-1);
+        // -1 is unevaluated, 0 is incapable.
+        found.retain(|entry| rating(entry) > 0);
+        found.sort_by_key(|entry| std::cmp::Reverse(rating(entry)));
+    }
+    found
+}
+
+fn matches_query(entry: &LLMRegistryEntry, query: &str) -> bool {
+    [
+        &entry.id,
+        &entry.name,
+        &entry.family_id,
+        &entry.organization,
+        &entry.description,
+    ]
+    .into_iter()
+    .chain(&entry.tags)
+    .any(|field| field.to_lowercase().contains(query))
+}
+
+/// Why [find_updates] suggests an entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UpdateKind {
+    /// Same id, higher [LLMRegistryEntry::version].
+    NewerVersion,
+    /// Supersedes the installed model, and has the same name and family, e.g. a GGUF
+    /// of what was a GGML.
+    Requantized,
+    /// Supersedes the installed model, e.g. the next generation of it.
+    Successor,
+}
+
+/// A model that could replace one Pantry already has.
+#[derive(Clone, Debug)]
+pub struct ModelUpdate {
+    pub installed: LLMStatus,
+    pub kind: UpdateKind,
+    /// Ready for [crate::PantryClient::request_download_llm] or
+    /// [crate::PantryClient::download_llm].
+    pub request: DownloadRequest,
+}
+
+/// Compares `installed` models against `index`, see [UpdateKind] for what counts as
+/// an update. Successors that are already installed aren't suggested again.
+pub fn find_updates(installed: &[LLMStatus], index: &[LLMRegistryEntry]) -> Vec<ModelUpdate> {
+    let mut updates = Vec::new();
+    for llm in installed {
+        for entry in index {
+            let kind = if entry.id == llm.id {
+                match (&entry.version, &llm.version) {
+                    (Some(newer), Some(current)) if is_newer(newer, current) => {
+                        UpdateKind::NewerVersion
+                    }
+                    _ => continue,
+                }
+            } else if entry.supersedes.contains(&llm.id)
+                && !installed.iter().any(|other| other.id == entry.id)
+            {
+                let same_model = !llm.family_id.is_empty()
+                    && entry.family_id == llm.family_id
+                    && entry.name.eq_ignore_ascii_case(&llm.name);
+                if same_model {
+                    UpdateKind::Requantized
+                } else {
+                    UpdateKind::Successor
+                }
+            } else {
+                continue;
+            };
+            updates.push(ModelUpdate {
+                installed: llm.clone(),
+                kind,
+                request: DownloadRequest {
+                    llm_registry_entry: entry.clone(),
+                    target_dir: None,
+                },
+            });
+        }
+    }
+    updates
+}
+
+/// Compares dotted versions part by part, numerically where they start with a number:
+/// `3.10` is newer than `3.9`, and `1.0.1` than `1.0`.
+fn is_newer(candidate: &str, current: &str) -> bool {
+    version_key(candidate) > version_key(current)
+}
+
+fn version_key(version: &str) -> Vec<(u64, String)> {
+    version
+        .trim()
+        .trim_start_matches(['v', 'V'])
+        .split(['.', '-', '_'])
+        .map(|part| {
+            let digits = part.len() - part.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let number = part[..digits].parse().unwrap_or(0);
+            (number, part[digits..].to_lowercase())
+        })
+        .collect()
+}
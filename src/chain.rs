@@ -0,0 +1,254 @@
+//! An [llm-chain](https://github.com/sobelio/llm-chain) executor backed by Pantry.
+//!
+//! Requires the `llm-chain` feature. Chains, agents and tools built on llm-chain can
+//! then run on whatever LLM Pantry has running:
+//!
+//! ```no_run
+//! # use llm_chain::options::ModelRef;
+//! # use llm_chain::{options, parameters, prompt};
+//! # use pantry_rs::chain::Executor;
+//! # use pantry_rs::PantryClient;
+//! # async fn example(pantry: PantryClient) -> Result<(), Box<dyn std::error::Error>> {
+//! let exec = Executor::with_client(pantry, options!(Model: ModelRef::from_model_name("llama-2-7b-chat")));
+//! let res = prompt!("You are a robot assistant", "Write a haiku about {{text}}")
+//!     .run(&parameters!("text" => "rust"), &exec)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Each step runs in a fresh session through [crate::openai_compat], so `Model` picks
+//! the LLM the same way `model` does there. Chat prompts are rendered with the LLM's
+//! [crate::PromptFormat]. Supported options are `Model`, `MaxTokens`, `MaxContextSize`,
+//! `StopSequence`, `Stream`, `TopP` and `Temperature`; the rest are ignored.
+use crate::chat::{ChatMessage, Role};
+use crate::context::estimate_tokens;
+use crate::error::PantryError;
+use crate::openai_compat::{ChatCompletionRequest, CompletionRequest, Stop};
+use crate::PantryClient;
+use async_trait::async_trait;
+use futures::stream::StreamExt;
+use llm_chain::options::{Opt, OptDiscriminants, Options, OptionsCascade};
+use llm_chain::output::{Output, StreamSegment};
+use llm_chain::prompt::{ChatMessageCollection, ChatRole, Data, Prompt};
+use llm_chain::tokens::{
+    PromptTokensError, TokenCollection, TokenCount, Tokenizer, TokenizerError,
+};
+use llm_chain::traits::{self, ExecutorCreationError, ExecutorError};
+
+/// Context size assumed when the `MaxContextSize` option isn't set.
+pub const DEFAULT_CONTEXT_SIZE: usize = 2048;
+
+/// Runs llm-chain steps on Pantry. See the [module docs](self).
+#[derive(Clone, Debug)]
+pub struct Executor {
+    pub pantry: PantryClient,
+    /// Defaults for every step, overridden by the step's own options.
+    pub options: Options,
+}
+
+impl Executor {
+    /// An executor using `pantry`'s credentials.
+    pub fn with_client(pantry: PantryClient, options: Options) -> Self {
+        Executor { pantry, options }
+    }
+
+    /// Stream or collect a chat prompt.
+    async fn chat(&self, request: ChatCompletionRequest) -> Result<Output, PantryError> {
+        if !request.stream {
+            let response = self.pantry.chat_completions(&request).await?;
+            let content = response
+                .choices
+                .into_iter()
+                .next()
+                .map(|choice| choice.message.content)
+                .unwrap_or_default();
+            return Ok(Output::new_immediate(Data::Chat(
+                ChatMessageCollection::new().with_assistant(content),
+            )));
+        }
+
+        let chunks = self.pantry.chat_completions_stream(&request).await?;
+        Ok(Output::from_stream(chunks.flat_map(|chunk| {
+            let segments = match chunk {
+                Ok(chunk) => chunk
+                    .choices
+                    .into_iter()
+                    .flat_map(|choice| {
+                        let role = choice
+                            .delta
+                            .role
+                            .map(|_| StreamSegment::Role(ChatRole::Assistant));
+                        role.into_iter()
+                            .chain(choice.delta.content.map(StreamSegment::Content))
+                    })
+                    .collect(),
+                Err(err) => vec![StreamSegment::Err(inner_error(err))],
+            };
+            futures::stream::iter(segments)
+        })))
+    }
+
+    /// Stream or collect a text prompt.
+    async fn complete(&self, request: CompletionRequest) -> Result<Output, PantryError> {
+        if !request.stream {
+            let response = self.pantry.completions(&request).await?;
+            let text = response
+                .choices
+                .into_iter()
+                .next()
+                .map(|choice| choice.text)
+                .unwrap_or_default();
+            return Ok(Output::new_immediate(Data::Text(text)));
+        }
+
+        let chunks = self.pantry.completions_stream(&request).await?;
+        Ok(Output::from_stream(chunks.filter_map(|chunk| async move {
+            match chunk {
+                Ok(chunk) => chunk
+                    .choices
+                    .into_iter()
+                    .next()
+                    .map(|choice| StreamSegment::Content(choice.text))
+                    .filter(|segment| !matches!(segment, StreamSegment::Content(text) if text.is_empty())),
+                Err(err) => Some(StreamSegment::Err(inner_error(err))),
+            }
+        })))
+    }
+
+    fn cascade<'a>(&'a self, options: &'a Options) -> OptionsCascade<'a> {
+        OptionsCascade::new()
+            .with_options(&self.options)
+            .with_options(options)
+    }
+}
+
+#[async_trait]
+impl traits::Executor for Executor {
+    type StepTokenizer<'a> = EstimatingTokenizer;
+
+    /// An executor for the client set up by `PANTRY_*` environment variables, see
+    /// [PantryClient::from_env].
+    fn new_with_options(options: Options) -> Result<Self, ExecutorCreationError> {
+        let pantry =
+            PantryClient::from_env().map_err(|e| ExecutorCreationError::InnerError(Box::new(e)))?;
+        Ok(Executor::with_client(pantry, options))
+    }
+
+    async fn execute(&self, options: &Options, prompt: &Prompt) -> Result<Output, ExecutorError> {
+        let options = self.cascade(options);
+        let request = request(&options);
+        let res = match prompt {
+            Data::Chat(messages) => {
+                let messages = messages
+                    .iter()
+                    .map(|message| ChatMessage {
+                        role: match message.role() {
+                            ChatRole::System => Role::System,
+                            ChatRole::Assistant => Role::Assistant,
+                            _ => Role::User,
+                        },
+                        content: message.body().clone(),
+                    })
+                    .collect();
+                self.chat(ChatCompletionRequest {
+                    messages,
+                    ..request
+                })
+                .await
+            }
+            Data::Text(text) => {
+                let request = CompletionRequest {
+                    model: request.model,
+                    prompt: text.clone(),
+                    temperature: request.temperature,
+                    top_p: request.top_p,
+                    max_tokens: request.max_tokens,
+                    seed: request.seed,
+                    stop: request.stop,
+                    stream: request.stream,
+                };
+                self.complete(request).await
+            }
+        };
+        res.map_err(inner_error)
+    }
+
+    fn tokens_used(
+        &self,
+        options: &Options,
+        prompt: &Prompt,
+    ) -> Result<TokenCount, PromptTokensError> {
+        let used = estimate_tokens(&prompt.to_text());
+        Ok(TokenCount::new(
+            self.max_tokens_allowed(options),
+            used as i32,
+        ))
+    }
+
+    fn max_tokens_allowed(&self, options: &Options) -> i32 {
+        match self.cascade(options).get(OptDiscriminants::MaxContextSize) {
+            Some(Opt::MaxContextSize(size)) => *size as i32,
+            _ => DEFAULT_CONTEXT_SIZE as i32,
+        }
+    }
+
+    fn answer_prefix(&self, _prompt: &Prompt) -> Option<String> {
+        None
+    }
+
+    fn get_tokenizer(&self, _options: &Options) -> Result<EstimatingTokenizer, TokenizerError> {
+        Ok(EstimatingTokenizer)
+    }
+}
+
+/// Pantry doesn't expose its LLMs' tokenizers, so this stands in for them: one token
+/// per character. Counts come out high, which keeps text split with it well within
+/// limits; see [estimate_tokens] for the estimate used for prompts.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EstimatingTokenizer;
+
+impl Tokenizer for EstimatingTokenizer {
+    fn tokenize_str(&self, doc: &str) -> Result<TokenCollection, TokenizerError> {
+        Ok(doc.chars().map(|c| c as usize).collect::<Vec<_>>().into())
+    }
+
+    fn to_string(&self, tokens: TokenCollection) -> Result<String, TokenizerError> {
+        tokens
+            .as_usize()?
+            .into_iter()
+            .map(|token| u32::try_from(token).ok().and_then(char::from_u32))
+            .collect::<Option<String>>()
+            .ok_or(TokenizerError::ToStringError)
+    }
+}
+
+/// A chat request without messages, set up from llm-chain options.
+pub fn request(options: &OptionsCascade) -> ChatCompletionRequest {
+    let mut request = ChatCompletionRequest {
+        stream: options.is_streaming(),
+        ..Default::default()
+    };
+    if let Some(Opt::Model(model)) = options.get(OptDiscriminants::Model) {
+        request.model = model.to_name();
+    }
+    if let Some(Opt::MaxTokens(max_tokens)) = options.get(OptDiscriminants::MaxTokens) {
+        request.max_tokens = Some(*max_tokens as u32);
+    }
+    if let Some(Opt::StopSequence(stops)) = options.get(OptDiscriminants::StopSequence) {
+        if !stops.is_empty() {
+            request.stop = Some(Stop::Many(stops.clone()));
+        }
+    }
+    if let Some(Opt::TopP(top_p)) = options.get(OptDiscriminants::TopP) {
+        request.top_p = Some(*top_p);
+    }
+    if let Some(Opt::Temperature(temperature)) = options.get(OptDiscriminants::Temperature) {
+        request.temperature = Some(*temperature);
+    }
+    request
+}
+
+fn inner_error(err: PantryError) -> ExecutorError {
+    ExecutorError::InnerError(Box::new(err))
+}
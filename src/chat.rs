@@ -0,0 +1,580 @@
+//! Chatting with an LLM, on top of [LLMSession].
+//!
+//! Pantry does no preprompting: a session just continues whatever text it's given.
+//! [ChatSession] keeps track of the conversation as a list of messages and formats
+//! each turn with a [ChatTemplate], so the LLM sees a proper chat transcript. The
+//! standard formats live in [crate::prompt_format].
+//!
+//! ```no_run
+//! # use futures::StreamExt;
+//! # use pantry_rs::interface::LLMEventInternal;
+//! # use pantry_rs::{ChatSession, PantryClient};
+//! # use std::collections::HashMap;
+//! # async fn example(pantry: PantryClient) -> Result<(), Box<dyn std::error::Error>> {
+//! let sess = pantry.create_session(HashMap::new()).await?;
+//! let mut chat = ChatSession::new(sess).system("You are a helpful assistant.");
+//!
+//! let mut reply = chat.send("What's the capital of France?").await?;
+//! while let Some(event) = reply.next().await {
+//!     if let LLMEventInternal::PromptProgress { next, .. } = event?.event {
+//!         print!("{}", next);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+#[cfg(feature = "streaming")]
+pub use self::session::{ChatSession, ChatStream};
+use crate::api::{LLMFilter, LLMPreference};
+
+/// Who said something in a chat.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Instructions for the assistant, usually first.
+    System,
+    User,
+    Assistant,
+}
+
+/// One turn of a chat.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn system(content: impl Into<String>) -> Self {
+        ChatMessage {
+            role: Role::System,
+            content: content.into(),
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        ChatMessage {
+            role: Role::User,
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        ChatMessage {
+            role: Role::Assistant,
+            content: content.into(),
+        }
+    }
+}
+
+/// Everything a [ChatSession] needs to continue a conversation after a restart, along
+/// with its session's [crate::SessionHandle]. See [ChatSession::transcript].
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ChatTranscript {
+    pub messages: Vec<ChatMessage>,
+    /// The rendered conversation the Pantry session has been fed so far.
+    pub fed: String,
+}
+
+/// What a [ChatSession] does when its LLM has stopped running, e.g. because the owner
+/// unloaded it, see [ChatSession::failover].
+#[derive(Clone, Debug, Default)]
+#[allow(clippy::large_enum_variant)]
+pub enum Failover {
+    /// Fail with [crate::PantryError::LlmNotRunning].
+    #[default]
+    Off,
+    /// Load the same LLM again. Requires [crate::interface::UserPermissions::perm_load_llm].
+    Reload,
+    /// Carry on with a running LLM chosen by `filter` and `preference`, loading one if
+    /// none is running (which requires
+    /// [crate::interface::UserPermissions::perm_load_llm]).
+    Select {
+        filter: Option<LLMFilter>,
+        preference: Option<LLMPreference>,
+    },
+}
+
+/// Turns a list of messages into the text an LLM was trained to continue.
+pub trait ChatTemplate: Send + Sync {
+    /// Renders the conversation. With `add_generation_prompt`, the result ends with
+    /// whatever cues the assistant to reply.
+    ///
+    /// Rendering must be append-only: rendering more messages must extend the text of
+    /// fewer messages, so that only the new part has to be sent to a running session.
+    fn render(&self, messages: &[ChatMessage], add_generation_prompt: bool) -> String;
+
+    /// Text that marks the end of the assistant's turn. Generation stops there.
+    fn stop_sequences(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// A template for models without a chat format of their own:
+///
+/// ```text
+/// System: You are a helpful assistant.
+///
+/// User: Hi!
+/// Assistant: Hello!
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlainTemplate;
+
+impl ChatTemplate for PlainTemplate {
+    fn render(&self, messages: &[ChatMessage], add_generation_prompt: bool) -> String {
+        let mut out = String::new();
+        for message in messages {
+            match message.role {
+                Role::System => out.push_str(&format!("System: {}\n\n", message.content)),
+                Role::User => out.push_str(&format!("User: {}\n", message.content)),
+                Role::Assistant => out.push_str(&format!("Assistant: {}\n", message.content)),
+            }
+        }
+        if add_generation_prompt {
+            out.push_str("Assistant:");
+        }
+        out
+    }
+
+    fn stop_sequences(&self) -> Vec<String> {
+        vec!["\nUser:".into()]
+    }
+}
+
+#[cfg(feature = "streaming")]
+mod session {
+    use super::{ChatMessage, ChatTemplate, ChatTranscript, Failover, Role};
+    use crate::context::{estimate_tokens, TruncationStrategy, DEFAULT_REPLY_RESERVE};
+    use crate::error::PantryError;
+    use crate::interface::{LLMEvent, LLMEventInternal, LLMRunningStatus};
+    use crate::params::{InferenceParams, LoadOptions};
+    use crate::prompt_format::PromptFormat;
+    use crate::stream::LLMEventStream;
+    use crate::LLMSession;
+    use futures::stream::{Stream, StreamExt};
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+
+    /// A conversation with an LLM, see the [module docs](super).
+    ///
+    /// Pantry sessions remember everything they've been prompted with, so each turn only
+    /// sends what's new. If the history gets edited in a way that changes what the LLM has
+    /// already seen, the next turn starts a fresh Pantry session with the same LLM.
+    ///
+    /// Before each turn the conversation is checked against the LLM's context length,
+    /// leaving room for the reply, and shortened with a [TruncationStrategy] if needed
+    /// (by default dropping the oldest messages). Shortening also starts a fresh session,
+    /// and the dropped messages are gone from [ChatSession::messages] too.
+    pub struct ChatSession {
+        session: LLMSession,
+        template: Arc<dyn ChatTemplate>,
+        messages: Vec<ChatMessage>,
+        params: InferenceParams,
+        truncation: TruncationStrategy,
+        context_length: Option<usize>,
+        token_counter: Arc<dyn Fn(&str) -> usize + Send + Sync>,
+        /// Everything the Pantry session has been fed, prompts and replies.
+        seen: String,
+        failover: Failover,
+        /// Whether the template and context length come from the LLM, and change with it.
+        detected_template: bool,
+        detected_context_length: bool,
+    }
+
+    impl ChatSession {
+        /// Starts a chat in `session`, formatted for its LLM, see [PromptFormat::detect].
+        pub fn new(session: LLMSession) -> Self {
+            let format = PromptFormat::detect(&session.llm_status);
+            let mut chat = ChatSession::with_template(session, format);
+            chat.detected_template = true;
+            chat
+        }
+
+        /// Starts a chat in `session` with a specific template, for LLMs that aren't
+        /// detected correctly or use a format of their own.
+        pub fn with_template(session: LLMSession, template: impl ChatTemplate + 'static) -> Self {
+            let context_length = session.context_length().map(|n| n as usize);
+            ChatSession {
+                session,
+                template: Arc::new(template),
+                messages: Vec::new(),
+                params: InferenceParams::default(),
+                truncation: TruncationStrategy::default(),
+                context_length,
+                token_counter: Arc::new(estimate_tokens),
+                seen: String::new(),
+                failover: Failover::default(),
+                detected_template: false,
+                detected_context_length: true,
+            }
+        }
+
+        /// Adds a system message, e.g. instructions for the assistant.
+        pub fn system(mut self, prompt: impl Into<String>) -> Self {
+            self.messages.push(ChatMessage::system(prompt));
+            self
+        }
+
+        /// Inference parameters for every turn. The template's stop sequences get added.
+        pub fn params(mut self, params: InferenceParams) -> Self {
+            self.params = params;
+            self
+        }
+
+        /// How to shorten the conversation once it outgrows the context window.
+        pub fn truncation(mut self, strategy: TruncationStrategy) -> Self {
+            self.truncation = strategy;
+            self
+        }
+
+        /// Overrides the context length, in tokens. By default it's taken from the LLM's
+        /// [crate::interface::LLMStatus::context_length]; without one, nothing gets
+        /// truncated.
+        pub fn context_length(mut self, tokens: usize) -> Self {
+            self.context_length = Some(tokens);
+            self.detected_context_length = false;
+            self
+        }
+
+        /// What to do when the LLM stops running mid-conversation. With anything but
+        /// [Failover::Off], the chat moves to a new session and sends the whole
+        /// conversation again, shortened to fit if need be. A chat with a detected
+        /// template switches templates along with the LLM.
+        pub fn failover(mut self, failover: Failover) -> Self {
+            self.failover = failover;
+            self
+        }
+
+        /// Counts tokens with `counter` instead of [estimate_tokens], e.g. with the
+        /// model's actual tokenizer.
+        pub fn token_counter(
+            mut self,
+            counter: impl Fn(&str) -> usize + Send + Sync + 'static,
+        ) -> Self {
+            self.token_counter = Arc::new(counter);
+            self
+        }
+
+        /// Tokens the conversation so far takes up, as counted by the token counter.
+        pub fn tokens_used(&self) -> usize {
+            (self.token_counter)(&self.template.render(&self.messages, false))
+        }
+
+        pub fn set_template(&mut self, template: impl ChatTemplate + 'static) {
+            self.template = Arc::new(template);
+            self.detected_template = false;
+        }
+
+        /// The conversation so far. Replies are added once they're complete.
+        pub fn messages(&self) -> &[ChatMessage] {
+            &self.messages
+        }
+
+        /// Direct access to the history, e.g. to seed or edit it.
+        pub fn messages_mut(&mut self) -> &mut Vec<ChatMessage> {
+            &mut self.messages
+        }
+
+        /// The conversation and what the Pantry session has seen of it, for saving.
+        pub fn transcript(&self) -> ChatTranscript {
+            ChatTranscript {
+                messages: self.messages.clone(),
+                fed: self.seen.clone(),
+            }
+        }
+
+        /// Continues a conversation saved with [ChatSession::transcript], in the session
+        /// it was saved from, e.g. one from [crate::PantryClient::resume_session]. In any
+        /// other session, empty `fed` first so the whole conversation gets sent again.
+        pub fn with_transcript(mut self, transcript: ChatTranscript) -> Self {
+            self.messages = transcript.messages;
+            self.seen = transcript.fed;
+            self
+        }
+
+        /// Forgets everything but the system messages.
+        pub fn clear(&mut self) {
+            self.messages.retain(|m| m.role == Role::System);
+        }
+
+        /// The underlying Pantry session.
+        pub fn session(&self) -> &LLMSession {
+            &self.session
+        }
+
+        /// Sends a message, returning a stream of the reply.
+        ///
+        /// The message and reply are added to [ChatSession::messages] once the reply is
+        /// complete. If the stream is dropped (or fails) before then, neither is.
+        pub async fn send(
+            &mut self,
+            user_message: impl Into<String>,
+        ) -> Result<ChatStream<'_>, PantryError> {
+            let user_message = ChatMessage::user(user_message);
+            let (stream, rendered) = match self.start(&user_message).await {
+                Err(PantryError::LlmNotRunning(_)) if !matches!(self.failover, Failover::Off) => {
+                    self.fail_over().await?;
+                    self.start(&user_message).await?
+                }
+                res => res?,
+            };
+            Ok(ChatStream {
+                inner: stream,
+                chat: self,
+                user_message: Some(user_message),
+                rendered,
+                reply: String::new(),
+            })
+        }
+
+        /// Prompts with the conversation plus `user_message`, returning the stream and
+        /// everything the session will have seen once it's done.
+        async fn start(
+            &mut self,
+            user_message: &ChatMessage,
+        ) -> Result<(LLMEventStream, String), PantryError> {
+            let mut messages = self.messages.clone();
+            messages.push(user_message.clone());
+            if let Some(context_length) = self.context_length {
+                let reserve = match self.params.max_tokens {
+                    Some(max_tokens) => max_tokens as usize,
+                    None => DEFAULT_REPLY_RESERVE,
+                };
+                let template = &self.template;
+                let counter = &self.token_counter;
+                let truncated = self
+                    .truncation
+                    .apply(
+                        &mut messages,
+                        |m| counter(&template.render(m, true)),
+                        context_length.saturating_sub(reserve),
+                    )
+                    .await?;
+                if truncated {
+                    self.messages = messages[..messages.len() - 1].to_vec();
+                }
+            }
+            let rendered = self.template.render(&messages, true);
+
+            let prompt = match rendered.strip_prefix(self.seen.as_str()) {
+                Some(new) => new.to_string(),
+                None => {
+                    self.restart().await?;
+                    rendered.clone()
+                }
+            };
+
+            let mut params = self.params.clone();
+            for stop in self.template.stop_sequences() {
+                if !params.stop_sequences.contains(&stop) {
+                    params.stop_sequences.push(stop);
+                }
+            }
+            let stream = self.session.prompt_session(prompt, params).await?;
+            Ok((stream, rendered))
+        }
+
+        /// Copies the conversation into a new chat, backed by a fork of the Pantry session
+        /// (see [LLMSession::fork]), so the two can continue differently.
+        pub async fn fork(&self) -> Result<ChatSession, PantryError> {
+            Ok(ChatSession {
+                session: self.session.fork().await?,
+                template: self.template.clone(),
+                messages: self.messages.clone(),
+                params: self.params.clone(),
+                truncation: self.truncation.clone(),
+                context_length: self.context_length,
+                token_counter: self.token_counter.clone(),
+                seen: self.seen.clone(),
+                failover: self.failover.clone(),
+                detected_template: self.detected_template,
+                detected_context_length: self.detected_context_length,
+            })
+        }
+
+        /// Sends a message and waits for the whole reply.
+        pub async fn send_and_collect(
+            &mut self,
+            user_message: impl Into<String>,
+        ) -> Result<String, PantryError> {
+            let mut stream = self.send(user_message).await?;
+            while let Some(event) = stream.next().await {
+                if let LLMEventInternal::PromptError { message } = event?.event {
+                    return Err(PantryError::PromptError(message));
+                }
+            }
+            if !stream.is_recorded() {
+                return Err(PantryError::StreamError(
+                    "stream ended before the reply was complete".into(),
+                ));
+            }
+            Ok(stream.reply.trim().to_string())
+        }
+
+        /// Swaps in a fresh Pantry session for the same LLM.
+        async fn restart(&mut self) -> Result<(), PantryError> {
+            let res = self
+                .session
+                .client
+                .create_session_id(
+                    self.session.user_id,
+                    self.session.api_key.clone(),
+                    self.session.llm_uuid,
+                    self.session.session_parameters.clone(),
+                )
+                .await?;
+            self.session.id = res.session_id;
+            self.session.session_parameters = res.session_parameters;
+            self.session.llm_status = res.llm_status;
+            self.seen.clear();
+            Ok(())
+        }
+
+        /// Moves to a new session according to [ChatSession::failover], after the LLM
+        /// stopped running.
+        async fn fail_over(&mut self) -> Result<(), PantryError> {
+            let session = &self.session;
+            let client = &session.client;
+            let (user_id, api_key) = (session.user_id, session.api_key.clone());
+            let parameters = session.session_parameters.clone();
+            let res = match &self.failover {
+                Failover::Off => return Ok(()),
+                Failover::Reload => {
+                    client
+                        .load_llm(
+                            user_id,
+                            api_key.clone(),
+                            session.llm_uuid.to_string(),
+                            LoadOptions::default(),
+                        )
+                        .await?;
+                    client
+                        .create_session_id(user_id, api_key, session.llm_uuid, parameters)
+                        .await?
+                }
+                Failover::Select { filter, preference } => {
+                    let created = client
+                        .create_session_flex(
+                            user_id,
+                            api_key.clone(),
+                            filter.clone(),
+                            preference.clone(),
+                            parameters.clone(),
+                        )
+                        .await;
+                    match created {
+                        Err(PantryError::LlmNotFound(_) | PantryError::LlmNotRunning(_)) => {
+                            client
+                                .load_llm_flex(
+                                    user_id,
+                                    api_key.clone(),
+                                    filter.clone(),
+                                    preference.clone(),
+                                    LoadOptions::default(),
+                                )
+                                .await?;
+                            client
+                                .create_session_flex(
+                                    user_id,
+                                    api_key,
+                                    filter.clone(),
+                                    preference.clone(),
+                                    parameters,
+                                )
+                                .await?
+                        }
+                        res => res?,
+                    }
+                }
+            };
+            self.session.id = res.session_id;
+            self.session.llm_uuid = res.llm_status.uuid;
+            self.session.session_parameters = res.session_parameters;
+            self.session.llm_status = res.llm_status;
+            self.seen.clear();
+            if self.detected_template {
+                self.template = Arc::new(PromptFormat::detect(&self.session.llm_status));
+            }
+            if self.detected_context_length {
+                self.context_length = self.session.context_length().map(|n| n as usize);
+            }
+            Ok(())
+        }
+    }
+
+    /// The reply to [ChatSession::send], as a stream of inference events.
+    ///
+    /// Dereferences to the underlying [LLMEventStream] for its metadata.
+    pub struct ChatStream<'a> {
+        inner: LLMEventStream,
+        chat: &'a mut ChatSession,
+        user_message: Option<ChatMessage>,
+        rendered: String,
+        reply: String,
+    }
+
+    impl<'a> ChatStream<'a> {
+        /// The reply so far.
+        pub fn reply(&self) -> &str {
+            &self.reply
+        }
+
+        /// Whether the exchange has made it into the chat history, which happens when the
+        /// completion event comes through.
+        pub fn is_recorded(&self) -> bool {
+            self.user_message.is_none()
+        }
+
+        /// Interrupts the reply, see [LLMEventStream::interrupt]. Whatever Pantry still
+        /// sends is read, and the exchange recorded with the reply cut short, so the chat
+        /// history matches what the session has seen.
+        pub async fn interrupt(&mut self) -> Result<LLMRunningStatus, PantryError> {
+            let status = self.inner.interrupt().await?;
+            while let Some(event) = self.next().await {
+                event?;
+            }
+            self.record();
+            Ok(status)
+        }
+
+        fn record(&mut self) {
+            if let Some(user_message) = self.user_message.take() {
+                self.chat.seen = format!("{}{}", self.rendered, self.reply);
+                self.chat.messages.push(user_message);
+                self.chat
+                    .messages
+                    .push(ChatMessage::assistant(self.reply.trim()));
+            }
+        }
+    }
+
+    impl<'a> std::ops::Deref for ChatStream<'a> {
+        type Target = LLMEventStream;
+
+        fn deref(&self) -> &Self::Target {
+            &self.inner
+        }
+    }
+
+    impl<'a> Stream for ChatStream<'a> {
+        type Item = Result<LLMEvent, PantryError>;
+
+        fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            let this = self.get_mut();
+            let poll = Pin::new(&mut this.inner).poll_next(cx);
+            if let Poll::Ready(Some(Ok(event))) = &poll {
+                // Prompting again after a failure moves the conversation to a fresh session,
+                // see [crate::PantryClientBuilder::prompt_retry].
+                this.chat.session.id = this.inner.session_id();
+                match &event.event {
+                    LLMEventInternal::PromptProgress { next, .. } => this.reply.push_str(next),
+                    LLMEventInternal::PromptCompletion { .. } => this.record(),
+                    _ => {}
+                }
+            }
+            poll
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Client settings from the environment or a config file.
+//!
+//! Lets scripts and CLI tools connect to an already set up Pantry without any
+//! configuration of their own, see [crate::PantryClient::from_env]. Settings come from,
+//! in order:
+//!
+//! * `PANTRY_URL`, `PANTRY_SOCKET`, `PANTRY_USER_ID`, `PANTRY_API_KEY` and
+//!   `PANTRY_TIMEOUT` (in seconds)
+//! * `client.toml` in the pantry config directory, e.g.
+//!   `~/.config/pantry/client.toml` on linux (see [ClientConfig::default_path]), or
+//!   wherever `PANTRY_CONFIG` points:
+//!
+//! ```toml
+//! url = "http://localhost:9404"
+//! user_id = "1d2c3b4a-5e6f-4a1b-8c9d-0e1f2a3b4c5d"
+//! api_key = "..."
+//! timeout = 30
+//! ```
+//!
+//! Remote hosts can have settings of their own, under their url. They're used when
+//! `url` matches, for credentials the top level doesn't have and for [RemoteAuth]:
+//!
+//! ```toml
+//! url = "http://10.0.0.7:9404"
+//!
+//! [hosts."http://10.0.0.7:9404"]
+//! user_id = "1d2c3b4a-5e6f-4a1b-8c9d-0e1f2a3b4c5d"
+//! api_key = "..."
+//! bearer_token = "..."
+//! ```
+use crate::auth::{RemoteAuth, DEFAULT_SECRET_HEADER};
+use crate::error::PantryError;
+use crate::ids::UserId;
+use crate::logging::REDACTED;
+use crate::PantryClientBuilder;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Environment variable pointing at a config file to use instead of the default one.
+pub const CONFIG_PATH_ENV: &str = "PANTRY_CONFIG";
+
+/// Settings for connecting to Pantry, all optional. See the [module docs](self).
+#[derive(Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientConfig {
+    /// Connect over TCP instead of the unix socket.
+    pub url: Option<String>,
+    /// Unix socket to connect to, when there's no url.
+    pub socket: Option<String>,
+    pub user_id: Option<UserId>,
+    pub api_key: Option<String>,
+    /// Default timeout for calls, in seconds.
+    pub timeout: Option<u64>,
+    /// Settings for particular hosts, by url. Only read from the config file.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hosts: BTreeMap<String, HostConfig>,
+}
+
+/// Settings for one remote host, see the [module docs](self).
+#[derive(Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HostConfig {
+    pub user_id: Option<UserId>,
+    pub api_key: Option<String>,
+    /// Sent as `Authorization: Bearer <token>`.
+    pub bearer_token: Option<String>,
+    /// Pre-shared secret, sent in `secret_header`.
+    pub secret: Option<String>,
+    /// Header to send `secret` in, [DEFAULT_SECRET_HEADER] if not set.
+    pub secret_header: Option<String>,
+}
+
+impl HostConfig {
+    /// The [RemoteAuth] to connect with, `None` if there's neither a token nor a secret.
+    pub fn auth(&self) -> Option<RemoteAuth> {
+        if self.bearer_token.is_none() && self.secret.is_none() {
+            return None;
+        }
+        let mut auth = RemoteAuth::new();
+        auth.bearer_token = self.bearer_token.clone();
+        if let Some(secret) = &self.secret {
+            let header = self
+                .secret_header
+                .as_deref()
+                .unwrap_or(DEFAULT_SECRET_HEADER);
+            auth = auth.header(header, secret.clone());
+        }
+        Some(auth)
+    }
+}
+
+fn redacted(secret: &Option<String>) -> Option<&'static str> {
+    secret.as_ref().map(|_| REDACTED)
+}
+
+// Keeps keys out of logs and panics.
+impl fmt::Debug for ClientConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientConfig")
+            .field("url", &self.url)
+            .field("socket", &self.socket)
+            .field("user_id", &self.user_id)
+            .field("api_key", &redacted(&self.api_key))
+            .field("timeout", &self.timeout)
+            .field("hosts", &self.hosts)
+            .finish()
+    }
+}
+
+impl fmt::Debug for HostConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HostConfig")
+            .field("user_id", &self.user_id)
+            .field("api_key", &redacted(&self.api_key))
+            .field("bearer_token", &redacted(&self.bearer_token))
+            .field("secret", &redacted(&self.secret))
+            .field("secret_header", &self.secret_header)
+            .finish()
+    }
+}
+
+impl ClientConfig {
+    /// Settings from the environment, falling back to the config file.
+    pub fn load() -> Result<Self, PantryError> {
+        let file = match std::env::var_os(CONFIG_PATH_ENV).filter(|v| !v.is_empty()) {
+            // An explicitly named file has to exist.
+            Some(path) => Self::from_file(path)?,
+            None => match Self::default_path() {
+                Ok(path) if path.exists() => Self::from_file(path)?,
+                _ => ClientConfig::default(),
+            },
+        };
+        Ok(Self::from_env()?.or(file))
+    }
+
+    /// Settings from `PANTRY_*` environment variables only.
+    pub fn from_env() -> Result<Self, PantryError> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        Ok(ClientConfig {
+            url: var("PANTRY_URL"),
+            socket: var(crate::api::SOCKET_PATH_ENV),
+            user_id: var("PANTRY_USER_ID")
+                .map(|id| parse(&id, "PANTRY_USER_ID"))
+                .transpose()?,
+            api_key: var("PANTRY_API_KEY"),
+            timeout: var("PANTRY_TIMEOUT")
+                .map(|secs| parse(&secs, "PANTRY_TIMEOUT"))
+                .transpose()?,
+            hosts: BTreeMap::new(),
+        })
+    }
+
+    /// Settings from a TOML file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, PantryError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|e| {
+            PantryError::CredentialError(format!("invalid config {}: {}", path.display(), e))
+        })
+    }
+
+    /// `client.toml` in the pantry config directory, see
+    /// [crate::PantryCredentials::default_path].
+    pub fn default_path() -> Result<PathBuf, PantryError> {
+        Ok(crate::credentials::config_dir()?.join("client.toml"))
+    }
+
+    /// Settings from `self`, with any gaps filled from `fallback`. Hosts are merged,
+    /// those in `self` winning.
+    pub fn or(self, fallback: ClientConfig) -> ClientConfig {
+        let mut hosts = fallback.hosts;
+        hosts.extend(self.hosts);
+        ClientConfig {
+            url: self.url.or(fallback.url),
+            socket: self.socket.or(fallback.socket),
+            user_id: self.user_id.or(fallback.user_id),
+            api_key: self.api_key.or(fallback.api_key),
+            timeout: self.timeout.or(fallback.timeout),
+            hosts,
+        }
+    }
+
+    /// Settings for the host `url` points at, ignoring trailing slashes.
+    pub fn host(&self) -> Option<&HostConfig> {
+        let url = self.url.as_deref()?.trim_end_matches('/');
+        self.hosts
+            .iter()
+            .find(|(key, _)| key.trim_end_matches('/') == url)
+            .map(|(_, host)| host)
+    }
+
+    /// Credentials to log in with, from the top level or else the host's settings.
+    pub fn credentials(&self) -> Option<(UserId, String)> {
+        let host = self.host();
+        let user_id = self.user_id.or_else(|| host?.user_id)?;
+        let api_key = self.api_key.clone().or_else(|| host?.api_key.clone())?;
+        Some((user_id, api_key))
+    }
+
+    /// A builder with the connection settings applied, including the host's
+    /// [RemoteAuth]. Credentials are left to the caller.
+    pub fn builder(&self) -> PantryClientBuilder {
+        let mut builder = PantryClientBuilder::new();
+        if let Some(url) = &self.url {
+            builder = builder.base_url(url.clone());
+        }
+        if let Some(socket) = &self.socket {
+            builder = builder.socket_path(socket.clone());
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(Duration::from_secs(timeout));
+        }
+        if let Some(auth) = self.host().and_then(HostConfig::auth) {
+            builder = builder.remote_auth(auth);
+        }
+        builder
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str, name: &str) -> Result<T, PantryError> {
+    value
+        .parse()
+        .map_err(|_| PantryError::CredentialError(format!("invalid {}: {:?}", name, value)))
+}
@@ -0,0 +1,316 @@
+//! Typed `config` for each [LLMConnectorType].
+//!
+//! Pantry takes a registry entry's connector settings as a free-form
+//! [LLMRegistryEntry::config] map. The structs here name the keys each connector
+//! understands, and convert to and from that map:
+//!
+//! ```no_run
+//! # use pantry_rs::interface::{LLMConnectorType, LLMRegistryEntry, LLMStatus};
+//! # use pantry_rs::{ConnectorConfig, LlmrsConfig, ModelArchitecture};
+//! # async fn example(llm: LLMStatus) -> Result<(), Box<dyn std::error::Error>> {
+//! let entry = LLMRegistryEntry::builder("openchat", LLMConnectorType::LLMrs)
+//!     .url("https://example.com/openchat.q4_0.bin")
+//!     .connector_config(LlmrsConfig {
+//!         vocabulary_repository: Some("openchat/openchat_v3.2".into()),
+//!         ..LlmrsConfig::new(ModelArchitecture::Llama)
+//!     })
+//!     .build()?;
+//!
+//! if let ConnectorConfig::LLMrs(config) = llm.connector_config()? {
+//!     println!("{} model", config.model_architecture);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Keys this crate doesn't know about end up in each struct's `extra`, and are sent
+//! as they are.
+use crate::error::PantryError;
+use crate::interface::{LLMConnectorType, LLMRegistryEntry, LLMStatus};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Model architectures the LLMrs connector runs, see the [rustformers/llm
+/// documentation](https://docs.rs/llm/latest/llm/enum.ModelArchitecture.html).
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum ModelArchitecture {
+    Bloom,
+    Gpt2,
+    GptJ,
+    GptNeoX,
+    Llama,
+    Mpt,
+    Falcon,
+    /// Anything newer than this crate, kept as is.
+    Other(String),
+}
+
+impl ModelArchitecture {
+    /// The name LLMrs uses for this architecture.
+    pub fn as_str(&self) -> &str {
+        match self {
+            ModelArchitecture::Bloom => "bloom",
+            ModelArchitecture::Gpt2 => "gpt2",
+            ModelArchitecture::GptJ => "gptj",
+            ModelArchitecture::GptNeoX => "gptneox",
+            ModelArchitecture::Llama => "llama",
+            ModelArchitecture::Mpt => "mpt",
+            ModelArchitecture::Falcon => "falcon",
+            ModelArchitecture::Other(other) => other,
+        }
+    }
+
+    pub fn is_known(&self) -> bool {
+        !matches!(self, ModelArchitecture::Other(_))
+    }
+}
+
+impl From<&str> for ModelArchitecture {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "bloom" => ModelArchitecture::Bloom,
+            "gpt2" => ModelArchitecture::Gpt2,
+            "gptj" => ModelArchitecture::GptJ,
+            "gptneox" => ModelArchitecture::GptNeoX,
+            "llama" => ModelArchitecture::Llama,
+            "mpt" => ModelArchitecture::Mpt,
+            "falcon" => ModelArchitecture::Falcon,
+            _ => ModelArchitecture::Other(s.to_string()),
+        }
+    }
+}
+
+impl From<String> for ModelArchitecture {
+    fn from(s: String) -> Self {
+        ModelArchitecture::from(s.as_str())
+    }
+}
+
+impl From<ModelArchitecture> for String {
+    fn from(architecture: ModelArchitecture) -> Self {
+        match architecture {
+            ModelArchitecture::Other(other) => other,
+            known => known.as_str().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ModelArchitecture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Config for [LLMConnectorType::LLMrs], which runs model files locally.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct LlmrsConfig {
+    pub model_architecture: ModelArchitecture,
+    /// Path to a Hugging Face `tokenizer.json`, instead of the model's own vocabulary.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vocabulary_path: Option<String>,
+    /// Hugging Face repository to fetch the tokenizer from, e.g. `"openchat/openchat"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vocabulary_repository: Option<String>,
+    /// Context window in tokens, see [LLMStatus::context_length].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_length: Option<u64>,
+    /// Memory needed to run the model, see [LLMStatus::ram_bytes].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ram_bytes: Option<u64>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl LlmrsConfig {
+    pub fn new(model_architecture: ModelArchitecture) -> Self {
+        LlmrsConfig {
+            model_architecture,
+            vocabulary_path: None,
+            vocabulary_repository: None,
+            context_length: None,
+            ram_bytes: None,
+            extra: HashMap::new(),
+        }
+    }
+}
+
+/// Config for [LLMConnectorType::OpenAI].
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct OpenAIConfig {
+    /// Sent back to anyone who can view the LLM, prefer
+    /// [crate::PantryClient::set_connector_secret].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// For OpenAI compatible APIs elsewhere, e.g. `"https://api.together.xyz/v1"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_base: Option<String>,
+    /// The model to ask for, e.g. `"gpt-4"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_length: Option<u64>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// Config for [LLMConnectorType::GenericAPI], which calls the API at the entry's
+/// `url`.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct GenericAPIConfig {
+    /// Sent back to anyone who can view the LLM, prefer
+    /// [crate::PantryClient::set_connector_secret].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// The model to ask for, for APIs that serve several.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_length: Option<u64>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// Config for [LLMConnectorType::Whisper], which runs speech to text models locally.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct WhisperConfig {
+    /// Language the audio is taken to be in, e.g. `"en"`, unless a transcription says
+    /// otherwise. Detected when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// For the English-only models, e.g. `base.en`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub english_only: Option<bool>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// The config of any connector, see the [module docs](self).
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConnectorConfig {
+    LLMrs(LlmrsConfig),
+    OpenAI(OpenAIConfig),
+    GenericAPI(GenericAPIConfig),
+    Whisper(WhisperConfig),
+}
+
+impl ConnectorConfig {
+    pub fn connector_type(&self) -> LLMConnectorType {
+        match self {
+            ConnectorConfig::LLMrs(_) => LLMConnectorType::LLMrs,
+            ConnectorConfig::OpenAI(_) => LLMConnectorType::OpenAI,
+            ConnectorConfig::GenericAPI(_) => LLMConnectorType::GenericAPI,
+            ConnectorConfig::Whisper(_) => LLMConnectorType::Whisper,
+        }
+    }
+
+    /// Reads `config` as `connector_type`'s. Fails with
+    /// [PantryError::InvalidRegistryEntry] if a key is missing or has the wrong type.
+    pub fn from_map(
+        connector_type: &LLMConnectorType,
+        config: &HashMap<String, Value>,
+    ) -> Result<Self, PantryError> {
+        Ok(match connector_type {
+            LLMConnectorType::LLMrs => {
+                match config.get("model_architecture") {
+                    Some(Value::String(arch)) if !arch.trim().is_empty() => {}
+                    Some(_) => return Err(invalid("config.model_architecture must be a string")),
+                    None => {
+                        return Err(invalid(
+                            "LLMrs entries need config.model_architecture, see https://docs.rs/llm/latest/llm/enum.ModelArchitecture.html",
+                        ))
+                    }
+                }
+                ConnectorConfig::LLMrs(parse(config)?)
+            }
+            LLMConnectorType::OpenAI => ConnectorConfig::OpenAI(parse(config)?),
+            LLMConnectorType::GenericAPI => ConnectorConfig::GenericAPI(parse(config)?),
+            LLMConnectorType::Whisper => ConnectorConfig::Whisper(parse(config)?),
+        })
+    }
+
+    /// The config as the map Pantry takes.
+    pub fn to_map(&self) -> HashMap<String, Value> {
+        let value = match self {
+            ConnectorConfig::LLMrs(config) => to_object(config),
+            ConnectorConfig::OpenAI(config) => to_object(config),
+            ConnectorConfig::GenericAPI(config) => to_object(config),
+            ConnectorConfig::Whisper(config) => to_object(config),
+        };
+        value.into_iter().collect()
+    }
+}
+
+impl From<LlmrsConfig> for ConnectorConfig {
+    fn from(config: LlmrsConfig) -> Self {
+        ConnectorConfig::LLMrs(config)
+    }
+}
+
+impl From<OpenAIConfig> for ConnectorConfig {
+    fn from(config: OpenAIConfig) -> Self {
+        ConnectorConfig::OpenAI(config)
+    }
+}
+
+impl From<GenericAPIConfig> for ConnectorConfig {
+    fn from(config: GenericAPIConfig) -> Self {
+        ConnectorConfig::GenericAPI(config)
+    }
+}
+
+impl From<WhisperConfig> for ConnectorConfig {
+    fn from(config: WhisperConfig) -> Self {
+        ConnectorConfig::Whisper(config)
+    }
+}
+
+impl LLMRegistryEntry {
+    /// [LLMRegistryEntry::config], typed according to the connector.
+    pub fn connector_config(&self) -> Result<ConnectorConfig, PantryError> {
+        ConnectorConfig::from_map(&self.connector_type, &self.config)
+    }
+
+    /// Replaces the connector and its config.
+    pub fn set_connector_config(&mut self, config: impl Into<ConnectorConfig>) {
+        let config = config.into();
+        self.connector_type = config.connector_type();
+        self.config = config.to_map();
+    }
+}
+
+impl LLMStatus {
+    /// [LLMStatus::config], typed according to the connector. Fails with
+    /// [PantryError::InvalidRegistryEntry] for connectors this crate doesn't know.
+    pub fn connector_config(&self) -> Result<ConnectorConfig, PantryError> {
+        let connector_type = match self.connector_type.to_lowercase().as_str() {
+            "llmrs" => LLMConnectorType::LLMrs,
+            "openai" => LLMConnectorType::OpenAI,
+            "genericapi" => LLMConnectorType::GenericAPI,
+            "whisper" => LLMConnectorType::Whisper,
+            other => return Err(invalid(&format!("unknown connector {}", other))),
+        };
+        ConnectorConfig::from_map(&connector_type, &self.config)
+    }
+}
+
+fn parse<T: DeserializeOwned>(config: &HashMap<String, Value>) -> Result<T, PantryError> {
+    let map: Map<String, Value> = config.clone().into_iter().collect();
+    serde_json::from_value(Value::Object(map)).map_err(|e| invalid(&format!("config: {}", e)))
+}
+
+fn to_object<T: Serialize>(config: &T) -> Map<String, Value> {
+    match serde_json::to_value(config) {
+        Ok(Value::Object(map)) => map,
+        _ => unreachable!("connector configs serialize to objects"),
+    }
+}
+
+fn invalid(msg: &str) -> PantryError {
+    PantryError::InvalidRegistryEntry(msg.into())
+}
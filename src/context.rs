@@ -0,0 +1,174 @@
+//! Keeping conversations within an LLM's context window.
+//!
+//! Pantry doesn't stop a session from growing past what its model can attend to; the
+//! output just degrades. [crate::chat::ChatSession] checks each turn against the
+//! model's context length and, if it wouldn't fit, shortens the history with a
+//! [TruncationStrategy].
+//!
+//! Token counts are estimated (see [estimate_tokens]) unless you supply a tokenizer
+//! through [crate::chat::ChatSession::token_counter].
+use crate::chat::{ChatMessage, Role};
+use crate::error::PantryError;
+use futures::future::BoxFuture;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+/// Tokens left free for the reply when [crate::params::InferenceParams::max_tokens]
+/// isn't set.
+pub const DEFAULT_REPLY_RESERVE: usize = 256;
+
+/// Rough token count for `text`, about four characters per token. Errs high for
+/// English and low for code and most other languages.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+const SUMMARY_PREFIX: &str = "Summary of the conversation so far: ";
+
+/// Turns messages about to be dropped into a short summary.
+pub type Summarizer =
+    Arc<dyn Fn(Vec<ChatMessage>) -> BoxFuture<'static, Result<String, PantryError>> + Send + Sync>;
+
+/// What to do when a conversation no longer fits the context window.
+///
+/// System messages and the message being sent are always kept.
+#[derive(Clone, Default)]
+pub enum TruncationStrategy {
+    /// Fail with [PantryError::ContextOverflow].
+    Error,
+    /// Drop the oldest messages until the conversation fits.
+    #[default]
+    DropOldest,
+    /// Keep only this many of the most recent messages, dropping more if that still
+    /// doesn't fit.
+    SlidingWindow(usize),
+    /// Like [TruncationStrategy::DropOldest], but the dropped messages get replaced by a
+    /// system message summarizing them. See [TruncationStrategy::summarize].
+    Summarize(Summarizer),
+}
+
+impl TruncationStrategy {
+    /// Summarizes dropped messages with `summarizer`, e.g. by asking a separate session
+    /// to do it.
+    ///
+    /// ```no_run
+    /// # use pantry_rs::context::TruncationStrategy;
+    /// # use pantry_rs::LLMSession;
+    /// # use std::collections::HashMap;
+    /// # use std::sync::Arc;
+    /// # fn example(summary_session: Arc<LLMSession>) {
+    /// let strategy = TruncationStrategy::summarize(move |messages| {
+    ///     let sess = summary_session.clone();
+    ///     async move {
+    ///         let transcript = messages.iter().map(|m| m.content.as_str()).collect::<Vec<_>>().join("\n");
+    ///         sess.prompt_and_collect(format!("Summarize:\n{}\n", transcript), HashMap::new()).await
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub fn summarize<F, Fut>(summarizer: F) -> Self
+    where
+        F: Fn(Vec<ChatMessage>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, PantryError>> + Send + 'static,
+    {
+        TruncationStrategy::Summarize(Arc::new(move |messages| Box::pin(summarizer(messages))))
+    }
+
+    /// Shortens `messages` until `count(messages) <= budget`. Returns whether anything
+    /// changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` — the conversation, ending with the message about to be sent.
+    /// * `count` — tokens the conversation takes up once rendered.
+    /// * `budget` — tokens available for it.
+    pub async fn apply(
+        &self,
+        messages: &mut Vec<ChatMessage>,
+        count: impl Fn(&[ChatMessage]) -> usize,
+        budget: usize,
+    ) -> Result<bool, PantryError> {
+        if count(messages) <= budget {
+            return Ok(false);
+        }
+
+        match self {
+            TruncationStrategy::Error => {}
+            TruncationStrategy::DropOldest => {
+                drop_oldest(messages, &count, budget);
+            }
+            TruncationStrategy::SlidingWindow(size) => {
+                let mut excess = messages
+                    .iter()
+                    .filter(|m| m.role != Role::System)
+                    .count()
+                    .saturating_sub((*size).max(1));
+                messages.retain(|m| {
+                    if m.role == Role::System || excess == 0 {
+                        true
+                    } else {
+                        excess -= 1;
+                        false
+                    }
+                });
+                drop_oldest(messages, &count, budget);
+            }
+            TruncationStrategy::Summarize(summarizer) => {
+                // Make room for the summary while dropping, then fill it in.
+                let at = messages
+                    .iter()
+                    .position(|m| m.role != Role::System)
+                    .unwrap_or(messages.len());
+                messages.insert(at, ChatMessage::system(SUMMARY_PREFIX));
+                let dropped = drop_oldest(messages, &count, budget);
+                if dropped.is_empty() {
+                    messages.remove(at);
+                } else {
+                    let summary = summarizer(dropped).await?;
+                    messages[at].content = format!("{}{}", SUMMARY_PREFIX, summary.trim());
+                    drop_oldest(messages, &count, budget);
+                }
+            }
+        }
+
+        let needed = count(messages);
+        if needed > budget {
+            return Err(PantryError::ContextOverflow(needed, budget));
+        }
+        Ok(true)
+    }
+}
+
+/// Drops the oldest droppable message until the conversation fits or there's nothing
+/// left to drop, returning what was dropped.
+fn drop_oldest(
+    messages: &mut Vec<ChatMessage>,
+    count: &impl Fn(&[ChatMessage]) -> usize,
+    budget: usize,
+) -> Vec<ChatMessage> {
+    let mut dropped = Vec::new();
+    while count(messages) > budget {
+        let last = messages.len().saturating_sub(1);
+        match messages
+            .iter()
+            .position(|m| m.role != Role::System)
+            .filter(|i| *i < last)
+        {
+            Some(i) => dropped.push(messages.remove(i)),
+            None => break,
+        }
+    }
+    dropped
+}
+
+impl fmt::Debug for TruncationStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TruncationStrategy::Error => write!(f, "Error"),
+            TruncationStrategy::DropOldest => write!(f, "DropOldest"),
+            TruncationStrategy::SlidingWindow(size) => write!(f, "SlidingWindow({})", size),
+            TruncationStrategy::Summarize(_) => write!(f, "Summarize(..)"),
+        }
+    }
+}
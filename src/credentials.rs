@@ -0,0 +1,193 @@
+//! Storing a client's `user_id` and `api_key` between runs.
+//!
+//! The API key is your sole authentication mechanism, so it shouldn't end up in your
+//! repository. [PantryCredentials] saves it to a file in the user's config directory
+//! (readable only by them on unix) or, with the `keyring` feature, to the OS keyring.
+//!
+//! ```no_run
+//! # use pantry_rs::interface::UserPermissions;
+//! # use pantry_rs::{PantryClient, PantryCredentials};
+//! # async fn example(perms: UserPermissions) -> Result<(), Box<dyn std::error::Error>> {
+//! let pantry = match PantryCredentials::load("my-app")? {
+//!     Some(creds) => creds.client(),
+//!     None => {
+//!         let (pantry, _request) = PantryClient::register("my app".into(), perms, None).await?;
+//!         pantry.credentials().save("my-app")?;
+//!         pantry
+//!     }
+//! };
+//! # Ok(())
+//! # }
+//! ```
+use crate::error::PantryError;
+use crate::ids::UserId;
+use crate::logging::REDACTED;
+use crate::PantryClient;
+use std::fmt;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// Everything needed to log back in, see [PantryClient::login].
+#[derive(Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PantryCredentials {
+    pub user_id: UserId,
+    pub api_key: String,
+    /// `None` for the local instance.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl PantryCredentials {
+    pub fn new(user_id: UserId, api_key: String, url: Option<String>) -> Self {
+        PantryCredentials {
+            user_id,
+            api_key,
+            url,
+        }
+    }
+
+    /// A client using these credentials. Does not make any API calls.
+    pub fn client(&self) -> PantryClient {
+        PantryClient::login(self.user_id, self.api_key.clone(), self.url.clone())
+    }
+
+    /// Where [PantryCredentials::save] puts the credentials for `app`:
+    /// `<config dir>/pantry/credentials/<app>.json`, where the config dir is
+    /// `$XDG_CONFIG_HOME` or `~/.config` on linux, `~/Library/Application Support` on
+    /// macOS and `%APPDATA%` on windows.
+    pub fn default_path(app: &str) -> Result<PathBuf, PantryError> {
+        if app.is_empty() || app.contains(['/', '\\']) || app.starts_with('.') {
+            return Err(PantryError::CredentialError(format!(
+                "invalid app name: {:?}",
+                app
+            )));
+        }
+        Ok(config_dir()?
+            .join("credentials")
+            .join(format!("{}.json", app)))
+    }
+
+    /// Saves the credentials for `app` to [PantryCredentials::default_path].
+    pub fn save(&self, app: &str) -> Result<(), PantryError> {
+        self.save_to(Self::default_path(app)?)
+    }
+
+    /// Loads what [PantryCredentials::save] saved for `app`, `None` if nothing was.
+    pub fn load(app: &str) -> Result<Option<Self>, PantryError> {
+        Self::load_from(Self::default_path(app)?)
+    }
+
+    /// Forgets the saved credentials for `app`. Fine if there are none.
+    pub fn delete(app: &str) -> Result<(), PantryError> {
+        match fs::remove_file(Self::default_path(app)?) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Saves the credentials as JSON at `path`, creating parent directories as needed.
+    /// On unix the file is only readable by the current user.
+    pub fn save_to(&self, path: impl AsRef<Path>) -> Result<(), PantryError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(0o600);
+            // mode() only applies to new files.
+            if path.exists() {
+                fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+            }
+        }
+        let mut file = options.open(path)?;
+        file.write_all(json.as_bytes())?;
+        Ok(())
+    }
+
+    /// Loads credentials saved with [PantryCredentials::save_to], `None` if there's no
+    /// file at `path`.
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Option<Self>, PantryError> {
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Saves the credentials for `app` in the OS keyring (Keychain, Credential Manager or
+    /// the Secret Service). Requires the `keyring` feature.
+    #[cfg(feature = "keyring")]
+    pub fn save_keyring(&self, app: &str) -> Result<(), PantryError> {
+        let json = serde_json::to_string(self)?;
+        keyring_entry(app)?
+            .set_password(&json)
+            .map_err(|e| PantryError::CredentialError(e.to_string()))
+    }
+
+    /// Loads what [PantryCredentials::save_keyring] saved for `app`, `None` if nothing
+    /// was. Requires the `keyring` feature.
+    #[cfg(feature = "keyring")]
+    pub fn load_keyring(app: &str) -> Result<Option<Self>, PantryError> {
+        match keyring_entry(app)?.get_password() {
+            Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(PantryError::CredentialError(e.to_string())),
+        }
+    }
+
+    /// Removes the credentials for `app` from the OS keyring. Fine if there are none.
+    /// Requires the `keyring` feature.
+    #[cfg(feature = "keyring")]
+    pub fn delete_keyring(app: &str) -> Result<(), PantryError> {
+        match keyring_entry(app)?.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(PantryError::CredentialError(e.to_string())),
+        }
+    }
+}
+
+// Keeps the key out of logs and panics.
+impl fmt::Debug for PantryCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PantryCredentials")
+            .field("user_id", &self.user_id)
+            .field("api_key", &REDACTED)
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+impl PantryClient {
+    /// This client's credentials, for saving with [PantryCredentials::save].
+    pub fn credentials(&self) -> PantryCredentials {
+        PantryCredentials::new(self.user_id, self.api_key.clone(), self.client.base_url())
+    }
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_entry(app: &str) -> Result<keyring::Entry, PantryError> {
+    keyring::Entry::new("pantry", app).map_err(|e| PantryError::CredentialError(e.to_string()))
+}
+
+/// `pantry` in the user's config directory, see [PantryCredentials::default_path].
+pub(crate) fn config_dir() -> Result<PathBuf, PantryError> {
+    let var = |name: &str| std::env::var_os(name).filter(|v| !v.is_empty());
+    let base = if cfg!(windows) {
+        var("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        var("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map(|dir| dir.join("pantry"))
+        .ok_or_else(|| PantryError::CredentialError("can't find a config directory".into()))
+}
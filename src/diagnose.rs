@@ -0,0 +1,206 @@
+//! Checking a Pantry setup, see [PantryClient::diagnose].
+//!
+//! Most problems come down to "is the daemon running" and "does my key have the
+//! permission". [DiagnosticReport] answers both:
+//!
+//! ```no_run
+//! # use pantry_rs::PantryClient;
+//! # async fn example(pantry: PantryClient) -> Result<(), Box<dyn std::error::Error>> {
+//! let report = pantry.diagnose().await;
+//! for (name, check) in report.checks() {
+//!     println!("{:<12} {}", name, check);
+//! }
+//! if !report.is_healthy() {
+//!     std::process::exit(1);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use crate::api::Transport;
+use crate::error::PantryError;
+use crate::interface::{LLMStatus, ServerInfo, UserPermissions};
+use crate::retry::RetryPolicy;
+use crate::PantryClient;
+use std::fmt;
+use std::time::Duration;
+
+/// How long each check waits for Pantry before failing.
+pub const DIAGNOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How one check went.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "status", content = "detail", rename_all = "lowercase")]
+pub enum Check {
+    Ok(String),
+    Failed(String),
+    /// Didn't apply, e.g. the unix socket when connecting to a url.
+    Skipped(String),
+}
+
+impl Check {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Check::Ok(_))
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self, Check::Failed(_))
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Check::Ok(detail) => write!(f, "ok: {}", detail),
+            Check::Failed(detail) => write!(f, "FAILED: {}", detail),
+            Check::Skipped(detail) => write!(f, "skipped: {}", detail),
+        }
+    }
+}
+
+/// What [PantryClient::diagnose] found.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct DiagnosticReport {
+    /// Whether the daemon answers on its unix socket.
+    pub socket: Check,
+    /// Whether the daemon answers over TCP.
+    pub tcp: Check,
+    /// Whether `server_info` works, and what the daemon is.
+    pub server: Check,
+    /// Whether Pantry accepts the user id and API key.
+    pub credentials: Check,
+    /// Whether listing running LLMs works.
+    pub llms: Check,
+    pub server_info: Option<ServerInfo>,
+    /// What's been granted, if the credentials are valid.
+    pub permissions: Option<UserPermissions>,
+    pub running_llms: Vec<LLMStatus>,
+}
+
+impl DiagnosticReport {
+    /// Every check, by name, in the order they were made.
+    pub fn checks(&self) -> Vec<(&'static str, &Check)> {
+        vec![
+            ("socket", &self.socket),
+            ("tcp", &self.tcp),
+            ("server", &self.server),
+            ("credentials", &self.credentials),
+            ("llms", &self.llms),
+        ]
+    }
+
+    /// Whether Pantry can be reached one way or another and nothing failed past that.
+    pub fn is_healthy(&self) -> bool {
+        (self.socket.is_ok() || self.tcp.is_ok() || self.server.is_ok())
+            && !self.server.is_failed()
+            && !self.credentials.is_failed()
+            && !self.llms.is_failed()
+    }
+}
+
+impl PantryClient {
+    /// Checks that Pantry is reachable, over the unix socket and TCP, that it accepts
+    /// this client's credentials, and which permissions they carry, listing the running
+    /// LLMs along the way.
+    ///
+    /// Never fails: whatever goes wrong ends up in the report. Each check gives up
+    /// after [DIAGNOSE_TIMEOUT] and isn't retried.
+    pub async fn diagnose(&self) -> DiagnosticReport {
+        let client = self
+            .client
+            .with_timeout(Some(DIAGNOSE_TIMEOUT))
+            .with_retry(RetryPolicy::none());
+
+        let socket = match self.client.base_url() {
+            Some(url) => Check::Skipped(format!("connecting to {}", url)),
+            None => probe_check(client.probe(Transport::UnixSocket).await),
+        };
+        let tcp = probe_check(client.probe(Transport::Tcp).await);
+
+        let (server, server_info) = match client.server_info().await {
+            Ok(info) if info.is_compatible() => (Check::Ok(describe(&info)), Some(info)),
+            Ok(info) => (
+                Check::Failed(format!("{}, not supported by this client", describe(&info))),
+                Some(info),
+            ),
+            Err(PantryError::Unsupported(e)) => (Check::Skipped(e), None),
+            Err(e) => (Check::Failed(e.to_string()), None),
+        };
+
+        let (credentials, permissions) = match client
+            .get_permissions(self.user_id, self.api_key.clone())
+            .await
+        {
+            Ok(permissions) => (
+                Check::Ok(format!("granted {}", granted(&permissions))),
+                Some(permissions),
+            ),
+            Err(e) => (Check::Failed(e.to_string()), None),
+        };
+
+        let (llms, running_llms) = match client
+            .get_running_llms(self.user_id, self.api_key.clone())
+            .await
+        {
+            Ok(running) => {
+                let ids: Vec<&str> = running.iter().map(|llm| llm.id.as_str()).collect();
+                let detail = if ids.is_empty() {
+                    "none running".to_string()
+                } else {
+                    format!("running {}", ids.join(", "))
+                };
+                (Check::Ok(detail), running)
+            }
+            Err(e) => (Check::Failed(e.to_string()), Vec::new()),
+        };
+
+        DiagnosticReport {
+            socket,
+            tcp,
+            server,
+            credentials,
+            llms,
+            server_info,
+            permissions,
+            running_llms,
+        }
+    }
+}
+
+fn probe_check(res: Result<(), PantryError>) -> Check {
+    match res {
+        Ok(()) => Check::Ok("reachable".into()),
+        Err(PantryError::Unsupported(e)) => Check::Skipped(e),
+        Err(e) => Check::Failed(e.to_string()),
+    }
+}
+
+fn describe(info: &ServerInfo) -> String {
+    format!(
+        "Pantry {}, protocol {}",
+        info.version, info.protocol_version
+    )
+}
+
+fn granted(permissions: &UserPermissions) -> String {
+    let names: Vec<&str> = [
+        (permissions.perm_superuser, "superuser"),
+        (permissions.perm_load_llm, "load_llm"),
+        (permissions.perm_unload_llm, "unload_llm"),
+        (permissions.perm_download_llm, "download_llm"),
+        (permissions.perm_session, "session"),
+        (permissions.perm_request_download, "request_download"),
+        (permissions.perm_request_load, "request_load"),
+        (permissions.perm_request_unload, "request_unload"),
+        (permissions.perm_view_llms, "view_llms"),
+        (permissions.perm_bare_model, "bare_model"),
+    ]
+    .into_iter()
+    .filter(|(granted, _)| *granted)
+    .map(|(_, name)| name)
+    .collect();
+    if names.is_empty() {
+        "no permissions".into()
+    } else {
+        names.join(", ")
+    }
+}
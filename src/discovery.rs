@@ -0,0 +1,134 @@
+//! Finding Pantry daemons on the local network over mDNS/DNS-SD.
+//!
+//! Pantry instances that accept remote connections advertise themselves as
+//! [SERVICE_TYPE]. [discover] browses for them for a while and asks each one for its
+//! [ServerInfo], so the app can offer a list instead of asking for an IP and port:
+//!
+//! ```no_run
+//! # use pantry_rs::discovery::{discover, DiscoveredPantry, DEFAULT_DISCOVERY_TIMEOUT};
+//! # use pantry_rs::UserId;
+//! # async fn example(chosen: DiscoveredPantry, user_id: UserId, api_key: String) -> Result<(), Box<dyn std::error::Error>> {
+//! for found in discover(DEFAULT_DISCOVERY_TIMEOUT).await? {
+//!     match &found.server_info {
+//!         Some(info) => println!("{} at {} (Pantry {})", found.name, found.base_url, info.version),
+//!         None => println!("{} at {} isn't answering", found.name, found.base_url),
+//!     }
+//! }
+//!
+//! let pantry = chosen.builder().login(user_id, api_key);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Only remote access goes over TCP, so a daemon that's only listening on its unix
+//! socket won't show up. A `scheme` TXT property of `https` is honored, see the
+//! `rustls` feature for connecting to those.
+use crate::api::PantryAPI;
+use crate::error::PantryError;
+use crate::interface::ServerInfo;
+use crate::retry::RetryPolicy;
+use crate::PantryClientBuilder;
+use futures::future::{self, join_all, Either};
+use futures_timer::Delay;
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// The DNS-SD service type Pantry advertises.
+pub const SERVICE_TYPE: &str = "_pantry._tcp.local.";
+
+/// A reasonable time to browse for, long enough for most networks to answer.
+pub const DEFAULT_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A Pantry instance found by [discover].
+#[derive(Clone, Debug)]
+pub struct DiscoveredPantry {
+    /// The advertised instance name, e.g. `workstation._pantry._tcp.local.`.
+    pub name: String,
+    pub hostname: String,
+    /// Url to connect to, e.g. `http://192.168.1.20:9404`.
+    pub base_url: String,
+    /// Every address the instance advertised, `base_url` uses the first IPv4 one.
+    pub addresses: Vec<IpAddr>,
+    /// What the instance answered to [PantryAPI::server_info], `None` if it didn't.
+    pub server_info: Option<ServerInfo>,
+}
+
+impl DiscoveredPantry {
+    /// An instance as advertised in `info`, without its server info. `None` if it
+    /// didn't advertise an address.
+    pub fn from_service_info(info: &ServiceInfo) -> Option<Self> {
+        let mut addresses: Vec<IpAddr> = info.get_addresses().iter().copied().collect();
+        // IPv4 first, as it's the likeliest to be reachable.
+        addresses.sort_by_key(|addr| (addr.is_ipv6(), *addr));
+        let host = match addresses.first()? {
+            IpAddr::V4(addr) => addr.to_string(),
+            IpAddr::V6(addr) => format!("[{}]", addr),
+        };
+        let scheme = match info.get_property_val_str("scheme") {
+            Some("https") => "https",
+            _ => "http",
+        };
+        Some(DiscoveredPantry {
+            name: info.get_fullname().to_string(),
+            hostname: info.get_hostname().to_string(),
+            base_url: format!("{}://{}:{}", scheme, host, info.get_port()),
+            addresses,
+            server_info: None,
+        })
+    }
+
+    /// A [PantryClientBuilder] pointed at this instance.
+    pub fn builder(&self) -> PantryClientBuilder {
+        PantryClientBuilder::new().base_url(self.base_url.clone())
+    }
+}
+
+/// Browses the local network for Pantry instances for `timeout`, then gives each of
+/// them up to `timeout` to answer [PantryAPI::server_info]. Instances that don't answer
+/// are still listed, see [DiscoveredPantry::server_info].
+///
+/// Blocks nothing; the browsing happens on a thread of its own. Fails with
+/// [PantryError::DiscoveryError] if multicast DNS can't be used at all.
+pub async fn discover(timeout: Duration) -> Result<Vec<DiscoveredPantry>, PantryError> {
+    let daemon = ServiceDaemon::new().map_err(discovery_error)?;
+    let events = daemon.browse(SERVICE_TYPE).map_err(discovery_error)?;
+
+    let mut found: Vec<DiscoveredPantry> = Vec::new();
+    let mut deadline = Delay::new(timeout);
+    loop {
+        match future::select(events.recv_async(), deadline).await {
+            Either::Left((Ok(ServiceEvent::ServiceResolved(info)), pending)) => {
+                deadline = pending;
+                let Some(pantry) = DiscoveredPantry::from_service_info(&info) else {
+                    continue;
+                };
+                // Instances are resolved again as their records get refreshed.
+                found.retain(|p| p.name != pantry.name);
+                found.push(pantry);
+            }
+            Either::Left((Ok(_), pending)) => deadline = pending,
+            Either::Left((Err(_), _)) | Either::Right(_) => break,
+        }
+    }
+    // Only the browsing has to stop, it doesn't matter if it was already done.
+    let _ = daemon.stop_browse(SERVICE_TYPE);
+    let _ = daemon.shutdown();
+
+    let infos = join_all(found.iter().map(|pantry| {
+        let mut api = PantryAPI::new(Some(pantry.base_url.clone()));
+        api.timeout = Some(timeout);
+        api.retry = RetryPolicy::none();
+        async move { api.server_info().await.ok() }
+    }))
+    .await;
+    for (pantry, info) in found.iter_mut().zip(infos) {
+        pantry.server_info = info;
+    }
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(found)
+}
+
+fn discovery_error(err: mdns_sd::Error) -> PantryError {
+    PantryError::DiscoveryError(err.to_string())
+}
@@ -1,7 +1,9 @@
 use hyper;
+use hyper::StatusCode;
 use quick_error::quick_error;
 
 use serde_json;
+use serde_json::Value;
 
 use std::convert::From;
 
@@ -16,6 +18,10 @@ quick_error! {
             display("decoding failure : {:?}", err)
             from()
         }
+        IoError (err: std::io::Error) {
+            display("io failure: {:?}", err)
+            from()
+        }
         HyperHttpError (err: hyper::http::Error) {
             display("hyper http failure: {:?}", err)
             from()
@@ -27,9 +33,222 @@ quick_error! {
         ApiError(status: hyper::StatusCode, msg: String) {
             display("API Returned {} — {}", status, msg)
         }
+        InvalidApiKey(msg: String) {
+            display("Invalid user id or API key: {}", msg)
+        }
+        PermissionDenied(msg: String) {
+            display("Permission denied: {}", msg)
+        }
+        LlmNotFound(msg: String) {
+            display("No matching LLM: {}", msg)
+        }
+        SessionNotFound(msg: String) {
+            display("No such session: {}", msg)
+        }
+        /// Something other than an LLM or session, e.g. a request or user, or the
+        /// endpoint itself.
+        NotFound(msg: String) {
+            display("Not found: {}", msg)
+        }
+        LlmNotRunning(msg: String) {
+            display("LLM is not running: {}", msg)
+        }
+        RequestRejected(msg: String) {
+            display("Request was rejected: {}", msg)
+        }
+        LlmInUse(msg: String) {
+            display("LLM in use: {}", msg)
+        }
+        InvalidRegistryEntry(msg: String) {
+            display("Invalid registry entry: {}", msg)
+        }
+        NoMatchingFile(repo: String, pattern: String) {
+            display("No file in {} matches {}", repo, pattern)
+        }
+        UnreachableUrl(msg: String) {
+            display("No download url responds: {}", msg)
+        }
+        InvalidModelFile(msg: String) {
+            display("Invalid model file: {}", msg)
+        }
+        IntegrityError(msg: String) {
+            display("Model file failed verification: {}", msg)
+        }
+        PromptError(msg: String) {
+            display("LLM failed during inference: {}", msg)
+        }
+        PromptRejected(msg: String) {
+            display("Prompt rejected by middleware: {}", msg)
+        }
+        StructuredOutputError(attempts: u32, msg: String) {
+            display("LLM output still unusable after {} tries: {}", attempts, msg)
+        }
+        InvalidToolCall(msg: String) {
+            display("LLM made a tool call that couldn't be parsed: {}", msg)
+        }
+        AgentStepLimit(steps: usize) {
+            display("Agent still calling tools after {} steps", steps)
+        }
+        ContextOverflow(needed: usize, available: usize) {
+            display("Prompt needs {} tokens, but only {} fit in the context window", needed, available)
+        }
+        Unsupported(msg: String) {
+            display("Not supported by this version of Pantry: {}", msg)
+        }
+        CredentialError(msg: String) {
+            display("Credential storage failure: {}", msg)
+        }
+        TemplateError(msg: String) {
+            display("Prompt template failure: {}", msg)
+        }
+        StoreError(msg: String) {
+            display("Conversation store failure: {}", msg)
+        }
+        DiscoveryError(msg: String) {
+            display("Network discovery failure: {}", msg)
+        }
+        FixtureError(msg: String) {
+            display("Fixture failure: {}", msg)
+        }
+        StreamError(err: String) {
+            display("Event stream failure: {}", err)
+        }
+        StreamStalled(idle: std::time::Duration) {
+            display("No event from Pantry for {:?}, the stream stalled", idle)
+        }
+        StreamLagged(missed: u64) {
+            display("Fell behind the stream, {} events were dropped", missed)
+        }
+        ClientShutDown {
+            display("The client was shut down")
+        }
+        Timeout(duration: std::time::Duration) {
+            display("Pantry did not respond within {:?}", duration)
+        }
+        // Only produced by the wasm transport.
+        FetchError(msg: String) {
+            display("fetch failure: {}", msg)
+        }
         OtherFailure(err: String) {
             display("Other Error: {:?}", err)
             from()
         }
     }
 }
+
+impl PantryError {
+    /// Turns a non-200 response into the most specific error we can.
+    ///
+    /// Pantry sends errors either as plain text or as JSON with a `message` (or
+    /// `error`) and optionally a machine readable `code`. The code wins if present,
+    /// otherwise we go by status. Anything unrecognized ends up as [PantryError::ApiError].
+    ///
+    /// Not knowing the endpoint, a 404 is [PantryError::NotFound], see
+    /// [PantryError::from_endpoint_response].
+    pub fn from_response(status: StatusCode, body: &str) -> Self {
+        Self::from_endpoint_response("", status, body)
+    }
+
+    /// [PantryError::from_response], for a response from `endpoint`, e.g.
+    /// `/load_llm`. What a 404 means depends on what the endpoint looks up: an LLM, a
+    /// session, or something else.
+    pub fn from_endpoint_response(endpoint: &str, status: StatusCode, body: &str) -> Self {
+        let json: Option<Value> = serde_json::from_str(body).ok();
+        let field = |key: &str| {
+            json.as_ref()
+                .and_then(|j| j.get(key))
+                .and_then(|v| v.as_str())
+                .map(String::from)
+        };
+        let msg = field("message")
+            .or_else(|| field("error"))
+            .unwrap_or_else(|| body.to_string());
+
+        match field("code").as_deref() {
+            Some("invalid_api_key") | Some("unauthorized") => return Self::InvalidApiKey(msg),
+            Some("permission_denied") | Some("forbidden") => return Self::PermissionDenied(msg),
+            Some("llm_not_found") => return Self::LlmNotFound(msg),
+            Some("session_not_found") => return Self::SessionNotFound(msg),
+            Some("not_found") => return Self::not_found(endpoint, msg),
+            Some("llm_not_running") => return Self::LlmNotRunning(msg),
+            Some("llm_in_use") => return Self::LlmInUse(msg),
+            Some("request_rejected") => return Self::RequestRejected(msg),
+            _ => {}
+        }
+
+        match status {
+            StatusCode::UNAUTHORIZED => Self::InvalidApiKey(msg),
+            StatusCode::FORBIDDEN => Self::PermissionDenied(msg),
+            StatusCode::NOT_FOUND => Self::not_found(endpoint, msg),
+            StatusCode::CONFLICT => Self::LlmNotRunning(msg),
+            _ => Self::ApiError(status, msg),
+        }
+    }
+
+    fn not_found(endpoint: &str, msg: String) -> Self {
+        match endpoint.trim_start_matches('/') {
+            "get_llm_status"
+            | "load_llm"
+            | "load_llm_flex"
+            | "unload_llm"
+            | "download_llm"
+            | "get_or_download_llm"
+            | "delete_llm"
+            | "request_load"
+            | "request_unload"
+            | "request_download"
+            | "request_delete"
+            | "create_session"
+            | "create_session_id"
+            | "create_session_flex"
+            | "bare_model"
+            | "bare_model_flex"
+            | "bare_model_lease"
+            | "export_bare_model"
+            | "list_adapters"
+            | "download_adapter" => Self::LlmNotFound(msg),
+            "load_session_id"
+            | "fork_session"
+            | "get_session_history"
+            | "delete_session"
+            | "interrupt_session"
+            | "prompt_session_stream"
+            | "transcribe_stream" => Self::SessionNotFound(msg),
+            _ => Self::NotFound(msg),
+        }
+    }
+
+    /// Whether the same call might succeed if made again: Pantry was unreachable,
+    /// too slow, or reported a temporary server-side problem.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            PantryError::HyperError(e) => {
+                e.is_connect() || e.is_closed() || e.is_incomplete_message()
+            }
+            // fetch doesn't tell network failures apart from others.
+            PantryError::Timeout(_) | PantryError::FetchError(_) => true,
+            PantryError::ApiError(status, _) => matches!(
+                *status,
+                StatusCode::TOO_MANY_REQUESTS
+                    | StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            ),
+            _ => false,
+        }
+    }
+
+    /// Whether Pantry can't have acted on the call: it couldn't be connected to, or
+    /// turned the call away unhandled. Other retryable failures, like timeouts, may
+    /// come after the call already ran.
+    pub(crate) fn never_reached(&self) -> bool {
+        match self {
+            PantryError::HyperError(e) => e.is_connect(),
+            PantryError::ApiError(status, _) => matches!(
+                *status,
+                StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+            ),
+            _ => false,
+        }
+    }
+}
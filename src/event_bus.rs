@@ -0,0 +1,85 @@
+//! A single place to hear about everything a client does.
+//!
+//! Every [crate::PantryAPI] has a [tokio::sync::broadcast] channel it publishes
+//! [ClientEvent]s on: requests being made and decided, download progress, sessions
+//! being created, prompts starting and finishing, and calls failing. GUIs can keep a
+//! status bar or activity log up to date from one subscription, instead of
+//! instrumenting every call site:
+//!
+//! ```no_run
+//! # use pantry_rs::{ClientEvent, PantryClient};
+//! # struct Bar;
+//! # impl Bar { fn set(&self, _: f32) {} }
+//! # fn example(pantry: PantryClient, bar: Bar, mut log: Vec<String>) {
+//! let mut events = pantry.client_events();
+//! tokio::spawn(async move {
+//!     while let Ok(event) = events.recv().await {
+//!         match event {
+//!             ClientEvent::DownloadProgress { progress, .. } => bar.set(progress.percent),
+//!             ClientEvent::Error { endpoint, message } => log.push(format!("{}: {}", endpoint, message)),
+//!             _ => {}
+//!         }
+//!     }
+//! });
+//! # }
+//! ```
+//!
+//! Copies of a client share the channel. Events are only kept for receivers that exist
+//! when they're published, and a receiver that falls more than [DEFAULT_EVENT_BUS_CAPACITY] events
+//! behind skips the oldest ones, see [broadcast::Receiver::recv].
+use crate::ids::{LlmUuid, SessionId};
+use crate::interface::{DownloadProgress, FinishReason, UserRequestStatus};
+use tokio::sync::broadcast;
+
+/// Events buffered for each receiver of a client's event bus.
+pub const DEFAULT_EVENT_BUS_CAPACITY: usize = 256;
+
+/// Something a client did, see the [module docs](self).
+#[derive(Clone, Debug)]
+pub enum ClientEvent {
+    /// A request went to the system owner, e.g. from
+    /// [crate::PantryClient::request_download_llm].
+    RequestSubmitted(UserRequestStatus),
+    /// [crate::PantryClient::await_request] saw a request get accepted or denied; check
+    /// [UserRequestStatus::accepted].
+    RequestDecided(UserRequestStatus),
+    /// A download moved along, as seen by [crate::PantryClient::await_download] or
+    /// [crate::PantryClient::download_progress].
+    DownloadProgress {
+        llm_uuid: LlmUuid,
+        progress: DownloadProgress,
+    },
+    SessionCreated {
+        session_id: SessionId,
+        llm_uuid: LlmUuid,
+    },
+    /// [crate::LLMSession::prompt_session] sent a prompt.
+    PromptStarted {
+        session_id: SessionId,
+        llm_uuid: LlmUuid,
+    },
+    /// A prompt's stream ended, whether it completed or not.
+    PromptFinished {
+        session_id: SessionId,
+        llm_uuid: LlmUuid,
+        reason: FinishReason,
+        tokens: usize,
+    },
+    /// A call to Pantry failed, in transport or with an error status.
+    Error { endpoint: String, message: String },
+}
+
+/// A fresh channel for [crate::PantryAPI::event_bus].
+pub(crate) fn channel() -> broadcast::Sender<ClientEvent> {
+    broadcast::channel(DEFAULT_EVENT_BUS_CAPACITY).0
+}
+
+/// Publishes `event` on `bus`, if there's one and anyone is listening.
+pub(crate) fn publish(
+    bus: Option<&broadcast::Sender<ClientEvent>>,
+    event: impl FnOnce() -> ClientEvent,
+) {
+    if let Some(bus) = bus.filter(|bus| bus.receiver_count() > 0) {
+        let _ = bus.send(event());
+    }
+}
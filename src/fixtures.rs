@@ -0,0 +1,283 @@
+//! Recording calls to Pantry and replaying them later, for regression tests.
+//!
+//! Record a session against a real daemon once:
+//!
+//! ```no_run
+//! # use pantry_rs::{Fixtures, PantryClient, PantryError, UserId};
+//! # async fn run_my_app(_: &PantryClient) -> Result<(), PantryError> { Ok(()) }
+//! # async fn example(user_id: UserId, api_key: String) -> Result<(), Box<dyn std::error::Error>> {
+//! let recording = Fixtures::record("tests/fixtures/chat.json");
+//! let pantry = PantryClient::builder()
+//!     .fixtures(recording.clone())
+//!     .login(user_id, api_key);
+//! run_my_app(&pantry).await?;
+//! recording.save()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Then run the same code against the recording, with no daemon or models installed:
+//!
+//! ```no_run
+//! # use pantry_rs::{Fixtures, PantryClient, PantryError, UserId};
+//! # async fn run_my_app(_: &PantryClient) -> Result<(), PantryError> { Ok(()) }
+//! # async fn example(user_id: UserId, api_key: String) -> Result<(), Box<dyn std::error::Error>> {
+//! let pantry = PantryClient::builder()
+//!     .fixtures(Fixtures::replay("tests/fixtures/chat.json")?)
+//!     .login(user_id, api_key);
+//! run_my_app(&pantry).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Every call is recorded as an [Exchange], streamed responses included, in the order
+//! the calls were made. Nothing is written until [Fixtures::save]. Secrets are redacted
+//! as in [crate::logging], so fixtures are safe to commit.
+//!
+//! Replay answers each call with the first unused exchange for the same endpoint and
+//! request body, falling back to the first unused one for the same endpoint. That keeps
+//! recordings usable when ids the client picks itself differ between runs. A call with
+//! nothing left to replay fails with [PantryError::FixtureError].
+use crate::error::PantryError;
+use crate::logging::redact;
+use futures::future::Future;
+use futures::stream::{self, StreamExt};
+use hyper::body::{Bytes, HttpBody};
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Response, StatusCode};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// One recorded call.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Exchange {
+    /// E.g. `"/create_session"`.
+    pub endpoint: String,
+    /// Request body, secrets redacted.
+    pub request: Value,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Response body as sent, event streams included. Secrets are redacted from JSON
+    /// bodies.
+    pub body: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Record,
+    Replay,
+}
+
+#[derive(Debug)]
+struct Tape {
+    exchanges: Vec<Exchange>,
+    // Which exchanges replay has handed out already, or when recording, which
+    // responses have been read to the end.
+    used: Vec<bool>,
+}
+
+/// A fixture file being recorded or replayed, see the [module docs](self).
+///
+/// Hand it to [crate::PantryClientBuilder::fixtures].
+#[derive(Debug)]
+pub struct Fixtures {
+    path: PathBuf,
+    mode: Mode,
+    tape: Mutex<Tape>,
+}
+
+impl Fixtures {
+    /// Records every call, for [Fixtures::save] to write into `path`.
+    pub fn record(path: impl AsRef<Path>) -> Arc<Self> {
+        Arc::new(Fixtures {
+            path: path.as_ref().to_path_buf(),
+            mode: Mode::Record,
+            tape: Mutex::new(Tape {
+                exchanges: Vec::new(),
+                used: Vec::new(),
+            }),
+        })
+    }
+
+    /// Answers calls from the recording in `path` instead of Pantry.
+    ///
+    /// Fails if the file can't be read or isn't a recording.
+    pub fn replay(path: impl AsRef<Path>) -> Result<Arc<Self>, PantryError> {
+        let path = path.as_ref().to_path_buf();
+        let text = std::fs::read_to_string(&path).map_err(|e| {
+            PantryError::FixtureError(format!("can't read {}: {}", path.display(), e))
+        })?;
+        let exchanges: Vec<Exchange> = serde_json::from_str(&text).map_err(|e| {
+            PantryError::FixtureError(format!("{} isn't a recording: {}", path.display(), e))
+        })?;
+        Ok(Arc::new(Fixtures {
+            path,
+            mode: Mode::Replay,
+            tape: Mutex::new(Tape {
+                used: vec![false; exchanges.len()],
+                exchanges,
+            }),
+        }))
+    }
+
+    /// The fixture file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Everything recorded so far, or everything there is to replay. Responses still
+    /// being read have the body received so far missing.
+    pub fn exchanges(&self) -> Vec<Exchange> {
+        self.tape.lock().unwrap().exchanges.clone()
+    }
+
+    /// Whether every recorded exchange has been replayed. Handy for asserting that
+    /// the code under test still makes all the calls it used to.
+    pub fn is_exhausted(&self) -> bool {
+        self.tape.lock().unwrap().used.iter().all(|used| *used)
+    }
+
+    /// Writes the recording to the fixture file, replacing whatever is there. Calls
+    /// whose responses are still being read are left out. Does nothing when replaying.
+    pub fn save(&self) -> Result<(), PantryError> {
+        if self.mode == Mode::Replay {
+            return Ok(());
+        }
+        let tape = self.tape.lock().unwrap();
+        let finished: Vec<&Exchange> = tape
+            .exchanges
+            .iter()
+            .zip(&tape.used)
+            .filter(|(_, done)| **done)
+            .map(|(exchange, _)| exchange)
+            .collect();
+        let json = serde_json::to_string_pretty(&finished)?;
+        std::fs::write(&self.path, json).map_err(|e| {
+            PantryError::FixtureError(format!("can't write {}: {}", self.path.display(), e))
+        })
+    }
+
+    /// Makes a call: replays it, or sends it with `send` and records the response.
+    pub(crate) async fn exchange<F>(
+        self: &Arc<Self>,
+        endpoint: &str,
+        body: &str,
+        send: F,
+    ) -> Result<Response<Body>, PantryError>
+    where
+        F: Future<Output = Result<Response<Body>, PantryError>>,
+    {
+        let request = redacted_request(body);
+        match self.mode {
+            Mode::Replay => self.next(endpoint, &request),
+            Mode::Record => {
+                let resp = send.await?;
+                Ok(self.tee(endpoint, request, resp))
+            }
+        }
+    }
+
+    fn next(&self, endpoint: &str, request: &Value) -> Result<Response<Body>, PantryError> {
+        let mut tape = self.tape.lock().unwrap();
+        let unused = |tape: &Tape, exact: bool| {
+            tape.exchanges.iter().enumerate().position(|(i, exchange)| {
+                !tape.used[i]
+                    && exchange.endpoint == endpoint
+                    && (!exact || exchange.request == *request)
+            })
+        };
+        let index = unused(&tape, true)
+            .or_else(|| unused(&tape, false))
+            .ok_or_else(|| {
+                PantryError::FixtureError(format!(
+                    "nothing left to replay for {} in {}",
+                    endpoint,
+                    self.path.display()
+                ))
+            })?;
+        tape.used[index] = true;
+
+        let exchange = &tape.exchanges[index];
+        let mut resp = Response::builder().status(
+            StatusCode::from_u16(exchange.status)
+                .map_err(|e| PantryError::FixtureError(e.to_string()))?,
+        );
+        if let Some(content_type) = &exchange.content_type {
+            resp = resp.header(CONTENT_TYPE, content_type);
+        }
+        Ok(resp.body(Body::from(exchange.body.clone()))?)
+    }
+
+    /// Hands `resp` on, recording its body once it's been read. Streams are passed
+    /// through as they arrive, and recorded as far as they got if dropped early.
+    fn tee(
+        self: &Arc<Self>,
+        endpoint: &str,
+        request: Value,
+        resp: Response<Body>,
+    ) -> Response<Body> {
+        let (parts, body) = resp.into_parts();
+        let exchange = Exchange {
+            endpoint: endpoint.to_string(),
+            request,
+            status: parts.status.as_u16(),
+            content_type: parts
+                .headers
+                .get(CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from),
+            body: String::new(),
+        };
+        // Taking its place now keeps the recording in call order.
+        let index = {
+            let mut tape = self.tape.lock().unwrap();
+            tape.exchanges.push(exchange);
+            tape.used.push(false);
+            tape.exchanges.len() - 1
+        };
+        let recording = Recording {
+            fixtures: self.clone(),
+            index,
+            received: Vec::new(),
+        };
+        let chunks = stream::unfold((body, recording), |(mut body, mut recording)| async move {
+            match body.data().await {
+                Some(Ok(chunk)) => {
+                    recording.received.extend_from_slice(&chunk);
+                    Some((Ok::<Bytes, hyper::Error>(chunk), (body, recording)))
+                }
+                Some(Err(e)) => Some((Err(e), (body, recording))),
+                // Dropping the recording finishes it.
+                None => None,
+            }
+        });
+        Response::from_parts(parts, Body::wrap_stream(chunks.boxed()))
+    }
+}
+
+/// A response body being recorded into the tape at `index`, finished when dropped.
+struct Recording {
+    fixtures: Arc<Fixtures>,
+    index: usize,
+    received: Vec<u8>,
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        let body = String::from_utf8_lossy(&self.received);
+        let body = match serde_json::from_str::<Value>(&body) {
+            Ok(_) => redact(&body),
+            Err(_) => body.into_owned(),
+        };
+        let mut tape = self.fixtures.tape.lock().unwrap();
+        tape.exchanges[self.index].body = body;
+        tape.used[self.index] = true;
+    }
+}
+
+fn redacted_request(body: &str) -> Value {
+    let redacted = redact(body);
+    serde_json::from_str(&redacted).unwrap_or(Value::String(redacted))
+}
@@ -0,0 +1,202 @@
+//! Working with Pantry on several machines at once.
+//!
+//! A [PantryFleet] holds a [PantryClient] per host, under a label of your choosing. It
+//! lists LLMs across all of them, and creates sessions on whichever host has an LLM
+//! that fits:
+//!
+//! ```no_run
+//! # use pantry_rs::{LLMFilter, PantryClient, PantryFleet, UserId};
+//! # use std::collections::HashMap;
+//! # async fn example(user_id: UserId, api_key: String, ws_user: UserId, ws_key: String, prompt: String) -> Result<(), Box<dyn std::error::Error>> {
+//! let fleet = PantryFleet::new()
+//!     .host("desktop", PantryClient::login(user_id, api_key, None))
+//!     .host("workstation", PantryClient::login(ws_user, ws_key, Some("http://10.0.0.7:9404".into())));
+//!
+//! let listing = fleet.get_running_llms().await;
+//! for hosted in &listing.llms {
+//!     println!("{}: {}", hosted.host, hosted.llm.name);
+//! }
+//! for failed in &listing.errors {
+//!     println!("{} failed: {}", failed.host, failed.error);
+//! }
+//!
+//! let hosted = fleet
+//!     .create_session_flex(Some(LLMFilter::new().tag("coding")), None, HashMap::new())
+//!     .await?;
+//! println!("prompting on {}", hosted.host);
+//! let reply = hosted.session.prompt_and_collect(prompt, HashMap::new()).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Hosts that fail, e.g. because they're off or refuse the client, don't stop the
+//! others from being used. Listings report their errors next to what the rest
+//! answered, and sessions are created on the hosts that answered. Creating one only
+//! fails that way if none of the hosts answer.
+use crate::api::{self, LLMFilter, LLMPreference};
+use crate::error::PantryError;
+use crate::interface::LLMStatus;
+use crate::{LLMSession, PantryClient};
+use futures::future::join_all;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+
+/// An LLM, and the host it's on.
+#[derive(Clone, Debug)]
+pub struct HostedLLM {
+    pub host: String,
+    pub llm: LLMStatus,
+}
+
+/// Why a host is missing from a [FleetListing].
+#[derive(Debug)]
+pub struct HostError {
+    pub host: String,
+    pub error: PantryError,
+}
+
+/// LLMs across the fleet, and the hosts that couldn't list theirs.
+#[derive(Debug, Default)]
+pub struct FleetListing {
+    pub llms: Vec<HostedLLM>,
+    pub errors: Vec<HostError>,
+}
+
+/// A session, and the host it's on.
+pub struct HostedSession {
+    pub host: String,
+    pub session: LLMSession,
+}
+
+/// Clients for several Pantry hosts, see the [module docs](self).
+#[derive(Clone, Debug, Default)]
+pub struct PantryFleet {
+    hosts: Vec<(String, PantryClient)>,
+}
+
+impl PantryFleet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `client` under `label`, replacing any host with the same label.
+    pub fn host(mut self, label: impl Into<String>, client: PantryClient) -> Self {
+        self.add_host(label, client);
+        self
+    }
+
+    /// Adds `client` under `label`, replacing any host with the same label.
+    pub fn add_host(&mut self, label: impl Into<String>, client: PantryClient) {
+        let label = label.into();
+        match self.hosts.iter_mut().find(|(l, _)| *l == label) {
+            Some((_, existing)) => *existing = client,
+            None => self.hosts.push((label, client)),
+        }
+    }
+
+    /// Removes the host under `label`, returning its client.
+    pub fn remove_host(&mut self, label: &str) -> Option<PantryClient> {
+        let index = self.hosts.iter().position(|(l, _)| l == label)?;
+        Some(self.hosts.remove(index).1)
+    }
+
+    /// The client for the host under `label`.
+    pub fn client(&self, label: &str) -> Option<&PantryClient> {
+        self.hosts
+            .iter()
+            .find(|(l, _)| l == label)
+            .map(|(_, client)| client)
+    }
+
+    /// Labels of the hosts, in the order they were added.
+    pub fn labels(&self) -> Vec<&str> {
+        self.hosts.iter().map(|(label, _)| label.as_str()).collect()
+    }
+
+    /// Every host's downloaded LLMs, see [PantryClient::get_available_llms].
+    pub async fn get_available_llms(&self) -> FleetListing {
+        self.gather(|client| client.get_available_llms()).await
+    }
+
+    /// Every host's running LLMs, see [PantryClient::get_running_llms].
+    pub async fn get_running_llms(&self) -> FleetListing {
+        self.gather(|client| client.get_running_llms()).await
+    }
+
+    /// Like [PantryFleet::get_running_llms], picking the LLM a session would be created
+    /// with by [PantryFleet::create_session_flex].
+    ///
+    /// Fails with the first host's error if no host answers, and with
+    /// [PantryError::LlmNotFound] if nothing passes the filter.
+    pub async fn select_running_llm(
+        &self,
+        filter: Option<LLMFilter>,
+        preference: Option<LLMPreference>,
+    ) -> Result<HostedLLM, PantryError> {
+        let FleetListing {
+            llms: hosted,
+            errors,
+        } = self.get_running_llms().await;
+        if !errors.is_empty() && errors.len() == self.hosts.len() {
+            return Err(errors.into_iter().next().unwrap().error);
+        }
+        let llms: Vec<LLMStatus> = hosted.iter().map(|h| h.llm.clone()).collect();
+        let chosen =
+            api::select_llm(&llms, filter.as_ref(), preference.as_ref()).ok_or_else(|| {
+                PantryError::LlmNotFound("no LLM in the fleet passes the filter".into())
+            })?;
+        let index = llms
+            .iter()
+            .position(|llm| std::ptr::eq(llm, chosen))
+            .expect("chosen from llms");
+        Ok(hosted[index].clone())
+    }
+
+    /// Creates a session on whichever host runs the LLM that best fits `filter` and
+    /// `preference`, ranking the running LLMs of all hosts together. See
+    /// [PantryClient::create_session_flex].
+    ///
+    /// Fails like [PantryFleet::select_running_llm].
+    pub async fn create_session_flex(
+        &self,
+        filter: Option<LLMFilter>,
+        preference: Option<LLMPreference>,
+        parameters: HashMap<String, Value>,
+    ) -> Result<HostedSession, PantryError> {
+        let chosen = self.select_running_llm(filter, preference).await?;
+        let client = self
+            .client(&chosen.host)
+            .expect("hosts are only removed through &mut self");
+        let session = client
+            .create_session_id(chosen.llm.uuid, parameters)
+            .await?;
+        Ok(HostedSession {
+            host: chosen.host,
+            session,
+        })
+    }
+
+    /// Calls `list` on every host at once, labelling what they answer with.
+    async fn gather<'a, F, Fut>(&'a self, list: F) -> FleetListing
+    where
+        F: Fn(&'a PantryClient) -> Fut,
+        Fut: Future<Output = Result<Vec<LLMStatus>, PantryError>>,
+    {
+        let results = join_all(self.hosts.iter().map(|(_, client)| list(client))).await;
+        let mut listing = FleetListing::default();
+        for ((label, _), result) in self.hosts.iter().zip(results) {
+            match result {
+                Ok(llms) => listing.llms.extend(llms.into_iter().map(|llm| HostedLLM {
+                    host: label.clone(),
+                    llm,
+                })),
+                Err(error) => listing.errors.push(HostError {
+                    host: label.clone(),
+                    error,
+                }),
+            }
+        }
+        listing
+    }
+}
@@ -0,0 +1,361 @@
+//! Reading metadata out of local GGUF model files. Requires the `gguf` feature.
+//!
+//! Only the header is read, so this is cheap even for multi-gigabyte models.
+//!
+//! ```no_run
+//! # use pantry_rs::api::CapabilityType;
+//! # use pantry_rs::interface::LLMRegistryEntry;
+//! # fn main() -> Result<(), pantry_rs::PantryError> {
+//! let entry = LLMRegistryEntry::from_gguf("/models/openchat_v3.2.Q4_0.gguf")?
+//!     .capability(CapabilityType::Assistant, 3)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::error::PantryError;
+use crate::interface::{LLMConnectorType, LLMRegistryEntry};
+use crate::registry::LLMRegistryEntryBuilder;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+const GGUF_MAGIC: &[u8; 4] = b"GGUF";
+// Legacy GGML containers, which don't carry any metadata worth reading.
+const GGML_MAGICS: [&[u8; 4]; 3] = [b"lmgg", b"fmgg", b"tjgg"];
+
+// Guards against garbage lengths making us allocate the world.
+const MAX_STRING_LEN: u64 = 1 << 24;
+const MAX_ARRAY_LEN: u64 = 1 << 24;
+
+/// A metadata value from a GGUF header.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GgufValue {
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    U64(u64),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    Bool(bool),
+    String(String),
+    Array(Vec<GgufValue>),
+}
+
+impl GgufValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            GgufValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Any non-negative integer value, widened.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            GgufValue::U8(v) => Some(v as u64),
+            GgufValue::U16(v) => Some(v as u64),
+            GgufValue::U32(v) => Some(v as u64),
+            GgufValue::U64(v) => Some(v),
+            GgufValue::I8(v) => u64::try_from(v).ok(),
+            GgufValue::I16(v) => u64::try_from(v).ok(),
+            GgufValue::I32(v) => u64::try_from(v).ok(),
+            GgufValue::I64(v) => u64::try_from(v).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// What we could learn from a GGUF header.
+#[derive(Debug, Clone)]
+pub struct GgufMetadata {
+    /// GGUF format version, 1 through 3.
+    pub version: u32,
+    /// `general.architecture`, e.g. "llama".
+    pub architecture: Option<String>,
+    /// `general.name`.
+    pub name: Option<String>,
+    /// `general.license`.
+    pub license: Option<String>,
+    /// `general.description`.
+    pub description: Option<String>,
+    /// `general.organization` or `general.author`.
+    pub organization: Option<String>,
+    /// `{architecture}.context_length`, the context the model was trained with.
+    pub context_length: Option<u64>,
+    /// Total number of weights, summed over all tensors.
+    pub parameter_count: u64,
+    /// Quantization, e.g. "Q4_0", from `general.file_type`.
+    pub quantization: Option<String>,
+    pub tensor_count: u64,
+    /// Every key/value pair in the header.
+    pub metadata: HashMap<String, GgufValue>,
+}
+
+impl GgufMetadata {
+    /// Reads the header of the GGUF file at `path`.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, PantryError> {
+        let file = File::open(path)?;
+        Self::from_reader(BufReader::new(file))
+    }
+
+    /// Reads a GGUF header from the start of `reader`.
+    pub fn from_reader(reader: impl Read) -> Result<Self, PantryError> {
+        let mut r = GgufReader {
+            inner: reader,
+            version: 0,
+        };
+
+        let mut magic = [0u8; 4];
+        r.inner.read_exact(&mut magic)?;
+        if GGML_MAGICS.contains(&&magic) {
+            return Err(invalid(
+                "this is a legacy GGML file, which has no metadata to read. Convert it to GGUF or fill in the entry by hand",
+            ));
+        }
+        if &magic != GGUF_MAGIC {
+            return Err(invalid("not a GGUF file"));
+        }
+        r.version = r.u32()?;
+        if !(1..=3).contains(&r.version) {
+            return Err(invalid(&format!("unsupported GGUF version {}", r.version)));
+        }
+
+        let tensor_count = r.count()?;
+        let kv_count = r.count()?;
+        let mut metadata = HashMap::new();
+        for _ in 0..kv_count {
+            let key = r.string()?;
+            let value_type = r.u32()?;
+            let value = r.value(value_type)?;
+            metadata.insert(key, value);
+        }
+
+        let mut parameter_count: u64 = 0;
+        let mut tensor_types: HashMap<u32, u64> = HashMap::new();
+        for _ in 0..tensor_count {
+            r.string()?;
+            let n_dims = r.u32()?;
+            let mut elements: u64 = 1;
+            for _ in 0..n_dims {
+                elements = elements.saturating_mul(r.count()?);
+            }
+            let tensor_type = r.u32()?;
+            r.u64()?; // offset
+            parameter_count = parameter_count.saturating_add(elements);
+            *tensor_types.entry(tensor_type).or_default() += elements;
+        }
+
+        let text = |key: &str| metadata.get(key).and_then(|v| v.as_str()).map(String::from);
+        let architecture = text("general.architecture");
+        let context_length = architecture
+            .as_ref()
+            .and_then(|arch| metadata.get(&format!("{}.context_length", arch)))
+            .and_then(|v| v.as_u64());
+        // Older files lack general.file_type, fall back to the most common tensor type.
+        let quantization = metadata
+            .get("general.file_type")
+            .and_then(|v| v.as_u64())
+            .and_then(file_type_name)
+            .or_else(|| {
+                tensor_types
+                    .iter()
+                    .max_by_key(|(_, elements)| **elements)
+                    .and_then(|(t, _)| tensor_type_name(*t))
+            })
+            .map(String::from);
+
+        Ok(GgufMetadata {
+            version: r.version,
+            name: text("general.name"),
+            license: text("general.license"),
+            description: text("general.description"),
+            organization: text("general.organization").or_else(|| text("general.author")),
+            architecture,
+            context_length,
+            parameter_count,
+            quantization,
+            tensor_count,
+            metadata,
+        })
+    }
+
+    /// Parameter count the way people write it, e.g. "7B" or "350M".
+    pub fn parameter_size(&self) -> String {
+        let params = self.parameter_count as f64;
+        if params >= 1e9 {
+            format!("{:.0}B", params / 1e9)
+        } else {
+            format!("{:.0}M", params / 1e6)
+        }
+    }
+}
+
+impl LLMRegistryEntry {
+    /// Starts a registry entry for a local GGUF file, filled in from its header.
+    ///
+    /// The url points at the file. Parameter size and quantization end up in the
+    /// tags, the context length in `config.context_length`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` — Path to the model file.
+    pub fn from_gguf(path: impl AsRef<Path>) -> Result<LLMRegistryEntryBuilder, PantryError> {
+        let path = path.as_ref();
+        let meta = GgufMetadata::read(path)?;
+        let path = path.canonicalize()?;
+        let id = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut builder = LLMRegistryEntry::builder(id, LLMConnectorType::LLMrs)
+            .url(format!("file://{}", path.display()))
+            .local(true)
+            .tag(meta.parameter_size());
+        if let Some(name) = &meta.name {
+            builder = builder.name(name.as_str());
+        }
+        if let Some(license) = &meta.license {
+            builder = builder.license(license.as_str());
+        }
+        if let Some(description) = &meta.description {
+            builder = builder.description(description.as_str());
+        }
+        if let Some(organization) = &meta.organization {
+            builder = builder.organization(organization.as_str());
+        }
+        if let Some(arch) = &meta.architecture {
+            builder = builder
+                .family_id(arch.as_str())
+                .config("model_architecture", arch.as_str());
+        }
+        if let Some(quantization) = &meta.quantization {
+            builder = builder.tag(quantization.as_str());
+        }
+        if let Some(context_length) = meta.context_length {
+            builder = builder.config("context_length", context_length);
+        }
+        Ok(builder)
+    }
+}
+
+fn invalid(msg: &str) -> PantryError {
+    PantryError::InvalidModelFile(msg.into())
+}
+
+struct GgufReader<R> {
+    inner: R,
+    version: u32,
+}
+
+impl<R: Read> GgufReader<R> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], PantryError> {
+        let mut buf = [0u8; N];
+        self.inner.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn u32(&mut self) -> Result<u32, PantryError> {
+        Ok(u32::from_le_bytes(self.bytes()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, PantryError> {
+        Ok(u64::from_le_bytes(self.bytes()?))
+    }
+
+    /// Lengths and counts were 32 bit in version 1, 64 bit since.
+    fn count(&mut self) -> Result<u64, PantryError> {
+        if self.version == 1 {
+            Ok(self.u32()? as u64)
+        } else {
+            self.u64()
+        }
+    }
+
+    fn string(&mut self) -> Result<String, PantryError> {
+        let len = self.count()?;
+        if len > MAX_STRING_LEN {
+            return Err(invalid("string too long"));
+        }
+        let mut buf = vec![0u8; len as usize];
+        self.inner.read_exact(&mut buf)?;
+        Ok(String::from_utf8_lossy(&buf).into_owned())
+    }
+
+    fn value(&mut self, value_type: u32) -> Result<GgufValue, PantryError> {
+        Ok(match value_type {
+            0 => GgufValue::U8(u8::from_le_bytes(self.bytes()?)),
+            1 => GgufValue::I8(i8::from_le_bytes(self.bytes()?)),
+            2 => GgufValue::U16(u16::from_le_bytes(self.bytes()?)),
+            3 => GgufValue::I16(i16::from_le_bytes(self.bytes()?)),
+            4 => GgufValue::U32(self.u32()?),
+            5 => GgufValue::I32(i32::from_le_bytes(self.bytes()?)),
+            6 => GgufValue::F32(f32::from_le_bytes(self.bytes()?)),
+            7 => GgufValue::Bool(self.bytes::<1>()?[0] != 0),
+            8 => GgufValue::String(self.string()?),
+            9 => {
+                let item_type = self.u32()?;
+                let len = self.count()?;
+                if len > MAX_ARRAY_LEN {
+                    return Err(invalid("array too long"));
+                }
+                let mut items = Vec::with_capacity(len.min(4096) as usize);
+                for _ in 0..len {
+                    items.push(self.value(item_type)?);
+                }
+                GgufValue::Array(items)
+            }
+            10 => GgufValue::U64(self.u64()?),
+            11 => GgufValue::I64(i64::from_le_bytes(self.bytes()?)),
+            12 => GgufValue::F64(f64::from_le_bytes(self.bytes()?)),
+            other => return Err(invalid(&format!("unknown metadata type {}", other))),
+        })
+    }
+}
+
+/// Names for llama.cpp's `llama_ftype`.
+fn file_type_name(file_type: u64) -> Option<&'static str> {
+    Some(match file_type {
+        0 => "F32",
+        1 => "F16",
+        2 => "Q4_0",
+        3 => "Q4_1",
+        7 => "Q8_0",
+        8 => "Q5_0",
+        9 => "Q5_1",
+        10 => "Q2_K",
+        11 => "Q3_K_S",
+        12 => "Q3_K_M",
+        13 => "Q3_K_L",
+        14 => "Q4_K_S",
+        15 => "Q4_K_M",
+        16 => "Q5_K_S",
+        17 => "Q5_K_M",
+        18 => "Q6_K",
+        _ => return None,
+    })
+}
+
+/// Names for ggml's tensor types.
+fn tensor_type_name(tensor_type: u32) -> Option<&'static str> {
+    Some(match tensor_type {
+        0 => "F32",
+        1 => "F16",
+        2 => "Q4_0",
+        3 => "Q4_1",
+        6 => "Q5_0",
+        7 => "Q5_1",
+        8 => "Q8_0",
+        10 => "Q2_K",
+        11 => "Q3_K",
+        12 => "Q4_K",
+        13 => "Q5_K",
+        14 => "Q6_K",
+        _ => return None,
+    })
+}
@@ -0,0 +1,238 @@
+//! Filling in [LLMRegistryEntry]s from models hosted on [Hugging Face](https://huggingface.co).
+use crate::error::PantryError;
+use crate::interface::{LLMConnectorType, LLMRegistryEntry};
+use crate::registry::LLMRegistryEntryBuilder;
+use hyper::{Body, Client, Request, StatusCode};
+use hyper_tls::HttpsConnector;
+use serde::Deserialize;
+use serde_json::Value;
+
+pub const HUGGINGFACE_URL: &str = "https://huggingface.co";
+
+/// The parts of `GET /api/models/{repo}` we use.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HuggingFaceModel {
+    pub id: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default, rename = "cardData")]
+    pub card_data: Option<Value>,
+    #[serde(default)]
+    pub config: Option<Value>,
+    #[serde(default)]
+    pub siblings: Vec<HuggingFaceFile>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HuggingFaceFile {
+    pub rfilename: String,
+}
+
+impl LLMRegistryEntry {
+    /// Looks up a model repository on Hugging Face and fills in a registry entry for
+    /// one of its files: download url, license, description from the model card, and
+    /// the `model_architecture` config.
+    ///
+    /// Returns a builder so anything we couldn't figure out (or got wrong) can still
+    /// be set before building.
+    ///
+    /// ```no_run
+    /// # use pantry_rs::api::CapabilityType;
+    /// # use pantry_rs::interface::LLMRegistryEntry;
+    /// # use pantry_rs::PantryClient;
+    /// # async fn example(pantry: PantryClient) -> Result<(), Box<dyn std::error::Error>> {
+    /// let entry = LLMRegistryEntry::from_huggingface("TheBloke/OpenChat_v3.2-GGML", "*q4_0.bin")
+    ///     .await?
+    ///     .capability(CapabilityType::Assistant, 3)
+    ///     .build()?;
+    /// let req = pantry.request_download_llm(entry).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `repo` — Repository id, `owner/name`.
+    /// * `file_pattern` — Which file of the repository to download. `*` matches any run
+    ///   of characters, `?` any single one. If several files match, the first in
+    ///   alphabetical order wins. Fails with [PantryError::NoMatchingFile] if none do.
+    pub async fn from_huggingface(
+        repo: &str,
+        file_pattern: &str,
+    ) -> Result<LLMRegistryEntryBuilder, PantryError> {
+        let client = Client::builder().build::<_, Body>(HttpsConnector::new());
+
+        let info = get(&client, format!("{}/api/models/{}", HUGGINGFACE_URL, repo)).await?;
+        let info: HuggingFaceModel = serde_json::from_str(&info)?;
+        // Not every repository has a model card, that's fine.
+        let readme = get(
+            &client,
+            format!("{}/{}/raw/main/README.md", HUGGINGFACE_URL, repo),
+        )
+        .await
+        .ok();
+
+        LLMRegistryEntry::from_huggingface_info(&info, readme.as_deref(), file_pattern)
+    }
+
+    /// Offline half of [LLMRegistryEntry::from_huggingface], for when you've already
+    /// got the model info (and optionally the model card).
+    pub fn from_huggingface_info(
+        info: &HuggingFaceModel,
+        readme: Option<&str>,
+        file_pattern: &str,
+    ) -> Result<LLMRegistryEntryBuilder, PantryError> {
+        let mut files: Vec<&str> = info
+            .siblings
+            .iter()
+            .map(|f| f.rfilename.as_str())
+            .filter(|f| glob_match(file_pattern, f))
+            .collect();
+        files.sort();
+        let file = files
+            .first()
+            .ok_or_else(|| PantryError::NoMatchingFile(info.id.clone(), file_pattern.into()))?;
+
+        let repo_name = info.id.rsplit('/').next().unwrap_or(&info.id);
+        let id = file.rsplit('/').next().unwrap_or(file);
+        let id = id.rsplit_once('.').map_or(id, |(stem, _)| stem);
+
+        let card = |key: &str| info.card_data.as_ref().and_then(|c| c.get(key));
+
+        let mut builder = LLMRegistryEntry::builder(id, LLMConnectorType::LLMrs)
+            .name(repo_name)
+            .homepage(format!("{}/{}", HUGGINGFACE_URL, info.id))
+            .url(format!(
+                "{}/{}/resolve/main/{}",
+                HUGGINGFACE_URL, info.id, file
+            ));
+
+        if let Some(org) = card("model_creator")
+            .and_then(|v| v.as_str())
+            .or(info.author.as_deref())
+        {
+            builder = builder.organization(org);
+        }
+
+        // License is either a string or a list of them.
+        match card("license") {
+            Some(Value::String(license)) => builder = builder.license(license.as_str()),
+            Some(Value::Array(licenses)) => {
+                let licenses: Vec<&str> = licenses.iter().filter_map(|l| l.as_str()).collect();
+                builder = builder.license(licenses.join(", "));
+            }
+            _ => {}
+        }
+
+        if let Some(description) = readme.and_then(model_card_description) {
+            builder = builder.description(description);
+        }
+
+        // Hub tags like "license:mit" or "region:us" are noise in Pantry's UI.
+        for tag in info.tags.iter().filter(|t| !t.contains(':')) {
+            builder = builder.tag(tag.as_str());
+        }
+
+        let model_type = info
+            .config
+            .as_ref()
+            .and_then(|c| c.get("model_type"))
+            .or_else(|| card("model_type"))
+            .and_then(|v| v.as_str());
+        let architecture = model_type
+            .and_then(llm_architecture)
+            .or_else(|| info.tags.iter().find_map(|t| llm_architecture(t)));
+        if let Some(arch) = architecture {
+            builder = builder.family_id(arch).config("model_architecture", arch);
+        }
+
+        Ok(builder)
+    }
+}
+
+pub(crate) async fn get(
+    client: &Client<HttpsConnector<hyper::client::HttpConnector>>,
+    url: String,
+) -> Result<String, PantryError> {
+    let req = Request::get(url)
+        .header("user-agent", "pantry-rs")
+        .body(Body::empty())?;
+    let resp = client.request(req).await?;
+    let status = resp.status();
+    let body_bytes = hyper::body::to_bytes(resp.into_body()).await?;
+    let body_str = std::str::from_utf8(&body_bytes)?;
+    match status {
+        StatusCode::OK => Ok(body_str.to_string()),
+        // Deliberately not PantryError::from_response, these aren't Pantry's status codes.
+        _ => Err(PantryError::ApiError(status, body_str.into())),
+    }
+}
+
+/// Maps Hugging Face `model_type`s (and tags) to rustformers/llm architecture names.
+fn llm_architecture(model_type: &str) -> Option<&'static str> {
+    match model_type.to_lowercase().as_str() {
+        "llama" => Some("llama"),
+        "gpt2" => Some("gpt2"),
+        "gptj" | "gpt-j" => Some("gptj"),
+        "gpt_neox" | "gptneox" | "gpt-neox" => Some("gptneox"),
+        "bloom" => Some("bloom"),
+        "mpt" => Some("mpt"),
+        "falcon" | "refinedweb" | "refinedwebmodel" => Some("falcon"),
+        _ => None,
+    }
+}
+
+/// First paragraph of prose in a model card, skipping the YAML header, headings,
+/// badges and html.
+fn model_card_description(readme: &str) -> Option<String> {
+    let mut body = readme.trim_start();
+    if let Some(rest) = body.strip_prefix("---") {
+        body = rest.split_once("\n---").map_or("", |(_, rest)| rest);
+    }
+
+    let mut paragraph: Vec<&str> = Vec::new();
+    for line in body.lines().map(str::trim) {
+        let skip = line.starts_with('#')
+            || line.starts_with('<')
+            || line.starts_with("![")
+            || line.starts_with("[!");
+        if line.is_empty() || skip {
+            if !paragraph.is_empty() {
+                break;
+            }
+            continue;
+        }
+        paragraph.push(line);
+    }
+    if paragraph.is_empty() {
+        None
+    } else {
+        Some(paragraph.join(" "))
+    }
+}
+
+/// Glob matching supporting `*` and `?`.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            p = star_p + 1;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
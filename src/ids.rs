@@ -0,0 +1,93 @@
+//! Typed identifiers, so that a session id can't be passed where an LLM's is expected.
+//!
+//! Each is a [Uuid] underneath and goes over the wire as one. Converting from and to
+//! plain [Uuid]s and strings is there for code that still has those:
+//!
+//! ```
+//! # use pantry_rs::{SessionId, UserId};
+//! # use uuid::Uuid;
+//! # fn main() -> Result<(), uuid::Error> {
+//! let session_id: SessionId = "67e55044-10b1-426f-9247-bb680e5fe0c8".parse()?;
+//! let raw: Uuid = session_id.into();
+//! let user_id = UserId::from(raw);
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+macro_rules! id_type {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(
+            Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize,
+            serde::Deserialize,
+        )]
+        #[serde(transparent)]
+        pub struct $name(pub Uuid);
+
+        impl $name {
+            /// A new random id.
+            pub fn new_v4() -> Self {
+                $name(Uuid::new_v4())
+            }
+
+            /// The underlying [Uuid].
+            pub fn as_uuid(&self) -> &Uuid {
+                &self.0
+            }
+        }
+
+        impl From<Uuid> for $name {
+            fn from(uuid: Uuid) -> Self {
+                $name(uuid)
+            }
+        }
+
+        impl From<$name> for Uuid {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = uuid::Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Uuid::parse_str(s).map($name)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+id_type!(
+    /// A user, as returned by [crate::PantryAPI::register_user].
+    UserId
+);
+id_type!(
+    /// A session with an LLM.
+    SessionId
+);
+id_type!(
+    /// A request waiting on the owner's approval, see [crate::interface::UserRequestStatus].
+    RequestId
+);
+id_type!(
+    /// A downloaded LLM, as opposed to its id in the registry (e.g. `"llama-2-7b"`).
+    LlmUuid
+);
+id_type!(
+    /// A conversation saved in a [crate::store::ConversationStore].
+    ConversationId
+);
+id_type!(
+    /// A lease pinning a bare model's file, see [crate::lease::BareModelLease].
+    LeaseId
+);
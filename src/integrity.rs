@@ -0,0 +1,145 @@
+//! Checksums and signatures for model files. Requires the `integrity` feature.
+//!
+//! Distributors fill in [LLMRegistryEntry::sha256] and [LLMRegistryEntry::size_bytes]
+//! (and optionally sign them) when publishing an entry, and apps check the file they
+//! get from [crate::PantryClient::bare_model] against the same entry:
+//!
+//! ```no_run
+//! # use pantry_rs::interface::LLMRegistryEntry;
+//! # use pantry_rs::PantryClient;
+//! # const DISTRIBUTOR_KEY: [u8; 32] = [0; 32];
+//! # async fn example(pantry: PantryClient, entry: LLMRegistryEntry) -> Result<(), Box<dyn std::error::Error>> {
+//! let (status, path) = pantry.bare_model(entry.id.clone()).await?;
+//! entry.verify_file(&path)?;
+//! entry.verify_signature(&DISTRIBUTOR_KEY)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Hashing reads the whole file and blocks, so for multi-gigabyte models call these
+//! from `tokio::task::spawn_blocking` or similar.
+use crate::error::PantryError;
+use crate::interface::LLMRegistryEntry;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// SHA-256 and size of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDigest {
+    /// Hex encoded, lowercase.
+    pub sha256: String,
+    pub size_bytes: u64,
+}
+
+/// Hashes the file at `path`.
+pub fn file_digest(path: impl AsRef<Path>) -> Result<FileDigest, PantryError> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut size_bytes = 0;
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size_bytes += n as u64;
+    }
+    Ok(FileDigest {
+        sha256: to_hex(&hasher.finalize()),
+        size_bytes,
+    })
+}
+
+impl LLMRegistryEntry {
+    /// Fills in `sha256` and `size_bytes` from the file at `path`. Clears any existing
+    /// signature, since it no longer matches.
+    pub fn set_file_digest(&mut self, path: impl AsRef<Path>) -> Result<(), PantryError> {
+        let digest = file_digest(path)?;
+        self.sha256 = Some(digest.sha256);
+        self.size_bytes = Some(digest.size_bytes);
+        self.signature = None;
+        Ok(())
+    }
+
+    /// Checks the file at `path` against `size_bytes` and `sha256`.
+    ///
+    /// Size is checked first, so a truncated download fails without hashing it. Fails
+    /// if the entry has no `sha256`, since there'd be nothing to verify.
+    pub fn verify_file(&self, path: impl AsRef<Path>) -> Result<(), PantryError> {
+        let expected = self
+            .sha256
+            .as_ref()
+            .ok_or_else(|| PantryError::IntegrityError("entry has no sha256".into()))?;
+        let path = path.as_ref();
+        if let Some(size_bytes) = self.size_bytes {
+            let actual = std::fs::metadata(path)?.len();
+            if actual != size_bytes {
+                return Err(PantryError::IntegrityError(format!(
+                    "{} is {} bytes, expected {}",
+                    path.display(),
+                    actual,
+                    size_bytes
+                )));
+            }
+        }
+        let digest = file_digest(path)?;
+        if !digest.sha256.eq_ignore_ascii_case(expected) {
+            return Err(PantryError::IntegrityError(format!(
+                "{} has sha256 {}, expected {}",
+                path.display(),
+                digest.sha256,
+                expected
+            )));
+        }
+        Ok(())
+    }
+
+    /// Signs `sha256`, storing the result in `signature`.
+    pub fn sign(&mut self, key: &SigningKey) -> Result<(), PantryError> {
+        let digest = self.sha256_bytes()?;
+        self.signature = Some(to_hex(&key.sign(&digest).to_bytes()));
+        Ok(())
+    }
+
+    /// Checks `signature` was made over `sha256` by the holder of `public_key`.
+    ///
+    /// This only vouches for the hash; use [LLMRegistryEntry::verify_file] to check the
+    /// file actually matches it.
+    pub fn verify_signature(&self, public_key: &[u8; 32]) -> Result<(), PantryError> {
+        let digest = self.sha256_bytes()?;
+        let signature = self
+            .signature
+            .as_deref()
+            .and_then(from_hex)
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or_else(|| PantryError::IntegrityError("missing or malformed signature".into()))?;
+        let key = VerifyingKey::from_bytes(public_key)
+            .map_err(|e| PantryError::IntegrityError(e.to_string()))?;
+        key.verify(&digest, &signature)
+            .map_err(|_| PantryError::IntegrityError("signature does not match".into()))
+    }
+
+    fn sha256_bytes(&self) -> Result<Vec<u8>, PantryError> {
+        self.sha256
+            .as_deref()
+            .and_then(from_hex)
+            .filter(|bytes| bytes.len() == 32)
+            .ok_or_else(|| PantryError::IntegrityError("missing or malformed sha256".into()))
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `None` on odd lengths too, since the last `get` runs past the end.
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -1,3 +1,4 @@
+use crate::params::Constraint;
 use chrono::{DateTime, Utc};
 use serde;
 use serde_json::Value;
@@ -5,6 +6,8 @@ use std::collections::HashMap;
 use std::fmt;
 use uuid::Uuid;
 
+pub use crate::ids::{LlmUuid, RequestId, SessionId, UserId};
+
 /*
  * User info returned by the API, exclusively describing the current user.
  * `id` and `api_key` are required to reconstruct the user later.
@@ -12,7 +15,7 @@ use uuid::Uuid;
  */
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct UserInfo {
-    pub id: String,
+    pub id: UserId,
     // Can be anything, useful for the user to do.
     pub name: String,
     pub api_key: String,
@@ -27,6 +30,32 @@ pub struct UserInfo {
     pub perm_request_unload: bool,
     pub perm_view_llms: bool,
     pub perm_bare_model: bool,
+    /// See [UserPermissions::expires_at].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// See [UserPermissions::llm_scope].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub llm_scope: Vec<String>,
+}
+
+impl UserInfo {
+    /// The permissions this user has been granted.
+    pub fn permissions(&self) -> UserPermissions {
+        UserPermissions {
+            perm_superuser: self.perm_superuser,
+            perm_load_llm: self.perm_load_llm,
+            perm_unload_llm: self.perm_unload_llm,
+            perm_download_llm: self.perm_download_llm,
+            perm_session: self.perm_session,
+            perm_request_download: self.perm_request_download,
+            perm_request_load: self.perm_request_load,
+            perm_request_unload: self.perm_request_unload,
+            perm_view_llms: self.perm_view_llms,
+            perm_bare_model: self.perm_bare_model,
+            expires_at: self.expires_at,
+            llm_scope: self.llm_scope.clone(),
+        }
+    }
 }
 
 /*
@@ -34,13 +63,91 @@ pub struct UserInfo {
  *
  * At the moment, 10 represents GPT-4 quality.
  */
-#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
-#[serde(rename_all = "lowercase")]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
 pub enum CapabilityType {
     General,
     Assistant,
     Writing,
     Coding,
+    /// Understanding images, see [crate::multimodal].
+    Vision,
+    /// Turning speech into text, see [crate::transcription].
+    Transcription,
+    /// Anything Pantry knows about that we don't (yet), kept as is.
+    Other(String),
+}
+
+impl CapabilityType {
+    /// The capabilities every LLM gets rated on.
+    pub const KNOWN: [CapabilityType; 6] = [
+        CapabilityType::General,
+        CapabilityType::Assistant,
+        CapabilityType::Writing,
+        CapabilityType::Coding,
+        CapabilityType::Vision,
+        CapabilityType::Transcription,
+    ];
+
+    /// The key Pantry uses for this capability.
+    pub fn as_str(&self) -> &str {
+        match self {
+            CapabilityType::General => "general",
+            CapabilityType::Assistant => "assistant",
+            CapabilityType::Writing => "writing",
+            CapabilityType::Coding => "coding",
+            CapabilityType::Vision => "vision",
+            CapabilityType::Transcription => "transcription",
+            CapabilityType::Other(other) => other,
+        }
+    }
+
+    pub fn is_known(&self) -> bool {
+        !matches!(self, CapabilityType::Other(_))
+    }
+}
+
+impl From<&str> for CapabilityType {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "general" => CapabilityType::General,
+            "assistant" => CapabilityType::Assistant,
+            "writing" => CapabilityType::Writing,
+            "coding" => CapabilityType::Coding,
+            "vision" => CapabilityType::Vision,
+            "transcription" => CapabilityType::Transcription,
+            _ => CapabilityType::Other(s.to_string()),
+        }
+    }
+}
+
+impl From<String> for CapabilityType {
+    fn from(s: String) -> Self {
+        CapabilityType::from(s.as_str())
+    }
+}
+
+impl From<CapabilityType> for String {
+    fn from(capability: CapabilityType) -> Self {
+        match capability {
+            CapabilityType::Other(other) => other,
+            known => known.as_str().to_string(),
+        }
+    }
+}
+
+impl std::str::FromStr for CapabilityType {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(CapabilityType::from(s))
+    }
+}
+
+impl fmt::Display for CapabilityType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
 }
 
 /*
@@ -67,6 +174,28 @@ pub struct LLMStatus {
     pub connector_type: String,
 
     pub download_progress: f32,
+    /// Bytes downloaded so far, for pantry versions that report it.
+    #[serde(default)]
+    pub download_bytes: Option<u64>,
+    /// Total download size, for pantry versions that report it.
+    #[serde(default)]
+    pub download_total: Option<u64>,
+    /// Context window in tokens, for pantry versions that report it. See
+    /// [LLMStatus::context_length].
+    #[serde(default, rename = "context_length")]
+    pub reported_context_length: Option<u64>,
+    /// Memory needed to run the model, for pantry versions that report it. See
+    /// [LLMStatus::ram_bytes].
+    #[serde(default, rename = "ram_bytes")]
+    pub reported_ram_bytes: Option<u64>,
+    /// Release of the model, for pantry versions that report it. See
+    /// [LLMRegistryEntry::version].
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Ids of the adapters applied to the running model, see
+    /// [crate::PantryClient::apply_adapter].
+    #[serde(default)]
+    pub applied_adapters: Vec<String>,
 
     /*
      * Configuration for connectors. Varies by connector.
@@ -77,6 +206,8 @@ pub struct LLMStatus {
      * - model_architecture (automatically set by UI, but necessary in API)
      * - vocabulary_path [HuggingFaceTokenizerFile](https://github.com/rustformers/llm/blob/2259555544dbf3fadf609b3883f1edda4eb67677/crates/llm-base/src/tokenizer/mod.rs#L56)
      * - vocabulary_repository [HuggingFaceTokenizerString](https://github.com/rustformers/llm/blob/2259555544dbf3fadf609b3883f1edda4eb67677/crates/llm-base/src/tokenizer/mod.rs#L56)
+     *
+     * See LLMStatus::connector_config for a typed view.
      */
     pub config: HashMap<String, Value>,
 
@@ -88,14 +219,106 @@ pub struct LLMStatus {
     pub user_session_parameters: Vec<String>, //User Parameters
 
     //non llminfo fields
-    pub uuid: String, // All LLMStatus are downloaded,
+    pub uuid: LlmUuid, // All LLMStatus are downloaded,
     pub running: bool,
 }
+
+impl LLMStatus {
+    /// Context window in tokens, as reported by Pantry or set in the registry
+    /// entry's `config.context_length`.
+    pub fn context_length(&self) -> Option<u64> {
+        self.reported_context_length
+            .or_else(|| self.config.get("context_length").and_then(|v| v.as_u64()))
+    }
+
+    /// Memory needed to run the model, as reported by Pantry or set in the registry
+    /// entry's `config.ram_bytes`.
+    pub fn ram_bytes(&self) -> Option<u64> {
+        self.reported_ram_bytes
+            .or_else(|| self.config.get("ram_bytes").and_then(|v| v.as_u64()))
+    }
+
+    /// Whether the LLM takes `constraint`, going by its `user_parameters`.
+    pub fn supports_constraint(&self, constraint: &Constraint) -> bool {
+        self.user_parameters
+            .iter()
+            .any(|param| param == constraint.key())
+    }
+
+    /// Whether the LLM can be prompted with images: rated for
+    /// [CapabilityType::Vision], tagged `vision` or `multimodal`, or a LLaVA. LLMs
+    /// that don't say are taken not to.
+    pub fn accepts_images(&self) -> bool {
+        self.capabilities
+            .get(&CapabilityType::Vision)
+            .is_some_and(|rating| *rating > 0)
+            || self.tags.iter().any(|tag| {
+                tag.eq_ignore_ascii_case("vision") || tag.eq_ignore_ascii_case("multimodal")
+            })
+            || self.id.to_lowercase().contains("llava")
+    }
+
+    /// Whether the LLM turns speech into text: a [LLMConnectorType::Whisper] model,
+    /// rated for [CapabilityType::Transcription], tagged `transcription` or
+    /// `speech-to-text`, or a Whisper.
+    pub fn transcribes_audio(&self) -> bool {
+        self.connector_type.eq_ignore_ascii_case("whisper")
+            || self
+                .capabilities
+                .get(&CapabilityType::Transcription)
+                .is_some_and(|rating| *rating > 0)
+            || self.tags.iter().any(|tag| {
+                tag.eq_ignore_ascii_case("transcription")
+                    || tag.eq_ignore_ascii_case("speech-to-text")
+            })
+            || self.id.to_lowercase().contains("whisper")
+    }
+}
+
+/// Stage of an LLM download, see [DownloadProgress].
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DownloadPhase {
+    /// Accepted, but no data received yet.
+    Queued,
+    Downloading,
+    Complete,
+}
+
+/// Snapshot of an LLM download, as reported by [crate::PantryClient::download_progress].
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug)]
+pub struct DownloadProgress {
+    /// Bytes downloaded so far, if Pantry reports it.
+    pub bytes: Option<u64>,
+    /// Total size of the download, if Pantry reports it.
+    pub total: Option<u64>,
+    /// Between 0 and 100.
+    pub percent: f32,
+    pub phase: DownloadPhase,
+}
+
+impl From<&LLMStatus> for DownloadProgress {
+    fn from(status: &LLMStatus) -> Self {
+        let phase = if status.download_progress >= 100.0 {
+            DownloadPhase::Complete
+        } else if status.download_progress > 0.0 || status.download_bytes.unwrap_or(0) > 0 {
+            DownloadPhase::Downloading
+        } else {
+            DownloadPhase::Queued
+        };
+        DownloadProgress {
+            bytes: status.download_bytes,
+            total: status.download_total,
+            percent: status.download_progress,
+            phase,
+        }
+    }
+}
+
 //This is a lot like frontend::LLMRunningInfo, but limited for non-superusers
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 pub struct LLMRunningStatus {
     pub llm_info: LLMStatus,
-    pub uuid: String,
+    pub uuid: LlmUuid,
     // #[serde(skip_serializing)]
     // pub llm: dyn LLMWrapper + Send + Sync
 }
@@ -103,11 +326,18 @@ pub struct LLMRunningStatus {
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct DownloadRequest {
     pub llm_registry_entry: LLMRegistryEntry,
+    /// Directory on the Pantry machine to store the model in, e.g. on an external
+    /// drive. `None` for Pantry's own models directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_dir: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PermissionRequest {
     pub requested_permissions: UserPermissions,
+    /// How long the grant should last once accepted, in seconds. `None` for good.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<u64>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -120,6 +350,17 @@ pub struct UnloadRequest {
     pub llm_id: String,
 }
 
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DeleteRequest {
+    pub llm_id: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RegisterLocalRequest {
+    pub path: String,
+    pub llm_registry_entry: LLMRegistryEntry,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "type")]
 pub enum UserRequestType {
@@ -127,18 +368,53 @@ pub enum UserRequestType {
     PermissionRequest(PermissionRequest),
     LoadRequest(LoadRequest),
     UnloadRequest(UnloadRequest),
+    DeleteRequest(DeleteRequest),
+    RegisterLocalRequest(RegisterLocalRequest),
 }
 
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct UserRequestStatus {
-    pub id: Uuid,
-    pub user_id: Uuid,
+    pub id: RequestId,
+    pub user_id: UserId,
     pub timestamp: DateTime<Utc>,
     pub request: UserRequestType,
     pub accepted: bool,
     pub complete: bool,
 }
 
+/// How a request ended up, see [crate::PantryClient::await_request].
+#[derive(Debug)]
+pub enum RequestOutcome {
+    Accepted(UserRequestStatus),
+    Denied(UserRequestStatus),
+    /// Still pending when we stopped waiting. Contains the last status seen.
+    TimedOut(UserRequestStatus),
+}
+
+/// Something that happened on the server, see [crate::PantryClient::subscribe_events].
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug)]
+#[serde(tag = "type")]
+pub enum ServerEvent {
+    LLMLoaded {
+        llm_uuid: LlmUuid,
+    },
+    LLMUnloaded {
+        llm_uuid: LlmUuid,
+    },
+    DownloadFinished {
+        llm_uuid: LlmUuid,
+    },
+    RequestAccepted {
+        request_id: RequestId,
+    },
+    RequestDenied {
+        request_id: RequestId,
+    },
+    /// Event types this version of the library doesn't know about.
+    #[serde(other)]
+    Other,
+}
+
 /// Returned by inference, containing inference events.
 #[derive(Clone, serde::Deserialize, serde::Serialize, Debug)]
 pub struct LLMEvent {
@@ -147,7 +423,7 @@ pub struct LLMEvent {
     pub call_timestamp: DateTime<Utc>,
     pub parameters: HashMap<String, Value>,
     pub input: String,
-    pub llm_uuid: Uuid,
+    pub llm_uuid: LlmUuid,
     pub session: LLMSessionStatus,
     pub event: LLMEventInternal,
 }
@@ -155,16 +431,55 @@ pub struct LLMEvent {
 #[derive(Clone, serde::Deserialize, serde::Serialize, Debug)]
 #[serde(tag = "type")]
 pub enum LLMEventInternal {
-    PromptProgress { previous: String, next: String }, // Next words of an LLM.
-    PromptCompletion { previous: String },             // Finished the prompt
-    PromptError { message: String },
+    PromptProgress {
+        previous: String,
+        next: String,
+    }, // Next words of an LLM.
+    // Finished the prompt. Pantry versions that don't report the extra fields get them
+    // filled in by LLMEventStream where possible.
+    PromptCompletion {
+        previous: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        finish_reason: Option<FinishReason>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        prompt_tokens: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        completion_tokens: Option<u32>,
+    },
+    PromptError {
+        message: String,
+    },
     Other,
 }
 
+/// Why inference stopped.
+#[derive(Clone, Copy, serde::Deserialize, serde::Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FinishReason {
+    /// The LLM finished on its own, or hit a stop sequence.
+    Stop,
+    /// Hit `max_tokens`.
+    Length,
+    /// Interrupted, see [crate::LLMSession::interrupt_session].
+    Interrupted,
+    /// The LLM failed, see [LLMEventInternal::PromptError].
+    Error,
+}
+
+/// A finished completion, see [crate::stream::LLMEventStream::collect_completion].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Completion {
+    pub text: String,
+    pub finish_reason: FinishReason,
+    /// Tokens in the prompt, if Pantry reports it.
+    pub prompt_tokens: Option<u32>,
+    pub completion_tokens: u32,
+}
+
 /// Structure representing user permissions, generally used for making requests.
 ///
 /// See documentation on [crate::api::PantryAPI] for which calls require which permissions.
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct UserPermissions {
     // We flatten these in here for easier DB storage.
     pub perm_superuser: bool,
@@ -177,19 +492,369 @@ pub struct UserPermissions {
     pub perm_request_unload: bool,
     pub perm_view_llms: bool,
     pub perm_bare_model: bool,
+    /// When these permissions lapse, `None` if they don't.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Ids or family ids of the LLMs the LLM specific permissions (see
+    /// [UserPermissions::is_llm_specific]) are limited to, e.g. `["llama"]`. Empty for
+    /// every LLM.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub llm_scope: Vec<String>,
+}
+
+impl UserPermissions {
+    fn flags(&self) -> [bool; 10] {
+        [
+            self.perm_superuser,
+            self.perm_load_llm,
+            self.perm_unload_llm,
+            self.perm_download_llm,
+            self.perm_session,
+            self.perm_request_download,
+            self.perm_request_load,
+            self.perm_request_unload,
+            self.perm_view_llms,
+            self.perm_bare_model,
+        ]
+    }
+
+    /// Whether any permission that [UserPermissions::llm_scope] limits is set: loading,
+    /// unloading, sessions and bare models.
+    pub fn is_llm_specific(&self) -> bool {
+        self.perm_load_llm
+            || self.perm_unload_llm
+            || self.perm_session
+            || self.perm_request_load
+            || self.perm_request_unload
+            || self.perm_bare_model
+    }
+
+    /// Whether these permissions have lapsed, see [UserPermissions::expires_at].
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|at| at <= Utc::now())
+    }
+
+    /// These permissions, or none at all if they've lapsed.
+    pub fn in_effect(&self) -> UserPermissions {
+        match self.is_expired() {
+            true => UserPermissions::default(),
+            false => self.clone(),
+        }
+    }
+
+    /// Whether the LLM specific permissions apply to `llm`, see
+    /// [UserPermissions::llm_scope].
+    pub fn covers(&self, llm: &LLMStatus) -> bool {
+        self.llm_scope.is_empty()
+            || self
+                .llm_scope
+                .iter()
+                .any(|scope| *scope == llm.id || *scope == llm.family_id)
+    }
+
+    /// Whether every permission set here is also set in `other`, for at least the
+    /// same LLMs. Expiry isn't compared, see [UserPermissions::in_effect].
+    pub fn is_subset_of(&self, other: &UserPermissions) -> bool {
+        let flags = self
+            .flags()
+            .iter()
+            .zip(other.flags().iter())
+            .all(|(mine, theirs)| !mine || *theirs);
+        let scoped = !self.is_llm_specific()
+            || other.llm_scope.is_empty()
+            || (!self.llm_scope.is_empty()
+                && self.llm_scope.iter().all(|s| other.llm_scope.contains(s)));
+        flags && scoped
+    }
+
+    /// Permissions set in either `self` or `other`, or `None` if one grant can't hold
+    /// both without widening either: differently scoped LLM specific permissions, or
+    /// different expiries. A side that grants nothing, or only what the other already
+    /// grants for at least as long, is absorbed.
+    pub fn union(&self, other: &UserPermissions) -> Option<UserPermissions> {
+        if !other.flags().contains(&true) || other.outlived_by(self) {
+            return Some(self.clone());
+        }
+        if !self.flags().contains(&true) || self.outlived_by(other) {
+            return Some(other.clone());
+        }
+        if self.expires_at != other.expires_at {
+            return None;
+        }
+        let llm_scope = match (self.is_llm_specific(), other.is_llm_specific()) {
+            (_, false) => self.llm_scope.clone(),
+            (false, true) => other.llm_scope.clone(),
+            _ if same_scope(&self.llm_scope, &other.llm_scope) => self.llm_scope.clone(),
+            // The same permissions on more LLMs, rather than some on LLMs they weren't
+            // asked for.
+            _ if self.scoped_flags() == other.scoped_flags() => {
+                if self.llm_scope.is_empty() || other.llm_scope.is_empty() {
+                    Vec::new()
+                } else {
+                    let mut scope = self.llm_scope.clone();
+                    scope.extend(
+                        other
+                            .llm_scope
+                            .iter()
+                            .filter(|s| !self.llm_scope.contains(s))
+                            .cloned(),
+                    );
+                    scope
+                }
+            }
+            _ => return None,
+        };
+        Some(UserPermissions {
+            perm_superuser: self.perm_superuser || other.perm_superuser,
+            perm_load_llm: self.perm_load_llm || other.perm_load_llm,
+            perm_unload_llm: self.perm_unload_llm || other.perm_unload_llm,
+            perm_download_llm: self.perm_download_llm || other.perm_download_llm,
+            perm_session: self.perm_session || other.perm_session,
+            perm_request_download: self.perm_request_download || other.perm_request_download,
+            perm_request_load: self.perm_request_load || other.perm_request_load,
+            perm_request_unload: self.perm_request_unload || other.perm_request_unload,
+            perm_view_llms: self.perm_view_llms || other.perm_view_llms,
+            perm_bare_model: self.perm_bare_model || other.perm_bare_model,
+            expires_at: self.expires_at,
+            llm_scope,
+        })
+    }
+
+    /// The permissions [UserPermissions::llm_scope] limits, see
+    /// [UserPermissions::is_llm_specific].
+    fn scoped_flags(&self) -> [bool; 6] {
+        [
+            self.perm_load_llm,
+            self.perm_unload_llm,
+            self.perm_session,
+            self.perm_request_load,
+            self.perm_request_unload,
+            self.perm_bare_model,
+        ]
+    }
+
+    /// Whether `other` grants everything here, for at least as long.
+    fn outlived_by(&self, other: &UserPermissions) -> bool {
+        let lasts = match (self.expires_at, other.expires_at) {
+            (_, None) => true,
+            (Some(mine), Some(theirs)) => theirs >= mine,
+            (None, Some(_)) => false,
+        };
+        lasts && self.is_subset_of(other)
+    }
+}
+
+fn same_scope(a: &[String], b: &[String]) -> bool {
+    a.iter().all(|s| b.contains(s)) && b.iter().all(|s| a.contains(s))
+}
+
+/// A registered user as seen by a superuser, see [crate::admin::AdminClient].
+///
+/// Unlike [UserInfo], this never includes the API key.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct UserStatus {
+    pub id: UserId,
+    pub name: String,
+    #[serde(flatten)]
+    pub permissions: UserPermissions,
+    /// When the user last made a call, if ever.
+    #[serde(default)]
+    pub last_active: Option<DateTime<Utc>>,
 }
 
 /// This is a minimal copy of session internals returned with [LLMEvent].
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct LLMSessionStatus {
-    pub id: Uuid, //this is a uuid
-    pub llm_uuid: Uuid,
-    pub user_id: Uuid,
+    pub id: SessionId,
+    pub llm_uuid: LlmUuid,
+    pub user_id: UserId,
     pub started: DateTime<Utc>,
     pub last_called: DateTime<Utc>,
     pub session_parameters: HashMap<String, Value>,
 }
 
+/// One prompt and its completion, from [crate::LLMSession::history].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LLMHistoryItem {
+    /// Same as the [LLMEvent::stream_id] of the prompt's events.
+    pub id: Uuid,
+    pub llm_uuid: LlmUuid,
+    pub session_id: SessionId,
+    /// When the prompt was sent.
+    pub call_timestamp: DateTime<Utc>,
+    /// When the completion last changed.
+    pub updated_timestamp: DateTime<Utc>,
+    /// False while inference is ongoing, or if it was interrupted or failed.
+    pub complete: bool,
+    pub parameters: HashMap<String, Value>,
+    /// The prompt.
+    pub input: String,
+    /// The completion, as far as it got.
+    pub output: String,
+}
+
+/// Protocol version this client speaks, compared against
+/// [ServerInfo::protocol_version].
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// What a Pantry daemon reports about itself, see [crate::PantryAPI::server_info].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ServerInfo {
+    /// Version of the Pantry application, e.g. "0.0.4".
+    pub version: String,
+    /// Bumped whenever the API changes incompatibly.
+    pub protocol_version: u32,
+    /// Paths of the endpoints this daemon serves, without the leading slash.
+    #[serde(default)]
+    pub endpoints: Vec<String>,
+}
+
+impl ServerInfo {
+    /// Whether the daemon serves `endpoint`, e.g. "fork_session".
+    pub fn supports(&self, endpoint: &str) -> bool {
+        let endpoint = endpoint.trim_start_matches('/');
+        self.endpoints.iter().any(|e| e == endpoint)
+    }
+
+    /// Whether the daemon speaks the same protocol as this client.
+    pub fn is_compatible(&self) -> bool {
+        self.protocol_version == PROTOCOL_VERSION
+    }
+}
+
+/// Resource usage of the machine Pantry runs on, see [crate::PantryAPI::get_system_status].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SystemStatus {
+    pub ram_total_bytes: u64,
+    pub ram_used_bytes: u64,
+    /// None if the daemon found no GPU it can query.
+    #[serde(default)]
+    pub vram_total_bytes: Option<u64>,
+    #[serde(default)]
+    pub vram_used_bytes: Option<u64>,
+    /// Of the disk Pantry stores models on.
+    pub disk_total_bytes: u64,
+    pub disk_free_bytes: u64,
+    /// What each running LLM takes up.
+    #[serde(default)]
+    pub llms: Vec<LLMResourceUsage>,
+}
+
+impl SystemStatus {
+    pub fn ram_free_bytes(&self) -> u64 {
+        self.ram_total_bytes.saturating_sub(self.ram_used_bytes)
+    }
+
+    pub fn vram_free_bytes(&self) -> Option<u64> {
+        Some(self.vram_total_bytes?.saturating_sub(self.vram_used_bytes?))
+    }
+
+    /// Whether there's enough free RAM to load `llm`, or None if it doesn't say how
+    /// much it needs (see [LLMStatus::ram_bytes]).
+    pub fn fits(&self, llm: &LLMStatus) -> Option<bool> {
+        Some(llm.ram_bytes()? <= self.ram_free_bytes())
+    }
+}
+
+/// Memory a running LLM takes up, part of [SystemStatus].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LLMResourceUsage {
+    pub llm_uuid: LlmUuid,
+    pub ram_bytes: u64,
+    #[serde(default)]
+    pub vram_bytes: Option<u64>,
+}
+
+/// Disk space taken up by downloaded LLMs, see [crate::PantryAPI::get_storage_info].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct StorageInfo {
+    pub models: Vec<ModelStorage>,
+    /// All of the models together.
+    pub total_bytes: u64,
+}
+
+impl StorageInfo {
+    /// The models, biggest first, e.g. for offering what to delete.
+    pub fn largest_first(&self) -> Vec<&ModelStorage> {
+        let mut models: Vec<&ModelStorage> = self.models.iter().collect();
+        models.sort_by_key(|model| std::cmp::Reverse(model.size_bytes));
+        models
+    }
+}
+
+/// How [crate::PantryClient::export_bare_model] puts a model file somewhere else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportMode {
+    /// A copy of its own, which works anywhere but takes up the space twice.
+    Copy,
+    /// A hard link, which needs to be on the same filesystem as Pantry's models.
+    Hardlink,
+    /// A symbolic link, which the consumer needs to be able to follow.
+    Symlink,
+}
+
+/// A LoRA adapter for a base model, offered in [LLMRegistryEntry::adapters] or
+/// downloaded with [crate::PantryClient::download_adapter].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AdapterEntry {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Where to download the adapter file from.
+    pub url: String,
+    /// Hex encoded SHA-256 of the adapter file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+    /// How strongly to apply the adapter, `1.0` if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f32>,
+}
+
+impl AdapterEntry {
+    pub fn new(id: impl Into<String>, url: impl Into<String>) -> Self {
+        let id = id.into();
+        AdapterEntry {
+            name: id.clone(),
+            id,
+            description: String::new(),
+            url: url.into(),
+            sha256: None,
+            size_bytes: None,
+            scale: None,
+        }
+    }
+}
+
+/// An adapter Pantry knows about for one of its LLMs, see
+/// [crate::PantryClient::list_adapters].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AdapterStatus {
+    /// The base model.
+    pub llm_uuid: LlmUuid,
+    #[serde(flatten)]
+    pub adapter: AdapterEntry,
+    /// Adapters offered by the registry entry aren't downloaded until asked for.
+    pub downloaded: bool,
+    /// Whether it's applied to the running model.
+    pub applied: bool,
+}
+
+/// A downloaded LLM's files, part of [StorageInfo].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ModelStorage {
+    pub llm_uuid: LlmUuid,
+    /// The LLM's id in the registry, e.g. "llama-2-7b".
+    pub id: String,
+    pub size_bytes: u64,
+    /// Where the model lives on the Pantry machine.
+    pub path: String,
+}
+
 /// Registry entry, containing all the information to upload an LLM.
 ///
 /// Most of this information is non-mandatory, and it's fine to send empty
@@ -198,7 +863,9 @@ pub struct LLMSessionStatus {
 ///
 /// At present, [LLMConnectorType::LLMrs] is the only working connector,
 /// and using it requires config['model_architecture'] to be set according to
-/// the [rustformers/llm documentation](https://docs.rs/llm/latest/llm/enum.ModelArchitecture.html)
+/// the [rustformers/llm documentation](https://docs.rs/llm/latest/llm/enum.ModelArchitecture.html).
+/// [LLMRegistryEntry::connector_config] and [crate::connector_config::LlmrsConfig]
+/// spell the keys out.
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct LLMRegistryEntry {
@@ -211,7 +878,7 @@ pub struct LLMRegistryEntry {
     pub description: String,
     pub homepage: String,
 
-    pub capabilities: HashMap<String, i32>,
+    pub capabilities: HashMap<CapabilityType, i32>,
     pub tags: Vec<String>,
     pub requirements: String,
 
@@ -220,6 +887,30 @@ pub struct LLMRegistryEntry {
     #[serde(default)]
     pub backend_uuid: String,
     pub url: String,
+    /// Fallbacks for `url`, in order of preference. See [LLMRegistryEntry::urls].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+
+    /// Hex encoded SHA-256 of the model file, see [LLMRegistryEntry::verify_file].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Size of the model file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+    /// Hex encoded ed25519 signature of the raw `sha256` digest, for distributors that
+    /// want to prove a model came from them. See [LLMRegistryEntry::verify_signature].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Release of the model, e.g. `"3.2"`, so newer ones can be found, see
+    /// [crate::catalog::find_updates].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Ids of the models this one replaces, e.g. older quantizations or predecessors.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub supersedes: Vec<String>,
+    /// LoRA adapters made for this model, downloaded on request.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub adapters: Vec<AdapterEntry>,
 
     pub config: HashMap<String, Value>,
     pub local: bool,
@@ -238,6 +929,8 @@ pub enum LLMConnectorType {
     GenericAPI,
     LLMrs,
     OpenAI,
+    /// Whisper-class speech to text models, see [crate::transcription].
+    Whisper,
 }
 
 impl fmt::Display for LLMConnectorType {
@@ -246,6 +939,7 @@ impl fmt::Display for LLMConnectorType {
             LLMConnectorType::GenericAPI => write!(f, "GenericAPI"),
             LLMConnectorType::LLMrs => write!(f, "LLMrs"),
             LLMConnectorType::OpenAI => write!(f, "OpenAI"),
+            LLMConnectorType::Whisper => write!(f, "Whisper"),
         }
     }
 }
@@ -0,0 +1,223 @@
+//! Holding on to calls while Pantry is unreachable, and sending them once it's back.
+//!
+//! Some calls don't need an answer right away: asking for permissions, requesting a
+//! download, unloading an LLM. An [Outbox] sends them if it can, and otherwise writes
+//! them to a journal file to try again later, even after a restart:
+//!
+//! ```no_run
+//! # use pantry_rs::interface::LLMRegistryEntry;
+//! # use pantry_rs::journal::{JournaledCall, Outbox, Submitted};
+//! # use pantry_rs::PantryClient;
+//! # use std::path::Path;
+//! # async fn example(pantry: PantryClient, data_dir: &Path, entry: LLMRegistryEntry) -> Result<(), Box<dyn std::error::Error>> {
+//! let outbox = Outbox::open(data_dir.join("outbox.json"))?;
+//!
+//! match outbox.submit(&pantry, JournaledCall::RequestDownload { entry }).await? {
+//!     Submitted::Sent(status) => println!("requested: {}", status),
+//!     Submitted::Queued(_) => println!("Pantry isn't running, will ask later"),
+//! }
+//!
+//! // Once Pantry is up again, e.g. after [crate::PantryClient::wait_for_server]:
+//! for (entry, result) in outbox.replay(&pantry).await? {
+//!     println!("{:?}: {:?}", entry.call, result);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Only failures that [PantryError::is_retryable] counts as Pantry being unreachable
+//! get a call queued; anything else is returned as usual. Calls that would be
+//! duplicated by sending them twice, like requests for the user to approve, are only
+//! queued if Pantry can't have received them, e.g. when it refused the connection. If
+//! they time out instead, the timeout is returned, since they may have gone through.
+use crate::error::PantryError;
+use crate::ids::LlmUuid;
+use crate::interface::{LLMRegistryEntry, UserPermissions};
+use crate::PantryClient;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// A call an [Outbox] can hold on to.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "call", rename_all = "snake_case")]
+pub enum JournaledCall {
+    /// [PantryClient::request_permissions].
+    RequestPermissions { permissions: UserPermissions },
+    /// [PantryClient::request_download_llm].
+    RequestDownload { entry: LLMRegistryEntry },
+    /// [PantryClient::download_llm].
+    DownloadLlm { entry: LLMRegistryEntry },
+    /// [PantryClient::request_unload_llm].
+    RequestUnload { llm_uuid: LlmUuid },
+    /// [PantryClient::unload_llm].
+    UnloadLlm { llm_id: String },
+}
+
+impl JournaledCall {
+    /// Makes the call, returning its result as JSON.
+    pub async fn send(&self, pantry: &PantryClient) -> Result<Value, PantryError> {
+        let value = match self {
+            JournaledCall::RequestPermissions { permissions } => {
+                serde_json::to_value(pantry.request_permissions(permissions.clone()).await?)
+            }
+            JournaledCall::RequestDownload { entry } => {
+                serde_json::to_value(pantry.request_download_llm(entry.clone()).await?)
+            }
+            JournaledCall::DownloadLlm { entry } => {
+                serde_json::to_value(pantry.download_llm(entry.clone()).await?)
+            }
+            JournaledCall::RequestUnload { llm_uuid } => {
+                serde_json::to_value(pantry.request_unload_llm(*llm_uuid).await?)
+            }
+            JournaledCall::UnloadLlm { llm_id } => {
+                serde_json::to_value(pantry.unload_llm(llm_id.clone()).await?)
+            }
+        };
+        Ok(value?)
+    }
+
+    /// Whether sending the call twice has the same effect as sending it once.
+    pub fn is_idempotent(&self) -> bool {
+        matches!(self, JournaledCall::UnloadLlm { .. })
+    }
+
+    /// Whether the call can be sent again after failing with `error`, without risking
+    /// it running twice.
+    fn can_resend(&self, error: &PantryError) -> bool {
+        error.is_retryable() && (self.is_idempotent() || error.never_reached())
+    }
+}
+
+/// A call waiting in an [Outbox].
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct JournalEntry {
+    pub id: Uuid,
+    pub queued_at: DateTime<Utc>,
+    pub call: JournaledCall,
+}
+
+/// What [Outbox::submit] did with a call.
+#[derive(Clone, Debug)]
+pub enum Submitted {
+    /// Pantry answered with this.
+    Sent(Value),
+    /// Pantry was unreachable, so the call is waiting under this id.
+    Queued(Uuid),
+}
+
+/// Calls waiting for Pantry to be reachable, see the [module docs](self).
+#[derive(Debug)]
+pub struct Outbox {
+    path: Option<PathBuf>,
+    entries: Mutex<Vec<JournalEntry>>,
+}
+
+impl Outbox {
+    /// An outbox journaled to `path`, picking up whatever is already waiting there.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, PantryError> {
+        let path = path.into();
+        let entries = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Outbox {
+            path: Some(path),
+            entries: Mutex::new(entries),
+        })
+    }
+
+    /// An outbox that forgets its calls when dropped.
+    pub fn in_memory() -> Self {
+        Outbox {
+            path: None,
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Calls waiting to be sent, oldest first.
+    pub fn pending(&self) -> Vec<JournalEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    /// Makes `call`, or queues it if Pantry is unreachable.
+    pub async fn submit(
+        &self,
+        pantry: &PantryClient,
+        call: JournaledCall,
+    ) -> Result<Submitted, PantryError> {
+        match call.send(pantry).await {
+            Ok(value) => Ok(Submitted::Sent(value)),
+            Err(e) if call.can_resend(&e) => Ok(Submitted::Queued(self.queue(call)?)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Queues `call` without trying it first.
+    pub fn queue(&self, call: JournaledCall) -> Result<Uuid, PantryError> {
+        let entry = JournalEntry {
+            id: Uuid::new_v4(),
+            queued_at: Utc::now(),
+            call,
+        };
+        let id = entry.id;
+        let mut entries = self.entries.lock().unwrap();
+        entries.push(entry);
+        self.save(&entries)?;
+        Ok(id)
+    }
+
+    /// Drops the call queued under `id`, returning whether there was one.
+    pub fn cancel(&self, id: Uuid) -> Result<bool, PantryError> {
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|entry| entry.id != id);
+        self.save(&entries)?;
+        Ok(entries.len() < before)
+    }
+
+    /// Sends the waiting calls in the order they were queued, returning each with its
+    /// result. Calls that fail are dropped along with their error, except that if Pantry
+    /// turns out to be unreachable, replaying stops and the rest stay queued. A call
+    /// that may have gone through anyway, see the [module docs](self), is dropped too.
+    pub async fn replay(
+        &self,
+        pantry: &PantryClient,
+    ) -> Result<Vec<(JournalEntry, Result<Value, PantryError>)>, PantryError> {
+        let mut results = Vec::new();
+        loop {
+            let next = self.entries.lock().unwrap().first().cloned();
+            let Some(entry) = next else { break };
+            let result = entry.call.send(pantry).await;
+            if matches!(&result, Err(e) if entry.call.can_resend(e)) {
+                break;
+            }
+            {
+                let mut entries = self.entries.lock().unwrap();
+                entries.retain(|e| e.id != entry.id);
+                self.save(&entries)?;
+            }
+            results.push((entry, result));
+        }
+        Ok(results)
+    }
+
+    fn save(&self, entries: &[JournalEntry]) -> Result<(), PantryError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Written to the side and moved over, so a crash never leaves half a journal.
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(entries)?)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+}
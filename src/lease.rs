@@ -0,0 +1,67 @@
+//! Pinning a bare model's file while running it yourself.
+//!
+//! [PantryClient::bare_model] only hands back a path, so nothing stops Pantry from
+//! deleting or moving the file while an external runner has it open. A
+//! [BareModelLease] pins it until it's released or its TTL runs out:
+//!
+//! ```no_run
+//! # use pantry_rs::PantryClient;
+//! # use std::time::Duration;
+//! # fn run_my_runner(_: &str, _: &str) -> std::io::Result<String> { Ok(String::new()) }
+//! # async fn example(pantry: PantryClient, llm_id: String, prompt: &str) -> Result<(), Box<dyn std::error::Error>> {
+//! let lease = pantry.bare_model_lease(llm_id, Duration::from_secs(3600)).await?;
+//! let output = run_my_runner(lease.path(), prompt)?;
+//! lease.release().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Dropping a lease doesn't release it, as that takes a call to Pantry; the pin then
+//! lasts until the TTL runs out. Deleting a pinned model fails with
+//! [PantryError::LlmInUse].
+use crate::api::BareModelLeaseResponse;
+use crate::error::PantryError;
+use crate::ids::LeaseId;
+use crate::interface::LLMStatus;
+use crate::PantryClient;
+use chrono::{DateTime, Utc};
+
+/// A pinned bare model, see the [module docs](self).
+#[derive(Debug)]
+pub struct BareModelLease {
+    client: PantryClient,
+    lease: BareModelLeaseResponse,
+}
+
+impl BareModelLease {
+    pub(crate) fn new(client: PantryClient, lease: BareModelLeaseResponse) -> Self {
+        BareModelLease { client, lease }
+    }
+
+    pub fn id(&self) -> LeaseId {
+        self.lease.lease_id
+    }
+
+    pub fn model(&self) -> &LLMStatus {
+        &self.lease.model
+    }
+
+    /// Where the model file is on the Pantry machine.
+    pub fn path(&self) -> &str {
+        &self.lease.path
+    }
+
+    /// When the pin lapses, unless released before.
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.lease.expires_at
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.lease.expires_at <= Utc::now()
+    }
+
+    /// Unpins the file, see [PantryClient::release_bare_model].
+    pub async fn release(self) -> Result<(), PantryError> {
+        self.client.release_bare_model(self.lease.lease_id).await
+    }
+}
@@ -38,29 +38,161 @@
 //! ```
 //! let (model, path) = pantry.bare_model_flex(None, None).await.unwrap();
 //! ```
+// quick_error! needs more room than the default for [PantryError]'s variants.
+#![recursion_limit = "256"]
 pub use self::error::PantryError;
-use self::interface::{LLMRegistryEntry, LLMStatus, UserPermissions, UserRequestStatus};
+use self::interface::{
+    AdapterEntry, AdapterStatus, DownloadPhase, DownloadProgress, ExportMode, LLMConnectorType,
+    LLMHistoryItem, LLMRegistryEntry, LLMSessionStatus, LLMStatus, RequestOutcome, ServerInfo,
+    StorageInfo, SystemStatus, UserInfo, UserPermissions, UserRequestStatus,
+};
 
+pub use admin::AdminClient;
 pub use api::PantryAPI;
-pub use api::{LLMFilter, LLMPreference};
+pub use api::{LLMFilter, LLMPreference, StreamFormat};
+pub use auth::RemoteAuth;
+pub use backend::PantryBackend;
+#[cfg(feature = "streaming")]
+pub use cache::ResponseCache;
+pub use chat::ChatMessage;
+#[cfg(feature = "streaming")]
+pub use chat::ChatSession;
+pub use connector_config::{
+    ConnectorConfig, GenericAPIConfig, LlmrsConfig, ModelArchitecture, OpenAIConfig, WhisperConfig,
+};
+pub use credentials::PantryCredentials;
+#[cfg(feature = "streaming")]
+pub use event_bus::ClientEvent;
+pub use fixtures::Fixtures;
+pub use fleet::PantryFleet;
+pub use ids::{ConversationId, LeaseId, LlmUuid, RequestId, SessionId, UserId};
+pub use lease::BareModelLease;
+pub use metrics::MetricsSink;
+pub use multimodal::{AttachmentTransport, PromptInput};
+pub use params::{Constraint, InferenceParams, LoadOptions};
+pub use prompt_format::PromptFormat;
+#[cfg(feature = "streaming")]
+pub use queue::PromptQueue;
+pub use rate_limit::RateLimit;
+pub use registry::LLMRegistryEntryBuilder;
+pub use retry::RetryPolicy;
+#[cfg(feature = "streaming")]
+pub use transcription::TranscriptionSession;
 
+#[cfg(not(target_arch = "wasm32"))]
+use api::Connector;
+use futures::stream::Stream;
+#[cfg(feature = "streaming")]
+use futures::stream::StreamExt;
 use futures_timer::Delay;
+#[cfg(feature = "streaming")]
+use interface::LLMEvent;
 use interface::LLMRunningStatus;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::fmt;
+#[cfg(feature = "streaming")]
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
 use std::{thread, time};
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
 
-use uuid::Uuid;
-
+pub mod admin;
+#[cfg(feature = "streaming")]
+pub mod agent;
 pub mod api;
+pub mod auth;
+pub mod backend;
+#[cfg(feature = "streaming")]
+pub mod cache;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod catalog;
+#[cfg(feature = "llm-chain")]
+pub mod chain;
+pub mod chat;
+pub mod config;
+pub mod connector_config;
+pub mod context;
+pub mod credentials;
+pub mod diagnose;
+#[cfg(all(feature = "discovery", not(target_arch = "wasm32")))]
+pub mod discovery;
 pub mod error;
+#[cfg(feature = "streaming")]
+pub mod event_bus;
+pub mod fixtures;
+pub mod fleet;
+#[cfg(feature = "gguf")]
+pub mod gguf;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod huggingface;
+pub mod ids;
+#[cfg(feature = "integrity")]
+pub mod integrity;
 pub mod interface;
+pub mod journal;
+pub mod lease;
+#[cfg(feature = "streaming")]
+pub mod lifecycle;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod llama_cpp;
+pub mod logging;
+pub mod metrics;
+#[cfg(feature = "streaming")]
+pub mod middleware;
+pub mod multimodal;
+#[cfg(feature = "streaming")]
+pub mod ndjson;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ollama;
+#[cfg(feature = "streaming")]
+pub mod openai_compat;
+pub mod params;
+pub mod prompt_format;
+#[cfg(feature = "proxy")]
+pub mod proxy;
+#[cfg(feature = "streaming")]
+pub mod queue;
+#[cfg(feature = "streaming")]
+pub mod rag;
+pub mod rate_limit;
+pub mod registry;
+pub mod retry;
+#[cfg(feature = "streaming")]
+pub mod sse;
+#[cfg(feature = "store")]
+pub mod store;
+#[cfg(feature = "streaming")]
+pub mod stream;
+#[cfg(feature = "streaming")]
+pub mod structured;
+#[cfg(feature = "templates")]
+pub mod templates;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "rustls")]
+pub mod tls;
+#[cfg(feature = "streaming")]
+pub mod tools;
+#[cfg(feature = "streaming")]
+pub mod transcription;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
+
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm")))]
+compile_error!("pantry-rs needs the `wasm` feature to build for wasm32");
 
 /// Wrapper around the Pantry LLM API.
 ///
 /// The API client connects to the Pantry application, which by default runs a server on
-/// `/tmp/pantrylock.sock` and on `0.0.0.0:9404`. If the pantry application is not running,
-/// all api calls will fail.
+/// `/tmp/pantrylocal.sock` and on `0.0.0.0:9404`. If the pantry application is not running,
+/// all api calls will fail. Set `PANTRY_SOCKET` or use [PantryClientBuilder::socket_path] if
+/// your instance uses a different socket.
 ///
 /// Accessing the API requires a `user_id` and an `api_key`. If you don't have those yet,
 /// use [PantryClient::register] tog retrieve them and get an instance of the struct. Otherwise,
@@ -76,13 +208,25 @@ pub mod interface;
 ///
 /// The same is true for the _id or _flex calls to load and prompt LLMs: Be specific for yourself,
 /// and as broad as possible with others.
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct PantryClient {
     /// user_id is a UUID representing the remote user
-    pub user_id: Uuid,
+    pub user_id: UserId,
     pub api_key: String,
 
-    pub client: PantryAPI,
+    /// What calls go through, normally a [PantryAPI]. See [backend].
+    pub client: Arc<dyn PantryBackend>,
+}
+
+// Keeps the key out of logs and panics.
+impl fmt::Debug for PantryClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PantryClient")
+            .field("user_id", &self.user_id)
+            .field("api_key", &logging::REDACTED)
+            .field("client", &self.client)
+            .finish()
+    }
 }
 
 impl PantryClient {
@@ -98,14 +242,87 @@ impl PantryClient {
         permissions: UserPermissions,
         url: Option<String>,
     ) -> Result<(Self, UserRequestStatus), PantryError> {
-        let client = PantryAPI {
-            client: hyper::Client::new(),
-            base_url: url,
-        };
+        Self::register_with(Arc::new(PantryAPI::new(url)), name, permissions).await
+    }
+
+    /// Returns a copy of this client with a different timeout, overriding the default
+    /// for the calls made through it.
+    ///
+    /// ```no_run
+    /// # use pantry_rs::PantryClient;
+    /// # use std::time::Duration;
+    /// # async fn example(pantry: PantryClient, llm_id: String) -> Result<(), Box<dyn std::error::Error>> {
+    /// // Loading big models can take a while.
+    /// let running = pantry
+    ///     .with_timeout(Some(Duration::from_secs(600)))
+    ///     .load_llm(llm_id)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// * `timeout` — how long to wait before failing with [PantryError::Timeout], `None`
+    ///   to wait forever.
+    pub fn with_timeout(&self, timeout: Option<Duration>) -> Self {
+        PantryClient {
+            client: self.client.with_timeout(timeout),
+            ..self.clone()
+        }
+    }
+
+    /// Like [PantryClient::login], but checks the credentials with Pantry first.
+    ///
+    /// Fails with [PantryError::InvalidApiKey] if they're unknown or revoked, rather than
+    /// on the first real call. Returns the user's info alongside the client, so you can
+    /// check which permissions have been granted.
+    ///
+    /// * `user_id` — A UUID, originally obtained from [PantryClient::register].
+    /// * `api_key` — An API key, originally obtained from [PantryClient::register]
+    /// * `url` — None for localhost (default). Some("https://<url>/") for remote.
+    pub async fn login_verified(
+        user_id: UserId,
+        api_key: String,
+        url: Option<String>,
+    ) -> Result<(Self, UserInfo), PantryError> {
+        let pantry = Self::login(user_id, api_key, url);
+        let info = pantry.get_user_info().await?;
+        Ok((pantry, info))
+    }
+
+    /// Gets this user's info, including the permissions they've been granted so far.
+    pub async fn get_user_info(&self) -> Result<UserInfo, PantryError> {
+        self.client
+            .get_user_info(self.user_id, self.api_key.clone())
+            .await
+    }
+
+    /// Creates a [PantryClient] from `PANTRY_*` environment variables, falling back to
+    /// `client.toml` in the pantry config directory. See [config] for the details.
+    ///
+    /// Does not make any API calls. Fails if no user id or API key is configured.
+    pub fn from_env() -> Result<Self, PantryError> {
+        let config = config::ClientConfig::load()?;
+        match config.credentials() {
+            Some((user_id, api_key)) => Ok(config.builder().login(user_id, api_key)),
+            None => Err(PantryError::CredentialError(
+                "no credentials: set PANTRY_USER_ID and PANTRY_API_KEY, or user_id and api_key in client.toml".into(),
+            )),
+        }
+    }
+
+    /// Starts a [PantryClientBuilder], for connecting with non-default settings.
+    pub fn builder() -> PantryClientBuilder {
+        PantryClientBuilder::new()
+    }
+
+    async fn register_with(
+        client: Arc<dyn PantryBackend>,
+        name: String,
+        permissions: UserPermissions,
+    ) -> Result<(Self, UserRequestStatus), PantryError> {
         let res = client.register_user(name).await?;
 
-        let user_id =
-            Uuid::parse_str(&res.id).map_err(|e| (PantryError::OtherFailure(e.to_string())))?;
+        let user_id = res.id;
 
         let api = PantryClient {
             user_id: user_id,
@@ -113,9 +330,7 @@ impl PantryClient {
             client: client.clone(),
         };
 
-        let res2 = client
-            .request_permissions(api.user_id.clone(), api.api_key.clone(), permissions)
-            .await?;
+        let res2 = api.request_permissions(permissions).await?;
 
         Ok((api, res2))
     }
@@ -144,33 +359,48 @@ impl PantryClient {
     ///
     /// * `user_id` — A UUID, originally obtained from [PantryClient::register].
     /// * `api_key` — An API key, originally obtained from [PantryClient::register]
-    pub fn login(user_id: Uuid, api_key: String, url: Option<String>) -> Self {
-        let client = PantryAPI {
-            client: hyper::Client::new(),
-            base_url: url,
-        };
-
+    pub fn login(user_id: UserId, api_key: String, url: Option<String>) -> Self {
         PantryClient {
             user_id,
             api_key,
-            client: client,
+            client: Arc::new(PantryAPI::new(url)),
         }
     }
 
-    /*
-     * If the session has been moved to disk, puts it back into memory.
-     * Doing this repeatedly for different sessions will result in thrash.
-     *
-     * Note that the associated LLM _must_ be activated, or Pantry will return
-     * an error.
-     *
-     * TODO: not available until future edition.
-     */
-    // pub fn load_session_id(&self, session_id: Uuid) -> Result<>{
-    //     client.load_session_id(session_id)
+    /// Creates a [PantryClient] that makes its calls through `backend`, e.g. a mock for
+    /// tests. See [backend].
+    ///
+    /// Does not make any API calls.
+    ///
+    /// * `backend` — What to call instead of a [PantryAPI].
+    /// * `user_id` — A UUID, originally obtained from [PantryClient::register].
+    /// * `api_key` — An API key, originally obtained from [PantryClient::register]
+    pub fn with_backend(backend: Arc<dyn PantryBackend>, user_id: UserId, api_key: String) -> Self {
+        PantryClient {
+            user_id,
+            api_key,
+            client: backend,
+        }
+    }
 
-    //     todo!();
-    // }
+    /// If the session has been moved to disk, puts it back into memory, returning a
+    /// usable [LLMSession] with the context it had.
+    ///
+    /// Doing this repeatedly for different sessions will result in thrash. Note that the
+    /// associated LLM _must_ be running, or Pantry will return an error. Unlike
+    /// [PantryClient::resume_session], this checks with Pantry that the session still
+    /// exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` — the `id` of an [LLMSession], e.g. from [PantryClient::get_sessions].
+    pub async fn load_session_id(&self, session_id: SessionId) -> Result<LLMSession, PantryError> {
+        let res = self
+            .client
+            .load_session_id(self.user_id, self.api_key.clone(), session_id)
+            .await?;
+        self.session_from_response(res)
+    }
 
     /// Creates a session for an LLM. Will use the "best" available LLM based on capability levels.
     ///
@@ -193,22 +423,7 @@ impl PantryClient {
             .client
             .create_session(self.user_id.clone(), self.api_key.clone(), parameters)
             .await?;
-        let session_uuid = Uuid::parse_str(&res.session_id)
-            .map_err(|e| (PantryError::OtherFailure(e.to_string())))?;
-        let llm_uuid = Uuid::parse_str(&res.llm_status.uuid)
-            .map_err(|e| (PantryError::OtherFailure(e.to_string())))?;
-
-        Ok(LLMSession {
-            user_id: self.user_id.clone(),
-            api_key: self.api_key.clone(),
-
-            id: session_uuid,
-            llm_uuid: llm_uuid,
-            session_parameters: res.session_parameters,
-            llm_status: res.llm_status,
-
-            client: self.client.clone(),
-        })
+        self.session_created(res)
     }
 
     /// Creates a session for an LLM.
@@ -231,7 +446,7 @@ impl PantryClient {
     /// parameters, user+system, were actually used to create the session.
     pub async fn create_session_id(
         &self,
-        llm_id: Uuid,
+        llm_id: LlmUuid,
         parameters: HashMap<String, Value>,
     ) -> Result<LLMSession, PantryError> {
         let res = self
@@ -243,10 +458,114 @@ impl PantryClient {
                 parameters,
             )
             .await?;
-        let session_uuid = Uuid::parse_str(&res.session_id)
-            .map_err(|e| (PantryError::OtherFailure(e.to_string())))?;
-        let llm_uuid = Uuid::parse_str(&res.llm_status.uuid)
-            .map_err(|e| (PantryError::OtherFailure(e.to_string())))?;
+        self.session_created(res)
+    }
+
+    /// Creates a session for a Whisper-class LLM, for transcribing speech, see
+    /// [transcription].
+    ///
+    /// Fails with [PantryError::Unsupported] if the LLM doesn't take audio, after the
+    /// session was created.
+    ///
+    /// # Arguments
+    ///
+    /// * `llm_id` — UUID of a running LLM that [LLMStatus::transcribes_audio].
+    /// * `parameters` — used as session_parameters, see [PantryClient::create_session_id].
+    #[cfg(feature = "streaming")]
+    pub async fn create_transcription_session(
+        &self,
+        llm_id: LlmUuid,
+        parameters: HashMap<String, Value>,
+    ) -> Result<TranscriptionSession, PantryError> {
+        let session = self.create_session_id(llm_id, parameters).await?;
+        TranscriptionSession::new(session)
+    }
+
+    /// Creates a session for an LLM chosen by `filter` and `preference`, from the
+    /// currently running LLMs.
+    ///
+    /// Like [PantryClient::create_session], but with control over which LLM gets picked.
+    /// The chosen LLM is available as [LLMSession::llm_status].
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` — A [LLMFilter] object, for what _must_ be true of an LLM to use it.
+    /// * `preference` — A [LLMPreference] object, for how to rank and then select from the LLMs
+    ///   that pass the filter.
+    /// * `parameters` — used as session_parameters. Since the LLM isn't known ahead of time,
+    ///   Pantry will _attempt_ to set them; check [LLMSession::session_parameters] for what
+    ///   was actually used.
+    pub async fn create_session_flex(
+        &self,
+        filter: Option<LLMFilter>,
+        preference: Option<LLMPreference>,
+        parameters: HashMap<String, Value>,
+    ) -> Result<LLMSession, PantryError> {
+        let res = self
+            .client
+            .create_session_flex(
+                self.user_id,
+                self.api_key.clone(),
+                filter,
+                preference,
+                parameters,
+            )
+            .await?;
+        self.session_created(res)
+    }
+
+    /// Rebuilds an [LLMSession] from a [SessionHandle], e.g. one stored before a restart.
+    ///
+    /// Does not make any API calls, so a session Pantry no longer knows about only
+    /// surfaces as an error once prompted.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` — obtained from [LLMSession::handle].
+    pub fn resume_session(&self, handle: SessionHandle) -> LLMSession {
+        LLMSession {
+            user_id: self.user_id,
+            api_key: self.api_key.clone(),
+
+            id: handle.session_id,
+            llm_uuid: handle.llm_uuid,
+            session_parameters: handle.session_parameters,
+            llm_status: handle.llm_status,
+
+            client: self.client.clone(),
+        }
+    }
+
+    /// [PantryClient::session_from_response], for a session that was just created.
+    fn session_created(&self, res: api::CreateSessionResponse) -> Result<LLMSession, PantryError> {
+        let session = self.session_from_response(res)?;
+        #[cfg(feature = "streaming")]
+        self.publish(|| ClientEvent::SessionCreated {
+            session_id: session.id,
+            llm_uuid: session.llm_uuid,
+        });
+        Ok(session)
+    }
+
+    /// Publishes on the client's event bus, see [event_bus].
+    #[cfg(feature = "streaming")]
+    fn publish(&self, event: impl FnOnce() -> ClientEvent) {
+        event_bus::publish(self.client.event_bus(), event);
+    }
+
+    /// Publishes [ClientEvent::RequestSubmitted] for a request that was just made.
+    fn submitted(&self, status: UserRequestStatus) -> UserRequestStatus {
+        #[cfg(feature = "streaming")]
+        self.publish(|| ClientEvent::RequestSubmitted(status.clone()));
+        status
+    }
+
+    fn session_from_response(
+        &self,
+        res: api::CreateSessionResponse,
+    ) -> Result<LLMSession, PantryError> {
+        let session_uuid = res.session_id;
+        let llm_uuid = res.llm_status.uuid;
 
         Ok(LLMSession {
             user_id: self.user_id.clone(),
@@ -271,6 +590,120 @@ impl PantryClient {
         Ok(v)
     }
 
+    /// Gets the RAM, VRAM and disk usage of the machine Pantry runs on, and what each
+    /// running LLM takes up, see [api::PantryAPI::get_system_status].
+    ///
+    /// Lets you check whether loading another LLM could work before asking the user to
+    /// approve it:
+    ///
+    /// ```no_run
+    /// # use pantry_rs::{LLMFilter, PantryClient};
+    /// # async fn example(pantry: PantryClient, filter: LLMFilter) -> Result<(), Box<dyn std::error::Error>> {
+    /// let llm = pantry.select_llm(Some(filter), None).await?;
+    /// if pantry.get_system_status().await?.fits(&llm) != Some(false) {
+    ///     pantry.request_load_llm(llm.uuid).await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_system_status(&self) -> Result<SystemStatus, PantryError> {
+        self.client
+            .get_system_status(self.user_id, self.api_key.clone())
+            .await
+    }
+
+    /// Gets the disk space each downloaded LLM takes up, and their total, see
+    /// [api::PantryAPI::get_storage_info].
+    ///
+    /// Together with [PantryClient::delete_llm], lets you offer cleanup:
+    ///
+    /// ```no_run
+    /// # use pantry_rs::PantryClient;
+    /// # fn confirm(_: &str) -> bool { false }
+    /// # async fn example(pantry: PantryClient) -> Result<(), Box<dyn std::error::Error>> {
+    /// let storage = pantry.get_storage_info().await?;
+    /// println!("Pantry is using {} GB", storage.total_bytes >> 30);
+    /// for model in storage.largest_first() {
+    ///     if confirm(&format!("Delete {}?", model.id)) {
+    ///         pantry.delete_llm(model.llm_uuid).await?;
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_storage_info(&self) -> Result<StorageInfo, PantryError> {
+        self.client
+            .get_storage_info(self.user_id, self.api_key.clone())
+            .await
+    }
+
+    /// Previews which downloaded LLM [PantryClient::load_llm_flex] or
+    /// [PantryClient::bare_model_flex] would pick, without loading anything.
+    ///
+    /// Fetches the available LLMs and applies the filter and preference locally, see
+    /// [api::select_llm]. Pass the result's uuid to the `_id` variant to get exactly
+    /// that LLM, even if the set of LLMs changes in between.
+    ///
+    /// ```no_run
+    /// # use pantry_rs::{LLMFilter, PantryClient};
+    /// # fn confirm(_: &str) -> bool { false }
+    /// # async fn example(pantry: PantryClient, filter: LLMFilter) -> Result<(), Box<dyn std::error::Error>> {
+    /// let llm = pantry.select_llm(Some(filter), None).await?;
+    /// if confirm(&llm.name) {
+    ///     pantry.load_llm(llm.id).await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Fails with [PantryError::LlmNotFound] if nothing passes the filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` — A [LLMFilter] object, for what _must_ be true of an LLM to use it.
+    /// * `preference` — A [LLMPreference] object, for how to rank and then select from the LLMs
+    ///   that pass the filter.
+    pub async fn select_llm(
+        &self,
+        filter: Option<LLMFilter>,
+        preference: Option<LLMPreference>,
+    ) -> Result<LLMStatus, PantryError> {
+        let llms = self.get_available_llms().await?;
+        select_from(&llms, filter, preference)
+    }
+
+    /// Like [PantryClient::select_llm], but from the running LLMs, previewing
+    /// [PantryClient::create_session_flex].
+    pub async fn select_running_llm(
+        &self,
+        filter: Option<LLMFilter>,
+        preference: Option<LLMPreference>,
+    ) -> Result<LLMStatus, PantryError> {
+        let llms = self.get_running_llms().await?;
+        select_from(&llms, filter, preference)
+    }
+
+    /// Gets this user's sessions, across all LLMs.
+    ///
+    /// Pantry keeps sessions until they're deleted, so apps that create many should
+    /// clean up after themselves with [PantryClient::delete_session].
+    pub async fn get_sessions(&self) -> Result<Vec<LLMSessionStatus>, PantryError> {
+        self.client
+            .get_sessions(self.user_id, self.api_key.clone())
+            .await
+    }
+
+    /// Deletes a session, freeing its state on the host.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` — the `id` of an [LLMSession] or [LLMSessionStatus].
+    pub async fn delete_session(&self, session_id: SessionId) -> Result<(), PantryError> {
+        self.client
+            .delete_session(self.user_id, self.api_key.clone(), session_id)
+            .await
+    }
+
     /// Gets the available LLMs.
     pub async fn get_available_llms(&self) -> Result<Vec<LLMStatus>, PantryError> {
         let v = self
@@ -284,7 +717,7 @@ impl PantryClient {
     /// Gets a request status
     pub async fn get_request_status(
         &self,
-        request_id: Uuid,
+        request_id: RequestId,
     ) -> Result<UserRequestStatus, PantryError> {
         let v = self
             .client
@@ -294,23 +727,293 @@ impl PantryClient {
         Ok(v)
     }
 
+    /// Waits for the system owner to accept or deny a request.
+    ///
+    /// Polls [PantryClient::get_request_status], starting at twice a second and backing off
+    /// to every ten seconds, since owners can take a while to get to the UI.
+    ///
+    /// ```no_run
+    /// # use pantry_rs::interface::{RequestOutcome, UserPermissions};
+    /// # use pantry_rs::PantryClient;
+    /// # use std::time::Duration;
+    /// # async fn example(perms: UserPermissions) -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pantry, req) = PantryClient::register("my app".into(), perms, None).await?;
+    /// match pantry.await_request(req.id, Duration::from_secs(120)).await? {
+    ///     RequestOutcome::Accepted(_) => println!("Good to go"),
+    ///     RequestOutcome::Denied(_) => println!("Permissions denied"),
+    ///     RequestOutcome::TimedOut(_) => println!("Nobody answered"),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `request_id` — UUID of the request, from the [UserRequestStatus] returned when
+    ///   making it.
+    /// * `timeout` — How long to wait before giving up with [RequestOutcome::TimedOut].
+    pub async fn await_request(
+        &self,
+        request_id: RequestId,
+        timeout: Duration,
+    ) -> Result<RequestOutcome, PantryError> {
+        let schedule = RetryPolicy {
+            max_attempts: u32::MAX,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: false,
+        };
+        let deadline = Instant::now() + timeout;
+        let mut poll = 0;
+        loop {
+            let status = self.get_request_status(request_id).await?;
+            if status.complete {
+                #[cfg(feature = "streaming")]
+                self.publish(|| ClientEvent::RequestDecided(status.clone()));
+                return Ok(match status.accepted {
+                    true => RequestOutcome::Accepted(status),
+                    false => RequestOutcome::Denied(status),
+                });
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(RequestOutcome::TimedOut(status));
+            }
+            Delay::new(schedule.backoff(poll).min(deadline - now)).await;
+            poll += 1;
+        }
+    }
+
+    /// Gets the daemon's version and the endpoints it serves, see [api::PantryAPI::server_info].
+    ///
+    /// ```no_run
+    /// # use pantry_rs::{PantryClient, PantryError};
+    /// # fn show(_: &str) {}
+    /// # async fn example(pantry: PantryClient) {
+    /// match pantry.server_info().await {
+    ///     Ok(info) if info.is_compatible() => {}
+    ///     Ok(_) | Err(PantryError::Unsupported(_)) => show("Pantry is too old, please update it"),
+    ///     Err(_) => show("Pantry isn't running"),
+    /// }
+    /// # }
+    /// ```
+    pub async fn server_info(&self) -> Result<ServerInfo, PantryError> {
+        self.client.server_info().await
+    }
+
+    /// Waits until the daemon is reachable, e.g. right after launching it.
+    ///
+    /// Fails with [PantryError::Timeout] if it isn't within `timeout`, and with
+    /// [PantryError::Unsupported] if it's reachable but too old to report its info.
+    /// Other errors are returned immediately.
+    pub async fn wait_for_server(&self, timeout: Duration) -> Result<ServerInfo, PantryError> {
+        let schedule = RetryPolicy {
+            max_attempts: u32::MAX,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            multiplier: 2.0,
+            jitter: false,
+        };
+        let deadline = Instant::now() + timeout;
+        let mut attempt = 0;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let api = self
+                .client
+                .with_timeout(Some(remaining))
+                .with_retry(RetryPolicy::none());
+            match api.server_info().await {
+                Err(e) if e.is_retryable() => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(PantryError::Timeout(timeout));
+                    }
+                    Delay::new(schedule.backoff(attempt).min(deadline - now)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Subscribes to server events, like LLMs being loaded or unloaded, downloads
+    /// finishing, and requests being accepted or denied.
+    ///
+    /// Useful for apps that want to react as soon as the owner clicks "accept" instead of
+    /// polling. The stream stays open until Pantry shuts down or it's dropped.
+    ///
+    /// ```no_run
+    /// # use futures::StreamExt;
+    /// # use pantry_rs::interface::ServerEvent;
+    /// # use pantry_rs::PantryClient;
+    /// # async fn example(pantry: PantryClient) -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut events = pantry.subscribe_events().await?;
+    /// while let Some(event) = events.next().await {
+    ///     if let ServerEvent::RequestAccepted { request_id } = event? {
+    ///         println!("{} got accepted", request_id);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// The stream ends when the client is [shut down](PantryClient::shutdown).
+    #[cfg(feature = "streaming")]
+    pub async fn subscribe_events(&self) -> Result<api::ServerEventStream, PantryError> {
+        let lifecycle = self.client.lifecycle();
+        if let Some(lifecycle) = lifecycle {
+            lifecycle.check()?;
+        }
+        let events = self
+            .client
+            .subscribe_events(self.user_id, self.api_key.clone())
+            .await?;
+        Ok(match lifecycle {
+            Some(lifecycle) => Box::pin(events.take_until(lifecycle.closed())),
+            None => events,
+        })
+    }
+
+    /// Shuts the client down, and every copy of it, see [lifecycle].
+    ///
+    /// Server event subscriptions end right away. Prompts in flight get up to
+    /// `options.drain_timeout` to finish, after which their streams end with
+    /// [PantryError::ClientShutDown], and if `options.interrupt_sessions` is set their
+    /// inference is interrupted. From then on, prompting and subscribing fail with
+    /// [PantryError::ClientShutDown].
+    #[cfg(feature = "streaming")]
+    pub async fn shutdown(&self, options: lifecycle::ShutdownOptions) -> lifecycle::ShutdownReport {
+        let Some(lifecycle) = self.client.lifecycle() else {
+            return lifecycle::ShutdownReport::default();
+        };
+        let (drained, cut_off) = lifecycle.shut_down(options.drain_timeout).await;
+        if options.interrupt_sessions {
+            // Nothing more can be done about a prompt that won't be interrupted.
+            futures::future::join_all(cut_off.iter().map(|prompt| {
+                self.client.interrupt_session(
+                    prompt.user_id,
+                    prompt.api_key.clone(),
+                    prompt.llm_uuid,
+                    prompt.session_id,
+                )
+            }))
+            .await;
+        }
+        lifecycle::ShutdownReport {
+            drained,
+            cut_off: cut_off.len(),
+        }
+    }
+
+    /// Gets all of this user's requests that are still waiting on the system owner.
+    pub async fn get_pending_requests(&self) -> Result<Vec<UserRequestStatus>, PantryError> {
+        self.client
+            .get_pending_requests(self.user_id, self.api_key.clone())
+            .await
+    }
+
+    /// Withdraws a pending request.
+    ///
+    /// # Arguments
+    ///
+    /// * `request_id` — UUID of the request, see [PantryClient::get_pending_requests].
+    pub async fn cancel_request(
+        &self,
+        request_id: RequestId,
+    ) -> Result<UserRequestStatus, PantryError> {
+        self.client
+            .cancel_request(self.user_id, self.api_key.clone(), request_id)
+            .await
+    }
+
+    /// Gets the permissions this user currently has.
+    pub async fn get_permissions(&self) -> Result<UserPermissions, PantryError> {
+        self.client
+            .get_permissions(self.user_id, self.api_key.clone())
+            .await
+    }
+
+    /// Makes sure this user has at least `perms`, requesting whatever is missing.
+    ///
+    /// Unlike [PantryClient::request_permissions], this only bothers the system owner if
+    /// something is actually missing, so it's safe to call every time your app starts.
+    /// Returns `None` if nothing had to be requested. Otherwise the request asks for the
+    /// granted permissions plus the missing ones, so accepting it never takes anything away.
+    ///
+    /// # Arguments
+    ///
+    /// * `perms` — The permissions this api user needs.
+    pub async fn ensure_permissions(
+        &self,
+        perms: UserPermissions,
+    ) -> Result<Option<UserRequestStatus>, PantryError> {
+        // Lapsed permissions have to be asked for again.
+        let granted = self.get_permissions().await?.in_effect();
+        if perms.is_subset_of(&granted) {
+            return Ok(None);
+        }
+        // If they can't share a grant without widening it, see [UserPermissions::union],
+        // it's up to the system owner.
+        let merged = granted.union(&perms).unwrap_or(perms);
+        let status = self.request_permissions(merged).await?;
+        Ok(Some(status))
+    }
+
     /// Request additional permissions.
     ///
     /// # Arguments
     ///
-    /// * `permissions` — The permissions this api user wants.
+    /// * `permissions` — The permissions this api user wants. Set
+    ///   [UserPermissions::llm_scope] to only ask for them on some LLMs.
     pub async fn request_permissions(
         &self,
         perms: UserPermissions,
     ) -> Result<UserRequestStatus, PantryError> {
         self.client
-            .request_permissions(self.user_id.clone(), self.api_key.clone(), perms)
+            .request_permissions(self.user_id, self.api_key.clone(), perms)
+            .await
+            .map(|status| self.submitted(status))
+    }
+
+    /// Like [PantryClient::request_permissions], for permissions that lapse `duration`
+    /// after they're accepted, e.g. sessions with llama models for a day:
+    ///
+    /// ```no_run
+    /// # use pantry_rs::interface::UserPermissions;
+    /// # use pantry_rs::PantryClient;
+    /// # use std::time::Duration;
+    /// # async fn example(pantry: PantryClient) -> Result<(), Box<dyn std::error::Error>> {
+    /// let perms = UserPermissions {
+    ///     perm_session: true,
+    ///     llm_scope: vec!["llama".into()],
+    ///     ..Default::default()
+    /// };
+    /// pantry.request_permissions_for(perms, Duration::from_secs(24 * 60 * 60)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Pantry refuses grants that can't be merged with the granted permissions without
+    /// widening either, e.g. when those don't lapse or are for other LLMs, see
+    /// [UserPermissions::union].
+    pub async fn request_permissions_for(
+        &self,
+        perms: UserPermissions,
+        duration: Duration,
+    ) -> Result<UserRequestStatus, PantryError> {
+        self.client
+            .request_permissions_with(self.user_id, self.api_key.clone(), perms, duration)
             .await
+            .map(|status| self.submitted(status))
     }
 
     /// Creates a request to download a new model. Must be accepted by the system
     /// owner (currently via the UI).
     ///
+    /// The entry is checked with [LLMRegistryEntry::validate] before the request is sent,
+    /// and so are its urls, see [LLMRegistryEntry::check_urls].
+    ///
     /// # Arguments
     ///
     /// * `llm_registry_entry` — A valid LLM registry entry to download. This specifies
@@ -320,27 +1023,107 @@ impl PantryClient {
         &self,
         reg: LLMRegistryEntry,
     ) -> Result<UserRequestStatus, PantryError> {
+        self.file_download_request(reg, None).await
+    }
+
+    /// Like [PantryClient::request_download_llm], storing the model in `target_dir` on
+    /// the Pantry machine instead of Pantry's models directory, e.g. on an external
+    /// drive or network share.
+    pub async fn request_download_llm_to(
+        &self,
+        reg: LLMRegistryEntry,
+        target_dir: impl Into<String>,
+    ) -> Result<UserRequestStatus, PantryError> {
+        self.file_download_request(reg, Some(target_dir.into()))
+            .await
+    }
+
+    async fn file_download_request(
+        &self,
+        reg: LLMRegistryEntry,
+        target_dir: Option<String>,
+    ) -> Result<UserRequestStatus, PantryError> {
+        reg.validate()?;
+        #[cfg(not(target_arch = "wasm32"))]
+        let reg = reg.check_urls(registry::DEFAULT_URL_CHECK_TIMEOUT).await?;
         self.client
-            .request_download(self.user_id.clone(), self.api_key.clone(), reg)
+            .request_download(self.user_id, self.api_key.clone(), reg, target_dir)
             .await
+            .map(|status| self.submitted(status))
     }
 
     /// Download a new model.
     ///
+    /// The entry is checked with [LLMRegistryEntry::validate] before the request is sent,
+    /// and so are its urls, see [LLMRegistryEntry::check_urls].
+    ///
     /// # Arguments
     ///
     /// * `llm_registry_entry` — A valid LLM registry entry to download. This specifies
     /// the location of the model as well as any metadata. For better usability, try
     /// being comprehensive about this.
-    pub async fn download_llm(&self, reg: LLMRegistryEntry) -> Result<Uuid, PantryError> {
+    pub async fn download_llm(&self, reg: LLMRegistryEntry) -> Result<LlmUuid, PantryError> {
+        self.start_download(reg, None).await
+    }
+
+    /// Like [PantryClient::download_llm], storing the model in `target_dir` on the
+    /// Pantry machine instead of Pantry's models directory.
+    pub async fn download_llm_to(
+        &self,
+        reg: LLMRegistryEntry,
+        target_dir: impl Into<String>,
+    ) -> Result<LlmUuid, PantryError> {
+        self.start_download(reg, Some(target_dir.into())).await
+    }
+
+    /// Compares the LLMs Pantry has against the curated index, and suggests newer
+    /// releases, quantizations and successors. See [catalog::find_updates].
+    ///
+    /// ```no_run
+    /// # use pantry_rs::PantryClient;
+    /// # async fn example(pantry: PantryClient) -> Result<(), Box<dyn std::error::Error>> {
+    /// for update in pantry.check_updates().await? {
+    ///     println!("{} can be replaced by {}", update.installed.id, update.request.llm_registry_entry.id);
+    ///     pantry.request_download_llm(update.request.llm_registry_entry).await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Requires the [UserPermissions::perm_view_llms] permission.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn check_updates(&self) -> Result<Vec<catalog::ModelUpdate>, PantryError> {
+        self.check_updates_at(catalog::PANTRY_REGISTRY_URL).await
+    }
+
+    /// Like [PantryClient::check_updates], against the index at `index_url`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn check_updates_at(
+        &self,
+        index_url: &str,
+    ) -> Result<Vec<catalog::ModelUpdate>, PantryError> {
+        let installed = self.get_available_llms().await?;
+        let index = catalog::fetch_registry(index_url).await?;
+        Ok(catalog::find_updates(&installed, &index))
+    }
+
+    async fn start_download(
+        &self,
+        reg: LLMRegistryEntry,
+        target_dir: Option<String>,
+    ) -> Result<LlmUuid, PantryError> {
+        reg.validate()?;
+        #[cfg(not(target_arch = "wasm32"))]
+        let reg = reg.check_urls(registry::DEFAULT_URL_CHECK_TIMEOUT).await?;
         let val = self
             .client
-            .download_llm(self.user_id.clone(), self.api_key.clone(), reg)
+            .download_llm(self.user_id, self.api_key.clone(), reg, target_dir)
             .await?;
         let string_uuid = val.as_str().ok_or(PantryError::OtherFailure(
             "failed to deserialize uuid".into(),
         ))?;
-        Uuid::parse_str(string_uuid)
+        string_uuid
+            .parse()
             .map_err(|e| PantryError::OtherFailure("Failed to Deserialize UUID".into()))
     }
 
@@ -360,7 +1143,7 @@ impl PantryClient {
     /// * `llm_registry_entry` — A valid LLM registry entry to download. This specifies
     /// the location of the model as well as any metadata. For better usability, try
     /// being comprehensive about this.
-    pub async fn get_or_download_llm(&self, reg: LLMRegistryEntry) -> Result<Uuid, PantryError> {
+    pub async fn get_or_download_llm(&self, reg: LLMRegistryEntry) -> Result<LlmUuid, PantryError> {
         let val = self
             .client
             .get_or_download_llm(self.user_id.clone(), self.api_key.clone(), reg)
@@ -368,14 +1151,19 @@ impl PantryClient {
         let string_uuid = val.as_str().ok_or(PantryError::OtherFailure(
             "failed to deserialize uuid".into(),
         ))?;
-        Uuid::parse_str(string_uuid)
+        string_uuid
+            .parse()
             .map_err(|e| PantryError::OtherFailure("Failed to Deserialize UUID".into()))
     }
 
-    pub async fn request_load_llm(&self, llm_uuid: Uuid) -> Result<UserRequestStatus, PantryError> {
-        self.client
+    pub async fn request_load_llm(
+        &self,
+        llm_uuid: LlmUuid,
+    ) -> Result<UserRequestStatus, PantryError> {
+        self.client
             .request_load(self.user_id.clone(), self.api_key.clone(), llm_uuid)
             .await
+            .map(|status| self.submitted(status))
     }
 
     /// Requests a load, but doesn't predetermine the exact LLM ahead of time.
@@ -397,6 +1185,7 @@ impl PantryClient {
                 preference,
             )
             .await
+            .map(|status| self.submitted(status))
     }
 
     /// Requests an LLM be shutdown, conserving resources. This should
@@ -407,20 +1196,119 @@ impl PantryClient {
     /// * `llm_id` — UUID of the LLM. Find running llms via [PantryClient::get_running_llms].
     pub async fn request_unload_llm(
         &self,
-        llm_uuid: Uuid,
+        llm_uuid: LlmUuid,
     ) -> Result<UserRequestStatus, PantryError> {
         self.client
             .request_unload(self.user_id.clone(), self.api_key.clone(), llm_uuid)
             .await
+            .map(|status| self.submitted(status))
+    }
+    /// Requests an LLM be deleted from disk, freeing up space. Must be accepted by the
+    /// system owner (currently via the UI).
+    ///
+    /// # Arguments
+    ///
+    /// * `llm_uuid` — UUID of the LLM. Find downloaded llms via [PantryClient::get_available_llms].
+    pub async fn request_delete_llm(
+        &self,
+        llm_uuid: LlmUuid,
+    ) -> Result<UserRequestStatus, PantryError> {
+        self.client
+            .request_delete(self.user_id, self.api_key.clone(), llm_uuid)
+            .await
+            .map(|status| self.submitted(status))
     }
+
+    /// Deletes a downloaded LLM from disk. If it's running, it gets unloaded first.
+    ///
+    /// Requires the [UserPermissions::perm_download_llm] permission.
+    ///
+    /// # Arguments
+    ///
+    /// * `llm_uuid` — UUID of the LLM. Find downloaded llms via [PantryClient::get_available_llms].
+    pub async fn delete_llm(&self, llm_uuid: LlmUuid) -> Result<LLMStatus, PantryError> {
+        self.client
+            .delete_llm(self.user_id, self.api_key.clone(), llm_uuid)
+            .await
+    }
+
+    /// Creates a request to add a model file that's already on disk, instead of
+    /// downloading it. Must be accepted by the system owner (currently via the UI).
+    ///
+    /// The entry is checked with [LLMRegistryEntry::validate_local] before the request is sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` — Path of the model file. Relative paths are resolved locally, so only
+    ///   use them when Pantry runs on the same machine.
+    /// * `llm_registry_entry` — Metadata for the model. The url is ignored.
+    pub async fn request_register_local(
+        &self,
+        path: impl AsRef<Path>,
+        mut reg: LLMRegistryEntry,
+    ) -> Result<UserRequestStatus, PantryError> {
+        reg.local = true;
+        reg.validate_local()?;
+        self.client
+            .request_register_local(
+                self.user_id,
+                self.api_key.clone(),
+                model_path(path.as_ref())?,
+                reg,
+            )
+            .await
+            .map(|status| self.submitted(status))
+    }
+
+    /// Adds a model file that's already on disk, instead of downloading it.
+    ///
+    /// Requires the [UserPermissions::perm_download_llm] permission. The entry is
+    /// checked with [LLMRegistryEntry::validate_local] before the request is sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` — Path of the model file. Relative paths are resolved locally, so only
+    ///   use them when Pantry runs on the same machine.
+    /// * `llm_registry_entry` — Metadata for the model. The url is ignored.
+    pub async fn register_local_model(
+        &self,
+        path: impl AsRef<Path>,
+        mut reg: LLMRegistryEntry,
+    ) -> Result<LLMStatus, PantryError> {
+        reg.local = true;
+        reg.validate_local()?;
+        self.client
+            .register_local_model(
+                self.user_id,
+                self.api_key.clone(),
+                model_path(path.as_ref())?,
+                reg,
+            )
+            .await
+    }
+
     /// Requests Pantry to load a specific LLM.
     ///
     /// # Arguments
     ///
     /// * `llm_id` — A UUID or ID for the LLM you want to load. Find one via [PantryClient::get_available_llms].
     pub async fn load_llm(&self, llm: String) -> Result<LLMRunningStatus, PantryError> {
+        self.load_llm_with(llm, LoadOptions::default()).await
+    }
+
+    /// Like [PantryClient::load_llm], limiting the resources the LLM may use.
+    ///
+    /// # Arguments
+    ///
+    /// * `llm_id` — A UUID or ID for the LLM you want to load.
+    /// * `options` — GPU layers, RAM cap etc. for this load, see [LoadOptions].
+    pub async fn load_llm_with(
+        &self,
+        llm: String,
+        options: LoadOptions,
+    ) -> Result<LLMRunningStatus, PantryError> {
         self.client
-            .load_llm(self.user_id.clone(), self.api_key.clone(), llm)
+            .load_llm(self.user_id, self.api_key.clone(), llm, options)
             .await
     }
 
@@ -431,19 +1319,38 @@ impl PantryClient {
     /// # Arguments
     ///
     /// * `filter` — A [LLMFilter] object, for what _must_ be true of an LLM to load it.
-    /// * `preference` — A [LLMPreference] object, for how to rank and then select from the LLMs
+    /// * `preference` — A [LLMPreference] object, for how to rank and then select from the LLMs
     /// that pass the filter.
     pub async fn load_llm_flex(
         &self,
         filter: Option<LLMFilter>,
         preference: Option<LLMPreference>,
+    ) -> Result<LLMRunningStatus, PantryError> {
+        self.load_llm_flex_with(filter, preference, LoadOptions::default())
+            .await
+    }
+
+    /// Like [PantryClient::load_llm_flex], limiting the resources the LLM may use.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` — A [LLMFilter] object, for what _must_ be true of an LLM to load it.
+    /// * `preference` — A [LLMPreference] object, for how to rank and then select from the LLMs
+    ///   that pass the filter.
+    /// * `options` — GPU layers, RAM cap etc. for this load, see [LoadOptions].
+    pub async fn load_llm_flex_with(
+        &self,
+        filter: Option<LLMFilter>,
+        preference: Option<LLMPreference>,
+        options: LoadOptions,
     ) -> Result<LLMRunningStatus, PantryError> {
         self.client
             .load_llm_flex(
-                self.user_id.clone(),
+                self.user_id,
                 self.api_key.clone(),
                 filter,
                 preference,
+                options,
             )
             .await
     }
@@ -462,6 +1369,43 @@ impl PantryClient {
             .await
     }
 
+    /// Unloads whichever running LLM `filter` and `preference` pick, like
+    /// [PantryClient::select_running_llm] followed by [PantryClient::unload_llm].
+    ///
+    /// Requires the [UserPermissions::perm_unload_llm] and
+    /// [UserPermissions::perm_view_llms] permissions. Fails with [PantryError::LlmNotFound]
+    /// if no running LLM passes the filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` — A [LLMFilter] object, for what _must_ be true of an LLM to unload it.
+    /// * `preference` — A [LLMPreference] object, for how to rank and then select from the LLMs
+    ///   that pass the filter.
+    pub async fn unload_llm_flex(
+        &self,
+        filter: Option<LLMFilter>,
+        preference: Option<LLMPreference>,
+    ) -> Result<LLMStatus, PantryError> {
+        let llm = self.select_running_llm(filter, preference).await?;
+        self.unload_llm(llm.uuid.to_string()).await
+    }
+
+    /// Unloads an LLM and loads it again, e.g. after it got into a bad state.
+    ///
+    /// Returns once the LLM is loaded again. An LLM that wasn't running just gets loaded.
+    /// Requires the [UserPermissions::perm_unload_llm] and [UserPermissions::perm_load_llm]
+    /// permissions.
+    ///
+    /// # Arguments
+    /// * `llm_uuid` — UUID of the LLM.
+    pub async fn reload_llm(&self, llm_uuid: LlmUuid) -> Result<LLMRunningStatus, PantryError> {
+        match self.unload_llm(llm_uuid.to_string()).await {
+            Ok(_) | Err(PantryError::LlmNotRunning(_)) => {}
+            Err(e) => return Err(e),
+        }
+        self.load_llm(llm_uuid.to_string()).await
+    }
+
     /// Gets the bare path of a model, useful if you want to use an LLM with your own runner.
     ///
     /// Requires the [UserPermissions::perm_bare_model] permission.
@@ -503,13 +1447,199 @@ impl PantryClient {
         Ok((resp.model, resp.path))
     }
 
+    /// Has Pantry put a bare model's file at `destination`, for when the path
+    /// [PantryClient::bare_model] returns can't be reached, e.g. from a Flatpak or macOS
+    /// app container. Returns where the file ended up:
+    ///
+    /// ```no_run
+    /// # use pantry_rs::interface::ExportMode;
+    /// # use pantry_rs::PantryClient;
+    /// # async fn example(pantry: PantryClient, llm_id: String, app_data_dir: String) -> Result<(), Box<dyn std::error::Error>> {
+    /// let (model, path) = pantry
+    ///     .export_bare_model(llm_id, app_data_dir, ExportMode::Hardlink)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Requires the [UserPermissions::perm_bare_model] permission.
+    ///
+    /// # Arguments
+    /// * `llm_id` — UUID of the LLM, or the LLM's id.
+    /// * `destination` — Path on the Pantry machine to put the file at, or a directory
+    ///   to put it in under its own name.
+    /// * `mode` — Whether to copy or link the file, see [ExportMode].
+    pub async fn export_bare_model(
+        &self,
+        llm_id: String,
+        destination: impl Into<String>,
+        mode: ExportMode,
+    ) -> Result<(LLMStatus, String), PantryError> {
+        let destination = destination.into();
+        if destination.trim().is_empty() {
+            return Err(PantryError::OtherFailure(
+                "export destination must not be empty".into(),
+            ));
+        }
+        let resp = self
+            .client
+            .export_bare_model(
+                self.user_id,
+                self.api_key.clone(),
+                llm_id,
+                destination,
+                mode,
+            )
+            .await?;
+        Ok((resp.model, resp.path))
+    }
+
+    /// Like [PantryClient::bare_model], pinning the file so Pantry won't delete or move
+    /// it until the lease is released or `ttl` runs out. See [lease].
+    ///
+    /// Requires the [UserPermissions::perm_bare_model] permission.
+    ///
+    /// # Arguments
+    /// * `llm_id` — UUID of the LLM, or the LLM's id.
+    /// * `ttl` — How long to pin the file for at most.
+    pub async fn bare_model_lease(
+        &self,
+        llm_id: String,
+        ttl: Duration,
+    ) -> Result<BareModelLease, PantryError> {
+        let resp = self
+            .client
+            .bare_model_lease(self.user_id, self.api_key.clone(), llm_id, ttl)
+            .await?;
+        Ok(BareModelLease::new(self.clone(), resp))
+    }
+
+    /// Releases a lease from [PantryClient::bare_model_lease] by id, e.g. one that was
+    /// persisted across restarts. See [BareModelLease::release].
+    pub async fn release_bare_model(&self, lease_id: LeaseId) -> Result<(), PantryError> {
+        self.client
+            .release_bare_model(self.user_id, self.api_key.clone(), lease_id)
+            .await
+    }
+
+    /// Lists the LoRA adapters Pantry has for an LLM, including ones its registry
+    /// entry offers that haven't been downloaded yet.
+    ///
+    /// Requires the [UserPermissions::perm_view_llms] permission.
+    ///
+    /// # Arguments
+    /// * `llm_id` — UUID of the base LLM, or its id.
+    pub async fn list_adapters(&self, llm_id: String) -> Result<Vec<AdapterStatus>, PantryError> {
+        self.client
+            .list_adapters(self.user_id, self.api_key.clone(), llm_id)
+            .await
+    }
+
+    /// Downloads a LoRA adapter for an LLM, e.g. one from [LLMRegistryEntry::adapters]:
+    ///
+    /// ```no_run
+    /// # use pantry_rs::interface::AdapterEntry;
+    /// # use pantry_rs::PantryClient;
+    /// # async fn example(pantry: PantryClient, llm_id: String, url: String) -> Result<(), Box<dyn std::error::Error>> {
+    /// let adapter = pantry.download_adapter(llm_id, AdapterEntry::new("sql", url)).await?;
+    /// pantry.apply_adapter(adapter.llm_uuid, "sql").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Requires the [UserPermissions::perm_download_llm] permission.
+    ///
+    /// # Arguments
+    /// * `llm_id` — UUID of the base LLM, or its id.
+    /// * `adapter` — The adapter, checked with [AdapterEntry::validate] first.
+    pub async fn download_adapter(
+        &self,
+        llm_id: String,
+        adapter: AdapterEntry,
+    ) -> Result<AdapterStatus, PantryError> {
+        adapter.validate()?;
+        self.client
+            .download_adapter(self.user_id, self.api_key.clone(), llm_id, adapter)
+            .await
+    }
+
+    /// Applies a downloaded adapter to a running LLM, until it's removed or the LLM is
+    /// unloaded. Returns the LLM with the adapter in [LLMStatus::applied_adapters].
+    ///
+    /// Requires the [UserPermissions::perm_load_llm] permission.
+    pub async fn apply_adapter(
+        &self,
+        llm_uuid: LlmUuid,
+        adapter_id: impl Into<String>,
+    ) -> Result<LLMStatus, PantryError> {
+        self.client
+            .apply_adapter(
+                self.user_id,
+                self.api_key.clone(),
+                llm_uuid,
+                adapter_id.into(),
+            )
+            .await
+    }
+
+    /// Takes an adapter applied with [PantryClient::apply_adapter] off a running LLM.
+    ///
+    /// Requires the [UserPermissions::perm_load_llm] permission.
+    pub async fn remove_adapter(
+        &self,
+        llm_uuid: LlmUuid,
+        adapter_id: impl Into<String>,
+    ) -> Result<LLMStatus, PantryError> {
+        self.client
+            .remove_adapter(
+                self.user_id,
+                self.api_key.clone(),
+                llm_uuid,
+                adapter_id.into(),
+            )
+            .await
+    }
+
+    /// Gives Pantry the secret `connector` authenticates with, e.g. the OpenAI API key.
+    /// Secrets aren't echoed back by status calls the way [LLMStatus::config] is, so
+    /// set keys here and keep them out of registry entries:
+    ///
+    /// ```no_run
+    /// # use pantry_rs::interface::{LLMConnectorType, LLMRegistryEntry};
+    /// # use pantry_rs::PantryClient;
+    /// # async fn example(pantry: PantryClient, openai_key: String) -> Result<(), Box<dyn std::error::Error>> {
+    /// pantry
+    ///     .set_connector_secret(LLMConnectorType::OpenAI, openai_key)
+    ///     .await?;
+    /// pantry.download_llm(LLMRegistryEntry::openai("gpt-4").build()?).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Requires the [UserPermissions::perm_download_llm] permission.
+    pub async fn set_connector_secret(
+        &self,
+        connector: LLMConnectorType,
+        secret: impl Into<String>,
+    ) -> Result<(), PantryError> {
+        let secret = secret.into();
+        if secret.trim().is_empty() {
+            return Err(PantryError::OtherFailure(
+                "connector secret must not be empty".into(),
+            ));
+        }
+        self.client
+            .set_connector_secret(self.user_id, self.api_key.clone(), connector, secret)
+            .await
+    }
+
     /// Gets the status of an LLM.
     ///
     /// Requires the [UserPermissions::perm_view_llms] permission.
     ///
     /// # Arguments
     /// * `llm_id` — UUID of the LLM.
-    pub async fn llm_status(&self, llm_id: Uuid) -> Result<LLMStatus, PantryError> {
+    pub async fn llm_status(&self, llm_id: LlmUuid) -> Result<LLMStatus, PantryError> {
         let resp = self
             .client
             .get_llm_status(self.user_id.clone(), self.api_key.clone(), llm_id)
@@ -524,68 +1654,732 @@ impl PantryClient {
     /// thread.
     ///
     /// # Arguments
-    /// * `llm_id` — UUID of the LLM.
-    /// * `progress_callback` — Takes in a float of download progress. Use it to print or
-    /// provide info.
+    /// * `llm_id` — UUID of the LLM.
+    /// * `progress_callback` — Called with a [DownloadProgress] about once a second. Use it
+    ///   to print or render a progress bar.
     pub async fn await_download<F>(
         &self,
-        llm_id: Uuid,
+        llm_id: LlmUuid,
         mut progress_callback: F,
     ) -> Result<LLMStatus, PantryError>
     where
-        F: FnMut(f32) -> (),
+        F: FnMut(DownloadProgress),
     {
         let mut status = self.llm_status(llm_id).await?;
         let one_sec = time::Duration::from_secs(1);
         while status.download_progress < 100.0 {
-            progress_callback(status.download_progress);
+            progress_callback(self.download_progressed(&status));
             Delay::new(one_sec).await;
             status = self.llm_status(llm_id).await?;
         }
-        progress_callback(status.download_progress);
+        progress_callback(self.download_progressed(&status));
         Ok(status)
     }
+
+    /// Stream of [DownloadProgress] updates for an LLM, polled every `interval`.
+    ///
+    /// The stream ends after yielding [DownloadPhase::Complete], or after the first error.
+    /// Requires [UserPermissions::perm_view_llms] permission.
+    ///
+    /// # Arguments
+    /// * `llm_id` — UUID of the LLM, as returned by [PantryClient::download_llm].
+    /// * `interval` — How often to ask Pantry for progress.
+    pub fn download_progress(
+        &self,
+        llm_id: LlmUuid,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<DownloadProgress, PantryError>> + Send {
+        futures::stream::unfold(Some((self.clone(), true)), move |state| async move {
+            let (client, first) = state?;
+            if !first {
+                Delay::new(interval).await;
+            }
+            match client.llm_status(llm_id).await {
+                Ok(status) => {
+                    let progress = client.download_progressed(&status);
+                    let next = match progress.phase {
+                        DownloadPhase::Complete => None,
+                        _ => Some((client, false)),
+                    };
+                    Some((Ok(progress), next))
+                }
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+    }
+
+    /// [DownloadProgress] of `status`, published as [ClientEvent::DownloadProgress].
+    fn download_progressed(&self, status: &LLMStatus) -> DownloadProgress {
+        let progress = DownloadProgress::from(status);
+        #[cfg(feature = "streaming")]
+        self.publish(|| ClientEvent::DownloadProgress {
+            llm_uuid: status.uuid,
+            progress,
+        });
+        progress
+    }
+
+    /// Receives the client's [ClientEvent]s from now on, see [event_bus]. The receiver
+    /// is closed straight away if the client's backend has no event bus.
+    ///
+    /// ```no_run
+    /// # use pantry_rs::PantryClient;
+    /// # async fn example(pantry: PantryClient) {
+    /// let mut events = pantry.client_events();
+    /// while let Ok(event) = events.recv().await {
+    ///     println!("{:?}", event);
+    /// }
+    /// # }
+    /// ```
+    #[cfg(feature = "streaming")]
+    pub fn client_events(&self) -> tokio::sync::broadcast::Receiver<ClientEvent> {
+        match self.client.event_bus() {
+            Some(bus) => bus.subscribe(),
+            None => tokio::sync::broadcast::channel(1).1,
+        }
+    }
+}
+
+fn select_from(
+    llms: &[LLMStatus],
+    filter: Option<LLMFilter>,
+    preference: Option<LLMPreference>,
+) -> Result<LLMStatus, PantryError> {
+    api::select_llm(llms, filter.as_ref(), preference.as_ref())
+        .cloned()
+        .ok_or_else(|| PantryError::LlmNotFound("no LLM passes the filter".into()))
+}
+
+/// Makes relative paths absolute, since Pantry doesn't share our working directory.
+fn model_path(path: &Path) -> Result<String, PantryError> {
+    let path = if path.is_relative() {
+        path.canonicalize()?
+    } else {
+        path.to_path_buf()
+    };
+    path.to_str()
+        .map(String::from)
+        .ok_or_else(|| PantryError::OtherFailure("model path is not valid UTF-8".into()))
+}
+
+/// Builder for a [PantryClient] with non-default connection settings.
+///
+/// ```
+/// # use pantry_rs::{PantryClient, UserId};
+/// # use std::time::Duration;
+/// # let (user_id, api_key) = (UserId::new_v4(), String::new());
+/// let pantry = PantryClient::builder()
+///     .base_url("http://192.168.1.20:9404")
+///     .timeout(Duration::from_secs(30))
+///     .login(user_id, api_key);
+/// ```
+///
+/// Anything left unset falls back to the same defaults as [PantryClient::register]
+/// and [PantryClient::login].
+#[derive(Clone, Debug, Default)]
+pub struct PantryClientBuilder {
+    base_url: Option<String>,
+    socket_path: Option<String>,
+    timeout: Option<Duration>,
+    retry: Option<RetryPolicy>,
+    reconnect: Option<RetryPolicy>,
+    stall_timeout: Option<Duration>,
+    stream_format: Option<StreamFormat>,
+    attachment_transport: Option<AttachmentTransport>,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    rate_limit: Option<RateLimit>,
+    prompt_retry: Option<RetryPolicy>,
+    #[cfg(feature = "streaming")]
+    middleware: Vec<Arc<dyn middleware::PromptMiddleware>>,
+    #[cfg(feature = "templates")]
+    templates: Option<Arc<templates::PromptTemplates>>,
+    fixtures: Option<Arc<Fixtures>>,
+    remote_auth: Option<RemoteAuth>,
+    #[cfg(not(target_arch = "wasm32"))]
+    client: Option<hyper::Client<Connector>>,
+}
+
+impl PantryClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connect over TCP to `url`, e.g. "http://localhost:9404", instead of the unix socket.
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = Some(url.into());
+        self
+    }
+
+    /// Unix socket to connect to when no base url is set. Defaults to
+    /// `$PANTRY_SOCKET`, or [api::DEFAULT_SOCKET_PATH] if that isn't set.
+    pub fn socket_path(mut self, path: impl Into<String>) -> Self {
+        self.socket_path = Some(path.into());
+        self
+    }
+
+    /// Default maximum time to wait on any single call before failing with
+    /// [PantryError::Timeout]. Override per call with [PantryClient::with_timeout].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// How to retry read-only calls when Pantry can't be reached. Use
+    /// [RetryPolicy::none] to fail immediately instead.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// How to reconnect prompt streams whose connection drops midway. Use
+    /// [RetryPolicy::none] to end the stream with an error instead.
+    pub fn reconnect(mut self, policy: RetryPolicy) -> Self {
+        self.reconnect = Some(policy);
+        self
+    }
+
+    /// Fail prompt streams that go `timeout` without an event with
+    /// [PantryError::StreamStalled], interrupting the session. Streams wait forever by
+    /// default; see [api::LLMEventStream::set_stall_timeout] to change it per stream.
+    pub fn stall_timeout(mut self, timeout: Duration) -> Self {
+        self.stall_timeout = Some(timeout);
+        self
+    }
+
+    /// Ask for streamed responses in `format` rather than as server-sent events, e.g.
+    /// [StreamFormat::Ndjson] behind a proxy that doesn't pass those through.
+    pub fn stream_format(mut self, format: StreamFormat) -> Self {
+        self.stream_format = Some(format);
+        self
+    }
+
+    /// Send prompt images as `transport`, see [multimodal]. Base64 by default.
+    pub fn attachment_transport(mut self, transport: AttachmentTransport) -> Self {
+        self.attachment_transport = Some(transport);
+        self
+    }
+
+    /// Report latency, errors and token throughput to `sink`, see [metrics].
+    pub fn metrics(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.metrics = Some(sink);
+        self
+    }
+
+    /// Throttle calls, and the tokens prompts generate, to `limit`, see [rate_limit].
+    /// The limit covers every call from the client, including through copies made with
+    /// [PantryClient::with_timeout] and the sessions it creates.
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limit = Some(limit);
+        self
+    }
+
+    /// When the LLM fails on a prompt before generating anything, e.g. running out of
+    /// memory, prompt again in a fresh session according to `retry`. Off by default.
+    ///
+    /// The fresh session is fed the old one's history along with the prompt, and the old
+    /// one is deleted. The stream carries on in the fresh one, see
+    /// [api::LLMEventStream::session_id], so set [LLMSession::id] to it to keep prompting
+    /// the same conversation. [chat::ChatSession] does so by itself.
+    pub fn prompt_retry(mut self, retry: RetryPolicy) -> Self {
+        self.prompt_retry = Some(retry);
+        self
+    }
+
+    /// Run every prompt, and the events of its reply, through `middleware`, after any
+    /// added before. See [middleware].
+    #[cfg(feature = "streaming")]
+    pub fn middleware(mut self, middleware: impl middleware::PromptMiddleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Templates for [LLMSession::prompt_template], see [templates].
+    #[cfg(feature = "templates")]
+    pub fn templates(mut self, templates: templates::PromptTemplates) -> Self {
+        self.templates = Some(Arc::new(templates));
+        self
+    }
+
+    /// Record every call to a fixture file, or replay calls from one instead of
+    /// talking to Pantry, see [fixtures].
+    pub fn fixtures(mut self, fixtures: Arc<Fixtures>) -> Self {
+        self.fixtures = Some(fixtures);
+        self
+    }
+
+    /// Use an existing hyper client, e.g. to share its connection pool.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn client(mut self, client: hyper::Client<Connector>) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Send `auth` with every call to the base url, for remote instances behind a
+    /// gateway that checks it. See [auth].
+    pub fn remote_auth(mut self, auth: RemoteAuth) -> Self {
+        self.remote_auth = Some(auth);
+        self
+    }
+
+    /// Talk HTTPS using the given TLS settings. Only relevant with a `https://` base url.
+    ///
+    /// Fails if any of the configured certificates or keys can't be parsed.
+    #[cfg(feature = "rustls")]
+    pub fn tls(self, config: &tls::TlsConfig) -> Result<Self, PantryError> {
+        Ok(self.client(hyper::Client::builder().build(config.connector()?)))
+    }
+
+    /// Builds the underlying [PantryAPI] without attaching any credentials.
+    pub fn build_api(self) -> PantryAPI {
+        let mut api = PantryAPI::new(self.base_url);
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(client) = self.client {
+            api.client = client;
+        }
+        if let Some(socket_path) = self.socket_path {
+            api.socket_path = socket_path;
+        }
+        if let Some(retry) = self.retry {
+            api.retry = retry;
+        }
+        api.stall_timeout = self.stall_timeout;
+        if let Some(stream_format) = self.stream_format {
+            api.stream_format = stream_format;
+        }
+        if let Some(transport) = self.attachment_transport {
+            api.attachment_transport = transport;
+        }
+        if let Some(reconnect) = self.reconnect {
+            api.reconnect = reconnect;
+        }
+        if let Some(prompt_retry) = self.prompt_retry {
+            api.prompt_retry = prompt_retry;
+        }
+        api.metrics = self.metrics;
+        api.rate_limiter = self
+            .rate_limit
+            .map(|limit| Arc::new(rate_limit::RateLimiter::new(limit)));
+        #[cfg(feature = "streaming")]
+        {
+            api.middleware = self.middleware;
+        }
+        #[cfg(feature = "templates")]
+        {
+            api.templates = self.templates;
+        }
+        api.fixtures = self.fixtures;
+        api.remote_auth = self.remote_auth;
+        api.timeout = self.timeout;
+        api
+    }
+
+    /// Same as [PantryClient::register], using this builder's settings.
+    pub async fn register(
+        self,
+        name: String,
+        permissions: UserPermissions,
+    ) -> Result<(PantryClient, UserRequestStatus), PantryError> {
+        PantryClient::register_with(Arc::new(self.build_api()), name, permissions).await
+    }
+
+    /// Same as [PantryClient::login], using this builder's settings.
+    pub fn login(self, user_id: UserId, api_key: String) -> PantryClient {
+        PantryClient {
+            user_id,
+            api_key,
+            client: Arc::new(self.build_api()),
+        }
+    }
+
+    /// Same as [PantryClient::login_verified], using this builder's settings.
+    pub async fn login_verified(
+        self,
+        user_id: UserId,
+        api_key: String,
+    ) -> Result<(PantryClient, UserInfo), PantryError> {
+        let pantry = self.login(user_id, api_key);
+        let info = pantry.get_user_info().await?;
+        Ok((pantry, info))
+    }
 }
 
 pub struct LLMSession {
-    pub user_id: Uuid,
+    pub user_id: UserId,
     pub api_key: String,
 
-    pub id: Uuid,
-    pub llm_uuid: Uuid,
+    pub id: SessionId,
+    pub llm_uuid: LlmUuid,
     pub session_parameters: HashMap<String, Value>,
     pub llm_status: LLMStatus,
 
-    pub client: PantryAPI,
+    pub client: Arc<dyn PantryBackend>,
+}
+
+/// The persistable part of an [LLMSession].
+///
+/// Store this in your own database and hand it to [PantryClient::resume_session] after a
+/// restart to keep prompting the same session. Credentials aren't included, store those
+/// separately.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SessionHandle {
+    pub session_id: SessionId,
+    pub llm_uuid: LlmUuid,
+    pub session_parameters: HashMap<String, Value>,
+    pub llm_status: LLMStatus,
 }
 
 impl LLMSession {
+    /// Returns a [SessionHandle] from which this session can be resumed later.
+    pub fn handle(&self) -> SessionHandle {
+        SessionHandle {
+            session_id: self.id,
+            llm_uuid: self.llm_uuid,
+            session_parameters: self.session_parameters.clone(),
+            llm_status: self.llm_status.clone(),
+        }
+    }
+
+    /// Context window of the session's LLM in tokens, if known. Pantry doesn't stop a
+    /// session from outgrowing it, see [chat::ChatSession] for that.
+    pub fn context_length(&self) -> Option<u64> {
+        self.llm_status.context_length()
+    }
+
     /// Prompts a session, triggering inference by the LLM.
     ///
-    /// Requires [UserPermissions::perm_session].
+    /// Requires [UserPermissions::perm_session]. If `max_tokens` or stop sequences are
+    /// set, the stream stops there even if Pantry doesn't.
     ///
     /// # Arguments
     ///
     /// * `prompt` — Prompt for the LLM. Pantry does no preprompting, so if you want a
     /// chatbot style response, you'll need to insert a chatbot type prompt _then_ whatever
     /// the user requested.
-    /// * `parameters` — Things like temperature or k value. Whats available varies by LLM,
-    /// you can find out what an LLM has either in the UI or in the `user_parameters` and
-    /// `user_session_parameters` vectors of an [LLMStatus].
+    /// * `parameters` — Things like temperature or k value, as [InferenceParams] or a
+    ///   plain map. Whats available varies by LLM, you can find out what an LLM has either
+    ///   in the UI or in the `user_parameters` and `user_session_parameters` vectors of an
+    ///   [LLMStatus].
+    #[cfg(feature = "streaming")]
     pub async fn prompt_session(
         &self,
         prompt: String,
-        parameters: HashMap<String, Value>,
+        parameters: impl Into<InferenceParams>,
     ) -> Result<api::LLMEventStream, PantryError> {
-        self.client
+        if let Some(lifecycle) = self.client.lifecycle() {
+            lifecycle.check()?;
+        }
+        let middleware = self.client.middleware();
+        let mut request = middleware::PromptRequest {
+            session_id: self.id,
+            llm: self.llm_status.clone(),
+            prompt,
+            parameters: parameters.into(),
+        };
+        for hook in &middleware {
+            hook.before_prompt(&mut request)?;
+        }
+        let max_tokens = request.parameters.max_tokens;
+        let stop_sequences = request.parameters.stop_sequences.clone();
+        let parameters = request.parameters.into_map();
+        let retry = self.client.prompt_retry();
+        let replay = (retry.max_attempts > 1).then(|| (request.prompt.clone(), parameters.clone()));
+        let mut stream = self
+            .client
             .prompt_session_stream(
                 self.user_id.clone(),
                 self.api_key.clone(),
                 self.id.clone(),
-                self.llm_status.uuid.clone(),
+                self.llm_status.uuid,
+                request.prompt,
+                parameters,
+            )
+            .await?;
+        stream.set_max_tokens(max_tokens);
+        stream.set_stop_sequences(stop_sequences);
+        stream.set_middleware(middleware);
+        if let Some((prompt, parameters)) = replay {
+            stream.set_replay(prompt, parameters, self.session_parameters.clone(), retry);
+        }
+        event_bus::publish(self.client.event_bus(), || ClientEvent::PromptStarted {
+            session_id: self.id,
+            llm_uuid: self.llm_uuid,
+        });
+        Ok(stream)
+    }
+
+    /// Prompts a session with text and images, for LLMs that
+    /// [LLMStatus::accepts_images], see [multimodal]. Otherwise the same as
+    /// [LLMSession::prompt_session], except that middleware sees each piece of text as
+    /// its own prompt, and failed prompts aren't retried.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` — The prompt, text and images in the order the LLM should see them.
+    /// * `parameters` — Inference parameters, see [LLMSession::prompt_session].
+    #[cfg(feature = "streaming")]
+    pub async fn prompt_session_multimodal(
+        &self,
+        inputs: Vec<PromptInput>,
+        parameters: impl Into<InferenceParams>,
+    ) -> Result<api::LLMEventStream, PantryError> {
+        if let Some(lifecycle) = self.client.lifecycle() {
+            lifecycle.check()?;
+        }
+        let middleware = self.client.middleware();
+        let mut parameters = parameters.into();
+        let mut checked = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            let PromptInput::Text(prompt) = input else {
+                checked.push(input);
+                continue;
+            };
+            let mut request = middleware::PromptRequest {
+                session_id: self.id,
+                llm: self.llm_status.clone(),
                 prompt,
                 parameters,
+            };
+            for hook in &middleware {
+                hook.before_prompt(&mut request)?;
+            }
+            checked.push(PromptInput::Text(request.prompt));
+            parameters = request.parameters;
+        }
+        let max_tokens = parameters.max_tokens;
+        let stop_sequences = parameters.stop_sequences.clone();
+        let mut stream = self
+            .client
+            .prompt_session_stream_multimodal(
+                self.user_id,
+                self.api_key.clone(),
+                self.id,
+                self.llm_status.uuid,
+                checked,
+                parameters.into_map(),
             )
+            .await?;
+        stream.set_max_tokens(max_tokens);
+        stream.set_stop_sequences(stop_sequences);
+        stream.set_middleware(middleware);
+        event_bus::publish(self.client.event_bus(), || ClientEvent::PromptStarted {
+            session_id: self.id,
+            llm_uuid: self.llm_uuid,
+        });
+        Ok(stream)
+    }
+
+    /// Prompts the session and waits for the whole completion.
+    ///
+    /// Convenient when you only care about the final text. Requires
+    /// [UserPermissions::perm_session].
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` — Prompt for the LLM, see [LLMSession::prompt_session].
+    /// * `parameters` — Inference parameters, see [LLMSession::prompt_session].
+    #[cfg(feature = "streaming")]
+    pub async fn prompt_and_collect(
+        &self,
+        prompt: String,
+        parameters: impl Into<InferenceParams>,
+    ) -> Result<String, PantryError> {
+        self.prompt_session(prompt, parameters)
+            .await?
+            .collect_text()
+            .await
+    }
+
+    /// Renders the template called `name`, for this session's LLM, see [templates].
+    ///
+    /// Fails with [PantryError::TemplateError] if the client has no such template (see
+    /// [PantryClientBuilder::templates]) or `context` is missing one of its variables.
+    #[cfg(feature = "templates")]
+    pub fn render_template(
+        &self,
+        name: &str,
+        context: impl serde::Serialize,
+    ) -> Result<String, PantryError> {
+        let templates = self.client.templates().ok_or_else(|| {
+            PantryError::TemplateError("the client has no prompt templates".into())
+        })?;
+        templates.render(name, Some(&self.llm_status), context)
+    }
+
+    /// Prompts the session with the template called `name`, filled in from `context`,
+    /// e.g. `sess.prompt_template("summarize", context! { text })`. See
+    /// [LLMSession::render_template], and [LLMSession::prompt_session] for the stream.
+    #[cfg(all(feature = "templates", feature = "streaming"))]
+    pub async fn prompt_template(
+        &self,
+        name: &str,
+        context: impl serde::Serialize,
+    ) -> Result<api::LLMEventStream, PantryError> {
+        let prompt = self.render_template(name, context)?;
+        self.prompt_session(prompt, InferenceParams::new()).await
+    }
+
+    /// Prompts the session for JSON and deserializes it into a `T`, telling the LLM
+    /// what was wrong and asking again if it doesn't fit.
+    ///
+    /// Makes up to [structured::DEFAULT_MAX_ATTEMPTS] tries, see
+    /// [structured::prompt_typed] to choose how many. Fails with
+    /// [PantryError::StructuredOutputError] if none of them work out. Requires
+    /// [UserPermissions::perm_session].
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` — Prompt for the LLM, describing the JSON it should answer with.
+    /// * `parameters` — Inference parameters, see [LLMSession::prompt_session].
+    #[cfg(feature = "streaming")]
+    pub async fn prompt_typed<T: serde::de::DeserializeOwned>(
+        &self,
+        prompt: String,
+        parameters: impl Into<InferenceParams>,
+    ) -> Result<T, PantryError> {
+        structured::prompt_typed(self, prompt, parameters, structured::DEFAULT_MAX_ATTEMPTS).await
+    }
+
+    /// Prompts the session, handing each event to `callback` as it arrives.
+    ///
+    /// For code that would rather not deal with streams. Returning
+    /// [ControlFlow::Break] from the callback stops reading and interrupts inference.
+    /// Requires [UserPermissions::perm_session].
+    ///
+    /// ```no_run
+    /// # use pantry_rs::interface::LLMEventInternal;
+    /// # use pantry_rs::LLMSession;
+    /// # use std::collections::HashMap;
+    /// # use std::ops::ControlFlow;
+    /// # async fn example(sess: LLMSession, prompt: String) -> Result<(), Box<dyn std::error::Error>> {
+    /// sess.prompt_session_with_callback(prompt, HashMap::new(), |event| {
+    ///     if let LLMEventInternal::PromptProgress { next, .. } = event.event {
+    ///         print!("{}", next);
+    ///     }
+    ///     ControlFlow::Continue(())
+    /// })
+    /// .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` — Prompt for the LLM, see [LLMSession::prompt_session].
+    /// * `parameters` — Inference parameters, see [LLMSession::prompt_session].
+    /// * `callback` — Called with every event, in order.
+    #[cfg(feature = "streaming")]
+    pub async fn prompt_session_with_callback<F>(
+        &self,
+        prompt: String,
+        parameters: impl Into<InferenceParams>,
+        mut callback: F,
+    ) -> Result<(), PantryError>
+    where
+        F: FnMut(LLMEvent) -> ControlFlow<()>,
+    {
+        let mut stream = self.prompt_session(prompt, parameters).await?;
+        while let Some(event) = stream.next().await {
+            if callback(event?).is_break() {
+                stream.interrupt().await?;
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Same as [LLMSession::prompt_session], but also returns a [stream::PromptGuard]
+    /// that interrupts inference if dropped before the stream finishes.
+    ///
+    /// Use this when the stream might get abandoned midway, e.g. when a user navigates
+    /// away from a response that's still being generated.
+    #[cfg(feature = "streaming")]
+    pub async fn prompt_session_guarded(
+        &self,
+        prompt: String,
+        parameters: impl Into<InferenceParams>,
+    ) -> Result<(api::LLMEventStream, stream::PromptGuard), PantryError> {
+        let stream = self.prompt_session(prompt, parameters).await?;
+        let guard = stream.guard();
+        Ok((stream, guard))
+    }
+
+    /// Same as [LLMSession::prompt_session], but cancelling `token` ends the stream and
+    /// interrupts inference, for prompts that belong to a scope that can get cancelled
+    /// as a whole.
+    ///
+    /// ```no_run
+    /// # use pantry_rs::LLMSession;
+    /// # use std::collections::HashMap;
+    /// # use tokio_util::sync::CancellationToken;
+    /// # async fn example(sess: LLMSession, prompt: String, request_scope: CancellationToken) -> Result<(), Box<dyn std::error::Error>> {
+    /// let token = request_scope.child_token();
+    /// let text = sess
+    ///     .prompt_session_cancellable(prompt, HashMap::new(), token)
+    ///     .await?
+    ///     .collect_text()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "cancellation")]
+    pub async fn prompt_session_cancellable(
+        &self,
+        prompt: String,
+        parameters: impl Into<InferenceParams>,
+        token: tokio_util::sync::CancellationToken,
+    ) -> Result<api::LLMEventStream, PantryError> {
+        let mut stream = self.prompt_session(prompt, parameters).await?;
+        stream.cancel_on(token);
+        Ok(stream)
+    }
+
+    /// Gets what's been said in this session so far, oldest first. Useful for showing a
+    /// resumed conversation, see [LLMSession::handle].
+    pub async fn history(&self) -> Result<Vec<LLMHistoryItem>, PantryError> {
+        self.client
+            .get_session_history(self.user_id, self.api_key.clone(), self.id)
+            .await
+    }
+
+    /// Copies this session, context and all, into a new one on the same LLM.
+    ///
+    /// Lets you try different continuations from the same point without replaying the
+    /// prompts that got you there:
+    ///
+    /// ```no_run
+    /// # use pantry_rs::LLMSession;
+    /// # use std::collections::HashMap;
+    /// # async fn example(sess: LLMSession) -> Result<(), Box<dyn std::error::Error>> {
+    /// let branch = sess.fork().await?;
+    /// let a = sess.prompt_and_collect("Option A: ".into(), HashMap::new()).await?;
+    /// let b = branch.prompt_and_collect("Option B: ".into(), HashMap::new()).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fork(&self) -> Result<LLMSession, PantryError> {
+        let res = self
+            .client
+            .fork_session(self.user_id, self.api_key.clone(), self.id)
+            .await?;
+        let id = res.session_id;
+
+        Ok(LLMSession {
+            user_id: self.user_id,
+            api_key: self.api_key.clone(),
+
+            id,
+            llm_uuid: self.llm_uuid,
+            session_parameters: res.session_parameters,
+            llm_status: res.llm_status,
+
+            client: self.client.clone(),
+        })
+    }
+
+    /// Deletes the session on the host, see [PantryClient::delete_session].
+    pub async fn delete(self) -> Result<(), PantryError> {
+        self.client
+            .delete_session(self.user_id, self.api_key, self.id)
             .await
     }
 
@@ -595,7 +2389,7 @@ impl LLMSession {
     /// Depending on your system, this might take a moment, especially given that some
     /// tokens might already be inferred but not yet transmitted.
     pub async fn interrupt_session(&self) -> Result<LLMRunningStatus, PantryError> {
-        let llm_uuid = Uuid::parse_str(&self.llm_status.uuid).unwrap();
+        let llm_uuid = self.llm_status.uuid;
         self.client
             .interrupt_session(
                 self.user_id.clone(),
@@ -0,0 +1,197 @@
+//! Shutting a client down cleanly.
+//!
+//! Prompt streams, and the tasks reading them (see [crate::stream::LLMEventStream::broadcast]),
+//! keep going until Pantry is done or they're dropped, whatever the rest of the app is
+//! up to. [crate::PantryClient::shutdown] puts an end to that: it stops server event
+//! subscriptions, gives prompts in flight a chance to finish, and cuts off the rest,
+//! optionally interrupting their inference too:
+//!
+//! ```no_run
+//! # use pantry_rs::lifecycle::ShutdownOptions;
+//! # use pantry_rs::PantryClient;
+//! # use std::time::Duration;
+//! # async fn example(pantry: PantryClient) {
+//! let report = pantry
+//!     .shutdown(ShutdownOptions {
+//!         drain_timeout: Duration::from_secs(5),
+//!         interrupt_sessions: true,
+//!     })
+//!     .await;
+//! println!("{} prompts finished, {} were cut off", report.drained, report.cut_off);
+//! # }
+//! ```
+//!
+//! Shutting down affects every copy of the client. Afterwards new prompts and
+//! subscriptions fail with [PantryError::ClientShutDown]; other calls still work.
+use crate::error::PantryError;
+use crate::ids::{LlmUuid, SessionId, UserId};
+use futures::future::{self, BoxFuture};
+use futures_timer::Delay;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// How long [ShutdownOptions::default] waits for prompts in flight to finish.
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How [crate::PantryClient::shutdown] treats prompts in flight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShutdownOptions {
+    /// How long to wait for prompts in flight to finish before cutting them off.
+    /// `Duration::ZERO` cuts them off right away.
+    pub drain_timeout: Duration,
+    /// Whether prompts that get cut off are interrupted on Pantry too. Otherwise the LLM
+    /// keeps generating, as when a stream is dropped.
+    pub interrupt_sessions: bool,
+}
+
+impl Default for ShutdownOptions {
+    fn default() -> Self {
+        ShutdownOptions {
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            interrupt_sessions: false,
+        }
+    }
+}
+
+/// What [crate::PantryClient::shutdown] did.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Prompts that finished while draining.
+    pub drained: usize,
+    /// Prompts still going at the end of the drain, whose streams were ended with
+    /// [PantryError::ClientShutDown].
+    pub cut_off: usize,
+}
+
+/// A prompt whose stream is still open.
+#[derive(Clone, Debug)]
+pub(crate) struct InFlight {
+    pub user_id: UserId,
+    pub api_key: String,
+    pub session_id: SessionId,
+    pub llm_uuid: LlmUuid,
+}
+
+/// Keeps track of a client's prompts in flight, and of whether it was shut down. Shared
+/// by copies of a [crate::PantryAPI], see the [module docs](self).
+pub struct Lifecycle {
+    closed: watch::Sender<bool>,
+    cut: watch::Sender<bool>,
+    in_flight: watch::Sender<HashMap<u64, InFlight>>,
+    next_id: AtomicU64,
+}
+
+impl fmt::Debug for Lifecycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lifecycle")
+            .field("closed", &self.is_closed())
+            .field("in_flight", &self.in_flight.borrow().len())
+            .finish()
+    }
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Lifecycle {
+            closed: watch::channel(false).0,
+            cut: watch::channel(false).0,
+            in_flight: watch::channel(HashMap::new()).0,
+            next_id: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Lifecycle {
+    /// Whether the client was shut down.
+    pub fn is_closed(&self) -> bool {
+        *self.closed.borrow()
+    }
+
+    /// Number of prompts whose streams are still open.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.borrow().len()
+    }
+
+    /// Fails with [PantryError::ClientShutDown] once the client was shut down.
+    pub(crate) fn check(&self) -> Result<(), PantryError> {
+        match self.is_closed() {
+            true => Err(PantryError::ClientShutDown),
+            false => Ok(()),
+        }
+    }
+
+    /// Resolves once the client is shut down.
+    pub(crate) fn closed(self: &Arc<Self>) -> BoxFuture<'static, ()> {
+        wait_for(self.clone(), |lifecycle| &lifecycle.closed)
+    }
+
+    /// Keeps track of a prompt until the returned ticket is dropped.
+    pub(crate) fn track(self: &Arc<Self>, prompt: InFlight) -> Ticket {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.in_flight.send_modify(|in_flight| {
+            in_flight.insert(id, prompt);
+        });
+        Ticket {
+            lifecycle: self.clone(),
+            id,
+            cut: wait_for(self.clone(), |lifecycle| &lifecycle.cut),
+        }
+    }
+
+    /// Shuts down: ends subscriptions, waits up to `drain_timeout` for prompts in
+    /// flight, then cuts off the rest. Returns how many finished, and the ones cut off.
+    pub(crate) async fn shut_down(&self, drain_timeout: Duration) -> (usize, Vec<InFlight>) {
+        self.closed.send_replace(true);
+        let mut in_flight = self.in_flight.subscribe();
+        let before = in_flight.borrow().len();
+        if before > 0 && !drain_timeout.is_zero() {
+            let drained = Box::pin(in_flight.wait_for(|in_flight| in_flight.is_empty()));
+            future::select(drained, Delay::new(drain_timeout)).await;
+        }
+        let remaining: Vec<InFlight> = self.in_flight.borrow().values().cloned().collect();
+        self.cut.send_replace(true);
+        (before.saturating_sub(remaining.len()), remaining)
+    }
+}
+
+/// Resolves once the flag `flag` picks out of `lifecycle` is set.
+fn wait_for(
+    lifecycle: Arc<Lifecycle>,
+    flag: fn(&Lifecycle) -> &watch::Sender<bool>,
+) -> BoxFuture<'static, ()> {
+    Box::pin(async move {
+        let mut set = flag(&lifecycle).subscribe();
+        let _ = set.wait_for(|set| *set).await;
+    })
+}
+
+/// A prompt being kept track of by a [Lifecycle], until dropped.
+pub(crate) struct Ticket {
+    lifecycle: Arc<Lifecycle>,
+    id: u64,
+    /// Resolves when the prompt's stream has to end.
+    pub cut: BoxFuture<'static, ()>,
+}
+
+impl Ticket {
+    /// The prompt moved to a fresh session, e.g. see [crate::PantryClientBuilder::prompt_retry].
+    pub fn set_session(&self, session_id: SessionId) {
+        self.lifecycle.in_flight.send_modify(|in_flight| {
+            if let Some(prompt) = in_flight.get_mut(&self.id) {
+                prompt.session_id = session_id;
+            }
+        });
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        self.lifecycle.in_flight.send_modify(|in_flight| {
+            in_flight.remove(&self.id);
+        });
+    }
+}
@@ -0,0 +1,94 @@
+//! Fronting a running [llama.cpp](https://github.com/ggerganov/llama.cpp) server
+//! (`llama-server`) instead of downloading its model again.
+//!
+//! [list_llama_cpp_models] asks the server what it's serving, and
+//! [LLMRegistryEntry::from_llama_cpp] turns each into a
+//! [LLMConnectorType::GenericAPI] entry that talks to the server's OpenAI compatible
+//! API:
+//!
+//! ```no_run
+//! # use pantry_rs::interface::LLMRegistryEntry;
+//! # use pantry_rs::llama_cpp::{list_llama_cpp_models, LLAMA_CPP_URL};
+//! # use pantry_rs::PantryClient;
+//! # async fn example(pantry: PantryClient) -> Result<(), Box<dyn std::error::Error>> {
+//! for model in list_llama_cpp_models(LLAMA_CPP_URL).await? {
+//!     let entry = LLMRegistryEntry::from_llama_cpp(&model, LLAMA_CPP_URL).build()?;
+//!     pantry.download_llm(entry).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use crate::connector_config::GenericAPIConfig;
+use crate::error::PantryError;
+use crate::huggingface::get;
+use crate::interface::{LLMConnectorType, LLMRegistryEntry};
+use crate::registry::LLMRegistryEntryBuilder;
+use hyper::{Body, Client};
+use hyper_tls::HttpsConnector;
+use serde::Deserialize;
+
+/// Where `llama-server` listens by default.
+pub const LLAMA_CPP_URL: &str = "http://localhost:8080";
+
+/// A model a llama.cpp server serves, as listed by its `GET /v1/models`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct LlamaCppModel {
+    /// Usually the path of the model file, as the server was started with.
+    pub id: String,
+    #[serde(default)]
+    pub meta: Option<LlamaCppModelMeta>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct LlamaCppModelMeta {
+    /// Context length the model was trained with.
+    #[serde(default)]
+    pub n_ctx_train: Option<u64>,
+    #[serde(default)]
+    pub n_params: Option<u64>,
+    /// Size of the model file.
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct ModelList {
+    #[serde(default)]
+    data: Vec<LlamaCppModel>,
+}
+
+/// The models the llama.cpp server at `base_url` serves, e.g. [LLAMA_CPP_URL].
+pub async fn list_llama_cpp_models(base_url: &str) -> Result<Vec<LlamaCppModel>, PantryError> {
+    let client = Client::builder().build::<_, Body>(HttpsConnector::new());
+    let body = get(
+        &client,
+        format!("{}/v1/models", base_url.trim_end_matches('/')),
+    )
+    .await?;
+    let list: ModelList = serde_json::from_str(&body)?;
+    Ok(list.data)
+}
+
+impl LLMRegistryEntry {
+    /// Starts building an entry serving `model` through the llama.cpp server at
+    /// `base_url`.
+    pub fn from_llama_cpp(model: &LlamaCppModel, base_url: &str) -> LLMRegistryEntryBuilder {
+        // The id is a path, the file's stem makes a friendlier name.
+        let file = model.id.rsplit(['/', '\\']).next().unwrap_or(&model.id);
+        let name = file.strip_suffix(".gguf").unwrap_or(file);
+
+        let mut builder =
+            LLMRegistryEntry::builder(format!("llamacpp-{}", name), LLMConnectorType::GenericAPI)
+                .name(name)
+                .url(format!("{}/v1", base_url.trim_end_matches('/')))
+                .tag("llama.cpp")
+                .connector_config(GenericAPIConfig {
+                    model: Some(model.id.clone()),
+                    ..Default::default()
+                });
+        if let Some(tokens) = model.meta.as_ref().and_then(|meta| meta.n_ctx_train) {
+            builder = builder.context_length(tokens);
+        }
+        builder
+    }
+}
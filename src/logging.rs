@@ -0,0 +1,119 @@
+//! Debug logging of calls to Pantry.
+//!
+//! With the `logging` feature, every request body and response goes to the [log] crate
+//! at debug level, under the `pantry_rs::logging` target. Secrets like `api_key` are
+//! replaced with [REDACTED] first (see [redact]), so the logs are safe to share when
+//! chasing down why Pantry answers a call with a 4xx.
+//!
+//! ```ignore
+//! env_logger::Builder::new()
+//!     .filter_module("pantry_rs::logging", log::LevelFilter::Debug)
+//!     .init();
+//! ```
+//!
+//! Bodies are only read for logging when debug logging is enabled for the target.
+//! Streamed responses are logged without their body, since reading it would wait for
+//! the stream to end.
+use crate::error::PantryError;
+use serde_json::Value;
+
+/// What secrets get replaced with.
+pub const REDACTED: &str = "[redacted]";
+
+/// JSON fields whose values never get logged.
+pub const SECRET_FIELDS: &[&str] = &["api_key", "password", "secret", "token"];
+
+/// Endpoints that answer with a stream of events.
+#[cfg(feature = "logging")]
+const STREAMING_ENDPOINTS: &[&str] = &["/prompt_session_stream", "/subscribe_events"];
+
+/// `body` with the values of any [SECRET_FIELDS] replaced by [REDACTED], at any depth.
+///
+/// Bodies that aren't JSON are dropped entirely if they mention a secret field, since
+/// there's no telling where its value ends.
+pub fn redact(body: &str) -> String {
+    match serde_json::from_str::<Value>(body) {
+        Ok(mut json) => {
+            redact_value(&mut json);
+            json.to_string()
+        }
+        Err(_) if SECRET_FIELDS.iter().any(|field| body.contains(field)) => {
+            format!("{} ({} bytes)", REDACTED, body.len())
+        }
+        Err(_) => body.to_string(),
+    }
+}
+
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if SECRET_FIELDS.contains(&key.as_str()) {
+                    *value = Value::String(REDACTED.into());
+                } else {
+                    redact_value(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+/// Logs a request about to be sent. Does nothing without the `logging` feature.
+pub(crate) fn log_request(method: &hyper::Method, endpoint: &str, body: &str) {
+    #[cfg(feature = "logging")]
+    if log::log_enabled!(log::Level::Debug) {
+        log::debug!("-> {} {}: {}", method, endpoint, redact(body));
+    }
+    #[cfg(not(feature = "logging"))]
+    let _ = (method, endpoint, body);
+}
+
+/// Logs the outcome of a request, handing the response back with its body intact.
+/// Does nothing without the `logging` feature.
+pub(crate) async fn log_response(
+    endpoint: &str,
+    res: Result<hyper::Response<hyper::Body>, PantryError>,
+) -> Result<hyper::Response<hyper::Body>, PantryError> {
+    #[cfg(feature = "logging")]
+    {
+        if !log::log_enabled!(log::Level::Debug) {
+            return res;
+        }
+        let resp = match res {
+            Ok(resp) => resp,
+            Err(err) => {
+                log::debug!("<- {} failed: {}", endpoint, err);
+                return Err(err);
+            }
+        };
+
+        let event_stream = resp
+            .headers()
+            .get(hyper::header::CONTENT_TYPE)
+            .is_some_and(|v| {
+                v.as_bytes().starts_with(b"text/event-stream")
+                    || v.as_bytes().starts_with(b"application/x-ndjson")
+            });
+        if event_stream || STREAMING_ENDPOINTS.contains(&endpoint) {
+            log::debug!("<- {} {}: (event stream)", resp.status(), endpoint);
+            return Ok(resp);
+        }
+
+        let (parts, body) = resp.into_parts();
+        let bytes = hyper::body::to_bytes(body).await?;
+        log::debug!(
+            "<- {} {}: {}",
+            parts.status,
+            endpoint,
+            redact(&String::from_utf8_lossy(&bytes))
+        );
+        Ok(hyper::Response::from_parts(parts, hyper::Body::from(bytes)))
+    }
+    #[cfg(not(feature = "logging"))]
+    {
+        let _ = endpoint;
+        res
+    }
+}
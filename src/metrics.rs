@@ -0,0 +1,107 @@
+//! Hooks for recording how calls to Pantry perform.
+//!
+//! Give a [MetricsSink] to [crate::PantryClientBuilder::metrics] and it hears about
+//! every API call, and about every prompt once its stream ends, without wrapping any
+//! call sites. Forward what it gets to whatever feeds your dashboards:
+//!
+//! ```
+//! # use pantry_rs::metrics::{MetricsSink, PromptMetric, RequestMetric};
+//! # use pantry_rs::{PantryClient, UserId};
+//! # use std::sync::atomic::{AtomicUsize, Ordering};
+//! # use std::sync::Arc;
+//! # let (user_id, api_key) = (UserId::new_v4(), String::new());
+//! #[derive(Default)]
+//! struct Counters {
+//!     errors: AtomicUsize,
+//! }
+//!
+//! impl MetricsSink for Counters {
+//!     fn request(&self, metric: &RequestMetric) {
+//!         if metric.is_error() {
+//!             self.errors.fetch_add(1, Ordering::Relaxed);
+//!         }
+//!     }
+//!
+//!     fn prompt(&self, metric: &PromptMetric) {
+//!         println!("{}: {:?} tokens/s", metric.session_id, metric.tokens_per_second());
+//!     }
+//! }
+//!
+//! let pantry = PantryClient::builder()
+//!     .metrics(Arc::new(Counters::default()))
+//!     .login(user_id, api_key);
+//! ```
+//!
+//! Sinks are called inline, so they should be quick; hand anything slow off to another
+//! thread.
+use crate::ids::{LlmUuid, SessionId};
+use crate::interface::FinishReason;
+use hyper::StatusCode;
+use std::fmt;
+use std::time::Duration;
+
+/// Receives metrics from [crate::PantryAPI]. Both methods do nothing by default, so
+/// implement only what you need.
+pub trait MetricsSink: Send + Sync {
+    /// Called after every call to Pantry, including each retry.
+    fn request(&self, metric: &RequestMetric) {
+        let _ = metric;
+    }
+
+    /// Called when a prompt's stream ends, whether it completed or not.
+    fn prompt(&self, metric: &PromptMetric) {
+        let _ = metric;
+    }
+}
+
+impl fmt::Debug for dyn MetricsSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MetricsSink")
+    }
+}
+
+/// A single call to Pantry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestMetric {
+    /// Path of the endpoint called, e.g. `/get_running_llms`.
+    pub endpoint: String,
+    /// Time until Pantry responded. For streaming calls, that's until the stream
+    /// started.
+    pub latency: Duration,
+    /// `None` if Pantry couldn't be reached or didn't respond in time.
+    pub status: Option<StatusCode>,
+}
+
+impl RequestMetric {
+    /// Whether the call failed, either in transport or with an error status.
+    pub fn is_error(&self) -> bool {
+        !matches!(self.status, Some(status) if status.is_success())
+    }
+}
+
+/// A prompt's inference, see [crate::stream::LLMEventStream].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PromptMetric {
+    pub session_id: SessionId,
+    pub llm_uuid: LlmUuid,
+    /// Time from sending the prompt to the first token. `None` if none arrived.
+    pub time_to_first_token: Option<Duration>,
+    /// Time from sending the prompt until the stream ended.
+    pub duration: Duration,
+    /// Tokens generated.
+    pub tokens: usize,
+    pub finish_reason: FinishReason,
+}
+
+impl PromptMetric {
+    /// Generation speed once the first token arrived, leaving out the time spent
+    /// reading the prompt. `None` with fewer than two tokens.
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        let first = self.time_to_first_token?;
+        let generating = self.duration.checked_sub(first)?.as_secs_f64();
+        if self.tokens < 2 || generating <= 0.0 {
+            return None;
+        }
+        Some((self.tokens - 1) as f64 / generating)
+    }
+}
@@ -0,0 +1,82 @@
+//! Hooks that see every prompt and every event of its reply.
+//!
+//! Give a [PromptMiddleware] to [crate::PantryClientBuilder::middleware] and every
+//! [crate::LLMSession::prompt_session] of the client goes through it: it can rewrite
+//! or refuse prompts before they're sent, and rewrite events or end the stream as they
+//! come in. Chat sessions, agents and the rest are built on `prompt_session`, so they
+//! go through it too.
+//!
+//! ```ignore
+//! struct NoEmails(Regex);
+//!
+//! impl PromptMiddleware for NoEmails {
+//!     fn before_prompt(&self, request: &mut PromptRequest) -> Result<(), PantryError> {
+//!         request.prompt = self.0.replace_all(&request.prompt, "[email]").into_owned();
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let pantry = PantryClient::builder()
+//!     .middleware(NoEmails(Regex::new(r"\S+@\S+")?))
+//!     .middleware(MaxPromptChars(8000))
+//!     .login(user_id, api_key);
+//! ```
+//!
+//! Middleware runs in the order it was added, inline, so it should be quick. Events
+//! are seen as Pantry sends them, before `max_tokens` and stop sequences are applied.
+use crate::error::PantryError;
+use crate::interface::{LLMEvent, LLMStatus};
+use crate::params::InferenceParams;
+use crate::SessionId;
+use std::fmt;
+
+/// A prompt about to be sent, see [PromptMiddleware::before_prompt].
+#[derive(Clone, Debug)]
+pub struct PromptRequest {
+    pub session_id: SessionId,
+    /// The LLM that will answer. Changing it has no effect.
+    pub llm: LLMStatus,
+    pub prompt: String,
+    pub parameters: InferenceParams,
+}
+
+/// Inspects and rewrites prompts and their replies, see the [module docs](self). Both
+/// methods do nothing by default, so implement only what you need.
+pub trait PromptMiddleware: Send + Sync {
+    /// Called before `request` is sent. Returning an error, usually
+    /// [PantryError::PromptRejected], fails the prompt without sending it.
+    fn before_prompt(&self, request: &mut PromptRequest) -> Result<(), PantryError> {
+        let _ = request;
+        Ok(())
+    }
+
+    /// Called on each event of the reply. Returning an error interrupts inference and
+    /// ends the stream with it.
+    fn on_event(&self, event: &mut LLMEvent) -> Result<(), PantryError> {
+        let _ = event;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for dyn PromptMiddleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PromptMiddleware")
+    }
+}
+
+/// Rejects prompts longer than this many characters.
+#[derive(Clone, Copy, Debug)]
+pub struct MaxPromptChars(pub usize);
+
+impl PromptMiddleware for MaxPromptChars {
+    fn before_prompt(&self, request: &mut PromptRequest) -> Result<(), PantryError> {
+        let chars = request.prompt.chars().count();
+        if chars > self.0 {
+            return Err(PantryError::PromptRejected(format!(
+                "prompt is {} characters, the limit is {}",
+                chars, self.0
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+//! Prompts with images in them, for vision models.
+//!
+//! A prompt is a list of [PromptInput]s, text and images in the order the model
+//! should see them:
+//!
+//! ```no_run
+//! # use pantry_rs::{InferenceParams, LLMSession, PromptInput};
+//! # async fn example(sess: LLMSession) -> Result<(), Box<dyn std::error::Error>> {
+//! if sess.llm_status.accepts_images() {
+//!     let stream = sess
+//!         .prompt_session_multimodal(
+//!             vec![
+//!                 PromptInput::image_file("receipt.png")?,
+//!                 "What's the total on this receipt?".into(),
+//!             ],
+//!             InferenceParams::default(),
+//!         )
+//!         .await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Pantry gets the text joined into one prompt, and each image with the byte offset in
+//! it where it goes. Images travel base64 encoded inside the JSON request by default,
+//! or as parts of a `multipart/form-data` request, see [AttachmentTransport].
+use crate::error::PantryError;
+#[cfg(feature = "streaming")]
+use base64::engine::general_purpose::STANDARD;
+#[cfg(feature = "streaming")]
+use base64::Engine;
+#[cfg(feature = "streaming")]
+use hyper::body::Bytes;
+use std::path::Path;
+
+/// Part of a prompt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PromptInput {
+    Text(String),
+    /// An encoded image, e.g. a PNG, with its mime type, e.g. `image/png`.
+    Image {
+        bytes: Vec<u8>,
+        mime: String,
+    },
+}
+
+impl PromptInput {
+    pub fn text(text: impl Into<String>) -> Self {
+        PromptInput::Text(text.into())
+    }
+
+    pub fn image(bytes: impl Into<Vec<u8>>, mime: impl Into<String>) -> Self {
+        PromptInput::Image {
+            bytes: bytes.into(),
+            mime: mime.into(),
+        }
+    }
+
+    /// Reads an image, guessing its mime type from the extension. Fails with
+    /// [PantryError::OtherFailure] for extensions that aren't images.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn image_file(path: impl AsRef<Path>) -> Result<Self, PantryError> {
+        let path = path.as_ref();
+        let mime = image_mime(path).ok_or_else(|| {
+            PantryError::OtherFailure(format!("{} isn't a known image type", path.display()))
+        })?;
+        Ok(PromptInput::image(std::fs::read(path)?, mime))
+    }
+
+    pub fn is_image(&self) -> bool {
+        matches!(self, PromptInput::Image { .. })
+    }
+}
+
+impl From<&str> for PromptInput {
+    fn from(text: &str) -> Self {
+        PromptInput::Text(text.into())
+    }
+}
+
+impl From<String> for PromptInput {
+    fn from(text: String) -> Self {
+        PromptInput::Text(text)
+    }
+}
+
+/// How images are sent along with a prompt, see [crate::PantryClientBuilder::attachment_transport].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AttachmentTransport {
+    /// Base64 inside the JSON request. A third bigger, but works through anything
+    /// that passes JSON.
+    #[default]
+    Base64,
+    /// Raw bytes, each image a part of a `multipart/form-data` request next to the JSON.
+    /// Only over HTTP, not in the browser.
+    Multipart,
+}
+
+/// The mime type for an image file's extension.
+pub fn image_mime(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    Some(match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        _ => return None,
+    })
+}
+
+/// An image as sent to Pantry, alongside the prompt text.
+#[cfg(feature = "streaming")]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Attachment {
+    pub mime: String,
+    /// Byte offset in the prompt the image comes at.
+    pub position: usize,
+    /// Base64 encoded image, with [AttachmentTransport::Base64].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    /// Name of the part holding the image, with [AttachmentTransport::Multipart].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part: Option<String>,
+}
+
+/// A prompt taken apart for sending.
+#[cfg(feature = "streaming")]
+pub(crate) struct SplitPrompt {
+    pub text: String,
+    pub attachments: Vec<Attachment>,
+    /// Image bytes for each attachment, in order, when they go as parts.
+    pub parts: Vec<Vec<u8>>,
+}
+
+/// Joins the text of `inputs`, and places the images in it.
+#[cfg(feature = "streaming")]
+pub(crate) fn split_inputs(
+    inputs: Vec<PromptInput>,
+    transport: AttachmentTransport,
+) -> SplitPrompt {
+    let mut prompt = SplitPrompt {
+        text: String::new(),
+        attachments: Vec::new(),
+        parts: Vec::new(),
+    };
+    for input in inputs {
+        match input {
+            PromptInput::Text(text) => prompt.text.push_str(&text),
+            PromptInput::Image { bytes, mime } => {
+                let position = prompt.text.len();
+                let attachment = attach(bytes, mime, position, transport, &mut prompt.parts);
+                prompt.attachments.push(attachment);
+            }
+        }
+    }
+    prompt
+}
+
+/// `bytes` as an attachment at `position`, inline or as the next of `parts`.
+#[cfg(feature = "streaming")]
+pub(crate) fn attach(
+    bytes: Vec<u8>,
+    mime: String,
+    position: usize,
+    transport: AttachmentTransport,
+    parts: &mut Vec<Vec<u8>>,
+) -> Attachment {
+    let mut attachment = Attachment {
+        mime,
+        position,
+        data: None,
+        part: None,
+    };
+    match transport {
+        AttachmentTransport::Base64 => attachment.data = Some(STANDARD.encode(bytes)),
+        AttachmentTransport::Multipart => {
+            attachment.part = Some(format!("attachment{}", parts.len()));
+            parts.push(bytes);
+        }
+    }
+    attachment
+}
+
+/// A `multipart/form-data` body of the JSON `request`, then each of `attachments`' parts.
+/// Returns the body and its content type.
+#[cfg(feature = "streaming")]
+pub(crate) fn multipart_body(
+    request: &str,
+    attachments: &[Attachment],
+    parts: &[Vec<u8>],
+) -> (Bytes, String) {
+    let boundary = format!("pantry-{}", uuid::Uuid::new_v4().simple());
+    let mut body = Vec::new();
+    let mut part = |name: &str, mime: &str, bytes: &[u8]| {
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                boundary, name, mime
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(bytes);
+        body.extend_from_slice(b"\r\n");
+    };
+    part("request", "application/json", request.as_bytes());
+    for (attachment, bytes) in attachments.iter().zip(parts) {
+        if let Some(name) = &attachment.part {
+            part(name, &attachment.mime, bytes);
+        }
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    (
+        Bytes::from(body),
+        format!("multipart/form-data; boundary={}", boundary),
+    )
+}
@@ -0,0 +1,89 @@
+//! Decoding newline-delimited JSON, the fallback wire format for Pantry's streaming
+//! endpoints, see [crate::api::StreamFormat].
+//!
+//! Each line holds one event, the same JSON a server-sent event would carry in its
+//! `data:`. Being plain lines, it makes it through proxies that buffer or rewrite
+//! `text/event-stream` responses. It has no event ids though, so streams in this
+//! format can't be resumed after a dropped connection.
+//!
+//! ```
+//! # use pantry_rs::ndjson::NdjsonLines;
+//! let mut lines = NdjsonLines::new();
+//! assert!(lines.feed(b"{\"a\":").is_empty());
+//! assert_eq!(lines.feed(b" 1}\n"), vec!["{\"a\": 1}"]);
+//! ```
+use crate::error::PantryError;
+use futures::stream::{self, Stream, StreamExt};
+use hyper::body::Bytes;
+
+/// Incremental splitter of a body into its non-blank lines.
+#[derive(Clone, Debug, Default)]
+pub struct NdjsonLines {
+    // Bytes of the line being read, decoded once it's complete so multi-byte
+    // characters can be split across chunks.
+    line: Vec<u8>,
+}
+
+impl NdjsonLines {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Splits off the lines the next chunk of the body completes. `\r\n` endings and
+    /// blank lines, which some servers send as keep-alives, are taken care of.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut chunk = chunk;
+        while let Some(end) = chunk.iter().position(|b| *b == b'\n') {
+            self.line.extend_from_slice(&chunk[..end]);
+            lines.extend(self.take_line());
+            chunk = &chunk[end + 1..];
+        }
+        self.line.extend_from_slice(chunk);
+        lines
+    }
+
+    /// Ends the body, returning the last line if it wasn't terminated.
+    pub fn finish(&mut self) -> Option<String> {
+        self.take_line()
+    }
+
+    fn take_line(&mut self) -> Option<String> {
+        let line = std::mem::take(&mut self.line);
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim();
+        (!line.is_empty()).then(|| line.to_string())
+    }
+}
+
+/// Decodes a body, as a stream of chunks, into its lines.
+pub fn decode<S, E>(body: S) -> impl Stream<Item = Result<String, PantryError>> + Send
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + Unpin + 'static,
+    E: std::fmt::Display,
+{
+    stream::unfold(
+        (body, NdjsonLines::new(), Vec::new().into_iter(), false),
+        |(mut body, mut lines, mut pending, mut done)| async move {
+            loop {
+                if let Some(line) = pending.next() {
+                    return Some((Ok(line), (body, lines, pending, done)));
+                }
+                if done {
+                    return None;
+                }
+                match body.next().await {
+                    Some(Ok(chunk)) => pending = lines.feed(&chunk).into_iter(),
+                    Some(Err(e)) => {
+                        let err = PantryError::StreamError(e.to_string());
+                        return Some((Err(err), (body, lines, pending, true)));
+                    }
+                    None => {
+                        done = true;
+                        pending = lines.finish().into_iter().collect::<Vec<_>>().into_iter();
+                    }
+                }
+            }
+        },
+    )
+}
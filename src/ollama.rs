@@ -0,0 +1,194 @@
+//! Fronting models installed with [Ollama](https://ollama.com) instead of downloading
+//! them again.
+//!
+//! [list_ollama_models] reads Ollama's manifests, and
+//! [LLMRegistryEntry::from_ollama] turns each into a [LLMConnectorType::GenericAPI]
+//! entry that talks to Ollama's OpenAI compatible API:
+//!
+//! ```no_run
+//! # use pantry_rs::interface::LLMRegistryEntry;
+//! # use pantry_rs::ollama::{list_ollama_models, ollama_models_dir, OLLAMA_URL};
+//! # use pantry_rs::PantryClient;
+//! # async fn example(pantry: PantryClient) -> Result<(), Box<dyn std::error::Error>> {
+//! for model in list_ollama_models(&ollama_models_dir()?)? {
+//!     let entry = LLMRegistryEntry::from_ollama(&model, OLLAMA_URL).build()?;
+//!     pantry.download_llm(entry).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Only the manifests are read, Ollama has to be running to actually serve the models.
+use crate::connector_config::GenericAPIConfig;
+use crate::error::PantryError;
+use crate::interface::{LLMConnectorType, LLMRegistryEntry};
+use crate::registry::LLMRegistryEntryBuilder;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Where Ollama listens by default.
+pub const OLLAMA_URL: &str = "http://localhost:11434";
+
+/// Ollama's own registry, left out of model names.
+const DEFAULT_REGISTRY: &str = "registry.ollama.ai";
+const DEFAULT_NAMESPACE: &str = "library";
+const MODEL_MEDIA_TYPE: &str = "application/vnd.ollama.image.model";
+
+/// A model installed with Ollama, see [list_ollama_models].
+#[derive(Clone, Debug, PartialEq)]
+pub struct OllamaModel {
+    /// The name Ollama knows it by, e.g. `llama2:latest` or `someone/model:q4_0`.
+    pub name: String,
+    /// Hex encoded sha256 of the model file.
+    pub sha256: String,
+    /// The model file, a GGUF.
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    /// e.g. `llama`, if the manifest's config says.
+    pub family: Option<String>,
+    /// e.g. `7B`, if the manifest's config says.
+    pub parameter_size: Option<String>,
+    /// e.g. `Q4_0`, if the manifest's config says.
+    pub quantization: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    config: Option<Layer>,
+    #[serde(default)]
+    layers: Vec<Layer>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Layer {
+    #[serde(default)]
+    media_type: String,
+    digest: String,
+    #[serde(default)]
+    size: u64,
+}
+
+#[derive(Default, Deserialize)]
+struct ModelConfig {
+    model_family: Option<String>,
+    model_type: Option<String>,
+    file_type: Option<String>,
+}
+
+/// Ollama's model directory: `$OLLAMA_MODELS` if set, otherwise `~/.ollama/models`.
+pub fn ollama_models_dir() -> Result<PathBuf, PantryError> {
+    let var = |name: &str| std::env::var_os(name).filter(|v| !v.is_empty());
+    if let Some(dir) = var("OLLAMA_MODELS") {
+        return Ok(PathBuf::from(dir));
+    }
+    let home = if cfg!(windows) {
+        var("USERPROFILE")
+    } else {
+        var("HOME")
+    };
+    home.map(|home| PathBuf::from(home).join(".ollama").join("models"))
+        .ok_or_else(|| PantryError::OtherFailure("can't find a home directory".into()))
+}
+
+/// Every model installed in Ollama's `models_dir`, sorted by name. Manifests that
+/// can't be read, or don't have a model file, are skipped.
+pub fn list_ollama_models(models_dir: &Path) -> Result<Vec<OllamaModel>, PantryError> {
+    let manifests = models_dir.join("manifests");
+    let mut files = Vec::new();
+    collect_files(&manifests, &mut files)?;
+
+    let mut models: Vec<OllamaModel> = files
+        .iter()
+        .filter_map(|file| {
+            let relative = file.strip_prefix(&manifests).ok()?;
+            let manifest: Manifest = serde_json::from_slice(&std::fs::read(file).ok()?).ok()?;
+            read_model(models_dir, relative, manifest)
+        })
+        .collect();
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(models)
+}
+
+impl LLMRegistryEntry {
+    /// Starts building an entry serving `model` through the Ollama instance at
+    /// `base_url`, e.g. [OLLAMA_URL].
+    pub fn from_ollama(model: &OllamaModel, base_url: &str) -> LLMRegistryEntryBuilder {
+        let mut builder = LLMRegistryEntry::builder(
+            format!("ollama-{}", model.name.replace([':', '/'], "-")),
+            LLMConnectorType::GenericAPI,
+        )
+        .name(model.name.as_str())
+        .url(format!("{}/v1", base_url.trim_end_matches('/')))
+        .tag("ollama")
+        .connector_config(GenericAPIConfig {
+            model: Some(model.name.clone()),
+            ..Default::default()
+        });
+        if let Some(family) = &model.family {
+            builder = builder.family_id(family.as_str());
+        }
+        // Only Ollama's own library has pages to link to.
+        let (repo, _tag) = model.name.split_once(':').unwrap_or((&model.name, ""));
+        if !repo.contains('/') {
+            builder = builder.homepage(format!("https://ollama.com/library/{}", repo));
+        }
+        builder
+    }
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), PantryError> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// `relative` is `registry/namespace/model/tag` under `manifests`.
+fn read_model(models_dir: &Path, relative: &Path, manifest: Manifest) -> Option<OllamaModel> {
+    let parts: Vec<&str> = relative.iter().filter_map(|p| p.to_str()).collect();
+    let [registry, namespace, model, tag] = parts[..] else {
+        return None;
+    };
+    let name = match (registry, namespace) {
+        (DEFAULT_REGISTRY, DEFAULT_NAMESPACE) => format!("{}:{}", model, tag),
+        (DEFAULT_REGISTRY, _) => format!("{}/{}:{}", namespace, model, tag),
+        _ => format!("{}/{}/{}:{}", registry, namespace, model, tag),
+    };
+
+    let layer = manifest
+        .layers
+        .iter()
+        .find(|layer| layer.media_type == MODEL_MEDIA_TYPE)?;
+    let path = blob_path(models_dir, &layer.digest)?;
+    let config: ModelConfig = manifest
+        .config
+        .and_then(|config| blob_path(models_dir, &config.digest))
+        .and_then(|path| std::fs::read(path).ok())
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default();
+
+    Some(OllamaModel {
+        name,
+        sha256: layer.digest.trim_start_matches("sha256:").to_string(),
+        path,
+        size_bytes: layer.size,
+        family: config.model_family,
+        parameter_size: config.model_type,
+        quantization: config.file_type,
+    })
+}
+
+/// Blobs are named `sha256-<hex>`, or `sha256:<hex>` by older Ollama versions.
+fn blob_path(models_dir: &Path, digest: &str) -> Option<PathBuf> {
+    let blobs = models_dir.join("blobs");
+    [digest.replace(':', "-"), digest.to_string()]
+        .into_iter()
+        .map(|name| blobs.join(name))
+        .find(|path| path.is_file())
+}
@@ -0,0 +1,590 @@
+//! OpenAI-style completions on top of Pantry.
+//!
+//! Request and response types here follow the OpenAI chat completions and completions
+//! schema, so code (and serialized JSON) written for OpenAI works against local models
+//! with little more than swapping the client:
+//!
+//! ```no_run
+//! # use pantry_rs::openai_compat::ChatCompletionRequest;
+//! # use pantry_rs::PantryClient;
+//! # async fn example(pantry: PantryClient) -> Result<(), Box<dyn std::error::Error>> {
+//! let request: ChatCompletionRequest = serde_json::from_str(r#"{
+//!     "model": "llama-2-7b-chat",
+//!     "messages": [{"role": "user", "content": "Say hi"}],
+//!     "max_tokens": 32
+//! }"#)?;
+//! let response = pantry.chat_completions(&request).await?;
+//! println!("{}", response.choices[0].message.content);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Every call runs in a fresh session, deleted again once the completion is done. A
+//! stream dropped halfway, e.g. when a [crate::proxy] client disconnects, interrupts
+//! the inference first.
+//! `model` picks a running LLM: by UUID, by id, or whichever Pantry prefers if it's
+//! empty or [DEFAULT_MODEL]. Chat messages are rendered with the LLM's
+//! [PromptFormat].
+//!
+//! Fields Pantry has no equivalent for (`n`, `logprobs`, penalties, ...) are ignored.
+use crate::api::{LLMEventStream, LLMFilter};
+use crate::chat::{ChatMessage, ChatTemplate, Role};
+use crate::context::estimate_tokens;
+use crate::error::PantryError;
+use crate::ids::LlmUuid;
+use crate::interface::{Completion, FinishReason, LLMEventInternal};
+use crate::params::InferenceParams;
+use crate::prompt_format::PromptFormat;
+use crate::stream::spawn_background;
+use crate::{LLMSession, PantryClient};
+use futures::stream::{self, Stream, StreamExt};
+use std::collections::HashMap;
+use std::pin::Pin;
+use uuid::Uuid;
+
+/// `model` that leaves choosing the LLM to Pantry.
+pub const DEFAULT_MODEL: &str = "default";
+
+/// Stream of chunks, as returned by [PantryClient::chat_completions_stream] and
+/// [PantryClient::completions_stream].
+pub type ChunkStream<T> = Pin<Box<dyn Stream<Item = Result<T, PantryError>> + Send>>;
+
+/// Where to stop, either one sequence or several.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum Stop {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Stop {
+    pub fn sequences(&self) -> Vec<String> {
+        match self {
+            Stop::One(stop) => vec![stop.clone()],
+            Stop::Many(stops) => stops.clone(),
+        }
+    }
+}
+
+/// Body of a `/v1/chat/completions` call.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ChatCompletionRequest {
+    #[serde(default)]
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(
+        default,
+        alias = "max_completion_tokens",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Stop>,
+    /// Only used to tell which call to make; the calls themselves ignore it.
+    #[serde(default)]
+    pub stream: bool,
+}
+
+impl ChatCompletionRequest {
+    pub fn new(model: impl Into<String>, messages: Vec<ChatMessage>) -> Self {
+        ChatCompletionRequest {
+            model: model.into(),
+            messages,
+            ..Default::default()
+        }
+    }
+
+    /// The request's sampling settings as [InferenceParams].
+    pub fn params(&self) -> InferenceParams {
+        params(
+            self.temperature,
+            self.top_p,
+            self.max_tokens,
+            self.seed,
+            &self.stop,
+        )
+    }
+}
+
+/// Body of a `/v1/completions` call.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CompletionRequest {
+    #[serde(default)]
+    pub model: String,
+    pub prompt: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Stop>,
+    /// Only used to tell which call to make; the calls themselves ignore it.
+    #[serde(default)]
+    pub stream: bool,
+}
+
+impl CompletionRequest {
+    pub fn new(model: impl Into<String>, prompt: impl Into<String>) -> Self {
+        CompletionRequest {
+            model: model.into(),
+            prompt: prompt.into(),
+            ..Default::default()
+        }
+    }
+
+    /// The request's sampling settings as [InferenceParams].
+    pub fn params(&self) -> InferenceParams {
+        params(
+            self.temperature,
+            self.top_p,
+            self.max_tokens,
+            self.seed,
+            &self.stop,
+        )
+    }
+}
+
+/// Token counts for a call. The prompt's is estimated if Pantry doesn't report it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+/// Response to [ChatCompletionRequest].
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ChatCompletionResponse {
+    pub id: String,
+    /// Always `chat.completion`.
+    pub object: String,
+    /// Unix timestamp, in seconds.
+    pub created: i64,
+    pub model: String,
+    pub choices: Vec<ChatChoice>,
+    pub usage: Usage,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ChatChoice {
+    pub index: u32,
+    pub message: ChatMessage,
+    pub finish_reason: Option<FinishReason>,
+}
+
+/// Part of a streamed response to [ChatCompletionRequest].
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ChatCompletionChunk {
+    pub id: String,
+    /// Always `chat.completion.chunk`.
+    pub object: String,
+    pub created: i64,
+    pub model: String,
+    pub choices: Vec<ChatChunkChoice>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ChatChunkChoice {
+    pub index: u32,
+    pub delta: ChatDelta,
+    /// Set on the last chunk only.
+    pub finish_reason: Option<FinishReason>,
+}
+
+/// What a chunk adds to the message.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ChatDelta {
+    /// Set on the first chunk only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<Role>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+/// Response to [CompletionRequest], and streamed parts of it.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CompletionResponse {
+    pub id: String,
+    /// Always `text_completion`.
+    pub object: String,
+    pub created: i64,
+    pub model: String,
+    pub choices: Vec<CompletionChoice>,
+    /// Not set on streamed chunks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CompletionChoice {
+    pub index: u32,
+    pub text: String,
+    pub finish_reason: Option<FinishReason>,
+}
+
+/// An LLM, as listed by `/v1/models`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Model {
+    /// What to pass as `model` to use this LLM.
+    pub id: String,
+    /// Always `model`.
+    pub object: String,
+    /// Pantry doesn't track this, so it's always 0.
+    pub created: i64,
+    pub owned_by: String,
+}
+
+/// Response to `/v1/models`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ModelList {
+    /// Always `list`.
+    pub object: String,
+    pub data: Vec<Model>,
+}
+
+impl PantryClient {
+    /// Lists the running LLMs, like OpenAI's `/v1/models`.
+    pub async fn models(&self) -> Result<ModelList, PantryError> {
+        let data = self
+            .get_running_llms()
+            .await?
+            .into_iter()
+            .map(|llm| Model {
+                id: llm.id,
+                object: "model".into(),
+                created: 0,
+                owned_by: llm.organization,
+            })
+            .collect();
+        Ok(ModelList {
+            object: "list".into(),
+            data,
+        })
+    }
+
+    /// Answers a chat, like OpenAI's `/v1/chat/completions`.
+    ///
+    /// Requires [crate::interface::UserPermissions::perm_session].
+    pub async fn chat_completions(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, PantryError> {
+        let (session, prompt, params) = self.prepare_chat(request).await?;
+        let model = model_name(&request.model, &session);
+        let completion = complete(session, &prompt, params).await?;
+        let finish_reason = openai_reason(completion.finish_reason)?;
+        Ok(ChatCompletionResponse {
+            id: completion_id("chatcmpl"),
+            object: "chat.completion".into(),
+            created: chrono::Utc::now().timestamp(),
+            model,
+            usage: usage(&prompt, &completion),
+            choices: vec![ChatChoice {
+                index: 0,
+                message: ChatMessage::assistant(completion.text.trim()),
+                finish_reason: Some(finish_reason),
+            }],
+        })
+    }
+
+    /// Like [PantryClient::chat_completions], but streams the reply as it's generated,
+    /// like `"stream": true` does for OpenAI.
+    pub async fn chat_completions_stream(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<ChunkStream<ChatCompletionChunk>, PantryError> {
+        let (session, prompt, params) = self.prepare_chat(request).await?;
+        let model = model_name(&request.model, &session);
+        let (session, events) = start(session, prompt, params).await?;
+
+        let id = completion_id("chatcmpl");
+        let created = chrono::Utc::now().timestamp();
+        let mut first = true;
+        Ok(chunks(session, events, move |content, finish_reason| {
+            let role = if first { Some(Role::Assistant) } else { None };
+            first = false;
+            ChatCompletionChunk {
+                id: id.clone(),
+                object: "chat.completion.chunk".into(),
+                created,
+                model: model.clone(),
+                choices: vec![ChatChunkChoice {
+                    index: 0,
+                    delta: ChatDelta { role, content },
+                    finish_reason,
+                }],
+            }
+        }))
+    }
+
+    /// Continues a prompt, like OpenAI's `/v1/completions`.
+    ///
+    /// Requires [crate::interface::UserPermissions::perm_session].
+    pub async fn completions(
+        &self,
+        request: &CompletionRequest,
+    ) -> Result<CompletionResponse, PantryError> {
+        let session = self.session_for_model(&request.model).await?;
+        let model = model_name(&request.model, &session);
+        let completion = complete(session, &request.prompt, request.params()).await?;
+        let finish_reason = openai_reason(completion.finish_reason)?;
+        Ok(CompletionResponse {
+            id: completion_id("cmpl"),
+            object: "text_completion".into(),
+            created: chrono::Utc::now().timestamp(),
+            model,
+            usage: Some(usage(&request.prompt, &completion)),
+            choices: vec![CompletionChoice {
+                index: 0,
+                text: completion.text,
+                finish_reason: Some(finish_reason),
+            }],
+        })
+    }
+
+    /// Like [PantryClient::completions], but streams the text as it's generated.
+    pub async fn completions_stream(
+        &self,
+        request: &CompletionRequest,
+    ) -> Result<ChunkStream<CompletionResponse>, PantryError> {
+        let session = self.session_for_model(&request.model).await?;
+        let model = model_name(&request.model, &session);
+        let (session, events) = start(session, request.prompt.clone(), request.params()).await?;
+
+        let id = completion_id("cmpl");
+        let created = chrono::Utc::now().timestamp();
+        Ok(chunks(session, events, move |text, finish_reason| {
+            CompletionResponse {
+                id: id.clone(),
+                object: "text_completion".into(),
+                created,
+                model: model.clone(),
+                usage: None,
+                choices: vec![CompletionChoice {
+                    index: 0,
+                    text: text.unwrap_or_default(),
+                    finish_reason,
+                }],
+            }
+        }))
+    }
+
+    /// A fresh session for `model`, see the [module docs](self).
+    async fn session_for_model(&self, model: &str) -> Result<LLMSession, PantryError> {
+        if model.is_empty() || model == DEFAULT_MODEL {
+            return self.create_session(HashMap::new()).await;
+        }
+        match model.parse::<LlmUuid>() {
+            Ok(llm_uuid) => self.create_session_id(llm_uuid, HashMap::new()).await,
+            Err(_) => {
+                self.create_session_flex(Some(LLMFilter::new().id(model)), None, HashMap::new())
+                    .await
+            }
+        }
+    }
+
+    /// A session for the request, with its messages rendered for the session's LLM.
+    async fn prepare_chat(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<(LLMSession, String, InferenceParams), PantryError> {
+        let session = self.session_for_model(&request.model).await?;
+        let format = PromptFormat::detect(&session.llm_status);
+        let prompt = format.render(&request.messages, true);
+        let mut params = request.params();
+        for stop in format.stop_sequences() {
+            if !params.stop_sequences.contains(&stop) {
+                params.stop_sequences.push(stop);
+            }
+        }
+        Ok((session, prompt, params))
+    }
+}
+
+fn params(
+    temperature: Option<f32>,
+    top_p: Option<f32>,
+    max_tokens: Option<u32>,
+    seed: Option<u64>,
+    stop: &Option<Stop>,
+) -> InferenceParams {
+    InferenceParams {
+        temperature,
+        top_p,
+        max_tokens,
+        seed,
+        stop_sequences: stop.as_ref().map(Stop::sequences).unwrap_or_default(),
+        ..Default::default()
+    }
+}
+
+/// `reason` for an OpenAI response. OpenAI has no reason for a failed LLM, so that's
+/// an error instead.
+fn openai_reason(reason: FinishReason) -> Result<FinishReason, PantryError> {
+    match reason {
+        FinishReason::Error => Err(PantryError::PromptError("the LLM failed".into())),
+        reason => Ok(reason),
+    }
+}
+
+fn completion_id(prefix: &str) -> String {
+    format!("{}-{}", prefix, Uuid::new_v4().simple())
+}
+
+/// `model` as requested, or the LLM's id if Pantry got to choose.
+fn model_name(model: &str, session: &LLMSession) -> String {
+    if model.is_empty() || model == DEFAULT_MODEL {
+        session.llm_status.id.clone()
+    } else {
+        model.to_string()
+    }
+}
+
+fn usage(prompt: &str, completion: &Completion) -> Usage {
+    let prompt_tokens = completion
+        .prompt_tokens
+        .unwrap_or_else(|| estimate_tokens(prompt) as u32);
+    Usage {
+        prompt_tokens,
+        completion_tokens: completion.completion_tokens,
+        total_tokens: prompt_tokens + completion.completion_tokens,
+    }
+}
+
+/// Prompts `session`, deleting it if that fails.
+async fn start(
+    session: LLMSession,
+    prompt: String,
+    params: InferenceParams,
+) -> Result<(LLMSession, LLMEventStream), PantryError> {
+    match session.prompt_session(prompt, params).await {
+        Ok(events) => Ok((session, events)),
+        Err(err) => {
+            discard(session).await;
+            Err(err)
+        }
+    }
+}
+
+/// Runs a prompt to completion in `session`, then deletes it.
+async fn complete(
+    session: LLMSession,
+    prompt: &str,
+    params: InferenceParams,
+) -> Result<Completion, PantryError> {
+    let res = match session.prompt_session(prompt.to_string(), params).await {
+        Ok(events) => events.collect_completion().await,
+        Err(err) => Err(err),
+    };
+    discard(session).await;
+    res
+}
+
+/// Turns inference events into chunks made by `make` from the new text or the finish
+/// reason, deleting `session` once the stream ends. Dropping the stream early interrupts
+/// the inference and deletes the session too.
+fn chunks<T, F>(session: LLMSession, events: LLMEventStream, make: F) -> ChunkStream<T>
+where
+    T: Send + 'static,
+    F: FnMut(Option<String>, Option<FinishReason>) -> T + Send + 'static,
+{
+    let upstream = Upstream {
+        session: Some(session),
+        events,
+    };
+    Box::pin(stream::unfold(Some((upstream, make)), |state| async move {
+        let (mut upstream, mut make) = state?;
+        loop {
+            let event = match upstream.events.next().await {
+                Some(Ok(event)) => event,
+                Some(Err(err)) => {
+                    upstream.discard().await;
+                    return Some((Err(err), None));
+                }
+                None => {
+                    upstream.discard().await;
+                    return None;
+                }
+            };
+            match event.event {
+                LLMEventInternal::PromptProgress { next, .. } if !next.is_empty() => {
+                    let chunk = make(Some(next), None);
+                    return Some((Ok(chunk), Some((upstream, make))));
+                }
+                LLMEventInternal::PromptCompletion { finish_reason, .. } => {
+                    let reason = openai_reason(finish_reason.unwrap_or(FinishReason::Stop));
+                    let chunk = reason.map(|reason| make(None, Some(reason)));
+                    upstream.discard().await;
+                    return Some((chunk, None));
+                }
+                LLMEventInternal::PromptError { message } => {
+                    upstream.discard().await;
+                    return Some((Err(PantryError::PromptError(message)), None));
+                }
+                _ => {}
+            }
+        }
+    }))
+}
+
+/// A streaming prompt's session, cleaned up however the stream ends.
+struct Upstream {
+    // Taken once discarded.
+    session: Option<LLMSession>,
+    events: LLMEventStream,
+}
+
+impl Upstream {
+    async fn discard(&mut self) {
+        if let Some(session) = self.session() {
+            discard(session).await;
+        }
+    }
+
+    /// The session, moved along if the prompt was sent again in a fresh one.
+    fn session(&mut self) -> Option<LLMSession> {
+        let mut session = self.session.take()?;
+        session.id = self.events.session_id();
+        Some(session)
+    }
+}
+
+impl Drop for Upstream {
+    fn drop(&mut self) {
+        let session = match self.session() {
+            Some(session) => session,
+            None => return,
+        };
+        let finished = self.events.finish_reason().is_some();
+        spawn_background(async move {
+            if !finished {
+                let _ = session
+                    .client
+                    .interrupt_session(
+                        session.user_id,
+                        session.api_key.clone(),
+                        session.llm_uuid,
+                        session.id,
+                    )
+                    .await;
+            }
+            discard(session).await;
+        });
+    }
+}
+
+/// Deletes a session we're done with. Daemons that can't delete sessions clean them up
+/// eventually anyway, so failures are ignored.
+async fn discard(session: LLMSession) {
+    let _ = session.delete().await;
+}
@@ -0,0 +1,309 @@
+//! Typed inference and load parameters.
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Inference parameters for prompting a session, see [crate::LLMSession::prompt_session].
+///
+/// Everything is optional; unset fields are left to the LLM's defaults. Parameters
+/// without a field here go in `extra`, under whatever key the LLM expects (see the
+/// `user_parameters` of its [crate::interface::LLMStatus]).
+///
+/// ```no_run
+/// # use pantry_rs::{InferenceParams, LLMSession};
+/// # async fn example(sess: LLMSession, prompt: String) -> Result<(), Box<dyn std::error::Error>> {
+/// let params = InferenceParams::new()
+///     .temperature(0.7)
+///     .top_k(40)
+///     .max_tokens(256)
+///     .stop("\nUser:");
+/// let text = sess.prompt_and_collect(prompt, params).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Plain `HashMap<String, Value>`s convert into this, ending up in `extra`, so existing
+/// code keeps working.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct InferenceParams {
+    pub temperature: Option<f32>,
+    pub top_k: Option<u32>,
+    pub top_p: Option<f32>,
+    pub repeat_penalty: Option<f32>,
+    /// Stop after this many tokens.
+    pub max_tokens: Option<u32>,
+    pub seed: Option<u64>,
+    /// Stop as soon as the completion contains one of these.
+    pub stop_sequences: Vec<String>,
+    /// Restricts what the LLM can generate, see [Constraint].
+    pub constraint: Option<Constraint>,
+    /// Anything else, sent as is. Typed fields win if a key appears in both.
+    pub extra: HashMap<String, Value>,
+}
+
+impl InferenceParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn top_k(mut self, top_k: u32) -> Self {
+        self.top_k = Some(top_k);
+        self
+    }
+
+    pub fn top_p(mut self, top_p: f32) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    pub fn repeat_penalty(mut self, repeat_penalty: f32) -> Self {
+        self.repeat_penalty = Some(repeat_penalty);
+        self
+    }
+
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Adds a stop sequence. Can be repeated.
+    pub fn stop(mut self, sequence: impl Into<String>) -> Self {
+        self.stop_sequences.push(sequence.into());
+        self
+    }
+
+    /// Only generate text matching a GBNF `grammar`, see [Constraint::Grammar].
+    pub fn grammar(mut self, grammar: impl Into<String>) -> Self {
+        self.constraint = Some(Constraint::Grammar(grammar.into()));
+        self
+    }
+
+    /// Only generate JSON matching `schema`, see [Constraint::JsonSchema].
+    pub fn json_schema(mut self, schema: Value) -> Self {
+        self.constraint = Some(Constraint::JsonSchema(schema));
+        self
+    }
+
+    /// Only generate text matching `regex`, see [Constraint::Regex].
+    pub fn regex(mut self, regex: impl Into<String>) -> Self {
+        self.constraint = Some(Constraint::Regex(regex.into()));
+        self
+    }
+
+    /// Sets a parameter that has no typed field.
+    pub fn extra(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.extra.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sampler settings in the `sampler_string` format the LLMrs connector reads,
+    /// e.g. `repetition:penalty=1.3/topk:k=40/topp:p=0.95/temperature:temperature=0.8`.
+    /// `None` if no sampler settings are set.
+    pub fn sampler_string(&self) -> Option<String> {
+        let mut samplers = Vec::new();
+        if let Some(penalty) = self.repeat_penalty {
+            samplers.push(format!("repetition:penalty={}", penalty));
+        }
+        if let Some(k) = self.top_k {
+            samplers.push(format!("topk:k={}", k));
+        }
+        if let Some(p) = self.top_p {
+            samplers.push(format!("topp:p={}", p));
+        }
+        if let Some(temperature) = self.temperature {
+            samplers.push(format!("temperature:temperature={}", temperature));
+        }
+        if samplers.is_empty() {
+            None
+        } else {
+            Some(samplers.join("/"))
+        }
+    }
+
+    /// The parameter map sent to Pantry.
+    ///
+    /// Typed fields use the keys `temperature`, `top_k`, `top_p`, `repeat_penalty`,
+    /// `max_tokens`, `seed` and `stop_sequences`, and the constraint its
+    /// [Constraint::key]. Sampler settings are additionally
+    /// sent as `sampler_string` (unless `extra` has one), since that's all the LLMrs
+    /// connector understands.
+    pub fn into_map(self) -> HashMap<String, Value> {
+        let sampler_string = self.sampler_string();
+        let mut map = self.extra;
+        if let Some(sampler_string) = sampler_string {
+            map.entry("sampler_string".into())
+                .or_insert_with(|| sampler_string.into());
+        }
+        if let Some(temperature) = self.temperature {
+            map.insert("temperature".into(), temperature.into());
+        }
+        if let Some(top_k) = self.top_k {
+            map.insert("top_k".into(), top_k.into());
+        }
+        if let Some(top_p) = self.top_p {
+            map.insert("top_p".into(), top_p.into());
+        }
+        if let Some(repeat_penalty) = self.repeat_penalty {
+            map.insert("repeat_penalty".into(), repeat_penalty.into());
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            map.insert("max_tokens".into(), max_tokens.into());
+        }
+        if let Some(seed) = self.seed {
+            map.insert("seed".into(), seed.into());
+        }
+        if !self.stop_sequences.is_empty() {
+            map.insert("stop_sequences".into(), self.stop_sequences.into());
+        }
+        if let Some(constraint) = self.constraint {
+            map.insert(constraint.key().into(), constraint.into_value());
+        }
+        map
+    }
+}
+
+/// Constrained decoding: the LLM can only pick tokens that keep its output matching.
+///
+/// Guarantees the shape of the output, where asking for it in the prompt only makes it
+/// likely. Only some connectors support it, and those that don't ignore it; check with
+/// [crate::interface::LLMStatus::supports_constraint] first.
+///
+/// ```no_run
+/// # use pantry_rs::{InferenceParams, LLMSession};
+/// # use serde_json::json;
+/// # async fn example(sess: LLMSession) -> Result<(), Box<dyn std::error::Error>> {
+/// let params = InferenceParams::new().json_schema(json!({
+///     "type": "object",
+///     "properties": { "score": { "type": "integer" } },
+///     "required": ["score"],
+/// }));
+/// if !sess.llm_status.supports_constraint(params.constraint.as_ref().unwrap()) {
+///     // Fall back to structured::prompt_typed's retries.
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Constraint {
+    /// A grammar in llama.cpp's GBNF format, starting at its `root` rule.
+    Grammar(String),
+    /// A JSON schema; the output is JSON that validates against it.
+    JsonSchema(Value),
+    /// A regular expression the whole output matches.
+    Regex(String),
+}
+
+impl Constraint {
+    /// The parameter it's sent as, which is also what an LLM lists in its
+    /// `user_parameters` if it supports it.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Constraint::Grammar(_) => "grammar",
+            Constraint::JsonSchema(_) => "json_schema",
+            Constraint::Regex(_) => "regex",
+        }
+    }
+
+    fn into_value(self) -> Value {
+        match self {
+            Constraint::Grammar(grammar) => grammar.into(),
+            Constraint::JsonSchema(schema) => schema,
+            Constraint::Regex(regex) => regex.into(),
+        }
+    }
+}
+
+impl From<HashMap<String, Value>> for InferenceParams {
+    fn from(extra: HashMap<String, Value>) -> Self {
+        InferenceParams {
+            extra,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<InferenceParams> for HashMap<String, Value> {
+    fn from(params: InferenceParams) -> Self {
+        params.into_map()
+    }
+}
+
+/// Resource limits for loading an LLM, see [crate::PantryClient::load_llm_with].
+///
+/// Sent along with the load and merged into the LLM's connector config, so they only
+/// apply to this load. Unset fields are left to the connector's defaults, and not every
+/// connector honours every field; remote ones ignore them all.
+///
+/// ```no_run
+/// # use pantry_rs::{LoadOptions, PantryClient};
+/// # async fn example(pantry: PantryClient, llm_id: String) -> Result<(), Box<dyn std::error::Error>> {
+/// // Keep a laptop usable while the model runs.
+/// let options = LoadOptions::new().gpu_layers(20).n_threads(4).max_ram_mb(6144);
+/// pantry.load_llm_with(llm_id, options).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LoadOptions {
+    /// How many layers to offload to the GPU, 0 for none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_layers: Option<u32>,
+    /// Refuse to load if the model would need more than this.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_ram_mb: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n_threads: Option<u32>,
+    /// Whether to memory-map the model file rather than reading it into memory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mmap: Option<bool>,
+    /// Connector settings without a field here, sent as is.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl LoadOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn gpu_layers(mut self, gpu_layers: u32) -> Self {
+        self.gpu_layers = Some(gpu_layers);
+        self
+    }
+
+    pub fn max_ram_mb(mut self, max_ram_mb: u64) -> Self {
+        self.max_ram_mb = Some(max_ram_mb);
+        self
+    }
+
+    pub fn n_threads(mut self, n_threads: u32) -> Self {
+        self.n_threads = Some(n_threads);
+        self
+    }
+
+    pub fn mmap(mut self, mmap: bool) -> Self {
+        self.mmap = Some(mmap);
+        self
+    }
+
+    /// Sets a connector setting that has no typed field.
+    pub fn extra(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.extra.insert(key.into(), value.into());
+        self
+    }
+
+    /// Whether nothing is set, leaving the load as it would be without options.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
@@ -0,0 +1,243 @@
+//! Instruction templates for common model families.
+//!
+//! Chat models are trained with specific wrapper tokens around each turn, and a model
+//! prompted in the wrong format rambles or answers its own questions. [PromptFormat]
+//! knows the common ones and can pick one for an [LLMStatus], which is what makes
+//! [crate::PantryClient::create_session] (where Pantry picks the model) usable for chat.
+//!
+//! ```no_run
+//! # use pantry_rs::{ChatSession, PantryClient, PromptFormat};
+//! # use std::collections::HashMap;
+//! # async fn example(pantry: PantryClient) -> Result<(), Box<dyn std::error::Error>> {
+//! // Picked from the model's config, tags or family.
+//! let chat = ChatSession::new(pantry.create_session(HashMap::new()).await?);
+//! // Or, if you know better:
+//! let sess = pantry.create_session(HashMap::new()).await?;
+//! let chat = ChatSession::with_template(sess, PromptFormat::ChatML);
+//! # Ok(())
+//! # }
+//! ```
+use crate::chat::{ChatMessage, ChatTemplate, Role};
+use crate::error::PantryError;
+use crate::interface::LLMStatus;
+use std::fmt;
+use std::str::FromStr;
+
+/// A standard chat format, usable as a [ChatTemplate].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PromptFormat {
+    /// `System:`/`User:`/`Assistant:` lines, see [crate::chat::PlainTemplate]. For base
+    /// models and anything unrecognized.
+    Plain,
+    /// `<|im_start|>role ... <|im_end|>`, used by OpenHermes, Dolphin, Qwen and others.
+    ChatML,
+    /// `[INST] <<SYS>> ... <</SYS>> ... [/INST]`, used by Llama 2 chat models.
+    Llama2,
+    /// `[INST] ... [/INST]` without a system prompt, used by Mistral and Mixtral
+    /// instruct models. System messages are folded into the first instruction.
+    Mistral,
+    /// `### Instruction:`/`### Response:`, used by Alpaca and many fine-tunes of it.
+    Alpaca,
+    /// `USER:`/`ASSISTANT:`, used by Vicuna.
+    Vicuna,
+}
+
+impl PromptFormat {
+    pub const ALL: [PromptFormat; 6] = [
+        PromptFormat::Plain,
+        PromptFormat::ChatML,
+        PromptFormat::Llama2,
+        PromptFormat::Mistral,
+        PromptFormat::Alpaca,
+        PromptFormat::Vicuna,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PromptFormat::Plain => "plain",
+            PromptFormat::ChatML => "chatml",
+            PromptFormat::Llama2 => "llama2",
+            PromptFormat::Mistral => "mistral",
+            PromptFormat::Alpaca => "alpaca",
+            PromptFormat::Vicuna => "vicuna",
+        }
+    }
+
+    /// Picks the format for `llm`, looking at (in order):
+    ///
+    /// * a `prompt_format` key in its config, set by whoever registered it
+    /// * a tag naming a format, e.g. `chatml`
+    /// * well-known names in its family, id or name, e.g. `llama-2-*-chat`
+    ///
+    /// Falls back to [PromptFormat::Plain].
+    pub fn detect(llm: &LLMStatus) -> PromptFormat {
+        if let Some(format) = llm
+            .config
+            .get("prompt_format")
+            .and_then(|v| v.as_str())
+            .and_then(|s| s.parse().ok())
+        {
+            return format;
+        }
+        if let Some(format) = llm.tags.iter().find_map(|tag| tag.parse().ok()) {
+            return format;
+        }
+        [&llm.family_id, &llm.id, &llm.name]
+            .iter()
+            .find_map(|name| PromptFormat::from_name(name))
+            .unwrap_or(PromptFormat::Plain)
+    }
+
+    /// Guesses from a model name, `None` if nothing looks familiar.
+    fn from_name(name: &str) -> Option<PromptFormat> {
+        let name = name.to_lowercase().replace(['_', ' '], "-");
+        let has = |needles: &[&str]| needles.iter().any(|n| name.contains(n));
+        if has(&["chatml", "openhermes", "dolphin", "qwen", "yi-", "orca-2"]) {
+            Some(PromptFormat::ChatML)
+        } else if has(&["mistral", "mixtral"]) && has(&["instruct"]) {
+            Some(PromptFormat::Mistral)
+        } else if has(&["llama-2", "llama2", "codellama"]) && has(&["chat", "instruct"]) {
+            Some(PromptFormat::Llama2)
+        } else if has(&["vicuna"]) {
+            Some(PromptFormat::Vicuna)
+        } else if has(&["alpaca", "wizardlm", "gpt4all"]) {
+            Some(PromptFormat::Alpaca)
+        } else {
+            None
+        }
+    }
+}
+
+impl ChatTemplate for PromptFormat {
+    fn render(&self, messages: &[ChatMessage], add_generation_prompt: bool) -> String {
+        match self {
+            PromptFormat::Plain => {
+                crate::chat::PlainTemplate.render(messages, add_generation_prompt)
+            }
+            PromptFormat::ChatML => {
+                let mut out = String::new();
+                for message in messages {
+                    out.push_str(&format!(
+                        "<|im_start|>{}\n{}<|im_end|>\n",
+                        role_name(message.role),
+                        message.content
+                    ));
+                }
+                if add_generation_prompt {
+                    out.push_str("<|im_start|>assistant\n");
+                }
+                out
+            }
+            PromptFormat::Llama2 | PromptFormat::Mistral => {
+                // Both put the system prompt inside the first instruction, Llama 2 in
+                // <<SYS>> markers. Nothing to add for generation, [/INST] is the cue.
+                let mut out = String::new();
+                let mut system = String::new();
+                let mut first = true;
+                for message in messages {
+                    match message.role {
+                        Role::System => {
+                            if *self == PromptFormat::Llama2 {
+                                system.push_str(&format!(
+                                    "<<SYS>>\n{}\n<</SYS>>\n\n",
+                                    message.content
+                                ));
+                            } else {
+                                system.push_str(&format!("{}\n\n", message.content));
+                            }
+                        }
+                        Role::User => {
+                            if !first {
+                                out.push_str("<s>");
+                            }
+                            out.push_str(&format!(
+                                "[INST] {}{} [/INST]",
+                                std::mem::take(&mut system),
+                                message.content
+                            ));
+                            first = false;
+                        }
+                        Role::Assistant => {
+                            out.push_str(&format!(" {} </s>", message.content));
+                        }
+                    }
+                }
+                out
+            }
+            PromptFormat::Alpaca => {
+                let mut out = String::new();
+                for message in messages {
+                    match message.role {
+                        Role::System => out.push_str(&format!("{}\n\n", message.content)),
+                        Role::User => {
+                            out.push_str(&format!("### Instruction:\n{}\n\n", message.content))
+                        }
+                        Role::Assistant => {
+                            out.push_str(&format!("### Response:\n{}\n\n", message.content))
+                        }
+                    }
+                }
+                if add_generation_prompt {
+                    out.push_str("### Response:\n");
+                }
+                out
+            }
+            PromptFormat::Vicuna => {
+                let mut out = String::new();
+                for message in messages {
+                    match message.role {
+                        Role::System => out.push_str(&format!("{}\n\n", message.content)),
+                        Role::User => out.push_str(&format!("USER: {}\n", message.content)),
+                        Role::Assistant => {
+                            out.push_str(&format!("ASSISTANT: {}\n", message.content))
+                        }
+                    }
+                }
+                if add_generation_prompt {
+                    out.push_str("ASSISTANT:");
+                }
+                out
+            }
+        }
+    }
+
+    fn stop_sequences(&self) -> Vec<String> {
+        match self {
+            PromptFormat::Plain => crate::chat::PlainTemplate.stop_sequences(),
+            PromptFormat::ChatML => vec!["<|im_end|>".into()],
+            PromptFormat::Llama2 | PromptFormat::Mistral => {
+                vec!["</s>".into(), "[INST]".into()]
+            }
+            PromptFormat::Alpaca => vec!["### Instruction:".into()],
+            PromptFormat::Vicuna => vec!["</s>".into(), "\nUSER:".into()],
+        }
+    }
+}
+
+fn role_name(role: Role) -> &'static str {
+    match role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+    }
+}
+
+impl FromStr for PromptFormat {
+    type Err = PantryError;
+
+    /// Case-insensitive, ignoring `-` and `_`, so `ChatML`, `llama-2` and `llama_2` all
+    /// work.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.to_lowercase().replace(['-', '_'], "");
+        PromptFormat::ALL
+            .into_iter()
+            .find(|format| format.as_str() == normalized)
+            .ok_or_else(|| PantryError::OtherFailure(format!("unknown prompt format: {}", s)))
+    }
+}
+
+impl fmt::Display for PromptFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
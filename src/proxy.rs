@@ -0,0 +1,264 @@
+//! A local OpenAI-compatible HTTP server in front of Pantry.
+//!
+//! Lets tools that only speak the OpenAI API (Python scripts, editor plugins,
+//! LangChain) use Pantry's models by pointing their base url at the proxy. Requires the
+//! `proxy` feature.
+//!
+//! ```no_run
+//! # use pantry_rs::proxy::Proxy;
+//! # use pantry_rs::PantryClient;
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let pantry = PantryClient::from_env()?;
+//! Proxy::new(pantry).serve(([127, 0, 0, 1], 8080).into()).await?;
+//! // OPENAI_BASE_URL=http://127.0.0.1:8080/v1 python my_script.py
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Served endpoints are `GET /v1/models`, `POST /v1/chat/completions` and
+//! `POST /v1/completions`, streaming included; see [crate::openai_compat] for how
+//! requests map onto Pantry. All calls go to Pantry as the [PantryClient]'s user.
+use crate::error::PantryError;
+use crate::logging::REDACTED;
+use crate::openai_compat::{ChatCompletionRequest, ChunkStream, CompletionRequest};
+use crate::PantryClient;
+use futures::future;
+use futures::stream::{self, StreamExt};
+use hyper::body::Bytes;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde_json::json;
+use std::convert::Infallible;
+use std::fmt;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Serves the OpenAI API on top of a [PantryClient]. See the [module docs](self).
+#[derive(Clone)]
+pub struct Proxy {
+    pub pantry: PantryClient,
+    /// Key clients have to send as `Authorization: Bearer <key>`. `None` lets anyone
+    /// who can reach the proxy in, so only bind to localhost without one.
+    pub api_key: Option<String>,
+}
+
+impl Proxy {
+    pub fn new(pantry: PantryClient) -> Self {
+        Proxy {
+            pantry,
+            api_key: None,
+        }
+    }
+
+    /// Requires clients to authenticate with `api_key`.
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Serves on `addr` until the process exits.
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), PantryError> {
+        self.serve_with_shutdown(addr, future::pending()).await
+    }
+
+    /// Serves on `addr` until `signal` completes, then finishes the calls in flight.
+    pub async fn serve_with_shutdown(
+        self,
+        addr: SocketAddr,
+        signal: impl Future<Output = ()>,
+    ) -> Result<(), PantryError> {
+        let proxy = Arc::new(self);
+        let make_service = make_service_fn(move |_conn| {
+            let proxy = proxy.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let proxy = proxy.clone();
+                    async move { Ok::<_, Infallible>(proxy.handle(req).await) }
+                }))
+            }
+        });
+        hyper::Server::try_bind(&addr)?
+            .serve(make_service)
+            .with_graceful_shutdown(signal)
+            .await?;
+        Ok(())
+    }
+
+    /// Answers a single request. For embedding the proxy in a server of your own.
+    pub async fn handle(&self, req: Request<Body>) -> Response<Body> {
+        if !self.authorized(&req) {
+            return error_response(
+                StatusCode::UNAUTHORIZED,
+                "invalid_api_key",
+                "Missing or invalid API key",
+            );
+        }
+
+        let method = req.method().clone();
+        let path = req.uri().path().trim_end_matches('/').to_string();
+        match (&method, path.as_str()) {
+            (&Method::GET, "/v1/models") => match self.pantry.models().await {
+                Ok(models) => json_response(&models),
+                Err(err) => pantry_error_response(err),
+            },
+            (&Method::POST, "/v1/chat/completions") => {
+                let request: ChatCompletionRequest = match read_json(req).await {
+                    Ok(request) => request,
+                    Err(resp) => return resp,
+                };
+                if request.stream {
+                    match self.pantry.chat_completions_stream(&request).await {
+                        Ok(chunks) => event_stream_response(chunks),
+                        Err(err) => pantry_error_response(err),
+                    }
+                } else {
+                    match self.pantry.chat_completions(&request).await {
+                        Ok(response) => json_response(&response),
+                        Err(err) => pantry_error_response(err),
+                    }
+                }
+            }
+            (&Method::POST, "/v1/completions") => {
+                let request: CompletionRequest = match read_json(req).await {
+                    Ok(request) => request,
+                    Err(resp) => return resp,
+                };
+                if request.stream {
+                    match self.pantry.completions_stream(&request).await {
+                        Ok(chunks) => event_stream_response(chunks),
+                        Err(err) => pantry_error_response(err),
+                    }
+                } else {
+                    match self.pantry.completions(&request).await {
+                        Ok(response) => json_response(&response),
+                        Err(err) => pantry_error_response(err),
+                    }
+                }
+            }
+            _ => error_response(
+                StatusCode::NOT_FOUND,
+                "not_found",
+                &format!("Unknown endpoint {} {}", method, path),
+            ),
+        }
+    }
+
+    fn authorized(&self, req: &Request<Body>) -> bool {
+        let api_key = match &self.api_key {
+            Some(api_key) => api_key,
+            None => return true,
+        };
+        req.headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|key| key == api_key)
+    }
+}
+
+// Keeps keys, the proxy's and Pantry's, out of logs and panics.
+impl fmt::Debug for Proxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Proxy")
+            .field("user_id", &self.pantry.user_id)
+            .field("api_key", &self.api_key.as_ref().map(|_| REDACTED))
+            .finish_non_exhaustive()
+    }
+}
+
+async fn read_json<T: serde::de::DeserializeOwned>(
+    req: Request<Body>,
+) -> Result<T, Response<Body>> {
+    let body = hyper::body::to_bytes(req.into_body()).await.map_err(|e| {
+        error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_request_error",
+            &e.to_string(),
+        )
+    })?;
+    serde_json::from_slice(&body).map_err(|e| {
+        error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_request_error",
+            &e.to_string(),
+        )
+    })
+}
+
+fn json_response<T: serde::Serialize>(value: &T) -> Response<Body> {
+    match serde_json::to_vec(value) {
+        Ok(body) => Response::builder()
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap(),
+        Err(err) => pantry_error_response(err.into()),
+    }
+}
+
+/// Sends `chunks` as server-sent events, ending with `[DONE]` like OpenAI does.
+fn event_stream_response<T: serde::Serialize + Send + 'static>(
+    chunks: ChunkStream<T>,
+) -> Response<Body> {
+    let events = chunks
+        .map(|chunk| {
+            let data = match chunk {
+                Ok(chunk) => serde_json::to_string(&chunk)
+                    .unwrap_or_else(|e| error_json("internal_error", &e.to_string()).to_string()),
+                Err(err) => error_json(error_code(&err).1, &err.to_string()).to_string(),
+            };
+            format!("data: {}\n\n", data)
+        })
+        .chain(stream::once(future::ready("data: [DONE]\n\n".to_string())))
+        .map(|event| Ok::<_, Infallible>(Bytes::from(event)));
+    Response::builder()
+        .header(CONTENT_TYPE, "text/event-stream")
+        .header("Cache-Control", "no-cache")
+        .body(Body::wrap_stream(events))
+        .unwrap()
+}
+
+fn pantry_error_response(err: PantryError) -> Response<Body> {
+    let (status, code) = error_code(&err);
+    error_response(status, code, &err.to_string())
+}
+
+/// Status and OpenAI error code for a failed call.
+fn error_code(err: &PantryError) -> (StatusCode, &'static str) {
+    match err {
+        PantryError::InvalidApiKey(_) => (StatusCode::UNAUTHORIZED, "invalid_api_key"),
+        PantryError::PermissionDenied(_) => (StatusCode::FORBIDDEN, "permission_denied"),
+        PantryError::LlmNotFound(_) | PantryError::LlmNotRunning(_) => {
+            (StatusCode::NOT_FOUND, "model_not_found")
+        }
+        PantryError::SessionNotFound(_) | PantryError::NotFound(_) => {
+            (StatusCode::NOT_FOUND, "not_found")
+        }
+        PantryError::ContextOverflow(_, _) => (StatusCode::BAD_REQUEST, "context_length_exceeded"),
+        PantryError::DeserializationError(_) => (StatusCode::BAD_REQUEST, "invalid_request_error"),
+        PantryError::Timeout(_) => (StatusCode::GATEWAY_TIMEOUT, "timeout"),
+        PantryError::HyperError(_) | PantryError::ApiError(_, _) => {
+            (StatusCode::BAD_GATEWAY, "upstream_error")
+        }
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+    }
+}
+
+fn error_json(code: &str, message: &str) -> serde_json::Value {
+    json!({
+        "error": {
+            "message": message,
+            "type": code,
+            "code": code,
+        }
+    })
+}
+
+fn error_response(status: StatusCode, code: &str, message: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(error_json(code, message).to_string()))
+        .unwrap()
+}
@@ -0,0 +1,138 @@
+//! Limiting how many prompts run on an LLM at once.
+//!
+//! A local LLM works through prompts one after the other, however many are sent its
+//! way; firing several at once just has them fight over it, and their tokens come back
+//! interleaved with whatever else is running. A [PromptQueue] holds prompts back until
+//! one of an LLM's slots is free, in the order they were made.
+//!
+//! ```no_run
+//! # use pantry_rs::{InferenceParams, LLMSession, PromptQueue};
+//! # async fn example(sess_a: LLMSession, sess_b: LLMSession) -> Result<(), Box<dyn std::error::Error>> {
+//! let queue = PromptQueue::new(1);
+//! let (a, b) = futures::join!(
+//!     queue.prompt(&sess_a, "Once upon a time".into(), InferenceParams::new()),
+//!     queue.prompt(&sess_b, "It was a dark and stormy night".into(), InferenceParams::new()),
+//! );
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Slots are per LLM, so sessions on different LLMs don't wait on each other. A prompt
+//! keeps its slot until its stream ends or is dropped.
+use crate::error::PantryError;
+use crate::ids::LlmUuid;
+use crate::params::InferenceParams;
+use crate::stream::LLMEventStream;
+use crate::LLMSession;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+
+/// Limits concurrent prompts per LLM, see the [module docs](self).
+///
+/// Cloning gives another handle to the same queue.
+#[derive(Clone, Debug)]
+pub struct PromptQueue {
+    max_in_flight: usize,
+    slots: Arc<Mutex<HashMap<LlmUuid, Arc<Slots>>>>,
+}
+
+#[derive(Debug)]
+struct Slots {
+    // Hands out permits first come, first served.
+    semaphore: Arc<Semaphore>,
+    waiting: AtomicUsize,
+}
+
+impl PromptQueue {
+    /// A queue letting up to `max_in_flight` prompts run on each LLM, at least one.
+    pub fn new(max_in_flight: usize) -> Self {
+        PromptQueue {
+            max_in_flight: max_in_flight.max(1),
+            slots: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// A queue running one prompt per LLM at a time.
+    pub fn serial() -> Self {
+        Self::new(1)
+    }
+
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
+    /// Prompts `session` once its LLM has a free slot, see [LLMSession::prompt_session].
+    ///
+    /// Dropping the future before then gives up its place in the queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` — The session to prompt.
+    /// * `prompt` — Prompt for the LLM.
+    /// * `parameters` — As for [LLMSession::prompt_session].
+    pub async fn prompt(
+        &self,
+        session: &LLMSession,
+        prompt: String,
+        parameters: impl Into<InferenceParams>,
+    ) -> Result<LLMEventStream, PantryError> {
+        let slots = self.slots(session.llm_uuid);
+        let permit = {
+            let _waiting = Waiting::new(&slots.waiting);
+            slots.semaphore.clone().acquire_owned().await
+        };
+        // The semaphore is never closed.
+        let permit = permit.expect("prompt queue semaphore closed");
+
+        let mut stream = session.prompt_session(prompt, parameters).await?;
+        stream.hold(permit);
+        Ok(stream)
+    }
+
+    /// Prompts running on `llm_uuid` through this queue.
+    pub fn in_flight(&self, llm_uuid: LlmUuid) -> usize {
+        self.max_in_flight - self.slots(llm_uuid).semaphore.available_permits()
+    }
+
+    /// Prompts for `llm_uuid` waiting for a slot.
+    pub fn waiting(&self, llm_uuid: LlmUuid) -> usize {
+        self.slots(llm_uuid).waiting.load(Ordering::SeqCst)
+    }
+
+    fn slots(&self, llm_uuid: LlmUuid) -> Arc<Slots> {
+        let mut slots = self.slots.lock().unwrap();
+        slots
+            .entry(llm_uuid)
+            .or_insert_with(|| {
+                Arc::new(Slots {
+                    semaphore: Arc::new(Semaphore::new(self.max_in_flight)),
+                    waiting: AtomicUsize::new(0),
+                })
+            })
+            .clone()
+    }
+}
+
+/// Counts a prompt as waiting until dropped, including when its future is.
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl<'a> Waiting<'a> {
+    fn new(count: &'a AtomicUsize) -> Self {
+        count.fetch_add(1, Ordering::SeqCst);
+        Waiting(count)
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Default for PromptQueue {
+    fn default() -> Self {
+        Self::serial()
+    }
+}
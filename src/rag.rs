@@ -0,0 +1,270 @@
+//! Answering questions from your own documents.
+//!
+//! Retrieval-augmented generation in three parts: [chunk_text] cuts documents into
+//! pieces small enough to put in a prompt, a [Corpus] embeds them and finds the ones
+//! closest to a question, and [answer_with_context] prompts a session with those pieces
+//! in front of the question.
+//!
+//! Embeddings come from an [Embedder], e.g. a local embedding model or a remote API.
+//!
+//! ```no_run
+//! # use pantry_rs::rag::{self, Corpus, Embedder};
+//! # use pantry_rs::{LLMSession, PantryError};
+//! # struct MyEmbedder;
+//! # #[async_trait::async_trait]
+//! # impl Embedder for MyEmbedder {
+//! #     async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, PantryError> {
+//! #         Ok(texts.iter().map(|_| vec![0.0]).collect())
+//! #     }
+//! # }
+//! # impl MyEmbedder {
+//! #     fn load() -> Result<Self, PantryError> { Ok(MyEmbedder) }
+//! # }
+//! # async fn example(sess: LLMSession, documents: Vec<(String, String)>) -> Result<(), Box<dyn std::error::Error>> {
+//! let mut corpus = Corpus::new(MyEmbedder::load()?);
+//! for (path, text) in documents {
+//!     corpus.add_document(path, &text).await?;
+//! }
+//! let answer = rag::answer_with_context(&sess, "When was the warranty extended?", &corpus, 4).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The store is in memory and searched exhaustively, which is plenty for the few
+//! thousand chunks a prompt-sized corpus runs to.
+use crate::error::PantryError;
+use crate::params::InferenceParams;
+use crate::LLMSession;
+use async_trait::async_trait;
+use std::cmp::Ordering;
+
+/// Characters per chunk [Corpus::add_document] aims for.
+pub const DEFAULT_CHUNK_CHARS: usize = 1000;
+
+/// Characters consecutive chunks share, so a sentence cut in two is whole in one.
+pub const DEFAULT_CHUNK_OVERLAP: usize = 100;
+
+/// Turns text into vectors that are close together for texts with similar meaning.
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// One embedding per text, in order, all of the same length.
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, PantryError>;
+}
+
+/// A piece of a document, see [chunk_text].
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Chunk {
+    /// Where it came from, e.g. a file name.
+    pub source: String,
+    pub text: String,
+}
+
+/// Cuts `text` into chunks of up to `max_chars` characters, breaking between paragraphs
+/// where possible and between words otherwise. Each chunk starts with about `overlap`
+/// characters from the end of the one before, unless it starts a new paragraph.
+///
+/// A single word longer than `max_chars` becomes a chunk of its own.
+pub fn chunk_text(text: &str, max_chars: usize, overlap: usize) -> Vec<String> {
+    let max_chars = max_chars.max(1);
+    let mut chunks = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut len = 0;
+    for paragraph in text.split("\n\n").map(str::trim) {
+        if paragraph.is_empty() {
+            continue;
+        }
+        let paragraph_len = paragraph.chars().count();
+        if len > 0 && len + 2 + paragraph_len <= max_chars {
+            current.push("\n\n");
+            current.push(paragraph);
+            len += 2 + paragraph_len;
+            continue;
+        }
+        if len > 0 {
+            chunks.push(current.concat());
+            current.clear();
+            len = 0;
+        }
+        if paragraph_len <= max_chars {
+            current.push(paragraph);
+            len = paragraph_len;
+            continue;
+        }
+
+        // Too long for one chunk, so go word by word.
+        let mut words: Vec<&str> = Vec::new();
+        let mut words_len = 0;
+        for word in paragraph.split_whitespace() {
+            let word_len = word.chars().count();
+            if !words.is_empty() && words_len + 1 + word_len > max_chars {
+                chunks.push(words.join(" "));
+                // Carry over whole words from the end, up to `overlap` characters.
+                let mut kept = 0;
+                let mut carried = 0;
+                for word in words.iter().rev() {
+                    let with = carried + word.chars().count() + 1;
+                    if with > overlap || with + word_len > max_chars {
+                        break;
+                    }
+                    carried = with;
+                    kept += 1;
+                }
+                words.drain(..words.len() - kept);
+                words_len = carried.saturating_sub(1);
+            }
+            words_len += word_len + usize::from(!words.is_empty());
+            words.push(word);
+        }
+        if !words.is_empty() {
+            chunks.push(words.join(" "));
+        }
+    }
+    if len > 0 {
+        chunks.push(current.concat());
+    }
+    chunks
+}
+
+/// Chunks with their embeddings, searchable by similarity. See the
+/// [module docs](self).
+pub struct Corpus {
+    embedder: Box<dyn Embedder>,
+    chunks: Vec<Chunk>,
+    embeddings: Vec<Vec<f32>>,
+    chunk_chars: usize,
+    chunk_overlap: usize,
+}
+
+impl Corpus {
+    pub fn new(embedder: impl Embedder + 'static) -> Self {
+        Corpus {
+            embedder: Box::new(embedder),
+            chunks: Vec::new(),
+            embeddings: Vec::new(),
+            chunk_chars: DEFAULT_CHUNK_CHARS,
+            chunk_overlap: DEFAULT_CHUNK_OVERLAP,
+        }
+    }
+
+    /// How [Corpus::add_document] cuts documents up, see [chunk_text].
+    pub fn chunking(mut self, max_chars: usize, overlap: usize) -> Self {
+        self.chunk_chars = max_chars;
+        self.chunk_overlap = overlap;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.chunks
+    }
+
+    /// Chunks `text` and adds the chunks, labelled with `source`.
+    pub async fn add_document(
+        &mut self,
+        source: impl Into<String>,
+        text: &str,
+    ) -> Result<(), PantryError> {
+        let source = source.into();
+        let chunks = chunk_text(text, self.chunk_chars, self.chunk_overlap)
+            .into_iter()
+            .map(|text| Chunk {
+                source: source.clone(),
+                text,
+            })
+            .collect();
+        self.add_chunks(chunks).await
+    }
+
+    /// Adds chunks as they are, e.g. ones cut up some other way.
+    pub async fn add_chunks(&mut self, chunks: Vec<Chunk>) -> Result<(), PantryError> {
+        if chunks.is_empty() {
+            return Ok(());
+        }
+        let texts: Vec<String> = chunks.iter().map(|chunk| chunk.text.clone()).collect();
+        let embeddings = self.embedder.embed(&texts).await?;
+        if embeddings.len() != chunks.len() {
+            return Err(PantryError::OtherFailure(format!(
+                "embedder returned {} embeddings for {} texts",
+                embeddings.len(),
+                chunks.len()
+            )));
+        }
+        self.chunks.extend(chunks);
+        self.embeddings.extend(embeddings);
+        Ok(())
+    }
+
+    /// The `k` chunks most similar to `query`, most similar first, with their cosine
+    /// similarity.
+    pub async fn search(&self, query: &str, k: usize) -> Result<Vec<(f32, &Chunk)>, PantryError> {
+        let query = self
+            .embedder
+            .embed(&[query.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| PantryError::OtherFailure("embedder returned nothing".into()))?;
+        let mut scored: Vec<(f32, &Chunk)> = self
+            .embeddings
+            .iter()
+            .map(|embedding| cosine_similarity(&query, embedding))
+            .zip(&self.chunks)
+            .collect();
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+        scored.truncate(k);
+        Ok(scored)
+    }
+}
+
+/// Cosine of the angle between `a` and `b`, 0 if either is all zeros.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+/// The prompt [answer_with_context] sends: the chunks, numbered and labelled with their
+/// source, then the question.
+pub fn context_prompt(question: &str, chunks: &[&Chunk]) -> String {
+    let mut prompt = String::from(
+        "Answer the question using only the context below. If the context doesn't \
+         contain the answer, say so.\n\n",
+    );
+    for (i, chunk) in chunks.iter().enumerate() {
+        prompt.push_str(&format!(
+            "[{}] ({})\n{}\n\n",
+            i + 1,
+            chunk.source,
+            chunk.text
+        ));
+    }
+    prompt.push_str(&format!("Question: {}\nAnswer:", question));
+    prompt
+}
+
+/// Answers `question` with the `k` most relevant chunks of `corpus` in the prompt, see
+/// [context_prompt]. Requires [crate::interface::UserPermissions::perm_session].
+pub async fn answer_with_context(
+    session: &LLMSession,
+    question: &str,
+    corpus: &Corpus,
+    k: usize,
+) -> Result<String, PantryError> {
+    let found = corpus.search(question, k).await?;
+    let chunks: Vec<&Chunk> = found.into_iter().map(|(_, chunk)| chunk).collect();
+    let text = session
+        .prompt_and_collect(context_prompt(question, &chunks), InferenceParams::new())
+        .await?;
+    Ok(text.trim().to_string())
+}
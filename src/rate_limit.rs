@@ -0,0 +1,141 @@
+//! Throttling calls to Pantry.
+//!
+//! A [PantryClient](crate::PantryClient) makes its calls as fast as it's asked to. An
+//! app serving many users from one key can easily swamp the machine Pantry runs on;
+//! give its builder a [RateLimit] and calls wait their turn instead:
+//!
+//! ```
+//! # use pantry_rs::{PantryClient, RateLimit, UserId};
+//! # let (user_id, api_key) = (UserId::new_v4(), String::new());
+//! let pantry = PantryClient::builder()
+//!     .rate_limit(RateLimit {
+//!         requests_per_second: Some(5.0),
+//!         tokens_per_minute: Some(2000),
+//!     })
+//!     .login(user_id, api_key);
+//! ```
+//!
+//! Both limits are token buckets, so short bursts up to a second's worth of requests,
+//! or a minute's worth of tokens, go through right away. Tokens are only known once
+//! they've been generated, so they're counted as prompt streams yield them; once the
+//! budget is used up, the next call waits until it's earned back.
+use futures_timer::Delay;
+use std::sync::Mutex;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// Limits for a [RateLimiter]. `None`, the default, leaves that side unlimited.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RateLimit {
+    /// Calls to Pantry per second, including retries and stream reconnects. Must be
+    /// positive to have any effect.
+    pub requests_per_second: Option<f64>,
+    /// Tokens generated per minute, across all prompts. Must be positive to have any
+    /// effect.
+    pub tokens_per_minute: Option<u32>,
+}
+
+/// Shared state enforcing a [RateLimit], see the [module docs](self).
+///
+/// A [crate::PantryAPI] and all its copies share one limiter.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    buckets: Mutex<Buckets>,
+}
+
+#[derive(Debug)]
+struct Buckets {
+    requests: f64,
+    // Goes negative when prompts generate more than was left.
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        let limit = RateLimit {
+            requests_per_second: limit.requests_per_second.filter(|rate| *rate > 0.0),
+            tokens_per_minute: limit.tokens_per_minute.filter(|rate| *rate > 0),
+        };
+        let buckets = Buckets {
+            requests: limit.request_burst(),
+            tokens: limit.token_burst(),
+            refilled: Instant::now(),
+        };
+        RateLimiter {
+            limit,
+            buckets: Mutex::new(buckets),
+        }
+    }
+
+    pub fn limit(&self) -> &RateLimit {
+        &self.limit
+    }
+
+    /// Waits until a call may be made, and counts it.
+    pub async fn acquire(&self) {
+        while let Some(wait) = self.try_acquire() {
+            Delay::new(wait).await;
+        }
+    }
+
+    /// Counts a call if one may be made right now. Otherwise returns how long until
+    /// it may, without counting anything.
+    pub fn try_acquire(&self) -> Option<Duration> {
+        let mut buckets = self.refill();
+        let mut wait = 0.0_f64;
+        if let Some(rate) = self.limit.requests_per_second {
+            if buckets.requests < 1.0 {
+                wait = wait.max((1.0 - buckets.requests) / rate);
+            }
+        }
+        if let Some(rate) = self.limit.tokens_per_minute {
+            if buckets.tokens < 1.0 {
+                wait = wait.max((1.0 - buckets.tokens) / (rate as f64 / 60.0));
+            }
+        }
+        if wait > 0.0 {
+            return Some(Duration::from_secs_f64(wait));
+        }
+        if self.limit.requests_per_second.is_some() {
+            buckets.requests -= 1.0;
+        }
+        None
+    }
+
+    /// Counts `tokens` generated tokens against [RateLimit::tokens_per_minute].
+    pub fn spend_tokens(&self, tokens: usize) {
+        if self.limit.tokens_per_minute.is_some() {
+            self.refill().tokens -= tokens as f64;
+        }
+    }
+
+    fn refill(&self) -> std::sync::MutexGuard<'_, Buckets> {
+        let mut buckets = self.buckets.lock().unwrap();
+        let now = Instant::now();
+        let elapsed = now.duration_since(buckets.refilled).as_secs_f64();
+        buckets.refilled = now;
+        if let Some(rate) = self.limit.requests_per_second {
+            buckets.requests = (buckets.requests + elapsed * rate).min(self.limit.request_burst());
+        }
+        if let Some(rate) = self.limit.tokens_per_minute {
+            buckets.tokens =
+                (buckets.tokens + elapsed * rate as f64 / 60.0).min(self.limit.token_burst());
+        }
+        buckets
+    }
+}
+
+impl RateLimit {
+    fn request_burst(&self) -> f64 {
+        self.requests_per_second.map_or(0.0, |rate| rate.max(1.0))
+    }
+
+    fn token_burst(&self) -> f64 {
+        self.tokens_per_minute.map_or(0.0, |rate| rate as f64)
+    }
+}
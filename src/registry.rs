@@ -0,0 +1,466 @@
+//! Helpers for putting together [LLMRegistryEntry]s.
+use crate::connector_config::{ConnectorConfig, OpenAIConfig};
+use crate::error::PantryError;
+use crate::interface::{AdapterEntry, CapabilityType, LLMConnectorType, LLMRegistryEntry};
+#[cfg(not(target_arch = "wasm32"))]
+use futures::future::{self, Either};
+#[cfg(not(target_arch = "wasm32"))]
+use futures_timer::Delay;
+#[cfg(not(target_arch = "wasm32"))]
+use hyper::{Body, Client, Request};
+#[cfg(not(target_arch = "wasm32"))]
+use hyper_tls::HttpsConnector;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+impl LLMRegistryEntry {
+    /// Starts building an entry with defaults suitable for `connector_type`.
+    ///
+    /// ```
+    /// # use pantry_rs::interface::{LLMConnectorType, LLMRegistryEntry};
+    /// # use pantry_rs::{LlmrsConfig, ModelArchitecture};
+    /// # fn main() -> Result<(), pantry_rs::PantryError> {
+    /// let entry = LLMRegistryEntry::builder("openchat-3", LLMConnectorType::LLMrs)
+    ///     .name("Openchat LLM")
+    ///     .family_id("llama")
+    ///     .url("https://huggingface.co/TheBloke/OpenChat_v3.2-GGML/resolve/main/openchat_v3.2.ggmlv3.q4_0.bin")
+    ///     .connector_config(LlmrsConfig::new(ModelArchitecture::Llama))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder(
+        id: impl Into<String>,
+        connector_type: LLMConnectorType,
+    ) -> LLMRegistryEntryBuilder {
+        LLMRegistryEntryBuilder::new(id, connector_type)
+    }
+
+    /// Starts building an entry for one of OpenAI's models, e.g. `"gpt-4"`.
+    ///
+    /// The API key isn't part of the entry, set it with
+    /// [crate::PantryClient::set_connector_secret] so it isn't echoed back with the
+    /// LLM's config.
+    ///
+    /// ```
+    /// # use pantry_rs::interface::LLMRegistryEntry;
+    /// # fn main() -> Result<(), pantry_rs::PantryError> {
+    /// let entry = LLMRegistryEntry::openai("gpt-4")
+    ///     .name("GPT-4")
+    ///     .context_length(8192)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn openai(model_name: impl Into<String>) -> LLMRegistryEntryBuilder {
+        let model_name = model_name.into();
+        LLMRegistryEntryBuilder::new(model_name.clone(), LLMConnectorType::OpenAI)
+            .organization("OpenAI")
+            .homepage("https://platform.openai.com/docs/models")
+            .connector_config(OpenAIConfig {
+                model: Some(model_name),
+                ..Default::default()
+            })
+    }
+
+    /// Checks the entry for mistakes that would otherwise only show up once Pantry
+    /// tries to download or run it.
+    ///
+    /// In particular this checks the connector specific config keys, e.g.
+    /// `model_architecture` for [LLMConnectorType::LLMrs], see
+    /// [crate::connector_config].
+    pub fn validate(&self) -> Result<(), PantryError> {
+        self.check(true)
+    }
+
+    /// `url` followed by the mirrors, in order of preference.
+    pub fn urls(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.url.as_str())
+            .chain(self.mirrors.iter().map(String::as_str))
+            .filter(|url| !url.trim().is_empty())
+    }
+
+    /// Like [LLMRegistryEntry::validate], but for registering a file that's already on
+    /// disk, where there's nothing to download from.
+    pub fn validate_local(&self) -> Result<(), PantryError> {
+        self.check(false)
+    }
+
+    fn check(&self, needs_url: bool) -> Result<(), PantryError> {
+        if self.id.trim().is_empty() {
+            return Err(invalid("id must not be empty"));
+        }
+        match self.connector_type {
+            LLMConnectorType::LLMrs | LLMConnectorType::Whisper => {
+                if needs_url && self.url.trim().is_empty() {
+                    return Err(invalid(&format!(
+                        "{} entries need a url to download the model from",
+                        self.connector_type
+                    )));
+                }
+            }
+            LLMConnectorType::GenericAPI => {
+                if self.url.trim().is_empty() {
+                    return Err(invalid("GenericAPI entries need the url of the API"));
+                }
+            }
+            LLMConnectorType::OpenAI => {}
+        }
+        self.connector_config()?;
+        if self.mirrors.iter().any(|url| url.trim().is_empty()) {
+            return Err(invalid("mirrors must not be empty"));
+        }
+        if let Some(sha256) = &self.sha256 {
+            if !is_hex(sha256, 64) {
+                return Err(invalid("sha256 must be 64 hex characters"));
+            }
+        }
+        if let Some(signature) = &self.signature {
+            if self.sha256.is_none() {
+                return Err(invalid("a signature needs the sha256 it signs"));
+            }
+            if !is_hex(signature, 128) {
+                return Err(invalid("signature must be 128 hex characters"));
+            }
+        }
+        for (index, adapter) in self.adapters.iter().enumerate() {
+            adapter.validate()?;
+            if self.adapters[..index].iter().any(|a| a.id == adapter.id) {
+                return Err(invalid(&format!("adapter {} is listed twice", adapter.id)));
+            }
+        }
+        for (key, value) in &self.capabilities {
+            if *value < -1 {
+                return Err(invalid(&format!(
+                    "capability {} must be -1 (unevaluated) or higher",
+                    key
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AdapterEntry {
+    /// Checks the adapter has an id and a url to download it from.
+    pub fn validate(&self) -> Result<(), PantryError> {
+        if self.id.trim().is_empty() {
+            return Err(invalid("adapter id must not be empty"));
+        }
+        if self.url.trim().is_empty() {
+            return Err(invalid(&format!("adapter {} needs a url", self.id)));
+        }
+        if let Some(sha256) = &self.sha256 {
+            if !is_hex(sha256, 64) {
+                return Err(invalid("adapter sha256 must be 64 hex characters"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How long [LLMRegistryEntry::check_urls] waits for each url by default.
+pub const DEFAULT_URL_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl LLMRegistryEntry {
+    /// Sends a `HEAD` request to each of [LLMRegistryEntry::urls] in turn, and moves the
+    /// first that responds to `url`, so that's what Pantry downloads from. The rest are
+    /// kept as mirrors. Called by [crate::PantryClient::request_download_llm] and
+    /// [crate::PantryClient::download_llm].
+    ///
+    /// Only [LLMConnectorType::LLMrs] entries are downloaded, so others are returned as
+    /// they are. Urls that aren't `http` or `https`, e.g. `file://`, can't be checked and
+    /// are assumed to work. Fails with [PantryError::UnreachableUrl] if none respond.
+    pub async fn check_urls(mut self, timeout: Duration) -> Result<Self, PantryError> {
+        if !matches!(self.connector_type, LLMConnectorType::LLMrs) {
+            return Ok(self);
+        }
+        let client = Client::builder().build::<_, Body>(HttpsConnector::new());
+        let mut failures = Vec::new();
+        let mut reachable = None;
+        for (index, url) in self.urls().enumerate() {
+            match head(&client, url, timeout).await {
+                Ok(()) => {
+                    reachable = Some(index);
+                    break;
+                }
+                Err(reason) => failures.push(format!("{} ({})", url, reason)),
+            }
+        }
+        let Some(index) = reachable else {
+            return Err(PantryError::UnreachableUrl(failures.join(", ")));
+        };
+        let mut urls: Vec<String> = self.urls().map(String::from).collect();
+        self.url = urls.remove(index);
+        self.mirrors = urls;
+        Ok(self)
+    }
+}
+
+/// Whether `url` answers a `HEAD` request, and why not if it doesn't.
+#[cfg(not(target_arch = "wasm32"))]
+async fn head(
+    client: &Client<HttpsConnector<hyper::client::HttpConnector>>,
+    url: &str,
+    timeout: Duration,
+) -> Result<(), String> {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Ok(());
+    }
+    let req = Request::head(url)
+        .header("user-agent", "pantry-rs")
+        .body(Body::empty())
+        .map_err(|e| e.to_string())?;
+    let resp = match future::select(client.request(req), Delay::new(timeout)).await {
+        Either::Left((resp, _)) => resp.map_err(|e| e.to_string())?,
+        Either::Right(_) => return Err(format!("no response within {:?}", timeout)),
+    };
+    // Hugging Face and most CDNs redirect to where the file actually is.
+    match resp.status() {
+        status if status.is_success() || status.is_redirection() => Ok(()),
+        status => Err(status.to_string()),
+    }
+}
+
+fn invalid(msg: &str) -> PantryError {
+    PantryError::InvalidRegistryEntry(msg.into())
+}
+
+fn is_hex(s: &str, len: usize) -> bool {
+    s.len() == len && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Builder for [LLMRegistryEntry], see [LLMRegistryEntry::builder].
+///
+/// Everything except the id and connector type is optional. Text fields default to
+/// empty strings, capabilities to -1 (not evaluated).
+#[derive(Debug, Clone)]
+pub struct LLMRegistryEntryBuilder {
+    entry: LLMRegistryEntry,
+}
+
+impl LLMRegistryEntryBuilder {
+    pub fn new(id: impl Into<String>, connector_type: LLMConnectorType) -> Self {
+        let id = id.into();
+        let capabilities = CapabilityType::KNOWN.into_iter().map(|c| (c, -1)).collect();
+
+        // What the connectors understand out of the box.
+        let (local, user_parameters, user_session_parameters) = match connector_type {
+            LLMConnectorType::LLMrs => (
+                true,
+                vec![
+                    "sampler_string".into(),
+                    "pre_prompt".into(),
+                    "post_prompt".into(),
+                ],
+                vec!["system_prompt".into()],
+            ),
+            LLMConnectorType::Whisper => (
+                true,
+                vec!["language".into(), "translate".into()],
+                Vec::new(),
+            ),
+            LLMConnectorType::GenericAPI | LLMConnectorType::OpenAI => {
+                (false, Vec::new(), Vec::new())
+            }
+        };
+
+        LLMRegistryEntryBuilder {
+            entry: LLMRegistryEntry {
+                id: id.clone(),
+                family_id: String::new(),
+                organization: String::new(),
+                name: id,
+                license: String::new(),
+                description: String::new(),
+                homepage: String::new(),
+                capabilities,
+                tags: Vec::new(),
+                requirements: String::new(),
+                backend_uuid: String::new(),
+                url: String::new(),
+                mirrors: Vec::new(),
+                sha256: None,
+                size_bytes: None,
+                signature: None,
+                version: None,
+                supersedes: Vec::new(),
+                adapters: Vec::new(),
+                config: HashMap::new(),
+                local,
+                connector_type,
+                parameters: HashMap::new(),
+                user_parameters,
+                session_parameters: HashMap::new(),
+                user_session_parameters,
+            },
+        }
+    }
+
+    /// Human readable name. Defaults to the id.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.entry.name = name.into();
+        self
+    }
+
+    /// Model family, e.g. "llama".
+    pub fn family_id(mut self, family_id: impl Into<String>) -> Self {
+        self.entry.family_id = family_id.into();
+        self
+    }
+
+    pub fn organization(mut self, organization: impl Into<String>) -> Self {
+        self.entry.organization = organization.into();
+        self
+    }
+
+    pub fn license(mut self, license: impl Into<String>) -> Self {
+        self.entry.license = license.into();
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.entry.description = description.into();
+        self
+    }
+
+    pub fn homepage(mut self, homepage: impl Into<String>) -> Self {
+        self.entry.homepage = homepage.into();
+        self
+    }
+
+    /// Rates a capability, with 10 being GPT-4 quality.
+    pub fn capability(mut self, capability: CapabilityType, value: i32) -> Self {
+        self.entry.capabilities.insert(capability, value);
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.entry.tags.push(tag.into());
+        self
+    }
+
+    /// Free form hardware requirements, shown to the user.
+    pub fn requirements(mut self, requirements: impl Into<String>) -> Self {
+        self.entry.requirements = requirements.into();
+        self
+    }
+
+    /// Where to download the model from, or the API endpoint for remote connectors.
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.entry.url = url.into();
+        self
+    }
+
+    /// Somewhere else to download the model from, if `url` doesn't respond. Tried in
+    /// the order they're added.
+    pub fn mirror(mut self, url: impl Into<String>) -> Self {
+        self.entry.mirrors.push(url.into());
+        self
+    }
+
+    /// Expected SHA-256 of the model file, hex encoded.
+    pub fn sha256(mut self, sha256: impl Into<String>) -> Self {
+        self.entry.sha256 = Some(sha256.into());
+        self
+    }
+
+    /// Expected size of the model file.
+    pub fn size_bytes(mut self, size_bytes: u64) -> Self {
+        self.entry.size_bytes = Some(size_bytes);
+        self
+    }
+
+    /// Hex encoded ed25519 signature of the sha256 digest.
+    pub fn signature(mut self, signature: impl Into<String>) -> Self {
+        self.entry.signature = Some(signature.into());
+        self
+    }
+
+    /// Release of the model, see [LLMRegistryEntry::version].
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.entry.version = Some(version.into());
+        self
+    }
+
+    /// Marks this as replacing the model with id `id`. Can be repeated.
+    pub fn supersedes(mut self, id: impl Into<String>) -> Self {
+        self.entry.supersedes.push(id.into());
+        self
+    }
+
+    /// Offers a LoRA adapter for the model. Can be repeated.
+    pub fn adapter(mut self, adapter: AdapterEntry) -> Self {
+        self.entry.adapters.push(adapter);
+        self
+    }
+
+    pub fn local(mut self, local: bool) -> Self {
+        self.entry.local = local;
+        self
+    }
+
+    /// Sets a connector config key, see [crate::interface::LLMStatus::config].
+    pub fn config(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.entry.config.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets every config key `config` has, and the connector it's for. Keys it doesn't
+    /// have are left alone.
+    pub fn connector_config(mut self, config: impl Into<ConnectorConfig>) -> Self {
+        let config = config.into();
+        self.entry.connector_type = config.connector_type();
+        self.entry.config.extend(config.to_map());
+        self
+    }
+
+    /// Context window in tokens, stored as `config.context_length`.
+    pub fn context_length(self, tokens: u64) -> Self {
+        self.config("context_length", tokens)
+    }
+
+    /// Memory needed to run the model, stored as `config.ram_bytes`.
+    pub fn ram_bytes(self, bytes: u64) -> Self {
+        self.config("ram_bytes", bytes)
+    }
+
+    /// Sets a hardcoded inference parameter.
+    pub fn parameter(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.entry.parameters.insert(key.into(), value.into());
+        self
+    }
+
+    /// Allows users to set an inference parameter.
+    pub fn user_parameter(mut self, name: impl Into<String>) -> Self {
+        self.entry.user_parameters.push(name.into());
+        self
+    }
+
+    /// Sets a hardcoded session parameter.
+    pub fn session_parameter(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.entry
+            .session_parameters
+            .insert(key.into(), value.into());
+        self
+    }
+
+    /// Allows users to set a session parameter.
+    pub fn user_session_parameter(mut self, name: impl Into<String>) -> Self {
+        self.entry.user_session_parameters.push(name.into());
+        self
+    }
+
+    /// Validates and returns the entry, see [LLMRegistryEntry::validate].
+    pub fn build(self) -> Result<LLMRegistryEntry, PantryError> {
+        self.entry.validate()?;
+        Ok(self.entry)
+    }
+
+    /// Returns the entry without validating it.
+    pub fn build_unchecked(self) -> LLMRegistryEntry {
+        self.entry
+    }
+}
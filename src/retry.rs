@@ -0,0 +1,88 @@
+//! Retrying of idempotent API calls.
+use crate::error::PantryError;
+use futures::Future;
+use futures_timer::Delay;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// How [crate::PantryAPI] retries idempotent calls (status lookups, LLM listings)
+/// that fail with a [PantryError::is_retryable] error.
+///
+/// Calls that change state on the server, like loading an LLM or creating a
+/// session, are never retried.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Total number of tries, including the first. `1` disables retrying.
+    pub max_attempts: u32,
+    /// Wait before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound for the wait between retries.
+    pub max_backoff: Duration,
+    /// Factor the wait grows by after each retry.
+    pub multiplier: f64,
+    /// Randomize each wait between half and all of its nominal length, so that many
+    /// clients don't hammer a restarting daemon in lockstep.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Nominal wait before retry number `retry` (starting at 0), without jitter.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = self
+            .multiplier
+            .max(1.0)
+            .powi(retry.min(i32::MAX as u32) as i32);
+        let backoff = self.initial_backoff.as_secs_f64() * factor;
+        Duration::from_secs_f64(backoff.min(self.max_backoff.as_secs_f64()))
+    }
+
+    pub(crate) fn delay(&self, retry: u32) -> Duration {
+        let backoff = self.backoff(retry);
+        if !self.jitter {
+            return backoff;
+        }
+        // RandomState is seeded randomly per instance, which is plenty for jitter.
+        let random = RandomState::new().build_hasher().finish();
+        let fraction = 0.5 + (random as f64 / u64::MAX as f64) / 2.0;
+        backoff.mul_f64(fraction)
+    }
+
+    /// Runs `op`, retrying retryable failures according to this policy.
+    pub async fn run<T, F, Fut>(&self, mut op: F) -> Result<T, PantryError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, PantryError>>,
+    {
+        let mut retry = 0;
+        loop {
+            match op().await {
+                Err(err) if retry + 1 < self.max_attempts && err.is_retryable() => {
+                    Delay::new(self.delay(retry)).await;
+                    retry += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Decoding server-sent events, the wire format of Pantry's streaming endpoints.
+//!
+//! [SseParser] is incremental: feed it the body in whatever chunks the network hands
+//! over and it returns events as they complete. Events split across chunks anywhere,
+//! including in the middle of a line ending or a multi-byte character, come out the
+//! same as if they'd arrived in one piece. It follows the
+//! [HTML spec](https://html.spec.whatwg.org/multipage/server-sent-events.html#event-stream-interpretation):
+//! `\n`, `\r\n` and `\r` line endings, multi-line `data:`, `:` comments, `id:` and
+//! `retry:`.
+//!
+//! ```
+//! # use pantry_rs::sse::SseParser;
+//! let mut parser = SseParser::new();
+//! assert!(parser.feed(b"data: {\"a\":").is_empty());
+//! let events = parser.feed(b" 1}\n\n");
+//! assert_eq!(events[0].data, "{\"a\": 1}");
+//! ```
+use crate::error::PantryError;
+use futures::stream::{self, Stream, StreamExt};
+use hyper::body::Bytes;
+use std::time::Duration;
+
+/// One event from the stream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SseEvent {
+    /// From `event:`, `"message"` if not given.
+    pub event: String,
+    /// `data:` lines, joined with `\n`.
+    pub data: String,
+    /// The last `id:` seen up to this event, which is what a reconnect resumes from.
+    pub id: Option<String>,
+}
+
+/// Incremental server-sent events parser, see the [module docs](self).
+#[derive(Clone, Debug, Default)]
+pub struct SseParser {
+    // Bytes of the line being read, decoded once it's complete so multi-byte
+    // characters can be split across chunks.
+    line: Vec<u8>,
+    // A chunk ended in `\r`, so a `\n` starting the next one belongs to it.
+    after_cr: bool,
+    // Past the optional byte order mark.
+    started: bool,
+    data: String,
+    has_data: bool,
+    event: Option<String>,
+    last_event_id: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the next chunk of the body, returning the events it completes.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut chunk = chunk;
+        if !self.started {
+            // The BOM could itself be split, so wait until there's enough to tell.
+            let bom = b"\xEF\xBB\xBF";
+            let seen = self.line.len() + chunk.len();
+            if seen < bom.len() && [&self.line[..], chunk].concat() == bom[..seen] {
+                self.line.extend_from_slice(chunk);
+                return Vec::new();
+            }
+            let mut start = std::mem::take(&mut self.line);
+            start.extend_from_slice(chunk);
+            self.started = true;
+            let start = start.strip_prefix(bom).map(<[u8]>::to_vec).unwrap_or(start);
+            return self.feed(&start);
+        }
+
+        let mut events = Vec::new();
+        if self.after_cr {
+            self.after_cr = false;
+            chunk = chunk.strip_prefix(b"\n").unwrap_or(chunk);
+        }
+        while let Some(end) = chunk.iter().position(|b| *b == b'\n' || *b == b'\r') {
+            self.line.extend_from_slice(&chunk[..end]);
+            let line = std::mem::take(&mut self.line);
+            events.extend(self.line_done(&String::from_utf8_lossy(&line)));
+
+            let rest = &chunk[end + 1..];
+            chunk = match (chunk[end], rest.first()) {
+                (b'\r', Some(b'\n')) => &rest[1..],
+                (b'\r', None) => {
+                    self.after_cr = true;
+                    rest
+                }
+                _ => rest,
+            };
+        }
+        self.line.extend_from_slice(chunk);
+        events
+    }
+
+    /// Ends the stream, returning the last event if the body stopped without the
+    /// blank line that normally completes it.
+    ///
+    /// The spec has such events dropped, but a server closing the connection right
+    /// after its final event is common enough that losing it would be worse.
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        self.after_cr = false;
+        let mut event = None;
+        if !self.line.is_empty() {
+            let line = std::mem::take(&mut self.line);
+            event = self.line_done(&String::from_utf8_lossy(&line));
+        }
+        event.or_else(|| self.dispatch())
+    }
+
+    /// The last `id:` seen, for resuming the stream.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    /// Reconnection delay the server asked for with `retry:`, if it did.
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    fn line_done(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "data" => {
+                if self.has_data {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value);
+                self.has_data = true;
+            }
+            "event" => self.event = Some(value.to_string()),
+            "id" if !value.contains('\0') => self.last_event_id = Some(value.to_string()),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                if let Ok(ms) = value.parse() {
+                    self.retry = Some(Duration::from_millis(ms));
+                }
+            }
+            // Unknown fields are ignored.
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        if !self.has_data {
+            return None;
+        }
+        self.has_data = false;
+        Some(SseEvent {
+            event: event.unwrap_or_else(|| "message".into()),
+            data: std::mem::take(&mut self.data),
+            id: self.last_event_id.clone(),
+        })
+    }
+}
+
+/// Decodes a body, as a stream of chunks, into its events.
+pub fn decode<S, E>(body: S) -> impl Stream<Item = Result<SseEvent, PantryError>> + Send
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + Unpin + 'static,
+    E: std::fmt::Display,
+{
+    stream::unfold(
+        (body, SseParser::new(), Vec::new().into_iter(), false),
+        |(mut body, mut parser, mut pending, mut done)| async move {
+            loop {
+                if let Some(event) = pending.next() {
+                    return Some((Ok(event), (body, parser, pending, done)));
+                }
+                if done {
+                    return None;
+                }
+                match body.next().await {
+                    Some(Ok(chunk)) => pending = parser.feed(&chunk).into_iter(),
+                    Some(Err(e)) => {
+                        let err = PantryError::StreamError(e.to_string());
+                        return Some((Err(err), (body, parser, pending, true)));
+                    }
+                    None => {
+                        done = true;
+                        pending = parser.finish().into_iter().collect::<Vec<_>>().into_iter();
+                    }
+                }
+            }
+        },
+    )
+}
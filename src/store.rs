@@ -0,0 +1,347 @@
+//! Saving conversations to a local sqlite database.
+//!
+//! A [ConversationStore] keeps [ChatSession]s between runs: their messages, the Pantry
+//! session they're in and whatever metadata the app wants to attach. Saved
+//! conversations can be listed, resumed and exported:
+//!
+//! ```no_run
+//! # use pantry_rs::store::ConversationStore;
+//! # use pantry_rs::{ChatSession, ConversationId, PantryClient};
+//! # use std::collections::HashMap;
+//! # async fn example(pantry: PantryClient) -> Result<(), Box<dyn std::error::Error>> {
+//! let store = ConversationStore::open("conversations.db")?;
+//!
+//! let id = ConversationId::new_v4();
+//! let mut chat = ChatSession::new(pantry.create_session(HashMap::new()).await?);
+//! chat.send_and_collect("Hi!").await?;
+//! store.save(id, &chat)?;
+//! store.set_title(id, "Greetings")?;
+//!
+//! // After a restart:
+//! for conversation in store.list()? {
+//!     println!("{} {:?}", conversation.id, conversation.title);
+//! }
+//! let mut chat = store.resume(&pantry, id)?;
+//! chat.send_and_collect("Where were we?").await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Calls block on the database, which for a local file is quick enough to do from async
+//! code. Credentials aren't stored, see [crate::credentials] for those.
+use crate::chat::{ChatMessage, ChatSession, ChatTranscript, Role};
+use crate::error::PantryError;
+use crate::ids::ConversationId;
+use crate::{PantryClient, SessionHandle};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+const SCHEMA: &str = "
+    PRAGMA foreign_keys = ON;
+    CREATE TABLE IF NOT EXISTS conversations (
+        id TEXT PRIMARY KEY,
+        title TEXT,
+        metadata TEXT NOT NULL,
+        handle TEXT NOT NULL,
+        fed TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS messages (
+        conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        role TEXT NOT NULL,
+        content TEXT NOT NULL,
+        PRIMARY KEY (conversation_id, position)
+    );
+";
+
+/// A saved conversation, as listed by [ConversationStore::list].
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ConversationSummary {
+    pub id: ConversationId,
+    pub title: Option<String>,
+    pub message_count: usize,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Everything saved about a conversation, see [ConversationStore::load].
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct StoredConversation {
+    pub id: ConversationId,
+    pub title: Option<String>,
+    pub metadata: HashMap<String, Value>,
+    /// The Pantry session the conversation was last in.
+    pub handle: SessionHandle,
+    pub transcript: ChatTranscript,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Conversations in a sqlite database, see the [module docs](self).
+pub struct ConversationStore {
+    conn: Mutex<Connection>,
+}
+
+impl ConversationStore {
+    /// Opens the database at `path`, creating it if needed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PantryError> {
+        Self::with_connection(Connection::open(path).map_err(store_error)?)
+    }
+
+    /// A database that lives only as long as the store, e.g. for tests.
+    pub fn in_memory() -> Result<Self, PantryError> {
+        Self::with_connection(Connection::open_in_memory().map_err(store_error)?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, PantryError> {
+        conn.execute_batch(SCHEMA).map_err(store_error)?;
+        Ok(ConversationStore {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Saves `chat` as conversation `id`, replacing what was saved under it before.
+    /// Title and metadata are kept.
+    pub fn save(&self, id: ConversationId, chat: &ChatSession) -> Result<(), PantryError> {
+        let handle = serde_json::to_string(&chat.session().handle())?;
+        let transcript = chat.transcript();
+        let now = Utc::now().to_rfc3339();
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(store_error)?;
+        tx.execute(
+            "INSERT INTO conversations (id, metadata, handle, fed, created_at, updated_at)
+             VALUES (?1, '{}', ?2, ?3, ?4, ?4)
+             ON CONFLICT (id) DO UPDATE SET handle = ?2, fed = ?3, updated_at = ?4",
+            params![id.to_string(), handle, transcript.fed, now],
+        )
+        .map_err(store_error)?;
+        tx.execute(
+            "DELETE FROM messages WHERE conversation_id = ?1",
+            params![id.to_string()],
+        )
+        .map_err(store_error)?;
+        for (position, message) in transcript.messages.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO messages (conversation_id, position, role, content)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    id.to_string(),
+                    position as i64,
+                    role_name(message.role),
+                    message.content
+                ],
+            )
+            .map_err(store_error)?;
+        }
+        tx.commit().map_err(store_error)
+    }
+
+    pub fn set_title(&self, id: ConversationId, title: &str) -> Result<(), PantryError> {
+        self.update(id, "title", title)
+    }
+
+    /// Replaces the metadata saved with conversation `id`, e.g. tags or a project.
+    pub fn set_metadata(
+        &self,
+        id: ConversationId,
+        metadata: &HashMap<String, Value>,
+    ) -> Result<(), PantryError> {
+        self.update(id, "metadata", &serde_json::to_string(metadata)?)
+    }
+
+    fn update(&self, id: ConversationId, column: &str, value: &str) -> Result<(), PantryError> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn
+            .execute(
+                &format!(
+                    "UPDATE conversations SET {} = ?2, updated_at = ?3 WHERE id = ?1",
+                    column
+                ),
+                params![id.to_string(), value, Utc::now().to_rfc3339()],
+            )
+            .map_err(store_error)?;
+        if updated == 0 {
+            return Err(not_found(id));
+        }
+        Ok(())
+    }
+
+    /// Every saved conversation, most recently updated first.
+    pub fn list(&self) -> Result<Vec<ConversationSummary>, PantryError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, title, created_at, updated_at,
+                     (SELECT COUNT(*) FROM messages WHERE conversation_id = conversations.id)
+                 FROM conversations ORDER BY updated_at DESC",
+            )
+            .map_err(store_error)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            })
+            .map_err(store_error)?;
+        let mut conversations = Vec::new();
+        for row in rows {
+            let (id, title, created_at, updated_at, message_count) = row.map_err(store_error)?;
+            conversations.push(ConversationSummary {
+                id: parse_id(&id)?,
+                title,
+                message_count: message_count as usize,
+                created_at: parse_time(&created_at)?,
+                updated_at: parse_time(&updated_at)?,
+            });
+        }
+        Ok(conversations)
+    }
+
+    /// Conversation `id`, or `None` if nothing is saved under it.
+    pub fn load(&self, id: ConversationId) -> Result<Option<StoredConversation>, PantryError> {
+        let conn = self.conn.lock().unwrap();
+        let row = conn
+            .query_row(
+                "SELECT title, metadata, handle, fed, created_at, updated_at
+                 FROM conversations WHERE id = ?1",
+                params![id.to_string()],
+                |row| {
+                    Ok((
+                        row.get::<_, Option<String>>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, String>(4)?,
+                        row.get::<_, String>(5)?,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(store_error)?;
+        let Some((title, metadata, handle, fed, created_at, updated_at)) = row else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT role, content FROM messages WHERE conversation_id = ?1
+                 ORDER BY position",
+            )
+            .map_err(store_error)?;
+        let rows = stmt
+            .query_map(params![id.to_string()], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(store_error)?;
+        let mut messages = Vec::new();
+        for row in rows {
+            let (role, content) = row.map_err(store_error)?;
+            messages.push(ChatMessage {
+                role: parse_role(&role)?,
+                content,
+            });
+        }
+
+        Ok(Some(StoredConversation {
+            id,
+            title,
+            metadata: serde_json::from_str(&metadata)?,
+            handle: serde_json::from_str(&handle)?,
+            transcript: ChatTranscript { messages, fed },
+            created_at: parse_time(&created_at)?,
+            updated_at: parse_time(&updated_at)?,
+        }))
+    }
+
+    /// Picks conversation `id` up where it was saved, in the same Pantry session, see
+    /// [PantryClient::resume_session]. The chat uses the LLM's default template.
+    pub fn resume(
+        &self,
+        pantry: &PantryClient,
+        id: ConversationId,
+    ) -> Result<ChatSession, PantryError> {
+        let stored = self.load(id)?.ok_or_else(|| not_found(id))?;
+        let session = pantry.resume_session(stored.handle);
+        Ok(ChatSession::new(session).with_transcript(stored.transcript))
+    }
+
+    /// Conversation `id` as pretty-printed JSON, a [StoredConversation].
+    pub fn export_json(&self, id: ConversationId) -> Result<String, PantryError> {
+        let stored = self.load(id)?.ok_or_else(|| not_found(id))?;
+        Ok(serde_json::to_string_pretty(&stored)?)
+    }
+
+    /// Conversation `id` as a Markdown transcript, one section per message.
+    pub fn export_markdown(&self, id: ConversationId) -> Result<String, PantryError> {
+        let stored = self.load(id)?.ok_or_else(|| not_found(id))?;
+        let mut out = format!("# {}\n", stored.title.as_deref().unwrap_or("Conversation"));
+        for message in &stored.transcript.messages {
+            let heading = match message.role {
+                Role::System => "System",
+                Role::User => "User",
+                Role::Assistant => "Assistant",
+            };
+            out.push_str(&format!("\n## {}\n\n{}\n", heading, message.content));
+        }
+        Ok(out)
+    }
+
+    /// Deletes conversation `id`, returning whether there was one.
+    pub fn delete(&self, id: ConversationId) -> Result<bool, PantryError> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn
+            .execute(
+                "DELETE FROM conversations WHERE id = ?1",
+                params![id.to_string()],
+            )
+            .map_err(store_error)?;
+        Ok(deleted > 0)
+    }
+}
+
+fn store_error(err: rusqlite::Error) -> PantryError {
+    PantryError::StoreError(err.to_string())
+}
+
+fn not_found(id: ConversationId) -> PantryError {
+    PantryError::StoreError(format!("no conversation {}", id))
+}
+
+fn role_name(role: Role) -> &'static str {
+    match role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+    }
+}
+
+fn parse_role(role: &str) -> Result<Role, PantryError> {
+    match role {
+        "system" => Ok(Role::System),
+        "user" => Ok(Role::User),
+        "assistant" => Ok(Role::Assistant),
+        _ => Err(PantryError::StoreError(format!("unknown role {:?}", role))),
+    }
+}
+
+fn parse_id(id: &str) -> Result<ConversationId, PantryError> {
+    id.parse()
+        .map_err(|_| PantryError::StoreError(format!("invalid conversation id {:?}", id)))
+}
+
+fn parse_time(time: &str) -> Result<DateTime<Utc>, PantryError> {
+    DateTime::parse_from_rfc3339(time)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| PantryError::StoreError(format!("invalid timestamp {:?}: {}", time, e)))
+}
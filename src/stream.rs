@@ -0,0 +1,779 @@
+//! Streams returned by prompting a session.
+use crate::backend::PantryBackend;
+use crate::error::PantryError;
+use crate::event_bus::{self, ClientEvent};
+use crate::ids::{LlmUuid, SessionId, UserId};
+use crate::interface::{
+    Completion, FinishReason, LLMEvent, LLMEventInternal, LLMRunningStatus, ServerEvent,
+};
+use crate::lifecycle::{InFlight, Ticket};
+use crate::metrics::PromptMetric;
+use crate::middleware::PromptMiddleware;
+use crate::retry::RetryPolicy;
+use futures::future::BoxFuture;
+use futures::stream::{self, Stream, StreamExt};
+use futures::Future;
+use futures_timer::Delay;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+use tokio::sync::{broadcast, OwnedSemaphorePermit};
+use uuid::Uuid;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// Events as they arrive from Pantry, before [LLMEventStream] keeps track of them.
+pub type RawEventStream = Pin<Box<dyn Stream<Item = Result<LLMEvent, PantryError>> + Send>>;
+
+/// Stream of [ServerEvent]s, as returned by [crate::PantryAPI::subscribe_events].
+pub type ServerEventStream = Pin<Box<dyn Stream<Item = Result<ServerEvent, PantryError>> + Send>>;
+
+/// One consumer's copy of a stream split with [LLMEventStream::broadcast].
+pub type BroadcastEventStream = Pin<Box<dyn Stream<Item = Result<LLMEvent, PantryError>> + Send>>;
+
+/// Events buffered for each consumer of [LLMEventStream::tee].
+pub const DEFAULT_BROADCAST_CAPACITY: usize = 256;
+
+/// Stream of inference events, as returned by [crate::PantryAPI::prompt_session_stream] and
+/// [crate::LLMSession::prompt_session].
+///
+/// The stream ends once inference is done. If the connection breaks or an event can't
+/// be decoded it yields an `Err` instead, so that a broken stream can be told apart
+/// from a finished one.
+///
+/// Besides the events themselves, it keeps track of which session it belongs to and
+/// how far along inference is, and can interrupt the inference it's streaming.
+///
+/// If `max_tokens` was set and Pantry keeps generating past it, the stream interrupts
+/// inference itself and ends with a [LLMEventInternal::PromptCompletion] whose finish
+/// reason is [FinishReason::Length]. Likewise for stop sequences, with
+/// [FinishReason::Stop]; the stop sequence itself is left out of the completion, though
+/// the start of it may already have gone out in progress events.
+///
+/// With a stall timeout set, see [LLMEventStream::set_stall_timeout], going that long
+/// without an event fails the stream with [PantryError::StreamStalled] rather than
+/// waiting on a wedged LLM forever.
+pub struct LLMEventStream {
+    inner: RawEventStream,
+    client: Arc<dyn PantryBackend>,
+    user_id: UserId,
+    api_key: String,
+    session_id: SessionId,
+    llm_uuid: LlmUuid,
+    stream_id: Option<Uuid>,
+    started: Instant,
+    first_token: Option<Duration>,
+    tokens: usize,
+    text: String,
+    last_event: Option<LLMEvent>,
+    max_tokens: Option<u32>,
+    stop_sequences: Vec<String>,
+    stopped: bool,
+    finish_reason: Option<FinishReason>,
+    interrupted: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+    stall_timeout: Option<Duration>,
+    interrupt_on_stall: bool,
+    // Running while waiting on the next event.
+    watchdog: Option<Delay>,
+    cancelled: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+    // A slot of a [crate::queue::PromptQueue], given back once inference is done.
+    permit: Option<OwnedSemaphorePermit>,
+    middleware: Vec<Arc<dyn PromptMiddleware>>,
+    replay: Option<Replay>,
+    // A fresh session being prompted again, see [LLMEventStream::set_replay].
+    replaying: Option<BoxFuture<'static, Result<LLMEventStream, PantryError>>>,
+    // Held while inference is going, see [crate::PantryClient::shutdown].
+    ticket: Option<Ticket>,
+}
+
+/// What it takes to send a prompt again in a fresh session.
+struct Replay {
+    prompt: String,
+    parameters: HashMap<String, Value>,
+    session_parameters: HashMap<String, Value>,
+    policy: RetryPolicy,
+    replays: u32,
+}
+
+impl LLMEventStream {
+    /// Wraps the events of a prompt. For [PantryBackend] implementations; `client` is
+    /// what interrupts go to, and `started` is when the prompt was sent.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        inner: RawEventStream,
+        client: Arc<dyn PantryBackend>,
+        user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+        llm_uuid: LlmUuid,
+        started: Instant,
+    ) -> Self {
+        let ticket = client.lifecycle().map(|lifecycle| {
+            lifecycle.track(InFlight {
+                user_id,
+                api_key: api_key.clone(),
+                session_id,
+                llm_uuid,
+            })
+        });
+        LLMEventStream {
+            inner,
+            client,
+            user_id,
+            api_key,
+            session_id,
+            llm_uuid,
+            stream_id: None,
+            started,
+            first_token: None,
+            tokens: 0,
+            text: String::new(),
+            last_event: None,
+            max_tokens: None,
+            stop_sequences: Vec::new(),
+            stopped: false,
+            finish_reason: None,
+            interrupted: Arc::new(AtomicBool::new(false)),
+            finished: Arc::new(AtomicBool::new(false)),
+            stall_timeout: None,
+            interrupt_on_stall: true,
+            watchdog: None,
+            cancelled: None,
+            permit: None,
+            middleware: Vec::new(),
+            replay: None,
+            replaying: None,
+            ticket,
+        }
+    }
+
+    /// Caps the completion at `max_tokens`, in case Pantry doesn't.
+    pub(crate) fn set_max_tokens(&mut self, max_tokens: Option<u32>) {
+        self.max_tokens = max_tokens;
+    }
+
+    /// Ends the completion at the first of `stop_sequences`, in case Pantry doesn't.
+    pub(crate) fn set_stop_sequences(&mut self, stop_sequences: Vec<String>) {
+        self.stop_sequences = stop_sequences;
+        self.stop_sequences.retain(|s| !s.is_empty());
+    }
+
+    /// Fails the stream with [PantryError::StreamStalled] once `timeout` passes without
+    /// an event, including before the first one. `None`, the default, waits forever.
+    pub fn set_stall_timeout(&mut self, timeout: Option<Duration>) {
+        self.stall_timeout = timeout;
+        self.watchdog = None;
+    }
+
+    pub fn stall_timeout(&self) -> Option<Duration> {
+        self.stall_timeout
+    }
+
+    /// Whether a stalled stream interrupts its session, so the LLM is free for the next
+    /// prompt if it ever recovers. Defaults to `true`.
+    pub fn set_interrupt_on_stall(&mut self, interrupt_on_stall: bool) {
+        self.interrupt_on_stall = interrupt_on_stall;
+    }
+
+    pub fn interrupt_on_stall(&self) -> bool {
+        self.interrupt_on_stall
+    }
+
+    /// Ends the stream and interrupts inference once `token` is cancelled, see
+    /// [crate::LLMSession::prompt_session_cancellable].
+    #[cfg(feature = "cancellation")]
+    pub fn cancel_on(&mut self, token: tokio_util::sync::CancellationToken) {
+        self.cancelled = Some(Box::pin(token.cancelled_owned()));
+    }
+
+    /// Runs every event through `middleware`, see [crate::middleware].
+    pub(crate) fn set_middleware(&mut self, middleware: Vec<Arc<dyn PromptMiddleware>>) {
+        self.middleware = middleware;
+    }
+
+    /// Prompts again in a fresh session with `session_parameters`, as allowed by
+    /// `policy`, if the LLM fails before generating anything, see
+    /// [crate::PantryClientBuilder::prompt_retry].
+    pub(crate) fn set_replay(
+        &mut self,
+        prompt: String,
+        parameters: HashMap<String, Value>,
+        session_parameters: HashMap<String, Value>,
+        policy: RetryPolicy,
+    ) {
+        self.replay = Some(Replay {
+            prompt,
+            parameters,
+            session_parameters,
+            policy,
+            replays: 0,
+        });
+    }
+
+    /// How many times the prompt was sent again after the LLM failed, see
+    /// [crate::PantryClientBuilder::prompt_retry].
+    pub fn replays(&self) -> u32 {
+        self.replay.as_ref().map_or(0, |replay| replay.replays)
+    }
+
+    /// Holds on to `permit` until inference is done or the stream is dropped.
+    pub(crate) fn hold(&mut self, permit: OwnedSemaphorePermit) {
+        self.permit = Some(permit);
+    }
+
+    /// Id Pantry assigned to this inference. `None` until the first event arrives.
+    pub fn stream_id(&self) -> Option<Uuid> {
+        self.stream_id
+    }
+
+    /// The session being prompted.
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+
+    /// UUID of the LLM doing the inference.
+    pub fn llm_uuid(&self) -> LlmUuid {
+        self.llm_uuid
+    }
+
+    /// Time since the prompt was sent.
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Number of [LLMEventInternal::PromptProgress] events received so far.
+    pub fn token_count(&self) -> usize {
+        self.tokens
+    }
+
+    /// Why inference stopped. `None` while it's still going.
+    pub fn finish_reason(&self) -> Option<FinishReason> {
+        self.finish_reason
+    }
+
+    /// Drains the stream, returning the completion text.
+    ///
+    /// Fails with [PantryError::PromptError] if the LLM reports an error, or with
+    /// whatever error broke the stream.
+    pub async fn collect_text(self) -> Result<String, PantryError> {
+        Ok(self.collect_completion().await?.text)
+    }
+
+    /// Drains the stream, returning the completion text along with why it stopped and
+    /// how many tokens it took.
+    ///
+    /// Fails with [PantryError::PromptError] if the LLM reports an error, or with
+    /// whatever error broke the stream.
+    pub async fn collect_completion(mut self) -> Result<Completion, PantryError> {
+        let mut text = String::new();
+        let mut prompt_tokens = None;
+        while let Some(event) = self.next().await {
+            match event?.event {
+                LLMEventInternal::PromptProgress { next, .. } => text.push_str(&next),
+                LLMEventInternal::PromptError { message } => {
+                    return Err(PantryError::PromptError(message))
+                }
+                LLMEventInternal::PromptCompletion {
+                    prompt_tokens: tokens,
+                    ..
+                } => {
+                    prompt_tokens = tokens;
+                    break;
+                }
+                LLMEventInternal::Other => {}
+            }
+        }
+        Ok(Completion {
+            text,
+            finish_reason: self.finish_reason.unwrap_or(FinishReason::Interrupted),
+            prompt_tokens,
+            completion_tokens: self.tokens as u32,
+        })
+    }
+
+    /// Splits the stream into `consumers` copies that each get every event, e.g. one
+    /// rendering tokens while another logs the transcript.
+    ///
+    /// The stream is read on a background task, so this must be called inside a tokio
+    /// runtime (or in the browser). Up to `capacity` events are buffered for each copy;
+    /// one that falls further behind than that misses the oldest of them and gets a
+    /// [PantryError::StreamLagged] in their place. Errors reach every copy, as
+    /// [PantryError::StreamError] with the same message where the original can't be
+    /// copied.
+    ///
+    /// Reading stops once every copy is dropped. Like dropping the stream itself, that
+    /// doesn't interrupt inference; take a [LLMEventStream::guard] first for that.
+    ///
+    /// ```no_run
+    /// # use pantry_rs::stream::LLMEventStream;
+    /// # fn example(stream: LLMEventStream) {
+    /// let mut copies = stream.broadcast(2, 64);
+    /// let (ui, log) = (copies.remove(0), copies.remove(0));
+    /// # }
+    /// ```
+    pub fn broadcast(self, consumers: usize, capacity: usize) -> Vec<BroadcastEventStream> {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        let receivers = (0..consumers)
+            .map(|_| receive(sender.subscribe()))
+            .collect();
+        let pump = async move {
+            let mut stream = self;
+            while let Some(event) = stream.next().await {
+                if sender.send(event.map_err(Arc::new)).is_err() {
+                    // Everybody's gone.
+                    break;
+                }
+            }
+        };
+
+        #[cfg(target_arch = "wasm32")]
+        crate::wasm::spawn(pump);
+        #[cfg(not(target_arch = "wasm32"))]
+        tokio::spawn(pump);
+        receivers
+    }
+
+    /// Splits the stream in two, see [LLMEventStream::broadcast].
+    pub fn tee(self) -> (BroadcastEventStream, BroadcastEventStream) {
+        let mut copies = self.broadcast(2, DEFAULT_BROADCAST_CAPACITY);
+        let second = copies.pop().unwrap();
+        (copies.pop().unwrap(), second)
+    }
+
+    /// Creates a [PromptGuard] that interrupts this stream's inference when dropped,
+    /// unless the stream has finished by then.
+    pub fn guard(&self) -> PromptGuard {
+        PromptGuard {
+            client: self.client.clone(),
+            user_id: self.user_id,
+            api_key: self.api_key.clone(),
+            session_id: self.session_id,
+            llm_uuid: self.llm_uuid,
+            finished: self.finished.clone(),
+            interrupt_on_drop: true,
+        }
+    }
+
+    /// Interrupts the inference behind this stream. See [crate::PantryAPI::interrupt_session].
+    ///
+    /// The stream keeps going until Pantry stops sending, so keep polling it (or drop it).
+    pub async fn interrupt(&self) -> Result<LLMRunningStatus, PantryError> {
+        self.interrupted.store(true, Ordering::SeqCst);
+        self.client
+            .interrupt_session(
+                self.user_id,
+                self.api_key.clone(),
+                self.llm_uuid,
+                self.session_id,
+            )
+            .await
+    }
+
+    /// Where the earliest stop sequence starts in the text so far.
+    fn find_stop(&self) -> Option<usize> {
+        self.stop_sequences
+            .iter()
+            .filter_map(|stop| self.text.find(stop.as_str()))
+            .min()
+    }
+
+    fn finish(&mut self, reason: FinishReason) {
+        if self.finish_reason.is_none() {
+            if let Some(metrics) = self.client.metrics() {
+                metrics.prompt(&PromptMetric {
+                    session_id: self.session_id,
+                    llm_uuid: self.llm_uuid,
+                    time_to_first_token: self.first_token,
+                    duration: self.started.elapsed(),
+                    tokens: self.tokens,
+                    finish_reason: reason,
+                });
+            }
+            event_bus::publish(self.client.event_bus(), || ClientEvent::PromptFinished {
+                session_id: self.session_id,
+                llm_uuid: self.llm_uuid,
+                reason,
+                tokens: self.tokens,
+            });
+        }
+        self.finish_reason.get_or_insert(reason);
+        self.finished.store(true, Ordering::SeqCst);
+        self.permit = None;
+        self.ticket = None;
+    }
+
+    /// Starts prompting again in a fresh session, if the replay policy allows another go.
+    /// The fresh session is fed the old one's history first, leaving out the `failed`
+    /// inference, and the old one is deleted.
+    fn start_replay(&mut self, failed: Uuid) -> bool {
+        let replay = match &mut self.replay {
+            Some(replay) if replay.replays + 1 < replay.policy.max_attempts => replay,
+            _ => return false,
+        };
+        let delay = replay.policy.delay(replay.replays);
+        replay.replays += 1;
+        let client = self.client.clone();
+        let user_id = self.user_id;
+        let api_key = self.api_key.clone();
+        let llm_uuid = self.llm_uuid;
+        let abandoned = self.session_id;
+        let prompt = replay.prompt.clone();
+        let parameters = replay.parameters.clone();
+        let session_parameters = replay.session_parameters.clone();
+        self.replaying = Some(Box::pin(async move {
+            Delay::new(delay).await;
+            let history = client
+                .get_session_history(user_id, api_key.clone(), abandoned)
+                .await?;
+            let mut seen: String = history
+                .into_iter()
+                .filter(|item| item.id != failed)
+                .map(|item| item.input + &item.output)
+                .collect();
+            seen.push_str(&prompt);
+            let session = client
+                .create_session_id(user_id, api_key.clone(), llm_uuid, session_parameters)
+                .await?;
+            let stream = client
+                .prompt_session_stream(
+                    user_id,
+                    api_key.clone(),
+                    session.session_id,
+                    llm_uuid,
+                    seen,
+                    parameters,
+                )
+                .await?;
+            // Nothing useful is left in it, and it may be what broke.
+            let _ = client.delete_session(user_id, api_key, abandoned).await;
+            Ok(stream)
+        }));
+        true
+    }
+
+    /// Whether the watchdog went off while waiting on the next event.
+    fn stalled(&mut self, cx: &mut Context<'_>) -> bool {
+        let timeout = match self.stall_timeout {
+            Some(timeout) => timeout,
+            None => return false,
+        };
+        let watchdog = self.watchdog.get_or_insert_with(|| Delay::new(timeout));
+        Pin::new(watchdog).poll(cx).is_ready()
+    }
+
+    /// The completion event for a prompt we cut off at `max_tokens` or a stop sequence.
+    fn cut_off(&mut self, reason: FinishReason) -> Option<LLMEvent> {
+        spawn_interrupt(
+            &self.client,
+            self.user_id,
+            &self.api_key,
+            self.session_id,
+            self.llm_uuid,
+        );
+        self.finish(reason);
+        let mut event = self.last_event.take()?;
+        event.event = LLMEventInternal::PromptCompletion {
+            previous: std::mem::take(&mut self.text),
+            finish_reason: Some(reason),
+            prompt_tokens: None,
+            completion_tokens: Some(self.tokens as u32),
+        };
+        Some(event)
+    }
+}
+
+impl Stream for LLMEventStream {
+    type Item = Result<LLMEvent, PantryError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.finished.load(Ordering::SeqCst) {
+            return Poll::Ready(None);
+        }
+        if this.stopped {
+            return Poll::Ready(this.cut_off(FinishReason::Stop).map(Ok));
+        }
+        if let Some(cancelled) = &mut this.cancelled {
+            if cancelled.as_mut().poll(cx).is_ready() {
+                this.interrupted.store(true, Ordering::SeqCst);
+                spawn_interrupt(
+                    &this.client,
+                    this.user_id,
+                    &this.api_key,
+                    this.session_id,
+                    this.llm_uuid,
+                );
+                this.finish(FinishReason::Interrupted);
+                return Poll::Ready(None);
+            }
+        }
+        if let Some(ticket) = &mut this.ticket {
+            // Interrupting, if asked for, is up to the shutdown.
+            if ticket.cut.as_mut().poll(cx).is_ready() {
+                this.finish(FinishReason::Interrupted);
+                return Poll::Ready(Some(Err(PantryError::ClientShutDown)));
+            }
+        }
+        if let Some(replaying) = &mut this.replaying {
+            match replaying.as_mut().poll(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(stream)) => {
+                    this.replaying = None;
+                    this.inner = stream.inner;
+                    this.session_id = stream.session_id;
+                    if let Some(ticket) = &this.ticket {
+                        ticket.set_session(this.session_id);
+                    }
+                    this.stream_id = None;
+                }
+                Poll::Ready(Err(e)) => {
+                    this.replaying = None;
+                    this.finish(FinishReason::Error);
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+
+        let mut poll = this.inner.as_mut().poll_next(cx);
+        match &mut poll {
+            Poll::Ready(Some(Ok(event))) => {
+                // Nothing's been handed out yet, so a fresh start goes unnoticed.
+                if matches!(event.event, LLMEventInternal::PromptError { .. })
+                    && this.tokens == 0
+                    && this.start_replay(event.stream_id)
+                {
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                this.stream_id.get_or_insert(event.stream_id);
+                let rejected = this
+                    .middleware
+                    .iter()
+                    .try_for_each(|middleware| middleware.on_event(event));
+                if let Err(e) = rejected {
+                    spawn_interrupt(
+                        &this.client,
+                        this.user_id,
+                        &this.api_key,
+                        this.session_id,
+                        this.llm_uuid,
+                    );
+                    this.finish(FinishReason::Error);
+                    return Poll::Ready(Some(Err(e)));
+                }
+                match &mut event.event {
+                    LLMEventInternal::PromptProgress { next, .. } => {
+                        // A reply ending right at the cap completes as usual.
+                        if this
+                            .max_tokens
+                            .is_some_and(|max| this.tokens >= max as usize)
+                        {
+                            return Poll::Ready(this.cut_off(FinishReason::Length).map(Ok));
+                        }
+                        this.tokens += 1;
+                        if let Some(limiter) = this.client.rate_limiter() {
+                            limiter.spend_tokens(1);
+                        }
+                        this.first_token
+                            .get_or_insert_with(|| this.started.elapsed());
+                        let before = this.text.len();
+                        this.text.push_str(next);
+                        if let Some(stop) = this.find_stop() {
+                            // Only hand out what comes before the stop sequence.
+                            next.truncate(stop.saturating_sub(before));
+                            this.text.truncate(stop);
+                            this.stopped = true;
+                        }
+                        this.last_event = Some(event.clone());
+                    }
+                    LLMEventInternal::PromptCompletion {
+                        finish_reason,
+                        completion_tokens,
+                        ..
+                    } => {
+                        let reason = finish_reason.unwrap_or_else(|| {
+                            if this.interrupted.load(Ordering::SeqCst) {
+                                FinishReason::Interrupted
+                            } else {
+                                FinishReason::Stop
+                            }
+                        });
+                        *finish_reason = Some(reason);
+                        completion_tokens.get_or_insert(this.tokens as u32);
+                        this.finish(reason);
+                    }
+                    LLMEventInternal::PromptError { .. } => this.finish(FinishReason::Error),
+                    LLMEventInternal::Other => {}
+                }
+            }
+            // Ending without a completion event means inference got cut short.
+            Poll::Ready(None) => this.finish(FinishReason::Interrupted),
+            Poll::Ready(Some(Err(_))) => {}
+            Poll::Pending => {
+                if this.stalled(cx) {
+                    if this.interrupt_on_stall {
+                        spawn_interrupt(
+                            &this.client,
+                            this.user_id,
+                            &this.api_key,
+                            this.session_id,
+                            this.llm_uuid,
+                        );
+                    }
+                    this.finish(FinishReason::Error);
+                    let idle = this.stall_timeout.unwrap_or_default();
+                    return Poll::Ready(Some(Err(PantryError::StreamStalled(idle))));
+                }
+                return poll;
+            }
+        }
+        this.watchdog = None;
+        poll
+    }
+}
+
+type Broadcast = Result<LLMEvent, Arc<PantryError>>;
+
+/// A consumer's end of [LLMEventStream::broadcast].
+fn receive(receiver: broadcast::Receiver<Broadcast>) -> BroadcastEventStream {
+    Box::pin(stream::unfold(receiver, |mut receiver| async move {
+        let item = match receiver.recv().await {
+            Ok(Ok(event)) => Ok(event),
+            Ok(Err(err)) => Err(copy_error(&err)),
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                Err(PantryError::StreamLagged(missed))
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        };
+        Some((item, receiver))
+    }))
+}
+
+/// A copy of `err` for one consumer of a broadcast. Most errors can't be cloned, those
+/// turn into a [PantryError::StreamError] with the same message.
+fn copy_error(err: &PantryError) -> PantryError {
+    match err {
+        PantryError::PromptError(msg) => PantryError::PromptError(msg.clone()),
+        PantryError::StreamError(msg) => PantryError::StreamError(msg.clone()),
+        PantryError::StreamStalled(idle) => PantryError::StreamStalled(*idle),
+        PantryError::Timeout(duration) => PantryError::Timeout(*duration),
+        err => PantryError::StreamError(err.to_string()),
+    }
+}
+
+/// Interrupts a session on a background task, if there's a tokio runtime (or a browser)
+/// to run it on.
+fn spawn_interrupt(
+    client: &Arc<dyn PantryBackend>,
+    user_id: UserId,
+    api_key: &str,
+    session_id: SessionId,
+    llm_uuid: LlmUuid,
+) {
+    let client = client.clone();
+    let api_key = api_key.to_string();
+    let interrupt = async move {
+        // Nobody is left to hear about a failure.
+        let _ = client
+            .interrupt_session(user_id, api_key, llm_uuid, session_id)
+            .await;
+    };
+    #[cfg(feature = "logging")]
+    if !spawn_background(interrupt) {
+        log::warn!(
+            "Not inside a tokio runtime, not interrupting session {}",
+            session_id
+        );
+    }
+    #[cfg(not(feature = "logging"))]
+    spawn_background(interrupt);
+}
+
+/// Runs `task` in the background, for cleaning up from `Drop`. Returns `false` if there's
+/// nowhere to run it, outside a tokio runtime.
+pub(crate) fn spawn_background(task: impl Future<Output = ()> + Send + 'static) -> bool {
+    #[cfg(target_arch = "wasm32")]
+    crate::wasm::spawn(task);
+    #[cfg(target_arch = "wasm32")]
+    return true;
+    #[cfg(not(target_arch = "wasm32"))]
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => {
+            handle.spawn(task);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Interrupts an in-flight prompt when dropped.
+///
+/// Dropping an [LLMEventStream] only stops _reading_ events; the LLM keeps generating
+/// until it's done. Hold on to a guard for as long as you care about the output, and
+/// abandoned prompts get interrupted instead of burning CPU/GPU in the background.
+///
+/// ```no_run
+/// # use futures::StreamExt;
+/// # use pantry_rs::LLMSession;
+/// # use std::collections::HashMap;
+/// # fn user_cancelled() -> bool { false }
+/// # async fn example(sess: LLMSession, prompt: String) -> Result<(), Box<dyn std::error::Error>> {
+/// let (mut stream, _guard) = sess.prompt_session_guarded(prompt, HashMap::new()).await?;
+/// while let Some(event) = stream.next().await {
+///     if user_cancelled() {
+///         break; // _guard interrupts the session on the way out.
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Nothing happens if the stream already finished. Interrupting happens on a background
+/// task, so the guard must be dropped inside a tokio runtime.
+pub struct PromptGuard {
+    client: Arc<dyn PantryBackend>,
+    user_id: UserId,
+    api_key: String,
+    session_id: SessionId,
+    llm_uuid: LlmUuid,
+    finished: Arc<AtomicBool>,
+    interrupt_on_drop: bool,
+}
+
+impl PromptGuard {
+    /// Whether dropping the guard interrupts the prompt. Defaults to `true`.
+    pub fn set_interrupt_on_drop(&mut self, interrupt_on_drop: bool) {
+        self.interrupt_on_drop = interrupt_on_drop;
+    }
+
+    pub fn interrupt_on_drop(&self) -> bool {
+        self.interrupt_on_drop
+    }
+
+    /// Drops the guard without interrupting anything.
+    pub fn disarm(mut self) {
+        self.interrupt_on_drop = false;
+    }
+}
+
+impl Drop for PromptGuard {
+    fn drop(&mut self) {
+        if !self.interrupt_on_drop || self.finished.load(Ordering::SeqCst) {
+            return;
+        }
+        spawn_interrupt(
+            &self.client,
+            self.user_id,
+            &self.api_key,
+            self.session_id,
+            self.llm_uuid,
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Getting typed values, rather than text, out of an LLM.
+//!
+//! [LLMSession::prompt_typed] asks for JSON, digs it out of whatever the LLM wrapped
+//! around it, and deserializes it. If that fails, the LLM is told what was wrong and
+//! asked again, in the same session so it can see its previous answer:
+//!
+//! ```no_run
+//! # use pantry_rs::{InferenceParams, LLMSession};
+//! # async fn example(sess: LLMSession) -> Result<(), Box<dyn std::error::Error>> {
+//! #[derive(serde::Deserialize)]
+//! struct Review {
+//!     sentiment: String,
+//!     score: u8,
+//! }
+//!
+//! let review: Review = sess
+//!     .prompt_typed(
+//!         "Review: 'Great food, slow service.' Give its sentiment and a score out of 10 \
+//!          as {\"sentiment\": ..., \"score\": ...}"
+//!             .into(),
+//!         InferenceParams::new().temperature(0.2),
+//!     )
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The prompt should describe the shape it wants; only the type can check it.
+use crate::error::PantryError;
+use crate::params::InferenceParams;
+use crate::LLMSession;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Tries [LLMSession::prompt_typed] makes, including the first.
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// Appended to the prompt, so the LLM knows to answer in JSON.
+pub const JSON_INSTRUCTION: &str = "\n\nAnswer with a single JSON value and nothing else.";
+
+/// Prompts `session` for a `T`, asking again up to `max_attempts` times in total (at
+/// least once) while the answer doesn't deserialize. See [LLMSession::prompt_typed].
+///
+/// Fails with [PantryError::StructuredOutputError] when no attempt worked out, with the
+/// last problem found.
+pub async fn prompt_typed<T: DeserializeOwned>(
+    session: &LLMSession,
+    prompt: String,
+    parameters: impl Into<InferenceParams>,
+    max_attempts: u32,
+) -> Result<T, PantryError> {
+    let parameters = parameters.into();
+    let max_attempts = max_attempts.max(1);
+    let mut prompt = prompt + JSON_INSTRUCTION;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let text = session
+            .prompt_and_collect(prompt, parameters.clone())
+            .await?;
+        let problem = match parse_json::<T>(&text) {
+            Ok(value) => return Ok(value),
+            Err(problem) => problem,
+        };
+        if attempt >= max_attempts {
+            return Err(PantryError::StructuredOutputError(attempt, problem));
+        }
+        prompt = format!(
+            "That answer can't be used: {}. Reply with only the corrected JSON.",
+            problem
+        );
+    }
+}
+
+/// Deserializes the JSON in `text`, see [extract_json]. Failing that, the whole of
+/// `text` is tried as a scalar: a number, `true`/`false` or a string, quoted or not. On
+/// failure returns what went wrong, worded to be shown to the LLM.
+pub fn parse_json<T: DeserializeOwned>(text: &str) -> Result<T, String> {
+    let extracted = extract_json(text).map(serde_json::from_str::<T>);
+    if let Some(Ok(value)) = extracted {
+        return Ok(value);
+    }
+    let scalar = unfenced(text);
+    if let Ok(value) = serde_json::from_str(scalar)
+        .or_else(|_| serde_json::from_value(Value::String(scalar.to_string())))
+    {
+        return Ok(value);
+    }
+    match extracted {
+        Some(Err(e)) => Err(format!("the JSON doesn't fit ({})", e)),
+        _ => Err("it contains no JSON".to_string()),
+    }
+}
+
+/// `text` trimmed, without a markdown code fence around it.
+fn unfenced(text: &str) -> &str {
+    let text = text.trim();
+    match text.strip_prefix("```") {
+        Some(fenced) => {
+            let fenced = fenced.strip_suffix("```").unwrap_or(fenced);
+            // Skip the language, e.g. `json`.
+            let body = fenced.split_once('\n').map_or(fenced, |(_, body)| body);
+            body.trim()
+        }
+        None => text,
+    }
+}
+
+/// The first JSON object or array in `text`, skipping any prose or markdown code
+/// fence around it. Only brackets are matched, so the slice may still be invalid JSON.
+pub fn extract_json(text: &str) -> Option<&str> {
+    let start = text.find(['{', '['])?;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in text[start..].char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&text[start..start + i + 1]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
@@ -0,0 +1,124 @@
+//! Named prompt templates, rendered with [minijinja].
+//!
+//! Instead of formatting prompts inline, give each one a name and a Jinja template,
+//! and hand the set to [crate::PantryClientBuilder::templates]. Sessions then prompt by
+//! name, with the template's variables in a [context!]:
+//!
+//! ```no_run
+//! # use minijinja::context;
+//! # use pantry_rs::templates::PromptTemplates;
+//! # use pantry_rs::{PantryClientBuilder, UserId};
+//! # use std::collections::HashMap;
+//! # async fn example(user_id: UserId, api_key: String, article: String) -> Result<(), Box<dyn std::error::Error>> {
+//! let mut templates = PromptTemplates::new();
+//! templates.add("summarize", "{% include 'preamble' %}Summarize this:\n{{ text }}")?;
+//! templates.add("preamble", "")?;
+//! // Llama models get a different preamble.
+//! templates.add_for_family("llama", "preamble", "[INST] Be concise. [/INST]\n")?;
+//!
+//! let pantry = PantryClientBuilder::new().templates(templates).login(user_id, api_key);
+//! let sess = pantry.create_session(HashMap::new()).await?;
+//! let stream = sess.prompt_template("summarize", context! { text => article }).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Templates can include, import and extend each other. A template added for a model
+//! family (see [LLMStatus::family_id]) replaces the one of the same name for that
+//! family's models, wherever it's used. Variables that weren't passed are an error
+//! rather than empty, and trailing newlines are kept.
+use crate::error::PantryError;
+use crate::interface::LLMStatus;
+use minijinja::{Environment, UndefinedBehavior};
+use serde::Serialize;
+use std::collections::HashMap;
+
+pub use minijinja::context;
+
+/// A set of named templates, see the [module docs](self).
+#[derive(Clone, Debug, Default)]
+pub struct PromptTemplates {
+    templates: HashMap<String, String>,
+    /// Overrides by family id.
+    families: HashMap<String, HashMap<String, String>>,
+}
+
+impl PromptTemplates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds template `name`, replacing any of the same name. Fails if `source` isn't a
+    /// valid template.
+    pub fn add(
+        &mut self,
+        name: impl Into<String>,
+        source: impl Into<String>,
+    ) -> Result<(), PantryError> {
+        let source = source.into();
+        check(&source)?;
+        self.templates.insert(name.into(), source);
+        Ok(())
+    }
+
+    /// Adds template `name` for models of `family` only, e.g. a partial in the format
+    /// they were trained on.
+    pub fn add_for_family(
+        &mut self,
+        family: impl Into<String>,
+        name: impl Into<String>,
+        source: impl Into<String>,
+    ) -> Result<(), PantryError> {
+        let source = source.into();
+        check(&source)?;
+        self.families
+            .entry(family.into())
+            .or_default()
+            .insert(name.into(), source);
+        Ok(())
+    }
+
+    /// Names of the templates, family-specific ones included.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .templates
+            .keys()
+            .chain(self.families.values().flat_map(|t| t.keys()))
+            .map(String::as_str)
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Renders template `name` with the variables in `context`, for `llm` if given.
+    pub fn render(
+        &self,
+        name: &str,
+        llm: Option<&LLMStatus>,
+        context: impl Serialize,
+    ) -> Result<String, PantryError> {
+        let mut env = Environment::new();
+        env.set_undefined_behavior(UndefinedBehavior::Strict);
+        // Whitespace matters to LLMs, so templates render exactly as written.
+        env.set_keep_trailing_newline(true);
+        let family = llm.and_then(|llm| self.families.get(&llm.family_id));
+        for (template, source) in self.templates.iter().chain(family.into_iter().flatten()) {
+            env.add_template(template, source).map_err(template_error)?;
+        }
+        env.get_template(name)
+            .and_then(|template| template.render(context))
+            .map_err(template_error)
+    }
+}
+
+fn check(source: &str) -> Result<(), PantryError> {
+    Environment::new()
+        .template_from_str(source)
+        .map(|_| ())
+        .map_err(template_error)
+}
+
+fn template_error(err: minijinja::Error) -> PantryError {
+    PantryError::TemplateError(err.to_string())
+}
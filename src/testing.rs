@@ -0,0 +1,1839 @@
+//! A fake Pantry daemon for tests, requires the `testing` feature.
+//!
+//! [MockPantryServer] serves Pantry's routes from memory on an ephemeral port (or a unix
+//! socket), so code built on [PantryClient] can be tested in CI without a real daemon or
+//! anyone clicking "accept" in the UI:
+//!
+//! ```
+//! # use pantry_rs::interface::UserPermissions;
+//! # use pantry_rs::testing::{self, MockPantryServer};
+//! # use std::collections::HashMap;
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let perms = UserPermissions {
+//! #     perm_session: true,
+//! #     perm_load_llm: true,
+//! #     ..Default::default()
+//! # };
+//! let server = MockPantryServer::start().await?;
+//! server.add_llm(testing::mock_llm("openchat"));
+//!
+//! let (pantry, request) = server
+//!     .builder()
+//!     .register("my app".into(), perms)
+//!     .await?;
+//! assert!(request.accepted); // auto-approved
+//!
+//! pantry.load_llm_flex(None, None).await?;
+//! let sess = pantry.create_session(HashMap::new()).await?;
+//! let text = sess.prompt_and_collect("Hi".into(), HashMap::new()).await?;
+//! assert_eq!(text, "Hello, world!");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Behaviour is canned rather than simulated: requests are accepted immediately unless
+//! [MockPantryServer::auto_approve] is turned off, downloads finish instantly, and every
+//! prompt gets the same reply, see [MockPantryServer::reply]. Permissions and API keys
+//! are checked like Pantry does, so tests still catch calls made without them.
+use crate::api::{
+    select_llm, BareModelLeaseResponse, BareModelResponse, CreateSessionResponse, LLMFilter,
+    LLMPreference, StreamFormat,
+};
+use crate::error::PantryError;
+use crate::ids::{LeaseId, LlmUuid, RequestId, SessionId, UserId};
+use crate::interface::{
+    AdapterEntry, AdapterStatus, DeleteRequest, DownloadRequest, ExportMode, FinishReason,
+    LLMConnectorType, LLMEvent, LLMEventInternal, LLMHistoryItem, LLMRegistryEntry,
+    LLMResourceUsage, LLMRunningStatus, LLMSessionStatus, LLMStatus, LoadRequest, ModelStorage,
+    PermissionRequest, RegisterLocalRequest, ServerEvent, ServerInfo, StorageInfo, SystemStatus,
+    UnloadRequest, UserInfo, UserPermissions, UserRequestStatus, UserRequestType, UserStatus,
+    PROTOCOL_VERSION,
+};
+use crate::multimodal::PromptInput;
+use crate::{LLMSession, PantryClient, PantryClientBuilder, RetryPolicy};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use futures::channel::{mpsc, oneshot};
+use futures::stream::{self, Stream, StreamExt};
+use futures_timer::Delay;
+use hyper::body::Bytes;
+use hyper::header::{ACCEPT, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+#[cfg(all(unix, feature = "unix-socket"))]
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// What every prompt gets as a reply until [MockPantryServer::reply] says otherwise.
+pub const DEFAULT_REPLY: &[&str] = &["Hello", ",", " world", "!"];
+
+/// RAM the mock's machine has, as reported by its `get_system_status`. Running LLMs
+/// use up what their [LLMStatus::ram_bytes] says.
+pub const MOCK_RAM_BYTES: u64 = 16 << 30;
+
+/// Disk space the mock's machine has, all of it free.
+pub const MOCK_DISK_BYTES: u64 = 512 << 30;
+
+/// Routes [MockPantryServer] serves, as reported by its `server_info`.
+pub const ENDPOINTS: &[&str] = &[
+    "server_info",
+    "register_user",
+    "request_permissions",
+    "request_download",
+    "request_load",
+    "request_load_flex",
+    "request_unload",
+    "request_delete",
+    "request_register_local",
+    "get_request_status",
+    "get_pending_requests",
+    "cancel_request",
+    "get_permissions",
+    "get_user_info",
+    "get_llm_status",
+    "get_running_llms",
+    "get_available_llms",
+    "get_system_status",
+    "get_storage_info",
+    "load_llm",
+    "load_llm_flex",
+    "unload_llm",
+    "download_llm",
+    "get_or_download_llm",
+    "delete_llm",
+    "register_local_model",
+    "create_session",
+    "create_session_id",
+    "create_session_flex",
+    "load_session_id",
+    "fork_session",
+    "get_session_history",
+    "get_sessions",
+    "delete_session",
+    "interrupt_session",
+    "prompt_session_stream",
+    "transcribe_stream",
+    "subscribe_events",
+    "bare_model",
+    "bare_model_flex",
+    "export_bare_model",
+    "bare_model_lease",
+    "release_bare_model",
+    "set_connector_secret",
+    "list_adapters",
+    "download_adapter",
+    "apply_adapter",
+    "remove_adapter",
+    "list_users",
+    "get_user",
+    "list_pending_requests",
+    "approve_request",
+    "deny_request",
+    "revoke_api_key",
+];
+
+/// A downloaded, not yet running LLM with id `id`, for [MockPantryServer::add_llm].
+pub fn mock_llm(id: &str) -> LLMStatus {
+    serde_json::from_value(json!({
+        "id": id,
+        "family_id": "llama",
+        "organization": "pantry-rs",
+        "name": id,
+        "homepage": "",
+        "license": "MIT",
+        "description": "Mock LLM",
+        "capabilities": {"general": 1, "assistant": 1, "writing": 1, "coding": 1},
+        "requirements": "",
+        "tags": [],
+        "url": "",
+        "local": true,
+        "connector_type": "llmrs",
+        "download_progress": 100.0,
+        "config": {},
+        "parameters": {},
+        "user_parameters": [],
+        "session_parameters": {},
+        "user_session_parameters": [],
+        "uuid": LlmUuid::new_v4(),
+        "running": false
+    }))
+    .expect("mock LLM is a valid LLMStatus")
+}
+
+/// A fake Pantry daemon, see the [module docs](self).
+///
+/// Serves until dropped.
+#[derive(Debug)]
+pub struct MockPantryServer {
+    state: Arc<Mutex<MockState>>,
+    base_url: Option<String>,
+    #[cfg(all(unix, feature = "unix-socket"))]
+    socket_path: Option<PathBuf>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockPantryServer {
+    /// Starts serving on an ephemeral port on localhost. Needs a tokio runtime.
+    pub async fn start() -> Result<Self, PantryError> {
+        Self::start_at(SocketAddr::from(([127, 0, 0, 1], 0))).await
+    }
+
+    /// Starts serving on `addr`, e.g. `127.0.0.1:9404` to stand in for the local
+    /// daemon clients fall back to when the unix socket fails. Needs a tokio runtime.
+    pub async fn start_at(addr: SocketAddr) -> Result<Self, PantryError> {
+        let state = Arc::new(Mutex::new(MockState::new()));
+        let (shutdown, signal) = oneshot::channel::<()>();
+        let served = state.clone();
+        let server = hyper::Server::try_bind(&addr)?.serve(make_service_fn(move |_conn| {
+            let state = served.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |req| serve(state.clone(), req))) }
+        }));
+        let base_url = format!("http://{}", server.local_addr());
+        tokio::spawn(server.with_graceful_shutdown(async {
+            let _ = signal.await;
+        }));
+        Ok(MockPantryServer {
+            state,
+            base_url: Some(base_url),
+            #[cfg(all(unix, feature = "unix-socket"))]
+            socket_path: None,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// Starts serving on a unix socket at `path`, for testing the socket transport.
+    /// Needs a tokio runtime. The socket file is removed when the server is dropped.
+    #[cfg(all(unix, feature = "unix-socket"))]
+    pub async fn start_unix(path: impl AsRef<Path>) -> Result<Self, PantryError> {
+        use hyperlocal::UnixServerExt;
+
+        let path = path.as_ref().to_path_buf();
+        let state = Arc::new(Mutex::new(MockState::new()));
+        let (shutdown, signal) = oneshot::channel::<()>();
+        let served = state.clone();
+        let server = hyper::Server::bind_unix(&path)?.serve(make_service_fn(move |_conn| {
+            let state = served.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |req| serve(state.clone(), req))) }
+        }));
+        tokio::spawn(server.with_graceful_shutdown(async {
+            let _ = signal.await;
+        }));
+        Ok(MockPantryServer {
+            state,
+            base_url: None,
+            socket_path: Some(path),
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// Url to reach the server at, `None` when it's serving on a unix socket.
+    pub fn base_url(&self) -> Option<&str> {
+        self.base_url.as_deref()
+    }
+
+    /// A [PantryClientBuilder] pointed at this server, without retries so failures
+    /// show up right away.
+    pub fn builder(&self) -> PantryClientBuilder {
+        let builder = PantryClient::builder().retry(RetryPolicy::none());
+        #[cfg(all(unix, feature = "unix-socket"))]
+        if let Some(path) = &self.socket_path {
+            return builder.socket_path(path.to_string_lossy());
+        }
+        match &self.base_url {
+            Some(url) => builder.base_url(url.clone()),
+            None => builder,
+        }
+    }
+
+    /// Registers a user that already has `permissions`, skipping the request, and
+    /// returns a client logged in as them.
+    pub fn login(&self, permissions: UserPermissions) -> PantryClient {
+        let (user_id, api_key) = self.lock().add_user("test".into(), permissions);
+        self.builder().login(user_id, api_key)
+    }
+
+    /// Adds an LLM to the available ones, e.g. [mock_llm]. Set `running` to have it
+    /// loaded already.
+    pub fn add_llm(&self, llm: LLMStatus) {
+        self.lock().llms.push(llm);
+    }
+
+    /// Adds a running [mock_llm] called `id`, and returns a client logged in as a user
+    /// allowed sessions, the setup most tests start from.
+    pub fn running_client(&self, id: &str) -> PantryClient {
+        self.running_client_with(id, self.builder())
+    }
+
+    /// Like [MockPantryServer::running_client], for a client made by `builder`, e.g.
+    /// [MockPantryServer::builder] with more settings.
+    pub fn running_client_with(&self, id: &str, builder: PantryClientBuilder) -> PantryClient {
+        self.add_running(id);
+        self.session_user(builder)
+    }
+
+    /// A session on a running [mock_llm] called `id`, see
+    /// [MockPantryServer::running_client].
+    pub async fn running_session(&self, id: &str) -> LLMSession {
+        self.running_session_with(id, self.builder()).await
+    }
+
+    /// Like [MockPantryServer::running_session], for a client made by `builder`.
+    pub async fn running_session_with(&self, id: &str, builder: PantryClientBuilder) -> LLMSession {
+        let llm = self.add_running(id);
+        self.session_user(builder)
+            .create_session_id(llm.uuid, HashMap::new())
+            .await
+            .expect("the mock creates sessions on running LLMs")
+    }
+
+    /// Adds a [mock_llm] called `id` that's running already.
+    pub fn add_running(&self, id: &str) -> LLMStatus {
+        let mut llm = mock_llm(id);
+        llm.running = true;
+        self.add_llm(llm.clone());
+        llm
+    }
+
+    fn session_user(&self, builder: PantryClientBuilder) -> PantryClient {
+        let permissions = UserPermissions {
+            perm_session: true,
+            ..Default::default()
+        };
+        let (user_id, api_key) = self.lock().add_user("test".into(), permissions);
+        builder.login(user_id, api_key)
+    }
+
+    /// Tokens every prompt gets as a reply from now on, see [DEFAULT_REPLY].
+    pub fn reply<S: Into<String>>(&self, tokens: impl IntoIterator<Item = S>) {
+        self.lock().reply = tokens.into_iter().map(Into::into).collect();
+    }
+
+    /// Tokens for the next prompt only, ahead of [MockPantryServer::reply]. Queued
+    /// replies get used up in order, one per prompt, e.g. to script a conversation.
+    pub fn queue_reply<S: Into<String>>(&self, tokens: impl IntoIterator<Item = S>) {
+        let tokens = tokens.into_iter().map(Into::into).collect();
+        self.lock().queued_replies.push_back(tokens);
+    }
+
+    /// Pause between reply tokens, so tests can interrupt inference halfway. None by
+    /// default.
+    pub fn token_delay(&self, delay: Duration) {
+        self.lock().token_delay = delay;
+    }
+
+    /// Cuts the connection of the next prompt stream after `n` events, as if the network
+    /// dropped, to test reconnecting. Resuming it afterwards works.
+    pub fn drop_stream_after(&self, n: usize) {
+        self.lock().drop_stream_after = Some(n);
+    }
+
+    /// Fails the next `n` prompts with a [LLMEventInternal::PromptError] before any
+    /// token, as if the LLM ran out of memory, to test prompting again.
+    pub fn fail_prompts(&self, n: usize) {
+        self.lock().failing_prompts = n;
+    }
+
+    /// Rejects calls without header `name` set to `value`, like a gateway in front of a
+    /// remote Pantry would. See [crate::auth].
+    pub fn require_header(&self, name: &str, value: &str) {
+        self.lock()
+            .required_headers
+            .push((name.to_string(), value.to_string()));
+    }
+
+    /// Whether streaming calls get answered with NDJSON when the client prefers it, the
+    /// default. Otherwise they're always server-sent events, like older Pantry versions.
+    pub fn ndjson(&self, ndjson: bool) {
+        self.lock().ndjson = ndjson;
+    }
+
+    /// Whether requests get accepted as soon as they're made, the default. Otherwise
+    /// they stay pending until [MockPantryServer::approve] or [MockPantryServer::deny].
+    pub fn auto_approve(&self, auto_approve: bool) {
+        self.lock().auto_approve = auto_approve;
+    }
+
+    /// Accepts a pending request, like the owner clicking "accept". Returns whether
+    /// there was such a request.
+    pub fn approve(&self, request_id: RequestId) -> bool {
+        self.lock().decide(request_id, true).is_some()
+    }
+
+    /// Rejects a pending request. Returns whether there was such a request.
+    pub fn deny(&self, request_id: RequestId) -> bool {
+        self.lock().decide(request_id, false).is_some()
+    }
+
+    /// Ids of the requests still waiting for a decision.
+    pub fn pending_requests(&self) -> Vec<RequestId> {
+        let state = self.lock();
+        state
+            .requests
+            .iter()
+            .filter(|r| !r.complete)
+            .map(|r| r.id)
+            .collect()
+    }
+
+    /// Endpoints called so far, oldest first, e.g. `"create_session"`.
+    pub fn calls(&self) -> Vec<String> {
+        self.lock().calls.clone()
+    }
+
+    /// Snapshot of the LLMs the server knows about.
+    pub fn llms(&self) -> Vec<LLMStatus> {
+        self.lock().llms.clone()
+    }
+
+    /// The secret last set for `connector` with [PantryClient::set_connector_secret].
+    pub fn connector_secret(&self, connector: LLMConnectorType) -> Option<String> {
+        self.lock()
+            .connector_secrets
+            .get(&connector.to_string())
+            .cloned()
+    }
+
+    /// The last fresh prompt sent, with any images where they came in it.
+    pub fn last_prompt(&self) -> Option<Vec<PromptInput>> {
+        self.lock().last_prompt.clone()
+    }
+
+    /// The last recording sent to be transcribed.
+    pub fn last_transcription(&self) -> Option<MockTranscription> {
+        self.lock().last_transcription.clone()
+    }
+
+    /// `Content-Type` of the last call, e.g. `application/json`.
+    pub fn last_content_type(&self) -> Option<String> {
+        self.lock().last_content_type.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl Drop for MockPantryServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        #[cfg(all(unix, feature = "unix-socket"))]
+        if let Some(path) = &self.socket_path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// A recording sent to be transcribed, see [MockPantryServer::last_transcription].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockTranscription {
+    pub audio: Vec<u8>,
+    pub mime: String,
+    pub parameters: HashMap<String, Value>,
+}
+
+#[derive(Debug)]
+struct MockUser {
+    name: String,
+    api_key: String,
+    permissions: UserPermissions,
+    last_active: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+struct MockSession {
+    user_id: UserId,
+    llm_uuid: LlmUuid,
+    started: DateTime<Utc>,
+    last_called: DateTime<Utc>,
+    session_parameters: HashMap<String, Value>,
+    history: Arc<Mutex<Vec<LLMHistoryItem>>>,
+    interrupted: Arc<AtomicBool>,
+}
+
+impl MockSession {
+    fn status(&self, id: SessionId) -> LLMSessionStatus {
+        LLMSessionStatus {
+            id,
+            llm_uuid: self.llm_uuid,
+            user_id: self.user_id,
+            started: self.started,
+            last_called: self.last_called,
+            session_parameters: self.session_parameters.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MockState {
+    users: HashMap<UserId, MockUser>,
+    requests: Vec<UserRequestStatus>,
+    llms: Vec<LLMStatus>,
+    sessions: HashMap<SessionId, MockSession>,
+    reply: Vec<String>,
+    queued_replies: VecDeque<Vec<String>>,
+    token_delay: Duration,
+    drop_stream_after: Option<usize>,
+    failing_prompts: usize,
+    ndjson: bool,
+    auto_approve: bool,
+    calls: Vec<String>,
+    required_headers: Vec<(String, String)>,
+    /// Where models downloaded with a `target_dir` went.
+    model_dirs: HashMap<LlmUuid, String>,
+    /// Bare model leases, and when they run out.
+    leases: HashMap<LeaseId, (LlmUuid, DateTime<Utc>)>,
+    adapters: Vec<AdapterStatus>,
+    /// Connector secrets, by connector name.
+    connector_secrets: HashMap<String, String>,
+    last_prompt: Option<Vec<PromptInput>>,
+    last_transcription: Option<MockTranscription>,
+    last_content_type: Option<String>,
+    subscribers: Vec<mpsc::UnboundedSender<ServerEvent>>,
+}
+
+/// A failed call, answered the way Pantry reports errors.
+struct MockError(StatusCode, &'static str, String);
+
+impl MockError {
+    fn bad_request(msg: impl Into<String>) -> Self {
+        MockError(StatusCode::BAD_REQUEST, "bad_request", msg.into())
+    }
+
+    fn llm_not_found(llm_id: &str) -> Self {
+        MockError(
+            StatusCode::NOT_FOUND,
+            "llm_not_found",
+            format!("no LLM {}", llm_id),
+        )
+    }
+
+    fn not_found(msg: impl Into<String>) -> Self {
+        MockError(StatusCode::NOT_FOUND, "not_found", msg.into())
+    }
+}
+
+type Reply = Result<Response<Body>, MockError>;
+
+impl MockState {
+    fn new() -> Self {
+        MockState {
+            users: HashMap::new(),
+            requests: Vec::new(),
+            llms: Vec::new(),
+            sessions: HashMap::new(),
+            reply: DEFAULT_REPLY.iter().map(|t| t.to_string()).collect(),
+            queued_replies: VecDeque::new(),
+            token_delay: Duration::ZERO,
+            drop_stream_after: None,
+            failing_prompts: 0,
+            ndjson: true,
+            auto_approve: true,
+            calls: Vec::new(),
+            required_headers: Vec::new(),
+            model_dirs: HashMap::new(),
+            leases: HashMap::new(),
+            adapters: Vec::new(),
+            connector_secrets: HashMap::new(),
+            last_prompt: None,
+            last_transcription: None,
+            last_content_type: None,
+            subscribers: Vec::new(),
+        }
+    }
+
+    fn add_user(&mut self, name: String, permissions: UserPermissions) -> (UserId, String) {
+        let user_id = UserId::new_v4();
+        let api_key = Uuid::new_v4().simple().to_string();
+        self.users.insert(
+            user_id,
+            MockUser {
+                name,
+                api_key: api_key.clone(),
+                permissions,
+                last_active: None,
+            },
+        );
+        (user_id, api_key)
+    }
+
+    fn user_info(&self, user_id: UserId) -> UserInfo {
+        let user = &self.users[&user_id];
+        let perms = &user.permissions;
+        UserInfo {
+            id: user_id,
+            name: user.name.clone(),
+            api_key: user.api_key.clone(),
+            perm_superuser: perms.perm_superuser,
+            perm_load_llm: perms.perm_load_llm,
+            perm_unload_llm: perms.perm_unload_llm,
+            perm_download_llm: perms.perm_download_llm,
+            perm_session: perms.perm_session,
+            perm_request_download: perms.perm_request_download,
+            perm_request_load: perms.perm_request_load,
+            perm_request_unload: perms.perm_request_unload,
+            perm_view_llms: perms.perm_view_llms,
+            perm_bare_model: perms.perm_bare_model,
+            expires_at: perms.expires_at,
+            llm_scope: perms.llm_scope.clone(),
+        }
+    }
+
+    fn user_status(&self, user_id: UserId) -> UserStatus {
+        let user = &self.users[&user_id];
+        UserStatus {
+            id: user_id,
+            name: user.name.clone(),
+            permissions: user.permissions.clone(),
+            last_active: user.last_active,
+        }
+    }
+
+    /// The caller's id, if their credentials check out and they have the permission
+    /// `allowed` looks for. Superusers can do anything.
+    fn auth(
+        &mut self,
+        body: &Value,
+        allowed: impl Fn(&UserPermissions) -> bool,
+    ) -> Result<UserId, MockError> {
+        let invalid = || {
+            MockError(
+                StatusCode::UNAUTHORIZED,
+                "invalid_api_key",
+                "unknown user or API key".into(),
+            )
+        };
+        let user_id: UserId = field(body, "user_id").map_err(|_| invalid())?;
+        let api_key: String = field(body, "api_key").map_err(|_| invalid())?;
+        let user = self.users.get_mut(&user_id).ok_or_else(invalid)?;
+        if user.api_key != api_key {
+            return Err(invalid());
+        }
+        user.last_active = Some(Utc::now());
+        let permissions = user.permissions.in_effect();
+        if !permissions.perm_superuser && !allowed(&permissions) {
+            return Err(MockError(
+                StatusCode::FORBIDDEN,
+                "permission_denied",
+                "missing permission".into(),
+            ));
+        }
+        Ok(user_id)
+    }
+
+    fn llm_index(&self, llm_id: &str) -> Result<usize, MockError> {
+        self.llms
+            .iter()
+            .position(|llm| llm.uuid.to_string() == llm_id || llm.id == llm_id)
+            .ok_or_else(|| MockError::llm_not_found(llm_id))
+    }
+
+    fn llm(&self, llm_id: &str) -> Result<&LLMStatus, MockError> {
+        Ok(&self.llms[self.llm_index(llm_id)?])
+    }
+
+    fn running_llm(&self, llm_id: &str) -> Result<&LLMStatus, MockError> {
+        let llm = self.llm(llm_id)?;
+        if !llm.running {
+            return Err(MockError(
+                StatusCode::CONFLICT,
+                "llm_not_running",
+                format!("LLM {} isn't running", llm_id),
+            ));
+        }
+        Ok(llm)
+    }
+
+    /// Picks an LLM like the `_flex` calls do, among running ones if `running`.
+    fn select(&self, body: &Value, running: bool) -> Result<String, MockError> {
+        let filter: Option<LLMFilter> = field(body, "filter").unwrap_or(None);
+        let preference: Option<LLMPreference> = field(body, "preference").unwrap_or(None);
+        let llms: Vec<LLMStatus> = self
+            .llms
+            .iter()
+            .filter(|llm| llm.running || !running)
+            .cloned()
+            .collect();
+        select_llm(&llms, filter.as_ref(), preference.as_ref())
+            .map(|llm| llm.uuid.to_string())
+            .ok_or_else(|| MockError::llm_not_found("matching the filter"))
+    }
+
+    fn set_running(&mut self, llm_id: &str, running: bool) -> Result<LLMStatus, MockError> {
+        let index = self.llm_index(llm_id)?;
+        let llm = &mut self.llms[index];
+        if llm.running != running {
+            llm.running = running;
+            // Adapters don't survive unloading.
+            llm.applied_adapters.clear();
+            let llm_uuid = llm.uuid;
+            for adapter in self.adapters.iter_mut() {
+                if adapter.llm_uuid == llm_uuid {
+                    adapter.applied = false;
+                }
+            }
+            let llm_uuid = llm.uuid;
+            self.broadcast(match running {
+                true => ServerEvent::LLMLoaded { llm_uuid },
+                false => ServerEvent::LLMUnloaded { llm_uuid },
+            });
+        }
+        Ok(self.llms[index].clone())
+    }
+
+    /// "Downloads" `entry`, which finishes right away.
+    fn download(&mut self, entry: LLMRegistryEntry, target_dir: Option<String>) -> LLMStatus {
+        let offered = entry.adapters.clone();
+        let llm = status_from_entry(entry);
+        let llm_uuid = llm.uuid;
+        self.adapters
+            .extend(offered.into_iter().map(|adapter| AdapterStatus {
+                llm_uuid,
+                adapter,
+                downloaded: false,
+                applied: false,
+            }));
+        if let Some(dir) = target_dir {
+            self.model_dirs.insert(llm_uuid, dir);
+        }
+        self.llms.push(llm.clone());
+        self.broadcast(ServerEvent::DownloadFinished { llm_uuid });
+        llm
+    }
+
+    fn delete(&mut self, llm_id: &str) -> Result<LLMStatus, MockError> {
+        let index = self.llm_index(llm_id)?;
+        let uuid = self.llms[index].uuid;
+        let now = Utc::now();
+        self.leases.retain(|_, (_, expires_at)| *expires_at > now);
+        if self.leases.values().any(|(leased, _)| *leased == uuid) {
+            return Err(MockError(
+                StatusCode::CONFLICT,
+                "llm_in_use",
+                format!("{} is leased as a bare model", llm_id),
+            ));
+        }
+        let mut llm = self.llms.remove(index);
+        llm.running = false;
+        Ok(llm)
+    }
+
+    /// Files a request, deciding it right away with auto-approval on.
+    fn submit(&mut self, user_id: UserId, request: UserRequestType) -> Reply {
+        let id = RequestId::new_v4();
+        self.requests.push(UserRequestStatus {
+            id,
+            user_id,
+            timestamp: Utc::now(),
+            request,
+            accepted: false,
+            complete: false,
+        });
+        if self.auto_approve {
+            self.decide(id, true);
+        }
+        self.request_reply(id)
+    }
+
+    fn request_reply(&self, request_id: RequestId) -> Reply {
+        match self.requests.iter().find(|r| r.id == request_id) {
+            Some(request) => Ok(json_response(request)),
+            None => Err(MockError::not_found(format!("no request {}", request_id))),
+        }
+    }
+
+    /// Accepts or rejects a pending request, carrying it out if accepted.
+    fn decide(&mut self, request_id: RequestId, accept: bool) -> Option<()> {
+        let index = self
+            .requests
+            .iter()
+            .position(|r| r.id == request_id && !r.complete)?;
+        let merged = match &self.requests[index].request {
+            UserRequestType::PermissionRequest(PermissionRequest {
+                requested_permissions,
+                duration_secs,
+            }) if accept => {
+                let mut requested = requested_permissions.clone();
+                requested.expires_at =
+                    duration_secs.map(|secs| Utc::now() + chrono::Duration::seconds(secs as i64));
+                let granted = self.users.get(&self.requests[index].user_id);
+                let granted = granted.map(|user| user.permissions.in_effect());
+                granted.unwrap_or_default().union(&requested)
+            }
+            _ => None,
+        };
+        // Like Pantry, grants that can't be merged without widening them are refused.
+        let accept = accept
+            && (merged.is_some()
+                || !matches!(
+                    self.requests[index].request,
+                    UserRequestType::PermissionRequest(_)
+                ));
+        let request = &mut self.requests[index];
+        request.accepted = accept;
+        request.complete = true;
+        let user_id = request.user_id;
+        let action = request.request.clone();
+        if accept {
+            // Requests for LLMs that have gone away in the meantime just do nothing.
+            let _ = match action {
+                UserRequestType::PermissionRequest(_) => {
+                    if let (Some(user), Some(merged)) = (self.users.get_mut(&user_id), merged) {
+                        user.permissions = merged;
+                    }
+                    Ok(())
+                }
+                UserRequestType::DownloadRequest(DownloadRequest {
+                    llm_registry_entry,
+                    target_dir,
+                }) => {
+                    self.download(llm_registry_entry, target_dir);
+                    Ok(())
+                }
+                UserRequestType::RegisterLocalRequest(RegisterLocalRequest {
+                    llm_registry_entry,
+                    ..
+                }) => {
+                    self.download(llm_registry_entry, None);
+                    Ok(())
+                }
+                UserRequestType::LoadRequest(LoadRequest { llm_id }) => {
+                    self.set_running(&llm_id, true).map(drop)
+                }
+                UserRequestType::UnloadRequest(UnloadRequest { llm_id }) => {
+                    self.set_running(&llm_id, false).map(drop)
+                }
+                UserRequestType::DeleteRequest(DeleteRequest { llm_id }) => {
+                    self.delete(&llm_id).map(drop)
+                }
+            };
+        }
+        self.broadcast(match accept {
+            true => ServerEvent::RequestAccepted { request_id },
+            false => ServerEvent::RequestDenied { request_id },
+        });
+        Some(())
+    }
+
+    fn broadcast(&mut self, event: ServerEvent) {
+        self.subscribers
+            .retain(|subscriber| subscriber.unbounded_send(event.clone()).is_ok());
+    }
+
+    fn create_session(
+        &mut self,
+        user_id: UserId,
+        llm_id: &str,
+        user_session_parameters: HashMap<String, Value>,
+    ) -> Result<SessionId, MockError> {
+        let llm = self.running_llm(llm_id)?.clone();
+        let mut session_parameters = llm.session_parameters.clone();
+        session_parameters.extend(user_session_parameters);
+        let session_id = SessionId::new_v4();
+        let now = Utc::now();
+        self.sessions.insert(
+            session_id,
+            MockSession {
+                user_id,
+                llm_uuid: llm.uuid,
+                started: now,
+                last_called: now,
+                session_parameters,
+                history: Arc::new(Mutex::new(Vec::new())),
+                interrupted: Arc::new(AtomicBool::new(false)),
+            },
+        );
+        Ok(session_id)
+    }
+
+    fn session(
+        &self,
+        user_id: UserId,
+        body: &Value,
+    ) -> Result<(SessionId, &MockSession), MockError> {
+        let session_id: SessionId = field(body, "session_id")?;
+        match self.sessions.get(&session_id) {
+            Some(session) if session.user_id == user_id => Ok((session_id, session)),
+            _ => Err(MockError::not_found(format!("no session {}", session_id))),
+        }
+    }
+
+    fn session_reply(&self, session_id: SessionId) -> Reply {
+        let session = &self.sessions[&session_id];
+        let llm = self.llm(&session.llm_uuid.to_string())?.clone();
+        Ok(json_response(&CreateSessionResponse {
+            session_parameters: session.session_parameters.clone(),
+            llm_status: llm,
+            session_id,
+        }))
+    }
+
+    /// Answers a call, with streams in `format`.
+    fn handle(&mut self, endpoint: &str, body: Value, format: StreamFormat) -> Reply {
+        let any = |_: &UserPermissions| true;
+        let superuser = |_: &UserPermissions| false;
+        match endpoint {
+            "server_info" => Ok(json_response(&ServerInfo {
+                version: env!("CARGO_PKG_VERSION").into(),
+                protocol_version: PROTOCOL_VERSION,
+                endpoints: ENDPOINTS.iter().map(|e| e.to_string()).collect(),
+            })),
+            "register_user" => {
+                let name: String = field(&body, "user_name")?;
+                let (user_id, _) = self.add_user(name, UserPermissions::default());
+                Ok(json_response(&self.user_info(user_id)))
+            }
+            "request_permissions" => {
+                let user_id = self.auth(&body, any)?;
+                let requested_permissions = field(&body, "requested_permissions")?;
+                let duration_secs = field(&body, "duration_secs")?;
+                self.submit(
+                    user_id,
+                    UserRequestType::PermissionRequest(PermissionRequest {
+                        requested_permissions,
+                        duration_secs,
+                    }),
+                )
+            }
+            "request_download" => {
+                let user_id = self.auth(&body, |p| p.perm_request_download)?;
+                let llm_registry_entry = registry_entry_field(&body)?;
+                let target_dir = field(&body, "target_dir")?;
+                self.submit(
+                    user_id,
+                    UserRequestType::DownloadRequest(DownloadRequest {
+                        llm_registry_entry,
+                        target_dir,
+                    }),
+                )
+            }
+            "request_register_local" => {
+                let user_id = self.auth(&body, |p| p.perm_request_download)?;
+                let request = RegisterLocalRequest {
+                    path: field(&body, "path")?,
+                    llm_registry_entry: field(&body, "llm_registry_entry")?,
+                };
+                self.submit(user_id, UserRequestType::RegisterLocalRequest(request))
+            }
+            "request_load" | "request_load_flex" => {
+                let user_id = self.auth(&body, |p| p.perm_request_load)?;
+                let llm_id = match endpoint {
+                    "request_load" => self
+                        .llm(&field::<String>(&body, "llm_id")?)?
+                        .uuid
+                        .to_string(),
+                    _ => self.select(&body, false)?,
+                };
+                self.submit(
+                    user_id,
+                    UserRequestType::LoadRequest(LoadRequest { llm_id }),
+                )
+            }
+            "request_unload" => {
+                let user_id = self.auth(&body, |p| p.perm_request_unload)?;
+                let llm_id = self
+                    .llm(&field::<String>(&body, "llm_id")?)?
+                    .uuid
+                    .to_string();
+                self.submit(
+                    user_id,
+                    UserRequestType::UnloadRequest(UnloadRequest { llm_id }),
+                )
+            }
+            "request_delete" => {
+                let user_id = self.auth(&body, |p| p.perm_request_download)?;
+                let llm_id = self
+                    .llm(&field::<String>(&body, "llm_id")?)?
+                    .uuid
+                    .to_string();
+                self.submit(
+                    user_id,
+                    UserRequestType::DeleteRequest(DeleteRequest { llm_id }),
+                )
+            }
+            "get_request_status" | "cancel_request" => {
+                let user_id = self.auth(&body, any)?;
+                let request_id: RequestId = field(&body, "request_id")?;
+                let request = self
+                    .requests
+                    .iter_mut()
+                    .find(|r| r.id == request_id && r.user_id == user_id)
+                    .ok_or_else(|| MockError::not_found(format!("no request {}", request_id)))?;
+                if endpoint == "cancel_request" && !request.complete {
+                    request.complete = true;
+                }
+                self.request_reply(request_id)
+            }
+            "get_pending_requests" | "list_pending_requests" => {
+                let user_id = match endpoint {
+                    "get_pending_requests" => Some(self.auth(&body, any)?),
+                    _ => {
+                        self.auth(&body, superuser)?;
+                        None
+                    }
+                };
+                let pending: Vec<&UserRequestStatus> = self
+                    .requests
+                    .iter()
+                    .filter(|r| !r.complete && user_id.is_none_or(|id| r.user_id == id))
+                    .collect();
+                Ok(json_response(&pending))
+            }
+            "approve_request" | "deny_request" => {
+                self.auth(&body, superuser)?;
+                let request_id: RequestId = field(&body, "request_id")?;
+                self.decide(request_id, endpoint == "approve_request")
+                    .ok_or_else(|| {
+                        MockError::not_found(format!("no pending request {}", request_id))
+                    })?;
+                self.request_reply(request_id)
+            }
+            "get_permissions" => {
+                let user_id = self.auth(&body, any)?;
+                Ok(json_response(&self.users[&user_id].permissions))
+            }
+            "get_user_info" => {
+                let user_id = self.auth(&body, any)?;
+                Ok(json_response(&self.user_info(user_id)))
+            }
+            "list_users" => {
+                self.auth(&body, superuser)?;
+                let users: Vec<UserStatus> =
+                    self.users.keys().map(|id| self.user_status(*id)).collect();
+                Ok(json_response(&users))
+            }
+            "get_user" | "revoke_api_key" => {
+                self.auth(&body, superuser)?;
+                let target: UserId = field(&body, "target_user_id")?;
+                if !self.users.contains_key(&target) {
+                    return Err(MockError::not_found(format!("no user {}", target)));
+                }
+                if endpoint == "get_user" {
+                    return Ok(json_response(&self.user_status(target)));
+                }
+                self.users.get_mut(&target).unwrap().api_key = Uuid::new_v4().simple().to_string();
+                Ok(json_response(&Value::Null))
+            }
+            "get_llm_status" => {
+                self.auth(&body, |p| p.perm_view_llms)?;
+                Ok(json_response(self.llm(&field::<String>(&body, "llm_id")?)?))
+            }
+            "get_running_llms" | "get_available_llms" => {
+                self.auth(&body, |p| p.perm_view_llms)?;
+                let llms: Vec<&LLMStatus> = self
+                    .llms
+                    .iter()
+                    .filter(|llm| llm.running || endpoint == "get_available_llms")
+                    .collect();
+                Ok(json_response(&llms))
+            }
+            "get_system_status" => {
+                self.auth(&body, |p| p.perm_view_llms)?;
+                let llms: Vec<LLMResourceUsage> = self
+                    .llms
+                    .iter()
+                    .filter(|llm| llm.running)
+                    .map(|llm| LLMResourceUsage {
+                        llm_uuid: llm.uuid,
+                        ram_bytes: llm.ram_bytes().unwrap_or_default(),
+                        vram_bytes: None,
+                    })
+                    .collect();
+                Ok(json_response(&SystemStatus {
+                    ram_total_bytes: MOCK_RAM_BYTES,
+                    ram_used_bytes: llms.iter().map(|llm| llm.ram_bytes).sum(),
+                    vram_total_bytes: None,
+                    vram_used_bytes: None,
+                    disk_total_bytes: MOCK_DISK_BYTES,
+                    disk_free_bytes: MOCK_DISK_BYTES,
+                    llms,
+                }))
+            }
+            "get_storage_info" => {
+                self.auth(&body, |p| p.perm_view_llms)?;
+                let models: Vec<ModelStorage> = self
+                    .llms
+                    .iter()
+                    .map(|llm| ModelStorage {
+                        llm_uuid: llm.uuid,
+                        id: llm.id.clone(),
+                        size_bytes: llm.download_total.unwrap_or_default(),
+                        path: format!(
+                            "{}/{}.bin",
+                            self.model_dirs
+                                .get(&llm.uuid)
+                                .map_or("/mock/models", String::as_str),
+                            llm.uuid
+                        ),
+                    })
+                    .collect();
+                Ok(json_response(&StorageInfo {
+                    total_bytes: models.iter().map(|model| model.size_bytes).sum(),
+                    models,
+                }))
+            }
+            "load_llm" | "load_llm_flex" => {
+                self.auth(&body, |p| p.perm_load_llm)?;
+                let llm_id = match endpoint {
+                    "load_llm" => field(&body, "llm_id")?,
+                    _ => self.select(&body, false)?,
+                };
+                let config: HashMap<String, Value> = field(&body, "config").unwrap_or_default();
+                let mut llm = self.set_running(&llm_id, true)?;
+                llm.config.extend(config);
+                Ok(json_response(&LLMRunningStatus {
+                    uuid: llm.uuid,
+                    llm_info: llm,
+                }))
+            }
+            "unload_llm" => {
+                self.auth(&body, |p| p.perm_unload_llm)?;
+                let llm = self.set_running(&field::<String>(&body, "llm_id")?, false)?;
+                Ok(json_response(&llm))
+            }
+            "download_llm" | "get_or_download_llm" => {
+                self.auth(&body, |p| p.perm_download_llm)?;
+                let entry: LLMRegistryEntry = field(&body, "llm_registry_entry")?;
+                let target_dir = field(&body, "target_dir")?;
+                let existing = self.llms.iter().find(|llm| llm.id == entry.id);
+                let uuid = match existing {
+                    Some(llm) if endpoint == "get_or_download_llm" => llm.uuid,
+                    _ => self.download(entry, target_dir).uuid,
+                };
+                Ok(json_response(&uuid))
+            }
+            "register_local_model" => {
+                self.auth(&body, |p| p.perm_download_llm)?;
+                let llm = self.download(field(&body, "llm_registry_entry")?, None);
+                Ok(json_response(&llm))
+            }
+            "delete_llm" => {
+                self.auth(&body, |p| p.perm_download_llm)?;
+                let llm = self.delete(&field::<String>(&body, "llm_id")?)?;
+                Ok(json_response(&llm))
+            }
+            "bare_model" | "bare_model_flex" => {
+                self.auth(&body, |p| p.perm_bare_model)?;
+                let llm_id = match endpoint {
+                    "bare_model" => field(&body, "llm_id")?,
+                    _ => self.select(&body, false)?,
+                };
+                let model = self.llm(&llm_id)?.clone();
+                Ok(json_response(&BareModelResponse {
+                    path: format!("/tmp/pantry-mock/{}.bin", model.id),
+                    model,
+                }))
+            }
+            "export_bare_model" => {
+                self.auth(&body, |p| p.perm_bare_model)?;
+                let model = self.llm(&field::<String>(&body, "llm_id")?)?.clone();
+                let _mode: ExportMode = field(&body, "mode")?;
+                let mut destination =
+                    std::path::PathBuf::from(field::<String>(&body, "destination")?);
+                if destination.is_dir() {
+                    destination.push(format!("{}.bin", model.id));
+                }
+                // There's no model file to copy or link to, so whatever the mode, the
+                // export is a stand-in.
+                std::fs::write(&destination, format!("mock model {}", model.id))
+                    .map_err(|e| MockError::bad_request(e.to_string()))?;
+                Ok(json_response(&BareModelResponse {
+                    path: destination.to_string_lossy().into_owned(),
+                    model,
+                }))
+            }
+            "bare_model_lease" => {
+                self.auth(&body, |p| p.perm_bare_model)?;
+                let model = self.llm(&field::<String>(&body, "llm_id")?)?.clone();
+                let ttl_secs: i64 = field(&body, "ttl_secs")?;
+                let lease_id = LeaseId::new_v4();
+                let expires_at = Utc::now() + chrono::Duration::seconds(ttl_secs);
+                self.leases.insert(lease_id, (model.uuid, expires_at));
+                Ok(json_response(&BareModelLeaseResponse {
+                    lease_id,
+                    path: format!("/tmp/pantry-mock/{}.bin", model.id),
+                    model,
+                    expires_at,
+                }))
+            }
+            "release_bare_model" => {
+                self.auth(&body, |p| p.perm_bare_model)?;
+                self.leases.remove(&field(&body, "lease_id")?);
+                Ok(json_response(&json!({})))
+            }
+            "list_adapters" => {
+                self.auth(&body, |p| p.perm_view_llms)?;
+                let llm_uuid = self.llm(&field::<String>(&body, "llm_id")?)?.uuid;
+                let adapters: Vec<&AdapterStatus> = self
+                    .adapters
+                    .iter()
+                    .filter(|a| a.llm_uuid == llm_uuid)
+                    .collect();
+                Ok(json_response(&adapters))
+            }
+            "download_adapter" => {
+                self.auth(&body, |p| p.perm_download_llm)?;
+                let llm_uuid = self.llm(&field::<String>(&body, "llm_id")?)?.uuid;
+                let adapter: AdapterEntry = field(&body, "adapter")?;
+                self.adapters
+                    .retain(|a| !(a.llm_uuid == llm_uuid && a.adapter.id == adapter.id));
+                let status = AdapterStatus {
+                    llm_uuid,
+                    adapter,
+                    downloaded: true,
+                    applied: false,
+                };
+                self.adapters.push(status.clone());
+                Ok(json_response(&status))
+            }
+            "apply_adapter" | "remove_adapter" => {
+                self.auth(&body, |p| p.perm_load_llm)?;
+                let llm_id: String = field(&body, "llm_uuid")?;
+                let llm_uuid = self.running_llm(&llm_id)?.uuid;
+                let adapter_id: String = field(&body, "adapter_id")?;
+                let apply = endpoint == "apply_adapter";
+                let adapter = self
+                    .adapters
+                    .iter_mut()
+                    .find(|a| a.llm_uuid == llm_uuid && a.adapter.id == adapter_id)
+                    .ok_or_else(|| MockError::not_found(format!("no adapter {}", adapter_id)))?;
+                if apply && !adapter.downloaded {
+                    return Err(MockError::bad_request(format!(
+                        "adapter {} isn't downloaded",
+                        adapter_id
+                    )));
+                }
+                adapter.applied = apply;
+                let index = self.llm_index(&llm_id)?;
+                let llm = &mut self.llms[index];
+                llm.applied_adapters.retain(|id| *id != adapter_id);
+                if apply {
+                    llm.applied_adapters.push(adapter_id);
+                }
+                Ok(json_response(&self.llms[index]))
+            }
+            "set_connector_secret" => {
+                self.auth(&body, |p| p.perm_download_llm)?;
+                let connector: LLMConnectorType = field(&body, "connector")?;
+                let secret: String = field(&body, "secret")?;
+                self.connector_secrets.insert(connector.to_string(), secret);
+                Ok(json_response(&json!({})))
+            }
+            "create_session" | "create_session_id" | "create_session_flex" => {
+                let user_id = self.auth(&body, |p| p.perm_session)?;
+                let llm_id = match endpoint {
+                    "create_session_id" => field(&body, "llm_id")?,
+                    _ => self.select(&body, true)?,
+                };
+                let permissions = self.users[&user_id].permissions.in_effect();
+                if !permissions.perm_superuser && !permissions.covers(self.llm(&llm_id)?) {
+                    return Err(MockError(
+                        StatusCode::FORBIDDEN,
+                        "permission_denied",
+                        format!("no permission for {}", llm_id),
+                    ));
+                }
+                let parameters = field(&body, "user_session_parameters").unwrap_or_default();
+                let session_id = self.create_session(user_id, &llm_id, parameters)?;
+                self.session_reply(session_id)
+            }
+            "load_session_id" => {
+                let user_id = self.auth(&body, |p| p.perm_session)?;
+                let (session_id, session) = self.session(user_id, &body)?;
+                self.running_llm(&session.llm_uuid.to_string())?;
+                self.session_reply(session_id)
+            }
+            "fork_session" => {
+                let user_id = self.auth(&body, |p| p.perm_session)?;
+                let (_, session) = self.session(user_id, &body)?;
+                let llm_id = session.llm_uuid.to_string();
+                let parameters = session.session_parameters.clone();
+                let history = session.history.lock().unwrap().clone();
+                let fork_id = self.create_session(user_id, &llm_id, parameters)?;
+                *self.sessions[&fork_id].history.lock().unwrap() = history;
+                self.session_reply(fork_id)
+            }
+            "get_session_history" => {
+                let user_id = self.auth(&body, |p| p.perm_session)?;
+                let (_, session) = self.session(user_id, &body)?;
+                let history = session.history.lock().unwrap().clone();
+                Ok(json_response(&history))
+            }
+            "get_sessions" => {
+                let user_id = self.auth(&body, |p| p.perm_session)?;
+                let sessions: Vec<LLMSessionStatus> = self
+                    .sessions
+                    .iter()
+                    .filter(|(_, session)| session.user_id == user_id)
+                    .map(|(id, session)| session.status(*id))
+                    .collect();
+                Ok(json_response(&sessions))
+            }
+            "delete_session" => {
+                let user_id = self.auth(&body, |p| p.perm_session)?;
+                let (session_id, _) = self.session(user_id, &body)?;
+                self.sessions.remove(&session_id);
+                Ok(json_response(&Value::Null))
+            }
+            "interrupt_session" => {
+                let user_id = self.auth(&body, |p| p.perm_session)?;
+                let (_, session) = self.session(user_id, &body)?;
+                session.interrupted.store(true, Ordering::SeqCst);
+                let llm = self.llm(&session.llm_uuid.to_string())?.clone();
+                Ok(json_response(&LLMRunningStatus {
+                    uuid: llm.uuid,
+                    llm_info: llm,
+                }))
+            }
+            "prompt_session_stream" => {
+                let user_id = self.auth(&body, |p| p.perm_session)?;
+                let prompt: String = field(&body, "prompt")?;
+                let parameters = field(&body, "parameters").unwrap_or_default();
+                let resume: Option<Uuid> = field(&body, "resume_stream_id")?;
+                let (session_id, _) = self.session(user_id, &body)?;
+                let session = self.sessions.get_mut(&session_id).unwrap();
+                session.last_called = Utc::now();
+                let resume = match resume {
+                    Some(stream_id) => {
+                        if !session
+                            .history
+                            .lock()
+                            .unwrap()
+                            .iter()
+                            .any(|item| item.id == stream_id)
+                        {
+                            return Err(MockError::not_found(format!("no stream {}", stream_id)));
+                        }
+                        // Event ids count from 0, so the next one is the first not seen.
+                        let last: Option<String> = field(&body, "last_event_id")?;
+                        let sent = match last.map(|id| id.parse::<usize>()) {
+                            Some(Ok(id)) => id + 1,
+                            Some(Err(e)) => return Err(MockError::bad_request(e.to_string())),
+                            None => 0,
+                        };
+                        Some((stream_id, sent))
+                    }
+                    None => {
+                        session.interrupted.store(false, Ordering::SeqCst);
+                        None
+                    }
+                };
+                if resume.is_none() {
+                    self.last_prompt = Some(prompt_inputs(&prompt, &body)?);
+                }
+                let session = &self.sessions[&session_id];
+                self.running_llm(&session.llm_uuid.to_string())?;
+                // Queued replies are for fresh prompts, resuming replays the standing one.
+                let queued = match resume {
+                    Some(_) => None,
+                    None => self.queued_replies.pop_front(),
+                };
+                let failure = (resume.is_none() && self.failing_prompts > 0).then(|| {
+                    self.failing_prompts -= 1;
+                    "out of memory".to_string()
+                });
+                let tokens = match failure {
+                    Some(_) => Vec::new(),
+                    None => queued.unwrap_or_else(|| self.reply.clone()),
+                };
+                let inference = Inference {
+                    tokens,
+                    failure,
+                    delay: self.token_delay,
+                    status: session.status(session_id),
+                    history: session.history.clone(),
+                    interrupted: session.interrupted.clone(),
+                    prompt,
+                    parameters,
+                    resume,
+                };
+                Ok(resumable_stream_response(
+                    inference.events(),
+                    format,
+                    self.drop_stream_after.take(),
+                ))
+            }
+            "transcribe_stream" => {
+                let user_id = self.auth(&body, |p| p.perm_session)?;
+                let audio: MockAttachment = field(&body, "audio")?;
+                let parameters: HashMap<String, Value> =
+                    field(&body, "parameters").unwrap_or_default();
+                let (session_id, _) = self.session(user_id, &body)?;
+                let session = self.sessions.get_mut(&session_id).unwrap();
+                session.last_called = Utc::now();
+                session.interrupted.store(false, Ordering::SeqCst);
+                let session = &self.sessions[&session_id];
+                let llm = self.running_llm(&session.llm_uuid.to_string())?;
+                if !llm.transcribes_audio() {
+                    return Err(MockError::bad_request(format!(
+                        "{} doesn't transcribe audio",
+                        llm.id
+                    )));
+                }
+                self.last_transcription = Some(MockTranscription {
+                    audio: audio.bytes()?,
+                    mime: audio.mime,
+                    parameters: parameters.clone(),
+                });
+                let inference = Inference {
+                    tokens: self
+                        .queued_replies
+                        .pop_front()
+                        .unwrap_or_else(|| self.reply.clone()),
+                    failure: None,
+                    delay: self.token_delay,
+                    status: session.status(session_id),
+                    history: session.history.clone(),
+                    interrupted: session.interrupted.clone(),
+                    prompt: String::new(),
+                    parameters,
+                    resume: None,
+                };
+                Ok(resumable_stream_response(inference.events(), format, None))
+            }
+            "subscribe_events" => {
+                self.auth(&body, any)?;
+                let (sender, receiver) = mpsc::unbounded();
+                self.subscribers.push(sender);
+                Ok(event_stream_response(receiver, format))
+            }
+            _ => Err(MockError::not_found(format!("no endpoint /{}", endpoint))),
+        }
+    }
+}
+
+/// A prompt being answered with the canned reply.
+struct Inference {
+    tokens: Vec<String>,
+    /// Error to end with instead of a completion.
+    failure: Option<String>,
+    delay: Duration,
+    status: LLMSessionStatus,
+    history: Arc<Mutex<Vec<LLMHistoryItem>>>,
+    interrupted: Arc<AtomicBool>,
+    prompt: String,
+    parameters: HashMap<String, Value>,
+    /// Stream being resumed and how many of its events were already sent.
+    resume: Option<(Uuid, usize)>,
+}
+
+impl Inference {
+    /// Progress events for each token, then a completion, numbered from 0. Stops
+    /// without a completion when interrupted, like Pantry does.
+    fn events(self) -> impl Stream<Item = (usize, LLMEvent)> + Send {
+        let (stream_id, sent, call_timestamp) = match self.resume {
+            Some((stream_id, sent)) => {
+                let history = self.history.lock().unwrap();
+                let item = history.iter().find(|item| item.id == stream_id);
+                let call_timestamp = item.map_or_else(Utc::now, |item| item.call_timestamp);
+                (stream_id, sent, call_timestamp)
+            }
+            None => {
+                let call_timestamp = Utc::now();
+                let stream_id = Uuid::new_v4();
+                let item = LLMHistoryItem {
+                    id: stream_id,
+                    llm_uuid: self.status.llm_uuid,
+                    session_id: self.status.id,
+                    call_timestamp,
+                    updated_timestamp: call_timestamp,
+                    complete: false,
+                    parameters: self.parameters.clone(),
+                    input: self.prompt.clone(),
+                    output: String::new(),
+                };
+                self.history.lock().unwrap().push(item);
+                (stream_id, 0, call_timestamp)
+            }
+        };
+
+        let event = move |previous: &str, event: LLMEventInternal| LLMEvent {
+            stream_id,
+            timestamp: Utc::now(),
+            call_timestamp,
+            parameters: self.parameters.clone(),
+            input: self.prompt.clone(),
+            llm_uuid: self.status.llm_uuid,
+            session: self.status.clone(),
+            event: match event {
+                LLMEventInternal::PromptProgress { next, .. } => LLMEventInternal::PromptProgress {
+                    previous: previous.to_string(),
+                    next,
+                },
+                other => other,
+            },
+        };
+        let history = self.history;
+        let failure = self.failure;
+        let interrupted = self.interrupted;
+        let delay = self.delay;
+        let count = self.tokens.len();
+        let sent = sent.min(count + 1);
+        let text: String = self.tokens[..sent.min(count)].concat();
+        let tokens = self.tokens.into_iter().map(Some).chain([None]).skip(sent);
+
+        stream::iter(tokens)
+            .scan(text, move |text, token| {
+                let previous = text.clone();
+                let done = token.is_none();
+                if let Some(token) = &token {
+                    text.push_str(token);
+                }
+                let record = |complete: bool, output: &str| {
+                    if let Some(item) = history
+                        .lock()
+                        .unwrap()
+                        .iter_mut()
+                        .find(|item| item.id == stream_id)
+                    {
+                        item.output = output.to_string();
+                        item.complete = complete;
+                        item.updated_timestamp = Utc::now();
+                    }
+                };
+                record(done, text);
+                let out = match token {
+                    Some(next) => event(
+                        &previous,
+                        LLMEventInternal::PromptProgress {
+                            previous: String::new(),
+                            next,
+                        },
+                    ),
+                    None => match &failure {
+                        Some(message) => event(
+                            &previous,
+                            LLMEventInternal::PromptError {
+                                message: message.clone(),
+                            },
+                        ),
+                        None => event(
+                            &previous,
+                            LLMEventInternal::PromptCompletion {
+                                previous: previous.clone(),
+                                finish_reason: Some(FinishReason::Stop),
+                                prompt_tokens: None,
+                                completion_tokens: Some(count as u32),
+                            },
+                        ),
+                    },
+                };
+                let interrupted = interrupted.clone();
+                async move {
+                    if !delay.is_zero() {
+                        Delay::new(delay).await;
+                    }
+                    match interrupted.load(Ordering::SeqCst) {
+                        true => None,
+                        false => Some(out),
+                    }
+                }
+            })
+            .zip(stream::iter(sent..))
+            .map(|(event, id)| (id, event))
+    }
+}
+
+async fn serve(
+    state: Arc<Mutex<MockState>>,
+    req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let endpoint = req.uri().path().trim_matches('/').to_string();
+    state.lock().unwrap().calls.push(endpoint.clone());
+    if req.method() != Method::POST {
+        return Ok(error_response(MockError(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "method_not_allowed",
+            "Pantry only takes POST".into(),
+        )));
+    }
+    let missing = state
+        .lock()
+        .unwrap()
+        .required_headers
+        .iter()
+        .find(|(name, value)| {
+            req.headers()
+                .get(name.as_str())
+                .and_then(|v| v.to_str().ok())
+                != Some(value)
+        })
+        .map(|(name, _)| name.clone());
+    if let Some(name) = missing {
+        return Ok(error_response(MockError(
+            StatusCode::UNAUTHORIZED,
+            "unauthorized",
+            format!("missing or wrong {}", name),
+        )));
+    }
+    // Whichever format the client lists first, as long as the mock speaks it.
+    let prefers_ndjson = req
+        .headers()
+        .get(ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .and_then(|accept| accept.split(',').next())
+        .is_some_and(|first| first.trim().starts_with(StreamFormat::NDJSON_CONTENT_TYPE));
+    let content_type = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let boundary = content_type
+        .as_deref()
+        .filter(|value| value.starts_with("multipart/form-data"))
+        .and_then(|value| value.split_once("boundary="))
+        .map(|(_, boundary)| boundary.trim_matches('"').to_string());
+    let body = match hyper::body::to_bytes(req.into_body()).await {
+        Ok(bytes) => match boundary {
+            Some(boundary) => match read_multipart(&bytes, &boundary) {
+                Ok(body) => body,
+                Err(e) => return Ok(error_response(e)),
+            },
+            None => serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        },
+        Err(e) => return Ok(error_response(MockError::bad_request(e.to_string()))),
+    };
+    let mut state = state.lock().unwrap();
+    state.last_content_type = content_type;
+    let format = match prefers_ndjson && state.ndjson {
+        true => StreamFormat::Ndjson,
+        false => StreamFormat::Sse,
+    };
+    let reply = state.handle(&endpoint, body, format);
+    Ok(reply.unwrap_or_else(error_response))
+}
+
+/// The JSON `request` part of a multipart body, with the images of the other parts
+/// put into its `attachments` as base64, the way they'd come without multipart.
+fn read_multipart(bytes: &[u8], boundary: &str) -> Result<Value, MockError> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = HashMap::new();
+    let mut offset = 0;
+    while let Some(start) = find(bytes, delimiter.as_bytes(), offset) {
+        let headers_start = start + delimiter.len();
+        if bytes[headers_start..].starts_with(b"--") {
+            break;
+        }
+        let headers_end = find(bytes, b"\r\n\r\n", headers_start)
+            .ok_or_else(|| MockError::bad_request("multipart part without headers"))?;
+        let content_end = find(bytes, delimiter.as_bytes(), headers_end)
+            .ok_or_else(|| MockError::bad_request("unterminated multipart body"))?;
+        let headers = String::from_utf8_lossy(&bytes[headers_start..headers_end]);
+        let name = headers
+            .split("name=\"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .ok_or_else(|| MockError::bad_request("multipart part without a name"))?;
+        // Parts end with a CRLF before the next delimiter.
+        let content = &bytes[headers_end + 4..content_end];
+        let content = content.strip_suffix(b"\r\n").unwrap_or(content);
+        parts.insert(name.to_string(), content.to_vec());
+        offset = content_end;
+    }
+    let request = parts
+        .remove("request")
+        .ok_or_else(|| MockError::bad_request("multipart body without a request part"))?;
+    let mut request: Value = serde_json::from_slice(&request)
+        .map_err(|e| MockError::bad_request(format!("request: {}", e)))?;
+    let fill = |attachment: &mut Value| -> Result<(), MockError> {
+        if let Some(Value::String(name)) = attachment.get("part").cloned() {
+            let data = parts
+                .get(&name)
+                .ok_or_else(|| MockError::bad_request(format!("no part {}", name)))?;
+            attachment["data"] = Value::String(STANDARD.encode(data));
+        }
+        Ok(())
+    };
+    if let Some(Value::Array(attachments)) = request.get_mut("attachments") {
+        attachments.iter_mut().try_for_each(fill)?;
+    }
+    if let Some(audio) = request.get_mut("audio") {
+        fill(audio)?;
+    }
+    Ok(request)
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|position| from + position)
+}
+
+/// An image or recording sent along with a call.
+#[derive(serde::Deserialize)]
+struct MockAttachment {
+    mime: String,
+    #[serde(default)]
+    position: usize,
+    data: Option<String>,
+}
+
+impl MockAttachment {
+    fn bytes(&self) -> Result<Vec<u8>, MockError> {
+        let data = self
+            .data
+            .as_ref()
+            .ok_or_else(|| MockError::bad_request("attachment without data"))?;
+        STANDARD
+            .decode(data)
+            .map_err(|e| MockError::bad_request(format!("attachment: {}", e)))
+    }
+}
+
+/// A prompt's text, with the images of its `attachments` put back in place.
+fn prompt_inputs(prompt: &str, body: &Value) -> Result<Vec<PromptInput>, MockError> {
+    let mut attachments: Vec<MockAttachment> = field(body, "attachments").unwrap_or_default();
+    attachments.sort_by_key(|attachment| attachment.position);
+    let mut inputs = Vec::new();
+    let mut taken = 0;
+    for attachment in attachments {
+        let text = prompt
+            .get(taken..attachment.position)
+            .ok_or_else(|| MockError::bad_request("attachment position out of the prompt"))?;
+        if !text.is_empty() {
+            inputs.push(PromptInput::Text(text.to_string()));
+        }
+        inputs.push(PromptInput::image(attachment.bytes()?, attachment.mime));
+        taken = attachment.position;
+    }
+    if taken < prompt.len() {
+        inputs.push(PromptInput::Text(prompt[taken..].to_string()));
+    }
+    Ok(inputs)
+}
+
+/// The `llm_registry_entry` of a request body, which [crate::api::PantryAPI] sends as a
+/// JSON string for some calls.
+fn registry_entry_field(body: &Value) -> Result<LLMRegistryEntry, MockError> {
+    match body.get("llm_registry_entry") {
+        Some(Value::String(entry)) => serde_json::from_str(entry)
+            .map_err(|e| MockError::bad_request(format!("llm_registry_entry: {}", e))),
+        _ => field(body, "llm_registry_entry"),
+    }
+}
+
+/// A field of a request body.
+fn field<T: DeserializeOwned>(body: &Value, name: &str) -> Result<T, MockError> {
+    serde_json::from_value(body.get(name).cloned().unwrap_or(Value::Null))
+        .map_err(|e| MockError::bad_request(format!("{}: {}", name, e)))
+}
+
+/// What the LLM would look like once `entry` has been downloaded.
+fn status_from_entry(entry: LLMRegistryEntry) -> LLMStatus {
+    LLMStatus {
+        id: entry.id,
+        family_id: entry.family_id,
+        organization: entry.organization,
+        name: entry.name,
+        homepage: entry.homepage,
+        license: entry.license,
+        description: entry.description,
+        capabilities: entry.capabilities,
+        requirements: entry.requirements,
+        tags: entry.tags,
+        url: entry.url,
+        local: entry.local,
+        connector_type: entry.connector_type.to_string().to_lowercase(),
+        download_progress: 100.0,
+        download_bytes: entry.size_bytes,
+        download_total: entry.size_bytes,
+        reported_context_length: None,
+        reported_ram_bytes: None,
+        version: entry.version,
+        applied_adapters: Vec::new(),
+        config: entry.config,
+        parameters: entry.parameters,
+        user_parameters: entry.user_parameters,
+        session_parameters: entry.session_parameters,
+        user_session_parameters: entry.user_session_parameters,
+        uuid: LlmUuid::new_v4(),
+        running: false,
+    }
+}
+
+fn json_response<T: Serialize + ?Sized>(value: &T) -> Response<Body> {
+    Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            serde_json::to_vec(value).expect("mock responses serialize"),
+        ))
+        .unwrap()
+}
+
+fn event_stream_response<T: Serialize>(
+    events: impl Stream<Item = T> + Send + 'static,
+    format: StreamFormat,
+) -> Response<Body> {
+    let body = events.map(move |event| Ok(encode_event(format, None, &event)));
+    stream_response(body, format)
+}
+
+/// An event stream with ids, so clients can resume it. After `drop_after` events the
+/// connection is cut without finishing the body.
+fn resumable_stream_response<T: Serialize>(
+    events: impl Stream<Item = (usize, T)> + Send + 'static,
+    format: StreamFormat,
+    drop_after: Option<usize>,
+) -> Response<Body> {
+    let body = events
+        .take(drop_after.unwrap_or(usize::MAX))
+        .map(move |(id, event)| Ok(encode_event(format, Some(id), &event)))
+        .chain(stream::iter(drop_after).then(|_| async {
+            // Let what came before reach the client, rather than the whole response
+            // failing.
+            Delay::new(Duration::from_millis(10)).await;
+            Err(std::io::Error::new(
+                std::io::ErrorKind::ConnectionReset,
+                "mock dropped the stream",
+            ))
+        }));
+    stream_response(body, format)
+}
+
+/// One event on the wire. NDJSON has no room for the id.
+fn encode_event<T: Serialize>(format: StreamFormat, id: Option<usize>, event: &T) -> Bytes {
+    let data = serde_json::to_string(event).expect("mock events serialize");
+    match (format, id) {
+        (StreamFormat::Ndjson, _) => Bytes::from(format!("{}\n", data)),
+        (StreamFormat::Sse, Some(id)) => Bytes::from(format!("id: {}\ndata: {}\n\n", id, data)),
+        (StreamFormat::Sse, None) => Bytes::from(format!("data: {}\n\n", data)),
+    }
+}
+
+fn stream_response(
+    body: impl Stream<Item = Result<Bytes, std::io::Error>> + Send + 'static,
+    format: StreamFormat,
+) -> Response<Body> {
+    let content_type = match format {
+        StreamFormat::Sse => StreamFormat::SSE_CONTENT_TYPE,
+        StreamFormat::Ndjson => StreamFormat::NDJSON_CONTENT_TYPE,
+    };
+    Response::builder()
+        .header(CONTENT_TYPE, content_type)
+        .body(Body::wrap_stream(body))
+        .unwrap()
+}
+
+fn error_response(MockError(status, code, message): MockError) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            json!({"code": code, "message": message}).to_string(),
+        ))
+        .unwrap()
+}
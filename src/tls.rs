@@ -0,0 +1,165 @@
+//! HTTPS support for remote Pantry instances. Requires the `rustls` feature.
+//!
+//! Pantry only speaks plain HTTP itself, so this is for instances exposed through a
+//! TLS-terminating proxy, or anything else sitting between you and `0.0.0.0:9404`.
+//!
+//! ```no_run
+//! # use pantry_rs::tls::TlsConfig;
+//! # use pantry_rs::{PantryClient, UserId};
+//! # fn example(user_id: UserId, api_key: String) -> Result<(), pantry_rs::PantryError> {
+//! let tls = TlsConfig::new()
+//!     .add_ca_file("/etc/pantry/ca.pem")?
+//!     .client_auth_files("/etc/pantry/client.pem", "/etc/pantry/client.key")?;
+//!
+//! let pantry = PantryClient::builder()
+//!     .base_url("https://pantry.example.com")
+//!     .tls(&tls)?
+//!     .login(user_id, api_key);
+//! # Ok(())
+//! # }
+//! ```
+use crate::error::PantryError;
+use hyper::client::connect::HttpConnector;
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use std::io::BufReader;
+use std::path::Path;
+
+/// TLS settings for connecting to Pantry over HTTPS.
+///
+/// The default trusts the operating system's root certificates and sends no client
+/// certificate.
+#[derive(Clone, Debug)]
+pub struct TlsConfig {
+    /// Trust the operating system's root certificates.
+    pub native_roots: bool,
+    /// Additional PEM encoded CA certificates to trust, e.g. for a self-signed instance.
+    pub ca_certs: Vec<Vec<u8>>,
+    /// PEM encoded certificate chain and private key, for servers requiring client auth.
+    pub client_auth: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        TlsConfig {
+            native_roots: true,
+            ca_certs: Vec::new(),
+            client_auth: None,
+        }
+    }
+}
+
+impl TlsConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only trust the explicitly added CA certificates.
+    pub fn without_native_roots(mut self) -> Self {
+        self.native_roots = false;
+        self
+    }
+
+    /// Trust an additional PEM encoded CA certificate.
+    pub fn add_ca_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.ca_certs.push(pem.into());
+        self
+    }
+
+    /// Trust an additional CA certificate, read from a PEM file.
+    pub fn add_ca_file(self, path: impl AsRef<Path>) -> Result<Self, PantryError> {
+        Ok(self.add_ca_pem(read_file(path.as_ref())?))
+    }
+
+    /// Present a client certificate. Both arguments are PEM encoded.
+    pub fn client_auth_pem(
+        mut self,
+        cert_chain: impl Into<Vec<u8>>,
+        key: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.client_auth = Some((cert_chain.into(), key.into()));
+        self
+    }
+
+    /// Present a client certificate, read from PEM files.
+    pub fn client_auth_files(
+        self,
+        cert_chain: impl AsRef<Path>,
+        key: impl AsRef<Path>,
+    ) -> Result<Self, PantryError> {
+        let cert_chain = read_file(cert_chain.as_ref())?;
+        let key = read_file(key.as_ref())?;
+        Ok(self.client_auth_pem(cert_chain, key))
+    }
+
+    /// Builds a connector that speaks both `http://` and `https://`.
+    pub fn connector(&self) -> Result<HttpsConnector<HttpConnector>, PantryError> {
+        let mut roots = rustls::RootCertStore::empty();
+        if self.native_roots {
+            // Unparseable system certificates get skipped rather than failing the client.
+            if let Ok(certs) = rustls_native_certs::load_native_certs() {
+                let certs: Vec<Vec<u8>> = certs.into_iter().map(|c| c.0).collect();
+                roots.add_parsable_certificates(&certs);
+            }
+        }
+        for pem in &self.ca_certs {
+            for cert in parse_certs(pem)? {
+                roots.add(&cert).map_err(|e| {
+                    PantryError::OtherFailure(format!("invalid CA certificate: {}", e))
+                })?;
+            }
+        }
+
+        let builder = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots);
+        let config = match &self.client_auth {
+            None => builder.with_no_client_auth(),
+            Some((cert_chain, key)) => builder
+                .with_client_auth_cert(parse_certs(cert_chain)?, parse_key(key)?)
+                .map_err(|e| {
+                    PantryError::OtherFailure(format!("invalid client certificate: {}", e))
+                })?,
+        };
+
+        Ok(HttpsConnectorBuilder::new()
+            .with_tls_config(config)
+            .https_or_http()
+            .enable_http1()
+            .build())
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, PantryError> {
+    std::fs::read(path)
+        .map_err(|e| PantryError::OtherFailure(format!("failed to read {}: {}", path.display(), e)))
+}
+
+fn parse_certs(pem: &[u8]) -> Result<Vec<rustls::Certificate>, PantryError> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(pem))
+        .map_err(|e| PantryError::OtherFailure(format!("invalid PEM certificate: {}", e)))?;
+    if certs.is_empty() {
+        return Err(PantryError::OtherFailure(
+            "no certificates found in PEM".into(),
+        ));
+    }
+    Ok(certs.into_iter().map(rustls::Certificate).collect())
+}
+
+fn parse_key(pem: &[u8]) -> Result<rustls::PrivateKey, PantryError> {
+    let mut reader = BufReader::new(pem);
+    loop {
+        match rustls_pemfile::read_one(&mut reader)
+            .map_err(|e| PantryError::OtherFailure(format!("invalid PEM key: {}", e)))?
+        {
+            Some(rustls_pemfile::Item::RSAKey(key))
+            | Some(rustls_pemfile::Item::PKCS8Key(key))
+            | Some(rustls_pemfile::Item::ECKey(key)) => return Ok(rustls::PrivateKey(key)),
+            Some(_) => continue,
+            None => {
+                return Err(PantryError::OtherFailure(
+                    "no private key found in PEM".into(),
+                ))
+            }
+        }
+    }
+}
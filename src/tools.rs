@@ -0,0 +1,376 @@
+//! Letting an LLM call functions.
+//!
+//! Describe each function as a [Tool], with a JSON schema for its arguments, and a
+//! [ToolSet] writes them into a system prompt in the syntax the model family was
+//! trained on. When the LLM decides to call one, [ToolSet::events] picks the call out
+//! of the token stream as a typed [ToolCall]:
+//!
+//! ```no_run
+//! # use futures::StreamExt;
+//! # use pantry_rs::tools::{Tool, ToolEvent, ToolSet};
+//! # use pantry_rs::{ChatSession, LLMSession};
+//! # use serde_json::json;
+//! # async fn example(sess: LLMSession) -> Result<(), Box<dyn std::error::Error>> {
+//! let tools = ToolSet::detect(&sess.llm_status).tool(Tool::new(
+//!     "get_weather",
+//!     "Current weather for a city",
+//!     json!({
+//!         "type": "object",
+//!         "properties": { "city": { "type": "string" } },
+//!         "required": ["city"],
+//!     }),
+//! ));
+//! let mut chat = ChatSession::new(sess).system(tools.system_prompt());
+//!
+//! let mut events = tools.events(chat.send("Is it raining in Paris?").await?);
+//! while let Some(event) = events.next().await {
+//!     match event? {
+//!         ToolEvent::Text(text) => print!("{}", text),
+//!         ToolEvent::Call(call) => println!("\n-> {}({})", call.name, call.arguments),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Running the tools and handing back their results, with [ToolSet::render_results], is
+//! up to the caller, or to an [Agent](crate::agent::Agent).
+use crate::error::PantryError;
+use crate::interface::{LLMEvent, LLMEventInternal, LLMStatus};
+use crate::prompt_format::PromptFormat;
+use crate::structured::extract_json;
+use futures::stream::Stream;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A function the LLM may call.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Tool {
+    pub name: String,
+    /// What it does, for the LLM to decide when to call it.
+    pub description: String,
+    /// JSON schema of its arguments, usually an object.
+    pub parameters: Value,
+}
+
+impl Tool {
+    pub fn new(name: impl Into<String>, description: impl Into<String>, parameters: Value) -> Self {
+        Tool {
+            name: name.into(),
+            description: description.into(),
+            parameters,
+        }
+    }
+}
+
+/// A call the LLM made to a [Tool].
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ToolCall {
+    pub name: String,
+    /// Some models call them `parameters`, which is accepted too.
+    #[serde(default, alias = "parameters")]
+    pub arguments: Value,
+}
+
+impl ToolCall {
+    /// Deserializes the arguments, e.g. into the struct the schema was made from.
+    pub fn arguments_as<T: DeserializeOwned>(&self) -> Result<T, PantryError> {
+        Ok(serde_json::from_value(self.arguments.clone())?)
+    }
+}
+
+/// What came of running a [ToolCall], for handing back with [ToolSet::render_results].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ToolResult {
+    pub call: ToolCall,
+    /// What the tool returned, or why it failed.
+    pub result: Result<Value, String>,
+}
+
+/// What [ToolSet::events] turns inference events into.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ToolEvent {
+    /// Generated text that isn't part of a tool call.
+    Text(String),
+    Call(ToolCall),
+}
+
+/// The tools offered to an LLM, and the syntax it calls them in. See the
+/// [module docs](self).
+#[derive(Clone, Debug)]
+pub struct ToolSet {
+    tools: Vec<Tool>,
+    format: PromptFormat,
+}
+
+impl ToolSet {
+    /// No tools yet, for a model trained on `format`.
+    pub fn new(format: PromptFormat) -> Self {
+        ToolSet {
+            tools: Vec::new(),
+            format,
+        }
+    }
+
+    /// No tools yet, for `llm`'s format, see [PromptFormat::detect].
+    pub fn detect(llm: &LLMStatus) -> Self {
+        Self::new(PromptFormat::detect(llm))
+    }
+
+    /// Adds `tool`, replacing any tool of the same name.
+    pub fn tool(mut self, tool: Tool) -> Self {
+        self.tools.retain(|t| t.name != tool.name);
+        self.tools.push(tool);
+        self
+    }
+
+    pub fn tools(&self) -> &[Tool] {
+        &self.tools
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Tool> {
+        self.tools.iter().find(|tool| tool.name == name)
+    }
+
+    pub fn format(&self) -> PromptFormat {
+        self.format
+    }
+
+    /// Instructions describing the tools and how to call them, for the system prompt.
+    pub fn system_prompt(&self) -> String {
+        let specs: Vec<Value> = self
+            .tools
+            .iter()
+            .map(|tool| json!({ "type": "function", "function": tool }))
+            .collect();
+        match self.format {
+            // Mistral's function calling models expect the list as is.
+            PromptFormat::Mistral => {
+                format!(
+                    "[AVAILABLE_TOOLS] {} [/AVAILABLE_TOOLS]",
+                    Value::from(specs)
+                )
+            }
+            // What Hermes-style models are trained on, and clear enough for the rest.
+            _ => {
+                let specs: Vec<String> = specs.iter().map(Value::to_string).collect();
+                format!(
+                    "You can call the following tools:\n<tools>\n{}\n</tools>\n\
+                     To call one, reply with only\n\
+                     <tool_call>\n{{\"name\": <tool name>, \"arguments\": <arguments as JSON>}}\n</tool_call>",
+                    specs.join("\n")
+                )
+            }
+        }
+    }
+
+    /// Tool results as a message for the LLM, in the syntax it expects them in.
+    pub fn render_results(&self, results: &[ToolResult]) -> String {
+        let rendered: Vec<String> = results
+            .iter()
+            .map(|result| {
+                let content = match &result.result {
+                    Ok(value) => value.clone(),
+                    Err(error) => json!({ "error": error }),
+                };
+                let response = json!({ "name": result.call.name, "content": content });
+                match self.format {
+                    PromptFormat::Mistral => format!("[TOOL_RESULTS] {} [/TOOL_RESULTS]", response),
+                    _ => format!("<tool_response>\n{}\n</tool_response>", response),
+                }
+            })
+            .collect();
+        rendered.join("\n")
+    }
+
+    /// Every tool call in a finished reply.
+    pub fn parse(&self, text: &str) -> Result<Vec<ToolCall>, PantryError> {
+        let (open, _) = self.markers();
+        let mut calls = Vec::new();
+        let mut rest = text;
+        while let Some(start) = rest.find(open) {
+            rest = &rest[start + open.len()..];
+            let (mut found, used) = self.parse_call(rest)?;
+            calls.append(&mut found);
+            rest = &rest[used..];
+        }
+        Ok(calls)
+    }
+
+    /// Splits tool calls out of `events`, passing the text around them through as
+    /// [ToolEvent::Text].
+    ///
+    /// Works on anything yielding inference events, e.g. an
+    /// [LLMEventStream](crate::stream::LLMEventStream) or a
+    /// [ChatStream](crate::chat::ChatStream). Text that might be the start of a call
+    /// is held back until it's clear whether it is.
+    pub fn events<S>(&self, events: S) -> ToolEvents<S>
+    where
+        S: Stream<Item = Result<LLMEvent, PantryError>> + Unpin,
+    {
+        ToolEvents {
+            inner: events,
+            tools: self.clone(),
+            pending: String::new(),
+            in_call: false,
+            closing: false,
+            ready: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// What a call starts and ends with.
+    fn markers(&self) -> (&'static str, &'static str) {
+        match self.format {
+            PromptFormat::Mistral => ("[TOOL_CALLS]", ""),
+            _ => ("<tool_call>", "</tool_call>"),
+        }
+    }
+
+    /// Parses the call(s) at the start of `text`, returning them along with how much of
+    /// `text`, up to and including the closing marker, they took up.
+    fn parse_call(&self, text: &str) -> Result<(Vec<ToolCall>, usize), PantryError> {
+        let json = extract_json(text)
+            .ok_or_else(|| PantryError::InvalidToolCall("no JSON after the call marker".into()))?;
+        let value: Value = serde_json::from_str(json)
+            .map_err(|e| PantryError::InvalidToolCall(format!("{}: {}", e, json)))?;
+        let values = match value {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        let calls = values
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<Vec<ToolCall>, _>>()
+            .map_err(|e| PantryError::InvalidToolCall(format!("{}: {}", e, json)))?;
+
+        let mut used = json.as_ptr() as usize - text.as_ptr() as usize + json.len();
+        let (_, close) = self.markers();
+        let after = &text[used..];
+        let trimmed = after.trim_start();
+        if !close.is_empty() && trimmed.starts_with(close) {
+            used += after.len() - trimmed.len() + close.len();
+        }
+        Ok((calls, used))
+    }
+}
+
+/// Inference events split into text and tool calls, see [ToolSet::events].
+pub struct ToolEvents<S> {
+    inner: S,
+    tools: ToolSet,
+    // Text received but not handed out yet.
+    pending: String,
+    // Whether `pending` starts inside a call, after its opening marker.
+    in_call: bool,
+    // Whether a call just ended, and its closing marker may still be on its way.
+    closing: bool,
+    ready: VecDeque<ToolEvent>,
+    done: bool,
+}
+
+impl<S> ToolEvents<S> {
+    /// Moves whatever can be decided about `pending` into `ready`.
+    fn advance(&mut self) -> Result<(), PantryError> {
+        let (open, close) = self.tools.markers();
+        loop {
+            if self.closing {
+                let trimmed = self.pending.trim_start();
+                if trimmed.starts_with(close) {
+                    let used = self.pending.len() - trimmed.len() + close.len();
+                    self.pending.drain(..used);
+                } else if close.starts_with(trimmed) {
+                    return Ok(());
+                }
+                self.closing = false;
+            }
+            if self.in_call {
+                if extract_json(&self.pending).is_none() {
+                    return Ok(());
+                }
+                let (calls, used) = self.tools.parse_call(&self.pending)?;
+                self.ready.extend(calls.into_iter().map(ToolEvent::Call));
+                self.pending.drain(..used);
+                self.in_call = false;
+                self.closing = !close.is_empty();
+            } else if let Some(start) = self.pending.find(open) {
+                self.push_text(start);
+                self.pending.drain(..open.len());
+                self.in_call = true;
+            } else {
+                // Hold back anything that could turn into the marker.
+                let held = (1..open.len())
+                    .rev()
+                    .find(|n| self.pending.ends_with(&open[..*n]))
+                    .unwrap_or(0);
+                self.push_text(self.pending.len() - held);
+                return Ok(());
+            }
+        }
+    }
+
+    /// Hands out the first `len` bytes of `pending` as text.
+    fn push_text(&mut self, len: usize) {
+        if len > 0 {
+            let text: String = self.pending.drain(..len).collect();
+            self.ready.push_back(ToolEvent::Text(text));
+        }
+    }
+
+    /// Hands out everything left once the stream has ended.
+    fn flush(&mut self) -> Result<(), PantryError> {
+        if self.in_call {
+            return Err(PantryError::InvalidToolCall(format!(
+                "reply ended inside a tool call: {}",
+                self.pending
+            )));
+        }
+        self.push_text(self.pending.len());
+        Ok(())
+    }
+}
+
+impl<S> Stream for ToolEvents<S>
+where
+    S: Stream<Item = Result<LLMEvent, PantryError>> + Unpin,
+{
+    type Item = Result<ToolEvent, PantryError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(event) = this.ready.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+            let res = match Pin::new(&mut this.inner).poll_next(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Ok(event))) => match event.event {
+                    LLMEventInternal::PromptProgress { next, .. } => {
+                        this.pending.push_str(&next);
+                        this.advance()
+                    }
+                    LLMEventInternal::PromptError { message } => {
+                        Err(PantryError::PromptError(message))
+                    }
+                    LLMEventInternal::PromptCompletion { .. } | LLMEventInternal::Other => Ok(()),
+                },
+                Poll::Ready(Some(Err(e))) => Err(e),
+                Poll::Ready(None) => {
+                    this.done = true;
+                    this.flush()
+                }
+            };
+            if let Err(e) = res {
+                this.done = true;
+                this.ready.clear();
+                return Poll::Ready(Some(Err(e)));
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Speech to text with Whisper-class models.
+//!
+//! A [TranscriptionSession] is an [LLMSession] of an LLM that
+//! [crate::interface::LLMStatus::transcribes_audio], and streams what it hears as it
+//! goes:
+//!
+//! ```no_run
+//! # use futures::StreamExt;
+//! # use pantry_rs::interface::{LLMEventInternal, LLMStatus};
+//! # use pantry_rs::PantryClient;
+//! # use std::collections::HashMap;
+//! # async fn example(pantry: PantryClient, whisper: LLMStatus) -> Result<(), Box<dyn std::error::Error>> {
+//! let sess = pantry
+//!     .create_transcription_session(whisper.uuid, HashMap::new())
+//!     .await?
+//!     .language("en");
+//!
+//! let mut transcript = sess.transcribe(std::fs::read("meeting.wav")?).await?;
+//! while let Some(event) = transcript.next().await {
+//!     if let LLMEventInternal::PromptProgress { next, .. } = event?.event {
+//!         print!("{}", next);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Local Whisper models are registered as
+//! [crate::interface::LLMConnectorType::Whisper] entries, see
+//! [crate::connector_config::WhisperConfig].
+use crate::error::PantryError;
+use crate::stream::LLMEventStream;
+use crate::LLMSession;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Sent when [audio_mime] can't tell, leaving it to Pantry.
+const UNKNOWN_MIME: &str = "application/octet-stream";
+
+/// Transcribes recordings with a session's LLM, see the [module docs](self).
+pub struct TranscriptionSession {
+    session: LLMSession,
+    parameters: HashMap<String, Value>,
+}
+
+impl TranscriptionSession {
+    /// Fails with [PantryError::Unsupported] unless the session's LLM
+    /// [crate::interface::LLMStatus::transcribes_audio].
+    pub fn new(session: LLMSession) -> Result<Self, PantryError> {
+        if !session.llm_status.transcribes_audio() {
+            return Err(PantryError::Unsupported(format!(
+                "transcription with {}, which doesn't take audio",
+                session.llm_status.id
+            )));
+        }
+        Ok(TranscriptionSession {
+            session,
+            parameters: HashMap::new(),
+        })
+    }
+
+    /// Language spoken, e.g. `"en"`. Otherwise the LLM's default, or detected.
+    pub fn language(self, language: impl Into<String>) -> Self {
+        self.parameter("language", language.into())
+    }
+
+    /// Translate into English while transcribing, for models that can.
+    pub fn translate(self, translate: bool) -> Self {
+        self.parameter("translate", translate)
+    }
+
+    /// Any other parameter the LLM takes, see its `user_parameters`.
+    pub fn parameter(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.parameters.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn session(&self) -> &LLMSession {
+        &self.session
+    }
+
+    /// Streams the transcript of `audio`, an encoded recording whose format is told
+    /// from its first bytes, see [audio_mime]. Each
+    /// [crate::interface::LLMEventInternal::PromptProgress] is the next piece of it.
+    pub async fn transcribe(
+        &self,
+        audio: impl Into<Vec<u8>>,
+    ) -> Result<LLMEventStream, PantryError> {
+        let audio = audio.into();
+        let mime = audio_mime(&audio).unwrap_or(UNKNOWN_MIME);
+        self.transcribe_as(audio, mime).await
+    }
+
+    /// [TranscriptionSession::transcribe], for audio of a known `mime` type.
+    pub async fn transcribe_as(
+        &self,
+        audio: impl Into<Vec<u8>>,
+        mime: impl Into<String>,
+    ) -> Result<LLMEventStream, PantryError> {
+        let sess = &self.session;
+        if let Some(lifecycle) = sess.client.lifecycle() {
+            lifecycle.check()?;
+        }
+        sess.client
+            .transcribe_stream(
+                sess.user_id,
+                sess.api_key.clone(),
+                sess.id,
+                sess.llm_uuid,
+                audio.into(),
+                mime.into(),
+                self.parameters.clone(),
+            )
+            .await
+    }
+
+    /// Transcribes `audio` and waits for the whole transcript.
+    pub async fn transcribe_and_collect(
+        &self,
+        audio: impl Into<Vec<u8>>,
+    ) -> Result<String, PantryError> {
+        self.transcribe(audio).await?.collect_text().await
+    }
+}
+
+/// The format of an encoded recording, from its first bytes: WAV, MP3, FLAC, Ogg,
+/// WebM or MP4 audio.
+///
+/// MP3s without an ID3 tag are only told by their first frame header, which has to
+/// come first.
+pub fn audio_mime(audio: &[u8]) -> Option<&'static str> {
+    Some(match audio {
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => "audio/wav",
+        [b'I', b'D', b'3', ..] | [0xff, 0xe2 | 0xe3 | 0xf2 | 0xf3 | 0xfa | 0xfb, ..] => {
+            "audio/mpeg"
+        }
+        [b'f', b'L', b'a', b'C', ..] => "audio/flac",
+        [b'O', b'g', b'g', b'S', ..] => "audio/ogg",
+        [0x1a, 0x45, 0xdf, 0xa3, ..] => "audio/webm",
+        [_, _, _, _, b'f', b't', b'y', b'p', ..] => "audio/mp4",
+        _ => return None,
+    })
+}
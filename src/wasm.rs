@@ -0,0 +1,148 @@
+//! Talking to Pantry from the browser.
+//!
+//! hyper can't open connections on `wasm32-unknown-unknown`, so with the `wasm` feature
+//! [crate::PantryAPI] sends its calls through `fetch` instead, from a page or a worker.
+//! Responses, streamed ones included, still come back as [hyper::Response]s, so
+//! everything above the transport works the same as natively.
+//!
+//! ```toml
+//! pantry-rs = { version = "0.0.4", default-features = false, features = ["wasm", "streaming"] }
+//! ```
+//!
+//! There's no unix socket in a browser: calls go to `base_url`, or
+//! `http://localhost:9404` without one. The browser only lets them through if Pantry
+//! allows the page's origin (CORS).
+use crate::error::PantryError;
+use futures::channel::oneshot;
+use hyper::body::Bytes;
+use js_sys::{Array, Function, Promise, Reflect, Uint8Array};
+use std::future::Future;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Headers, ReadableStreamDefaultReader, Request, RequestInit, Response};
+
+/// Sends a request with `fetch`. The body is read on a local task and fed to the
+/// response as it arrives, so the returned future is `Send` like the native one.
+pub(crate) async fn fetch(
+    method: hyper::Method,
+    url: String,
+    body: String,
+    accept: &'static str,
+    extra_headers: Vec<(String, String)>,
+) -> Result<hyper::Response<hyper::Body>, PantryError> {
+    let (tx, rx) = oneshot::channel();
+    spawn(async move {
+        let resp = match start(method.as_str(), &url, &body, accept, &extra_headers).await {
+            Ok(resp) => resp,
+            Err(err) => {
+                let _ = tx.send(Err(err));
+                return;
+            }
+        };
+
+        let (mut sender, body) = hyper::Body::channel();
+        let mut builder = hyper::Response::builder().status(resp.status());
+        if let Some(entries) = js_sys::try_iter(&resp.headers()).ok().flatten() {
+            for entry in entries.flatten() {
+                let pair: Array = entry.unchecked_into();
+                if let (Some(name), Some(value)) =
+                    (pair.get(0).as_string(), pair.get(1).as_string())
+                {
+                    builder = builder.header(name, value);
+                }
+            }
+        }
+        if tx
+            .send(builder.body(body).map_err(PantryError::from))
+            .is_err()
+        {
+            return;
+        }
+
+        let reader: ReadableStreamDefaultReader = match resp.body() {
+            Some(stream) => stream.get_reader().unchecked_into(),
+            None => return,
+        };
+        loop {
+            let chunk = match JsFuture::from(reader.read()).await {
+                Ok(chunk) => chunk,
+                Err(_) => {
+                    sender.abort();
+                    return;
+                }
+            };
+            let done = Reflect::get(&chunk, &"done".into())
+                .ok()
+                .and_then(|done| done.as_bool())
+                .unwrap_or(true);
+            if done {
+                return;
+            }
+            let value = match Reflect::get(&chunk, &"value".into()) {
+                Ok(value) => Uint8Array::new(&value).to_vec(),
+                Err(_) => continue,
+            };
+            if sender.send_data(Bytes::from(value)).await.is_err() {
+                // Nobody's reading anymore.
+                let _ = reader.cancel();
+                return;
+            }
+        }
+    });
+
+    rx.await
+        .map_err(|_| PantryError::FetchError("fetch was dropped".into()))?
+}
+
+/// Runs `fut` on the browser's event loop.
+pub(crate) fn spawn(fut: impl Future<Output = ()> + 'static) {
+    wasm_bindgen_futures::spawn_local(fut)
+}
+
+async fn start(
+    method: &str,
+    url: &str,
+    body: &str,
+    accept: &str,
+    extra_headers: &[(String, String)],
+) -> Result<Response, PantryError> {
+    let headers = Headers::new().map_err(js_error)?;
+    headers
+        .set("Content-Type", "application/json")
+        .map_err(js_error)?;
+    headers.set("Accept", accept).map_err(js_error)?;
+    for (name, value) in extra_headers {
+        headers.set(name, value).map_err(js_error)?;
+    }
+
+    let init = RequestInit::new();
+    init.set_method(method);
+    init.set_headers(&headers);
+    init.set_body(&JsValue::from_str(body));
+    let request = Request::new_with_str_and_init(url, &init).map_err(js_error)?;
+
+    // Works in windows and workers alike, unlike `Window::fetch`.
+    let global = js_sys::global();
+    let fetch: Function = Reflect::get(&global, &"fetch".into())
+        .map_err(js_error)?
+        .dyn_into()
+        .map_err(|_| PantryError::FetchError("fetch isn't available here".into()))?;
+    let promise: Promise = fetch
+        .call1(&global, &request)
+        .map_err(js_error)?
+        .unchecked_into();
+
+    JsFuture::from(promise)
+        .await
+        .map_err(js_error)?
+        .dyn_into()
+        .map_err(js_error)
+}
+
+fn js_error(err: JsValue) -> PantryError {
+    let msg = match err.dyn_ref::<js_sys::Error>() {
+        Some(err) => String::from(err.message()),
+        None => format!("{:?}", err),
+    };
+    PantryError::FetchError(msg)
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "testing")]
+use pantry_rs::interface::{AdapterEntry, LLMConnectorType, LLMRegistryEntry, UserPermissions};
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::{PantryClient, PantryError};
+
+fn perms() -> UserPermissions {
+    UserPermissions {
+        perm_download_llm: true,
+        perm_load_llm: true,
+        perm_unload_llm: true,
+        perm_view_llms: true,
+        ..Default::default()
+    }
+}
+
+async fn downloaded(pantry: &PantryClient) -> String {
+    // The mock doesn't fetch anything, so the urls only need to look right.
+    let entry = LLMRegistryEntry::builder("openchat", LLMConnectorType::LLMrs)
+        .url("file:///models/openchat.gguf")
+        .config("model_architecture", "llama")
+        .adapter(AdapterEntry::new("sql", "file:///adapters/sql.bin"))
+        .build()
+        .unwrap();
+    pantry.download_llm(entry).await.unwrap().to_string()
+}
+
+#[test]
+fn validates_adapters() {
+    let builder = || {
+        LLMRegistryEntry::builder("openchat", LLMConnectorType::LLMrs)
+            .url("https://example.com/openchat.gguf")
+            .config("model_architecture", "llama")
+    };
+    assert!(builder()
+        .adapter(AdapterEntry::new("sql", ""))
+        .build()
+        .is_err());
+    assert!(builder()
+        .adapter(AdapterEntry::new("sql", "https://example.com/a.bin"))
+        .adapter(AdapterEntry::new("sql", "https://example.com/b.bin"))
+        .build()
+        .is_err());
+}
+
+#[tokio::test]
+async fn applies_downloaded_adapters() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.login(perms());
+    let llm_id = downloaded(&pantry).await;
+
+    let offered = pantry.list_adapters(llm_id.clone()).await.unwrap();
+    assert_eq!(offered.len(), 1);
+    assert_eq!(offered[0].adapter.id, "sql");
+    assert!(!offered[0].downloaded);
+
+    let llm_uuid = pantry.load_llm(llm_id.clone()).await.unwrap().uuid;
+    let res = pantry.apply_adapter(llm_uuid, "sql").await;
+    assert!(res.is_err());
+
+    let adapter = pantry
+        .download_adapter(llm_id.clone(), offered[0].adapter.clone())
+        .await
+        .unwrap();
+    assert!(adapter.downloaded);
+    assert_eq!(adapter.llm_uuid, llm_uuid);
+
+    let llm = pantry.apply_adapter(llm_uuid, "sql").await.unwrap();
+    assert_eq!(llm.applied_adapters, ["sql"]);
+    assert!(pantry.list_adapters(llm_id.clone()).await.unwrap()[0].applied);
+
+    let llm = pantry.remove_adapter(llm_uuid, "sql").await.unwrap();
+    assert!(llm.applied_adapters.is_empty());
+
+    pantry.apply_adapter(llm_uuid, "sql").await.unwrap();
+    let llm = pantry.unload_llm(llm_id.clone()).await.unwrap();
+    assert!(llm.applied_adapters.is_empty());
+    let res = pantry.apply_adapter(llm_uuid, "sql").await;
+    assert!(matches!(res, Err(PantryError::LlmNotRunning(_))));
+}
+
+#[tokio::test]
+async fn checks_adapters_before_sending() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.login(perms());
+    let llm_id = downloaded(&pantry).await;
+    let res = pantry
+        .download_adapter(llm_id, AdapterEntry::new(" ", "file:///adapters/x.bin"))
+        .await;
+    assert!(matches!(res, Err(PantryError::InvalidRegistryEntry(_))));
+    assert!(!server.calls().contains(&"download_adapter".to_string()));
+}
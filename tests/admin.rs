@@ -0,0 +1,34 @@
+use pantry_rs::interface::UserStatus;
+use pantry_rs::{AdminClient, PantryClient, UserId};
+use serde_json::json;
+
+#[test]
+fn user_status_flattens_permissions() {
+    let user: UserStatus = serde_json::from_value(json!({
+        "id": "1d2c3b4a-5e6f-4a1b-8c9d-0e1f2a3b4c5d",
+        "name": "ci",
+        "perm_superuser": false,
+        "perm_load_llm": true,
+        "perm_unload_llm": false,
+        "perm_download_llm": false,
+        "perm_session": true,
+        "perm_request_download": false,
+        "perm_request_load": false,
+        "perm_request_unload": false,
+        "perm_view_llms": true,
+        "perm_bare_model": false
+    }))
+    .unwrap();
+    assert_eq!(user.name, "ci");
+    assert!(user.permissions.perm_load_llm);
+    assert!(!user.permissions.perm_superuser);
+    assert_eq!(user.last_active, None);
+}
+
+#[test]
+fn debug_output_leaves_out_the_key() {
+    let pantry = PantryClient::login(UserId::new_v4(), "hunter2".into(), None);
+    assert!(!format!("{:?}", pantry).contains("hunter2"));
+    let admin = AdminClient::new(pantry);
+    assert!(!format!("{:?}", admin).contains("hunter2"));
+}
@@ -0,0 +1,104 @@
+#![cfg(feature = "testing")]
+use pantry_rs::agent::Agent;
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::tools::{Tool, ToolCall};
+use pantry_rs::PantryError;
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+fn weather() -> Tool {
+    Tool::new(
+        "get_weather",
+        "Current weather for a city",
+        json!({"type": "object", "properties": {"city": {"type": "string"}}}),
+    )
+}
+
+const CALL: &str =
+    "<tool_call>{\"name\": \"get_weather\", \"arguments\": {\"city\": \"Paris\"}}</tool_call>";
+
+#[tokio::test]
+async fn runs_tools_until_answered() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.queue_reply([CALL]);
+    server.queue_reply(["Bring an umbrella."]);
+    let sess = server.running_session("openhermes").await;
+
+    let traced = Arc::new(AtomicUsize::new(0));
+    let mut agent = Agent::new(sess)
+        .system("Be brief.")
+        .tool(weather(), |call: ToolCall| async move {
+            Ok(json!({"city": call.arguments["city"], "forecast": "rain"}))
+        })
+        .on_step({
+            let traced = traced.clone();
+            move |_| {
+                traced.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+    let run = agent.run("Umbrella in Paris?").await.unwrap();
+    assert_eq!(run.answer, "Bring an umbrella.");
+    assert_eq!(run.steps.len(), 1);
+    assert_eq!(
+        run.steps[0].results[0].result,
+        Ok(json!({"city": "Paris", "forecast": "rain"}))
+    );
+    assert_eq!(traced.load(Ordering::SeqCst), 1);
+
+    // The result went back to the LLM.
+    let messages = agent.chat().messages();
+    assert!(messages[0].content.starts_with("Be brief.\n\n"));
+    assert!(messages[3].content.contains("<tool_response>"));
+    assert!(messages[3].content.contains("\"forecast\":\"rain\""));
+}
+
+#[tokio::test]
+async fn unknown_tools_are_reported_back() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.queue_reply([CALL]);
+    server.queue_reply(["Sorry, I can't check."]);
+    let sess = server.running_session("openhermes").await;
+
+    let mut agent = Agent::new(sess);
+    let run = agent.run("Umbrella in Paris?").await.unwrap();
+    assert_eq!(
+        run.steps[0].results[0].result,
+        Err("there is no tool named get_weather".into())
+    );
+}
+
+#[tokio::test]
+async fn garbled_calls_are_reported_back() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.queue_reply(["<tool_call>{\"name\": \"get_weather\",</tool_call>"]);
+    server.queue_reply(["Sorry, I can't check."]);
+    let sess = server.running_session("openhermes").await;
+
+    let mut agent = Agent::new(sess).tool(weather(), |_| async { Ok(json!("rain")) });
+    let run = agent.run("Umbrella in Paris?").await.unwrap();
+    assert_eq!(run.answer, "Sorry, I can't check.");
+    let result = &run.steps[0].results[0];
+    assert_eq!(result.call.name, "");
+    assert!(result
+        .result
+        .as_ref()
+        .unwrap_err()
+        .contains("couldn't be parsed"));
+    let messages = agent.chat().messages();
+    assert!(messages[3].content.contains("couldn't be parsed"));
+}
+
+#[tokio::test]
+async fn stops_at_the_step_limit() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.reply([CALL]);
+    let sess = server.running_session("openhermes").await;
+
+    let mut agent = Agent::new(sess)
+        .tool(weather(), |_| async { Ok(json!("rain")) })
+        .max_steps(2);
+    let res = agent.run("Umbrella in Paris?").await;
+    assert!(matches!(res, Err(PantryError::AgentStepLimit(2))));
+}
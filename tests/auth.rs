@@ -0,0 +1,57 @@
+#![cfg(feature = "testing")]
+use pantry_rs::auth::{RemoteAuth, DEFAULT_SECRET_HEADER};
+use pantry_rs::interface::UserPermissions;
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::PantryError;
+
+#[test]
+fn builds_headers() {
+    let auth = RemoteAuth::bearer("token").header("X-Gateway", "lab");
+    assert_eq!(
+        auth.to_headers(),
+        [
+            ("Authorization".to_string(), "Bearer token".to_string()),
+            ("X-Gateway".to_string(), "lab".to_string()),
+        ]
+    );
+    assert_eq!(
+        RemoteAuth::secret("psk").to_headers(),
+        [(DEFAULT_SECRET_HEADER.to_string(), "psk".to_string())]
+    );
+}
+
+#[test]
+fn redacts_debug() {
+    let debug = format!(
+        "{:?}",
+        RemoteAuth::bearer("s3cr3t-token").header("X-Secret", "psk-value")
+    );
+    assert!(!debug.contains("s3cr3t"));
+    assert!(!debug.contains("psk-value"));
+    assert!(debug.contains("X-Secret"));
+}
+
+#[tokio::test]
+async fn sends_headers_to_the_base_url() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.require_header("Authorization", "Bearer token");
+    server.require_header(DEFAULT_SECRET_HEADER, "psk");
+    let plain = server.login(UserPermissions::default());
+
+    let rejected = server
+        .builder()
+        .remote_auth(RemoteAuth::bearer("token"))
+        .login(plain.user_id, plain.api_key.clone());
+    assert!(matches!(
+        rejected.get_permissions().await,
+        Err(PantryError::InvalidApiKey(_))
+    ));
+
+    let mut auth = RemoteAuth::secret("psk");
+    auth.bearer_token = Some("token".into());
+    let pantry = server
+        .builder()
+        .remote_auth(auth)
+        .login(plain.user_id, plain.api_key);
+    pantry.get_permissions().await.unwrap();
+}
@@ -0,0 +1,78 @@
+use async_trait::async_trait;
+use pantry_rs::interface::UserPermissions;
+use pantry_rs::{PantryBackend, PantryClient, PantryError, RetryPolicy, SessionId, UserId};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Clone, Debug, Default)]
+struct MockBackend {
+    deleted: Arc<Mutex<Vec<(String, SessionId)>>>,
+}
+
+#[async_trait]
+impl PantryBackend for MockBackend {
+    fn with_timeout(&self, _timeout: Option<Duration>) -> Arc<dyn PantryBackend> {
+        Arc::new(self.clone())
+    }
+
+    fn with_retry(&self, _retry: RetryPolicy) -> Arc<dyn PantryBackend> {
+        Arc::new(self.clone())
+    }
+
+    async fn get_permissions(
+        &self,
+        _user_id: UserId,
+        _api_key: String,
+    ) -> Result<UserPermissions, PantryError> {
+        Ok(UserPermissions {
+            perm_session: true,
+            ..Default::default()
+        })
+    }
+
+    async fn delete_session(
+        &self,
+        _user_id: UserId,
+        api_key: String,
+        session_id: SessionId,
+    ) -> Result<(), PantryError> {
+        self.deleted.lock().unwrap().push((api_key, session_id));
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn client_calls_backend() {
+    let mock = MockBackend::default();
+    let pantry = PantryClient::with_backend(Arc::new(mock.clone()), UserId::new_v4(), "key".into());
+
+    assert!(pantry.get_permissions().await.unwrap().perm_session);
+
+    let session_id = SessionId::new_v4();
+    pantry.delete_session(session_id).await.unwrap();
+    assert_eq!(
+        *mock.deleted.lock().unwrap(),
+        vec![("key".to_string(), session_id)]
+    );
+}
+
+#[tokio::test]
+async fn unimplemented_calls_are_unsupported() {
+    let pantry = PantryClient::with_backend(
+        Arc::new(MockBackend::default()),
+        UserId::new_v4(),
+        "key".into(),
+    );
+
+    assert!(matches!(
+        pantry.get_running_llms().await,
+        Err(PantryError::Unsupported(_))
+    ));
+    assert!(matches!(
+        pantry
+            .with_timeout(Some(Duration::from_secs(1)))
+            .server_info()
+            .await,
+        Err(PantryError::Unsupported(_))
+    ));
+}
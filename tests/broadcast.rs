@@ -0,0 +1,90 @@
+#![cfg(feature = "testing")]
+use futures::stream::StreamExt;
+use pantry_rs::interface::{LLMEvent, LLMEventInternal};
+use pantry_rs::stream::BroadcastEventStream;
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::{InferenceParams, LLMSession, PantryError, RetryPolicy};
+use std::time::Duration;
+
+async fn session(server: &MockPantryServer) -> LLMSession {
+    let builder = server.builder().reconnect(RetryPolicy::none());
+    server.running_session_with("openchat", builder).await
+}
+
+fn text(events: &[Result<LLMEvent, PantryError>]) -> String {
+    events
+        .iter()
+        .filter_map(|event| match &event.as_ref().ok()?.event {
+            LLMEventInternal::PromptProgress { next, .. } => Some(next.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+async fn drain(stream: BroadcastEventStream) -> Vec<Result<LLMEvent, PantryError>> {
+    stream.collect().await
+}
+
+#[tokio::test]
+async fn tee_copies_every_event() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.token_delay(Duration::from_millis(5));
+    let sess = session(&server).await;
+
+    let stream = sess
+        .prompt_session("Hi".into(), InferenceParams::new())
+        .await
+        .unwrap();
+    let (ui, log) = stream.tee();
+    let (ui, log) = tokio::join!(drain(ui), drain(log));
+
+    assert_eq!(ui.len(), 5);
+    assert_eq!(text(&ui), "Hello, world!");
+    assert_eq!(text(&log), "Hello, world!");
+    assert!(matches!(
+        log.last().unwrap().as_ref().unwrap().event,
+        LLMEventInternal::PromptCompletion { .. }
+    ));
+}
+
+#[tokio::test]
+async fn slow_consumers_lag() {
+    let server = MockPantryServer::start().await.unwrap();
+    let sess = session(&server).await;
+
+    let stream = sess
+        .prompt_session("Hi".into(), InferenceParams::new())
+        .await
+        .unwrap();
+    let mut copies = stream.broadcast(3, 2);
+    // Let the whole reply go through while nobody's reading.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    for copy in copies.drain(..) {
+        let events = drain(copy).await;
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[0], Err(PantryError::StreamLagged(3))));
+        assert!(matches!(
+            events[2].as_ref().unwrap().event,
+            LLMEventInternal::PromptCompletion { .. }
+        ));
+    }
+}
+
+#[tokio::test]
+async fn errors_reach_every_consumer() {
+    let server = MockPantryServer::start().await.unwrap();
+    let sess = session(&server).await;
+
+    server.drop_stream_after(1);
+    let stream = sess
+        .prompt_session("Hi".into(), InferenceParams::new())
+        .await
+        .unwrap();
+    let (a, b) = stream.tee();
+    for events in [drain(a).await, drain(b).await] {
+        assert_eq!(events.len(), 2);
+        assert_eq!(text(&events), "Hello");
+        assert!(matches!(events[1], Err(PantryError::StreamError(_))));
+    }
+}
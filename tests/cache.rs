@@ -0,0 +1,194 @@
+#![cfg(feature = "testing")]
+use pantry_rs::interface::FinishReason;
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::{InferenceParams, LLMSession, PantryClient, ResponseCache};
+use std::collections::HashMap;
+use std::time::Duration;
+
+fn greedy() -> InferenceParams {
+    InferenceParams::new().temperature(0.0)
+}
+
+async fn fresh(pantry: &PantryClient) -> LLMSession {
+    pantry.create_session(HashMap::new()).await.unwrap()
+}
+
+fn prompts(server: &MockPantryServer) -> usize {
+    server
+        .calls()
+        .iter()
+        .filter(|call| call.starts_with("prompt_session"))
+        .count()
+}
+
+#[tokio::test]
+async fn reuses_completions() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.running_client("openchat");
+    let cache = ResponseCache::new(10);
+    let params = InferenceParams::new().temperature(0.0).seed(42);
+
+    let first = cache
+        .prompt(&fresh(&pantry).await, "Hi".into(), params.clone())
+        .await
+        .unwrap();
+    assert_eq!(first.text, "Hello, world!");
+    assert_eq!(first.finish_reason, FinishReason::Stop);
+    let again = cache
+        .prompt(&fresh(&pantry).await, "Hi".into(), params.clone())
+        .await
+        .unwrap();
+    assert_eq!(again, first);
+    assert_eq!(prompts(&server), 1);
+
+    // Different parameters or prompts are different entries.
+    cache
+        .prompt(&fresh(&pantry).await, "Hi".into(), params.clone().seed(7))
+        .await
+        .unwrap();
+    cache
+        .prompt(&fresh(&pantry).await, "Hello".into(), params.clone())
+        .await
+        .unwrap();
+    assert_eq!(prompts(&server), 3);
+    assert_eq!(cache.len(), 3);
+
+    cache
+        .refresh(&fresh(&pantry).await, "Hi".into(), params)
+        .await
+        .unwrap();
+    assert_eq!(prompts(&server), 4);
+}
+
+#[tokio::test]
+async fn history_is_part_of_the_key() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.running_client("openchat");
+    let sess = fresh(&pantry).await;
+    let cache = ResponseCache::new(10);
+
+    for _ in 0..2 {
+        cache.prompt(&sess, "Hi".into(), greedy()).await.unwrap();
+    }
+    // The second time the session had the first exchange in it.
+    assert_eq!(prompts(&server), 2);
+    assert_eq!(cache.len(), 2);
+}
+
+#[tokio::test]
+async fn caches_seeded_prompts() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.running_client("openchat");
+    let cache = ResponseCache::new(10);
+    let params = InferenceParams::new().temperature(0.8).seed(42);
+
+    for _ in 0..2 {
+        cache
+            .prompt(&fresh(&pantry).await, "Hi".into(), params.clone())
+            .await
+            .unwrap();
+    }
+    assert_eq!(prompts(&server), 1);
+    assert_eq!(cache.len(), 1);
+}
+
+#[tokio::test]
+async fn skips_sampled_prompts() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.running_client("openchat");
+    let cache = ResponseCache::new(10);
+
+    // Without a seed, neither a temperature above 0 nor the LLM's default is repeatable.
+    for params in [
+        InferenceParams::new().temperature(0.8),
+        InferenceParams::new(),
+    ] {
+        for _ in 0..2 {
+            cache
+                .prompt(&fresh(&pantry).await, "Hi".into(), params.clone())
+                .await
+                .unwrap();
+        }
+    }
+    assert_eq!(prompts(&server), 4);
+    assert!(cache.is_empty());
+}
+
+#[tokio::test]
+async fn deduplicates_running_prompts() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.token_delay(Duration::from_millis(20));
+    let pantry = server.running_client("openchat");
+    let (a, b) = (fresh(&pantry).await, fresh(&pantry).await);
+    let cache = ResponseCache::new(10);
+
+    let (a, b) = futures::join!(
+        cache.prompt(&a, "Hi".into(), greedy()),
+        cache.prompt(&b, "Hi".into(), greedy()),
+    );
+    assert_eq!(a.unwrap(), b.unwrap());
+    assert_eq!(prompts(&server), 1);
+}
+
+#[tokio::test]
+async fn dropped_prompts_let_go() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.token_delay(Duration::from_millis(50));
+    let pantry = server.running_client("openchat");
+    let cache = ResponseCache::new(10);
+
+    let sess = fresh(&pantry).await;
+    let dropped = cache.prompt(&sess, "Hi".into(), greedy());
+    assert!(tokio::time::timeout(Duration::from_millis(20), dropped)
+        .await
+        .is_err());
+    // Not left waiting on the dropped one.
+    let sess = fresh(&pantry).await;
+    let res = tokio::time::timeout(
+        Duration::from_secs(5),
+        cache.prompt(&sess, "Hi".into(), greedy()),
+    )
+    .await;
+    assert!(res.unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn evicts_and_expires() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.running_client("openchat");
+    let cache = ResponseCache::new(2).ttl(Duration::from_millis(100));
+
+    for prompt in ["a", "b", "a", "c"] {
+        cache
+            .prompt(&fresh(&pantry).await, prompt.into(), greedy())
+            .await
+            .unwrap();
+    }
+    // "b" was the least recently used when "c" came in.
+    assert_eq!(cache.len(), 2);
+    assert_eq!(prompts(&server), 3);
+    cache
+        .prompt(&fresh(&pantry).await, "b".into(), greedy())
+        .await
+        .unwrap();
+    assert_eq!(prompts(&server), 4);
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    cache
+        .prompt(&fresh(&pantry).await, "b".into(), greedy())
+        .await
+        .unwrap();
+    assert_eq!(prompts(&server), 5);
+}
+
+#[tokio::test]
+async fn invalidates() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.running_client("openchat");
+    let sess = fresh(&pantry).await;
+    let cache = ResponseCache::new(10);
+
+    cache.prompt(&sess, "Hi".into(), greedy()).await.unwrap();
+    cache.invalidate(sess.llm_uuid, "Hi", greedy()).unwrap();
+    assert!(cache.is_empty());
+}
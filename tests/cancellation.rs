@@ -0,0 +1,73 @@
+#![cfg(all(feature = "cancellation", feature = "testing"))]
+use futures::stream::StreamExt;
+use pantry_rs::interface::FinishReason;
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::InferenceParams;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+fn interrupted(server: &MockPantryServer) -> bool {
+    server.calls().contains(&"interrupt_session".to_string())
+}
+
+#[tokio::test]
+async fn cancelling_interrupts() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.token_delay(Duration::from_millis(50));
+    let sess = server.running_session("openchat").await;
+
+    let token = CancellationToken::new();
+    let mut stream = sess
+        .prompt_session_cancellable("Hi".into(), InferenceParams::new(), token.clone())
+        .await
+        .unwrap();
+    assert!(stream.next().await.unwrap().is_ok());
+    token.cancel();
+    assert!(stream.next().await.is_none());
+    assert_eq!(stream.finish_reason(), Some(FinishReason::Interrupted));
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(interrupted(&server));
+}
+
+#[tokio::test]
+async fn cancelling_from_elsewhere() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.token_delay(Duration::from_millis(100));
+    let sess = server.running_session("openchat").await;
+
+    let scope = CancellationToken::new();
+    let stream = sess
+        .prompt_session_cancellable("Hi".into(), InferenceParams::new(), scope.child_token())
+        .await
+        .unwrap();
+    let cancel = scope.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        cancel.cancel();
+    });
+
+    let completion = stream.collect_completion().await.unwrap();
+    assert_eq!(completion.finish_reason, FinishReason::Interrupted);
+    assert_eq!(completion.text, "Hello");
+}
+
+#[tokio::test]
+async fn finishing_first_leaves_the_session_alone() {
+    let server = MockPantryServer::start().await.unwrap();
+    let sess = server.running_session("openchat").await;
+
+    let token = CancellationToken::new();
+    let text = sess
+        .prompt_session_cancellable("Hi".into(), InferenceParams::new(), token.clone())
+        .await
+        .unwrap()
+        .collect_text()
+        .await
+        .unwrap();
+    assert_eq!(text, "Hello, world!");
+    token.cancel();
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(!interrupted(&server));
+}
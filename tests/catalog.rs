@@ -0,0 +1,135 @@
+use pantry_rs::catalog::{find_updates, parse_registry_index, search_registry, UpdateKind};
+use pantry_rs::interface::{CapabilityType, LLMConnectorType, LLMRegistryEntry, LLMStatus};
+use serde_json::json;
+
+fn entry(id: &str, tags: &[&str], coding: i32) -> LLMRegistryEntry {
+    let mut builder = LLMRegistryEntry::builder(id, LLMConnectorType::LLMrs)
+        .url(format!("https://example.com/{}.gguf", id))
+        .config("model_architecture", "llama")
+        .capability(CapabilityType::Coding, coding);
+    for tag in tags {
+        builder = builder.tag(*tag);
+    }
+    builder.build().unwrap()
+}
+
+fn index() -> Vec<LLMRegistryEntry> {
+    vec![
+        entry("openchat", &["chat"], 3),
+        entry("codellama", &["chat", "code"], 7),
+        entry("llama2-base", &[], -1),
+        entry("deepseek-coder", &["Chat"], 8),
+    ]
+}
+
+fn ids(entries: &[LLMRegistryEntry]) -> Vec<&str> {
+    entries.iter().map(|e| e.id.as_str()).collect()
+}
+
+#[test]
+fn reads_either_index_shape() {
+    let entries = serde_json::to_value(index()).unwrap();
+    let mut invalid = serde_json::to_value(entry("broken", &[], -1)).unwrap();
+    invalid["url"] = json!("");
+
+    let list = json!([entries[0], invalid, {"unknown": true}, entries[1]]);
+    let parsed = parse_registry_index(&list.to_string()).unwrap();
+    assert_eq!(ids(&parsed), ["openchat", "codellama"]);
+
+    let wrapped = json!({"version": 1, "llms": entries});
+    let parsed = parse_registry_index(&wrapped.to_string()).unwrap();
+    assert_eq!(parsed.len(), 4);
+
+    assert!(parse_registry_index("{}").is_err());
+}
+
+#[test]
+fn searches_entries() {
+    assert_eq!(search_registry(index(), "", &[], None).len(), 4);
+    assert_eq!(
+        ids(&search_registry(index(), "LLAMA", &[], None)),
+        ["codellama", "llama2-base"]
+    );
+    assert_eq!(
+        ids(&search_registry(index(), "", &["chat"], None)),
+        ["openchat", "codellama", "deepseek-coder"]
+    );
+    assert_eq!(
+        ids(&search_registry(index(), "code", &["chat"], None)),
+        ["codellama", "deepseek-coder"]
+    );
+}
+
+#[test]
+fn ranks_by_capability() {
+    let found = search_registry(index(), "", &[], Some(CapabilityType::Coding));
+    assert_eq!(ids(&found), ["deepseek-coder", "codellama", "openchat"]);
+}
+
+fn installed(entry: LLMRegistryEntry) -> LLMStatus {
+    serde_json::from_value(json!({
+        "id": entry.id, "family_id": entry.family_id, "organization": "",
+        "name": entry.name, "homepage": "", "license": "", "description": "",
+        "capabilities": {}, "requirements": "", "tags": [], "url": entry.url,
+        "local": true, "connector_type": "llmrs", "download_progress": 100.0,
+        "version": entry.version, "config": {}, "parameters": {}, "user_parameters": [],
+        "session_parameters": {}, "user_session_parameters": [],
+        "uuid": "6f1e0b9e-8a43-4b0c-9a39-7d0d1f7c6a11", "running": false
+    }))
+    .unwrap()
+}
+
+#[test]
+fn finds_updates() {
+    let base = |id: &str| {
+        LLMRegistryEntry::builder(id, LLMConnectorType::LLMrs)
+            .url(format!("https://example.com/{}.gguf", id))
+            .config("model_architecture", "llama")
+            .family_id("llama")
+    };
+    let have = [
+        installed(base("openchat-ggml").name("OpenChat").build().unwrap()),
+        installed(base("mistral").version("0.9").build().unwrap()),
+        installed(base("llama2").name("Llama 2").build().unwrap()),
+    ];
+    let index = [
+        base("openchat-gguf")
+            .name("openchat")
+            .supersedes("openchat-ggml")
+            .build()
+            .unwrap(),
+        base("mistral").version("0.10").build().unwrap(),
+        base("llama3")
+            .name("Llama 3")
+            .supersedes("llama2")
+            .build()
+            .unwrap(),
+        // Already installed, so not suggested.
+        base("llama2").supersedes("openchat-ggml").build().unwrap(),
+        base("openchat-ggml").version("1").build().unwrap(),
+    ];
+
+    let updates = find_updates(&have, &index);
+    let found: Vec<(&str, &str, UpdateKind)> = updates
+        .iter()
+        .map(|u| {
+            (
+                u.installed.id.as_str(),
+                u.request.llm_registry_entry.id.as_str(),
+                u.kind.clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("openchat-ggml", "openchat-gguf", UpdateKind::Requantized),
+            ("mistral", "mistral", UpdateKind::NewerVersion),
+            ("llama2", "llama3", UpdateKind::Successor),
+        ]
+    );
+    assert!(updates.iter().all(|u| u.request.target_dir.is_none()));
+
+    let older = [base("mistral").version("0.8.5").build().unwrap()];
+    assert!(find_updates(&have, &older).is_empty());
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "llm-chain")]
+use llm_chain::options;
+use llm_chain::options::{ModelRef, Options, OptionsCascade};
+use llm_chain::prompt::Data;
+use llm_chain::tokens::Tokenizer;
+use llm_chain::traits::Executor as _;
+use pantry_rs::chain::{self, Executor, DEFAULT_CONTEXT_SIZE};
+use pantry_rs::openai_compat::Stop;
+use pantry_rs::{PantryClient, UserId};
+
+fn executor(options: Options) -> Executor {
+    let pantry = PantryClient::builder()
+        .base_url("http://127.0.0.1:1")
+        .login(UserId::new_v4(), "key".into());
+    Executor::with_client(pantry, options)
+}
+
+#[test]
+fn request_from_options() {
+    let defaults = options!(
+        Model: ModelRef::from_model_name("llama-2-7b-chat"),
+        Temperature: 0.2
+    );
+    let step = options!(
+        Temperature: 0.8,
+        MaxTokens: 64_usize,
+        StopSequence: vec!["\n".to_string()],
+        Stream: true
+    );
+    let options = OptionsCascade::new()
+        .with_options(&defaults)
+        .with_options(&step);
+
+    let request = chain::request(&options);
+    assert_eq!(request.model, "llama-2-7b-chat");
+    assert_eq!(request.temperature, Some(0.8));
+    assert_eq!(request.max_tokens, Some(64));
+    assert_eq!(request.stop, Some(Stop::Many(vec!["\n".into()])));
+    assert!(request.stream);
+    assert!(request.messages.is_empty());
+}
+
+#[test]
+fn token_limits() {
+    let exec = executor(Options::empty().clone());
+    let prompt = Data::Text("a".repeat(40));
+
+    let count = exec.tokens_used(Options::empty(), &prompt).unwrap();
+    assert!(count.has_tokens_remaining());
+    assert_eq!(
+        exec.max_tokens_allowed(Options::empty()),
+        DEFAULT_CONTEXT_SIZE as i32
+    );
+
+    let step = options!(MaxContextSize: 4096_usize);
+    assert_eq!(exec.max_tokens_allowed(&step), 4096);
+}
+
+#[test]
+fn tokenizer_round_trips() {
+    let exec = executor(Options::empty().clone());
+    let tokenizer = exec.get_tokenizer(Options::empty()).unwrap();
+    let tokens = tokenizer.tokenize_str("héllo").unwrap();
+    assert_eq!(tokens.len(), 5);
+    assert_eq!(tokenizer.to_string(tokens).unwrap(), "héllo");
+}
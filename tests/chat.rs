@@ -0,0 +1,151 @@
+use pantry_rs::chat::{ChatMessage, ChatTemplate, PlainTemplate, Role};
+use pantry_rs::interface::LLMStatus;
+use pantry_rs::PromptFormat;
+
+#[test]
+fn plain_template_renders_turns() {
+    let messages = vec![
+        ChatMessage::system("Be brief."),
+        ChatMessage::user("Hi!"),
+        ChatMessage::assistant("Hello!"),
+        ChatMessage::user("How are you?"),
+    ];
+    assert_eq!(
+        PlainTemplate.render(&messages, true),
+        "System: Be brief.\n\nUser: Hi!\nAssistant: Hello!\nUser: How are you?\nAssistant:"
+    );
+    assert_eq!(PlainTemplate.stop_sequences(), vec!["\nUser:".to_string()]);
+}
+
+#[test]
+fn plain_template_is_append_only() {
+    let mut messages = vec![ChatMessage::system("Be brief."), ChatMessage::user("Hi!")];
+    let first = PlainTemplate.render(&messages, true);
+    messages.push(ChatMessage::assistant("Hello!"));
+    messages.push(ChatMessage::user("Bye."));
+    let second = PlainTemplate.render(&messages, true);
+    assert!(second.starts_with(&first));
+}
+
+#[test]
+fn messages_serialize_with_lowercase_roles() {
+    let json = serde_json::to_value(ChatMessage::assistant("ok")).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({"role": "assistant", "content": "ok"})
+    );
+    let message: ChatMessage =
+        serde_json::from_value(serde_json::json!({"role": "user", "content": "hi"})).unwrap();
+    assert_eq!(message.role, Role::User);
+}
+
+fn llm(id: &str, tags: &[&str], config: serde_json::Value) -> LLMStatus {
+    serde_json::from_value(serde_json::json!({
+        "id": id, "family_id": "", "organization": "", "name": id, "homepage": "",
+        "license": "", "description": "", "capabilities": {}, "requirements": "",
+        "tags": tags, "url": "", "local": true, "connector_type": "llmrs",
+        "download_progress": 100.0, "config": config, "parameters": {},
+        "user_parameters": [], "session_parameters": {}, "user_session_parameters": [],
+        "uuid": "6f1e0b9e-8a43-4b0c-9a39-7d0d1f7c6a11", "running": true
+    }))
+    .unwrap()
+}
+
+#[test]
+fn prompt_format_detection() {
+    let none = serde_json::json!({});
+    assert_eq!(
+        PromptFormat::detect(&llm("llama-2-7b-chat", &[], none.clone())),
+        PromptFormat::Llama2
+    );
+    assert_eq!(
+        PromptFormat::detect(&llm("mistral-7b-instruct-v0.1", &[], none.clone())),
+        PromptFormat::Mistral
+    );
+    assert_eq!(
+        PromptFormat::detect(&llm("openhermes-2.5", &[], none.clone())),
+        PromptFormat::ChatML
+    );
+    assert_eq!(
+        PromptFormat::detect(&llm("llama-2-7b", &[], none.clone())),
+        PromptFormat::Plain
+    );
+    // Tags beat names, config beats tags.
+    assert_eq!(
+        PromptFormat::detect(&llm("llama-2-7b-chat", &["Alpaca"], none)),
+        PromptFormat::Alpaca
+    );
+    assert_eq!(
+        PromptFormat::detect(&llm(
+            "llama-2-7b-chat",
+            &["alpaca"],
+            serde_json::json!({"prompt_format": "chat_ml"})
+        )),
+        PromptFormat::ChatML
+    );
+}
+
+#[test]
+fn prompt_formats_are_append_only() {
+    let mut messages = vec![ChatMessage::system("Be brief."), ChatMessage::user("Hi!")];
+    for format in PromptFormat::ALL {
+        let first = format.render(&messages, true);
+        messages.push(ChatMessage::assistant("Hello!"));
+        messages.push(ChatMessage::user("Bye."));
+        let second = format.render(&messages, true);
+        messages.truncate(2);
+        // What the session has seen after the reply, as ChatSession tracks it.
+        let seen = format!(
+            "{}{}Hello!",
+            first,
+            if first.ends_with(':') || first.ends_with("]") {
+                " "
+            } else {
+                ""
+            }
+        );
+        assert!(second.starts_with(&seen), "{}: {:?}", format, second);
+    }
+}
+
+#[test]
+fn chatml_rendering() {
+    let messages = vec![ChatMessage::system("Be brief."), ChatMessage::user("Hi!")];
+    assert_eq!(
+        PromptFormat::ChatML.render(&messages, true),
+        "<|im_start|>system\nBe brief.<|im_end|>\n<|im_start|>user\nHi!<|im_end|>\n<|im_start|>assistant\n"
+    );
+    assert_eq!(
+        PromptFormat::Llama2.render(&messages, true),
+        "[INST] <<SYS>>\nBe brief.\n<</SYS>>\n\nHi! [/INST]"
+    );
+}
+
+#[cfg(feature = "testing")]
+#[tokio::test]
+async fn interrupted_replies_are_recorded() {
+    use futures::stream::StreamExt;
+    use pantry_rs::chat::ChatSession;
+    use pantry_rs::testing::MockPantryServer;
+    use pantry_rs::PromptInput;
+
+    let server = MockPantryServer::start().await.unwrap();
+    server.reply([" Hello", " there,", " how", " are", " you?"]);
+    server.token_delay(std::time::Duration::from_millis(50));
+    let mut chat = ChatSession::new(server.running_session("openchat").await);
+
+    let mut stream = chat.send("Hi!").await.unwrap();
+    stream.next().await.unwrap().unwrap();
+    stream.interrupt().await.unwrap();
+    assert!(stream.is_recorded());
+    let reply = stream.reply().to_string();
+    assert!(reply.starts_with(" Hello") && reply != " Hello there, how are you?");
+    assert_eq!(chat.messages()[1], ChatMessage::assistant(reply.trim()));
+
+    // The next turn carries on from the cut off reply, without replaying it.
+    chat.send_and_collect("Go on.").await.unwrap();
+    match &server.last_prompt().unwrap()[..] {
+        [PromptInput::Text(prompt)] => assert!(!prompt.contains("Hi!"), "{:?}", prompt),
+        other => panic!("unexpected prompt {:?}", other),
+    }
+}
@@ -0,0 +1,237 @@
+#![cfg(all(feature = "cli", feature = "testing"))]
+use pantry_rs::chat::{ChatMessage, Role};
+use pantry_rs::interface::UserPermissions;
+use pantry_rs::testing::{mock_llm, MockPantryServer};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// `pantry` with nothing configured but `config_dir`.
+fn bare_command(config_dir: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_pantry"));
+    command
+        .args(args)
+        .env("XDG_CONFIG_HOME", config_dir)
+        .env_remove("PANTRY_CONFIG")
+        .env_remove("PANTRY_URL")
+        .env_remove("PANTRY_SOCKET")
+        .env_remove("PANTRY_USER_ID")
+        .env_remove("PANTRY_API_KEY");
+    command
+}
+
+fn command(server: &MockPantryServer, config_dir: &Path, args: &[&str]) -> Command {
+    let url = server.base_url().unwrap();
+    bare_command(config_dir, &[&["--url", url], args].concat())
+}
+
+async fn output(mut command: Command) -> (bool, String) {
+    let out = command.output().await.unwrap();
+    (out.status.success(), String::from_utf8(out.stdout).unwrap())
+}
+
+async fn pantry(server: &MockPantryServer, config_dir: &Path, args: &[&str]) -> (bool, String) {
+    output(command(server, config_dir, args)).await
+}
+
+/// Runs `pantry chat` as a user with session permissions, typing `input`.
+async fn chat(server: &MockPantryServer, config_dir: &Path, args: &[&str], input: &str) -> String {
+    let user = server.login(UserPermissions {
+        perm_session: true,
+        ..Default::default()
+    });
+    let mut child = command(server, config_dir, &[&["chat"], args].concat())
+        .env("PANTRY_USER_ID", user.user_id.to_string())
+        .env("PANTRY_API_KEY", &user.api_key)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(input.as_bytes()).await.unwrap();
+    drop(stdin);
+    let out = child.wait_with_output().await.unwrap();
+    assert!(out.status.success());
+    String::from_utf8(out.stdout).unwrap()
+}
+
+fn temp_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("pantry-rs-cli-{}", uuid::Uuid::new_v4()))
+}
+
+fn read_transcript(path: &Path) -> Vec<ChatMessage> {
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn register_then_manage_llms() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.add_llm(mock_llm("openchat"));
+    let config_dir = temp_dir();
+
+    let (ok, _) = pantry(&server, &config_dir, &["llms", "list"]).await;
+    assert!(!ok, "no credentials yet");
+    let (ok, out) = pantry(&server, &config_dir, &["--json", "doctor"]).await;
+    assert!(!ok);
+    let report: Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(report["tcp"]["status"], "ok");
+    assert_eq!(report["credentials"]["status"], "failed");
+
+    let (ok, out) = pantry(
+        &server,
+        &config_dir,
+        &[
+            "--json",
+            "register",
+            "cli-test",
+            "--perm",
+            "load-llm",
+            "--perm",
+            "view-llms",
+            "--perm",
+            "session",
+        ],
+    )
+    .await;
+    assert!(ok);
+    let registered: Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(registered["request"]["accepted"], true);
+
+    let (ok, out) = pantry(&server, &config_dir, &["doctor"]).await;
+    assert!(ok);
+    assert!(out.contains("credentials  ok: granted load_llm, session, view_llms"));
+
+    let (ok, out) = pantry(&server, &config_dir, &["--json", "llms", "list"]).await;
+    assert!(ok);
+    let llms: Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(llms[0]["id"], "openchat");
+
+    let (ok, out) = pantry(&server, &config_dir, &["llm", "load", "openchat"]).await;
+    assert!(ok);
+    assert!(out.starts_with("Loaded openchat"));
+
+    let (ok, out) = pantry(&server, &config_dir, &["prompt", "Hi"]).await;
+    assert!(ok);
+    assert_eq!(out, "Hello, world!\n");
+
+    let (ok, out) = pantry(
+        &server,
+        &config_dir,
+        &["--json", "prompt", "Hi", "--llm", "openchat"],
+    )
+    .await;
+    assert!(ok);
+    let prompted: Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(prompted["text"], "Hello, world!");
+    let deleted = server
+        .calls()
+        .iter()
+        .filter(|call| *call == "delete_session")
+        .count();
+    assert_eq!(deleted, 2);
+
+    // The saved credentials remember where they're from.
+    let (ok, out) = output(bare_command(&config_dir, &["--json", "llms", "list"])).await;
+    assert!(ok);
+    let llms: Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(llms[0]["id"], "openchat");
+
+    std::fs::remove_dir_all(&config_dir).unwrap();
+}
+
+#[tokio::test]
+async fn socket_beats_configured_url() {
+    let server = MockPantryServer::start().await.unwrap();
+    let config_dir = temp_dir();
+    let socket = config_dir.join("missing.sock");
+
+    let mut doctor = bare_command(
+        &config_dir,
+        &["--json", "--socket", socket.to_str().unwrap(), "doctor"],
+    );
+    doctor.env("PANTRY_URL", server.base_url().unwrap());
+    let (ok, out) = output(doctor).await;
+    assert!(!ok);
+    let report: Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(report["socket"]["status"], "failed");
+}
+
+#[tokio::test]
+async fn chat_saves_transcript() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.add_running("openchat");
+    let config_dir = temp_dir();
+    let transcript = config_dir.join("chat.json");
+    let saved = config_dir.join("saved.json");
+
+    let input = format!("Hi\n/save {}\n/reset\n/quit\n", saved.display());
+    let out = chat(
+        &server,
+        &config_dir,
+        &[
+            "--template",
+            "chatml",
+            "--system",
+            "Be brief.",
+            "--transcript",
+            transcript.to_str().unwrap(),
+        ],
+        &input,
+    )
+    .await;
+    assert!(out.contains("Hello, world!"));
+    assert!(out.contains("(conversation reset)"));
+
+    assert_eq!(
+        read_transcript(&saved),
+        vec![
+            ChatMessage::system("Be brief."),
+            ChatMessage::user("Hi"),
+            ChatMessage::assistant("Hello, world!"),
+        ]
+    );
+    assert_eq!(
+        read_transcript(&transcript),
+        vec![ChatMessage::system("Be brief.")]
+    );
+
+    // Picks the conversation back up.
+    let out = chat(
+        &server,
+        &config_dir,
+        &["--transcript", saved.to_str().unwrap()],
+        "Again\n",
+    )
+    .await;
+    assert!(out.contains("Hello, world!"));
+    assert_eq!(read_transcript(&saved).len(), 5);
+
+    std::fs::remove_dir_all(&config_dir).unwrap();
+}
+
+#[tokio::test]
+async fn chat_interrupts() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.add_running("openchat");
+    server.token_delay(Duration::from_millis(200));
+    let config_dir = temp_dir();
+    let transcript = config_dir.join("chat.json");
+
+    let out = chat(
+        &server,
+        &config_dir,
+        &["--transcript", transcript.to_str().unwrap()],
+        "Hi\n/interrupt\n",
+    )
+    .await;
+    assert!(out.contains("(interrupted)"));
+    assert!(!out.contains("world"));
+    assert!(server.calls().contains(&"interrupt_session".to_string()));
+    let saved = read_transcript(&transcript);
+    assert_eq!(saved[0], ChatMessage::user("Hi"));
+    assert_eq!(saved[1].role, Role::Assistant);
+    assert!(!saved[1].content.contains("world"));
+}
@@ -0,0 +1,121 @@
+use pantry_rs::config::{ClientConfig, HostConfig};
+use uuid::Uuid;
+
+fn write_config(contents: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("pantry-rs-client-{}.toml", Uuid::new_v4()));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn reads_toml_file() {
+    let path = write_config(
+        r#"
+url = "http://localhost:9404"
+user_id = "1d2c3b4a-5e6f-4a1b-8c9d-0e1f2a3b4c5d"
+api_key = "secret"
+timeout = 30
+"#,
+    );
+    let config = ClientConfig::from_file(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(config.url.as_deref(), Some("http://localhost:9404"));
+    assert_eq!(config.api_key.as_deref(), Some("secret"));
+    assert_eq!(config.timeout, Some(30));
+    assert_eq!(config.socket, None);
+    assert!(!format!("{:?}", config).contains("secret"));
+}
+
+#[test]
+fn rejects_typos() {
+    let path = write_config("api-key = \"secret\"\n");
+    let result = ClientConfig::from_file(&path);
+    std::fs::remove_file(path).unwrap();
+    assert!(result.is_err());
+}
+
+#[test]
+fn earlier_settings_win() {
+    let env = ClientConfig {
+        api_key: Some("from env".into()),
+        ..Default::default()
+    };
+    let file = ClientConfig {
+        api_key: Some("from file".into()),
+        url: Some("http://localhost:9404".into()),
+        ..Default::default()
+    };
+    let config = env.or(file);
+    assert_eq!(config.api_key.as_deref(), Some("from env"));
+    assert_eq!(config.url.as_deref(), Some("http://localhost:9404"));
+}
+
+#[test]
+fn reads_host_settings() {
+    let path = write_config(
+        r#"
+url = "http://10.0.0.7:9404/"
+
+[hosts."http://10.0.0.7:9404"]
+user_id = "1d2c3b4a-5e6f-4a1b-8c9d-0e1f2a3b4c5d"
+api_key = "host key"
+secret = "psk"
+secret_header = "X-Gateway-Secret"
+
+[hosts."http://10.0.0.8:9404"]
+bearer_token = "token"
+"#,
+    );
+    let config = ClientConfig::from_file(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    let host = config.host().unwrap();
+    assert_eq!(
+        host.auth().unwrap().to_headers(),
+        [("X-Gateway-Secret".to_string(), "psk".to_string())]
+    );
+    let (_, api_key) = config.credentials().unwrap();
+    assert_eq!(api_key, "host key");
+
+    let top_level = ClientConfig {
+        api_key: Some("top key".into()),
+        ..config.clone()
+    };
+    assert_eq!(top_level.credentials().unwrap().1, "top key");
+    let debug = format!("{:?}", top_level);
+    for secret in ["top key", "host key", "psk"] {
+        assert!(!debug.contains(secret), "{}", debug);
+    }
+
+    let elsewhere = ClientConfig {
+        url: Some("http://10.0.0.9:9404".into()),
+        ..config
+    };
+    assert!(elsewhere.host().is_none());
+    assert!(elsewhere.credentials().is_none());
+}
+
+#[test]
+fn merges_hosts() {
+    let host = |key: &str| HostConfig {
+        api_key: Some(key.into()),
+        ..Default::default()
+    };
+    let env = ClientConfig {
+        hosts: [("http://a".to_string(), host("env"))].into(),
+        ..Default::default()
+    };
+    let file = ClientConfig {
+        hosts: [
+            ("http://a".to_string(), host("file")),
+            ("http://b".to_string(), host("file")),
+        ]
+        .into(),
+        ..Default::default()
+    };
+    let config = env.or(file);
+    assert_eq!(config.hosts["http://a"], host("env"));
+    assert_eq!(config.hosts["http://b"], host("file"));
+    assert!(HostConfig::default().auth().is_none());
+}
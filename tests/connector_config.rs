@@ -0,0 +1,112 @@
+use pantry_rs::interface::{LLMConnectorType, LLMRegistryEntry};
+use pantry_rs::{
+    ConnectorConfig, GenericAPIConfig, LlmrsConfig, ModelArchitecture, OpenAIConfig, PantryError,
+};
+use serde_json::json;
+
+#[test]
+fn builds_llmrs_config() {
+    let entry = LLMRegistryEntry::builder("openchat", LLMConnectorType::LLMrs)
+        .url("https://example.com/model.bin")
+        .context_length(4096)
+        .connector_config(LlmrsConfig {
+            vocabulary_repository: Some("openchat/openchat_v3.2".into()),
+            ..LlmrsConfig::new(ModelArchitecture::Llama)
+        })
+        .build()
+        .unwrap();
+    assert_eq!(entry.config["model_architecture"], "llama");
+    assert_eq!(entry.config["context_length"], 4096);
+
+    let ConnectorConfig::LLMrs(config) = entry.connector_config().unwrap() else {
+        panic!("not an LLMrs config");
+    };
+    assert_eq!(config.model_architecture, ModelArchitecture::Llama);
+    assert_eq!(config.context_length, Some(4096));
+    assert_eq!(
+        config.vocabulary_repository.as_deref(),
+        Some("openchat/openchat_v3.2")
+    );
+}
+
+#[test]
+fn keeps_unknown_keys() {
+    let entry = LLMRegistryEntry::builder("openchat", LLMConnectorType::LLMrs)
+        .url("https://example.com/model.bin")
+        .config("model_architecture", "rwkv")
+        .config("gpu_layers", 32)
+        .build()
+        .unwrap();
+    let config = entry.connector_config().unwrap();
+    let ConnectorConfig::LLMrs(llmrs) = &config else {
+        panic!("not an LLMrs config");
+    };
+    assert_eq!(
+        llmrs.model_architecture,
+        ModelArchitecture::Other("rwkv".into())
+    );
+    assert_eq!(llmrs.extra["gpu_layers"], 32);
+    assert_eq!(config.to_map(), entry.config);
+}
+
+#[test]
+fn rejects_mistyped_keys() {
+    let res = LLMRegistryEntry::builder("openchat", LLMConnectorType::LLMrs)
+        .url("https://example.com/model.bin")
+        .config("model_architecture", 7)
+        .build();
+    assert!(matches!(res, Err(PantryError::InvalidRegistryEntry(_))));
+
+    let res = LLMRegistryEntry::builder("openchat", LLMConnectorType::LLMrs)
+        .url("https://example.com/model.bin")
+        .config("model_architecture", "llama")
+        .config("context_length", "lots")
+        .build();
+    assert!(matches!(res, Err(PantryError::InvalidRegistryEntry(_))));
+
+    let res = LLMRegistryEntry::builder("gpt", LLMConnectorType::OpenAI)
+        .config("api_key", json!({"key": "sk"}))
+        .build();
+    assert!(matches!(res, Err(PantryError::InvalidRegistryEntry(_))));
+}
+
+#[test]
+fn switches_connectors() {
+    let mut entry = LLMRegistryEntry::builder("gpt", LLMConnectorType::GenericAPI)
+        .url("https://example.com/v1")
+        .connector_config(GenericAPIConfig {
+            model: Some("mixtral".into()),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    assert_eq!(entry.config["model"], "mixtral");
+
+    entry.set_connector_config(OpenAIConfig {
+        api_key: Some("sk-test".into()),
+        api_base: Some("https://api.together.xyz/v1".into()),
+        ..Default::default()
+    });
+    assert!(matches!(entry.connector_type, LLMConnectorType::OpenAI));
+    assert!(!entry.config.contains_key("model"));
+    let ConnectorConfig::OpenAI(config) = entry.connector_config().unwrap() else {
+        panic!("not an OpenAI config");
+    };
+    assert_eq!(config.api_key.as_deref(), Some("sk-test"));
+}
+
+#[test]
+fn openai_entries() {
+    let entry = LLMRegistryEntry::openai("gpt-4")
+        .context_length(8192)
+        .build()
+        .unwrap();
+    assert_eq!(entry.id, "gpt-4");
+    assert!(!entry.local);
+    assert!(!entry.config.contains_key("api_key"));
+    let ConnectorConfig::OpenAI(config) = entry.connector_config().unwrap() else {
+        panic!("not an OpenAI config");
+    };
+    assert_eq!(config.model.as_deref(), Some("gpt-4"));
+    assert_eq!(config.context_length, Some(8192));
+}
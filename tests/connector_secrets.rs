@@ -0,0 +1,53 @@
+#![cfg(feature = "testing")]
+use pantry_rs::interface::{LLMConnectorType, LLMRegistryEntry, UserPermissions};
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::PantryError;
+
+fn perms() -> UserPermissions {
+    UserPermissions {
+        perm_download_llm: true,
+        perm_view_llms: true,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn keeps_keys_out_of_the_config() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.login(perms());
+
+    pantry
+        .set_connector_secret(LLMConnectorType::OpenAI, "sk-test")
+        .await
+        .unwrap();
+    assert_eq!(
+        server.connector_secret(LLMConnectorType::OpenAI).as_deref(),
+        Some("sk-test")
+    );
+    assert!(server
+        .connector_secret(LLMConnectorType::GenericAPI)
+        .is_none());
+
+    let entry = LLMRegistryEntry::openai("gpt-4").build().unwrap();
+    pantry.download_llm(entry).await.unwrap();
+    let llm = &server.llms()[0];
+    assert_eq!(llm.config["model"], "gpt-4");
+    assert!(!llm.config.contains_key("api_key"));
+}
+
+#[tokio::test]
+async fn needs_download_permission() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.login(UserPermissions::default());
+    let res = pantry
+        .set_connector_secret(LLMConnectorType::OpenAI, "sk-test")
+        .await;
+    assert!(res.is_err());
+    assert!(server.connector_secret(LLMConnectorType::OpenAI).is_none());
+
+    let res = server
+        .login(perms())
+        .set_connector_secret(LLMConnectorType::OpenAI, " ")
+        .await;
+    assert!(matches!(res, Err(PantryError::OtherFailure(_))));
+}
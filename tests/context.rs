@@ -0,0 +1,92 @@
+use pantry_rs::chat::{ChatMessage, Role};
+use pantry_rs::context::{estimate_tokens, TruncationStrategy};
+use pantry_rs::PantryError;
+
+fn conversation() -> Vec<ChatMessage> {
+    vec![
+        ChatMessage::system("sys"),
+        ChatMessage::user("one"),
+        ChatMessage::assistant("two"),
+        ChatMessage::user("three"),
+        ChatMessage::assistant("four"),
+        ChatMessage::user("five"),
+    ]
+}
+
+// One token per message keeps the arithmetic obvious.
+fn count(messages: &[ChatMessage]) -> usize {
+    messages.len()
+}
+
+fn contents(messages: &[ChatMessage]) -> Vec<&str> {
+    messages.iter().map(|m| m.content.as_str()).collect()
+}
+
+#[test]
+fn estimates_four_chars_per_token() {
+    assert_eq!(estimate_tokens(""), 0);
+    assert_eq!(estimate_tokens("abcd"), 1);
+    assert_eq!(estimate_tokens("abcde"), 2);
+}
+
+#[tokio::test]
+async fn drop_oldest_keeps_system_and_latest() {
+    let mut messages = conversation();
+    let changed = TruncationStrategy::DropOldest
+        .apply(&mut messages, count, 3)
+        .await
+        .unwrap();
+    assert!(changed);
+    assert_eq!(contents(&messages), vec!["sys", "four", "five"]);
+
+    // Nothing to do if it fits.
+    let mut messages = conversation();
+    assert!(!TruncationStrategy::DropOldest
+        .apply(&mut messages, count, 6)
+        .await
+        .unwrap());
+    assert_eq!(messages.len(), 6);
+}
+
+#[tokio::test]
+async fn sliding_window_keeps_recent_messages() {
+    let mut messages = conversation();
+    TruncationStrategy::SlidingWindow(2)
+        .apply(&mut messages, count, 5)
+        .await
+        .unwrap();
+    assert_eq!(contents(&messages), vec!["sys", "four", "five"]);
+}
+
+#[tokio::test]
+async fn summarize_replaces_dropped_messages() {
+    let mut messages = conversation();
+    let strategy = TruncationStrategy::summarize(|dropped: Vec<ChatMessage>| async move {
+        Ok(contents(&dropped).join(","))
+    });
+    strategy.apply(&mut messages, count, 4).await.unwrap();
+    assert_eq!(messages.len(), 4);
+    assert_eq!(messages[1].role, Role::System);
+    assert_eq!(
+        messages[1].content,
+        "Summary of the conversation so far: one,two,three"
+    );
+    assert_eq!(contents(&messages[2..]), vec!["four", "five"]);
+}
+
+#[tokio::test]
+async fn overflow_is_an_error() {
+    let mut messages = conversation();
+    let err = TruncationStrategy::Error
+        .apply(&mut messages, count, 3)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PantryError::ContextOverflow(6, 3)));
+
+    // Can't drop the system prompt or the message being sent.
+    let err = TruncationStrategy::DropOldest
+        .apply(&mut messages, count, 1)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PantryError::ContextOverflow(2, 1)));
+}
@@ -0,0 +1,33 @@
+use pantry_rs::{PantryCredentials, UserId};
+use uuid::Uuid;
+
+#[test]
+fn file_round_trip() {
+    let dir = std::env::temp_dir().join(format!("pantry-rs-test-{}", Uuid::new_v4()));
+    let path = dir.join("nested").join("creds.json");
+    assert_eq!(PantryCredentials::load_from(&path).unwrap(), None);
+
+    let creds = PantryCredentials::new(
+        UserId::new_v4(),
+        "secret".into(),
+        Some("http://localhost:9404".into()),
+    );
+    assert!(!format!("{:?}", creds).contains("secret"));
+    creds.save_to(&path).unwrap();
+    assert_eq!(PantryCredentials::load_from(&path).unwrap(), Some(creds));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn app_names_stay_in_the_config_dir() {
+    assert!(PantryCredentials::default_path("../evil").is_err());
+    assert!(PantryCredentials::default_path("").is_err());
+}
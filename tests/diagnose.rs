@@ -0,0 +1,94 @@
+use pantry_rs::diagnose::Check;
+use pantry_rs::{PantryClient, RetryPolicy, UserId};
+
+#[tokio::test]
+async fn unreachable() {
+    let pantry = PantryClient::builder()
+        .base_url("http://127.0.0.1:1")
+        .retry(RetryPolicy::none())
+        .login(UserId::new_v4(), "key".into());
+
+    let report = pantry.diagnose().await;
+    assert!(matches!(report.socket, Check::Skipped(_)));
+    assert!(report.tcp.is_failed());
+    assert!(report.credentials.is_failed());
+    assert!(report.permissions.is_none());
+    assert!(!report.is_healthy());
+}
+
+#[cfg(feature = "testing")]
+mod mock {
+    use pantry_rs::diagnose::Check;
+    use pantry_rs::interface::UserPermissions;
+    use pantry_rs::testing::MockPantryServer;
+    use pantry_rs::UserId;
+    use uuid::Uuid;
+
+    fn perms() -> UserPermissions {
+        UserPermissions {
+            perm_session: true,
+            perm_view_llms: true,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn healthy_over_tcp() {
+        let server = MockPantryServer::start().await.unwrap();
+        server.add_running("openchat");
+
+        let report = server.login(perms()).diagnose().await;
+        assert!(matches!(report.socket, Check::Skipped(_)));
+        assert!(report.tcp.is_ok());
+        assert!(report.server.is_ok());
+        assert_eq!(
+            report.credentials,
+            Check::Ok("granted session, view_llms".into())
+        );
+        assert_eq!(report.permissions, Some(perms()));
+        assert_eq!(report.llms, Check::Ok("running openchat".into()));
+        assert_eq!(report.running_llms.len(), 1);
+        assert!(report.is_healthy());
+    }
+
+    #[tokio::test]
+    async fn bad_credentials() {
+        let server = MockPantryServer::start().await.unwrap();
+
+        let report = server
+            .builder()
+            .login(UserId::new_v4(), "wrong".into())
+            .diagnose()
+            .await;
+        assert!(report.tcp.is_ok());
+        assert!(report.credentials.is_failed());
+        assert!(report.llms.is_failed());
+        assert!(!report.is_healthy());
+    }
+
+    #[tokio::test]
+    async fn missing_permission() {
+        let server = MockPantryServer::start().await.unwrap();
+
+        let report = server.login(UserPermissions::default()).diagnose().await;
+        assert_eq!(
+            report.credentials,
+            Check::Ok("granted no permissions".into())
+        );
+        assert!(report.llms.is_failed());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn healthy_over_socket() {
+        let socket =
+            std::env::temp_dir().join(format!("pantry-rs-diagnose-{}.sock", Uuid::new_v4()));
+        let server = MockPantryServer::start_unix(&socket).await.unwrap();
+
+        let report = server.login(perms()).diagnose().await;
+        assert!(report.socket.is_ok());
+        assert!(report.credentials.is_ok());
+        assert_eq!(report.llms, Check::Ok("none running".into()));
+        assert!(report.is_healthy());
+    }
+}
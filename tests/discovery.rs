@@ -0,0 +1,74 @@
+#![cfg(all(feature = "discovery", feature = "testing"))]
+use mdns_sd::ServiceInfo;
+use pantry_rs::discovery::{discover, DiscoveredPantry, SERVICE_TYPE};
+use pantry_rs::interface::UserPermissions;
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::PantryError;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::{Duration, Instant};
+
+fn service(addresses: &[IpAddr], port: u16, properties: &[(&str, &str)]) -> ServiceInfo {
+    ServiceInfo::new(
+        SERVICE_TYPE,
+        "workstation",
+        "workstation.local.",
+        addresses,
+        port,
+        properties,
+    )
+    .unwrap()
+}
+
+#[test]
+fn prefers_ipv4() {
+    let v6 = IpAddr::V6(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1));
+    let v4 = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20));
+    let found = DiscoveredPantry::from_service_info(&service(&[v6, v4], 9404, &[])).unwrap();
+    assert_eq!(found.name, "workstation._pantry._tcp.local.");
+    assert_eq!(found.hostname, "workstation.local.");
+    assert_eq!(found.base_url, "http://192.168.1.20:9404");
+    assert_eq!(found.addresses, [v4, v6]);
+    assert!(found.server_info.is_none());
+
+    let found = DiscoveredPantry::from_service_info(&service(&[v6], 9404, &[])).unwrap();
+    assert_eq!(found.base_url, "http://[fe80::1]:9404");
+}
+
+#[test]
+fn honors_scheme() {
+    let v4 = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 7));
+    let found =
+        DiscoveredPantry::from_service_info(&service(&[v4], 443, &[("scheme", "https")])).unwrap();
+    assert_eq!(found.base_url, "https://10.0.0.7:443");
+}
+
+#[tokio::test]
+async fn connects_to_what_was_found() {
+    let server = MockPantryServer::start().await.unwrap();
+    let port: u16 = server
+        .base_url()
+        .unwrap()
+        .rsplit(':')
+        .next()
+        .unwrap()
+        .trim_end_matches('/')
+        .parse()
+        .unwrap();
+    let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    let found = DiscoveredPantry::from_service_info(&service(&[localhost], port, &[])).unwrap();
+
+    let plain = server.login(UserPermissions::default());
+    let pantry = found.builder().login(plain.user_id, plain.api_key);
+    assert!(pantry.server_info().await.unwrap().is_compatible());
+}
+
+#[tokio::test]
+async fn browses_for_the_timeout() {
+    let started = Instant::now();
+    match discover(Duration::from_millis(200)).await {
+        // Whatever happens to be on the network.
+        Ok(_) => assert!(started.elapsed() >= Duration::from_millis(200)),
+        // No multicast in some sandboxes.
+        Err(e) => assert!(matches!(e, PantryError::DiscoveryError(_))),
+    }
+}
@@ -0,0 +1,86 @@
+use hyper::StatusCode;
+use pantry_rs::PantryError;
+
+#[test]
+fn maps_status_codes() {
+    assert!(matches!(
+        PantryError::from_response(StatusCode::UNAUTHORIZED, "bad key"),
+        PantryError::InvalidApiKey(msg) if msg == "bad key"
+    ));
+    assert!(matches!(
+        PantryError::from_response(StatusCode::FORBIDDEN, "nope"),
+        PantryError::PermissionDenied(_)
+    ));
+    assert!(matches!(
+        PantryError::from_response(StatusCode::IM_A_TEAPOT, "tea"),
+        PantryError::ApiError(StatusCode::IM_A_TEAPOT, _)
+    ));
+}
+
+#[test]
+fn json_code_overrides_status() {
+    let err = PantryError::from_response(
+        StatusCode::BAD_REQUEST,
+        r#"{"code": "llm_not_running", "message": "load it first"}"#,
+    );
+    assert!(matches!(err, PantryError::LlmNotRunning(msg) if msg == "load it first"));
+}
+
+#[test]
+fn maps_not_found_by_endpoint() {
+    let not_found = |endpoint| {
+        PantryError::from_endpoint_response(endpoint, StatusCode::NOT_FOUND, "Not Found")
+    };
+    assert!(matches!(
+        not_found("/load_llm"),
+        PantryError::LlmNotFound(_)
+    ));
+    assert!(matches!(
+        not_found("/delete_session"),
+        PantryError::SessionNotFound(_)
+    ));
+    assert!(matches!(
+        not_found("/get_request_status"),
+        PantryError::NotFound(_)
+    ));
+    assert!(matches!(
+        PantryError::from_response(StatusCode::NOT_FOUND, ""),
+        PantryError::NotFound(_)
+    ));
+
+    // A generic code goes by endpoint too, a specific one wins.
+    let err = PantryError::from_endpoint_response(
+        "/interrupt_session",
+        StatusCode::NOT_FOUND,
+        r#"{"code": "not_found", "message": "no session"}"#,
+    );
+    assert!(matches!(err, PantryError::SessionNotFound(msg) if msg == "no session"));
+    let err = PantryError::from_endpoint_response(
+        "/create_session_id",
+        StatusCode::NOT_FOUND,
+        r#"{"code": "session_not_found", "message": "gone"}"#,
+    );
+    assert!(matches!(err, PantryError::SessionNotFound(_)));
+}
+
+#[test]
+fn retryable_errors() {
+    assert!(PantryError::from_response(StatusCode::SERVICE_UNAVAILABLE, "").is_retryable());
+    assert!(!PantryError::from_response(StatusCode::FORBIDDEN, "").is_retryable());
+}
+
+#[cfg(feature = "testing")]
+#[tokio::test]
+async fn missing_sessions_are_not_missing_llms() {
+    use pantry_rs::interface::UserPermissions;
+    use pantry_rs::testing::MockPantryServer;
+    use pantry_rs::SessionId;
+
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.login(UserPermissions {
+        perm_session: true,
+        ..Default::default()
+    });
+    let res = pantry.delete_session(SessionId::new_v4()).await;
+    assert!(matches!(res, Err(PantryError::SessionNotFound(_))));
+}
@@ -0,0 +1,132 @@
+#![cfg(feature = "testing")]
+use pantry_rs::interface::{FinishReason, UserPermissions};
+use pantry_rs::testing::{mock_llm, MockPantryServer};
+use pantry_rs::{ClientEvent, PantryClient, RetryPolicy, UserId};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio::sync::broadcast::Receiver;
+
+fn drain(events: &mut Receiver<ClientEvent>) -> Vec<ClientEvent> {
+    let mut drained = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        drained.push(event);
+    }
+    drained
+}
+
+#[tokio::test]
+async fn publishes_requests() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.auto_approve(false);
+    let pantry = server.login(UserPermissions::default());
+    let mut events = pantry.client_events();
+
+    let status = pantry
+        .request_permissions(UserPermissions {
+            perm_session: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(server.approve(status.id));
+    pantry
+        .await_request(status.id, Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    let events = drain(&mut events);
+    assert_eq!(events.len(), 2);
+    assert!(matches!(&events[0], ClientEvent::RequestSubmitted(s) if s.id == status.id));
+    assert!(
+        matches!(&events[1], ClientEvent::RequestDecided(s) if s.id == status.id && s.accepted)
+    );
+}
+
+#[tokio::test]
+async fn publishes_sessions_and_prompts() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.reply(["Hello", " there"]);
+    let pantry = server.running_client("openchat");
+    let mut events = pantry.client_events();
+
+    let sess = pantry.create_session(HashMap::new()).await.unwrap();
+    sess.prompt_and_collect("Hi".into(), HashMap::new())
+        .await
+        .unwrap();
+
+    let events = drain(&mut events);
+    assert_eq!(events.len(), 3);
+    assert!(
+        matches!(events[0], ClientEvent::SessionCreated { session_id, .. } if session_id == sess.id)
+    );
+    assert!(
+        matches!(events[1], ClientEvent::PromptStarted { session_id, .. } if session_id == sess.id)
+    );
+    assert!(matches!(
+        events[2],
+        ClientEvent::PromptFinished {
+            reason: FinishReason::Stop,
+            tokens: 2,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn publishes_download_progress() {
+    let server = MockPantryServer::start().await.unwrap();
+    let llm = mock_llm("openchat");
+    let llm_uuid = llm.uuid;
+    server.add_llm(llm);
+    let pantry = server.login(UserPermissions {
+        perm_view_llms: true,
+        ..Default::default()
+    });
+    let mut events = pantry.client_events();
+
+    pantry.await_download(llm_uuid, |_| {}).await.unwrap();
+
+    match &drain(&mut events)[..] {
+        [ClientEvent::DownloadProgress {
+            llm_uuid: uuid,
+            progress,
+        }] => {
+            assert_eq!(*uuid, llm_uuid);
+            assert_eq!(progress.percent, 100.0);
+        }
+        events => panic!("unexpected events: {:?}", events),
+    }
+}
+
+#[tokio::test]
+async fn publishes_errors() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.login(UserPermissions::default());
+    let mut events = pantry.client_events();
+
+    assert!(pantry.get_running_llms().await.is_err());
+
+    match &drain(&mut events)[..] {
+        [ClientEvent::Error { endpoint, .. }] => assert_eq!(endpoint, "/get_running_llms"),
+        events => panic!("unexpected events: {:?}", events),
+    }
+}
+
+#[tokio::test]
+async fn copies_share_the_bus() {
+    // Nothing listens on port 1.
+    let pantry = PantryClient::builder()
+        .base_url("http://127.0.0.1:1")
+        .retry(RetryPolicy::none())
+        .login(UserId::new_v4(), "key".into());
+    let mut events = pantry.client_events();
+
+    let _ = pantry
+        .with_timeout(Some(Duration::from_secs(1)))
+        .server_info()
+        .await;
+
+    assert!(matches!(events.try_recv(), Ok(ClientEvent::Error { .. })));
+    assert!(matches!(events.try_recv(), Err(TryRecvError::Empty)));
+}
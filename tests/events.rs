@@ -0,0 +1,87 @@
+use pantry_rs::interface::{FinishReason, LLMEventInternal, LLMHistoryItem};
+use serde_json::json;
+
+#[test]
+fn completion_without_finish_reason() {
+    let event: LLMEventInternal =
+        serde_json::from_value(json!({"type": "PromptCompletion", "previous": "Hi"})).unwrap();
+    match event {
+        LLMEventInternal::PromptCompletion {
+            previous,
+            finish_reason,
+            completion_tokens,
+            ..
+        } => {
+            assert_eq!(previous, "Hi");
+            assert_eq!(finish_reason, None);
+            assert_eq!(completion_tokens, None);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn completion_with_finish_reason() {
+    let event: LLMEventInternal = serde_json::from_value(json!({
+        "type": "PromptCompletion",
+        "previous": "Hi",
+        "finish_reason": "length",
+        "prompt_tokens": 12,
+        "completion_tokens": 1
+    }))
+    .unwrap();
+    assert!(matches!(
+        event,
+        LLMEventInternal::PromptCompletion {
+            finish_reason: Some(FinishReason::Length),
+            prompt_tokens: Some(12),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn history_item() {
+    let item: LLMHistoryItem = serde_json::from_value(json!({
+        "id": "0b8f3a52-86b6-4b3f-9d3d-5b0f6c8a7e21",
+        "llm_uuid": "6f1e0b9e-8a43-4b0c-9a39-7d0d1f7c6a11",
+        "session_id": "1d2c3b4a-5e6f-4a1b-8c9d-0e1f2a3b4c5d",
+        "call_timestamp": "2023-07-01T12:00:00Z",
+        "updated_timestamp": "2023-07-01T12:00:05Z",
+        "complete": true,
+        "parameters": {"temperature": 0.7},
+        "input": "Hello",
+        "output": " there"
+    }))
+    .unwrap();
+    assert!(item.complete);
+    assert_eq!(item.input, "Hello");
+    assert!(item.updated_timestamp > item.call_timestamp);
+}
+
+#[cfg(feature = "testing")]
+#[tokio::test]
+async fn replies_ending_at_max_tokens_complete() {
+    use pantry_rs::testing::MockPantryServer;
+    use pantry_rs::InferenceParams;
+
+    let server = MockPantryServer::start().await.unwrap();
+    let sess = server.running_session("openchat").await;
+    server.reply(["One", " two", " three"]);
+    let interrupted = || server.calls().contains(&"interrupt_session".to_string());
+
+    let params = InferenceParams::new().max_tokens(3);
+    let stream = sess.prompt_session("Count".into(), params).await.unwrap();
+    let completion = stream.collect_completion().await.unwrap();
+    assert_eq!(completion.text, "One two three");
+    assert_eq!(completion.finish_reason, FinishReason::Stop);
+    assert!(!interrupted());
+
+    let params = InferenceParams::new().max_tokens(2);
+    let stream = sess.prompt_session("Count".into(), params).await.unwrap();
+    let completion = stream.collect_completion().await.unwrap();
+    assert_eq!(completion.text, "One two");
+    assert_eq!(completion.finish_reason, FinishReason::Length);
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    assert!(interrupted());
+}
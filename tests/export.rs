@@ -0,0 +1,46 @@
+#![cfg(feature = "testing")]
+use pantry_rs::interface::{ExportMode, UserPermissions};
+use pantry_rs::testing::{mock_llm, MockPantryServer};
+use pantry_rs::PantryError;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn exports_into_a_directory() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.add_llm(mock_llm("openchat"));
+    let pantry = server.login(UserPermissions {
+        perm_bare_model: true,
+        ..Default::default()
+    });
+    let sandbox = std::env::temp_dir().join(format!("pantry-rs-export-{}", Uuid::new_v4()));
+    std::fs::create_dir(&sandbox).unwrap();
+
+    let (model, path) = pantry
+        .export_bare_model(
+            "openchat".into(),
+            sandbox.to_string_lossy(),
+            ExportMode::Hardlink,
+        )
+        .await
+        .unwrap();
+    assert_eq!(model.id, "openchat");
+    assert_eq!(path, sandbox.join("openchat.bin").to_string_lossy());
+    assert!(std::path::Path::new(&path).exists());
+    std::fs::remove_dir_all(sandbox).unwrap();
+
+    let res = pantry
+        .export_bare_model("openchat".into(), " ", ExportMode::Copy)
+        .await;
+    assert!(matches!(res, Err(PantryError::OtherFailure(_))));
+}
+
+#[tokio::test]
+async fn needs_bare_model_permission() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.add_llm(mock_llm("openchat"));
+    let pantry = server.login(UserPermissions::default());
+    let res = pantry
+        .export_bare_model("openchat".into(), "/tmp/x.bin", ExportMode::Symlink)
+        .await;
+    assert!(matches!(res, Err(PantryError::PermissionDenied(_))));
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "testing")]
+use pantry_rs::chat::{ChatSession, Failover};
+use pantry_rs::interface::UserPermissions;
+use pantry_rs::testing::{mock_llm, MockPantryServer};
+use pantry_rs::{PantryClient, PantryError};
+use std::collections::HashMap;
+
+async fn setup(server: &MockPantryServer) -> (PantryClient, ChatSession) {
+    for id in ["openhermes", "mistral-7b"] {
+        let mut llm = mock_llm(id);
+        llm.running = id == "openhermes";
+        server.add_llm(llm);
+    }
+    server.reply(["Hello!"]);
+    let pantry = server.login(UserPermissions {
+        perm_session: true,
+        perm_load_llm: true,
+        perm_unload_llm: true,
+        ..Default::default()
+    });
+    let llm = server.llms()[0].uuid;
+    let sess = pantry.create_session_id(llm, HashMap::new()).await.unwrap();
+    let mut chat = ChatSession::new(sess);
+    chat.send_and_collect("Hi").await.unwrap();
+    pantry.unload_llm(llm.to_string()).await.unwrap();
+    (pantry, chat)
+}
+
+#[tokio::test]
+async fn fails_without_failover() {
+    let server = MockPantryServer::start().await.unwrap();
+    let (_pantry, mut chat) = setup(&server).await;
+    let res = chat.send_and_collect("Still there?").await;
+    assert!(matches!(res, Err(PantryError::LlmNotRunning(_))));
+    assert_eq!(chat.messages().len(), 2);
+}
+
+#[tokio::test]
+async fn reloads_the_llm() {
+    let server = MockPantryServer::start().await.unwrap();
+    let (_pantry, chat) = setup(&server).await;
+    let old = chat.session().id;
+    let mut chat = chat.failover(Failover::Reload);
+
+    assert_eq!(
+        chat.send_and_collect("Still there?").await.unwrap(),
+        "Hello!"
+    );
+    assert_ne!(chat.session().id, old);
+    assert_eq!(chat.session().llm_status.id, "openhermes");
+    assert!(server.llms()[0].running);
+    assert_eq!(chat.messages().len(), 4);
+}
+
+#[tokio::test]
+async fn selects_another_llm() {
+    let server = MockPantryServer::start().await.unwrap();
+    let (_pantry, chat) = setup(&server).await;
+    let mut chat = chat.failover(Failover::Select {
+        filter: None,
+        preference: None,
+    });
+
+    // Nothing's running, so one gets loaded.
+    chat.send_and_collect("Still there?").await.unwrap();
+    assert!(chat.session().llm_status.running);
+    assert_eq!(chat.messages().len(), 4);
+}
@@ -0,0 +1,87 @@
+use pantry_rs::api::select_llm;
+use pantry_rs::interface::{CapabilityType, LLMStatus};
+use pantry_rs::{LLMFilter, LLMPreference};
+
+fn llm() -> LLMStatus {
+    serde_json::from_value(serde_json::json!({
+        "id": "openchat", "family_id": "llama", "organization": "OpenChat",
+        "name": "Openchat", "homepage": "", "license": "Apache-2.0", "description": "",
+        "capabilities": {"general": 4, "coding": 6}, "requirements": "",
+        "tags": ["chat", "7B"], "url": "", "local": true, "connector_type": "llmrs",
+        "download_progress": 100.0, "config": {"context_length": 4096},
+        "parameters": {}, "user_parameters": [], "session_parameters": {},
+        "user_session_parameters": [], "uuid": "6f1e0b9e-8a43-4b0c-9a39-7d0d1f7c6a11",
+        "running": false
+    }))
+    .unwrap()
+}
+
+#[test]
+fn builder_matches() {
+    let filter = LLMFilter::new()
+        .family("llama")
+        .local(true)
+        .min_capability(CapabilityType::Coding, 6)
+        .tag("chat")
+        .license("mit")
+        .license("apache-2.0")
+        .min_context_length(2048);
+    assert!(filter.matches(&llm()));
+    assert!(LLMFilter::new().id("OpenChat").matches(&llm()));
+
+    assert!(!LLMFilter::new()
+        .min_capability(CapabilityType::Coding, 7)
+        .matches(&llm()));
+    assert!(!LLMFilter::new().tag("vision").matches(&llm()));
+    assert!(!LLMFilter::new().min_context_length(8192).matches(&llm()));
+    // Unknown RAM requirements never match.
+    assert!(!LLMFilter::new().max_ram_bytes(8 << 30).matches(&llm()));
+}
+
+#[test]
+fn unset_dimensions_are_not_serialized() {
+    let json = serde_json::to_value(LLMFilter::new().local(true)).unwrap();
+    assert!(json.get("tags").is_none());
+    assert_eq!(json["local"], true);
+
+    let json =
+        serde_json::to_value(LLMPreference::new().capability(CapabilityType::Coding)).unwrap();
+    assert_eq!(json["capability_type"], "coding");
+}
+
+#[test]
+fn selection_follows_preference_order() {
+    let mut remote = llm();
+    remote.uuid = "0b2c6c33-5d2e-4b4e-9a61-3f1e6f1d7b22".parse().unwrap();
+    remote.local = false;
+    remote.capabilities.insert(CapabilityType::General, 9);
+    let mut coder = llm();
+    coder.uuid = "1c3d7d44-6e3f-4c5f-8b72-4a2f7a2e8c33".parse().unwrap();
+    coder.capabilities.insert(CapabilityType::Coding, 8);
+    let llms = vec![llm(), remote, coder];
+
+    // Best general model wins without preferences.
+    let picked = select_llm(&llms, None, None).unwrap();
+    assert_eq!(picked.uuid, llms[1].uuid);
+
+    // Local narrows first, then ranks by coding.
+    let preference = LLMPreference::new()
+        .local(true)
+        .capability(CapabilityType::Coding);
+    let picked = select_llm(&llms, None, Some(&preference)).unwrap();
+    assert_eq!(picked.uuid, llms[2].uuid);
+
+    // Ids match regardless of case, like they do in filters.
+    let mut named = llms.clone();
+    named[2].id = "coder".into();
+    let preference = LLMPreference::new().id("Coder");
+    let picked = select_llm(&named, None, Some(&preference)).unwrap();
+    assert_eq!(picked.uuid, llms[2].uuid);
+
+    // Preferences that match nothing are skipped.
+    let preference = LLMPreference::new().family("falcon");
+    assert!(select_llm(&llms, None, Some(&preference)).is_some());
+
+    let filter = LLMFilter::new().tag("vision");
+    assert!(select_llm(&llms, Some(&filter), None).is_none());
+}
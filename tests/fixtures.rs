@@ -0,0 +1,131 @@
+use pantry_rs::fixtures::{Exchange, Fixtures};
+use pantry_rs::interface::UserPermissions;
+use pantry_rs::{PantryClient, PantryError, RetryPolicy, UserId};
+use serde_json::json;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+fn temp_path() -> PathBuf {
+    std::env::temp_dir().join(format!("pantry-rs-fixtures-{}.json", Uuid::new_v4()))
+}
+
+#[tokio::test]
+async fn replays_recorded_calls() {
+    let path = temp_path();
+    let exchanges = vec![Exchange {
+        endpoint: "/get_permissions".into(),
+        request: json!({"user_id": "x", "api_key": "[redacted]"}),
+        status: 200,
+        content_type: Some("application/json".into()),
+        body: serde_json::to_string(&UserPermissions {
+            perm_session: true,
+            ..Default::default()
+        })
+        .unwrap(),
+    }];
+    std::fs::write(&path, serde_json::to_string(&exchanges).unwrap()).unwrap();
+
+    let fixtures = Fixtures::replay(&path).unwrap();
+    assert_eq!(fixtures.exchanges(), exchanges);
+    let pantry = PantryClient::builder()
+        .base_url("http://127.0.0.1:1")
+        .retry(RetryPolicy::none())
+        .fixtures(fixtures.clone())
+        .login(UserId::new_v4(), "key".into());
+
+    assert!(pantry.get_permissions().await.unwrap().perm_session);
+    assert!(fixtures.is_exhausted());
+    assert!(matches!(
+        pantry.get_permissions().await,
+        Err(PantryError::FixtureError(_))
+    ));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn missing_fixture_file() {
+    assert!(matches!(
+        Fixtures::replay(temp_path()),
+        Err(PantryError::FixtureError(_))
+    ));
+}
+
+#[cfg(feature = "testing")]
+#[tokio::test]
+async fn records_then_replays() {
+    use pantry_rs::testing::MockPantryServer;
+    use pantry_rs::InferenceParams;
+    use std::collections::HashMap;
+
+    let path = temp_path();
+    let server = MockPantryServer::start().await.unwrap();
+    let recording = Fixtures::record(&path);
+    let live = server.running_client_with("openchat", server.builder().fixtures(recording.clone()));
+    let sess = live.create_session(HashMap::new()).await.unwrap();
+    let text = sess
+        .prompt_and_collect("Hi".into(), InferenceParams::new())
+        .await
+        .unwrap();
+    assert_eq!(text, "Hello, world!");
+    recording.save().unwrap();
+
+    let recorded = recording.exchanges();
+    assert!(recorded
+        .iter()
+        .any(|e| e.endpoint == "/prompt_session_stream" && e.body.contains("world")));
+    assert!(!std::fs::read_to_string(&path)
+        .unwrap()
+        .contains(&live.api_key));
+    drop(server);
+
+    let replaying = Fixtures::replay(&path).unwrap();
+    let pantry = PantryClient::builder()
+        .base_url("http://127.0.0.1:1")
+        .retry(RetryPolicy::none())
+        .fixtures(replaying.clone())
+        .login(live.user_id, live.api_key);
+    let sess = pantry.create_session(HashMap::new()).await.unwrap();
+    let text = sess
+        .prompt_and_collect("Hi".into(), InferenceParams::new())
+        .await
+        .unwrap();
+    assert_eq!(text, "Hello, world!");
+    assert!(replaying.is_exhausted());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "testing")]
+#[tokio::test]
+async fn records_in_call_order() {
+    use pantry_rs::testing::MockPantryServer;
+    use pantry_rs::InferenceParams;
+
+    let path = temp_path();
+    let server = MockPantryServer::start().await.unwrap();
+    let recording = Fixtures::record(&path);
+    let builder = server.builder().fixtures(recording.clone());
+    let sess = server.running_session_with("openchat", builder).await;
+
+    // The stream's body is recorded last, but it was asked for first.
+    let stream = sess
+        .prompt_session("Hi".into(), InferenceParams::new())
+        .await
+        .unwrap();
+    sess.history().await.unwrap();
+    stream.collect_text().await.unwrap();
+    recording.save().unwrap();
+
+    let saved: Vec<Exchange> =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let endpoints: Vec<&str> = saved.iter().map(|e| e.endpoint.as_str()).collect();
+    assert_eq!(
+        endpoints,
+        [
+            "/create_session_id",
+            "/prompt_session_stream",
+            "/get_session_history"
+        ]
+    );
+    assert!(saved[1].body.contains("world"));
+    std::fs::remove_file(&path).unwrap();
+}
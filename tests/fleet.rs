@@ -0,0 +1,117 @@
+#![cfg(feature = "testing")]
+use pantry_rs::interface::UserPermissions;
+use pantry_rs::testing::{mock_llm, MockPantryServer};
+use pantry_rs::{LLMFilter, PantryClient, PantryError, PantryFleet, RetryPolicy, UserId};
+use std::collections::HashMap;
+
+fn perms() -> UserPermissions {
+    UserPermissions {
+        perm_session: true,
+        perm_view_llms: true,
+        ..Default::default()
+    }
+}
+
+async fn host(id: &str, running: bool) -> (MockPantryServer, PantryClient) {
+    let server = MockPantryServer::start().await.unwrap();
+    let mut llm = mock_llm(id);
+    llm.running = running;
+    server.add_llm(llm);
+    let client = server.login(perms());
+    (server, client)
+}
+
+fn offline() -> PantryClient {
+    // Nothing listens on port 9.
+    PantryClient::builder()
+        .base_url("http://127.0.0.1:9")
+        .retry(RetryPolicy::none())
+        .login(UserId::new_v4(), "key".into())
+}
+
+#[tokio::test]
+async fn lists_llms_by_host() {
+    let (_desktop, desktop) = host("openchat", true).await;
+    let (_server, server) = host("codellama", false).await;
+    let fleet = PantryFleet::new()
+        .host("desktop", desktop)
+        .host("server", server);
+
+    let available = fleet.get_available_llms().await;
+    assert!(available.errors.is_empty());
+    let mut listed: Vec<(&str, &str)> = available
+        .llms
+        .iter()
+        .map(|h| (h.host.as_str(), h.llm.id.as_str()))
+        .collect();
+    listed.sort();
+    assert_eq!(listed, [("desktop", "openchat"), ("server", "codellama")]);
+
+    let running = fleet.get_running_llms().await.llms;
+    assert_eq!(running.len(), 1);
+    assert_eq!(running[0].host, "desktop");
+}
+
+#[tokio::test]
+async fn routes_sessions_to_the_matching_host() {
+    let (_desktop, desktop) = host("openchat", true).await;
+    let (server, workstation) = host("codellama", true).await;
+    let fleet = PantryFleet::new()
+        .host("desktop", desktop)
+        .host("workstation", workstation);
+
+    let hosted = fleet
+        .create_session_flex(Some(LLMFilter::new().id("codellama")), None, HashMap::new())
+        .await
+        .unwrap();
+    assert_eq!(hosted.host, "workstation");
+    assert_eq!(hosted.session.llm_status.id, "codellama");
+    assert!(server.calls().contains(&"create_session_id".to_string()));
+
+    let res = fleet
+        .create_session_flex(Some(LLMFilter::new().id("mistral")), None, HashMap::new())
+        .await;
+    assert!(matches!(res, Err(PantryError::LlmNotFound(_))));
+}
+
+#[tokio::test]
+async fn skips_unreachable_hosts() {
+    let (_desktop, desktop) = host("openchat", true).await;
+    let mut fleet = PantryFleet::new()
+        .host("laptop", offline())
+        .host("desktop", desktop);
+
+    let running = fleet.get_running_llms().await;
+    assert_eq!(running.llms.len(), 1);
+    assert_eq!(running.llms[0].host, "desktop");
+    assert_eq!(running.errors.len(), 1);
+    assert_eq!(running.errors[0].host, "laptop");
+
+    fleet.remove_host("desktop").unwrap();
+    assert_eq!(fleet.labels(), ["laptop"]);
+    let res = fleet.create_session_flex(None, None, HashMap::new()).await;
+    assert!(res.err().unwrap().is_retryable());
+}
+
+#[tokio::test]
+async fn reports_refusing_hosts_next_to_the_rest() {
+    let (_desktop, desktop) = host("openchat", true).await;
+    let (server, _) = host("codellama", true).await;
+    let refusing = server.login(UserPermissions::default());
+    let fleet = PantryFleet::new()
+        .host("desktop", desktop)
+        .host("server", refusing);
+
+    let running = fleet.get_running_llms().await;
+    assert_eq!(running.llms.len(), 1);
+    assert_eq!(running.llms[0].host, "desktop");
+    assert_eq!(running.errors.len(), 1);
+    assert_eq!(running.errors[0].host, "server");
+    assert!(!running.errors[0].error.is_retryable());
+
+    let hosted = fleet
+        .create_session_flex(None, None, HashMap::new())
+        .await
+        .unwrap();
+    assert_eq!(hosted.host, "desktop");
+}
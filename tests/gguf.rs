@@ -0,0 +1,63 @@
+#![cfg(feature = "gguf")]
+use pantry_rs::gguf::{GgufMetadata, GgufValue};
+
+fn string(buf: &mut Vec<u8>, s: &str) {
+    buf.extend((s.len() as u64).to_le_bytes());
+    buf.extend(s.as_bytes());
+}
+
+fn kv_string(buf: &mut Vec<u8>, key: &str, value: &str) {
+    string(buf, key);
+    buf.extend(8u32.to_le_bytes());
+    string(buf, value);
+}
+
+fn kv_u32(buf: &mut Vec<u8>, key: &str, value: u32) {
+    string(buf, key);
+    buf.extend(4u32.to_le_bytes());
+    buf.extend(value.to_le_bytes());
+}
+
+fn tensor(buf: &mut Vec<u8>, name: &str, dims: &[u64], tensor_type: u32) {
+    string(buf, name);
+    buf.extend((dims.len() as u32).to_le_bytes());
+    for d in dims {
+        buf.extend(d.to_le_bytes());
+    }
+    buf.extend(tensor_type.to_le_bytes());
+    buf.extend(0u64.to_le_bytes());
+}
+
+#[test]
+fn reads_header() {
+    let mut buf = b"GGUF".to_vec();
+    buf.extend(3u32.to_le_bytes());
+    buf.extend(2u64.to_le_bytes()); // tensors
+    buf.extend(5u64.to_le_bytes()); // kv pairs
+    kv_string(&mut buf, "general.architecture", "llama");
+    kv_string(&mut buf, "general.name", "Tiny Llama");
+    kv_string(&mut buf, "general.license", "apache-2.0");
+    kv_u32(&mut buf, "llama.context_length", 2048);
+    kv_u32(&mut buf, "general.file_type", 2);
+    tensor(&mut buf, "token_embd.weight", &[1000, 1000], 2);
+    tensor(&mut buf, "output.weight", &[1000], 0);
+
+    let meta = GgufMetadata::from_reader(buf.as_slice()).unwrap();
+    assert_eq!(meta.version, 3);
+    assert_eq!(meta.architecture.as_deref(), Some("llama"));
+    assert_eq!(meta.name.as_deref(), Some("Tiny Llama"));
+    assert_eq!(meta.context_length, Some(2048));
+    assert_eq!(meta.quantization.as_deref(), Some("Q4_0"));
+    assert_eq!(meta.parameter_count, 1_001_000);
+    assert_eq!(meta.parameter_size(), "1M");
+    assert_eq!(
+        meta.metadata.get("general.license"),
+        Some(&GgufValue::String("apache-2.0".into()))
+    );
+}
+
+#[test]
+fn rejects_other_files() {
+    assert!(GgufMetadata::from_reader(&b"tjgg\x01\x00\x00\x00"[..]).is_err());
+    assert!(GgufMetadata::from_reader(&b"PK\x03\x04"[..]).is_err());
+}
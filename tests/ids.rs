@@ -0,0 +1,35 @@
+use pantry_rs::interface::LLMSessionStatus;
+use pantry_rs::{LlmUuid, SessionId, UserId};
+use serde_json::json;
+use uuid::Uuid;
+
+#[test]
+fn parse_and_display() {
+    let raw = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+    let id: SessionId = raw.parse().unwrap();
+    assert_eq!(id.to_string(), raw);
+    assert_eq!(Uuid::from(id), Uuid::parse_str(raw).unwrap());
+    assert!("not-a-uuid".parse::<UserId>().is_err());
+}
+
+#[test]
+fn serialized_as_plain_uuids() {
+    let session_id = SessionId::new_v4();
+    let llm_uuid = LlmUuid::new_v4();
+    let user_id = UserId::new_v4();
+    let status: LLMSessionStatus = serde_json::from_value(json!({
+        "id": session_id.to_string(),
+        "llm_uuid": llm_uuid.to_string(),
+        "user_id": user_id.to_string(),
+        "started": "2023-01-01T00:00:00Z",
+        "last_called": "2023-01-01T00:00:00Z",
+        "session_parameters": {}
+    }))
+    .unwrap();
+    assert_eq!(status.id, session_id);
+    assert_eq!(status.llm_uuid, llm_uuid);
+    assert_eq!(status.user_id, user_id);
+
+    let value = serde_json::to_value(&status).unwrap();
+    assert_eq!(value["id"], json!(session_id.to_string()));
+}
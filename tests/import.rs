@@ -0,0 +1,110 @@
+use pantry_rs::interface::{LLMConnectorType, LLMRegistryEntry};
+use pantry_rs::llama_cpp::LlamaCppModel;
+use pantry_rs::ollama::list_ollama_models;
+use pantry_rs::ConnectorConfig;
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MODEL_DIGEST: &str = "8934d96d3f08982e95922b2b7a2c626a1fe873d7c3b06e8e56d7bc0a1fef9246";
+const CONFIG_DIGEST: &str = "2e0493f67d0c8c9c68a8aeacdf6a38a2151cb3c4c1d42accf296e19810527988";
+
+fn temp_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("pantry-rs-import-{}", uuid::Uuid::new_v4()))
+}
+
+fn write(path: &Path, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+fn manifest(config_digest: &str) -> String {
+    json!({
+        "schemaVersion": 2,
+        "config": {"digest": format!("sha256:{}", config_digest), "size": 100},
+        "layers": [
+            {"mediaType": "application/vnd.ollama.image.license", "digest": "sha256:00", "size": 10},
+            {"mediaType": "application/vnd.ollama.image.model", "digest": format!("sha256:{}", MODEL_DIGEST), "size": 3825819519u64}
+        ]
+    })
+    .to_string()
+}
+
+#[test]
+fn lists_ollama_models() {
+    let dir = temp_dir();
+    let manifests = dir.join("manifests");
+    write(
+        &manifests.join("registry.ollama.ai/library/llama2/latest"),
+        &manifest(CONFIG_DIGEST),
+    );
+    write(
+        &manifests.join("registry.ollama.ai/someone/coder/q4_0"),
+        &manifest("missing"),
+    );
+    write(
+        &manifests.join("registry.ollama.ai/library/broken/latest"),
+        "{",
+    );
+    write(&dir.join(format!("blobs/sha256-{}", MODEL_DIGEST)), "gguf");
+    write(
+        &dir.join(format!("blobs/sha256-{}", CONFIG_DIGEST)),
+        r#"{"model_format":"gguf","model_family":"llama","model_type":"7B","file_type":"Q4_0"}"#,
+    );
+
+    let models = list_ollama_models(&dir).unwrap();
+    let names: Vec<&str> = models.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["llama2:latest", "someone/coder:q4_0"]);
+    assert_eq!(models[0].sha256, MODEL_DIGEST);
+    assert_eq!(models[0].size_bytes, 3825819519);
+    assert_eq!(models[0].family.as_deref(), Some("llama"));
+    assert_eq!(models[0].quantization.as_deref(), Some("Q4_0"));
+    assert!(models[0].path.is_file());
+    assert_eq!(models[1].family, None);
+
+    let entry = LLMRegistryEntry::from_ollama(&models[0], "http://localhost:11434/")
+        .build()
+        .unwrap();
+    assert_eq!(entry.id, "ollama-llama2-latest");
+    assert_eq!(entry.url, "http://localhost:11434/v1");
+    assert_eq!(entry.family_id, "llama");
+    assert_eq!(entry.homepage, "https://ollama.com/library/llama2");
+    assert!(!entry.local);
+    let ConnectorConfig::GenericAPI(config) = entry.connector_config().unwrap() else {
+        panic!("not a GenericAPI config");
+    };
+    assert_eq!(config.model.as_deref(), Some("llama2:latest"));
+
+    let entry = LLMRegistryEntry::from_ollama(&models[1], "http://localhost:11434")
+        .build()
+        .unwrap();
+    assert_eq!(entry.id, "ollama-someone-coder-q4_0");
+    assert!(entry.homepage.is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn missing_ollama_dir() {
+    assert!(list_ollama_models(&temp_dir()).is_err());
+}
+
+#[test]
+fn llama_cpp_entries() {
+    let model: LlamaCppModel = serde_json::from_value(json!({
+        "id": "/models/openchat_3.5.Q4_K_M.gguf",
+        "object": "model",
+        "owned_by": "llamacpp",
+        "meta": {"n_ctx_train": 8192, "n_params": 7241732096u64, "size": 4368438272u64}
+    }))
+    .unwrap();
+    let entry = LLMRegistryEntry::from_llama_cpp(&model, "http://localhost:8080")
+        .build()
+        .unwrap();
+    assert_eq!(entry.id, "llamacpp-openchat_3.5.Q4_K_M");
+    assert_eq!(entry.name, "openchat_3.5.Q4_K_M");
+    assert_eq!(entry.url, "http://localhost:8080/v1");
+    assert!(matches!(entry.connector_type, LLMConnectorType::GenericAPI));
+    assert_eq!(entry.config["model"], "/models/openchat_3.5.Q4_K_M.gguf");
+    assert_eq!(entry.config["context_length"], 8192);
+}
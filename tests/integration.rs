@@ -1,12 +1,31 @@
+#[cfg(feature = "streaming")]
 use futures::stream::StreamExt;
+#[cfg(feature = "streaming")]
 use maplit::hashmap;
-use pantry_rs::interface::{LLMConnectorType, LLMRegistryEntry, UserPermissions};
-use pantry_rs::PantryClient;
+use pantry_rs::interface::UserPermissions;
+#[cfg(feature = "streaming")]
+use pantry_rs::interface::{LLMConnectorType, LLMRegistryEntry};
+use pantry_rs::{AdminClient, PantryClient, RequestId};
+#[cfg(feature = "streaming")]
 use uuid::Uuid;
 
+#[cfg(feature = "streaming")]
 use std::collections::HashMap;
-use std::{thread, time};
+use std::time;
+
+/// Accepts `request_id` if `PANTRY_ADMIN_ID` and `PANTRY_ADMIN_KEY` name a superuser,
+/// so the tests can run headless. Otherwise someone has to click "accept" in the UI.
+async fn auto_approve(request_id: RequestId, url: Option<String>) {
+    if let (Ok(id), Ok(key)) = (
+        std::env::var("PANTRY_ADMIN_ID"),
+        std::env::var("PANTRY_ADMIN_KEY"),
+    ) {
+        let admin = AdminClient::new(PantryClient::login(id.parse().unwrap(), key, url));
+        admin.approve_request(request_id).await.unwrap();
+    }
+}
 
+#[cfg(feature = "streaming")]
 #[tokio::test]
 async fn basic_workflow() {
     let perms = UserPermissions {
@@ -20,25 +39,20 @@ async fn basic_workflow() {
         perm_request_unload: true,
         perm_view_llms: true,
         perm_bare_model: true,
+        ..Default::default()
     };
 
-    let (pantry, mut req_status) = PantryClient::register("testing".into(), perms, None)
+    let (pantry, req_status) = PantryClient::register("testing".into(), perms, None)
         .await
         .unwrap();
 
-    //wait for permission requests to be fulfilled.
-    //
-
-    let mut timeout_counter = 120;
+    auto_approve(req_status.id, None).await;
 
-    while timeout_counter > 0 {
-        req_status = pantry.get_request_status(req_status.id).await.unwrap();
-        if req_status.complete && req_status.accepted {
-            break;
-        }
-        timeout_counter = timeout_counter - 1;
-        thread::sleep(time::Duration::from_secs(1));
-    }
+    //wait for permission requests to be fulfilled.
+    pantry
+        .await_request(req_status.id, time::Duration::from_secs(120))
+        .await
+        .unwrap();
 
     println!("Request accepted, continuing");
     //We need at least one LLM.
@@ -61,6 +75,13 @@ async fn basic_workflow() {
             requirements: "".into(),
             backend_uuid: Uuid::new_v4().to_string(),
             url: "https://huggingface.co/TheBloke/OpenChat_v3.2-GGML/resolve/main/openchat_v3.2.ggmlv3.q4_0.bin".into(),
+            mirrors: Vec::new(),
+            sha256: None,
+            size_bytes: None,
+            signature: None,
+            version: None,
+            supersedes: Vec::new(),
+            adapters: Vec::new(),
             config: hashmap! {
                 "model_architecture".into() => "llama".into(),
             },
@@ -110,9 +131,10 @@ async fn bare_model_workflow() {
         perm_request_unload: true,
         perm_view_llms: true,
         perm_bare_model: true,
+        ..Default::default()
     };
 
-    let (pantry, mut req_status) = PantryClient::register(
+    let (pantry, req_status) = PantryClient::register(
         "bare_model_test".into(),
         perms,
         Some("http://localhost:9404".into()),
@@ -120,18 +142,13 @@ async fn bare_model_workflow() {
     .await
     .unwrap();
 
-    //wait for permission requests to be fulfilled.
-
-    let mut timeout_counter = 120;
+    auto_approve(req_status.id, Some("http://localhost:9404".into())).await;
 
-    while timeout_counter > 0 {
-        req_status = pantry.get_request_status(req_status.id).await.unwrap();
-        if req_status.complete && req_status.accepted {
-            break;
-        }
-        timeout_counter = timeout_counter - 1;
-        thread::sleep(time::Duration::from_secs(1));
-    }
+    //wait for permission requests to be fulfilled.
+    pantry
+        .await_request(req_status.id, time::Duration::from_secs(120))
+        .await
+        .unwrap();
 
     println!("Request accepted, continuing");
 
@@ -0,0 +1,51 @@
+#![cfg(feature = "integrity")]
+use ed25519_dalek::SigningKey;
+use pantry_rs::integrity::file_digest;
+use pantry_rs::interface::{LLMConnectorType, LLMRegistryEntry};
+use std::io::Write;
+
+fn model_file(contents: &[u8]) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("pantry-rs-{}.bin", uuid::Uuid::new_v4()));
+    std::fs::File::create(&path)
+        .unwrap()
+        .write_all(contents)
+        .unwrap();
+    path
+}
+
+#[test]
+fn digest_and_verify() {
+    let path = model_file(b"abc");
+    let digest = file_digest(&path).unwrap();
+    assert_eq!(
+        digest.sha256,
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(digest.size_bytes, 3);
+
+    let mut entry = LLMRegistryEntry::builder("m", LLMConnectorType::LLMrs).build_unchecked();
+    entry.set_file_digest(&path).unwrap();
+    assert!(entry.verify_file(&path).is_ok());
+
+    std::fs::write(&path, b"abd").unwrap();
+    assert!(entry.verify_file(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn signatures() {
+    let path = model_file(b"model");
+    let key = SigningKey::from_bytes(&[7u8; 32]);
+    let mut entry = LLMRegistryEntry::builder("m", LLMConnectorType::LLMrs).build_unchecked();
+    entry.set_file_digest(&path).unwrap();
+    entry.sign(&key).unwrap();
+    assert!(entry
+        .verify_signature(&key.verifying_key().to_bytes())
+        .is_ok());
+
+    let other = SigningKey::from_bytes(&[8u8; 32]);
+    assert!(entry
+        .verify_signature(&other.verifying_key().to_bytes())
+        .is_err());
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "testing")]
+use pantry_rs::interface::UserPermissions;
+use pantry_rs::journal::{JournaledCall, Outbox, Submitted};
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::{PantryClient, PantryError, RetryPolicy, UserId};
+use std::time::Duration;
+
+fn call() -> JournaledCall {
+    JournaledCall::RequestPermissions {
+        permissions: UserPermissions {
+            perm_session: true,
+            ..Default::default()
+        },
+    }
+}
+
+#[tokio::test]
+async fn queues_while_unreachable_and_replays() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.login(UserPermissions::default());
+    // Nothing listens on the discard port.
+    let offline = PantryClient::builder()
+        .base_url("http://127.0.0.1:9")
+        .retry(RetryPolicy::none())
+        .login(pantry.user_id, pantry.api_key.clone());
+
+    let path = std::env::temp_dir().join(format!("pantry-outbox-{}.json", uuid::Uuid::new_v4()));
+    let outbox = Outbox::open(&path).unwrap();
+    let queued = match outbox.submit(&offline, call()).await.unwrap() {
+        Submitted::Queued(id) => id,
+        Submitted::Sent(_) => panic!("sent while offline"),
+    };
+    outbox.submit(&offline, call()).await.unwrap();
+    assert_eq!(outbox.pending().len(), 2);
+
+    // Still unreachable, so both stay.
+    assert!(outbox.replay(&offline).await.unwrap().is_empty());
+
+    // The journal survives a restart.
+    let outbox = Outbox::open(&path).unwrap();
+    assert_eq!(outbox.pending()[0].id, queued);
+    let replayed = outbox.replay(&pantry).await.unwrap();
+    assert_eq!(replayed.len(), 2);
+    assert!(replayed.iter().all(|(_, result)| result.is_ok()));
+    assert!(outbox.pending().is_empty());
+    assert!(Outbox::open(&path).unwrap().pending().is_empty());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn sends_right_away_when_reachable() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.login(UserPermissions::default());
+    let outbox = Outbox::in_memory();
+    let sent = outbox.submit(&pantry, call()).await.unwrap();
+    assert!(matches!(sent, Submitted::Sent(status) if status["complete"] == true));
+    assert!(outbox.pending().is_empty());
+
+    let id = outbox.queue(call()).unwrap();
+    assert!(outbox.cancel(id).unwrap());
+    assert!(!outbox.cancel(id).unwrap());
+}
+
+#[tokio::test]
+async fn only_queues_repeatable_calls_that_may_have_gone_through() {
+    // Accepts connections but never answers, so calls time out after being sent.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let _listener = tokio::spawn(async move {
+        let mut sockets = Vec::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            sockets.push(socket);
+        }
+    });
+    let hanging = PantryClient::builder()
+        .base_url(url)
+        .retry(RetryPolicy::none())
+        .timeout(Duration::from_millis(50))
+        .login(UserId::new_v4(), "key".into());
+
+    let outbox = Outbox::in_memory();
+    let res = outbox.submit(&hanging, call()).await;
+    assert!(matches!(res, Err(PantryError::Timeout(_))));
+    assert!(outbox.pending().is_empty());
+
+    let unload = JournaledCall::UnloadLlm {
+        llm_id: "openchat".into(),
+    };
+    assert!(unload.is_idempotent());
+    let res = outbox.submit(&hanging, unload).await.unwrap();
+    assert!(matches!(res, Submitted::Queued(_)));
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "testing")]
+use pantry_rs::interface::UserPermissions;
+use pantry_rs::testing::{mock_llm, MockPantryServer};
+use pantry_rs::PantryError;
+use std::time::Duration;
+
+fn perms() -> UserPermissions {
+    UserPermissions {
+        perm_bare_model: true,
+        perm_download_llm: true,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn pins_until_released() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.add_llm(mock_llm("openchat"));
+    let pantry = server.login(perms());
+
+    let lease = pantry
+        .bare_model_lease("openchat".into(), Duration::from_secs(3600))
+        .await
+        .unwrap();
+    assert_eq!(lease.model().id, "openchat");
+    assert!(lease.path().ends_with("openchat.bin"));
+    assert!(!lease.is_expired());
+    assert!(!format!("{:?}", lease).contains(&pantry.api_key));
+
+    let uuid = server.llms()[0].uuid;
+    let res = pantry.delete_llm(uuid).await;
+    assert!(matches!(res, Err(PantryError::LlmInUse(_))));
+
+    lease.release().await.unwrap();
+    pantry.delete_llm(uuid).await.unwrap();
+}
+
+#[tokio::test]
+async fn expires_after_ttl() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.add_llm(mock_llm("openchat"));
+    let pantry = server.login(perms());
+
+    let lease = pantry
+        .bare_model_lease("openchat".into(), Duration::from_secs(1))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert!(lease.is_expired());
+    pantry.delete_llm(server.llms()[0].uuid).await.unwrap();
+    // Too late, but harmless.
+    pantry.release_bare_model(lease.id()).await.unwrap();
+}
@@ -0,0 +1,28 @@
+use pantry_rs::logging::{redact, REDACTED};
+use serde_json::{json, Value};
+
+#[test]
+fn redacts_nested_secrets() {
+    let body = json!({
+        "user_id": "1d2c3b4a-5e6f-4a1b-8c9d-0e1f2a3b4c5d",
+        "api_key": "hunter2",
+        "llm_registry_entry": {"config": {"token": "hf_abc"}, "tags": [{"password": "pw"}]}
+    })
+    .to_string();
+
+    let redacted = redact(&body);
+    assert!(!redacted.contains("hunter2"));
+    assert!(!redacted.contains("hf_abc"));
+    assert!(!redacted.contains("\"pw\""));
+
+    let json: Value = serde_json::from_str(&redacted).unwrap();
+    assert_eq!(json["api_key"], REDACTED);
+    assert_eq!(json["user_id"], "1d2c3b4a-5e6f-4a1b-8c9d-0e1f2a3b4c5d");
+    assert_eq!(json["llm_registry_entry"]["config"]["token"], REDACTED);
+}
+
+#[test]
+fn redacts_plain_text_mentioning_secrets() {
+    assert_eq!(redact("not found"), "not found");
+    assert!(!redact("bad api_key=hunter2").contains("hunter2"));
+}
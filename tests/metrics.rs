@@ -0,0 +1,53 @@
+use pantry_rs::interface::FinishReason;
+use pantry_rs::metrics::{MetricsSink, PromptMetric, RequestMetric};
+use pantry_rs::{LlmUuid, PantryClient, RetryPolicy, SessionId, UserId};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Default)]
+struct Recorder {
+    requests: Mutex<Vec<RequestMetric>>,
+}
+
+impl MetricsSink for Recorder {
+    fn request(&self, metric: &RequestMetric) {
+        self.requests.lock().unwrap().push(metric.clone());
+    }
+}
+
+#[test]
+fn tokens_per_second() {
+    let mut metric = PromptMetric {
+        session_id: SessionId::new_v4(),
+        llm_uuid: LlmUuid::new_v4(),
+        time_to_first_token: Some(Duration::from_secs(1)),
+        duration: Duration::from_secs(3),
+        tokens: 11,
+        finish_reason: FinishReason::Stop,
+    };
+    assert_eq!(metric.tokens_per_second(), Some(5.0));
+
+    metric.tokens = 1;
+    assert_eq!(metric.tokens_per_second(), None);
+    metric.time_to_first_token = None;
+    assert_eq!(metric.tokens_per_second(), None);
+}
+
+#[tokio::test]
+async fn records_failed_requests() {
+    let recorder = Arc::new(Recorder::default());
+    // Nothing listens on port 1.
+    let pantry = PantryClient::builder()
+        .base_url("http://127.0.0.1:1")
+        .retry(RetryPolicy::none())
+        .metrics(recorder.clone())
+        .login(UserId::new_v4(), "key".into());
+
+    assert!(pantry.server_info().await.is_err());
+
+    let requests = recorder.requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].endpoint, "/server_info");
+    assert_eq!(requests[0].status, None);
+    assert!(requests[0].is_error());
+}
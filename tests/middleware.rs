@@ -0,0 +1,85 @@
+#![cfg(feature = "testing")]
+use futures::StreamExt;
+use pantry_rs::interface::{LLMEvent, LLMEventInternal};
+use pantry_rs::middleware::{MaxPromptChars, PromptMiddleware, PromptRequest};
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::{LLMSession, PantryError};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Shout {
+    prompts: Arc<Mutex<Vec<String>>>,
+}
+
+impl PromptMiddleware for Shout {
+    fn before_prompt(&self, request: &mut PromptRequest) -> Result<(), PantryError> {
+        request.prompt.push('!');
+        self.prompts.lock().unwrap().push(request.prompt.clone());
+        Ok(())
+    }
+
+    fn on_event(&self, event: &mut LLMEvent) -> Result<(), PantryError> {
+        if let LLMEventInternal::PromptProgress { next, .. } = &mut event.event {
+            if next.contains("secret") {
+                return Err(PantryError::PromptRejected("reply leaked a secret".into()));
+            }
+            *next = next.to_uppercase();
+        }
+        Ok(())
+    }
+}
+
+async fn session(server: &MockPantryServer, shout: Shout) -> LLMSession {
+    let builder = server
+        .builder()
+        .middleware(MaxPromptChars(10))
+        .middleware(shout);
+    server.running_session_with("openhermes", builder).await
+}
+
+#[tokio::test]
+async fn rewrites_prompts_and_events() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.reply(["Hello", " there"]);
+    let shout = Shout::default();
+    let prompts = shout.prompts.clone();
+    let sess = session(&server, shout).await;
+
+    let text = sess
+        .prompt_and_collect("Hi".into(), HashMap::new())
+        .await
+        .unwrap();
+    assert_eq!(text, "HELLO THERE");
+    assert_eq!(*prompts.lock().unwrap(), vec!["Hi!".to_string()]);
+
+    let res = sess
+        .prompt_and_collect("Far too long a prompt".into(), HashMap::new())
+        .await;
+    assert!(matches!(res, Err(PantryError::PromptRejected(_))));
+    assert_eq!(prompts.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn events_can_end_the_stream() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.reply(["The", " secret", " is", " 42"]);
+    let sess = session(&server, Shout::default()).await;
+
+    let mut stream = sess
+        .prompt_session("Tell me".into(), HashMap::new())
+        .await
+        .unwrap();
+    let mut seen = Vec::new();
+    while let Some(event) = stream.next().await {
+        match event {
+            Ok(event) => seen.push(event),
+            Err(e) => {
+                assert!(matches!(e, PantryError::PromptRejected(_)));
+                break;
+            }
+        }
+    }
+    assert_eq!(seen.len(), 1);
+    assert!(stream.next().await.is_none());
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "testing")]
+use pantry_rs::interface::{LLMConnectorType, LLMRegistryEntry, UserPermissions};
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::PantryError;
+use std::time::Duration;
+
+/// Serves `HEAD /ok` and nothing else, returning the base url.
+async fn mirror() -> String {
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Response, StatusCode};
+
+    let server =
+        hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service_fn(|_| async {
+            Ok::<_, std::convert::Infallible>(service_fn(|req| async move {
+                let status = match req.uri().path() {
+                    "/ok" => StatusCode::OK,
+                    _ => StatusCode::NOT_FOUND,
+                };
+                Ok::<_, std::convert::Infallible>(
+                    Response::builder()
+                        .status(status)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+            }))
+        }));
+    let url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+    url
+}
+
+#[tokio::test]
+async fn falls_back_to_mirrors() {
+    let base = mirror().await;
+    let entry = LLMRegistryEntry::builder("openchat", LLMConnectorType::LLMrs)
+        .url(format!("{}/gone", base))
+        // Nothing listens on port 9.
+        .mirror("http://127.0.0.1:9/model.bin")
+        .mirror(format!("{}/ok", base))
+        .config("model_architecture", "llama")
+        .build()
+        .unwrap();
+    assert_eq!(entry.urls().count(), 3);
+
+    let checked = entry.check_urls(Duration::from_secs(5)).await.unwrap();
+    assert_eq!(checked.url, format!("{}/ok", base));
+    assert_eq!(
+        checked.mirrors,
+        [
+            format!("{}/gone", base),
+            "http://127.0.0.1:9/model.bin".to_string()
+        ]
+    );
+}
+
+#[tokio::test]
+async fn fails_if_no_url_responds() {
+    let base = mirror().await;
+    let entry = LLMRegistryEntry::builder("openchat", LLMConnectorType::LLMrs)
+        .url(format!("{}/gone", base))
+        .config("model_architecture", "llama")
+        .build()
+        .unwrap();
+    let res = entry.check_urls(Duration::from_secs(5)).await;
+    assert!(matches!(res, Err(PantryError::UnreachableUrl(msg)) if msg.contains("404")));
+
+    // Only downloads are checked.
+    let remote = LLMRegistryEntry::builder("remote", LLMConnectorType::GenericAPI)
+        .url(format!("{}/gone", base))
+        .build()
+        .unwrap();
+    assert!(remote.check_urls(Duration::from_secs(5)).await.is_ok());
+}
+
+#[tokio::test]
+async fn checks_before_requesting_downloads() {
+    let base = mirror().await;
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.login(UserPermissions {
+        perm_request_download: true,
+        ..Default::default()
+    });
+    let entry = LLMRegistryEntry::builder("openchat", LLMConnectorType::LLMrs)
+        .url(format!("{}/gone", base))
+        .config("model_architecture", "llama")
+        .build()
+        .unwrap();
+
+    let res = pantry.request_download_llm(entry.clone()).await;
+    assert!(matches!(res, Err(PantryError::UnreachableUrl(_))));
+    assert!(!server.calls().contains(&"request_download".to_string()));
+
+    let mut mirrored = entry;
+    mirrored.mirrors.push(format!("{}/ok", base));
+    pantry.request_download_llm(mirrored).await.unwrap();
+    assert_eq!(server.llms()[0].url, format!("{}/ok", base));
+}
@@ -0,0 +1,128 @@
+#![cfg(feature = "testing")]
+use pantry_rs::interface::{CapabilityType, UserPermissions};
+use pantry_rs::testing::{mock_llm, MockPantryServer};
+use pantry_rs::{AttachmentTransport, InferenceParams, PantryClient, PromptInput};
+use std::collections::HashMap;
+
+fn perms() -> UserPermissions {
+    UserPermissions {
+        perm_session: true,
+        ..Default::default()
+    }
+}
+
+async fn server() -> MockPantryServer {
+    let server = MockPantryServer::start().await.unwrap();
+    server.add_running("llava");
+    server
+}
+
+fn inputs() -> Vec<PromptInput> {
+    // Not valid PNGs, but the mock doesn't look, and CRLFs and dashes inside are a
+    // good test of multipart.
+    vec![
+        "Compare ".into(),
+        PromptInput::image(b"\x89PNG\r\n--first".to_vec(), "image/png"),
+        " and ".into(),
+        PromptInput::image(vec![0xff, 0xd8, 0x00, 0xff], "image/jpeg"),
+        "".into(),
+    ]
+}
+
+async fn prompt(pantry: &PantryClient) -> String {
+    let sess = pantry.create_session(HashMap::new()).await.unwrap();
+    sess.prompt_session_multimodal(inputs(), InferenceParams::new())
+        .await
+        .unwrap()
+        .collect_text()
+        .await
+        .unwrap()
+}
+
+fn expected() -> Vec<PromptInput> {
+    let mut expected = inputs();
+    expected.pop();
+    expected
+}
+
+#[tokio::test]
+async fn sends_images_as_base64() {
+    let server = server().await;
+    let pantry = server.login(perms());
+
+    assert_eq!(prompt(&pantry).await, "Hello, world!");
+    assert_eq!(server.last_prompt(), Some(expected()));
+    assert_eq!(
+        server.last_content_type().as_deref(),
+        Some("application/json")
+    );
+}
+
+#[tokio::test]
+async fn sends_images_as_multipart() {
+    let server = server().await;
+    let (pantry, _) = server
+        .builder()
+        .attachment_transport(AttachmentTransport::Multipart)
+        .register("test".into(), perms())
+        .await
+        .unwrap();
+
+    assert_eq!(prompt(&pantry).await, "Hello, world!");
+    assert_eq!(server.last_prompt(), Some(expected()));
+    assert!(server
+        .last_content_type()
+        .unwrap()
+        .starts_with("multipart/form-data; boundary="));
+}
+
+#[tokio::test]
+async fn text_only_prompts_are_plain() {
+    let server = server().await;
+    let (pantry, _) = server
+        .builder()
+        .attachment_transport(AttachmentTransport::Multipart)
+        .register("test".into(), perms())
+        .await
+        .unwrap();
+    let sess = pantry.create_session(HashMap::new()).await.unwrap();
+
+    sess.prompt_session_multimodal(vec!["Hi".into(), " there".into()], HashMap::new())
+        .await
+        .unwrap()
+        .collect_text()
+        .await
+        .unwrap();
+    assert_eq!(server.last_prompt(), Some(vec!["Hi there".into()]));
+    assert_eq!(
+        server.last_content_type().as_deref(),
+        Some("application/json")
+    );
+}
+
+#[test]
+fn detects_vision() {
+    let mut llm = mock_llm("openchat");
+    assert!(!llm.accepts_images());
+    llm.capabilities.insert(CapabilityType::Vision, 0);
+    assert!(!llm.accepts_images());
+    llm.capabilities.insert(CapabilityType::Vision, 6);
+    assert!(llm.accepts_images());
+
+    let mut llm = mock_llm("openchat");
+    llm.tags.push("Multimodal".into());
+    assert!(llm.accepts_images());
+    assert!(mock_llm("llava-1.5-7b").accepts_images());
+
+    assert_eq!(CapabilityType::from("vision"), CapabilityType::Vision);
+}
+
+#[test]
+fn guesses_image_types() {
+    assert!(PromptInput::image_file("notes.txt").is_err());
+    assert!(PromptInput::image_file("missing.png").is_err());
+    assert_eq!(
+        pantry_rs::multimodal::image_mime("a/B.JPG".as_ref()),
+        Some("image/jpeg")
+    );
+}
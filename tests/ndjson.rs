@@ -0,0 +1,97 @@
+#![cfg(feature = "streaming")]
+use futures::stream::{self, StreamExt};
+use hyper::body::Bytes;
+use pantry_rs::ndjson::{self, NdjsonLines};
+
+#[test]
+fn splits_lines() {
+    let body = b"{\"a\": 1}\r\n\n{\"b\": \"\xC3\xA9\"}\n  \n{\"c\": 3}";
+    for split in 0..=body.len() {
+        let (a, b) = body.split_at(split);
+        let mut lines = NdjsonLines::new();
+        let mut out = lines.feed(a);
+        out.extend(lines.feed(b));
+        out.extend(lines.finish());
+        assert_eq!(
+            out,
+            vec!["{\"a\": 1}", "{\"b\": \"é\"}", "{\"c\": 3}"],
+            "split at {}",
+            split
+        );
+    }
+}
+
+#[tokio::test]
+async fn decodes_streams() {
+    let chunks = vec![
+        Ok(Bytes::from("{\"n\":")),
+        Ok(Bytes::from(" 1}\n{\"n\": 2}\n")),
+        Err(std::io::Error::other("reset")),
+        Ok(Bytes::from("{\"n\": 3}\n")),
+    ];
+    let lines: Vec<_> = ndjson::decode(stream::iter(chunks)).collect().await;
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0].as_ref().unwrap(), "{\"n\": 1}");
+    assert_eq!(lines[1].as_ref().unwrap(), "{\"n\": 2}");
+    assert!(lines[2].is_err());
+}
+
+#[cfg(feature = "testing")]
+mod mock {
+    use pantry_rs::interface::UserPermissions;
+    use pantry_rs::testing::MockPantryServer;
+    use pantry_rs::{InferenceParams, LLMSession, StreamFormat};
+
+    async fn session(server: &MockPantryServer, format: StreamFormat) -> LLMSession {
+        let builder = server.builder().stream_format(format);
+        server.running_session_with("openchat", builder).await
+    }
+
+    async fn content_type(server: &MockPantryServer, format: StreamFormat) -> String {
+        let pantry = server.login(UserPermissions::default());
+        let body = serde_json::json!({"user_id": pantry.user_id, "api_key": pantry.api_key});
+        let req = hyper::Request::post(format!("{}/subscribe_events", server.base_url().unwrap()))
+            .header("Accept", format.accept())
+            .body(hyper::Body::from(body.to_string()))
+            .unwrap();
+        let resp = hyper::Client::new().request(req).await.unwrap();
+        assert_eq!(StreamFormat::of(&resp), format);
+        resp.headers()["content-type"].to_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn negotiates_the_format() {
+        let server = MockPantryServer::start().await.unwrap();
+        assert_eq!(
+            content_type(&server, StreamFormat::Ndjson).await,
+            "application/x-ndjson"
+        );
+        assert_eq!(
+            content_type(&server, StreamFormat::Sse).await,
+            "text/event-stream"
+        );
+    }
+
+    #[tokio::test]
+    async fn prompts_over_ndjson() {
+        let server = MockPantryServer::start().await.unwrap();
+        let sess = session(&server, StreamFormat::Ndjson).await;
+        let text = sess
+            .prompt_and_collect("Hi".into(), InferenceParams::new())
+            .await
+            .unwrap();
+        assert_eq!(text, "Hello, world!");
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_sse() {
+        let server = MockPantryServer::start().await.unwrap();
+        server.ndjson(false);
+        let sess = session(&server, StreamFormat::Ndjson).await;
+        let text = sess
+            .prompt_and_collect("Hi".into(), InferenceParams::new())
+            .await
+            .unwrap();
+        assert_eq!(text, "Hello, world!");
+    }
+}
@@ -0,0 +1,128 @@
+#![cfg(feature = "streaming")]
+use pantry_rs::chat::{ChatMessage, Role};
+use pantry_rs::interface::FinishReason;
+use pantry_rs::openai_compat::{
+    ChatChoice, ChatCompletionRequest, ChatCompletionResponse, CompletionRequest, Stop, Usage,
+};
+use serde_json::json;
+
+#[test]
+fn chat_request_from_openai_json() {
+    let request: ChatCompletionRequest = serde_json::from_value(json!({
+        "model": "llama-2-7b-chat",
+        "messages": [
+            {"role": "system", "content": "Be brief."},
+            {"role": "user", "content": "Say hi"}
+        ],
+        "temperature": 0.5,
+        "max_completion_tokens": 32,
+        "stop": "\n\n",
+        "presence_penalty": 0.1,
+        "stream": true
+    }))
+    .unwrap();
+
+    assert_eq!(request.messages[0], ChatMessage::system("Be brief."));
+    assert_eq!(request.stop, Some(Stop::One("\n\n".into())));
+    assert!(request.stream);
+
+    let params = request.params();
+    assert_eq!(params.temperature, Some(0.5));
+    assert_eq!(params.max_tokens, Some(32));
+    assert_eq!(params.stop_sequences, vec!["\n\n".to_string()]);
+}
+
+#[test]
+fn completion_request_stop_list() {
+    let request: CompletionRequest = serde_json::from_value(json!({
+        "model": "default",
+        "prompt": "Once upon a time",
+        "stop": ["\n", "The end"]
+    }))
+    .unwrap();
+    assert_eq!(
+        request.params().stop_sequences,
+        vec!["\n".to_string(), "The end".to_string()]
+    );
+    assert_eq!(request.params().max_tokens, None);
+}
+
+#[test]
+fn chat_response_to_openai_json() {
+    let response = ChatCompletionResponse {
+        id: "chatcmpl-1".into(),
+        object: "chat.completion".into(),
+        created: 1700000000,
+        model: "llama-2-7b-chat".into(),
+        choices: vec![ChatChoice {
+            index: 0,
+            message: ChatMessage::assistant("Hi!"),
+            finish_reason: Some(FinishReason::Length),
+        }],
+        usage: Usage {
+            prompt_tokens: 5,
+            completion_tokens: 2,
+            total_tokens: 7,
+        },
+    };
+    let value = serde_json::to_value(&response).unwrap();
+    assert_eq!(value["choices"][0]["message"]["role"], "assistant");
+    assert_eq!(value["choices"][0]["finish_reason"], "length");
+    assert_eq!(value["usage"]["total_tokens"], 7);
+    assert_eq!(response.choices[0].message.role, Role::Assistant);
+}
+
+#[cfg(feature = "testing")]
+#[tokio::test]
+async fn dropped_streams_clean_up() {
+    use futures::StreamExt;
+    use pantry_rs::testing::MockPantryServer;
+    use std::time::Duration;
+
+    let server = MockPantryServer::start().await.unwrap();
+    server.token_delay(Duration::from_millis(50));
+    let pantry = server.running_client("openhermes");
+
+    let request: CompletionRequest =
+        serde_json::from_value(json!({"model": "default", "prompt": "Hi", "stream": true}))
+            .unwrap();
+    let mut chunks = pantry.completions_stream(&request).await.unwrap();
+    chunks.next().await.unwrap().unwrap();
+    drop(chunks);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let calls = server.calls();
+    assert!(calls.contains(&"interrupt_session".to_string()));
+    assert!(calls.contains(&"delete_session".to_string()));
+}
+
+#[cfg(feature = "testing")]
+#[tokio::test]
+async fn interrupted_replies_say_so() {
+    use pantry_rs::testing::MockPantryServer;
+    use std::time::Duration;
+
+    let server = MockPantryServer::start().await.unwrap();
+    server.token_delay(Duration::from_millis(50));
+    let pantry = server.running_client("openhermes");
+
+    let request: CompletionRequest =
+        serde_json::from_value(json!({"model": "default", "prompt": "Hi"})).unwrap();
+    let completing = {
+        let pantry = pantry.clone();
+        tokio::spawn(async move { pantry.completions(&request).await })
+    };
+    tokio::time::sleep(Duration::from_millis(80)).await;
+    let session = pantry.get_sessions().await.unwrap().remove(0);
+    pantry
+        .load_session_id(session.id)
+        .await
+        .unwrap()
+        .interrupt_session()
+        .await
+        .unwrap();
+
+    let response = completing.await.unwrap().unwrap();
+    let value = serde_json::to_value(&response).unwrap();
+    assert_eq!(value["choices"][0]["finish_reason"], "interrupted");
+}
@@ -0,0 +1,80 @@
+use pantry_rs::{InferenceParams, LoadOptions};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+#[test]
+fn typed_fields_become_parameter_keys() {
+    let map = InferenceParams::new()
+        .temperature(0.5)
+        .top_k(40)
+        .max_tokens(128)
+        .stop("\n")
+        .extra("pre_prompt", "You are helpful.")
+        .into_map();
+    assert_eq!(map["temperature"], json!(0.5));
+    assert_eq!(map["top_k"], json!(40));
+    assert_eq!(map["max_tokens"], json!(128));
+    assert_eq!(map["stop_sequences"], json!(["\n"]));
+    assert_eq!(map["pre_prompt"], json!("You are helpful."));
+    assert_eq!(
+        map["sampler_string"],
+        json!("topk:k=40/temperature:temperature=0.5")
+    );
+    assert!(!map.contains_key("seed"));
+}
+
+#[test]
+fn plain_maps_pass_through() {
+    let mut raw: HashMap<String, Value> = HashMap::new();
+    raw.insert("sampler_string".into(), json!("topk:k=1"));
+    let params: InferenceParams = raw.clone().into();
+    assert_eq!(params.clone().into_map(), raw);
+
+    // An explicit sampler_string is not overwritten.
+    let map = params.temperature(1.0).into_map();
+    assert_eq!(map["sampler_string"], json!("topk:k=1"));
+}
+
+#[test]
+fn load_options_skip_unset_fields() {
+    let options = LoadOptions::new()
+        .gpu_layers(20)
+        .mmap(false)
+        .extra("flash_attention", true);
+    assert_eq!(
+        serde_json::to_value(&options).unwrap(),
+        json!({"gpu_layers": 20, "mmap": false, "flash_attention": true})
+    );
+    assert!(!options.is_empty());
+    assert!(LoadOptions::new().is_empty());
+}
+
+#[test]
+fn constraints_go_under_their_key() {
+    let schema = json!({"type": "object", "required": ["score"]});
+    let map = InferenceParams::new()
+        .json_schema(schema.clone())
+        .into_map();
+    assert_eq!(map["json_schema"], schema);
+
+    // Only one constraint applies, the last one set.
+    let map = InferenceParams::new()
+        .grammar("root ::= \"yes\" | \"no\"")
+        .regex("[0-9]+")
+        .into_map();
+    assert_eq!(map["regex"], json!("[0-9]+"));
+    assert!(!map.contains_key("grammar"));
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn constraint_support_comes_from_user_parameters() {
+    use pantry_rs::Constraint;
+
+    let mut llm = pantry_rs::testing::mock_llm("openchat");
+    let grammar = Constraint::Grammar("root ::= \"yes\"".into());
+    assert!(!llm.supports_constraint(&grammar));
+    llm.user_parameters.push("grammar".into());
+    assert!(llm.supports_constraint(&grammar));
+    assert!(!llm.supports_constraint(&Constraint::Regex(".*".into())));
+}
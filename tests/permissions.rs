@@ -0,0 +1,132 @@
+#![cfg(feature = "testing")]
+use chrono::{Duration as ChronoDuration, Utc};
+use pantry_rs::interface::UserPermissions;
+use pantry_rs::testing::{mock_llm, MockPantryServer};
+use pantry_rs::PantryError;
+use std::collections::HashMap;
+use std::time::Duration;
+
+fn sessions_on(scope: &[&str]) -> UserPermissions {
+    UserPermissions {
+        perm_session: true,
+        llm_scope: scope.iter().map(|s| s.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn grants_scoped_permissions_for_a_while() {
+    let server = MockPantryServer::start().await.unwrap();
+    let mut llama = mock_llm("openllama");
+    llama.running = true;
+    server.add_llm(llama);
+    let mut mistral = mock_llm("mistral");
+    mistral.family_id = "mistral".into();
+    mistral.running = true;
+    server.add_llm(mistral);
+
+    let pantry = server.login(UserPermissions::default());
+    let request = pantry
+        .request_permissions_for(sessions_on(&["llama"]), Duration::from_secs(24 * 60 * 60))
+        .await
+        .unwrap();
+    assert!(request.accepted);
+
+    let granted = pantry.get_permissions().await.unwrap();
+    assert_eq!(granted.llm_scope, ["llama"]);
+    let left = granted.expires_at.unwrap() - Utc::now();
+    assert!(left > ChronoDuration::hours(23) && left <= ChronoDuration::hours(24));
+
+    pantry
+        .create_session_id(server.llms()[0].uuid, HashMap::new())
+        .await
+        .unwrap();
+    let res = pantry
+        .create_session_id(server.llms()[1].uuid, HashMap::new())
+        .await;
+    assert!(matches!(res, Err(PantryError::PermissionDenied(_))));
+}
+
+#[tokio::test]
+async fn lapsed_permissions_are_requested_again() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.login(UserPermissions::default());
+    pantry
+        .request_permissions_for(sessions_on(&[]), Duration::from_secs(1))
+        .await
+        .unwrap();
+    assert!(pantry
+        .ensure_permissions(sessions_on(&[]))
+        .await
+        .unwrap()
+        .is_none());
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert!(pantry.get_permissions().await.unwrap().is_expired());
+    assert!(pantry
+        .ensure_permissions(sessions_on(&[]))
+        .await
+        .unwrap()
+        .is_some());
+}
+
+#[tokio::test]
+async fn refuses_grants_that_would_widen() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.login(sessions_on(&["llama"]));
+    let request = pantry
+        .request_permissions_for(sessions_on(&["mistral"]), Duration::from_secs(60))
+        .await
+        .unwrap();
+    assert!(!request.accepted);
+    assert_eq!(
+        pantry.get_permissions().await.unwrap(),
+        sessions_on(&["llama"])
+    );
+}
+
+#[test]
+fn compares_scopes() {
+    let everything = sessions_on(&[]);
+    let llama = sessions_on(&["llama"]);
+    let both = sessions_on(&["llama", "mistral"]);
+    assert!(llama.is_subset_of(&everything));
+    assert!(llama.is_subset_of(&both));
+    assert!(!everything.is_subset_of(&llama));
+    assert!(!both.is_subset_of(&llama));
+
+    assert_eq!(llama.union(&sessions_on(&["mistral"])), Some(both));
+    assert_eq!(llama.union(&everything), Some(everything.clone()));
+    // Permissions that aren't about LLMs don't widen the scope.
+    let view = UserPermissions {
+        perm_view_llms: true,
+        ..Default::default()
+    };
+    assert!(view.is_subset_of(&llama.union(&view).unwrap()));
+    assert_eq!(view.union(&llama).unwrap().llm_scope, ["llama"]);
+}
+
+#[test]
+fn refuses_widening_unions() {
+    let sessions = sessions_on(&[]);
+    let load_llama = UserPermissions {
+        perm_load_llm: true,
+        llm_scope: vec!["llama".into()],
+        ..Default::default()
+    };
+    // Loading would cover every LLM, or sessions only llama.
+    assert_eq!(sessions.union(&load_llama), None);
+
+    let tomorrow = UserPermissions {
+        perm_view_llms: true,
+        expires_at: Some(Utc::now() + ChronoDuration::days(1)),
+        ..Default::default()
+    };
+    // The view permission would last for good, or sessions lapse.
+    assert_eq!(sessions.union(&tomorrow), None);
+    // Unless the lasting grant already has it.
+    let mut lasting = sessions.clone();
+    lasting.perm_view_llms = true;
+    assert_eq!(lasting.union(&tomorrow), Some(lasting.clone()));
+    assert_eq!(tomorrow.union(&lasting), Some(lasting));
+}
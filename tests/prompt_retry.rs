@@ -0,0 +1,94 @@
+#![cfg(feature = "testing")]
+use futures::StreamExt;
+use pantry_rs::interface::{FinishReason, LLMEventInternal};
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::{LLMSession, PantryError, RetryPolicy};
+use std::collections::HashMap;
+use std::time::Duration;
+
+async fn session(server: &MockPantryServer, retry: Option<RetryPolicy>) -> LLMSession {
+    let mut builder = server.builder();
+    if let Some(retry) = retry {
+        builder = builder.prompt_retry(retry);
+    }
+    server.running_session_with("openhermes", builder).await
+}
+
+fn retry(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        initial_backoff: Duration::from_millis(1),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn errors_are_final_by_default() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.fail_prompts(1);
+    let sess = session(&server, None).await;
+    let res = sess.prompt_and_collect("Hi".into(), HashMap::new()).await;
+    assert!(matches!(res, Err(PantryError::PromptError(_))));
+}
+
+#[tokio::test]
+async fn replays_in_a_fresh_session() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.reply(["Hello"]);
+    server.fail_prompts(2);
+    let sess = session(&server, Some(retry(3))).await;
+
+    let mut stream = sess
+        .prompt_session("Hi".into(), HashMap::new())
+        .await
+        .unwrap();
+    let mut text = String::new();
+    while let Some(event) = stream.next().await {
+        if let LLMEventInternal::PromptProgress { next, .. } = event.unwrap().event {
+            text.push_str(&next);
+        }
+    }
+    assert_eq!(text, "Hello");
+    assert_eq!(stream.replays(), 2);
+    assert_ne!(stream.session_id(), sess.id);
+    assert_eq!(stream.finish_reason(), Some(FinishReason::Stop));
+    let created = server
+        .calls()
+        .iter()
+        .filter(|call| call.starts_with("create_session"))
+        .count();
+    assert_eq!(created, 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_max_attempts() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.fail_prompts(2);
+    let sess = session(&server, Some(retry(2))).await;
+    let res = sess.prompt_and_collect("Hi".into(), HashMap::new()).await;
+    assert!(matches!(res, Err(PantryError::PromptError(_))));
+}
+
+#[tokio::test]
+async fn replays_the_history_too() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.reply(["Hello"]);
+    let mut sess = session(&server, Some(retry(2))).await;
+    sess.prompt_and_collect("Hi".into(), HashMap::new())
+        .await
+        .unwrap();
+
+    server.fail_prompts(1);
+    let mut stream = sess
+        .prompt_session(" Again".into(), HashMap::new())
+        .await
+        .unwrap();
+    while stream.next().await.is_some() {}
+    assert_eq!(stream.replays(), 1);
+    assert_eq!(server.last_prompt(), Some(vec!["HiHello Again".into()]));
+
+    // The old session is gone, the conversation carries on in the fresh one.
+    assert!(sess.history().await.is_err());
+    sess.id = stream.session_id();
+    assert_eq!(sess.history().await.unwrap().len(), 1);
+}
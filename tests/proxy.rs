@@ -0,0 +1,63 @@
+#![cfg(feature = "proxy")]
+use hyper::{Body, Method, Request, StatusCode};
+use pantry_rs::proxy::Proxy;
+use pantry_rs::{PantryClient, UserId};
+use serde_json::Value;
+
+fn proxy() -> Proxy {
+    // Nothing listens on port 1, none of these calls get as far as Pantry.
+    let pantry = PantryClient::login(
+        UserId::new_v4(),
+        "key".into(),
+        Some("http://127.0.0.1:1".into()),
+    );
+    Proxy::new(pantry)
+}
+
+async fn error_code(resp: hyper::Response<Body>) -> String {
+    let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    json["error"]["code"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn unknown_endpoint() {
+    let req = Request::get("/v1/embeddings").body(Body::empty()).unwrap();
+    let resp = proxy().handle(req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    assert_eq!(error_code(resp).await, "not_found");
+}
+
+#[tokio::test]
+async fn rejects_invalid_json() {
+    let req = Request::builder()
+        .method(Method::POST)
+        .uri("/v1/chat/completions")
+        .body(Body::from("{\"messages\": 3}"))
+        .unwrap();
+    let resp = proxy().handle(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(error_code(resp).await, "invalid_request_error");
+}
+
+#[tokio::test]
+async fn requires_api_key() {
+    let proxy = proxy().api_key("secret");
+
+    let req = Request::get("/v1/models").body(Body::empty()).unwrap();
+    let resp = proxy.handle(req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let req = Request::get("/v1/nothing")
+        .header("Authorization", "Bearer secret")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(proxy.handle(req).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn debug_hides_keys() {
+    let debug = format!("{:?}", proxy().api_key("proxy-secret"));
+    assert!(!debug.contains("proxy-secret"), "{}", debug);
+    assert!(!debug.contains("\"key\""), "{}", debug);
+}
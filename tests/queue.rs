@@ -0,0 +1,84 @@
+#![cfg(feature = "testing")]
+use futures::stream::StreamExt;
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::{InferenceParams, PromptQueue};
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[tokio::test]
+async fn waits_for_a_free_slot() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.running_client("openchat");
+    let first = pantry.create_session(HashMap::new()).await.unwrap();
+    let second = pantry.create_session(HashMap::new()).await.unwrap();
+    let llm = first.llm_uuid;
+
+    let queue = PromptQueue::serial();
+    let stream = queue
+        .prompt(&first, "Hi".into(), InferenceParams::new())
+        .await
+        .unwrap();
+    assert_eq!(queue.in_flight(llm), 1);
+
+    let waiting = {
+        let queue = queue.clone();
+        tokio::spawn(async move {
+            queue
+                .prompt(&second, "Hi".into(), InferenceParams::new())
+                .await
+                .unwrap()
+                .collect_text()
+                .await
+                .unwrap()
+        })
+    };
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(queue.waiting(llm), 1);
+    assert!(!waiting.is_finished());
+
+    // Finishing the first prompt frees its slot.
+    assert_eq!(stream.collect_text().await.unwrap(), "Hello, world!");
+    assert_eq!(waiting.await.unwrap(), "Hello, world!");
+    assert_eq!(queue.waiting(llm), 0);
+    assert_eq!(queue.in_flight(llm), 0);
+}
+
+#[tokio::test]
+async fn dropping_a_stream_frees_its_slot() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.token_delay(Duration::from_millis(50));
+    let pantry = server.running_client("openchat");
+    let sess = pantry.create_session(HashMap::new()).await.unwrap();
+
+    let queue = PromptQueue::serial();
+    let mut stream = queue
+        .prompt(&sess, "Hi".into(), InferenceParams::new())
+        .await
+        .unwrap();
+    assert!(stream.next().await.unwrap().is_ok());
+    drop(stream);
+    assert_eq!(queue.in_flight(sess.llm_uuid), 0);
+}
+
+#[tokio::test]
+async fn llms_have_their_own_slots() {
+    let server = MockPantryServer::start().await.unwrap();
+    let sessions = [
+        server.running_session("openchat").await,
+        server.running_session("mistral").await,
+    ];
+
+    let queue = PromptQueue::serial();
+    let _a = queue
+        .prompt(&sessions[0], "Hi".into(), InferenceParams::new())
+        .await
+        .unwrap();
+    let b = tokio::time::timeout(
+        Duration::from_secs(1),
+        queue.prompt(&sessions[1], "Hi".into(), InferenceParams::new()),
+    )
+    .await;
+    assert!(b.is_ok());
+    assert_eq!(queue.in_flight(sessions[0].llm_uuid), 1);
+    assert_eq!(queue.in_flight(sessions[1].llm_uuid), 1);
+}
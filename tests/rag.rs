@@ -0,0 +1,98 @@
+use async_trait::async_trait;
+use pantry_rs::rag::{chunk_text, context_prompt, Chunk, Corpus, Embedder};
+use pantry_rs::PantryError;
+
+/// Counts of a few words, which is all it takes to tell these documents apart.
+struct WordCounts;
+
+const WORDS: [&str; 4] = ["warranty", "battery", "screen", "shipping"];
+
+#[async_trait]
+impl Embedder for WordCounts {
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, PantryError> {
+        Ok(texts
+            .iter()
+            .map(|text| {
+                let text = text.to_lowercase();
+                WORDS
+                    .iter()
+                    .map(|w| text.matches(w).count() as f32)
+                    .collect()
+            })
+            .collect())
+    }
+}
+
+#[test]
+fn chunks_by_paragraph_then_word() {
+    let text = "One two.\n\nThree four.\n\nfive six seven eight nine ten";
+    assert_eq!(
+        chunk_text(text, 25, 0),
+        vec![
+            "One two.\n\nThree four.",
+            "five six seven eight nine",
+            "ten"
+        ]
+    );
+    // Overlapping chunks repeat the last words of the one before.
+    assert_eq!(
+        chunk_text("a1 b2 c3 d4 e5 f6", 8, 3),
+        vec!["a1 b2 c3", "c3 d4 e5", "e5 f6"]
+    );
+    assert_eq!(chunk_text("unbreakable", 4, 0), vec!["unbreakable"]);
+    assert!(chunk_text("\n\n \n\n", 10, 0).is_empty());
+}
+
+#[tokio::test]
+async fn finds_the_closest_chunks() {
+    let mut corpus = Corpus::new(WordCounts).chunking(30, 0);
+    corpus
+        .add_document(
+            "faq.md",
+            "The warranty lasts two years.\n\nShipping takes a week.\n\nThe battery holds a day.",
+        )
+        .await
+        .unwrap();
+    assert_eq!(corpus.len(), 3);
+
+    let found = corpus
+        .search("how long is the battery good", 2)
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].1.text, "The battery holds a day.");
+    assert!(found[0].0 > 0.99);
+    assert_eq!(found[0].1.source, "faq.md");
+}
+
+#[test]
+fn prompt_lists_sources() {
+    let chunk = Chunk {
+        source: "faq.md".into(),
+        text: "Shipping takes a week.".into(),
+    };
+    let prompt = context_prompt("How long is shipping?", &[&chunk]);
+    assert!(prompt.contains("[1] (faq.md)\nShipping takes a week."));
+    assert!(prompt.ends_with("Question: How long is shipping?\nAnswer:"));
+}
+
+#[cfg(feature = "testing")]
+#[tokio::test]
+async fn answers_with_context() {
+    use pantry_rs::rag::answer_with_context;
+    use pantry_rs::testing::MockPantryServer;
+
+    let server = MockPantryServer::start().await.unwrap();
+    server.reply([" Two", " years."]);
+    let sess = server.running_session("openhermes").await;
+
+    let mut corpus = Corpus::new(WordCounts);
+    corpus
+        .add_document("faq.md", "The warranty lasts two years.")
+        .await
+        .unwrap();
+    let answer = answer_with_context(&sess, "What's the warranty?", &corpus, 3)
+        .await
+        .unwrap();
+    assert_eq!(answer, "Two years.");
+}
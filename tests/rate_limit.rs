@@ -0,0 +1,75 @@
+use pantry_rs::rate_limit::{RateLimit, RateLimiter};
+use std::time::Duration;
+
+#[test]
+fn unlimited_never_waits() {
+    let limiter = RateLimiter::new(RateLimit::default());
+    limiter.spend_tokens(1_000_000);
+    for _ in 0..1000 {
+        assert_eq!(limiter.try_acquire(), None);
+    }
+}
+
+#[test]
+fn requests_burst_then_wait() {
+    let limiter = RateLimiter::new(RateLimit {
+        requests_per_second: Some(2.0),
+        ..Default::default()
+    });
+    assert_eq!(limiter.try_acquire(), None);
+    assert_eq!(limiter.try_acquire(), None);
+    let wait = limiter.try_acquire().unwrap();
+    assert!(wait > Duration::from_millis(400) && wait <= Duration::from_millis(500));
+}
+
+#[test]
+fn spent_tokens_hold_up_calls() {
+    let limiter = RateLimiter::new(RateLimit {
+        tokens_per_minute: Some(600),
+        ..Default::default()
+    });
+    assert_eq!(limiter.try_acquire(), None);
+    // 10 tokens a second, so going 20 into debt takes a bit over 2s to earn back.
+    limiter.spend_tokens(620);
+    let wait = limiter.try_acquire().unwrap();
+    assert!(wait > Duration::from_secs(2) && wait <= Duration::from_millis(2100));
+}
+
+#[test]
+fn non_positive_limits_are_ignored() {
+    let limiter = RateLimiter::new(RateLimit {
+        requests_per_second: Some(0.0),
+        tokens_per_minute: Some(0),
+    });
+    assert_eq!(limiter.limit(), &RateLimit::default());
+    assert_eq!(limiter.try_acquire(), None);
+}
+
+#[cfg(feature = "testing")]
+mod client {
+    use super::*;
+    use pantry_rs::interface::UserPermissions;
+    use pantry_rs::testing::MockPantryServer;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn throttles_client_calls() {
+        let server = MockPantryServer::start().await.unwrap();
+        let (pantry, _) = server
+            .builder()
+            .rate_limit(RateLimit {
+                requests_per_second: Some(10.0),
+                ..Default::default()
+            })
+            .register("throttled".into(), UserPermissions::default())
+            .await
+            .unwrap();
+
+        // Registering used up two of the ten in the bucket.
+        let started = Instant::now();
+        for _ in 0..10 {
+            pantry.server_info().await.unwrap();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+}
@@ -0,0 +1,106 @@
+#![cfg(feature = "testing")]
+use futures::stream::StreamExt;
+use pantry_rs::interface::LLMEventInternal;
+use pantry_rs::testing::{MockPantryServer, DEFAULT_REPLY};
+use pantry_rs::{InferenceParams, PantryClient, PantryError, RetryPolicy};
+use std::collections::HashMap;
+use std::time::Duration;
+
+fn quick_policy() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(5),
+        multiplier: 2.0,
+        jitter: false,
+    }
+}
+
+async fn start() -> (MockPantryServer, PantryClient) {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.running_client("openchat");
+    (server, pantry)
+}
+
+fn stream_calls(server: &MockPantryServer) -> usize {
+    server
+        .calls()
+        .iter()
+        .filter(|call| *call == "prompt_session_stream")
+        .count()
+}
+
+#[tokio::test]
+async fn resumes_after_the_last_event() {
+    let (server, pantry) = start().await;
+    let pantry = server
+        .builder()
+        .reconnect(quick_policy())
+        .login(pantry.user_id, pantry.api_key);
+    let sess = pantry.create_session(HashMap::new()).await.unwrap();
+
+    server.drop_stream_after(2);
+    let events: Vec<_> = sess
+        .prompt_session("Hi".into(), InferenceParams::new())
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+
+    // Every token exactly once, all from the same stream, then the completion.
+    let tokens: Vec<String> = events
+        .iter()
+        .filter_map(|event| match &event.event {
+            LLMEventInternal::PromptProgress { next, .. } => Some(next.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(tokens, DEFAULT_REPLY);
+    assert!(events.iter().all(|e| e.stream_id == events[0].stream_id));
+    assert!(matches!(
+        events.last().unwrap().event,
+        LLMEventInternal::PromptCompletion { .. }
+    ));
+    assert_eq!(stream_calls(&server), 2);
+
+    let history = sess.history().await.unwrap();
+    assert_eq!(history.len(), 1);
+    assert!(history[0].complete);
+}
+
+#[tokio::test]
+async fn gives_up_without_reconnects() {
+    let (server, pantry) = start().await;
+    let pantry = server
+        .builder()
+        .reconnect(RetryPolicy::none())
+        .login(pantry.user_id, pantry.api_key);
+    let sess = pantry.create_session(HashMap::new()).await.unwrap();
+
+    server.drop_stream_after(1);
+    let events: Vec<_> = sess
+        .prompt_session("Hi".into(), InferenceParams::new())
+        .await
+        .unwrap()
+        .collect()
+        .await;
+
+    assert_eq!(events.len(), 2);
+    assert!(events[0].is_ok());
+    assert!(matches!(events[1], Err(PantryError::StreamError(_))));
+    assert_eq!(stream_calls(&server), 1);
+}
+
+#[tokio::test]
+async fn collects_across_drops() {
+    let (server, pantry) = start().await;
+    let sess = pantry.create_session(HashMap::new()).await.unwrap();
+
+    server.drop_stream_after(3);
+    let text = sess
+        .prompt_and_collect("Hi".into(), InferenceParams::new())
+        .await
+        .unwrap();
+    assert_eq!(text, DEFAULT_REPLY.concat());
+}
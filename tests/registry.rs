@@ -0,0 +1,96 @@
+use pantry_rs::huggingface::HuggingFaceModel;
+use pantry_rs::interface::{CapabilityType, LLMConnectorType, LLMRegistryEntry};
+use pantry_rs::PantryError;
+
+#[test]
+fn llmrs_defaults() {
+    let entry = LLMRegistryEntry::builder("openchat", LLMConnectorType::LLMrs)
+        .url("https://example.com/model.bin")
+        .config("model_architecture", "llama")
+        .build()
+        .unwrap();
+    assert!(entry.local);
+    assert_eq!(entry.name, "openchat");
+    assert_eq!(entry.capabilities.get(&CapabilityType::General), Some(&-1));
+    assert!(entry
+        .user_session_parameters
+        .contains(&"system_prompt".to_string()));
+}
+
+#[test]
+fn llmrs_requires_model_architecture() {
+    let res = LLMRegistryEntry::builder("openchat", LLMConnectorType::LLMrs)
+        .url("https://example.com/model.bin")
+        .build();
+    assert!(matches!(res, Err(PantryError::InvalidRegistryEntry(_))));
+}
+
+#[test]
+fn generic_api_requires_url() {
+    let entry = LLMRegistryEntry::builder("remote", LLMConnectorType::GenericAPI).build_unchecked();
+    assert!(!entry.local);
+    assert!(entry.validate().is_err());
+}
+
+#[test]
+fn huggingface_info() {
+    let info: HuggingFaceModel = serde_json::from_value(serde_json::json!({
+        "id": "TheBloke/OpenChat_v3.2-GGML",
+        "author": "TheBloke",
+        "tags": ["transformers", "llama", "license:other"],
+        "cardData": {"license": "other", "model_type": "llama", "model_creator": "OpenChat"},
+        "siblings": [
+            {"rfilename": "README.md"},
+            {"rfilename": "openchat_v3.2.ggmlv3.q5_0.bin"},
+            {"rfilename": "openchat_v3.2.ggmlv3.q4_0.bin"}
+        ]
+    }))
+    .unwrap();
+    let readme = "---\nlicense: other\n---\n# OpenChat\n\n<img src=\"x\">\n\nOpenChat is a series\nof open-source models.\n\nMore text.";
+
+    let entry = LLMRegistryEntry::from_huggingface_info(&info, Some(readme), "*q4_0.bin")
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(entry.id, "openchat_v3.2.ggmlv3.q4_0");
+    assert_eq!(
+        entry.url,
+        "https://huggingface.co/TheBloke/OpenChat_v3.2-GGML/resolve/main/openchat_v3.2.ggmlv3.q4_0.bin"
+    );
+    assert_eq!(entry.config["model_architecture"], "llama");
+    assert_eq!(entry.organization, "OpenChat");
+    assert_eq!(
+        entry.description,
+        "OpenChat is a series of open-source models."
+    );
+    assert_eq!(entry.tags, vec!["transformers", "llama"]);
+
+    assert!(matches!(
+        LLMRegistryEntry::from_huggingface_info(&info, None, "*.gguf"),
+        Err(PantryError::NoMatchingFile(repo, pattern)) if repo == info.id && pattern == "*.gguf"
+    ));
+}
+
+#[test]
+fn local_entries_need_no_url() {
+    let entry = LLMRegistryEntry::builder("local", LLMConnectorType::LLMrs)
+        .config("model_architecture", "llama")
+        .build_unchecked();
+    assert!(entry.validate().is_err());
+    assert!(entry.validate_local().is_ok());
+}
+
+#[test]
+fn capability_keys() {
+    let entry = LLMRegistryEntry::builder("m", LLMConnectorType::LLMrs).build_unchecked();
+    let mut json = serde_json::to_value(&entry).unwrap();
+    assert_eq!(json["capabilities"]["coding"], -1);
+
+    json["capabilities"] = serde_json::json!({"coding": 5, "reasoning": 3});
+    let entry: LLMRegistryEntry = serde_json::from_value(json).unwrap();
+    assert_eq!(entry.capabilities[&CapabilityType::Coding], 5);
+    let other = CapabilityType::Other("reasoning".into());
+    assert_eq!(entry.capabilities[&other], 3);
+    assert!(!other.is_known());
+    assert_eq!(CapabilityType::from("Coding"), CapabilityType::Coding);
+}
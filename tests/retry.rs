@@ -0,0 +1,51 @@
+use pantry_rs::{PantryError, RetryPolicy};
+use std::time::Duration;
+
+fn quick_policy() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 4,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(5),
+        multiplier: 2.0,
+        jitter: false,
+    }
+}
+
+#[test]
+fn backoff_is_capped() {
+    let policy = quick_policy();
+    assert_eq!(policy.backoff(0), Duration::from_millis(1));
+    assert_eq!(policy.backoff(1), Duration::from_millis(2));
+    assert_eq!(policy.backoff(10), Duration::from_millis(5));
+}
+
+#[tokio::test]
+async fn retries_transient_errors_until_exhausted() {
+    let mut attempts = 0;
+    let res: Result<(), PantryError> = quick_policy()
+        .run(|| {
+            attempts += 1;
+            async { Err(PantryError::Timeout(Duration::from_secs(1))) }
+        })
+        .await;
+    assert!(matches!(res, Err(PantryError::Timeout(_))));
+    assert_eq!(attempts, 4);
+}
+
+#[tokio::test]
+async fn does_not_retry_api_errors() {
+    let mut attempts = 0;
+    let res: Result<(), PantryError> = quick_policy()
+        .run(|| {
+            attempts += 1;
+            async {
+                Err(PantryError::ApiError(
+                    hyper::StatusCode::FORBIDDEN,
+                    "no".into(),
+                ))
+            }
+        })
+        .await;
+    assert!(res.is_err());
+    assert_eq!(attempts, 1);
+}
@@ -0,0 +1,36 @@
+use pantry_rs::interface::{ServerInfo, PROTOCOL_VERSION};
+use pantry_rs::{PantryClient, PantryError, UserId};
+use serde_json::json;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+#[test]
+fn server_info_endpoints() {
+    let info: ServerInfo = serde_json::from_value(json!({
+        "version": "0.0.5",
+        "protocol_version": PROTOCOL_VERSION,
+        "endpoints": ["get_sessions", "fork_session"]
+    }))
+    .unwrap();
+    assert!(info.is_compatible());
+    assert!(info.supports("fork_session"));
+    assert!(info.supports("/get_sessions"));
+    assert!(!info.supports("server_info"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn wait_for_server_times_out() {
+    let socket = std::env::temp_dir().join(format!("pantry-rs-missing-{}.sock", Uuid::new_v4()));
+    let pantry = PantryClient::builder()
+        .socket_path(socket.to_string_lossy())
+        .login(UserId::new_v4(), "key".into());
+
+    let start = Instant::now();
+    let err = pantry
+        .wait_for_server(Duration::from_millis(300))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PantryError::Timeout(_)), "{:?}", err);
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
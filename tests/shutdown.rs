@@ -0,0 +1,94 @@
+#![cfg(feature = "testing")]
+use futures::StreamExt;
+use pantry_rs::lifecycle::{ShutdownOptions, ShutdownReport};
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::PantryError;
+use std::collections::HashMap;
+use std::time::Duration;
+
+fn drain_for(drain_timeout: Duration, interrupt_sessions: bool) -> ShutdownOptions {
+    ShutdownOptions {
+        drain_timeout,
+        interrupt_sessions,
+    }
+}
+
+#[tokio::test]
+async fn drains_prompts_in_flight() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.reply(["Hello", " there"]);
+    server.token_delay(Duration::from_millis(20));
+    let pantry = server.running_client("openchat");
+    let sess = pantry.create_session(HashMap::new()).await.unwrap();
+    let stream = sess
+        .prompt_session("Hi".into(), HashMap::new())
+        .await
+        .unwrap();
+    let reader = tokio::spawn(stream.collect_text());
+
+    let report = pantry
+        .shutdown(drain_for(Duration::from_secs(5), false))
+        .await;
+    assert_eq!(
+        report,
+        ShutdownReport {
+            drained: 1,
+            cut_off: 0
+        }
+    );
+    assert_eq!(reader.await.unwrap().unwrap(), "Hello there");
+}
+
+#[tokio::test]
+async fn cuts_off_and_interrupts_the_rest() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.reply(["Hello", " there"]);
+    server.token_delay(Duration::from_secs(5));
+    let pantry = server.running_client("openchat");
+    let sess = pantry.create_session(HashMap::new()).await.unwrap();
+    let mut stream = sess
+        .prompt_session("Hi".into(), HashMap::new())
+        .await
+        .unwrap();
+
+    let report = pantry
+        .shutdown(drain_for(Duration::from_millis(50), true))
+        .await;
+    assert_eq!(
+        report,
+        ShutdownReport {
+            drained: 0,
+            cut_off: 1
+        }
+    );
+    assert!(matches!(
+        stream.next().await,
+        Some(Err(PantryError::ClientShutDown))
+    ));
+    assert!(stream.next().await.is_none());
+    assert!(server.calls().contains(&"interrupt_session".to_string()));
+}
+
+#[tokio::test]
+async fn ends_subscriptions_and_refuses_new_work() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.running_client("openchat");
+    let sess = pantry.create_session(HashMap::new()).await.unwrap();
+    let mut events = pantry.subscribe_events().await.unwrap();
+
+    // Copies shut down with the original.
+    let copy = pantry.with_timeout(Some(Duration::from_secs(5)));
+    copy.shutdown(ShutdownOptions::default()).await;
+
+    assert!(events.next().await.is_none());
+    assert!(matches!(
+        pantry.subscribe_events().await,
+        Err(PantryError::ClientShutDown)
+    ));
+    assert!(matches!(
+        sess.prompt_session("Hi".into(), HashMap::new()).await,
+        Err(PantryError::ClientShutDown)
+    ));
+    // Other calls still work.
+    assert!(pantry.get_permissions().await.unwrap().perm_session);
+}
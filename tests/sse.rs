@@ -0,0 +1,118 @@
+#![cfg(feature = "streaming")]
+use futures::stream::{self, StreamExt};
+use hyper::body::Bytes;
+use pantry_rs::sse::{self, SseEvent, SseParser};
+use std::time::Duration;
+
+fn message(data: &str, id: Option<&str>) -> SseEvent {
+    SseEvent {
+        event: "message".into(),
+        data: data.into(),
+        id: id.map(String::from),
+    }
+}
+
+fn parse(chunks: &[&[u8]]) -> Vec<SseEvent> {
+    let mut parser = SseParser::new();
+    let mut events: Vec<SseEvent> = chunks.iter().flat_map(|c| parser.feed(c)).collect();
+    events.extend(parser.finish());
+    events
+}
+
+/// Parses `body` whole, split at every single point, and byte by byte, checking all
+/// of them come out as `expected`.
+fn assert_parses(body: &[u8], expected: &[SseEvent]) {
+    assert_eq!(parse(&[body]), expected, "whole");
+    for split in 0..=body.len() {
+        let (a, b) = body.split_at(split);
+        assert_eq!(parse(&[a, b]), expected, "split at {}", split);
+    }
+    let bytes: Vec<&[u8]> = body.chunks(1).collect();
+    assert_eq!(parse(&bytes), expected, "byte by byte");
+}
+
+#[test]
+fn line_endings() {
+    let expected = [message("a", None), message("b", None), message("c", None)];
+    assert_parses(b"data: a\n\ndata: b\r\n\r\ndata: c\r\r", &expected);
+    assert_parses(b"data: a\r\n\rdata: b\n\r\ndata: c\n\n", &expected);
+}
+
+#[test]
+fn multi_line_data_and_comments() {
+    assert_parses(
+        b": keep-alive\ndata: {\"a\":\ndata:1}\n: another\n\n:\n\n",
+        &[message("{\"a\":\n1}", None)],
+    );
+    // Empty data lines still count.
+    assert_parses(b"data\ndata\n\n", &[message("\n", None)]);
+    // No data, no event.
+    assert_parses(b"event: ping\n\nid: 7\n\n", &[]);
+}
+
+#[test]
+fn split_utf8() {
+    let body = "data: héllo wörld 🦀\n\n".as_bytes();
+    assert_parses(body, &[message("héllo wörld 🦀", None)]);
+}
+
+#[test]
+fn fields() {
+    let body = b"\xEF\xBB\xBFretry: 2500\nid: 1\nevent: progress\ndata:no space\n\nid\ndata: x\n\nbogus: y\nretry: soon\ndata: z\n\n";
+    let expected = [
+        SseEvent {
+            event: "progress".into(),
+            data: "no space".into(),
+            id: Some("1".into()),
+        },
+        message("x", Some("")),
+        message("z", Some("")),
+    ];
+    assert_parses(body, &expected);
+
+    let mut parser = SseParser::new();
+    parser.feed(body);
+    assert_eq!(parser.retry(), Some(Duration::from_millis(2500)));
+    assert_eq!(parser.last_event_id(), Some(""));
+}
+
+#[test]
+fn unterminated_last_event() {
+    assert_parses(
+        b"data: a\n\ndata: b",
+        &[message("a", None), message("b", None)],
+    );
+    assert_parses(
+        b"data: a\n\ndata: b\n",
+        &[message("a", None), message("b", None)],
+    );
+}
+
+#[tokio::test]
+async fn decodes_streams() {
+    let chunks: Vec<Result<Bytes, std::io::Error>> =
+        ["data: {\"n\"", ": 1}\r", "\n\r\ndata: ", "{\"n\": 2}"]
+            .into_iter()
+            .map(|c| Ok(Bytes::from(c)))
+            .collect();
+    let events: Vec<SseEvent> = sse::decode(stream::iter(chunks))
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(
+        events,
+        vec![message("{\"n\": 1}", None), message("{\"n\": 2}", None)]
+    );
+}
+
+#[tokio::test]
+async fn stops_on_errors() {
+    let chunks = vec![
+        Ok(Bytes::from("data: a\n\n")),
+        Err(std::io::Error::other("reset")),
+        Ok(Bytes::from("data: b\n\n")),
+    ];
+    let events: Vec<_> = sse::decode(stream::iter(chunks)).collect().await;
+    assert_eq!(events.len(), 2);
+    assert!(events[1].is_err());
+}
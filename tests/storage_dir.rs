@@ -0,0 +1,51 @@
+#![cfg(feature = "testing")]
+use pantry_rs::interface::{LLMConnectorType, LLMRegistryEntry, UserPermissions};
+use pantry_rs::testing::MockPantryServer;
+
+fn entry(id: &str) -> LLMRegistryEntry {
+    // A file url, so there's nothing to check before filing the download.
+    LLMRegistryEntry::builder(id, LLMConnectorType::LLMrs)
+        .url(format!("file:///models/{}.bin", id))
+        .config("model_architecture", "llama")
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn downloads_to_target_dir() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.login(UserPermissions {
+        perm_download_llm: true,
+        perm_request_download: true,
+        perm_view_llms: true,
+        ..Default::default()
+    });
+
+    let external = pantry
+        .download_llm_to(entry("external"), "/media/ssd/pantry")
+        .await
+        .unwrap();
+    let requested = pantry
+        .request_download_llm_to(entry("requested"), "/mnt/share/models")
+        .await
+        .unwrap();
+    assert!(requested.accepted);
+    let default = pantry.download_llm(entry("default")).await.unwrap();
+
+    let storage = pantry.get_storage_info().await.unwrap();
+    let path = |id: &str| {
+        storage
+            .models
+            .iter()
+            .find(|model| model.id == id)
+            .unwrap()
+            .path
+            .clone()
+    };
+    assert_eq!(
+        path("external"),
+        format!("/media/ssd/pantry/{}.bin", external)
+    );
+    assert!(path("requested").starts_with("/mnt/share/models/"));
+    assert_eq!(path("default"), format!("/mock/models/{}.bin", default));
+}
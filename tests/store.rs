@@ -0,0 +1,83 @@
+#![cfg(all(feature = "store", feature = "testing"))]
+use pantry_rs::chat::{ChatSession, Role};
+use pantry_rs::store::ConversationStore;
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::{ConversationId, PantryClient};
+use serde_json::json;
+use std::collections::HashMap;
+
+async fn chat(server: &MockPantryServer) -> (PantryClient, ChatSession) {
+    let pantry = server.running_client("openhermes");
+    let sess = pantry.create_session(HashMap::new()).await.unwrap();
+    (pantry, ChatSession::new(sess).system("Be brief."))
+}
+
+#[tokio::test]
+async fn saves_and_resumes_conversations() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.reply(["Hello!"]);
+    let (pantry, mut chat) = chat(&server).await;
+    chat.send_and_collect("Hi").await.unwrap();
+
+    let store = ConversationStore::in_memory().unwrap();
+    let id = ConversationId::new_v4();
+    store.save(id, &chat).unwrap();
+    store.set_title(id, "Greetings").unwrap();
+    store
+        .set_metadata(id, &HashMap::from([("tag".to_string(), json!("demo"))]))
+        .unwrap();
+
+    let listed = store.list().unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].title.as_deref(), Some("Greetings"));
+    assert_eq!(listed[0].message_count, 3);
+
+    let stored = store.load(id).unwrap().unwrap();
+    assert_eq!(stored.metadata["tag"], json!("demo"));
+    assert_eq!(stored.handle.session_id, chat.session().id);
+
+    // Resumed, it carries on in the same session.
+    let mut resumed = store.resume(&pantry, id).unwrap();
+    assert_eq!(resumed.messages(), chat.messages());
+    assert_eq!(resumed.transcript(), chat.transcript());
+    resumed.send_and_collect("Again").await.unwrap();
+    assert_eq!(resumed.session().id, chat.session().id);
+
+    // Saving again keeps the title.
+    store.save(id, &resumed).unwrap();
+    assert_eq!(store.list().unwrap()[0].message_count, 5);
+    let markdown = store.export_markdown(id).unwrap();
+    assert!(markdown.starts_with("# Greetings\n"));
+    assert!(markdown.contains("## User\n\nAgain\n"));
+    let exported: serde_json::Value =
+        serde_json::from_str(&store.export_json(id).unwrap()).unwrap();
+    assert_eq!(
+        exported["transcript"]["messages"][0]["role"],
+        json!("system")
+    );
+    assert_eq!(
+        store.load(id).unwrap().unwrap().transcript.messages[4].role,
+        Role::Assistant
+    );
+
+    assert!(store.delete(id).unwrap());
+    assert!(store.load(id).unwrap().is_none());
+    assert!(store.resume(&pantry, id).is_err());
+}
+
+#[test]
+fn persists_to_disk() {
+    let path = std::env::temp_dir().join(format!("pantry-store-{}.db", ConversationId::new_v4()));
+    let store = ConversationStore::open(&path).unwrap();
+    assert!(store.list().unwrap().is_empty());
+    assert!(store
+        .set_title(ConversationId::new_v4(), "missing")
+        .is_err());
+    drop(store);
+    assert!(ConversationStore::open(&path)
+        .unwrap()
+        .list()
+        .unwrap()
+        .is_empty());
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,87 @@
+use pantry_rs::structured::{extract_json, parse_json};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Review {
+    sentiment: String,
+    score: u8,
+}
+
+#[test]
+fn extracts_json_from_prose() {
+    let text = "Sure! Here it is:\n```json\n{\"a\": [1, 2], \"b\": \"}\"}\n```\nAnything else?";
+    assert_eq!(extract_json(text), Some("{\"a\": [1, 2], \"b\": \"}\"}"));
+    assert_eq!(extract_json("[1, [2]] and [3]"), Some("[1, [2]]"));
+    assert_eq!(extract_json("{\"unterminated\": 1"), None);
+    assert_eq!(extract_json("no json here"), None);
+}
+
+#[test]
+fn parses_into_the_type() {
+    let review: Review = parse_json("{\"sentiment\": \"mixed\", \"score\": 6}").unwrap();
+    assert_eq!(
+        review,
+        Review {
+            sentiment: "mixed".into(),
+            score: 6
+        }
+    );
+    assert!(parse_json::<Review>("{\"sentiment\": \"mixed\"}").is_err());
+    assert!(parse_json::<Review>("mixed, 6").is_err());
+}
+
+#[test]
+fn parses_scalars() {
+    assert_eq!(parse_json::<u32>(" 42\n"), Ok(42));
+    assert_eq!(parse_json::<bool>("```json\ntrue\n```"), Ok(true));
+    assert_eq!(parse_json::<String>("\"Paris\""), Ok("Paris".to_string()));
+    assert_eq!(parse_json::<String>("Paris"), Ok("Paris".to_string()));
+    assert_eq!(parse_json::<Vec<u8>>("Sure: [1, 2]"), Ok(vec![1, 2]));
+    assert!(parse_json::<u32>("about 42").is_err());
+    assert!(parse_json::<Review>("{\"sentiment\": 1}")
+        .unwrap_err()
+        .starts_with("the JSON doesn't fit"));
+}
+
+#[cfg(feature = "testing")]
+mod session {
+    use super::*;
+
+    use pantry_rs::testing::MockPantryServer;
+    use pantry_rs::{InferenceParams, PantryError};
+
+    fn prompts(server: &MockPantryServer) -> usize {
+        server
+            .calls()
+            .iter()
+            .filter(|call| call.starts_with("prompt_session"))
+            .count()
+    }
+
+    #[tokio::test]
+    async fn prompts_typed() {
+        let server = MockPantryServer::start().await.unwrap();
+        server.reply(["Here you go: {\"sentiment\": ", "\"good\", \"score\": 9}"]);
+        let sess = server.running_session("openchat").await;
+
+        let review: Review = sess
+            .prompt_typed("Review this".into(), InferenceParams::new())
+            .await
+            .unwrap();
+        assert_eq!(review.score, 9);
+        assert_eq!(prompts(&server), 1);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let server = MockPantryServer::start().await.unwrap();
+        server.reply(["{\"sentiment\": \"good\"}"]);
+        let sess = server.running_session("openchat").await;
+
+        let res = sess
+            .prompt_typed::<Review>("Review this".into(), InferenceParams::new())
+            .await;
+        assert!(matches!(res, Err(PantryError::StructuredOutputError(3, _))));
+        assert_eq!(prompts(&server), 3);
+    }
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "testing")]
+use pantry_rs::interface::UserPermissions;
+use pantry_rs::testing::{mock_llm, MockPantryServer, MOCK_RAM_BYTES};
+use pantry_rs::PantryError;
+
+#[tokio::test]
+async fn running_llms_use_ram() {
+    let server = MockPantryServer::start().await.unwrap();
+    let mut running = mock_llm("openchat");
+    running.running = true;
+    running.reported_ram_bytes = Some(4 << 30);
+    let uuid = running.uuid;
+    server.add_llm(running);
+    let mut big = mock_llm("falcon-180b");
+    big.reported_ram_bytes = Some(100 << 30);
+    let mut small = mock_llm("tinyllama");
+    small.reported_ram_bytes = Some(1 << 30);
+    server.add_llm(big.clone());
+
+    let pantry = server.login(UserPermissions {
+        perm_view_llms: true,
+        ..Default::default()
+    });
+    let status = pantry.get_system_status().await.unwrap();
+    assert_eq!(status.ram_used_bytes, 4 << 30);
+    assert_eq!(status.ram_free_bytes(), MOCK_RAM_BYTES - (4 << 30));
+    assert_eq!(status.vram_free_bytes(), None);
+    assert_eq!(status.llms.len(), 1);
+    assert_eq!(status.llms[0].llm_uuid, uuid);
+
+    assert_eq!(status.fits(&big), Some(false));
+    assert_eq!(status.fits(&small), Some(true));
+    assert_eq!(status.fits(&mock_llm("mystery")), None);
+}
+
+#[tokio::test]
+async fn needs_view_permission() {
+    let server = MockPantryServer::start().await.unwrap();
+    let pantry = server.login(UserPermissions::default());
+    assert!(matches!(
+        pantry.get_system_status().await,
+        Err(PantryError::PermissionDenied(_))
+    ));
+}
+
+#[tokio::test]
+async fn storage_per_model() {
+    let server = MockPantryServer::start().await.unwrap();
+    for (id, size) in [("openchat", 4u64 << 30), ("falcon-180b", 100 << 30)] {
+        let mut llm = mock_llm(id);
+        llm.download_total = Some(size);
+        server.add_llm(llm);
+    }
+    let pantry = server.login(UserPermissions {
+        perm_view_llms: true,
+        ..Default::default()
+    });
+
+    let storage = pantry.get_storage_info().await.unwrap();
+    assert_eq!(storage.total_bytes, 104 << 30);
+    let ids: Vec<&str> = storage
+        .largest_first()
+        .iter()
+        .map(|model| model.id.as_str())
+        .collect();
+    assert_eq!(ids, ["falcon-180b", "openchat"]);
+}
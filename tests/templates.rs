@@ -0,0 +1,85 @@
+#![cfg(feature = "templates")]
+use pantry_rs::templates::{context, PromptTemplates};
+use pantry_rs::PantryError;
+
+fn templates() -> PromptTemplates {
+    let mut templates = PromptTemplates::new();
+    templates
+        .add("summarize", "{% include 'preamble' %}Summarize: {{ text }}")
+        .unwrap();
+    templates.add("preamble", "").unwrap();
+    templates
+        .add_for_family("llama", "preamble", "[INST] Be brief. [/INST]\n")
+        .unwrap();
+    templates
+}
+
+#[test]
+fn renders_with_family_partials() {
+    let templates = templates();
+    assert_eq!(templates.names(), vec!["preamble", "summarize"]);
+    assert_eq!(
+        templates
+            .render("summarize", None, context! { text => "a long story" })
+            .unwrap(),
+        "Summarize: a long story"
+    );
+
+    #[cfg(feature = "testing")]
+    {
+        let mut llm = pantry_rs::testing::mock_llm("llama-2-7b");
+        llm.family_id = "llama".into();
+        assert_eq!(
+            templates
+                .render("summarize", Some(&llm), context! { text => "a long story" })
+                .unwrap(),
+            "[INST] Be brief. [/INST]\nSummarize: a long story"
+        );
+    }
+}
+
+#[test]
+fn reports_template_errors() {
+    let mut templates = templates();
+    assert!(matches!(
+        templates.add("broken", "{% if %}"),
+        Err(PantryError::TemplateError(_))
+    ));
+    // Missing variables and templates fail instead of rendering blanks.
+    assert!(templates.render("summarize", None, context! {}).is_err());
+    assert!(templates.render("translate", None, context! {}).is_err());
+}
+
+#[cfg(feature = "testing")]
+#[tokio::test]
+async fn sessions_prompt_by_name() {
+    use futures::StreamExt;
+    use pantry_rs::interface::LLMEventInternal;
+    use pantry_rs::testing::MockPantryServer;
+    use std::collections::HashMap;
+
+    let server = MockPantryServer::start().await.unwrap();
+    server.reply(["Short."]);
+    let sess = server.running_session("openhermes").await;
+    assert!(matches!(
+        sess.render_template("summarize", context! {}),
+        Err(PantryError::TemplateError(_))
+    ));
+
+    let pantry = server
+        .builder()
+        .templates(templates())
+        .login(sess.user_id, sess.api_key.clone());
+    let sess = pantry.create_session(HashMap::new()).await.unwrap();
+    let mut stream = sess
+        .prompt_template("summarize", context! { text => "a long story" })
+        .await
+        .unwrap();
+    let mut reply = String::new();
+    while let Some(event) = stream.next().await {
+        if let LLMEventInternal::PromptProgress { next, .. } = event.unwrap().event {
+            reply.push_str(&next);
+        }
+    }
+    assert_eq!(reply, "Short.");
+}
@@ -0,0 +1,179 @@
+#![cfg(feature = "testing")]
+use futures::stream::StreamExt;
+use pantry_rs::interface::{RequestOutcome, ServerEvent, UserPermissions};
+use pantry_rs::testing::{mock_llm, MockPantryServer};
+use pantry_rs::{InferenceParams, LLMFilter, LoadOptions, PantryError};
+use std::collections::HashMap;
+use std::time::Duration;
+
+fn perms() -> UserPermissions {
+    UserPermissions {
+        perm_load_llm: true,
+        perm_session: true,
+        perm_view_llms: true,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn register_load_and_prompt() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.add_llm(mock_llm("openchat"));
+
+    let (pantry, request) = server
+        .builder()
+        .register("testing".into(), perms())
+        .await
+        .unwrap();
+    assert!(request.accepted);
+    assert_eq!(pantry.get_permissions().await.unwrap(), perms());
+
+    pantry.load_llm_flex(None, None).await.unwrap();
+    assert_eq!(pantry.get_running_llms().await.unwrap().len(), 1);
+
+    let sess = pantry.create_session(HashMap::new()).await.unwrap();
+    let text = sess
+        .prompt_and_collect("Hi".into(), InferenceParams::new())
+        .await
+        .unwrap();
+    assert_eq!(text, "Hello, world!");
+
+    let history = sess.history().await.unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].input, "Hi");
+    assert!(history[0].complete);
+
+    sess.delete().await.unwrap();
+    assert!(pantry.get_sessions().await.unwrap().is_empty());
+    assert!(server
+        .calls()
+        .contains(&"prompt_session_stream".to_string()));
+}
+
+#[tokio::test]
+async fn checks_permissions() {
+    let server = MockPantryServer::start().await.unwrap();
+    let mut llm = mock_llm("openchat");
+    llm.running = true;
+    server.add_llm(llm);
+
+    let pantry = server.login(UserPermissions {
+        perm_view_llms: true,
+        ..Default::default()
+    });
+    assert!(matches!(
+        pantry.create_session(HashMap::new()).await,
+        Err(PantryError::PermissionDenied(_))
+    ));
+
+    let stranger = server.builder().login(pantry.user_id, "wrong".into());
+    assert!(matches!(
+        stranger.get_running_llms().await,
+        Err(PantryError::InvalidApiKey(_))
+    ));
+}
+
+#[tokio::test]
+async fn load_options_reach_the_connector() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.add_llm(mock_llm("openchat"));
+    let pantry = server.login(perms());
+
+    let running = pantry
+        .load_llm_flex_with(None, None, LoadOptions::new().n_threads(4).max_ram_mb(2048))
+        .await
+        .unwrap();
+    assert_eq!(running.llm_info.config["n_threads"], 4);
+    assert_eq!(running.llm_info.config["max_ram_mb"], 2048);
+    assert!(!running.llm_info.config.contains_key("gpu_layers"));
+}
+
+#[tokio::test]
+async fn unload_by_id_and_filter() {
+    let server = MockPantryServer::start().await.unwrap();
+    for id in ["openchat", "mistral"] {
+        let mut llm = mock_llm(id);
+        llm.running = true;
+        if id == "mistral" {
+            llm.family_id = "mistral".into();
+        }
+        server.add_llm(llm);
+    }
+    let pantry = server.login(UserPermissions {
+        perm_unload_llm: true,
+        perm_view_llms: true,
+        ..Default::default()
+    });
+
+    let unloaded = pantry
+        .unload_llm_flex(Some(LLMFilter::new().family("llama")), None)
+        .await
+        .unwrap();
+    assert_eq!(unloaded.id, "openchat");
+    assert!(matches!(
+        pantry
+            .unload_llm_flex(Some(LLMFilter::new().family("llama")), None)
+            .await,
+        Err(PantryError::LlmNotFound(_))
+    ));
+
+    assert!(!pantry.unload_llm("mistral".into()).await.unwrap().running);
+    assert!(pantry.get_running_llms().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn reload() {
+    let server = MockPantryServer::start().await.unwrap();
+    let mut llm = mock_llm("openchat");
+    llm.running = true;
+    let uuid = llm.uuid;
+    server.add_llm(llm);
+    let pantry = server.login(UserPermissions {
+        perm_load_llm: true,
+        perm_unload_llm: true,
+        ..Default::default()
+    });
+
+    let running = pantry.reload_llm(uuid).await.unwrap();
+    assert_eq!(running.uuid, uuid);
+    assert!(running.llm_info.running);
+    let calls = server.calls();
+    assert_eq!(calls[calls.len() - 2..], ["unload_llm", "load_llm"]);
+}
+
+#[tokio::test]
+async fn manual_approval() {
+    let server = MockPantryServer::start().await.unwrap();
+    server.auto_approve(false);
+
+    let pantry = server.login(UserPermissions::default());
+    let mut events = pantry.subscribe_events().await.unwrap();
+    let request = pantry.request_permissions(perms()).await.unwrap();
+    assert!(!request.complete);
+    assert_eq!(server.pending_requests(), vec![request.id]);
+
+    assert!(server.approve(request.id));
+    let outcome = pantry
+        .await_request(request.id, Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert!(matches!(outcome, RequestOutcome::Accepted(_)));
+    assert!(matches!(
+        events.next().await,
+        Some(Ok(ServerEvent::RequestAccepted { request_id })) if request_id == request.id
+    ));
+    assert!(pantry.get_permissions().await.unwrap().perm_session);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn serves_unix_socket() {
+    let socket = std::env::temp_dir().join(format!("pantry-rs-mock-{}.sock", uuid::Uuid::new_v4()));
+    let server = MockPantryServer::start_unix(&socket).await.unwrap();
+    assert!(server.base_url().is_none());
+
+    let pantry = server.login(perms());
+    let info = pantry.server_info().await.unwrap();
+    assert!(info.is_compatible());
+    assert!(info.supports("prompt_session_stream"));
+}
@@ -0,0 +1,109 @@
+use pantry_rs::tools::{Tool, ToolCall, ToolSet};
+use pantry_rs::PromptFormat;
+use serde_json::json;
+
+fn weather() -> Tool {
+    Tool::new(
+        "get_weather",
+        "Current weather for a city",
+        json!({"type": "object", "properties": {"city": {"type": "string"}}}),
+    )
+}
+
+#[test]
+fn renders_tools_per_format() {
+    let chatml = ToolSet::new(PromptFormat::ChatML).tool(weather());
+    let prompt = chatml.system_prompt();
+    assert!(prompt.contains("<tools>"));
+    assert!(prompt.contains("\"name\":\"get_weather\""));
+
+    let mistral = ToolSet::new(PromptFormat::Mistral).tool(weather());
+    assert!(mistral
+        .system_prompt()
+        .starts_with("[AVAILABLE_TOOLS] [{\"function\":"));
+}
+
+#[test]
+fn parses_calls_from_text() {
+    let tools = ToolSet::new(PromptFormat::ChatML).tool(weather());
+    let calls = tools
+        .parse("Let me check.\n<tool_call>\n{\"name\": \"get_weather\", \"arguments\": {\"city\": \"Paris\"}}\n</tool_call>")
+        .unwrap();
+    assert_eq!(
+        calls,
+        vec![ToolCall {
+            name: "get_weather".into(),
+            arguments: json!({"city": "Paris"}),
+        }]
+    );
+    assert!(tools.parse("No tools needed.").unwrap().is_empty());
+    assert!(tools.parse("<tool_call>{\"city\": \"Paris\"}").is_err());
+
+    let mistral = ToolSet::new(PromptFormat::Mistral);
+    let calls = mistral
+        .parse("[TOOL_CALLS] [{\"name\": \"a\", \"parameters\": {}}, {\"name\": \"b\"}]")
+        .unwrap();
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0].arguments, json!({}));
+}
+
+#[test]
+fn typed_arguments() {
+    #[derive(serde::Deserialize)]
+    struct Args {
+        city: String,
+    }
+    let call = ToolCall {
+        name: "get_weather".into(),
+        arguments: json!({"city": "Paris"}),
+    };
+    assert_eq!(call.arguments_as::<Args>().unwrap().city, "Paris");
+}
+
+#[cfg(feature = "testing")]
+mod stream {
+    use super::*;
+    use futures::stream::StreamExt;
+    use pantry_rs::testing::MockPantryServer;
+    use pantry_rs::tools::ToolEvent;
+    use pantry_rs::InferenceParams;
+
+    #[tokio::test]
+    async fn splits_calls_out_of_the_stream() {
+        let server = MockPantryServer::start().await.unwrap();
+        // The marker and the JSON arrive in pieces.
+        server.reply([
+            "Checking",
+            " <tool",
+            "_call>{\"name\": \"get_",
+            "weather\", \"arguments\": {\"city\": \"Paris\"}}",
+            "</tool_call> <",
+            "done",
+        ]);
+        let sess = server.running_session("openchat").await;
+        let tools = ToolSet::new(PromptFormat::ChatML).tool(weather());
+
+        let stream = sess
+            .prompt_session("Weather?".into(), InferenceParams::new())
+            .await
+            .unwrap();
+        let events: Vec<ToolEvent> = tools
+            .events(stream)
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+        assert_eq!(
+            events,
+            vec![
+                ToolEvent::Text("Checking".into()),
+                ToolEvent::Text(" ".into()),
+                ToolEvent::Call(ToolCall {
+                    name: "get_weather".into(),
+                    arguments: json!({"city": "Paris"}),
+                }),
+                ToolEvent::Text(" ".into()),
+                ToolEvent::Text("<done".into()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,132 @@
+#![cfg(feature = "testing")]
+use pantry_rs::interface::{CapabilityType, LLMConnectorType, LLMRegistryEntry, UserPermissions};
+use pantry_rs::testing::{mock_llm, MockPantryServer};
+use pantry_rs::transcription::audio_mime;
+use pantry_rs::{AttachmentTransport, ConnectorConfig, PantryError, WhisperConfig};
+use serde_json::json;
+use std::collections::HashMap;
+
+fn perms() -> UserPermissions {
+    UserPermissions {
+        perm_session: true,
+        ..Default::default()
+    }
+}
+
+fn wav() -> Vec<u8> {
+    let mut wav = b"RIFF\x24\x00\x00\x00WAVEfmt ".to_vec();
+    wav.extend_from_slice(b"\r\n--\x00\xff");
+    wav
+}
+
+#[tokio::test]
+async fn streams_transcripts() {
+    let server = MockPantryServer::start().await.unwrap();
+    let whisper = server.add_running("whisper-base");
+    server.reply(["Hello", " from", " the", " meeting."]);
+    let pantry = server.login(perms());
+
+    let sess = pantry
+        .create_transcription_session(whisper.uuid, HashMap::new())
+        .await
+        .unwrap()
+        .language("en");
+    let text = sess.transcribe_and_collect(wav()).await.unwrap();
+    assert_eq!(text, "Hello from the meeting.");
+
+    let sent = server.last_transcription().unwrap();
+    assert_eq!(sent.audio, wav());
+    assert_eq!(sent.mime, "audio/wav");
+    assert_eq!(sent.parameters["language"], json!("en"));
+}
+
+#[tokio::test]
+async fn sends_audio_as_multipart() {
+    let server = MockPantryServer::start().await.unwrap();
+    let whisper = server.add_running("whisper-base");
+    let (pantry, _) = server
+        .builder()
+        .attachment_transport(AttachmentTransport::Multipart)
+        .register("test".into(), perms())
+        .await
+        .unwrap();
+
+    let sess = pantry
+        .create_transcription_session(whisper.uuid, HashMap::new())
+        .await
+        .unwrap();
+    sess.transcribe_as(vec![1, 2, 3], "audio/x-custom")
+        .await
+        .unwrap()
+        .collect_text()
+        .await
+        .unwrap();
+
+    let sent = server.last_transcription().unwrap();
+    assert_eq!(sent.audio, vec![1, 2, 3]);
+    assert_eq!(sent.mime, "audio/x-custom");
+    assert!(server
+        .last_content_type()
+        .unwrap()
+        .starts_with("multipart/form-data"));
+}
+
+#[tokio::test]
+async fn rejects_text_models() {
+    let server = MockPantryServer::start().await.unwrap();
+    let openchat = server.add_running("openchat");
+    let pantry = server.login(perms());
+
+    let res = pantry
+        .create_transcription_session(openchat.uuid, HashMap::new())
+        .await;
+    assert!(matches!(res, Err(PantryError::Unsupported(_))));
+}
+
+#[test]
+fn detects_audio_models() {
+    assert!(mock_llm("whisper-large-v3").transcribes_audio());
+    assert!(!mock_llm("openchat").transcribes_audio());
+
+    let mut llm = mock_llm("distil-large");
+    llm.connector_type = "whisper".into();
+    assert!(llm.transcribes_audio());
+
+    let mut llm = mock_llm("distil-large");
+    llm.capabilities.insert(CapabilityType::Transcription, 7);
+    assert!(llm.transcribes_audio());
+}
+
+#[test]
+fn whisper_entries() {
+    let entry = LLMRegistryEntry::builder("whisper-base", LLMConnectorType::Whisper)
+        .url("https://example.com/ggml-base.bin")
+        .connector_config(WhisperConfig {
+            language: Some("de".into()),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    assert!(entry.local);
+    assert!(entry.user_parameters.contains(&"language".to_string()));
+    assert_eq!(entry.capabilities[&CapabilityType::Transcription], -1);
+    match entry.connector_config().unwrap() {
+        ConnectorConfig::Whisper(config) => assert_eq!(config.language.as_deref(), Some("de")),
+        other => panic!("wrong config {:?}", other),
+    }
+
+    let no_url = LLMRegistryEntry::builder("whisper-base", LLMConnectorType::Whisper).build();
+    assert!(no_url.is_err());
+}
+
+#[test]
+fn sniffs_audio_formats() {
+    assert_eq!(audio_mime(&wav()), Some("audio/wav"));
+    assert_eq!(audio_mime(b"ID3\x04\x00"), Some("audio/mpeg"));
+    assert_eq!(audio_mime(&[0xff, 0xfb, 0x90]), Some("audio/mpeg"));
+    assert_eq!(audio_mime(b"fLaC\x00"), Some("audio/flac"));
+    assert_eq!(audio_mime(b"OggS\x00"), Some("audio/ogg"));
+    assert_eq!(audio_mime(b"\x00\x00\x00\x20ftypM4A "), Some("audio/mp4"));
+    assert_eq!(audio_mime(&[0xff, 0xf1, 0x50]), None);
+    assert_eq!(audio_mime(b"hello"), None);
+}
@@ -0,0 +1,89 @@
+#![cfg(all(unix, feature = "testing", feature = "unix-socket"))]
+use pantry_rs::api::{PantryAPI, Transport};
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::RetryPolicy;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+// The TCP fallback always goes to localhost:9404, so only one test can use it at a time.
+static FALLBACK_PORT: Mutex<()> = Mutex::const_new(());
+
+async fn fallback() -> MockPantryServer {
+    let addr = SocketAddr::from(([127, 0, 0, 1], 9404));
+    // The previous test's server may still be shutting down.
+    for _ in 0..50 {
+        if let Ok(server) = MockPantryServer::start_at(addr).await {
+            return server;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("port 9404 is taken");
+}
+
+fn socket() -> PathBuf {
+    std::env::temp_dir().join(format!("pantry-rs-mock-{}.sock", uuid::Uuid::new_v4()))
+}
+
+fn api(socket: &Path) -> PantryAPI {
+    let mut api = PantryAPI::new(None);
+    api.socket_path = socket.to_string_lossy().into();
+    api.retry = RetryPolicy::none();
+    api
+}
+
+fn info_calls(server: &MockPantryServer) -> usize {
+    server
+        .calls()
+        .iter()
+        .filter(|call| *call == "server_info")
+        .count()
+}
+
+#[tokio::test]
+async fn sticks_to_tcp_after_the_socket_fails() {
+    let _port = FALLBACK_PORT.lock().await;
+    let tcp = fallback().await;
+    let socket = socket();
+    let api = api(&socket);
+
+    api.server_info().await.unwrap();
+    assert_eq!(api.current_transport(), Some(Transport::Tcp));
+    assert_eq!(info_calls(&tcp), 1);
+
+    // The socket coming up doesn't matter until it's due for another try.
+    let unix = MockPantryServer::start_unix(&socket).await.unwrap();
+    api.server_info().await.unwrap();
+    assert_eq!(api.current_transport(), Some(Transport::Tcp));
+    assert_eq!(info_calls(&tcp), 2);
+    assert_eq!(info_calls(&unix), 0);
+}
+
+#[tokio::test]
+async fn reprobes_the_socket() {
+    let _port = FALLBACK_PORT.lock().await;
+    let tcp = fallback().await;
+    let socket = socket();
+    let mut api = api(&socket);
+    api.transport_reprobe = Duration::from_millis(50);
+
+    api.server_info().await.unwrap();
+    assert_eq!(api.current_transport(), Some(Transport::Tcp));
+
+    let unix = MockPantryServer::start_unix(&socket).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(80)).await;
+    api.server_info().await.unwrap();
+    assert_eq!(api.current_transport(), Some(Transport::UnixSocket));
+    assert_eq!(info_calls(&tcp), 1);
+    assert_eq!(info_calls(&unix), 1);
+}
+
+#[tokio::test]
+async fn does_not_remember_failing_tcp() {
+    let _port = FALLBACK_PORT.lock().await;
+    let api = api(&socket());
+
+    assert!(api.server_info().await.is_err());
+    assert_eq!(api.current_transport(), None);
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "testing")]
+use futures::stream::StreamExt;
+use pantry_rs::interface::FinishReason;
+use pantry_rs::testing::MockPantryServer;
+use pantry_rs::{InferenceParams, LLMSession, PantryError};
+use std::time::Duration;
+
+async fn session(server: &MockPantryServer, stall_timeout: Duration) -> LLMSession {
+    let builder = server.builder().stall_timeout(stall_timeout);
+    server.running_session_with("openchat", builder).await
+}
+
+#[tokio::test]
+async fn fails_stalled_streams() {
+    let server = MockPantryServer::start().await.unwrap();
+    let sess = session(&server, Duration::from_millis(50)).await;
+    server.token_delay(Duration::from_secs(5));
+
+    let mut stream = sess
+        .prompt_session("Hi".into(), InferenceParams::new())
+        .await
+        .unwrap();
+    let err = stream.next().await.unwrap().unwrap_err();
+    assert!(matches!(err, PantryError::StreamStalled(idle) if idle == Duration::from_millis(50)));
+    assert_eq!(stream.finish_reason(), Some(FinishReason::Error));
+    assert!(stream.next().await.is_none());
+
+    // The interrupt goes out in the background.
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(server.calls().contains(&"interrupt_session".to_string()));
+}
+
+#[tokio::test]
+async fn resets_on_every_event() {
+    let server = MockPantryServer::start().await.unwrap();
+    let sess = session(&server, Duration::from_millis(200)).await;
+    // The whole reply takes longer than the timeout, each token doesn't.
+    server.token_delay(Duration::from_millis(80));
+
+    let text = sess
+        .prompt_and_collect("Hi".into(), InferenceParams::new())
+        .await
+        .unwrap();
+    assert_eq!(text, "Hello, world!");
+}
+
+#[tokio::test]
+async fn optionally_leaves_the_session_alone() {
+    let server = MockPantryServer::start().await.unwrap();
+    let sess = session(&server, Duration::from_secs(60)).await;
+    server.token_delay(Duration::from_secs(5));
+
+    let mut stream = sess
+        .prompt_session("Hi".into(), InferenceParams::new())
+        .await
+        .unwrap();
+    stream.set_stall_timeout(Some(Duration::from_millis(20)));
+    stream.set_interrupt_on_stall(false);
+    assert!(matches!(
+        stream.next().await,
+        Some(Err(PantryError::StreamStalled(_)))
+    ));
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!server.calls().contains(&"interrupt_session".to_string()));
+}